@@ -0,0 +1,94 @@
+//! Measures opening and closing many entry readers with and without a buffer pool, and reports the number of bytes
+//! allocated per entry reader for each.
+//!
+//! Run with `cargo bench --bench buffer_pool`.
+
+use async_zip::read::mem::ZipFileReader;
+use async_zip::read::pool::BufferPool;
+use async_zip::read::{ReaderOptions, DEFAULT_BUFFER_SIZE};
+use async_zip::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use tokio::runtime::Runtime;
+
+const READERS: usize = 100_000;
+
+// Counts every allocation (including reallocations) and the bytes requested, so they can be reported alongside the
+// timings.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+async fn write_archive() -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut writer = ZipFileWriter::new(&mut archive);
+
+    let builder = ZipEntryBuilder::new("entry.txt", Compression::Stored);
+    writer.write_entry_whole(builder, b"Lorem ipsum dolor sit amet.").await.expect("failed to write entry");
+    writer.close().await.expect("failed to close writer");
+    archive
+}
+
+// Open (and immediately drop) an entry reader for the archive's only entry, READERS times.
+async fn open_readers(archive: &[u8], options: ReaderOptions) {
+    let mut reader = ZipFileReader::with_options(archive, options).await.expect("failed to open reader");
+
+    for _ in 0..READERS {
+        drop(reader.entry_reader(0).await.expect("failed to open entry reader"));
+    }
+}
+
+fn buffer_pool(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("failed to build runtime");
+    let archive = runtime.block_on(write_archive());
+
+    let configurations = [
+        ("allocate", ReaderOptions::new()),
+        ("pool", ReaderOptions::new().buffer_pool(BufferPool::new(DEFAULT_BUFFER_SIZE, 16))),
+    ];
+
+    let mut group = c.benchmark_group("buffer_pool");
+    group.throughput(Throughput::Elements(READERS as u64));
+    group.sample_size(10);
+
+    for (name, options) in configurations {
+        let (allocations, bytes) = (ALLOCATIONS.load(Ordering::Relaxed), ALLOCATED_BYTES.load(Ordering::Relaxed));
+        runtime.block_on(open_readers(&archive, options.clone()));
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+        let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes;
+
+        println!(
+            "{}: {:.2} allocations and {:.0} bytes allocated per entry reader",
+            name,
+            allocations as f64 / READERS as f64,
+            bytes as f64 / READERS as f64
+        );
+
+        group.bench_function(name, |b| b.to_async(&runtime).iter(|| open_readers(&archive, options.clone())));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, buffer_pool);
+criterion_main!(benches);
@@ -0,0 +1,73 @@
+//! Compares entry extraction throughput across reader buffer sizes, for both stored and deflated entries.
+//!
+//! Run with `cargo bench --bench buffer_size --features deflate`.
+
+use async_zip::read::seek::ZipFileReader;
+use async_zip::read::ReaderOptions;
+use async_zip::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::path::Path;
+use tokio::fs::File;
+use tokio::runtime::Runtime;
+
+const ENTRY_SIZE: usize = 16 * 1024 * 1024;
+const BUFFER_SIZES: [usize; 3] = [8 * 1024, 64 * 1024, 256 * 1024];
+
+// Produce data which compresses reasonably, without being trivially repetitive.
+fn entry_data() -> Vec<u8> {
+    let mut state: u32 = 0x12345678;
+
+    (0..ENTRY_SIZE)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            b"abcdefghijklmnop"[(state % 16) as usize]
+        })
+        .collect()
+}
+
+async fn write_archive(path: &Path, compression: Compression) {
+    let mut file = File::create(path).await.expect("failed to create archive");
+    let mut writer = ZipFileWriter::new(&mut file);
+
+    let builder = ZipEntryBuilder::new(String::from("entry.bin"), compression);
+    writer.write_entry_whole(builder, &entry_data()).await.expect("failed to write entry");
+    writer.close().await.expect("failed to close writer");
+}
+
+async fn extract(path: &Path, buffer_size: usize) {
+    let file = File::open(path).await.expect("failed to open archive");
+    let options = ReaderOptions::new().buffer_size(buffer_size);
+    let mut reader = ZipFileReader::with_options(file, options).await.expect("failed to open reader");
+
+    let entry_reader = reader.entry_reader(0).await.expect("failed to open entry reader");
+    entry_reader.copy_to_end_crc(&mut tokio::io::sink(), 64 * 1024).await.expect("failed to read entry");
+}
+
+fn buffer_size(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("failed to build runtime");
+
+    for (name, compression) in [("stored", Compression::Stored), ("deflate", Compression::Deflate)] {
+        let path = std::env::temp_dir().join(format!("async_zip_bench_{}_{}.zip", name, std::process::id()));
+        runtime.block_on(write_archive(&path, compression));
+
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(ENTRY_SIZE as u64));
+        group.sample_size(20);
+
+        for size in BUFFER_SIZES {
+            group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, size| {
+                b.to_async(&runtime).iter(|| extract(&path, *size));
+            });
+        }
+
+        group.finish();
+        std::fs::remove_file(&path).expect("failed to remove archive");
+    }
+}
+
+criterion_group!(benches, buffer_size);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+//! Compares the extraction throughput of a stored entry with and without CRC32 computation.
+//!
+//! Run with `cargo bench --bench crc`.
+
+use async_zip::read::mem::ZipFileReader;
+use async_zip::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tokio::runtime::Runtime;
+
+const ENTRY_SIZE: usize = 64 * 1024 * 1024;
+
+async fn write_archive() -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut writer = ZipFileWriter::new(&mut archive);
+
+    let builder = ZipEntryBuilder::new(String::from("entry.bin"), Compression::Stored);
+    writer.write_entry_whole(builder, &vec![0x5A; ENTRY_SIZE]).await.expect("failed to write entry");
+    writer.close().await.expect("failed to close writer");
+
+    archive
+}
+
+async fn extract(archive: &[u8], compute_crc: bool) {
+    let mut reader = ZipFileReader::new(archive).await.expect("failed to open reader");
+    let mut entry_reader = reader.entry_reader(0).await.expect("failed to open entry reader");
+
+    if !compute_crc {
+        entry_reader = entry_reader.without_crc();
+    }
+
+    entry_reader.copy_to_end_crc(&mut tokio::io::sink(), 64 * 1024).await.expect("failed to read entry");
+}
+
+fn crc(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("failed to build runtime");
+    let archive = runtime.block_on(write_archive());
+
+    let mut group = c.benchmark_group("stored");
+    group.throughput(Throughput::Bytes(ENTRY_SIZE as u64));
+    group.sample_size(20);
+
+    for (name, compute_crc) in [("with_crc", true), ("without_crc", false)] {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &compute_crc, |b, compute_crc| {
+            b.to_async(&runtime).iter(|| extract(&archive, *compute_crc));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, crc);
+criterion_main!(benches);
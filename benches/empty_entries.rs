@@ -0,0 +1,82 @@
+//! Measures listing and extracting an archive of many empty files and directories (eg. a `node_modules` tree), where
+//! the cost of opening each entry dominates.
+//!
+//! Run with `cargo bench --bench empty_entries --features deflate`.
+
+use async_zip::read::mem::ZipFileReader;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use tokio::runtime::Runtime;
+
+const ENTRIES: usize = 50_000;
+
+// Lay out the archive by hand, as the writer would compress each empty file into a couple of bytes of Deflate data
+// rather than declaring Deflate with no data at all (as some other producers do).
+//
+// Every tenth entry is a directory, and every file is empty but declares the Deflate compression method.
+fn build_archive() -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut central_directory = Vec::new();
+
+    for index in 0..ENTRIES {
+        let (filename, compression): (String, u16) = match index % 10 {
+            0 => (format!("package-{}/", index), 0),
+            _ => (format!("package-{}/file-{}.js", index / 10 * 10, index), 8),
+        };
+
+        // The version needed, flags, compression method, time, date, CRC32 value, sizes, and field lengths.
+        let mut common = Vec::new();
+        common.extend_from_slice(&20u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&compression.to_le_bytes());
+        common.extend_from_slice(&[0, 0, 0x21, 0]);
+        common.extend_from_slice(&[0; 12]);
+        common.extend_from_slice(&(filename.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        central_directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes());
+        central_directory.extend_from_slice(&common);
+        central_directory.extend_from_slice(&[0; 10]);
+        central_directory.extend_from_slice(&(archive.len() as u32).to_le_bytes());
+        central_directory.extend_from_slice(filename.as_bytes());
+
+        archive.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        archive.extend_from_slice(&common);
+        archive.extend_from_slice(filename.as_bytes());
+    }
+
+    let cd_offset = archive.len() as u32;
+    archive.extend_from_slice(&central_directory);
+    archive.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    archive.extend_from_slice(&[0; 4]);
+    archive.extend_from_slice(&(ENTRIES as u16).to_le_bytes());
+    archive.extend_from_slice(&(ENTRIES as u16).to_le_bytes());
+    archive.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&cd_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+    archive
+}
+
+async fn extract(archive: &[u8]) {
+    let mut reader = ZipFileReader::new(archive).await.expect("failed to open reader");
+
+    for index in 0..ENTRIES {
+        let entry_reader = reader.entry_reader(index).await.expect("failed to open entry reader");
+        entry_reader.copy_to_end_crc(&mut tokio::io::sink(), 64 * 1024).await.expect("failed to read entry");
+    }
+}
+
+fn empty_entries(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("failed to build runtime");
+    let archive = build_archive();
+
+    let mut group = c.benchmark_group("empty_entries");
+    group.throughput(Throughput::Elements(ENTRIES as u64));
+    group.sample_size(10);
+    group.bench_function("extract", |b| b.to_async(&runtime).iter(|| extract(&archive)));
+    group.finish();
+}
+
+criterion_group!(benches, empty_entries);
+criterion_main!(benches);
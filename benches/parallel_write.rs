@@ -0,0 +1,62 @@
+//! Compares the throughput of writing many deflated entries across levels of concurrency.
+//!
+//! Run with `cargo bench --bench parallel_write --features deflate`. On a machine with N cores, the time taken at a
+//! concurrency of N should approach 1/N of that at a concurrency of one.
+
+use async_zip::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::io::Cursor;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+const ENTRY_COUNT: usize = 64;
+const ENTRY_SIZE: usize = 1024 * 1024;
+
+// Produce data which compresses reasonably, without being trivially repetitive.
+fn entry_data(seed: u32) -> Vec<u8> {
+    let mut state: u32 = 0x12345678 ^ seed;
+
+    (0..ENTRY_SIZE)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            b"abcdefghijklmnop"[(state % 16) as usize]
+        })
+        .collect()
+}
+
+async fn write_archive(entries: &[Arc<Vec<u8>>], concurrency: usize) {
+    let mut writer = ZipFileWriter::new(tokio::io::sink());
+
+    let sources = entries.iter().enumerate().map(|(index, data)| {
+        let builder = ZipEntryBuilder::new(format!("entry{}.bin", index), Compression::Deflate);
+        (builder, Cursor::new(data.as_ref().clone()))
+    });
+
+    writer.write_entries_parallel(sources, concurrency).await.expect("failed to write entries");
+    writer.close().await.expect("failed to close writer");
+}
+
+fn parallel_write(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("failed to build runtime");
+    let entries: Vec<Arc<Vec<u8>>> = (0..ENTRY_COUNT as u32).map(|seed| Arc::new(entry_data(seed))).collect();
+    let cores = std::thread::available_parallelism().map(usize::from).unwrap_or(1);
+
+    let mut group = c.benchmark_group("parallel_write");
+    group.throughput(Throughput::Bytes((ENTRY_COUNT * ENTRY_SIZE) as u64));
+    group.sample_size(10);
+
+    for concurrency in [1, 2, cores] {
+        group.bench_with_input(BenchmarkId::from_parameter(concurrency), &concurrency, |b, concurrency| {
+            b.to_async(&runtime).iter(|| write_archive(&entries, *concurrency));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parallel_write);
+criterion_main!(benches);
@@ -0,0 +1,72 @@
+//! Measures writing an archive of many empty entries, where the writer's per-entry bookkeeping dominates, and reports
+//! the number of allocations made per entry.
+//!
+//! Run with `cargo bench --bench write_entries`.
+
+use async_zip::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use tokio::runtime::Runtime;
+
+const ENTRIES: usize = 1_000_000;
+
+// Counts every allocation (including reallocations) so they can be reported alongside the timings.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// Write every entry via a single builder which is reset for each, with filenames generated into a reused buffer.
+async fn write_archive() -> Vec<u8> {
+    let mut archive = Vec::with_capacity(ENTRIES * 128);
+    let mut writer = ZipFileWriter::new(&mut archive);
+    let mut builder = ZipEntryBuilder::new("", Compression::Stored);
+    let mut filename = String::new();
+
+    for index in 0..ENTRIES {
+        filename.clear();
+        write!(filename, "package-{}/file-{}.js", index / 1000, index).expect("failed to format filename");
+
+        builder.reset(filename.as_str(), Compression::Stored);
+        writer.write_entry_whole(&builder, &[]).await.expect("failed to write entry");
+    }
+
+    writer.close().await.expect("failed to close writer");
+    archive
+}
+
+fn write_entries(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("failed to build runtime");
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    runtime.block_on(write_archive());
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("{:.2} allocations per entry", allocations as f64 / ENTRIES as f64);
+
+    let mut group = c.benchmark_group("write_entries");
+    group.throughput(Throughput::Elements(ENTRIES as u64));
+    group.sample_size(10);
+    group.bench_function("write", |b| b.to_async(&runtime).iter(write_archive));
+    group.finish();
+}
+
+criterion_group!(benches, write_entries);
+criterion_main!(benches);
@@ -0,0 +1,417 @@
+//! A small command-line ZIP utility built on top of the crate's public API.
+//!
+//! ```text
+//! azip list <archive>
+//! azip extract <archive> <directory>
+//! azip create <archive> <path>...
+//! azip verify <archive>
+//! ```
+//!
+//! Local archives are read with the fs reader. An archive of `-` instead reads from stdin with the stream reader (or,
+//! when creating, writes to stdout using data descriptors), so the utility may also be used within a pipeline:
+//! ```text
+//! curl -L https://example.com/archive.zip | azip extract - ./out
+//! azip create - ./docs | ssh host 'cat > docs.zip'
+//! ```
+//!
+//! Extraction mirrors `file_extraction.rs`: each entry's filename is sanitized before being joined onto the output
+//! directory, and entries whose filenames can't be made safe are skipped. Unix permissions and last modification dates
+//! are restored where present.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use async_zip::read::{fs, stream, ZipEntryReader};
+use async_zip::write::fs::{write_dir, DirWriteOptions};
+use async_zip::write::{ZipFileWriter, DEFAULT_COPY_BUFFER_SIZE};
+use async_zip::{Compression, ZipEntry, ZipEntryBuilder, ZipEntryExt};
+use tokio::fs::{create_dir_all, File};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const USAGE: &str = "usage:
+    azip list <archive>
+    azip extract <archive> <directory>
+    azip create <archive> <path>...
+    azip verify <archive>
+
+An archive of '-' reads from stdin, or writes to stdout when creating.";
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match run(&args).await {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            eprintln!("azip: {}", err);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Runs a single subcommand, returning whether or not it succeeded.
+async fn run(args: &[String]) -> Result<bool> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args[..] {
+        ["list", "-"] => print_lines(list_stream(tokio::io::stdin()).await?),
+        ["list", archive] => print_lines(list_file(Path::new(archive)).await?),
+        ["extract", "-", dir] => extract_stream(tokio::io::stdin(), Path::new(dir)).await?,
+        ["extract", archive, dir] => extract_file(Path::new(archive), Path::new(dir)).await?,
+        ["create", "-", ref paths @ ..] if !paths.is_empty() => {
+            let mut stdout = tokio::io::stdout();
+            create(&mut stdout, &to_paths(paths)).await?;
+            stdout.flush().await?;
+        }
+        ["create", archive, ref paths @ ..] if !paths.is_empty() => {
+            let mut file = File::create(archive).await?;
+            create(&mut file, &to_paths(paths)).await?;
+            file.flush().await?;
+        }
+        ["verify", "-"] => return report(verify_stream(tokio::io::stdin()).await?),
+        ["verify", archive] => return report(verify_file(Path::new(archive)).await?),
+        _ => {
+            eprintln!("{}", USAGE);
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+fn to_paths(paths: &[&str]) -> Vec<PathBuf> {
+    paths.iter().map(PathBuf::from).collect()
+}
+
+fn print_lines(lines: Vec<String>) {
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+/// Prints any verification failures, returning whether or not there were none.
+fn report(failures: Vec<String>) -> Result<bool> {
+    for failure in &failures {
+        eprintln!("{}", failure);
+    }
+
+    Ok(failures.is_empty())
+}
+
+/// Formats the listing line of a single entry, given its sizes.
+fn list_line(entry: &ZipEntry, uncompressed_size: String, compressed_size: String) -> String {
+    let compression = format!("{:?}", entry.compression());
+    let date = entry.last_modification_date().format("%Y-%m-%d %H:%M");
+
+    format!("{:>10} {:>10}  {:<8} {}  {}", uncompressed_size, compressed_size, compression, date, entry.filename())
+}
+
+/// Lists the entries of a local archive from its central directory.
+async fn list_file(archive: &Path) -> Result<Vec<String>> {
+    let reader = fs::ZipFileReader::new(archive).await?;
+    let lines = reader
+        .entries()
+        .into_iter()
+        .map(|entry| list_line(entry, entry.uncompressed_size().to_string(), entry.compressed_size().to_string()));
+
+    Ok(lines.collect())
+}
+
+/// Lists the entries of a streamed archive from their local file headers.
+///
+/// An entry which uses a data descriptor only declares its sizes after its data, so is listed once it has been
+/// skipped over.
+async fn list_stream<R: AsyncRead + Unpin>(input: R) -> Result<Vec<String>> {
+    let mut reader = stream::ZipFileReader::new(input);
+    let mut lines = Vec::new();
+
+    while !reader.finished() {
+        let entry_reader = match reader.entry_reader().await? {
+            Some(entry_reader) => entry_reader,
+            None => break,
+        };
+
+        let entry = entry_reader.entry().clone();
+        let size = entry_reader.copy_to_end_crc(&mut tokio::io::sink(), DEFAULT_COPY_BUFFER_SIZE).await?;
+
+        // The local file header's compressed size is zeroed when a data descriptor follows the data.
+        let compressed_size = match entry.general_purpose_flags().data_descriptor {
+            true => String::from("-"),
+            false => entry.compressed_size().to_string(),
+        };
+
+        lines.push(list_line(&entry, size.to_string(), compressed_size));
+    }
+
+    Ok(lines)
+}
+
+/// Extracts every entry of a local archive into a directory.
+async fn extract_file(archive: &Path, dir: &Path) -> Result<()> {
+    let reader = fs::ZipFileReader::new(archive).await?;
+
+    for index in 0..reader.entries().len() {
+        extract_entry(reader.entry_reader(index).await?, dir).await?;
+    }
+
+    Ok(())
+}
+
+/// Extracts every entry of a streamed archive into a directory, in the order they appear.
+async fn extract_stream<R: AsyncRead + Unpin>(input: R, dir: &Path) -> Result<()> {
+    let mut reader = stream::ZipFileReader::new(input);
+
+    while let Some(entry_reader) = reader.entry_reader().await? {
+        extract_entry(entry_reader, dir).await?;
+    }
+
+    Ok(())
+}
+
+/// Extracts a single entry beneath a directory.
+///
+/// The entry's data is always read in full (even when it's skipped), as the stream reader can only move onto the next
+/// entry once the current one has been consumed.
+async fn extract_entry<R: AsyncRead + Unpin>(entry_reader: ZipEntryReader<'_, R>, dir: &Path) -> Result<()> {
+    let entry = entry_reader.entry().clone();
+
+    let path = match entry.sanitized_path() {
+        Some(path) if !path.as_os_str().is_empty() => dir.join(path),
+        _ => {
+            eprintln!("skipping {:?}: unsafe filename", entry.filename());
+            entry_reader.copy_to_end_crc(&mut tokio::io::sink(), DEFAULT_COPY_BUFFER_SIZE).await?;
+            return Ok(());
+        }
+    };
+
+    // If the filename of the entry ends with '/', it is treated as a directory (see file_extraction.rs).
+    if entry.filename().ends_with('/') {
+        create_dir_all(&path).await?;
+        entry_reader.copy_to_end_crc(&mut tokio::io::sink(), DEFAULT_COPY_BUFFER_SIZE).await?;
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).await?;
+    }
+
+    if entry.is_symlink() {
+        let target = entry_reader.read_to_string_crc().await?;
+        return extract_symlink(&entry, &target, &path).await;
+    }
+
+    let mut file = File::create(&path).await?;
+    let size = entry_reader.copy_to_end_crc(&mut file, DEFAULT_COPY_BUFFER_SIZE).await?;
+    file.flush().await?;
+    eprintln!("extracted {} ({} bytes)", entry.filename(), size);
+
+    #[cfg(unix)]
+    if let Some(mode) = entry.unix_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(u32::from(mode) & 0o7777)).await?;
+    }
+
+    let file = file.into_std().await;
+    file.set_modified(SystemTime::from(*entry.last_modification_date()))?;
+
+    Ok(())
+}
+
+/// Creates a symbolic link entry's link, as long as its target stays within the extraction directory.
+#[cfg(unix)]
+async fn extract_symlink(entry: &ZipEntry, target: &str, path: &Path) -> Result<()> {
+    if async_zip::sanitize::sanitize_entry_path(target).is_err() {
+        eprintln!("skipping {:?}: unsafe link target {:?}", entry.filename(), target);
+        return Ok(());
+    }
+
+    tokio::fs::symlink(target, path).await?;
+    eprintln!("extracted {} -> {}", entry.filename(), target);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn extract_symlink(entry: &ZipEntry, _: &str, _: &Path) -> Result<()> {
+    eprintln!("skipping {:?}: symbolic links aren't supported on this platform", entry.filename());
+    Ok(())
+}
+
+/// Writes the provided files and directory trees into a new archive, each under its own name.
+async fn create<W: AsyncWrite + Unpin>(output: W, paths: &[PathBuf]) -> Result<()> {
+    let mut writer = ZipFileWriter::new(output);
+
+    for path in paths {
+        let name = path.file_name().and_then(|name| name.to_str()).ok_or("path has no valid UTF-8 name")?;
+        let metadata = tokio::fs::metadata(path).await?;
+
+        if metadata.is_dir() {
+            let options = DirWriteOptions::new().prefix(&format!("{}/", name)).compression(|_| Compression::Deflate);
+            writer.write_entry_whole(ZipEntryBuilder::new(format!("{}/", name), Compression::Stored), &[]).await?;
+            write_dir(&mut writer, path, options).await?;
+        } else {
+            let mut builder = ZipEntryBuilder::new(name.to_owned(), Compression::Deflate);
+            if let Ok(modified) = metadata.modified() {
+                builder = builder.last_modification_date(modified.into());
+            }
+
+            #[cfg(unix)]
+            {
+                use async_zip::ZipEntryBuilderExt;
+                use std::os::unix::fs::PermissionsExt;
+                builder = builder.unix_permissions(metadata.permissions().mode() as u16);
+            }
+
+            writer.write_entry_copy(builder, File::open(path).await?, DEFAULT_COPY_BUFFER_SIZE).await?;
+        }
+    }
+
+    writer.close().await?;
+    Ok(())
+}
+
+/// Verifies every entry of a local archive, returning a description of each failure.
+async fn verify_file(archive: &Path) -> Result<Vec<String>> {
+    let reader = fs::ZipFileReader::new(archive).await?;
+    let report = reader.verify(false).await?;
+
+    Ok(report.failures().iter().map(|failure| format!("{}: {:?}", failure.filename(), failure.kind())).collect())
+}
+
+/// Verifies every entry of a streamed archive by decompressing it and checking its CRC32 value.
+///
+/// Without a central directory to check against, a failure to read an entry ends verification.
+async fn verify_stream<R: AsyncRead + Unpin>(input: R) -> Result<Vec<String>> {
+    let mut reader = stream::ZipFileReader::new(input);
+
+    while let Some(entry_reader) = reader.entry_reader().await? {
+        let filename = entry_reader.entry().filename().to_owned();
+
+        if let Err(err) = entry_reader.copy_to_end_crc(&mut tokio::io::sink(), DEFAULT_COPY_BUFFER_SIZE).await {
+            return Ok(vec![format!("{}: {}", filename, err)]);
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const FIXTURES: &[&str] =
+        &["macos_finder.zip", "python_zipfile.zip", "descriptor_no_signature.zip", "go_archive_zip.zip", "jdk_jar.zip"];
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/fixtures").join(name)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("azip_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    // The content shared by every fixture (see src/tests/fixtures/README.md).
+    fn assert_extracted(dir: &Path) {
+        assert_eq!(std::fs::read(dir.join("hello.txt")).unwrap(), b"Hello, world!\n");
+        let lorem = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(20);
+        assert_eq!(std::fs::read(dir.join("docs/lorem.txt")).unwrap(), lorem);
+        assert!(dir.join("docs").is_dir());
+    }
+
+    #[tokio::test]
+    async fn extract_fixtures() {
+        for name in FIXTURES {
+            let dir = temp_dir(&format!("file_{}", name));
+            extract_file(&fixture(name), &dir).await.unwrap();
+            assert_extracted(&dir);
+            std::fs::remove_dir_all(&dir).unwrap();
+
+            let dir = temp_dir(&format!("stream_{}", name));
+            let data = std::fs::read(fixture(name)).unwrap();
+            extract_stream(Cursor::new(data), &dir).await.unwrap();
+            assert_extracted(&dir);
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn list_and_verify_fixtures() {
+        for name in FIXTURES {
+            let lines = list_file(&fixture(name)).await.unwrap();
+            assert!(lines.iter().any(|line| line.ends_with("  docs/lorem.txt")), "{}: {:?}", name, lines);
+
+            let data = std::fs::read(fixture(name)).unwrap();
+            let lines = list_stream(Cursor::new(data.clone())).await.unwrap();
+            assert!(lines.iter().any(|line| line.ends_with(" hello.txt")), "{}: {:?}", name, lines);
+
+            assert!(verify_file(&fixture(name)).await.unwrap().is_empty(), "{}", name);
+            assert!(verify_stream(Cursor::new(data)).await.unwrap().is_empty(), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_corrupted() {
+        let mut data = Vec::new();
+        let mut writer = ZipFileWriter::new(&mut data);
+        let entry = ZipEntryBuilder::new("hello.txt".to_owned(), Compression::Stored);
+        writer.write_entry_whole(entry, b"Hello, world!\n").await.unwrap();
+        writer.close().await.unwrap();
+
+        let position = data.windows(5).position(|window| window == b"Hello").unwrap();
+        data[position] ^= 0xFF;
+
+        let archive = temp_dir("corrupted.zip");
+        std::fs::write(&archive, &data).unwrap();
+        assert!(!verify_file(&archive).await.unwrap().is_empty());
+        assert!(!verify_stream(Cursor::new(data)).await.unwrap().is_empty());
+        std::fs::remove_file(&archive).unwrap();
+    }
+
+    #[tokio::test]
+    async fn create_round_trip() {
+        let source = temp_dir("source");
+        std::fs::create_dir_all(source.join("tree/nested")).unwrap();
+        std::fs::write(source.join("single.txt"), b"A single file.").unwrap();
+        std::fs::write(source.join("tree/nested/data.bin"), vec![7; 100_000]).unwrap();
+
+        let mut archive = Vec::new();
+        create(&mut archive, &[source.join("single.txt"), source.join("tree")]).await.unwrap();
+        assert!(verify_stream(Cursor::new(archive.clone())).await.unwrap().is_empty());
+
+        let dir = temp_dir("created");
+        extract_stream(Cursor::new(archive), &dir).await.unwrap();
+        assert_eq!(std::fs::read(dir.join("single.txt")).unwrap(), b"A single file.");
+        assert_eq!(std::fs::read(dir.join("tree/nested/data.bin")).unwrap(), vec![7; 100_000]);
+
+        let modified = |path: &Path| std::fs::metadata(path).unwrap().modified().unwrap();
+        let elapsed = modified(&source.join("single.txt")).duration_since(modified(&dir.join("single.txt")));
+        assert!(elapsed.map_or(true, |elapsed| elapsed.as_secs() < 2));
+
+        std::fs::remove_dir_all(&source).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn skips_unsafe_filenames() {
+        let mut archive = Vec::new();
+        let mut writer = ZipFileWriter::new(&mut archive);
+        for (filename, data) in [("../escape.txt", b"!"), ("safe.txt", b"?")] {
+            let entry = ZipEntryBuilder::new(filename.to_owned(), Compression::Stored);
+            writer.write_entry_whole(entry, data).await.unwrap();
+        }
+        writer.close().await.unwrap();
+
+        let dir = temp_dir("unsafe").join("out");
+        extract_stream(Cursor::new(archive), &dir).await.unwrap();
+        assert!(!dir.parent().unwrap().join("escape.txt").exists());
+        assert_eq!(std::fs::read(dir.join("safe.txt")).unwrap(), b"?");
+
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
@@ -85,7 +85,7 @@ async fn write_entry(filename: &str, input_path: &Path, writer: &mut ZipFileWrit
     let mut buffer = Vec::with_capacity(input_file_size);
     input_file.read_to_end(&mut buffer).await?;
 
-    let builder = ZipEntryBuilder::new(filename.into(), Compression::Deflate);
+    let builder = ZipEntryBuilder::new(filename, Compression::Deflate);
     writer.write_entry_whole(builder, &buffer).await?;
 
     Ok(())
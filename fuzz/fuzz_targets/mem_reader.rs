@@ -0,0 +1,31 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+#![no_main]
+
+use async_zip::read::mem::ZipFileReader;
+use async_zip::read::ReaderOptions;
+use libfuzzer_sys::fuzz_target;
+
+// Entries which decompress to more than this are rejected, so that compression bombs don't exhaust the fuzzer's memory.
+const MAX_DECOMPRESSED_SIZE: u64 = 16 * 1024 * 1024;
+
+fuzz_target!(|data: &[u8]| {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    runtime.block_on(async {
+        let options = ReaderOptions::new().max_decompressed_size(MAX_DECOMPRESSED_SIZE);
+        let mut zip_reader = match ZipFileReader::with_options(data, options).await {
+            Ok(zip_reader) => zip_reader,
+            Err(_) => return,
+        };
+
+        for index in 0..zip_reader.entries().len() {
+            if let Ok(entry_reader) = zip_reader.entry_reader(index).await {
+                let _ = entry_reader.read_to_end_crc().await;
+            }
+        }
+
+        let _ = zip_reader.verify(false).await;
+    });
+});
@@ -0,0 +1,25 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+#![no_main]
+
+use async_zip::read::stream::ZipFileReader;
+use libfuzzer_sys::fuzz_target;
+
+// Reads of entries which decompress to more than this fail, so compression bombs don't exhaust the fuzzer's memory.
+const MAX_DECOMPRESSED_SIZE: u64 = 16 * 1024 * 1024;
+
+fuzz_target!(|data: &[u8]| {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    runtime.block_on(async {
+        let mut zip_reader = ZipFileReader::new(data);
+
+        // Each entry must be fully read before the next can be, so the first error ends the stream.
+        while let Ok(Some(entry_reader)) = zip_reader.entry_reader().await {
+            if entry_reader.take_limit(MAX_DECOMPRESSED_SIZE).read_to_end_crc().await.is_err() {
+                break;
+            }
+        }
+    });
+});
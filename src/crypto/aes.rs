@@ -0,0 +1,183 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+// https://www.winzip.com/en/support/aes-encryption/
+//
+// WinZip's AES encryption (AE-1 and AE-2): an entry's data is preceded by a salt and a password verification value,
+// encrypted with AES in counter mode, and followed by an authentication code (the truncated HMAC-SHA1 of the encrypted
+// data). The keys and verification value are derived from the password and salt via PBKDF2-HMAC-SHA1.
+
+use crate::error::ZipError;
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use tokio::io::{AsyncRead, ReadBuf};
+
+pub(crate) type HmacSha1 = Hmac<Sha1>;
+
+/// The length of the password verification value which follows the salt.
+pub(crate) const VERIFICATION_LENGTH: usize = 2;
+
+/// The length of the authentication code which follows an entry's encrypted data.
+pub(crate) const AUTHENTICATION_CODE_LENGTH: usize = 10;
+
+// The number of PBKDF2 iterations used to derive the keys.
+const ITERATIONS: u32 = 1000;
+
+// Returns the length of the key for a strength (1 to 3, for 128, 192, and 256-bit keys).
+pub(crate) fn key_length(strength: u8) -> Option<usize> {
+    match strength {
+        1 => Some(16),
+        2 => Some(24),
+        3 => Some(32),
+        _ => None,
+    }
+}
+
+// Returns the length of the salt for a strength, which is half that of its key.
+pub(crate) fn salt_length(strength: u8) -> Option<usize> {
+    key_length(strength).map(|length| length / 2)
+}
+
+/// AES in counter mode, with a little-endian counter starting at one.
+pub(crate) enum Cipher {
+    Aes128(ctr::Ctr128LE<::aes::Aes128>),
+    Aes192(ctr::Ctr128LE<::aes::Aes192>),
+    Aes256(ctr::Ctr128LE<::aes::Aes256>),
+}
+
+impl Cipher {
+    fn new(key: &[u8]) -> Self {
+        let mut iv = [0; 16];
+        iv[0] = 1;
+
+        match key.len() {
+            16 => Cipher::Aes128(ctr::Ctr128LE::new(key.into(), &iv.into())),
+            24 => Cipher::Aes192(ctr::Ctr128LE::new(key.into(), &iv.into())),
+            _ => Cipher::Aes256(ctr::Ctr128LE::new(key.into(), &iv.into())),
+        }
+    }
+
+    /// Encrypts or decrypts a buffer in place.
+    pub(crate) fn apply(&mut self, buf: &mut [u8]) {
+        match self {
+            Cipher::Aes128(cipher) => cipher.apply_keystream(buf),
+            Cipher::Aes192(cipher) => cipher.apply_keystream(buf),
+            Cipher::Aes256(cipher) => cipher.apply_keystream(buf),
+        }
+    }
+}
+
+// Derive the cipher, the authentication code's HMAC, and the password verification value from a password and salt.
+//
+// Panics if the strength is invalid (see key_length()).
+pub(crate) fn derive_keys(password: &[u8], salt: &[u8], strength: u8) -> (Cipher, HmacSha1, [u8; VERIFICATION_LENGTH]) {
+    let key_length = key_length(strength).expect("invalid AES strength");
+    let mut derived = vec![0; key_length * 2 + VERIFICATION_LENGTH];
+    pbkdf2::pbkdf2::<HmacSha1>(password, salt, ITERATIONS, &mut derived);
+
+    let (encryption_key, rest) = derived.split_at(key_length);
+    let (authentication_key, verification) = rest.split_at(key_length);
+    let mac = HmacSha1::new_from_slice(authentication_key).expect("HMAC accepts keys of any length");
+
+    (Cipher::new(encryption_key), mac, [verification[0], verification[1]])
+}
+
+/// A reader which decrypts the first `remaining` bytes read from its inner reader, then checks the authentication code
+/// which follows them, and passes any bytes beyond that (eg. a data descriptor) through untouched.
+///
+/// The final decrypted bytes are held back until the authentication code has been checked, so that EOF is never
+/// reached over data which fails authentication.
+pub(crate) struct AesReader<R> {
+    inner: R,
+    cipher: Cipher,
+    mac: Option<HmacSha1>,
+    remaining: u64,
+    code: [u8; AUTHENTICATION_CODE_LENGTH],
+    code_filled: usize,
+    held: Vec<u8>,
+    filename: String,
+}
+
+impl<R: AsyncRead + Unpin> AesReader<R> {
+    pub(crate) fn new(inner: R, cipher: Cipher, mac: HmacSha1, remaining: u64, filename: String) -> Self {
+        let code = [0; AUTHENTICATION_CODE_LENGTH];
+        Self { inner, cipher, mac: Some(mac), remaining, code, code_filled: 0, held: Vec::new(), filename }
+    }
+
+    // Read the authentication code and check it against that computed over the encrypted data.
+    fn poll_authenticate(&mut self, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        while self.code_filled < AUTHENTICATION_CODE_LENGTH {
+            let mut buf = ReadBuf::new(&mut self.code[self.code_filled..]);
+
+            match Pin::new(&mut self.inner).poll_read(c, &mut buf) {
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => {
+                    let error = tokio::io::Error::new(tokio::io::ErrorKind::UnexpectedEof, "truncated AES entry");
+                    return Poll::Ready(Err(error));
+                }
+                Poll::Ready(Ok(())) => self.code_filled += buf.filled().len(),
+                poll => return poll,
+            }
+        }
+
+        // The HMAC is kept upon failure, so that any further reads fail in the same way.
+        if let Some(mac) = &self.mac {
+            if mac.clone().verify_truncated_left(&self.code).is_err() {
+                let error = ZipError::AuthenticationFailed(self.filename.clone());
+                return Poll::Ready(Err(tokio::io::Error::new(tokio::io::ErrorKind::InvalidData, error)));
+            }
+        }
+
+        self.mac = None;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AesReader<R> {
+    fn poll_read(self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let this = self.get_mut();
+
+        if this.remaining == 0 {
+            if this.mac.is_some() {
+                std::task::ready!(this.poll_authenticate(c))?;
+            }
+            if !this.held.is_empty() {
+                let length = this.held.len().min(b.remaining());
+                b.put_slice(&this.held[..length]);
+                this.held.drain(..length);
+                return Poll::Ready(Ok(()));
+            }
+
+            return Pin::new(&mut this.inner).poll_read(c, b);
+        }
+
+        let limit = b.remaining().min(usize::try_from(this.remaining).unwrap_or(usize::MAX));
+        if limit == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut limited = ReadBuf::new(b.initialize_unfilled_to(limit));
+        std::task::ready!(Pin::new(&mut this.inner).poll_read(c, &mut limited))?;
+
+        let read = limited.filled_mut();
+        if let Some(mac) = &mut this.mac {
+            mac.update(read);
+        }
+        this.cipher.apply(read);
+        this.remaining -= read.len() as u64;
+
+        // Hold back the final bytes, as they mustn't be returned until the authentication code has been checked.
+        if this.remaining == 0 && !read.is_empty() {
+            this.held = read.to_vec();
+            return Pin::new(this).poll_read(c, b);
+        }
+
+        let length = read.len();
+        b.advance(length);
+        Poll::Ready(Ok(()))
+    }
+}
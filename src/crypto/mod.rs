@@ -0,0 +1,14 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module which holds the ciphers used to encrypt and decrypt entries' data.
+
+pub(crate) mod zipcrypto;
+
+#[cfg(feature = "aes")]
+pub(crate) mod aes;
+
+// Fill a buffer from the operating system's random source, for the ZipCrypto encryption header and the AES salt.
+pub(crate) fn fill_random(buf: &mut [u8]) -> std::io::Result<()> {
+    getrandom::getrandom(buf).map_err(std::io::Error::from)
+}
@@ -0,0 +1,154 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+// https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#614
+//
+// The traditional PKWARE encryption scheme (commonly known as ZipCrypto): a stream cipher whose state is three 32-bit
+// keys, initialised from the password and updated with each plaintext byte.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// The length of the encryption header which precedes an encrypted entry's data.
+pub(crate) const HEADER_LENGTH: usize = 12;
+
+// The CRC32 lookup table, as the cipher updates its keys one byte at a time.
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            value = match value & 1 {
+                0 => value >> 1,
+                _ => 0xEDB88320 ^ (value >> 1),
+            };
+            bit += 1;
+        }
+
+        table[index] = value;
+        index += 1;
+    }
+
+    table
+}
+
+fn crc32_byte(crc: u32, byte: u8) -> u32 {
+    CRC_TABLE[((crc ^ u32::from(byte)) & 0xFF) as usize] ^ (crc >> 8)
+}
+
+/// The state of the cipher.
+#[derive(Clone)]
+pub(crate) struct ZipCryptoKeys {
+    key0: u32,
+    key1: u32,
+    key2: u32,
+}
+
+impl ZipCryptoKeys {
+    /// Initialises the keys from a password.
+    pub(crate) fn new(password: &[u8]) -> Self {
+        let mut keys = Self { key0: 0x12345678, key1: 0x23456789, key2: 0x34567890 };
+
+        for &byte in password {
+            keys.update(byte);
+        }
+
+        keys
+    }
+
+    /// Updates the keys with a plaintext byte.
+    pub(crate) fn update(&mut self, byte: u8) {
+        self.key0 = crc32_byte(self.key0, byte);
+        self.key1 = self.key1.wrapping_add(self.key0 & 0xFF).wrapping_mul(134775813).wrapping_add(1);
+        self.key2 = crc32_byte(self.key2, (self.key1 >> 24) as u8);
+    }
+
+    /// Returns the byte of the keystream which the next byte is XORed with.
+    pub(crate) fn stream_byte(&self) -> u8 {
+        let temp = (self.key2 | 2) as u16;
+        (temp.wrapping_mul(temp ^ 1) >> 8) as u8
+    }
+
+    /// Decrypts a buffer in place.
+    pub(crate) fn decrypt(&mut self, buf: &mut [u8]) {
+        for byte in buf {
+            *byte ^= self.stream_byte();
+            self.update(*byte);
+        }
+    }
+
+    /// Encrypts a buffer in place.
+    pub(crate) fn encrypt(&mut self, buf: &mut [u8]) {
+        for byte in buf {
+            let plain = *byte;
+            *byte ^= self.stream_byte();
+            self.update(plain);
+        }
+    }
+}
+
+// Initialise the keys from a password and construct an encrypted header from random bytes, ending with the provided
+// check byte.
+pub(crate) fn encrypt_header(password: &[u8], check: u8) -> std::io::Result<(ZipCryptoKeys, [u8; HEADER_LENGTH])> {
+    let mut header = [0; HEADER_LENGTH];
+    crate::crypto::fill_random(&mut header[..HEADER_LENGTH - 1])?;
+    header[HEADER_LENGTH - 1] = check;
+
+    let mut keys = ZipCryptoKeys::new(password);
+    keys.encrypt(&mut header);
+
+    Ok((keys, header))
+}
+
+// Initialise the keys from a password and decrypt an entry's encryption header with them, returning None if the
+// header's final byte doesn't match the expected check byte (ie. the password is wrong).
+//
+// As only a single byte is checked, roughly one in every 256 wrong passwords will pass, in which case the entry's data
+// decrypts to garbage and fails its CRC32 check instead.
+pub(crate) fn decrypt_header(password: &[u8], mut header: [u8; HEADER_LENGTH], check: u8) -> Option<ZipCryptoKeys> {
+    let mut keys = ZipCryptoKeys::new(password);
+    keys.decrypt(&mut header);
+
+    match header[HEADER_LENGTH - 1] == check {
+        true => Some(keys),
+        false => None,
+    }
+}
+
+/// A reader which decrypts the first `remaining` bytes read from its inner reader, and passes any beyond them (eg. a
+/// data descriptor) through untouched.
+pub(crate) struct ZipCryptoReader<R> {
+    inner: R,
+    keys: ZipCryptoKeys,
+    remaining: u64,
+}
+
+impl<R: AsyncRead + Unpin> ZipCryptoReader<R> {
+    pub(crate) fn new(inner: R, keys: ZipCryptoKeys, remaining: u64) -> Self {
+        Self { inner, keys, remaining }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ZipCryptoReader<R> {
+    fn poll_read(self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let this = self.get_mut();
+        let filled = b.filled().len();
+        let poll = Pin::new(&mut this.inner).poll_read(c, b);
+
+        let read = &mut b.filled_mut()[filled..];
+        let length = read.len().min(usize::try_from(this.remaining).unwrap_or(usize::MAX));
+
+        this.keys.decrypt(&mut read[..length]);
+        this.remaining -= length as u64;
+
+        poll
+    }
+}
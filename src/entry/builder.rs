@@ -4,10 +4,13 @@
 use chrono::{DateTime, Utc};
 use crate::spec::compression::Compression;
 use crate::spec::attribute::AttributeCompatibility;
-use crate::entry::ZipEntry;
-
-#[cfg(doc)]
+use crate::spec::extra_field::{ExtraField, EXTENDED_TIMESTAMP, NTFS};
 use crate::entry::ext::ZipEntryBuilderExt;
+use crate::entry::{EncryptionMethod, TimestampKind, TimestampSource, ZipEntry};
+use crate::error::Result;
+
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use super::CompressionLevel;
 
@@ -15,6 +18,12 @@ use super::CompressionLevel;
 /// 
 /// As with the built type, this builder is intended to solely provide access to the raw underlying data. Any
 /// additional or more complex operations are provided within an extension trait, [`ZipEntryBuilderExt`].
+///
+/// ## Reuse
+/// When writing many similar entries, a single builder may be passed to the writer by reference (which clones the
+/// entry, sharing its filename) and then [reset](ZipEntryBuilder::reset()) for the next entry, keeping the allocations
+/// of its extra field and comment.
+#[derive(Clone)]
 pub struct ZipEntryBuilder(pub(crate) ZipEntry);
 
 impl From<ZipEntry> for ZipEntryBuilder {
@@ -23,12 +32,56 @@ impl From<ZipEntry> for ZipEntryBuilder {
     }
 }
 
+impl From<&ZipEntryBuilder> for ZipEntry {
+    fn from(builder: &ZipEntryBuilder) -> Self {
+        builder.0.clone()
+    }
+}
+
 impl ZipEntryBuilder {
     /// Constructs a new builder which defines the raw underlying data of a ZIP entry.
     /// 
-    /// A filename and compression method are needed to construct the builder as minimal parameters.
-    pub fn new(filename: String, compression: Compression) -> Self {
-        Self(ZipEntry::new(filename, compression))
+    /// A filename and compression method are needed to construct the builder as minimal parameters. The filename may
+    /// be provided as any type convertible into an [`Arc<str>`] (eg. a [`String`] or a `&str` borrowed from a reused
+    /// buffer), which is then shared by any clones of the entry.
+    pub fn new(filename: impl Into<Arc<str>>, compression: Compression) -> Self {
+        Self(ZipEntry::new(filename.into(), compression))
+    }
+
+    /// Constructs a new builder for a directory entry.
+    ///
+    /// Any backslashes within the name are replaced with forward slashes, and a trailing slash is appended if it's
+    /// missing. The entry uses the Stored compression method, and has both the MS-DOS directory attribute and a Unix
+    /// permissions mode of `0o40755` (which may be changed via [`ZipEntryBuilderExt::unix_permissions()`]).
+    pub fn dir(name: &str) -> Self {
+        let mut name = name.replace('\\', "/");
+        if !name.ends_with('/') {
+            name.push('/');
+        }
+
+        Self::new(name, Compression::Stored).unix_permissions(0o755)
+    }
+
+    /// Sets the entry's filename.
+    pub fn filename(mut self, filename: impl Into<Arc<str>>) -> Self {
+        self.0.filename = filename.into();
+        self.0.filename_bytes = None;
+        self
+    }
+
+    /// Resets this builder to the state of one newly constructed with the provided filename and compression method.
+    ///
+    /// Unlike constructing a new builder, the existing allocations of the extra field and comment are kept for reuse.
+    pub fn reset(&mut self, filename: impl Into<Arc<str>>, compression: Compression) {
+        let mut extra_field = std::mem::take(&mut self.0.extra_field);
+        let mut extra_fields = std::mem::take(&mut self.0.extra_fields);
+        let mut comment = std::mem::take(&mut self.0.comment);
+
+        extra_field.clear();
+        extra_fields.clear();
+        comment.clear();
+
+        self.0 = ZipEntry { extra_field, extra_fields, comment, ..ZipEntry::new(filename.into(), compression) };
     }
 
     /// Set the compression level.
@@ -40,6 +93,29 @@ impl ZipEntryBuilder {
 	self
     }
 
+    /// Lets the writer choose between the Stored and Deflate compression methods, based on how well the entry's data
+    /// compresses.
+    ///
+    /// When the entry is written, up to its first [`AUTO_COMPRESSION_SAMPLE_SIZE`] bytes are compressed with Deflate.
+    /// The entry is then only compressed if the sample compresses to less than `threshold` times its size (see
+    /// [`DEFAULT_AUTO_COMPRESSION_THRESHOLD`]), and is otherwise Stored. This overrides the compression method which
+    /// the builder was constructed with, and avoids wasting time compressing data which is already compressed.
+    ///
+    /// ## Note
+    /// As a method must be chosen before any of the entry's data is written, this is only supported when the entry is
+    /// written whole (eg. via [`ZipFileWriter::write_entry_whole()`]). Streaming the entry instead fails with
+    /// [`ZipError::FeatureNotSupported`].
+    ///
+    /// [`AUTO_COMPRESSION_SAMPLE_SIZE`]: crate::write::AUTO_COMPRESSION_SAMPLE_SIZE
+    /// [`DEFAULT_AUTO_COMPRESSION_THRESHOLD`]: crate::write::DEFAULT_AUTO_COMPRESSION_THRESHOLD
+    /// [`ZipFileWriter::write_entry_whole()`]: crate::write::ZipFileWriter::write_entry_whole
+    /// [`ZipError::FeatureNotSupported`]: crate::error::ZipError::FeatureNotSupported
+    #[cfg(feature = "deflate")]
+    pub fn auto_compression(mut self, threshold: f32) -> Self {
+        self.0.auto_compression = Some(threshold);
+        self
+    }
+
     /// Sets the entry's attribute host compatibility.
     pub fn attribute_compatibility(mut self, compatibility: AttributeCompatibility) -> Self {
         self.0.attribute_compatibility = compatibility;
@@ -47,12 +123,53 @@ impl ZipEntryBuilder {
     }
 
     /// Sets the entry's last modification date.
+    ///
+    /// ## Note
+    /// This is written as an MS-DOS date & time, which has a precision of two seconds and can only represent the
+    /// years 1980 to 2107, so dates outside that range are clamped to its bounds. The date is also recorded to the
+    /// second within an extended timestamp extra field (0x5455) where it's within the range of a 32-bit Unix time,
+    /// which [`ZipEntry::last_modification_date()`] prefers when read back.
     pub fn last_modification_date(mut self, date: DateTime<Utc>) -> Self {
         self.0.last_modification_date = date;
+        self.set_timestamp(TimestampKind::Modified, date.into())
+    }
+
+    /// Sets the entry's last modification time, recording it within an extended timestamp extra field (to the second)
+    /// as well as the MS-DOS date & time (see [`ZipEntryBuilder::last_modification_date()`]).
+    ///
+    /// The time is also recorded within an NTFS extra field (to 100 nanoseconds) if enabled via
+    /// [`ZipEntryBuilder::ntfs_timestamps()`].
+    pub fn modified(mut self, time: SystemTime) -> Self {
+        self.0.last_modification_date = time.into();
+        self.set_timestamp(TimestampKind::Modified, time)
+    }
+
+    /// Sets the entry's last access time, recording it within the same extra fields as [`ZipEntryBuilder::modified()`].
+    pub fn accessed(self, time: SystemTime) -> Self {
+        self.set_timestamp(TimestampKind::Accessed, time)
+    }
+
+    /// Sets the entry's creation time, recording it within the same extra fields as [`ZipEntryBuilder::modified()`].
+    pub fn created(self, time: SystemTime) -> Self {
+        self.set_timestamp(TimestampKind::Created, time)
+    }
+
+    /// Sets whether times set via this builder are also recorded within an NTFS extra field, which is disabled by
+    /// default.
+    ///
+    /// ## Note
+    /// The extended timestamp extra field can only represent whole seconds between 1901 and 2038, so any time outside
+    /// that range is only recorded if this is enabled.
+    pub fn ntfs_timestamps(mut self, enabled: bool) -> Self {
+        self.0.ntfs_timestamps = enabled;
+        self.write_timestamp_fields();
         self
     }
 
     /// Sets the entry's internal file attribute.
+    ///
+    /// Bit 0 denotes that the entry is believed to be text (see [`ZipEntryBuilderExt::text()`]), whereas the other
+    /// bits are rarely used.
     pub fn internal_file_attribute(mut self, attribute: u16) -> Self {
         self.0.internal_file_attribute = attribute;
         self
@@ -64,9 +181,22 @@ impl ZipEntryBuilder {
         self
     }
 
-    /// Sets the entry's extra field data.
-    pub fn extra_field(mut self, field: Vec<u8>) -> Self {
-        self.0.extra_field = field;
+    /// Appends a record to the entry's extra field data.
+    pub fn extra_field(mut self, header_id: u16, data: Vec<u8>) -> Self {
+        let field = ExtraField::new(header_id, data);
+        field.write_to(&mut self.0.extra_field);
+        self.0.extra_fields.push(field);
+        self
+    }
+
+    /// Appends several custom extra field records to the entry, in order.
+    ///
+    /// Each record is written to both the local file header and the central directory record.
+    pub fn extra_fields(mut self, fields: Vec<ExtraField>) -> Self {
+        for field in fields {
+            field.write_to(&mut self.0.extra_field);
+            self.0.extra_fields.push(field);
+        }
         self
     }
 
@@ -76,6 +206,76 @@ impl ZipEntryBuilder {
         self
     }
 
+    /// Replaces any backslashes within the entry's filename with forward slashes.
+    ///
+    /// The specification requires forward slashes to be used as path separators, but archives written on Windows
+    /// often contain paths joined with backslashes. This is opt-in, as backslashes are otherwise valid characters
+    /// within a filename.
+    pub fn normalize_separators(mut self) -> Self {
+        if self.0.filename.contains('\\') {
+            self.0.filename = self.0.filename.replace('\\', "/").into();
+        }
+        self
+    }
+
+    /// Sets the entry's "version made by" field, overriding the value the writer would otherwise compute.
+    pub fn version_made_by(mut self, version: u16) -> Self {
+        self.0.version_made_by = Some(version);
+        self
+    }
+
+    /// Sets the entry's "version needed to extract" field, overriding the value the writer would otherwise compute.
+    pub fn version_needed(mut self, version: u16) -> Self {
+        self.0.version_needed = Some(version);
+        self
+    }
+
+    /// Sets the alignment of the entry's data within the ZIP file, in bytes.
+    ///
+    /// When written, a padding record (as used by Android's `zipalign` tool) is appended to the local file header's
+    /// extra field so that the entry's data begins at a multiple of this value. The central directory's copy of the
+    /// extra field is left unpadded. An alignment of 0 or 1 disables padding, which is the default.
+    ///
+    /// ## Note
+    /// - Android requires 4-byte alignment of stored entries, and 4096-byte alignment of stored native libraries.
+    /// - When splitting a ZIP file, the alignment is relative to the start of the first part rather than each part.
+    pub fn alignment(mut self, alignment: u32) -> Self {
+        self.0.alignment = alignment;
+        self
+    }
+
+    /// Sets whether the entry's local file header always reserves a ZIP64 extended information extra field, which is
+    /// disabled by default.
+    ///
+    /// The writer otherwise only uses ZIP64 where an entry's sizes or offset require it. That isn't known before a
+    /// stream-written entry's local file header is written, so such an entry may only exceed 4 GiB if its header is
+    /// followed by a data descriptor (and its sizes are then only recorded within the descriptor and central
+    /// directory). Reserving the field allows the entry's sizes to be patched into its local file header (see
+    /// [`ZipFileWriter::patch_local_headers()`]), and lets readers expect a ZIP64 data descriptor.
+    ///
+    /// [`ZipFileWriter::patch_local_headers()`]: crate::write::ZipFileWriter::patch_local_headers
+    pub fn force_zip64(mut self, force: bool) -> Self {
+        self.0.force_zip64 = force;
+        self
+    }
+
+    /// Encrypts the entry's data with the provided password whilst it's written, using the provided method.
+    ///
+    /// The entry is then marked as encrypted, and must be read via a reader's `entry_reader_with_password()` method
+    /// (or another tool, given the same password). Only the entry's data is encrypted; its filename and other metadata
+    /// remain readable by anyone.
+    ///
+    /// With [`EncryptionMethod::ZipCrypto`], an entry written via [`ZipFileWriter::write_entry_stream()`] is always
+    /// followed by a data descriptor (even if [`ZipFileWriter::patch_local_headers()`] is enabled), as the password
+    /// check byte within its encryption header is otherwise taken from the CRC32 value, which isn't yet known.
+    ///
+    /// [`ZipFileWriter::write_entry_stream()`]: crate::write::ZipFileWriter::write_entry_stream
+    /// [`ZipFileWriter::patch_local_headers()`]: crate::write::ZipFileWriter::patch_local_headers
+    pub fn password(mut self, password: &str, method: EncryptionMethod) -> Self {
+        self.0.encryption = Some((method, Arc::from(password.as_bytes())));
+        self
+    }
+
     /// Consumes this builder and returns a final [`ZipEntry`].
     /// 
     /// This is equivalent to:
@@ -85,7 +285,60 @@ impl ZipEntryBuilder {
     /// # let builder = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Deflate);
     /// let entry: ZipEntry = builder.into();
     /// ```
+    ///
+    /// The entry isn't validated until it's written, see [`ZipEntryBuilder::try_build()`].
     pub fn build(self) -> ZipEntry {
         self.into()
     }
+
+    /// Consumes this builder and returns a final [`ZipEntry`], if it can be written.
+    ///
+    /// This fails with [`ZipError::FilenameTooLong`], [`ZipError::CommentTooLong`], or [`ZipError::ExtraFieldTooLong`]
+    /// if any of those fields exceed 65535 bytes, or [`ZipError::InvalidFilename`] if the filename contains a NUL
+    /// character. Entries are always validated in the same way when written.
+    ///
+    /// [`ZipError::FilenameTooLong`]: crate::error::ZipError::FilenameTooLong
+    /// [`ZipError::CommentTooLong`]: crate::error::ZipError::CommentTooLong
+    /// [`ZipError::ExtraFieldTooLong`]: crate::error::ZipError::ExtraFieldTooLong
+    /// [`ZipError::InvalidFilename`]: crate::error::ZipError::InvalidFilename
+    pub fn try_build(self) -> Result<ZipEntry> {
+        self.0.validate()?;
+        Ok(self.0)
+    }
+
+    fn set_timestamp(mut self, kind: TimestampKind, time: SystemTime) -> Self {
+        // Keep any times already recorded within the extra field (eg. of an entry which has been read).
+        if self.0.timestamps.iter().all(Option::is_none) {
+            let kinds = [TimestampKind::Modified, TimestampKind::Accessed, TimestampKind::Created];
+            let recorded = kinds.map(|kind| self.0.timestamp(kind));
+            self.0.timestamps = recorded
+                .map(|recorded| recorded.filter(|(_, source)| *source != TimestampSource::Dos).map(|(time, _)| time));
+        }
+
+        self.0.timestamps[kind.index()] = Some(time.into());
+        self.write_timestamp_fields();
+        self
+    }
+
+    // Replace any timestamp records within the entry's extra field with ones holding the times set via this builder.
+    fn write_timestamp_fields(&mut self) {
+        let entry = &mut self.0;
+        if entry.timestamps.iter().all(Option::is_none) {
+            return;
+        }
+
+        entry.extra_fields.retain(|field| field.header_id != EXTENDED_TIMESTAMP && field.header_id != NTFS);
+
+        if let Some(data) = crate::spec::extra_field::extended_timestamp_data(&entry.timestamps) {
+            entry.extra_fields.push(ExtraField::new(EXTENDED_TIMESTAMP, data));
+        }
+        if entry.ntfs_timestamps {
+            entry.extra_fields.push(ExtraField::new(NTFS, crate::spec::extra_field::ntfs_data(&entry.timestamps)));
+        }
+
+        entry.extra_field.clear();
+        for field in &entry.extra_fields {
+            field.write_to(&mut entry.extra_field);
+        }
+    }
 }
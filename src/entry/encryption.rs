@@ -0,0 +1,18 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+/// A method of encrypting an entry's data whilst it's written, as selected via [`ZipEntryBuilder::password()`].
+///
+/// [`ZipEntryBuilder::password()`]: crate::ZipEntryBuilder::password
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionMethod {
+    /// The traditional PKWARE encryption scheme (commonly known as ZipCrypto).
+    ///
+    /// This is supported by almost every tool which can read encrypted ZIP files, but is cryptographically weak and
+    /// shouldn't be relied upon to keep data confidential.
+    ZipCrypto,
+    /// WinZip's AES encryption with a 256-bit key, in its AE-2 form (ie. the entry's CRC32 value isn't stored, as the
+    /// data is authenticated instead).
+    #[cfg(feature = "aes")]
+    Aes256,
+}
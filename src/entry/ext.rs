@@ -2,8 +2,27 @@
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
 use crate::spec::attribute::AttributeCompatibility;
+use crate::spec::compression::Compression;
 use crate::entry::{ZipEntry, ZipEntryBuilder};
 
+/// The header ID of the Info-ZIP Unix extra field which holds an entry's owning user and group IDs.
+pub(crate) const UNIX_OWNERSHIP_HEADER_ID: u16 = 0x7875;
+
+/// The bit of the internal file attribute which denotes that an entry is believed to be text.
+pub(crate) const TEXT_ATTRIBUTE: u16 = 0x0001;
+
+/// The directory within which macOS Finder stores the AppleDouble companions of an archive's entries.
+pub(crate) const MACOS_METADATA_DIRECTORY: &str = "__MACOSX/";
+
+// The Unix file type bits of a mode, and the file types of a directory, a regular file, and a symbolic link.
+pub(crate) const S_IFMT: u32 = 0o170000;
+pub(crate) const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+// The MS-DOS attribute bit (within the lower byte of the external file attribute) which denotes a directory.
+pub(crate) const DOS_DIRECTORY: u32 = 0x10;
+
 /// A trait that extends [`ZipEntry`]'s functionality.
 pub trait ZipEntryExt: Sized {
     /// Returns the entry's integer-based UNIX permissions.
@@ -11,6 +30,26 @@ pub trait ZipEntryExt: Sized {
     /// # Note
     /// This will return None if the attribute host compatibility is not listed as Unix.
     fn unix_permissions(&self) -> Option<u16>;
+
+    /// Returns whether or not the entry is a symbolic link, in which case its data is the link's target path.
+    ///
+    /// # Note
+    /// This will return false if the attribute host compatibility is not listed as Unix.
+    fn is_symlink(&self) -> bool;
+
+    /// Returns the entry's owning user and group IDs from its Info-ZIP Unix (0x7875) extra field, if present.
+    fn unix_ownership(&self) -> Option<(u32, u32)>;
+
+    /// Returns whether or not the entry is believed to be text, as denoted by bit 0 of its internal file attribute.
+    fn is_text(&self) -> bool;
+
+    /// Returns whether or not the entry is an AppleDouble file (ie. a file whose name begins with `._`), which holds
+    /// the extended attributes and resource fork of another entry written on macOS.
+    fn is_apple_double(&self) -> bool;
+
+    /// Returns whether or not the entry is metadata written by macOS rather than content: an AppleDouble file, a
+    /// `.DS_Store` file, or anything within the `__MACOSX/` directory.
+    fn is_macos_metadata(&self) -> bool;
 }
 
 impl ZipEntryExt for ZipEntry {
@@ -21,6 +60,83 @@ impl ZipEntryExt for ZipEntry {
 
         Some(((self.external_file_attribute) >> 16) as u16)
     }
+
+    fn is_symlink(&self) -> bool {
+        self.unix_permissions().map_or(false, |mode| u32::from(mode) & S_IFMT == S_IFLNK)
+    }
+
+    fn unix_ownership(&self) -> Option<(u32, u32)> {
+        let field = self.extra_fields.iter().find(|field| field.header_id() == UNIX_OWNERSHIP_HEADER_ID)?;
+        parse_unix_ownership(field.data())
+    }
+
+    fn is_text(&self) -> bool {
+        self.internal_file_attribute & TEXT_ATTRIBUTE != 0
+    }
+
+    fn is_apple_double(&self) -> bool {
+        !self.filename.ends_with('/') && split_last_component(&self.filename).1.starts_with("._")
+    }
+
+    fn is_macos_metadata(&self) -> bool {
+        let name = split_last_component(&self.filename).1;
+        self.filename.starts_with(MACOS_METADATA_DIRECTORY) || self.is_apple_double() || name == ".DS_Store"
+    }
+}
+
+// Split a filename into its parent path (including the trailing separator) and final component, ignoring any trailing
+// separator denoting a directory.
+fn split_last_component(filename: &str) -> (&str, &str) {
+    let filename = filename.strip_suffix('/').unwrap_or(filename);
+
+    match filename.rfind('/') {
+        Some(index) => filename.split_at(index + 1),
+        None => ("", filename),
+    }
+}
+
+// Returns the filenames which the AppleDouble companion of an entry may have: within the `__MACOSX/` directory (as
+// written by Finder), or alongside the entry itself (as written by other macOS tools). Metadata has no companion.
+pub(crate) fn apple_double_filenames(entry: &ZipEntry) -> Vec<String> {
+    if entry.is_macos_metadata() {
+        return Vec::new();
+    }
+
+    let (parent, name) = split_last_component(entry.filename());
+    vec![format!("{}{}._{}", MACOS_METADATA_DIRECTORY, parent, name), format!("{}._{}", parent, name)]
+}
+
+// Parse the data of a version 1 Info-ZIP Unix extra field: a version byte, followed by the byte size and little-endian
+// value of the UID, and then of the GID. Each ID may be stored with any width, but only those which fit a u32 are read.
+fn parse_unix_ownership(data: &[u8]) -> Option<(u32, u32)> {
+    let (&version, data) = data.split_first()?;
+    if version != 1 {
+        return None;
+    }
+
+    let (uid, data) = parse_sized_id(data)?;
+    let (gid, _) = parse_sized_id(data)?;
+
+    Some((uid, gid))
+}
+
+fn parse_sized_id(data: &[u8]) -> Option<(u32, &[u8])> {
+    let (&size, data) = data.split_first()?;
+    let size = usize::from(size);
+
+    if data.len() < size {
+        return None;
+    }
+
+    let (id, data) = data.split_at(size);
+    if id.iter().skip(4).any(|&byte| byte != 0) {
+        return None;
+    }
+
+    let mut bytes = [0; 4];
+    bytes[..size.min(4)].copy_from_slice(&id[..size.min(4)]);
+
+    Some((u32::from_le_bytes(bytes), data))
 }
 
 /// A trait that extends [`ZipEntryBuilder`]'s functionality.
@@ -30,13 +146,74 @@ pub trait ZipEntryBuilderExt {
     /// # Note
     /// This will force the entry's attribute host compatibility to Unix as well as override the previous upper
     /// sixteen bits of the entry's external file attribute (which includes any previous permissions mode).
+    ///
+    /// If the mode holds no file type bits, one is added: the directory file type (`0o040000`) if the entry is a
+    /// directory (ie. its filename ends with `/`), along with the MS-DOS directory attribute within the lower bits, and
+    /// the regular file type (`0o100000`) otherwise.
     fn unix_permissions(self, mode: u16) -> Self;
+
+    /// Marks the entry as a symbolic link to the provided target path.
+    ///
+    /// The target is written as the entry's data, so the entry must be written via
+    /// [`ZipFileWriter::write_entry_whole()`] with empty data. The Stored compression method is used, as is
+    /// conventional for symbolic links.
+    ///
+    /// # Note
+    /// This will force the entry's attribute host compatibility to Unix and set its permissions mode to `0o120777`
+    /// (ie. a symbolic link with all permissions), as expected by tools such as `unzip` when recreating links.
+    ///
+    /// [`ZipFileWriter::write_entry_whole()`]: crate::write::ZipFileWriter::write_entry_whole
+    fn symlink(self, target: &str) -> Self;
+
+    /// Appends an Info-ZIP Unix (0x7875) extra field holding the entry's owning user and group IDs.
+    fn unix_ownership(self, uid: u32, gid: u32) -> Self;
+
+    /// Sets whether or not the entry is believed to be text, via bit 0 of its internal file attribute.
+    ///
+    /// Some extractors use this to convert the entry's line endings or character set (eg. ASCII to EBCDIC). See also
+    /// [`ZipFileWriter::detect_text()`] to set this automatically.
+    ///
+    /// [`ZipFileWriter::detect_text()`]: crate::write::ZipFileWriter::detect_text
+    fn text(self, text: bool) -> Self;
 }
 
 impl ZipEntryBuilderExt for ZipEntryBuilder {
     fn unix_permissions(mut self, mode: u16) -> Self {
+        let mut mode = u32::from(mode);
+        let mut attribute = self.0.external_file_attribute & 0xFFFF;
+
+        if mode & S_IFMT == 0 && self.0.filename.ends_with('/') {
+            mode |= S_IFDIR;
+            attribute |= DOS_DIRECTORY;
+        } else if mode & S_IFMT == 0 {
+            mode |= S_IFREG;
+        }
+
         self.0.attribute_compatibility = AttributeCompatibility::Unix;
-        self.0.external_file_attribute = (self.0.external_file_attribute & 0xFFFF) | (mode as u32) << 16;
+        self.0.external_file_attribute = attribute | mode << 16;
+        self
+    }
+
+    fn symlink(mut self, target: &str) -> Self {
+        self.0.compression = Compression::Stored;
+        self.0.symlink_target = Some(target.to_owned());
+        self.unix_permissions((S_IFLNK | 0o777) as u16)
+    }
+
+    fn unix_ownership(self, uid: u32, gid: u32) -> Self {
+        let mut data = vec![1, 4];
+        data.extend_from_slice(&uid.to_le_bytes());
+        data.push(4);
+        data.extend_from_slice(&gid.to_le_bytes());
+
+        self.extra_field(UNIX_OWNERSHIP_HEADER_ID, data)
+    }
+
+    fn text(mut self, text: bool) -> Self {
+        match text {
+            true => self.0.internal_file_attribute |= TEXT_ATTRIBUTE,
+            false => self.0.internal_file_attribute &= !TEXT_ATTRIBUTE,
+        }
         self
     }
-}
\ No newline at end of file
+}
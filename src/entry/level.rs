@@ -23,6 +23,15 @@ pub enum CompressionLevel {
 }
 
 impl CompressionLevel {
+    pub(crate) fn from_level(level: Level) -> Self {
+        match level {
+            Level::Fastest => CompressionLevel::Fastest,
+            Level::Best => CompressionLevel::Best,
+            Level::Precise(n) => CompressionLevel::Precise(n),
+            _ => CompressionLevel::Default,
+        }
+    }
+
     pub(crate) fn into_level(self) -> Level {
 	match self {
 	    CompressionLevel::Fastest => Level::Fastest,
@@ -4,13 +4,28 @@
 mod level;
 pub use level::CompressionLevel;
 
+mod summary;
+pub use summary::EntrySummary;
+
+mod timestamp;
+pub use timestamp::{TimestampKind, TimestampSource};
+
+mod encryption;
+pub use encryption::EncryptionMethod;
+
 pub mod ext;
 pub mod builder;
 
 use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+use std::sync::Arc;
 use crate::spec::compression::Compression;
 use crate::spec::attribute::AttributeCompatibility;
+use crate::spec::extra_field::{ExtraField, EXTENDED_TIMESTAMP, NTFS};
+use crate::spec::header::GeneralPurposeFlag;
+use crate::spec::version::ZipVersion;
 use crate::entry::builder::ZipEntryBuilder;
+use crate::error::{Result, ZipError};
 
 #[cfg(doc)]
 use crate::entry::ext::ZipEntryExt;
@@ -23,20 +38,35 @@ use crate::entry::ext::ZipEntryExt;
 /// This type cannot be directly constructed so instead, the [`ZipEntryBuilder`] must be used. Internally this builder
 /// stores a [`ZipEntry`] so conversions between these two types via the [`From`] implementations will be
 /// non-allocating.
+///
+/// The filename is reference counted, so clones of an entry share it rather than copying it.
 #[derive(Clone)]
 pub struct ZipEntry {
-    pub(crate) filename: String,
+    pub(crate) filename: Arc<str>,
+    pub(crate) filename_raw: Option<Arc<str>>,
+    pub(crate) filename_bytes: Option<Arc<[u8]>>,
     pub(crate) compression: Compression,
     pub(crate) compression_level: async_compression::Level,
+    pub(crate) auto_compression: Option<f32>,
     pub(crate) crc32: u32,
-    pub(crate) uncompressed_size: u32,
-    pub(crate) compressed_size: u32,
+    pub(crate) uncompressed_size: u64,
+    pub(crate) compressed_size: u64,
     pub(crate) attribute_compatibility: AttributeCompatibility,
     pub(crate) last_modification_date: DateTime<Utc>,
     pub(crate) internal_file_attribute: u16,
     pub(crate) external_file_attribute: u32,
     pub(crate) extra_field: Vec<u8>,
+    pub(crate) extra_fields: Vec<ExtraField>,
     pub(crate) comment: String,
+    pub(crate) version_made_by: Option<u16>,
+    pub(crate) version_needed: Option<u16>,
+    pub(crate) general_purpose_flag: GeneralPurposeFlag,
+    pub(crate) symlink_target: Option<String>,
+    pub(crate) alignment: u32,
+    pub(crate) force_zip64: bool,
+    pub(crate) timestamps: [Option<DateTime<Utc>>; 3],
+    pub(crate) ntfs_timestamps: bool,
+    pub(crate) encryption: Option<(EncryptionMethod, Arc<[u8]>)>,
 }
 
 impl From<ZipEntryBuilder> for ZipEntry {
@@ -46,11 +76,14 @@ impl From<ZipEntryBuilder> for ZipEntry {
 }
 
 impl ZipEntry {
-    pub(crate) fn new(filename: String, compression: Compression) -> Self {
+    pub(crate) fn new(filename: Arc<str>, compression: Compression) -> Self {
         ZipEntry {
             filename,
+            filename_raw: None,
+            filename_bytes: None,
             compression,
             compression_level: async_compression::Level::Default,
+            auto_compression: None,
             crc32: 0,
             uncompressed_size: 0,
             compressed_size: 0,
@@ -59,7 +92,17 @@ impl ZipEntry {
             internal_file_attribute: 0,
             external_file_attribute: 0,
             extra_field: Vec::new(),
+            extra_fields: Vec::new(),
             comment: String::new(),
+            version_made_by: None,
+            version_needed: None,
+            general_purpose_flag: GeneralPurposeFlag::from(0),
+            symlink_target: None,
+            alignment: 0,
+            force_zip64: false,
+            timestamps: [None; 3],
+            ntfs_timestamps: false,
+            encryption: None,
         }
     }
 
@@ -73,23 +116,67 @@ impl ZipEntry {
         &self.filename
     }
 
+    /// Returns the entry's filename before it was sanitised whilst being read.
+    ///
+    /// ## Note
+    /// This only differs from [`ZipEntry::filename()`] where the filename was rewritten under [`PathPolicy::Sanitize`].
+    /// The undecoded bytes stored within the ZIP file are available via [`ZipEntry::filename_bytes()`].
+    ///
+    /// [`PathPolicy::Sanitize`]: crate::read::PathPolicy::Sanitize
+    pub fn filename_raw(&self) -> &str {
+        self.filename_raw.as_deref().unwrap_or(&self.filename)
+    }
+
+    /// Returns the bytes of the entry's filename exactly as they were stored within the ZIP file, so that callers can
+    /// apply their own decoding.
+    ///
+    /// ## Note
+    /// Filenames are decoded as UTF-8 where the entry's language encoding flag is set, and otherwise as CP437. A UTF-8
+    /// filename held by an Info-ZIP Unicode path extra field (0x7075) is preferred whenever its CRC32 value matches
+    /// these bytes. For entries which weren't read from a ZIP file, these are the bytes of
+    /// [`ZipEntry::filename_raw()`].
+    pub fn filename_bytes(&self) -> &[u8] {
+        self.filename_bytes.as_deref().unwrap_or(self.filename_raw().as_bytes())
+    }
+
     /// Returns the entry's compression method.
     pub fn compression(&self) -> Compression {
         self.compression
     }
 
+    /// Returns the level of compression used when writing the entry.
+    ///
+    /// ## Note
+    /// This isn't recorded within ZIP files, so is [`CompressionLevel::Default`] for any entry which has been read.
+    pub fn compression_level(&self) -> CompressionLevel {
+        CompressionLevel::from_level(self.compression_level)
+    }
+
+    /// Returns the entry's raw compression method, including any which this crate doesn't support.
+    pub fn compression_raw(&self) -> u16 {
+        self.compression.into()
+    }
+
+    /// Returns the entry's general purpose bit flags.
+    ///
+    /// ## Note
+    /// For entries which haven't yet been written, all flags are unset.
+    pub fn general_purpose_flags(&self) -> GeneralPurposeFlag {
+        self.general_purpose_flag
+    }
+
     /// Returns the entry's CRC32 value.
     pub fn crc32(&self) -> u32 {
         self.crc32
     }
 
     /// Returns the entry's uncompressed size.
-    pub fn uncompressed_size(&self) -> u32 {
+    pub fn uncompressed_size(&self) -> u64 {
         self.uncompressed_size
     }
 
     /// Returns the entry's compressed size.
-    pub fn compressed_size(&self) -> u32 {
+    pub fn compressed_size(&self) -> u64 {
         self.compressed_size
     }
 
@@ -99,10 +186,77 @@ impl ZipEntry {
     }
 
     /// Returns the entry's last modification time & date.
+    ///
+    /// ## Note
+    /// This is taken from the entry's extended timestamp extra field (0x5455) where present, which holds the time to
+    /// the second, and otherwise from its MS-DOS date & time, which has a precision of two seconds.
     pub fn last_modification_date(&self) -> &DateTime<Utc> {
         &self.last_modification_date
     }
 
+    // Remove any timestamp records from the entry's extra field, as done for reproducible output.
+    pub(crate) fn remove_timestamp_fields(&mut self) {
+        let is_timestamp = |field: &ExtraField| field.header_id == EXTENDED_TIMESTAMP || field.header_id == NTFS;
+        self.timestamps = [None; 3];
+        if !self.extra_fields.iter().any(is_timestamp) {
+            return;
+        }
+
+        self.extra_fields.retain(|field| !is_timestamp(field));
+        self.extra_field.clear();
+        for field in &self.extra_fields {
+            field.write_to(&mut self.extra_field);
+        }
+    }
+
+    /// Returns the entry's last modification time, from the most precise source recorded.
+    ///
+    /// See [`ZipEntry::timestamp()`] for the sources and their precedence.
+    pub fn modified(&self) -> Option<DateTime<Utc>> {
+        self.timestamp(TimestampKind::Modified).map(|(time, _)| time)
+    }
+
+    /// Returns the entry's last access time, if recorded within its extra field.
+    pub fn accessed(&self) -> Option<DateTime<Utc>> {
+        self.timestamp(TimestampKind::Accessed).map(|(time, _)| time)
+    }
+
+    /// Returns the entry's creation time, if recorded within its extra field.
+    pub fn created(&self) -> Option<DateTime<Utc>> {
+        self.timestamp(TimestampKind::Created).map(|(time, _)| time)
+    }
+
+    /// Returns one of the entry's times along with its source.
+    ///
+    /// An NTFS extra field takes precedence over an extended timestamp extra field, which takes precedence over the
+    /// MS-DOS date & time. The MS-DOS date & time is only a source of the modification time, and isn't used if it was
+    /// invalid.
+    ///
+    /// ## Note
+    /// As with [`ZipEntry::extra_fields()`], entries listed from the central directory are read from its extra
+    /// fields, which commonly hold only the modification time within an extended timestamp extra field.
+    pub fn timestamp(&self, kind: TimestampKind) -> Option<(DateTime<Utc>, TimestampSource)> {
+        let field = |header_id| self.extra_fields.iter().find(|field| field.header_id == header_id);
+
+        let ntfs = field(NTFS).and_then(|field| crate::spec::extra_field::parse_ntfs(&field.data)[kind.index()]);
+        if let Some(time) = ntfs {
+            return Some((time, TimestampSource::Ntfs));
+        }
+
+        let extended = field(EXTENDED_TIMESTAMP)
+            .and_then(|field| crate::spec::extra_field::parse_extended_timestamp(&field.data)[kind.index()]);
+        if let Some(time) = extended {
+            return Some((time, TimestampSource::ExtendedTimestamp));
+        }
+
+        match kind {
+            TimestampKind::Modified if self.last_modification_date != DateTime::<Utc>::MIN_UTC => {
+                Some((self.last_modification_date, TimestampSource::Dos))
+            }
+            _ => None,
+        }
+    }
+
     /// Returns the entry's internal file attribute.
     pub fn internal_file_attribute(&self) -> u16 {
         self.internal_file_attribute
@@ -113,13 +267,108 @@ impl ZipEntry {
         self.external_file_attribute
     }
 
-    /// Returns the entry's extra field data.
+    /// Returns whether or not the entry is a directory.
+    ///
+    /// This is the case if the entry's filename ends with a forward slash, or its external file attribute holds the
+    /// MS-DOS directory attribute (or, where its attribute host compatibility is Unix, a mode of the directory type).
+    pub fn dir(&self) -> bool {
+        use crate::entry::ext::{DOS_DIRECTORY, S_IFDIR, S_IFMT};
+
+        let unix_dir = self.attribute_compatibility == AttributeCompatibility::Unix
+            && (self.external_file_attribute >> 16) & S_IFMT == S_IFDIR;
+
+        self.filename.ends_with('/') || self.external_file_attribute & DOS_DIRECTORY != 0 || unix_dir
+    }
+
+    /// Returns the entry's raw extra field data.
     pub fn extra_field(&self) -> &[u8] {
         &self.extra_field
     }
 
+    /// Returns the records parsed from the entry's extra field data.
+    ///
+    /// ## Note
+    /// Entries listed from the central directory expose the central directory's extra fields, whereas entries from
+    /// the stream reader expose the local file header's. The local file header's fields of an entry listed from the
+    /// central directory can be retrieved via [`ZipEntryReader::local_extra_fields()`].
+    ///
+    /// Any malformed trailing record (ie. one which overruns the extra field block) is omitted, but remains available
+    /// within [`ZipEntry::extra_field()`].
+    ///
+    /// [`ZipEntryReader::local_extra_fields()`]: crate::read::ZipEntryReader::local_extra_fields
+    pub fn extra_fields(&self) -> &[ExtraField] {
+        &self.extra_fields
+    }
+
     /// Returns the entry's file comment.
     pub fn comment(&self) -> &str {
         &self.comment
     }
+
+    /// Returns the version of the specification which the entry was made by.
+    ///
+    /// ## Note
+    /// This is only present for entries which have been read from the central directory, or if explicitly set via
+    /// [`ZipEntryBuilder::version_made_by()`].
+    pub fn version_made_by(&self) -> Option<ZipVersion> {
+        self.version_made_by.map(ZipVersion::from_raw)
+    }
+
+    /// Returns the minimum version of the specification needed to extract the entry.
+    ///
+    /// ## Note
+    /// This is only present for entries which have been read, or if explicitly set via
+    /// [`ZipEntryBuilder::version_needed()`].
+    pub fn version_needed(&self) -> Option<ZipVersion> {
+        self.version_needed.map(ZipVersion::from_raw)
+    }
+
+    /// Returns the target path of a symbolic link entry, if known.
+    ///
+    /// ## Note
+    /// For entries built via [`ZipEntryBuilderExt::symlink()`], this is the provided target. For entries read by the
+    /// seek, fs, or mem readers, the (small) data of each symbolic link entry is read when the ZIP file is opened.
+    /// When entries are listed lazily this isn't the case, but [`ZipEntryExt::is_symlink()`] can still be used to
+    /// determine that the entry's data is a target path. Entries read by the stream reader are never known to be
+    /// symbolic links, as local file headers don't hold the external file attribute.
+    ///
+    /// [`ZipEntryBuilderExt::symlink()`]: crate::entry::ext::ZipEntryBuilderExt::symlink
+    pub fn symlink_target(&self) -> Option<&str> {
+        self.symlink_target.as_deref()
+    }
+
+    /// Returns the entry's filename as a relative path which is safe to join onto an extraction directory.
+    ///
+    /// `None` is returned if the filename is rejected by [`sanitize_entry_path()`].
+    ///
+    /// [`sanitize_entry_path()`]: crate::sanitize::sanitize_entry_path
+    pub fn sanitized_path(&self) -> Option<PathBuf> {
+        crate::sanitize::sanitize_entry_path(&self.filename).ok()
+    }
+
+    // Check that the entry's variable-length fields can be represented within its headers.
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.filename.len() > u16::MAX as usize {
+            return Err(ZipError::FilenameTooLong(self.filename.len()));
+        }
+        if self.filename.contains('\0') {
+            return Err(ZipError::InvalidFilename(self.filename.to_string()));
+        }
+        if self.comment.len() > u16::MAX as usize {
+            return Err(ZipError::CommentTooLong(self.comment.len()));
+        }
+        if self.extra_field.len() > u16::MAX as usize {
+            return Err(ZipError::ExtraFieldTooLong(self.extra_field.len()));
+        }
+
+        // The local file header's extra field must be able to hold the largest padding the alignment may require, along
+        // with any ZIP64 extended information record reserved ahead of time.
+        let reserved = if self.force_zip64 { 20 } else { 0 };
+        let padded_length = self.extra_field.len() + crate::write::max_alignment_padding(self.alignment) + reserved;
+        if padded_length > u16::MAX as usize {
+            return Err(ZipError::ExtraFieldTooLong(padded_length));
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,102 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::entry::ZipEntry;
+use crate::spec::attribute::AttributeCompatibility;
+use crate::spec::compression::Compression;
+
+use chrono::{DateTime, Utc};
+
+/// A snapshot of the metadata of a ZIP entry which is typically compared when determining if an entry has changed.
+///
+/// A list of summaries for every entry can be retrieved via a reader's `manifest()` method. As summaries implement
+/// [`PartialEq`], comparing an archive against an earlier version of itself (or a manifest stored from one) is
+/// straightforward.
+///
+/// With the `serde` feature enabled, this type implements `Serialize` and `Deserialize`. The compression method and
+/// attribute host compatibility are represented by their raw values from the specification, so a stored manifest can
+/// be read regardless of which compression features are enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntrySummary {
+    pub(crate) filename: String,
+    pub(crate) compression: Compression,
+    pub(crate) crc32: u32,
+    pub(crate) uncompressed_size: u64,
+    pub(crate) compressed_size: u64,
+    pub(crate) last_modification_date: DateTime<Utc>,
+    pub(crate) attribute_compatibility: AttributeCompatibility,
+    pub(crate) internal_file_attribute: u16,
+    pub(crate) external_file_attribute: u32,
+}
+
+impl From<&ZipEntry> for EntrySummary {
+    fn from(entry: &ZipEntry) -> Self {
+        EntrySummary {
+            filename: entry.filename().to_owned(),
+            compression: entry.compression,
+            crc32: entry.crc32,
+            uncompressed_size: entry.uncompressed_size,
+            compressed_size: entry.compressed_size,
+            last_modification_date: entry.last_modification_date,
+            attribute_compatibility: entry.attribute_compatibility,
+            internal_file_attribute: entry.internal_file_attribute,
+            external_file_attribute: entry.external_file_attribute,
+        }
+    }
+}
+
+impl EntrySummary {
+    /// Returns the entry's filename.
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Returns the entry's compression method.
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Returns the entry's CRC32 value.
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    /// Returns the entry's uncompressed size.
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    /// Returns the entry's compressed size.
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    /// Returns the entry's last modification time & date.
+    pub fn last_modification_date(&self) -> &DateTime<Utc> {
+        &self.last_modification_date
+    }
+
+    /// Returns the entry's attribute's host compatibility.
+    pub fn attribute_compatibility(&self) -> AttributeCompatibility {
+        self.attribute_compatibility
+    }
+
+    /// Returns the entry's internal file attribute.
+    pub fn internal_file_attribute(&self) -> u16 {
+        self.internal_file_attribute
+    }
+
+    /// Returns the entry's external file attribute.
+    pub fn external_file_attribute(&self) -> u32 {
+        self.external_file_attribute
+    }
+
+    /// Returns whether or not the entry's data is likely to differ from that of another summary.
+    ///
+    /// Only the CRC32 value and uncompressed size are compared, so entries which have merely been renamed,
+    /// re-compressed with another method, or had their metadata changed are considered unchanged.
+    pub fn data_differs(&self, other: &EntrySummary) -> bool {
+        self.crc32 != other.crc32 || self.uncompressed_size != other.uncompressed_size
+    }
+}
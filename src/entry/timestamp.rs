@@ -0,0 +1,33 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+/// One of the times which may be recorded for an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimestampKind {
+    Modified,
+    Accessed,
+    Created,
+}
+
+/// Where an entry's time was read from, in order of precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimestampSource {
+    /// The NTFS extra field (0x000A), with a precision of 100 nanoseconds.
+    Ntfs,
+    /// Info-ZIP's extended timestamp extra field (0x5455), with a precision of one second.
+    ExtendedTimestamp,
+    /// The MS-DOS date & time within the entry's headers, with a precision of two seconds and a range of 1980 to 2107.
+    /// This only ever holds the modification time.
+    Dos,
+}
+
+impl TimestampKind {
+    // The index of this time within the timestamp extra fields, which both order them in this way.
+    pub(crate) fn index(self) -> usize {
+        match self {
+            TimestampKind::Modified => 0,
+            TimestampKind::Accessed => 1,
+            TimestampKind::Created => 2,
+        }
+    }
+}
@@ -3,6 +3,8 @@
 
 //! A module which holds relevant error reporting structures/types.
 
+use crate::spec::version::ZipVersion;
+
 use thiserror::Error;
 
 /// A Result type alias over ZipError to minimise repetition.
@@ -11,20 +13,112 @@ pub type Result<V> = std::result::Result<V, ZipError>;
 /// An enum of possible errors and their descriptions.
 #[derive(Debug, Error)]
 pub enum ZipError {
-    #[error("Encountered an unexpected header (actual: {0:#x}, expected: {1:#x}).")]
-    UnexpectedHeaderError(u32, u32),
+    #[error("Encountered an unexpected signature{} (actual: {found:#x}, expected: {expected:#x}).", display_offset(.offset))]
+    UnexpectedSignature { offset: Option<u64>, expected: u32, found: u32 },
+    #[error("Unable to locate the end of central directory header within the last {searched_bytes} bytes.")]
+    EocdNotFound { searched_bytes: u64 },
     #[error("{0} is not a supported compression type.")]
     UnsupportedCompressionError(u16),
+    #[error("The entry '{entry}' uses compression method {compression}, which requires the '{feature}' feature.")]
+    FeatureNotEnabled { entry: String, compression: u16, feature: &'static str },
     #[error("{0} is not a supported host attribute compatibility.")]
     UnsupportedAttributeCompatibility(u16),
     #[error("An upstream reader returned an error: '{0:?}'.")]
     UpstreamReadError(#[from] std::io::Error),
     #[error("Feature not currently supported: '{0}'.")]
     FeatureNotSupported(&'static str),
-    #[error("A computed CRC32 value did not match the expected value.")]
-    CRC32CheckError,
+    #[error("A computed CRC32 value did not match the expected value for '{filename}' (actual: {found:#x}, expected: {expected:#x}).")]
+    CRC32CheckError { filename: String, expected: u32, found: u32 },
     #[error("Entry index was out of bounds.")]
     EntryIndexOutOfBounds,
     #[error("Compressed size is required to be present in the Local File Header when using Stored compression.")]
     MissingCompressedSize,
+    #[error("The ZIP file contains {0} entries, which exceeds the configured limit.")]
+    EntryLimitExceeded(u64),
+    #[error("An entry's decompressed size exceeds the configured limit.")]
+    DecompressedSizeExceeded,
+    #[error("The data of entries {0} and {1} overlap.")]
+    OverlappingEntries(usize, usize),
+    #[error("The data of entry {0} lies outside of the ZIP file's entry data.")]
+    EntryDataOutOfBounds(usize),
+    #[error("The uncompressed size of '{filename}' did not match the expected value (actual: {found}, expected: {expected}).")]
+    SizeMismatch { filename: String, expected: u64, found: u64 },
+    #[error("An entry requires a feature which isn't supported: {0:?}.")]
+    UnsupportedFeature(Feature),
+    #[error("The ZIP file spans disk {0}, which wasn't provided.")]
+    MissingDisk(u16),
+    #[error("An entry named '{0}' has already been written.")]
+    DuplicateFilename(String),
+    #[error("A record requires {expected} bytes, but only {found} were provided.")]
+    TruncatedRecord { expected: usize, found: usize },
+    #[error("An earlier entry write was cancelled or failed part-way through, so the ZIP file can't be completed.")]
+    WriterPoisoned,
+    #[error("The entry's data descriptor hasn't been read, as the entry hasn't been fully consumed.")]
+    DataDescriptorNotRead,
+    #[error("An entry's filename is {0} bytes long, which exceeds the maximum of 65535 bytes.")]
+    FilenameTooLong(usize),
+    #[error("The filename '{0}' contains a NUL character.")]
+    InvalidFilename(String),
+    #[error("A comment is {0} bytes long, which exceeds the maximum of 65535 bytes.")]
+    CommentTooLong(usize),
+    #[error("An entry's extra field data is {0} bytes long, which exceeds the maximum of 65535 bytes.")]
+    ExtraFieldTooLong(usize),
+    #[error("A checkpoint of {0} bytes lies beyond the end of the entry's data.")]
+    CheckpointOutOfBounds(u64),
+    #[error("The entry's CRC32 value can't be checked, as CRC32 computation was disabled for its reader.")]
+    CRC32Disabled,
+    #[error("The range {start}..{end} lies beyond the end of the entry's {size} bytes of data.")]
+    RangeOutOfBounds { start: u64, end: u64, size: u64 },
+    #[error("The symbolic link entry '{0}' was provided with data, but its data is always its target path.")]
+    SymlinkData(String),
+    #[error("An offset derived from the ZIP file's headers overflowed.")]
+    OffsetOverflow,
+    #[error("The path {0:?} isn't valid UTF-8, so can't be used as a filename.")]
+    NonUtf8Path(std::path::PathBuf),
+    #[error("The nested ZIP file '{0}' is compressed, and decompressing nested ZIP files into memory is disabled.")]
+    NestedEntryCompressed(String),
+    #[error("Entry {index} has an unsafe filename: '{name}'.")]
+    UnsafeEntryName { index: usize, name: String },
+    #[error("Entry {index} is a symbolic link with an unsafe target: '{target}'.")]
+    UnsafeSymlinkTarget { index: usize, target: String },
+    #[error("No entry's local file header has been parsed, so there's no entry data to read.")]
+    NoCurrentEntry,
+    #[error("The data of the entry '{0}' isn't valid UTF-8.")]
+    NonUtf8Data(String),
+    #[error("The local file header of entry {index} disagrees with its central directory record on its {field}.")]
+    HeaderMismatch { field: &'static str, index: usize },
+    #[error("The entry's data hasn't been fully consumed.")]
+    EntryNotFullyConsumed,
+    #[error("The mimetype entry must be written before anything else.")]
+    MimetypeNotFirst,
+    #[error("The entry '{0}' requires ZIP64 sizes, which its local file header didn't reserve space for.")]
+    Zip64NotReserved(String),
+    #[error("The entry '{0}' is encrypted, so must be read with a password.")]
+    PasswordRequired(String),
+    #[error("The password provided for the entry '{0}' is incorrect.")]
+    WrongPassword(String),
+    #[error("The data of the entry '{0}' failed authentication, so has been corrupted or tampered with.")]
+    AuthenticationFailed(String),
+    #[error("The entry '{0}' is encrypted with AES, but its AES extra field is missing or malformed.")]
+    InvalidAesExtraField(String),
+}
+
+fn display_offset(offset: &Option<u64>) -> String {
+    offset.map(|offset| format!(" at offset {:#x}", offset)).unwrap_or_default()
+}
+
+/// A feature which an entry may require, but of which this crate (with its enabled features) doesn't support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// Encryption which can't be decrypted (ie. AES encryption without the `aes` feature enabled, or with an
+    /// unrecognised AES record).
+    Encryption,
+    /// PKWARE's strong encryption.
+    StrongEncryption,
+    /// Compressed patched data.
+    PatchedData,
+    /// A compression method, holding its raw value.
+    Compression(u16),
+    /// A newer version of the specification than this crate implements.
+    Version(ZipVersion),
 }
@@ -5,24 +5,35 @@
 //!
 //! ## Features
 //! - Asynchronous design powered by tokio.
-//! - Support for Stored, Deflate, bzip2, LZMA, zstd, and xz compression methods.
+//! - Support for Stored, Deflate, bzip2, LZMA, zstd, and xz compression methods (plus reading Deflate64, and the legacy
+//!   Shrink and Implode methods via the `legacy-codecs` feature).
 //! - Various different reading approaches (seek, stream, filesystem, in-memory buffer).
 //! - Support for writing complete data (u8 slices) or stream writing using data descriptors.
+//! - Reading and writing of entries encrypted with the traditional PKWARE scheme (ZipCrypto), or with WinZip's AES
+//!   encryption via the `aes` feature, given their password.
 //! - Aims for reasonable [specification](https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT) compliance.
 //!
 //! [Read more.](https://github.com/Majored/rs-async-zip)
 
+pub(crate) mod crypto;
 pub mod error;
+pub(crate) mod progress;
 pub mod read;
-pub(crate) mod spec;
+pub mod sanitize;
+pub mod spec;
 pub(crate) mod entry;
 #[cfg(test)]
 pub(crate) mod tests;
+pub(crate) mod trace;
 pub(crate) mod utils;
 pub mod write;
 
 pub use crate::spec::compression::Compression;
 pub use crate::spec::attribute::AttributeCompatibility;
+pub use crate::spec::extra_field::ExtraField;
+pub use crate::spec::header::GeneralPurposeFlag;
+pub use crate::spec::version::ZipVersion;
+pub use crate::progress::Progress;
 
-pub use crate::entry::{ZipEntry, builder::ZipEntryBuilder};
+pub use crate::entry::{ZipEntry, EntrySummary, EncryptionMethod, builder::ZipEntryBuilder};
 pub use crate::entry::ext::{ZipEntryExt, ZipEntryBuilderExt};
\ No newline at end of file
@@ -0,0 +1,34 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module which supports observing the progress of entry readers & writers.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// A handle which observes the number of bytes processed by an entry reader or writer.
+///
+/// Handles are cheap to clone and may be polled from another task (eg. one driving a progress bar) whilst the reader
+/// or writer is in use. See [`ZipEntryReader::progress()`], [`EntryStreamWriter::progress()`] and
+/// [`ZipFileWriter::progress()`].
+///
+/// [`ZipEntryReader::progress()`]: crate::read::ZipEntryReader::progress
+/// [`EntryStreamWriter::progress()`]: crate::write::EntryStreamWriter::progress
+/// [`ZipFileWriter::progress()`]: crate::write::ZipFileWriter::progress
+#[derive(Debug, Clone)]
+pub struct Progress(Arc<AtomicU64>);
+
+impl Progress {
+    pub(crate) fn new(bytes: u64) -> Self {
+        Progress(Arc::new(AtomicU64::new(bytes)))
+    }
+
+    /// Returns the number of bytes processed so far.
+    pub fn bytes(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set(&self, bytes: u64) {
+        self.0.store(bytes, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::read::pool::{BufferPool, PooledBuffer};
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+/// Where the buffer placed between the underlying reader and an entry's decompressor comes from, as configured via
+/// [`ReaderOptions::buffer_size()`] and [`ReaderOptions::buffer_pool()`].
+///
+/// [`ReaderOptions::buffer_size()`]: crate::read::ReaderOptions::buffer_size
+/// [`ReaderOptions::buffer_pool()`]: crate::read::ReaderOptions::buffer_pool
+#[derive(Clone)]
+pub(crate) enum BufferSource {
+    Allocate(usize),
+    Pool(BufferPool),
+}
+
+impl BufferSource {
+    fn checkout(&self) -> Buffer {
+        match self {
+            BufferSource::Allocate(size) => Buffer::Owned(vec![0; *size].into_boxed_slice()),
+            BufferSource::Pool(pool) => Buffer::Pooled(pool.checkout()),
+        }
+    }
+}
+
+enum Buffer {
+    Owned(Box<[u8]>),
+    Pooled(PooledBuffer),
+}
+
+impl std::ops::Deref for Buffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Buffer::Owned(buffer) => buffer,
+            Buffer::Pooled(buffer) => buffer,
+        }
+    }
+}
+
+impl std::ops::DerefMut for Buffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        match self {
+            Buffer::Owned(buffer) => buffer,
+            Buffer::Pooled(buffer) => buffer,
+        }
+    }
+}
+
+/// A buffered reader equivalent to tokio's `BufReader`, but whose buffer may be checked out from a [`BufferPool`].
+pub(crate) struct BufferedReader<R> {
+    inner: R,
+    buffer: Buffer,
+    position: usize,
+    filled: usize,
+    // Holds a copy of every byte consumed since capturing began, if it has.
+    captured: Option<Vec<u8>>,
+}
+
+impl<R: AsyncRead + Unpin> BufferedReader<R> {
+    pub(crate) fn new(source: &BufferSource, inner: R) -> Self {
+        Self { inner, buffer: source.checkout(), position: 0, filled: 0, captured: None }
+    }
+
+    // Constructs a reader without a buffer, for which every read passes straight through to the inner reader.
+    pub(crate) fn unbuffered(inner: R) -> Self {
+        Self { inner, buffer: Buffer::Owned(Box::default()), position: 0, filled: 0, captured: None }
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the bytes which have been read from the inner reader but not yet consumed.
+    pub(crate) fn buffer(&self) -> &[u8] {
+        &self.buffer[self.position..self.filled]
+    }
+
+    // Begin keeping a copy of every byte consumed from this reader (eg. by a decompressor), which the caller drains via
+    // captured() as it reads.
+    pub(crate) fn capture(&mut self) {
+        self.captured = Some(Vec::new());
+    }
+
+    pub(crate) fn captured(&mut self) -> Option<&mut Vec<u8>> {
+        self.captured.as_mut()
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for BufferedReader<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        // Reads at least as large as the buffer bypass it entirely whenever it's empty, unless they must be captured
+        // (an unbuffered reader has nothing to capture, as it only reads entries without any data).
+        let bypass = self.captured.is_none() || self.buffer.is_empty();
+        if bypass && self.position == self.filled && b.remaining() >= self.buffer.len() {
+            return Pin::new(&mut self.inner).poll_read(c, b);
+        }
+
+        let available = ready!(self.as_mut().poll_fill_buf(c))?;
+        let length = available.len().min(b.remaining());
+        b.put_slice(&available[..length]);
+        self.consume(length);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncBufRead for BufferedReader<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<&[u8]>> {
+        let this = self.get_mut();
+
+        if this.position >= this.filled {
+            let mut buf = ReadBuf::new(&mut this.buffer);
+            ready!(Pin::new(&mut this.inner).poll_read(c, &mut buf))?;
+
+            this.filled = buf.filled().len();
+            this.position = 0;
+        }
+
+        Poll::Ready(Ok(&this.buffer[this.position..this.filled]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amount: usize) {
+        let this = self.get_mut();
+        let end = (this.position + amount).min(this.filled);
+
+        if let Some(captured) = &mut this.captured {
+            captured.extend_from_slice(&this.buffer[this.position..end]);
+        }
+
+        this.position = end;
+    }
+}
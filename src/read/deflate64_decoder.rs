@@ -0,0 +1,61 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use deflate64::InflaterManaged;
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+/// An asynchronous adapter over the synchronous Deflate64 inflater provided by the `deflate64` crate.
+///
+/// Like the decoders provided by `async-compression`, this reader is self-terminating and will only consume bytes
+/// from its inner reader which form part of the compressed stream.
+pub(crate) struct Deflate64Decoder<R: AsyncBufRead + Unpin> {
+    inner: R,
+    inflater: Box<InflaterManaged>,
+}
+
+impl<R: AsyncBufRead + Unpin> Deflate64Decoder<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self { inner, inflater: Box::new(InflaterManaged::new()) }
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for Deflate64Decoder<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let this = &mut *self;
+
+        loop {
+            if this.inflater.finished() || b.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+
+            let input = ready!(Pin::new(&mut this.inner).poll_fill_buf(c))?;
+            let eof = input.is_empty();
+            let result = this.inflater.inflate(input, b.initialize_unfilled());
+
+            Pin::new(&mut this.inner).consume(result.bytes_consumed);
+            b.advance(result.bytes_written);
+
+            if result.data_error {
+                return Poll::Ready(Err(tokio::io::Error::new(
+                    tokio::io::ErrorKind::InvalidData,
+                    "invalid Deflate64 compressed data",
+                )));
+            }
+
+            if result.bytes_written > 0 || this.inflater.finished() {
+                return Poll::Ready(Ok(()));
+            }
+
+            if eof {
+                return Poll::Ready(Err(tokio::io::ErrorKind::UnexpectedEof.into()));
+            }
+        }
+    }
+}
@@ -0,0 +1,321 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::entry::ext::ZipEntryExt;
+use crate::entry::ZipEntry;
+use crate::error::{Result, ZipError};
+use crate::read::DEFAULT_BUFFER_SIZE;
+use crate::sanitize::SanitizeError;
+
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+/// A set of options which control how a ZIP file is extracted into a directory by each reader's `extract()` method
+/// (eg. [`fs::ZipFileReader::extract()`]).
+///
+/// The defaults fail upon any entry whose filename (or symbolic link target) would escape the directory, refuse to
+/// overwrite existing files, restore each file's last modification time (as well as its permissions on Unix
+/// platforms), and create symbolic links on Unix platforms.
+///
+/// [`fs::ZipFileReader::extract()`]: crate::read::fs::ZipFileReader::extract
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    pub(crate) overwrite: bool,
+    pub(crate) skip_unsafe: bool,
+    pub(crate) restore_metadata: bool,
+    pub(crate) symlinks: bool,
+    pub(crate) buffer_size: usize,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            overwrite: false,
+            skip_unsafe: false,
+            restore_metadata: true,
+            symlinks: true,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+        }
+    }
+}
+
+impl ExtractOptions {
+    /// Constructs a new set of options with their default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether or not existing files are overwritten, rather than failing with an I/O error of kind
+    /// [`AlreadyExists`](std::io::ErrorKind::AlreadyExists). Defaults to false.
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Sets whether or not entries with unsafe filenames or symbolic link targets are silently skipped, rather than
+    /// failing with [`ZipError::UnsafeEntryName`] or [`ZipError::UnsafeSymlinkTarget`]. Defaults to false.
+    ///
+    /// A filename is unsafe if it's rejected by [`sanitize_entry_path()`] (eg. it's absolute or contains a `..`
+    /// component), so such entries are never written outside of the directory either way. A symbolic link's target is
+    /// unsafe if it's absolute, or if it leads outside of the directory via `..` components.
+    ///
+    /// [`sanitize_entry_path()`]: crate::sanitize::sanitize_entry_path
+    pub fn skip_unsafe(mut self, skip: bool) -> Self {
+        self.skip_unsafe = skip;
+        self
+    }
+
+    /// Sets whether or not each file's last modification time and (on Unix platforms) permissions are restored from
+    /// its entry. Defaults to true.
+    pub fn restore_metadata(mut self, restore: bool) -> Self {
+        self.restore_metadata = restore;
+        self
+    }
+
+    /// Sets whether or not symbolic link entries are created as symbolic links on Unix platforms, rather than being
+    /// skipped. Defaults to true.
+    ///
+    /// Links are only created once every other entry has been extracted, so no entry can be written through one.
+    /// Entries are always skipped on other platforms, though [`ZipEntryExt::is_symlink()`] still reports them.
+    ///
+    /// [`ZipEntryExt::is_symlink()`]: crate::ZipEntryExt::is_symlink
+    pub fn symlinks(mut self, symlinks: bool) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Sets the size of the buffer used to copy each entry's data into its file.
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size;
+        self
+    }
+}
+
+// Where an entry is extracted to.
+pub(crate) enum Target {
+    Directory(PathBuf),
+    File(PathBuf),
+    Symlink(PathBuf, String),
+    Skip,
+}
+
+// Resolve where an entry at the provided index is extracted to within the destination directory.
+//
+// Entries which resolve to the directory itself (eg. `./`) are skipped, as are symbolic links unless they're enabled
+// and supported by the platform.
+pub(crate) fn target(dest: &Path, index: usize, entry: &ZipEntry, options: &ExtractOptions) -> Result<Target> {
+    if entry.is_symlink() && !(cfg!(unix) && options.symlinks) {
+        return Ok(Target::Skip);
+    }
+
+    let relative = match crate::sanitize::sanitize_entry_path(entry.filename()) {
+        Ok(relative) => relative,
+        Err(SanitizeError::Empty) => return Ok(Target::Skip),
+        Err(_) if options.skip_unsafe => return Ok(Target::Skip),
+        Err(_) => return Err(ZipError::UnsafeEntryName { index, name: entry.filename().to_owned() }),
+    };
+
+    if entry.is_symlink() {
+        // A target which couldn't be read upfront (eg. as the entry is encrypted) is skipped.
+        let link_target = match entry.symlink_target() {
+            Some(link_target) => link_target,
+            None => return Ok(Target::Skip),
+        };
+
+        return match symlink_target_is_safe(&relative, link_target) {
+            true => Ok(Target::Symlink(dest.join(relative), link_target.to_owned())),
+            false if options.skip_unsafe => Ok(Target::Skip),
+            false => Err(ZipError::UnsafeSymlinkTarget { index, target: link_target.to_owned() }),
+        };
+    }
+
+    match entry.dir() || entry.filename().ends_with('\\') {
+        true => Ok(Target::Directory(dest.join(relative))),
+        false => Ok(Target::File(dest.join(relative))),
+    }
+}
+
+// Returns whether a symbolic link at the provided path (relative to the destination directory) stays within the
+// directory when followed.
+//
+// The target must be relative, and `..` components may only lead it, rising no further than the link's own directory
+// is nested. As no link is created beneath another (see `create_symlink()`), each of the target's other components
+// then resolves to somewhere within the directory, whether it's a link itself or not.
+fn symlink_target_is_safe(relative: &Path, target: &str) -> bool {
+    if target.is_empty() || target.starts_with(['/', '\\']) || target.contains('\0') {
+        return false;
+    }
+
+    if crate::sanitize::has_drive_prefix(target) {
+        return false;
+    }
+
+    let depth = relative.components().count().saturating_sub(1);
+    let mut components = target.split(['/', '\\']).filter(|component| !component.is_empty() && *component != ".");
+    let rises = components.by_ref().take_while(|component| *component == "..").count();
+
+    // The component which ended the leading `..` components has been consumed above, but couldn't have been `..`.
+    rises <= depth && components.all(|component| component != "..")
+}
+
+// Create a symbolic link which an entry has been resolved to, once every other entry has been extracted.
+//
+// The link is refused if any of its ancestors within the destination directory is itself a link (eg. one created by an
+// earlier entry), as the link could then be placed outside of the directory.
+pub(crate) async fn create_symlink(
+    dest: &Path,
+    path: &Path,
+    target: &str,
+    index: usize,
+    options: &ExtractOptions,
+) -> Result<()> {
+    let mut ancestor = path.parent();
+
+    while let Some(directory) = ancestor.filter(|directory| directory.starts_with(dest) && *directory != dest) {
+        match tokio::fs::symlink_metadata(directory).await {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                return Err(ZipError::UnsafeSymlinkTarget { index, target: target.to_owned() });
+            }
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+            _ => ancestor = directory.parent(),
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    if options.overwrite {
+        match tokio::fs::symlink_metadata(path).await {
+            Ok(metadata) if !metadata.is_dir() => tokio::fs::remove_file(path).await?,
+            _ => (),
+        }
+    }
+
+    #[cfg(unix)]
+    tokio::fs::symlink(target, path).await?;
+    #[cfg(not(unix))]
+    let _ = target;
+
+    Ok(())
+}
+
+// Create a file (along with any missing parent directories) to extract an entry into.
+pub(crate) async fn create_file(path: &Path, options: &ExtractOptions) -> Result<File> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut open_options = tokio::fs::OpenOptions::new();
+    open_options.write(true);
+
+    if options.overwrite {
+        open_options.create(true).truncate(true);
+    } else {
+        open_options.create_new(true);
+    }
+
+    Ok(open_options.open(path).await?)
+}
+
+// Flush a file which an entry has been extracted into, then restore the entry's metadata onto it.
+//
+// A permissions mode of zero is treated as absent, as it's what's held by entries which never had one set.
+pub(crate) async fn finish_file(mut file: File, entry: &ZipEntry, options: &ExtractOptions) -> Result<()> {
+    file.flush().await?;
+
+    if !options.restore_metadata {
+        return Ok(());
+    }
+
+    let file = file.into_std().await;
+    let modified = entry.modified().map(std::time::SystemTime::from);
+    let mode = entry.unix_permissions().map(|mode| u32::from(mode) & 0o777).filter(|mode| *mode != 0);
+
+    let restore = tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+        if let Some(modified) = modified {
+            file.set_modified(modified)?;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Some(mode) = mode {
+                file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+
+        Ok(())
+    });
+
+    restore.await.map_err(std::io::Error::from)??;
+    Ok(())
+}
+
+macro_rules! reader_extract_impl {
+    () => {
+        /// Extracts every entry into the provided directory, creating it (and the parent directories of entries) as
+        /// needed.
+        ///
+        /// Entries are extracted in the order their data is stored, and each file's size and CRC32 value are checked
+        /// once written, so a corrupt entry fails with [`ZipError::CRC32CheckError`] naming it (and its partially
+        /// written file is removed). Symbolic links are created last (on Unix platforms), so that no entry can be
+        /// written through one, and only if their targets stay within the directory. See [`ExtractOptions`] for how
+        /// unsafe filenames, symbolic links, existing files, and metadata are handled.
+        ///
+        /// [`ZipError::CRC32CheckError`]: crate::error::ZipError::CRC32CheckError
+        /// [`ExtractOptions`]: crate::read::ExtractOptions
+        pub async fn extract<P: AsRef<std::path::Path>>(
+            &mut self,
+            dest: P,
+            options: crate::read::ExtractOptions,
+        ) -> crate::error::Result<()> {
+            use crate::read::extract::Target;
+
+            let dest = dest.as_ref();
+            let order: Vec<usize> = self.entries_by_offset().into_iter().map(|(index, _)| index).collect();
+            tokio::fs::create_dir_all(dest).await?;
+            let mut symlinks = Vec::new();
+
+            for index in order {
+                let path = match crate::read::extract::target(dest, index, &self.entries[index].0, &options)? {
+                    Target::File(path) => path,
+                    Target::Directory(path) => {
+                        tokio::fs::create_dir_all(path).await?;
+                        continue;
+                    }
+                    Target::Symlink(path, target) => {
+                        symlinks.push((index, path, target));
+                        continue;
+                    }
+                    Target::Skip => continue,
+                };
+
+                let mut file = crate::read::extract::create_file(&path, &options).await?;
+                let copied = match self.entry_reader(index).await {
+                    Ok(reader) => reader.copy_to_end_crc(&mut file, options.buffer_size).await,
+                    Err(err) => Err(err),
+                };
+
+                if let Err(err) = copied {
+                    drop(file);
+                    let _ = tokio::fs::remove_file(&path).await;
+                    return Err(err);
+                }
+
+                crate::read::extract::finish_file(file, &self.entries[index].0, &options).await?;
+            }
+
+            for (index, path, target) in symlinks {
+                crate::read::extract::create_symlink(dest, &path, &target, index, &options).await?;
+            }
+
+            Ok(())
+        }
+    };
+}
+
+pub(crate) use reader_extract_impl;
@@ -25,48 +25,307 @@
 
 use super::CompressionReader;
 use crate::error::{Result, ZipError};
-use crate::read::{OwnedReader, PrependReader, ZipEntry, ZipEntryReader};
-use crate::spec::header::LocalFileHeader;
-use crate::read::ZipEntryMeta;
+use crate::read::seek::{CentralDirectoryEntries, CentralDirectoryEntry, CentralDirectoryLocation};
+use crate::read::{seek, ArchiveMetadata, NestedReader, ZipEntryMeta};
+use crate::read::{EntryIndex, OwnedReader, OwnedZipEntryReader, PrependReader, ReadCheckpoint, ReaderOptions};
+use crate::read::{VerificationReport, ZipEntry, ZipEntryRangeReader, ZipEntryReader};
 
+use std::borrow::Cow;
 use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
 use tokio::fs::File;
-use tokio::io::AsyncSeekExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, Take};
 
 /// A reader which acts concurrently over a filesystem file.
 pub struct ZipFileReader {
     pub(crate) filename: PathBuf,
     pub(crate) entries: Vec<(ZipEntry, ZipEntryMeta)>,
-    pub(crate) comment: Option<String>,
+    pub(crate) index: EntryIndex,
+    pub(crate) metadata: ArchiveMetadata,
+    pub(crate) options: ReaderOptions,
+    pub(crate) location: CentralDirectoryLocation,
 }
 
 impl ZipFileReader {
     /// Constructs a new ZIP file reader from a filename.
     pub async fn new<P: AsRef<Path>>(filename: P) -> Result<ZipFileReader> {
+        Self::with_options(filename, ReaderOptions::default()).await
+    }
+
+    /// Constructs a new ZIP file reader from a filename, using the provided options.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "open_archive", level = "debug", skip_all, fields(reader = "fs"))
+    )]
+    pub async fn with_options<P: AsRef<Path>>(filename: P, options: ReaderOptions) -> Result<ZipFileReader> {
         let mut fs_file = File::open(&filename).await?;
-        let (entries, comment) = crate::read::seek::read_cd(&mut fs_file).await?;
+        let (entries, metadata, location) = crate::read::seek::read_cd(&mut fs_file, &options).await?;
+        let index = EntryIndex::default();
+
+        Ok(ZipFileReader { filename: filename.as_ref().to_path_buf(), entries, index, metadata, options, location })
+    }
+
+    /// Returns the number of bytes which precede the ZIP data (eg. the stub of a self-extracting archive).
+    ///
+    /// ## Note
+    /// When entries are listed lazily, only a preamble which the ZIP file's recorded offsets don't account for is
+    /// detected.
+    pub fn preamble_length(&self) -> u64 {
+        self.location.preamble_length
+    }
+
+    /// Returns the number of entries declared by the ZIP file, including any skipped by an entry filter.
+    pub fn total_entries(&self) -> usize {
+        self.location.num_of_entries as usize
+    }
+
+    /// Returns a lazy iterator over the entries of the ZIP file's central directory.
+    ///
+    /// This is primarily intended for use alongside [`ReaderOptions::lazy_entries()`].
+    pub async fn cd_entries(&self) -> Result<CentralDirectoryEntries<File>> {
+        let file = File::open(&self.filename).await?;
+        CentralDirectoryEntries::from_raw(file, self.location, self.options.path_policy).await
+    }
+
+    /// Verifies the integrity of every entry within the ZIP file.
+    ///
+    /// Each entry is decompressed in full and its CRC32 value and sizes are checked against the local file header,
+    /// data descriptor, and central directory. Failures are collected into the returned report rather than aborting
+    /// verification, unless `stop_early` is set.
+    ///
+    /// ## Note
+    /// This isn't supported when entries are listed lazily.
+    pub async fn verify(&self, stop_early: bool) -> Result<VerificationReport> {
+        let mut fs_file = File::open(&self.filename).await?;
+        crate::read::verify::verify_entries(&mut fs_file, &self.entries, self.location, &self.options, true, stop_early)
+            .await
+    }
+
+    /// Verifies the structure of the ZIP file without decompressing any entry data.
+    ///
+    /// This only checks that each local file header agrees with the central directory, and that the number of
+    /// central directory records matches the number declared.
+    pub async fn verify_structure_only(&self, stop_early: bool) -> Result<VerificationReport> {
+        let mut fs_file = File::open(&self.filename).await?;
+        crate::read::verify::verify_entries(
+            &mut fs_file,
+            &self.entries,
+            self.location,
+            &self.options,
+            false,
+            stop_early,
+        )
+        .await
+    }
 
-        Ok(ZipFileReader { filename: filename.as_ref().to_path_buf(), entries, comment })
+    /// Returns the mimetype of an OpenDocument or EPUB file, if its first entry follows their convention.
+    ///
+    /// The first entry must be named `mimetype` and be Stored without encryption, a data descriptor, or extra fields,
+    /// with its local file header at the very start of the ZIP file (as written by
+    /// [`ZipFileWriter::write_mimetype_entry()`]). Only that entry's local file header and data are read, and `None`
+    /// is returned if the convention isn't followed (or entries are listed lazily).
+    ///
+    /// [`ZipFileWriter::write_mimetype_entry()`]: crate::write::ZipFileWriter::write_mimetype_entry
+    pub async fn sniff_mimetype(&self) -> Result<Option<String>> {
+        let mut fs_file = File::open(&self.filename).await?;
+        crate::read::sniff_mimetype(&mut fs_file, &self.entries).await
     }
 
     crate::read::reader_entry_impl!();
+    crate::read::extract::reader_extract_impl!();
 
     /// Opens an entry at the provided index for reading.
+    ///
+    /// Encrypted entries fail with [`ZipError::PasswordRequired`], and must instead be opened via
+    /// [`Self::entry_reader_with_password()`].
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "read_entry",
+            level = "debug",
+            skip(self),
+            fields(
+                filename = tracing::field::Empty,
+                compression = tracing::field::Empty,
+                compressed_size = tracing::field::Empty,
+                uncompressed_size = tracing::field::Empty
+            )
+        )
+    )]
     pub async fn entry_reader(&self, index: usize) -> Result<ZipEntryReader<'_, File>> {
         let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        crate::trace::record_entry!(&entry.0);
+        crate::read::check_readable(&entry.0, &entry.1)?;
+
+        let mut fs_file = File::open(&self.filename).await?;
+        fs_file.seek(SeekFrom::Start(entry.1.file_offset.unwrap() + 4)).await?;
+
+        let header = crate::read::read_local_header(&mut fs_file, index, &entry.0, &entry.1, &self.options);
+        let (header, local_extra_fields) = header.await?;
+
+        let reader = OwnedReader::Owned(fs_file);
+        let reader = PrependReader::Normal(reader);
+        let compressed_size = Some(self.options.size_source.compressed_size(&entry.0, &header));
+        let buffer = self.options.buffer_source();
+        let reader = CompressionReader::from_reader(&entry.0, reader, compressed_size, buffer)?;
+
+        let mut reader = ZipEntryReader::from_raw(
+            Cow::Borrowed(&entry.0),
+            Cow::Borrowed(&entry.1),
+            reader,
+            entry.1.general_purpose_flag.data_descriptor,
+        );
+        reader.poll_byte_budget = self.options.poll_byte_budget;
+        reader.local_extra_fields = local_extra_fields;
+
+        Ok(reader)
+    }
+
+    /// Opens an entry at the provided index for reading, decrypting its data with the provided password.
+    ///
+    /// See [`seek::ZipFileReader::entry_reader_with_password()`], with the entry read via its own file handle.
+    ///
+    /// [`seek::ZipFileReader::entry_reader_with_password()`]:
+    /// crate::read::seek::ZipFileReader::entry_reader_with_password
+    pub async fn entry_reader_with_password(&self, index: usize, password: &[u8]) -> Result<ZipEntryReader<'_, File>> {
+        let reader = OwnedReader::Owned(File::open(&self.filename).await?);
+        crate::read::password_entry_reader(&self.entries, index, reader, &self.options, password).await
+    }
+
+    /// Opens an entry listed lazily via [`Self::cd_entries()`] for reading.
+    ///
+    /// See [`seek::ZipFileReader::reader_with_entry()`], with the entry read via its own file handle.
+    pub async fn reader_with_entry<'b>(&self, entry: &'b CentralDirectoryEntry) -> Result<ZipEntryReader<'b, File>> {
+        let reader = OwnedReader::Owned(File::open(&self.filename).await?);
+        crate::read::lazy_entry_reader(entry, reader, &self.options).await
+    }
+
+    /// Opens the ZIP file nested within the entry at the provided index (eg. a JAR within a fat JAR) as a new reader.
+    ///
+    /// See [`seek::ZipFileReader::open_nested()`], with the nested ZIP file read in place via its own file handle.
+    ///
+    /// [`seek::ZipFileReader::open_nested()`]: crate::read::seek::ZipFileReader::open_nested
+    pub async fn open_nested(&self, index: usize) -> Result<seek::ZipFileReader<NestedReader<File>>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        let options = self.options.clone();
+
+        if !crate::read::nested::in_place(&entry.0, &options)? {
+            let data = self.entry_reader(index).await?.read_to_end_crc().await?;
+            return seek::ZipFileReader::with_options(NestedReader::buffered(data), options).await;
+        }
+
+        let mut fs_file = File::open(&self.filename).await?;
+        fs_file.seek(SeekFrom::Start(entry.1.file_offset.unwrap() + 4)).await?;
+        let (header, _) = crate::read::skip_local_header(&mut fs_file).await?;
+
+        let start = fs_file.stream_position().await?;
+        let length = self.options.size_source.compressed_size(&entry.0, &header);
+        let reader = NestedReader::window(fs_file, start, length).await?;
+
+        seek::ZipFileReader::with_options(reader, options).await
+    }
+
+    /// Opens a reader over the raw (still compressed, and possibly encrypted) data of the entry at the provided index,
+    /// via its own file handle.
+    ///
+    /// See [`seek::ZipFileReader::raw_entry_reader()`].
+    ///
+    /// [`seek::ZipFileReader::raw_entry_reader()`]: crate::read::seek::ZipFileReader::raw_entry_reader
+    pub async fn raw_entry_reader(&self, index: usize) -> Result<Take<File>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+
+        let mut fs_file = File::open(&self.filename).await?;
+        fs_file.seek(SeekFrom::Start(entry.1.file_offset.unwrap() + 4)).await?;
+        crate::read::skip_local_header(&mut fs_file).await?;
+
+        Ok(fs_file.take(entry.0.compressed_size()))
+    }
+
+    /// Opens an entry at the provided index for reading, resuming from a checkpoint reached by an earlier reader.
+    ///
+    /// The bytes already consumed are skipped (by seeking for Stored entries, or otherwise by decompressing them
+    /// again), and the returned reader's CRC32 value continues from the checkpoint's, so the final CRC32 check still
+    /// covers the entry's data in full.
+    pub async fn entry_reader_resumed(
+        &self,
+        index: usize,
+        checkpoint: ReadCheckpoint,
+    ) -> Result<ZipEntryReader<'_, File>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        crate::read::check_readable(&entry.0, &entry.1)?;
+
+        let mut fs_file = File::open(&self.filename).await?;
+        fs_file.seek(SeekFrom::Start(entry.1.file_offset.unwrap() + 4)).await?;
+
+        let header = crate::read::read_local_header(&mut fs_file, index, &entry.0, &entry.1, &self.options);
+        let (header, local_extra_fields) = header.await?;
+
+        let reader = OwnedReader::Owned(fs_file);
+        let compressed_size = self.options.size_source.compressed_size(&entry.0, &header);
+        let (entry, meta) = (Cow::Borrowed(&entry.0), Cow::Borrowed(&entry.1));
+        let buffer = self.options.buffer_source();
+
+        let reader = crate::read::resume_entry_reader(entry, meta, reader, compressed_size, checkpoint, buffer);
+        let mut reader = reader.await?;
+        reader.poll_byte_budget = self.options.poll_byte_budget;
+        reader.local_extra_fields = local_extra_fields;
+
+        Ok(reader)
+    }
+
+    /// Opens an entry at the provided index for reading a range of its decompressed data.
+    ///
+    /// The range spans from `start` up to (but excluding) `end`, or to the end of the entry's data if no end is
+    /// provided. For Stored entries, the reader seeks directly to the start of the range; any other entry must have
+    /// the bytes preceding it decompressed and discarded. Ranges beyond the entry's uncompressed size fail with
+    /// [`ZipError::RangeOutOfBounds`].
+    pub async fn entry_reader_range(
+        &self,
+        index: usize,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<ZipEntryRangeReader<'_, File>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        crate::read::check_readable(&entry.0, &entry.1)?;
 
         let mut fs_file = File::open(&self.filename).await?;
-        fs_file.seek(SeekFrom::Start(entry.1.file_offset.unwrap() as u64 + 4)).await?;
+        fs_file.seek(SeekFrom::Start(entry.1.file_offset.unwrap() + 4)).await?;
+        let header = crate::read::read_local_header(&mut fs_file, index, &entry.0, &entry.1, &self.options);
+        let (header, _) = header.await?;
 
-        let header = LocalFileHeader::from_reader(&mut fs_file).await?;
-        let data_offset = (header.file_name_length + header.extra_field_length) as i64;
-        fs_file.seek(SeekFrom::Current(data_offset)).await?;
+        let reader = OwnedReader::Owned(fs_file);
+        let buffer = self.options.buffer_source();
+        let compressed_size = self.options.size_source.compressed_size(&entry.0, &header);
+        let entry = Cow::Borrowed(&entry.0);
+        crate::read::range::range_entry_reader(entry, reader, compressed_size, start, end, buffer).await
+    }
+
+    /// Opens an entry at the provided index for reading, without borrowing from this reader.
+    ///
+    /// The entry's metadata is cloned so that the returned reader may outlive this one, and be moved into a spawned
+    /// task.
+    pub async fn entry_reader_owned(&self, index: usize) -> Result<OwnedZipEntryReader<File>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        crate::read::check_readable(&entry.0, &entry.1)?;
+
+        let mut fs_file = File::open(&self.filename).await?;
+        fs_file.seek(SeekFrom::Start(entry.1.file_offset.unwrap() + 4)).await?;
+
+        let header = crate::read::read_local_header(&mut fs_file, index, &entry.0, &entry.1, &self.options);
+        let (header, local_extra_fields) = header.await?;
 
         let reader = OwnedReader::Owned(fs_file);
         let reader = PrependReader::Normal(reader);
-        let reader = CompressionReader::from_reader(&entry.0.compression(), reader, Some(entry.0.compressed_size()).map(u32::into))?;
+        let compressed_size = Some(self.options.size_source.compressed_size(&entry.0, &header));
+        let buffer = self.options.buffer_source();
+        let reader = CompressionReader::from_reader(&entry.0, reader, compressed_size, buffer)?;
+
+        let data_descriptor = entry.1.general_purpose_flag.data_descriptor;
+        let mut reader =
+            ZipEntryReader::from_raw(Cow::Owned(entry.0.clone()), Cow::Owned(entry.1.clone()), reader, data_descriptor);
+        reader.poll_byte_budget = self.options.poll_byte_budget;
+        reader.local_extra_fields = local_extra_fields;
 
-        Ok(ZipEntryReader::from_raw(&entry.0, &entry.1, reader, entry.1.general_purpose_flag.data_descriptor))
+        Ok(reader)
     }
 }
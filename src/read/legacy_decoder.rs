@@ -0,0 +1,332 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::entry::ZipEntry;
+use crate::spec::compression::Compression;
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+const SHRINK_TABLE_SIZE: usize = 1 << 13;
+const SHRINK_MAX_CODE_SIZE: u32 = 13;
+const SHRINK_CONTROL_CODE: usize = 256;
+const SHRINK_FIRST_CODE: usize = 257;
+const SHRINK_FREE: u16 = u16::MAX;
+const SHRINK_ROOT: u16 = u16::MAX - 1;
+
+/// A decoder for the legacy Shrink and Implode compression methods used by PKZIP 1.x.
+///
+/// Neither method marks the end of its compressed data, so the inner reader must be limited to the entry's compressed
+/// size. All of that data is read into memory and decoded in one go once its end is reached, as entries using these
+/// methods are small in practice. The decoded output never exceeds the entry's uncompressed size.
+pub(crate) struct LegacyDecoder<R: AsyncBufRead + Unpin> {
+    inner: R,
+    method: Method,
+    uncompressed_size: usize,
+    input: Vec<u8>,
+    output: Option<Cursor<Vec<u8>>>,
+}
+
+#[derive(Clone, Copy)]
+enum Method {
+    Shrink,
+    Implode { large_window: bool, literal_tree: bool },
+}
+
+impl<R: AsyncBufRead + Unpin> LegacyDecoder<R> {
+    pub(crate) fn new(inner: R, compression: Compression, entry: &ZipEntry) -> Self {
+        // Bits 1 and 2 of an imploded entry's general purpose flag select its sliding window size and whether or not
+        // its literals are encoded with a tree.
+        let options = entry.general_purpose_flags().compression_options();
+        let method = match compression {
+            Compression::Implode => Method::Implode { large_window: options & 1 != 0, literal_tree: options & 2 != 0 },
+            _ => Method::Shrink,
+        };
+        let uncompressed_size = entry.uncompressed_size() as usize;
+
+        Self { inner, method, uncompressed_size, input: Vec::new(), output: None }
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for LegacyDecoder<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(output) = &mut this.output {
+                return Pin::new(output).poll_read(c, b);
+            }
+
+            let input = ready!(Pin::new(&mut this.inner).poll_fill_buf(c))?;
+            let read = input.len();
+
+            if read > 0 {
+                this.input.extend_from_slice(input);
+                Pin::new(&mut this.inner).consume(read);
+                continue;
+            }
+
+            let (output, name) = match this.method {
+                Method::Shrink => (unshrink(&this.input, this.uncompressed_size), "Shrink"),
+                Method::Implode { large_window, literal_tree } => {
+                    (explode(&this.input, this.uncompressed_size, large_window, literal_tree), "Implode")
+                }
+            };
+
+            let output = output.ok_or_else(|| {
+                let message = format!("invalid {} compressed data", name);
+                tokio::io::Error::new(tokio::io::ErrorKind::InvalidData, message)
+            })?;
+
+            this.input = Vec::new();
+            this.output = Some(Cursor::new(output));
+        }
+    }
+}
+
+// A reader over the bits of compressed data, which are packed starting from the least significant bit of each byte.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    // Read a value of `count` bits, whose least significant bit comes first. None is returned once the data ends.
+    fn read(&mut self, count: u32) -> Option<u32> {
+        let mut value = 0;
+
+        for index in 0..count {
+            let byte = *self.data.get(self.position / 8)?;
+            value |= u32::from((byte >> (self.position % 8)) & 1) << index;
+            self.position += 1;
+        }
+
+        Some(value)
+    }
+}
+
+// Decode Shrink compressed data, a variant of LZW with codes from 9 to 13 bits in size whose table may be partially
+// cleared. None is returned if the data is invalid or ends before `size` bytes have been decoded.
+fn unshrink(input: &[u8], size: usize) -> Option<Vec<u8>> {
+    let mut bits = BitReader::new(input);
+    let mut prefixes = vec![SHRINK_FREE; SHRINK_TABLE_SIZE];
+    let mut suffixes = vec![0; SHRINK_TABLE_SIZE];
+
+    prefixes[..=SHRINK_CONTROL_CODE].fill(SHRINK_ROOT);
+    for (code, suffix) in suffixes.iter_mut().enumerate().take(SHRINK_CONTROL_CODE) {
+        *suffix = code as u8;
+    }
+
+    let mut code_size = 9;
+    let mut previous = None;
+    let mut next_free = SHRINK_FIRST_CODE;
+    let mut output = Vec::new();
+    let mut string = Vec::new();
+
+    while output.len() < size {
+        let code = match bits.read(code_size) {
+            Some(code) => code as usize,
+            None => break,
+        };
+
+        // The control code is followed by either a 1 (the code size increases) or a 2 (the table is partially
+        // cleared, freeing every code which isn't the prefix of another).
+        if code == SHRINK_CONTROL_CODE {
+            match bits.read(code_size)? {
+                1 if code_size < SHRINK_MAX_CODE_SIZE => code_size += 1,
+                2 => {
+                    partial_clear(&mut prefixes);
+                    next_free = SHRINK_FIRST_CODE;
+                }
+                _ => return None,
+            }
+            continue;
+        }
+
+        while next_free < SHRINK_TABLE_SIZE && prefixes[next_free] != SHRINK_FREE {
+            next_free += 1;
+        }
+
+        string.clear();
+
+        // A free code may only be the one which is about to be added, whose string is the previous code's string
+        // followed by its own first byte.
+        if prefixes[code] == SHRINK_FREE {
+            let previous = previous.filter(|_| code == next_free)?;
+            expand(&prefixes, &suffixes, previous, &mut string)?;
+            string.push(string[0]);
+        } else {
+            expand(&prefixes, &suffixes, code, &mut string)?;
+        }
+
+        if let Some(previous) = previous {
+            if next_free < SHRINK_TABLE_SIZE {
+                prefixes[next_free] = previous as u16;
+                suffixes[next_free] = string[0];
+            }
+        }
+
+        output.extend_from_slice(&string);
+        previous = Some(code);
+    }
+
+    output.truncate(size);
+    match output.len() == size {
+        true => Some(output),
+        false => None,
+    }
+}
+
+// Write the string a Shrink code represents into `string`, failing if it's free or its prefixes form a cycle.
+fn expand(prefixes: &[u16], suffixes: &[u8], mut code: usize, string: &mut Vec<u8>) -> Option<()> {
+    let start = string.len();
+
+    while prefixes[code] != SHRINK_ROOT {
+        if prefixes[code] == SHRINK_FREE || string.len() - start >= SHRINK_TABLE_SIZE {
+            return None;
+        }
+
+        string.push(suffixes[code]);
+        code = usize::from(prefixes[code]);
+    }
+
+    string.push(suffixes[code]);
+    string[start..].reverse();
+    Some(())
+}
+
+// Free every code in the Shrink table which isn't the prefix of another code.
+fn partial_clear(prefixes: &mut [u16]) {
+    let mut parents = vec![false; SHRINK_TABLE_SIZE];
+
+    for &prefix in &prefixes[SHRINK_FIRST_CODE..] {
+        if prefix != SHRINK_FREE {
+            parents[usize::from(prefix)] = true;
+        }
+    }
+
+    for (prefix, &parent) in prefixes.iter_mut().zip(&parents).skip(SHRINK_FIRST_CODE) {
+        if !parent {
+            *prefix = SHRINK_FREE;
+        }
+    }
+}
+
+// Decode Implode compressed data, which holds literals and back-references into a 4K or 8K sliding window encoded with
+// up to three Shannon-Fano trees. None is returned if the data is invalid or ends before `size` bytes are decoded.
+fn explode(input: &[u8], size: usize, large_window: bool, literal_tree: bool) -> Option<Vec<u8>> {
+    let mut bits = BitReader::new(input);
+
+    let literals = match literal_tree {
+        true => Some(ShannonFanoTree::read(&mut bits, 256)?),
+        false => None,
+    };
+    let lengths = ShannonFanoTree::read(&mut bits, 64)?;
+    let distances = ShannonFanoTree::read(&mut bits, 64)?;
+
+    let distance_bits = if large_window { 7 } else { 6 };
+    let minimum_length = if literal_tree { 3 } else { 2 };
+    let mut output = Vec::new();
+
+    while output.len() < size {
+        if bits.read(1)? == 1 {
+            let literal = match &literals {
+                Some(tree) => tree.decode(&mut bits)?,
+                None => bits.read(8)? as u16,
+            };
+
+            output.push(literal as u8);
+            continue;
+        }
+
+        let low_bits = bits.read(distance_bits)? as usize;
+        let distance = ((usize::from(distances.decode(&mut bits)?) << distance_bits) | low_bits) + 1;
+
+        let mut length = usize::from(lengths.decode(&mut bits)?);
+        if length == 63 {
+            length += bits.read(8)? as usize;
+        }
+        length += minimum_length;
+
+        // A back-reference may extend before the start of the data, which is treated as being zeroed.
+        for _ in 0..length.min(size - output.len()) {
+            let byte = output.len().checked_sub(distance).map(|index| output[index]).unwrap_or(0);
+            output.push(byte);
+        }
+    }
+
+    Some(output)
+}
+
+// A Shannon-Fano tree used by the Implode method, mapping each code (keyed alongside its length) to its symbol.
+struct ShannonFanoTree {
+    symbols: HashMap<u32, u16>,
+}
+
+impl ShannonFanoTree {
+    // Read a tree's bit lengths, which are run-length encoded as a byte count followed by that many bytes (each holding
+    // a bit length and the number of consecutive symbols using it), and assign their codes.
+    fn read(bits: &mut BitReader<'_>, count: usize) -> Option<Self> {
+        let mut bit_lengths = Vec::with_capacity(count);
+
+        for _ in 0..=bits.read(8)? {
+            let byte = bits.read(8)?;
+            bit_lengths.extend(std::iter::repeat((byte & 0xF) + 1).take((byte >> 4) as usize + 1));
+        }
+
+        if bit_lengths.len() != count {
+            return None;
+        }
+
+        // Codes are assigned from the longest bit length to the shortest, with ties ordered by their symbol.
+        let mut sorted: Vec<usize> = (0..count).collect();
+        sorted.sort_by_key(|&symbol| bit_lengths[symbol]);
+
+        let mut symbols = HashMap::with_capacity(count);
+        let (mut code, mut increment, mut last_bit_length) = (0u32, 0u32, 0u32);
+
+        for &symbol in sorted.iter().rev() {
+            code += increment;
+
+            if bit_lengths[symbol] != last_bit_length {
+                last_bit_length = bit_lengths[symbol];
+                increment = 1 << (16 - last_bit_length);
+            }
+
+            if code >= 1 << 16 {
+                return None;
+            }
+
+            symbols.insert((last_bit_length << 16) | (code >> (16 - last_bit_length)), symbol as u16);
+        }
+
+        Some(Self { symbols })
+    }
+
+    // Decode the next symbol, whose code is stored most significant bit first and with each of its bits inverted.
+    fn decode(&self, bits: &mut BitReader<'_>) -> Option<u16> {
+        let mut code = 0;
+
+        for length in 1..=16 {
+            code = (code << 1) | (bits.read(1)? ^ 1);
+
+            if let Some(&symbol) = self.symbols.get(&((length << 16) | code)) {
+                return Some(symbol);
+            }
+        }
+
+        None
+    }
+}
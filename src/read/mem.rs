@@ -4,10 +4,12 @@
 //! A module for reading ZIP file entries concurrently from an in-memory buffer.
 
 use crate::error::{Result, ZipError};
-use crate::read::{CompressionReader, OwnedReader, PrependReader, ZipEntry, ZipEntryReader};
-use crate::spec::header::LocalFileHeader;
-use crate::read::ZipEntryMeta;
+use crate::read::seek::{CentralDirectoryEntries, CentralDirectoryEntry, CentralDirectoryLocation};
+use crate::read::{ArchiveMetadata, ZipEntryMeta};
+use crate::read::{CompressionReader, EntryIndex, OwnedReader, PrependReader, ReadCheckpoint, ReaderOptions};
+use crate::read::{VerificationReport, ZipEntry, ZipEntryRangeReader, ZipEntryReader};
 
+use std::borrow::Cow;
 use std::io::{Cursor, SeekFrom};
 
 use tokio::io::AsyncSeekExt;
@@ -19,33 +21,221 @@ pub type ConcurrentReader<'b, 'a> = ZipEntryReader<'b, Cursor<&'a [u8]>>;
 pub struct ZipFileReader<'a> {
     pub(crate) data: &'a [u8],
     pub(crate) entries: Vec<(ZipEntry, ZipEntryMeta)>,
-    pub(crate) comment: Option<String>,
+    pub(crate) index: EntryIndex,
+    pub(crate) metadata: ArchiveMetadata,
+    pub(crate) options: ReaderOptions,
+    pub(crate) location: CentralDirectoryLocation,
 }
 
 impl<'a> ZipFileReader<'a> {
     /// Constructs a new ZIP file reader from an in-memory buffer.
     pub async fn new(data: &'a [u8]) -> Result<ZipFileReader<'a>> {
-        let (entries, comment) = crate::read::seek::read_cd(&mut Cursor::new(data)).await?;
-        Ok(ZipFileReader { data, entries, comment })
+        Self::with_options(data, ReaderOptions::default()).await
+    }
+
+    /// Constructs a new ZIP file reader from an in-memory buffer, using the provided options.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "open_archive",
+            level = "debug",
+            skip_all,
+            fields(reader = "mem", length = data.len())
+        )
+    )]
+    pub async fn with_options(data: &'a [u8], options: ReaderOptions) -> Result<ZipFileReader<'a>> {
+        let (entries, metadata, location) = crate::read::seek::read_cd(&mut Cursor::new(data), &options).await?;
+        Ok(ZipFileReader { data, entries, index: EntryIndex::default(), metadata, options, location })
+    }
+
+    /// Returns the number of bytes which precede the ZIP data (eg. the stub of a self-extracting archive).
+    ///
+    /// ## Note
+    /// When entries are listed lazily, only a preamble which the ZIP file's recorded offsets don't account for is
+    /// detected.
+    pub fn preamble_length(&self) -> u64 {
+        self.location.preamble_length
+    }
+
+    /// Returns the number of entries declared by the ZIP file, including any skipped by an entry filter.
+    pub fn total_entries(&self) -> usize {
+        self.location.num_of_entries as usize
+    }
+
+    /// Returns a lazy iterator over the entries of the ZIP file's central directory.
+    ///
+    /// This is primarily intended for use alongside [`ReaderOptions::lazy_entries()`].
+    pub async fn cd_entries(&self) -> Result<CentralDirectoryEntries<Cursor<&'a [u8]>>> {
+        CentralDirectoryEntries::from_raw(Cursor::new(self.data), self.location, self.options.path_policy).await
+    }
+
+    /// Verifies the integrity of every entry within the ZIP file.
+    ///
+    /// Each entry is decompressed in full and its CRC32 value and sizes are checked against the local file header,
+    /// data descriptor, and central directory. Failures are collected into the returned report rather than aborting
+    /// verification, unless `stop_early` is set.
+    ///
+    /// ## Note
+    /// This isn't supported when entries are listed lazily.
+    pub async fn verify(&self, stop_early: bool) -> Result<VerificationReport> {
+        let mut cursor = Cursor::new(self.data);
+        crate::read::verify::verify_entries(&mut cursor, &self.entries, self.location, &self.options, true, stop_early)
+            .await
+    }
+
+    /// Verifies the structure of the ZIP file without decompressing any entry data.
+    ///
+    /// This only checks that each local file header agrees with the central directory, and that the number of
+    /// central directory records matches the number declared.
+    pub async fn verify_structure_only(&self, stop_early: bool) -> Result<VerificationReport> {
+        let mut cursor = Cursor::new(self.data);
+        crate::read::verify::verify_entries(&mut cursor, &self.entries, self.location, &self.options, false, stop_early)
+            .await
+    }
+
+    /// Returns the mimetype of an OpenDocument or EPUB file, if its first entry follows their convention.
+    ///
+    /// The first entry must be named `mimetype` and be Stored without encryption, a data descriptor, or extra fields,
+    /// with its local file header at the very start of the ZIP file (as written by
+    /// [`ZipFileWriter::write_mimetype_entry()`]). Only that entry's local file header and data are read, and `None`
+    /// is returned if the convention isn't followed (or entries are listed lazily).
+    ///
+    /// [`ZipFileWriter::write_mimetype_entry()`]: crate::write::ZipFileWriter::write_mimetype_entry
+    pub async fn sniff_mimetype(&self) -> Result<Option<String>> {
+        crate::read::sniff_mimetype(&mut Cursor::new(self.data), &self.entries).await
     }
 
     crate::read::reader_entry_impl!();
+    crate::read::extract::reader_extract_impl!();
 
     /// Opens an entry at the provided index for reading.
+    ///
+    /// Encrypted entries fail with [`ZipError::PasswordRequired`], and must instead be opened via
+    /// [`Self::entry_reader_with_password()`].
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "read_entry",
+            level = "debug",
+            skip(self),
+            fields(
+                filename = tracing::field::Empty,
+                compression = tracing::field::Empty,
+                compressed_size = tracing::field::Empty,
+                uncompressed_size = tracing::field::Empty
+            )
+        )
+    )]
     pub async fn entry_reader<'b>(&'b mut self, index: usize) -> Result<ConcurrentReader<'b, 'a>> {
         let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        crate::trace::record_entry!(&entry.0);
+        crate::read::check_readable(&entry.0, &entry.1)?;
 
         let mut cursor = Cursor::new(<&[u8]>::clone(&self.data));
-        cursor.seek(SeekFrom::Start(entry.1.file_offset.unwrap() as u64 + 4)).await?;
+        cursor.seek(SeekFrom::Start(entry.1.file_offset.unwrap() + 4)).await?;
 
-        let header = LocalFileHeader::from_reader(&mut cursor).await?;
-        let data_offset = (header.file_name_length + header.extra_field_length) as i64;
-        cursor.seek(SeekFrom::Current(data_offset)).await?;
+        let header = crate::read::read_local_header(&mut cursor, index, &entry.0, &entry.1, &self.options);
+        let (header, local_extra_fields) = header.await?;
 
         let reader = OwnedReader::Owned(cursor);
         let reader = PrependReader::Normal(reader);
-        let reader = CompressionReader::from_reader(&entry.0.compression(), reader, Some(entry.0.compressed_size()).map(u32::into))?;
+        let compressed_size = Some(self.options.size_source.compressed_size(&entry.0, &header));
+        let buffer = self.options.buffer_source();
+        let reader = CompressionReader::from_reader(&entry.0, reader, compressed_size, buffer)?;
+
+        let mut reader = ZipEntryReader::from_raw(
+            Cow::Borrowed(&entry.0),
+            Cow::Borrowed(&entry.1),
+            reader,
+            entry.1.general_purpose_flag.data_descriptor,
+        );
+        reader.poll_byte_budget = self.options.poll_byte_budget;
+        reader.local_extra_fields = local_extra_fields;
+
+        Ok(reader)
+    }
+
+    /// Opens an entry at the provided index for reading, decrypting its data with the provided password.
+    ///
+    /// See [`seek::ZipFileReader::entry_reader_with_password()`].
+    ///
+    /// [`seek::ZipFileReader::entry_reader_with_password()`]:
+    /// crate::read::seek::ZipFileReader::entry_reader_with_password
+    pub async fn entry_reader_with_password<'b>(
+        &'b mut self,
+        index: usize,
+        password: &[u8],
+    ) -> Result<ConcurrentReader<'b, 'a>> {
+        let reader = OwnedReader::Owned(Cursor::new(<&[u8]>::clone(&self.data)));
+        crate::read::password_entry_reader(&self.entries, index, reader, &self.options, password).await
+    }
+
+    /// Opens an entry listed lazily via [`Self::cd_entries()`] for reading.
+    ///
+    /// See [`seek::ZipFileReader::reader_with_entry()`].
+    ///
+    /// [`seek::ZipFileReader::reader_with_entry()`]: crate::read::seek::ZipFileReader::reader_with_entry
+    pub async fn reader_with_entry<'b>(&'b self, entry: &'b CentralDirectoryEntry) -> Result<ConcurrentReader<'b, 'a>> {
+        let reader = OwnedReader::Owned(Cursor::new(<&[u8]>::clone(&self.data)));
+        crate::read::lazy_entry_reader(entry, reader, &self.options).await
+    }
+
+    /// Opens an entry at the provided index for reading, resuming from a checkpoint reached by an earlier reader.
+    ///
+    /// The bytes already consumed are skipped (by seeking for Stored entries, or otherwise by decompressing them
+    /// again), and the returned reader's CRC32 value continues from the checkpoint's, so the final CRC32 check still
+    /// covers the entry's data in full.
+    pub async fn entry_reader_resumed<'b>(
+        &'b mut self,
+        index: usize,
+        checkpoint: ReadCheckpoint,
+    ) -> Result<ConcurrentReader<'b, 'a>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        crate::read::check_readable(&entry.0, &entry.1)?;
+
+        let mut cursor = Cursor::new(<&[u8]>::clone(&self.data));
+        cursor.seek(SeekFrom::Start(entry.1.file_offset.unwrap() + 4)).await?;
+
+        let header = crate::read::read_local_header(&mut cursor, index, &entry.0, &entry.1, &self.options);
+        let (header, local_extra_fields) = header.await?;
+
+        let reader = OwnedReader::Owned(cursor);
+        let compressed_size = self.options.size_source.compressed_size(&entry.0, &header);
+        let (entry, meta) = (Cow::Borrowed(&entry.0), Cow::Borrowed(&entry.1));
+        let buffer = self.options.buffer_source();
+
+        let reader = crate::read::resume_entry_reader(entry, meta, reader, compressed_size, checkpoint, buffer);
+        let mut reader = reader.await?;
+        reader.poll_byte_budget = self.options.poll_byte_budget;
+        reader.local_extra_fields = local_extra_fields;
 
-        Ok(ZipEntryReader::from_raw(&entry.0, &entry.1, reader, entry.1.general_purpose_flag.data_descriptor))
+        Ok(reader)
+    }
+
+    /// Opens an entry at the provided index for reading a range of its decompressed data.
+    ///
+    /// The range spans from `start` up to (but excluding) `end`, or to the end of the entry's data if no end is
+    /// provided. For Stored entries, the reader seeks directly to the start of the range; any other entry must have
+    /// the bytes preceding it decompressed and discarded. Ranges beyond the entry's uncompressed size fail with
+    /// [`ZipError::RangeOutOfBounds`].
+    pub async fn entry_reader_range<'b>(
+        &'b self,
+        index: usize,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<ZipEntryRangeReader<'b, Cursor<&'a [u8]>>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        crate::read::check_readable(&entry.0, &entry.1)?;
+
+        let mut cursor = Cursor::new(<&[u8]>::clone(&self.data));
+        cursor.seek(SeekFrom::Start(entry.1.file_offset.unwrap() + 4)).await?;
+        let header = crate::read::read_local_header(&mut cursor, index, &entry.0, &entry.1, &self.options);
+        let (header, _) = header.await?;
+
+        let reader = OwnedReader::Owned(cursor);
+        let buffer = self.options.buffer_source();
+        let compressed_size = self.options.size_source.compressed_size(&entry.0, &header);
+        let entry = Cow::Borrowed(&entry.0);
+        crate::read::range::range_entry_reader(entry, reader, compressed_size, start, end, buffer).await
     }
 }
@@ -0,0 +1,128 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::spec::header::{EndOfCentralDirectoryHeader, Zip64EndOfCentralDirectoryRecord};
+
+/// Metadata of a ZIP file as a whole, largely as recorded within its end of central directory header.
+///
+/// Where a ZIP64 end of central directory record is present, the entry counts and the central directory's size and
+/// offset are instead those it records.
+///
+/// Returned by the `metadata()` method of the seek, fs, and mem readers.
+#[derive(Debug, Clone)]
+pub struct ArchiveMetadata {
+    pub(crate) disk_num: u16,
+    pub(crate) start_cent_dir_disk: u16,
+    pub(crate) num_of_entries_disk: u64,
+    pub(crate) num_of_entries: u64,
+    pub(crate) size_cent_dir: u64,
+    pub(crate) cent_dir_offset: u64,
+    pub(crate) zip64_locator: bool,
+    pub(crate) preamble_length: u64,
+    pub(crate) trailing_length: u64,
+    pub(crate) zip64_entries: bool,
+    pub(crate) comment_raw: Vec<u8>,
+    pub(crate) comment: Option<String>,
+}
+
+impl ArchiveMetadata {
+    pub(crate) fn from_header(header: &EndOfCentralDirectoryHeader, comment_raw: Vec<u8>) -> Self {
+        let comment = match comment_raw.is_empty() {
+            true => None,
+            false => Some(String::from_utf8_lossy(&comment_raw).into_owned()),
+        };
+
+        Self {
+            disk_num: header.disk_num,
+            start_cent_dir_disk: header.start_cent_dir_disk,
+            num_of_entries_disk: header.num_of_entries_disk.into(),
+            num_of_entries: header.num_of_entries.into(),
+            size_cent_dir: header.size_cent_dir.into(),
+            cent_dir_offset: header.cent_dir_offset.into(),
+            zip64_locator: false,
+            preamble_length: 0,
+            trailing_length: 0,
+            zip64_entries: false,
+            comment_raw,
+            comment,
+        }
+    }
+
+    pub(crate) fn apply_zip64(&mut self, record: &Zip64EndOfCentralDirectoryRecord) {
+        self.disk_num = u16::try_from(record.disk_num).unwrap_or(u16::MAX);
+        self.start_cent_dir_disk = u16::try_from(record.start_cent_dir_disk).unwrap_or(u16::MAX);
+        self.num_of_entries_disk = record.num_of_entries_disk;
+        self.num_of_entries = record.num_of_entries;
+        self.size_cent_dir = record.size_cent_dir;
+        self.cent_dir_offset = record.cent_dir_offset;
+    }
+
+    /// Returns the number of the disk holding the end of central directory header.
+    pub fn disk_number(&self) -> u16 {
+        self.disk_num
+    }
+
+    /// Returns the number of the disk on which the central directory begins.
+    pub fn central_directory_disk(&self) -> u16 {
+        self.start_cent_dir_disk
+    }
+
+    /// Returns the number of entries declared on the disk holding the end of central directory header.
+    pub fn entries_on_disk(&self) -> u64 {
+        self.num_of_entries_disk
+    }
+
+    /// Returns the total number of entries declared across all disks.
+    pub fn total_entries(&self) -> u64 {
+        self.num_of_entries
+    }
+
+    /// Returns the declared size of the central directory, in bytes.
+    pub fn central_directory_size(&self) -> u64 {
+        self.size_cent_dir
+    }
+
+    /// Returns the offset of the central directory as recorded, which may differ from its actual offset if data has
+    /// been prepended to the ZIP file (see [`ArchiveMetadata::preamble_length()`]).
+    pub fn central_directory_offset(&self) -> u64 {
+        self.cent_dir_offset
+    }
+
+    /// Returns whether or not a ZIP64 end of central directory locator directly precedes the end of central directory
+    /// header.
+    pub fn has_zip64_locator(&self) -> bool {
+        self.zip64_locator
+    }
+
+    /// Returns the number of bytes preceding the first entry (eg. a self-extracting stub).
+    pub fn preamble_length(&self) -> u64 {
+        self.preamble_length
+    }
+
+    /// Returns the number of bytes following the ZIP file's comment which were ignored (eg. a signature appended by a
+    /// signing tool).
+    ///
+    /// Where the comment's recorded length overstates the bytes which follow the end of central directory header, the
+    /// comment is instead truncated and this is zero.
+    pub fn trailing_length(&self) -> u64 {
+        self.trailing_length
+    }
+
+    /// Returns whether or not any entry's central directory record holds a ZIP64 extended information extra field.
+    ///
+    /// # Note
+    /// This is always false when entries are listed lazily, as they're never parsed whilst opening the ZIP file.
+    pub fn requires_zip64(&self) -> bool {
+        self.zip64_entries
+    }
+
+    /// Returns the ZIP file comment as its exact raw bytes, which aren't guaranteed to be valid UTF-8.
+    pub fn comment_raw(&self) -> &[u8] {
+        &self.comment_raw
+    }
+
+    /// Returns the ZIP file comment, with any invalid UTF-8 sequences replaced (see [`String::from_utf8_lossy()`]).
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+}
@@ -5,34 +5,71 @@
 
 pub mod fs;
 pub mod mem;
+pub mod pool;
 pub mod seek;
+pub mod split;
 pub mod stream;
 pub mod sync;
 
-use crate::error::{Result, ZipError};
+pub(crate) mod buffered;
+pub(crate) mod extract;
+pub(crate) mod metadata;
+pub(crate) mod nested;
+pub(crate) mod options;
+pub(crate) mod range;
+pub(crate) mod verify;
+
+#[cfg(feature = "deflate64")]
+pub(crate) mod deflate64_decoder;
+#[cfg(feature = "legacy-codecs")]
+pub(crate) mod legacy_decoder;
+
+pub use extract::ExtractOptions;
+pub use metadata::ArchiveMetadata;
+pub use nested::NestedReader;
+pub use options::{
+    PathPolicy, RawEntryHeader, ReaderOptions, SearchStrategy, SizeSource, DEFAULT_BUFFER_SIZE,
+    DEFAULT_POLL_BYTE_BUDGET,
+};
+pub use range::ZipEntryRangeReader;
+pub use verify::{EntryFailure, FailureKind, VerificationReport};
+
+use crate::crypto::zipcrypto::{self, ZipCryptoReader};
+use crate::error::{Feature, Result, ZipError};
 use crate::spec::compression::Compression;
-use crate::spec::header::GeneralPurposeFlag;
+use crate::spec::extra_field::ExtraField;
+use crate::spec::header::{GeneralPurposeFlag, LocalFileHeader};
 use crate::entry::ZipEntry;
-use std::borrow::BorrowMut;
+use crate::progress::Progress;
+use buffered::{BufferSource, BufferedReader};
+use std::borrow::{BorrowMut, Cow};
 
+use std::collections::HashMap;
 use std::convert::TryInto;
+use std::io::SeekFrom;
 use std::pin::Pin;
+use std::sync::OnceLock;
 use std::task::{Context, Poll};
 
 #[cfg(any(feature = "deflate", feature = "bzip2", feature = "zstd", feature = "lzma", feature = "xz"))]
 use async_compression::tokio::bufread;
 use async_io_utilities::AsyncPrependReader;
 use crc32fast::Hasher;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, BufReader, ReadBuf, Take};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, BufReader, ReadBuf, Take};
 
+#[derive(Clone)]
 pub(crate) struct ZipEntryMeta {
     pub(crate) general_purpose_flag: GeneralPurposeFlag,
-    pub(crate) file_offset: Option<u32>,
+    pub(crate) file_offset: Option<u64>,
+    pub(crate) disk_start: u16,
 }
 
 pub(crate) enum PrependReader<'a, R: AsyncRead + Unpin> {
     Normal(OwnedReader<'a, R>),
     Prepend(OwnedReader<'a, AsyncPrependReader<R>>),
+    ZipCrypto(ZipCryptoReader<OwnedReader<'a, R>>),
+    #[cfg(feature = "aes")]
+    Aes(crate::crypto::aes::AesReader<OwnedReader<'a, R>>),
 }
 
 impl<'a, R: AsyncRead + Unpin> AsyncRead for PrependReader<'a, R> {
@@ -40,6 +77,9 @@ impl<'a, R: AsyncRead + Unpin> AsyncRead for PrependReader<'a, R> {
         match *self {
             PrependReader::Normal(ref mut inner) => Pin::new(inner).poll_read(c, b),
             PrependReader::Prepend(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            PrependReader::ZipCrypto(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "aes")]
+            PrependReader::Aes(ref mut inner) => Pin::new(inner).poll_read(c, b),
         }
     }
 }
@@ -58,68 +98,320 @@ impl<'a, R: AsyncRead + Unpin> AsyncRead for OwnedReader<'a, R> {
     }
 }
 
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> AsyncSeek for OwnedReader<'a, R> {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> tokio::io::Result<()> {
+        match *self {
+            OwnedReader::Owned(ref mut inner) => Pin::new(inner).start_seek(position),
+            OwnedReader::Borrow(ref mut inner) => Pin::new(inner).start_seek(position),
+        }
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<u64>> {
+        match *self {
+            OwnedReader::Owned(ref mut inner) => Pin::new(inner).poll_complete(c),
+            OwnedReader::Borrow(ref mut inner) => Pin::new(inner).poll_complete(c),
+        }
+    }
+}
+
 /// A ZIP file entry reader which may implement decompression.
 pub struct ZipEntryReader<'a, R: AsyncRead + Unpin> {
-    pub(crate) entry: &'a ZipEntry,
-    pub(crate) meta: &'a ZipEntryMeta,
+    pub(crate) entry: Cow<'a, ZipEntry>,
+    pub(crate) meta: Cow<'a, ZipEntryMeta>,
     pub(crate) reader: CompressionReader<PrependReader<'a, R>>,
     pub(crate) hasher: Hasher,
+    pub(crate) compute_crc: bool,
     pub(crate) consumed: bool,
     pub(crate) state: State,
-    pub(crate) data_descriptor: Option<(u32, u32, u32)>,
+    pub(crate) data_descriptor: Option<(u32, u64, u64)>,
+    pub(crate) local_extra_fields: Vec<ExtraField>,
+    pub(crate) bytes_read: u64,
+    pub(crate) limit: Option<u64>,
+    pub(crate) progress: Option<Progress>,
+    pub(crate) poll_byte_budget: usize,
+    pub(crate) polled_bytes: usize,
+    pub(crate) drain_on_finish: bool,
+    // Set for entries which hold no CRC32 value, as their data is instead authenticated (ie. AE-2 AES entries).
+    pub(crate) crc_omitted: bool,
+    // Cleared once finished, so that the stream reader which opened this reader may move on to the next entry.
+    pub(crate) unfinished: Option<&'a mut bool>,
 }
 
+/// A ZIP file entry reader which owns both its entry's metadata and its inner reader.
+///
+/// As no borrows are held, this reader is `'static` (and [`Send`] when `R` is), so it may be moved into a spawned
+/// task. See [`fs::ZipFileReader::entry_reader_owned()`].
+pub type OwnedZipEntryReader<R> = ZipEntryReader<'static, R>;
+
 /// The state of the ZIP entry reader.
 ///
-/// The state is expected to go from [`State::ReadData`] to [`State::ReadDescriptor`] and
-/// to [`State::PrepareNext`] then back to [`State::ReadData`] **at most** once.
-/// It is allowed to never leave the [`State::ReadData`] state, but not allowed to advance
-/// its state more than one time, unless the [`ZipEntryReader::poll_data_descriptor`]
-/// is adapted to support this.
+/// The state is expected to go from [`State::ReadData`] to [`State::ReadDescriptor`] (only if the entry has a data
+/// descriptor) and to [`State::PrepareNext`], then finally to [`State::Finished`] once EOF has been reached. It is
+/// allowed to never leave the [`State::ReadData`] state, but once [`State::Finished`] is reached, the inner reader is
+/// never polled again so that repeated reads can't prepend the remaining bytes more than once.
 ///
 /// This enum is needed to support the [`ZipEntryReader::poll_data_descriptor`] method,
 /// `poll*` can be called multiple times and needs a State Machine to behave as intended.
 #[derive(Clone, Copy)]
 pub(crate) enum State {
     ReadData,
-    ReadDescriptor([u8; 16], usize),
-    PrepareNext([u8; 16], usize),
+    ReadDescriptor([u8; DESCRIPTOR_LOOKAHEAD], usize),
+    // Holds the range of the buffer which was read past the end of the descriptor (if any).
+    PrepareNext([u8; DESCRIPTOR_LOOKAHEAD], usize, usize),
+    Finished,
 }
 
+/// The number of bytes read when polling for a data descriptor: the largest layout (24 bytes, with a signature and
+/// ZIP64 sizes) plus the signature of whatever follows it.
+pub(crate) const DESCRIPTOR_LOOKAHEAD: usize = 28;
+
 impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
     /// Construct an entry reader from its raw parts (a shared reference to the entry and an inner reader).
-    pub(crate) fn from_raw(entry: &'a ZipEntry, meta: &'a ZipEntryMeta, reader: CompressionReader<PrependReader<'a, R>>, _: bool) -> Self {
+    pub(crate) fn from_raw(
+        entry: Cow<'a, ZipEntry>,
+        meta: Cow<'a, ZipEntryMeta>,
+        reader: CompressionReader<PrependReader<'a, R>>,
+        data_descriptor: bool,
+    ) -> Self {
+        // An entry with no data (eg. a directory) has nothing left to consume, unless a data descriptor follows it.
+        let consumed = !data_descriptor && entry.compressed_size() == 0;
+
         ZipEntryReader {
             entry,
             meta,
             reader,
             hasher: Hasher::new(),
-            consumed: false,
+            compute_crc: true,
+            consumed,
             state: State::ReadData,
             data_descriptor: None,
+            local_extra_fields: Vec::new(),
+            bytes_read: 0,
+            limit: None,
+            progress: None,
+            poll_byte_budget: DEFAULT_POLL_BYTE_BUDGET,
+            polled_bytes: 0,
+            drain_on_finish: true,
+            crc_omitted: false,
+            unfinished: None,
         }
     }
 
+    /// Limits the number of decompressed bytes which may be read from this entry.
+    ///
+    /// Once more than `limit` bytes have been decompressed, reads will fail with
+    /// [`ZipError::DecompressedSizeExceeded`] (wrapped within an I/O error when reading via [`AsyncRead`] directly).
+    pub fn take_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sets the number of decompressed bytes this reader may produce before voluntarily yielding to the runtime.
+    ///
+    /// See [`ReaderOptions::poll_byte_budget()`], which sets the budget of every entry reader opened by a ZIP file
+    /// reader. A budget of zero disables yielding entirely.
+    pub fn poll_byte_budget(mut self, budget: usize) -> Self {
+        self.poll_byte_budget = budget;
+        self
+    }
+
+    /// Disables the computation of the CRC32 value of the bytes read from this entry.
+    ///
+    /// This avoids the cost of hashing every byte (which is most noticeable for Stored entries) where an entry's
+    /// integrity is verified by other means.
+    ///
+    /// # Note
+    /// Once disabled, [`ZipEntryReader::verify_crc()`] fails with [`ZipError::CRC32Disabled`] and
+    /// [`ZipEntryReader::compare_crc()`] returns false, whereas methods with an integrated CRC32 check (eg.
+    /// [`ZipEntryReader::read_to_end_crc()`]) only check the entry's uncompressed size. The values returned by
+    /// [`ZipEntryReader::current_crc32()`] and [`ZipEntryReader::checkpoint()`] are also meaningless.
+    pub fn without_crc(mut self) -> Self {
+        self.compute_crc = false;
+        self
+    }
+
+    /// Sets whether [`ZipEntryReader::finish()`] discards any data which hasn't yet been read, rather than failing
+    /// with [`ZipError::EntryNotFullyConsumed`]. Enabled by default.
+    pub fn drain_on_finish(mut self, drain: bool) -> Self {
+        self.drain_on_finish = drain;
+        self
+    }
+
     /// Returns a reference to the inner entry's data.
     pub fn entry(&self) -> &ZipEntry {
-        self.entry
+        &self.entry
+    }
+
+    /// Returns the extra fields parsed from the entry's local file header.
+    ///
+    /// These may differ from the central directory's extra fields, which are exposed via [`ZipEntry::extra_fields()`].
+    pub fn local_extra_fields(&self) -> &[ExtraField] {
+        &self.local_extra_fields
     }
 
     ///  Returns whether or not this reader has been fully consumed.
+    ///
+    /// Entries without any data (and without a data descriptor) are considered consumed from the outset.
     pub fn consumed(&self) -> bool {
         self.consumed
     }
 
-    /// Returns true if the computed CRC32 value of all bytes read so far matches the expected value.
-    pub fn compare_crc(&mut self) -> bool {
-        let hasher = std::mem::take(&mut self.hasher);
-        let final_crc = hasher.finalize();
+    /// Returns the number of decompressed bytes read from this entry so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Returns the CRC32 value computed over all decompressed bytes read from this entry so far.
+    pub fn current_crc32(&self) -> u32 {
+        self.hasher.clone().finalize()
+    }
 
+    /// Returns a checkpoint of the bytes read from this entry so far, from which a later reader may resume.
+    ///
+    /// See the `entry_reader_resumed()` method of the seek, fs, and mem readers.
+    pub fn checkpoint(&self) -> ReadCheckpoint {
+        ReadCheckpoint { crc32: self.current_crc32(), bytes_consumed: self.bytes_read }
+    }
+
+    /// Returns a handle which observes the number of decompressed bytes read from this entry.
+    ///
+    /// The handle is updated on each read, so may be polled from another task. Readers which never have a handle
+    /// requested don't incur the cost of these updates.
+    pub fn progress(&mut self) -> Progress {
+        let bytes_read = self.bytes_read;
+        self.progress.get_or_insert_with(|| Progress::new(bytes_read)).clone()
+    }
+
+    /// Returns true if the number of bytes read so far matches the expected uncompressed size.
+    ///
+    /// As with [`ZipEntryReader::compare_crc()`], false is returned if the entry's data descriptor hasn't yet been
+    /// read.
+    pub fn compare_size(&self) -> bool {
+        matches!(self.expected_size(), Ok(expected) if expected == self.bytes_read)
+    }
+
+    fn expected_size(&self) -> Result<u64> {
         if self.meta.general_purpose_flag.data_descriptor {
-            self.data_descriptor.expect("Data descriptor was not read").0 == final_crc
+            self.data_descriptor.map(|descriptor| descriptor.2).ok_or(ZipError::DataDescriptorNotRead)
         } else {
-            self.entry.crc32() == final_crc
+            Ok(self.entry.uncompressed_size())
+        }
+    }
+
+    fn expected_crc(&self) -> Result<u32> {
+        if self.meta.general_purpose_flag.data_descriptor {
+            self.data_descriptor.map(|descriptor| descriptor.0).ok_or(ZipError::DataDescriptorNotRead)
+        } else {
+            Ok(self.entry.crc32())
+        }
+    }
+
+    // Checks both the uncompressed size and the CRC32 value of all bytes read once EOF has been reached.
+    fn verify(&mut self) -> Result<()> {
+        let expected = self.expected_size()?;
+
+        if self.bytes_read != expected {
+            let filename = self.entry.filename().to_owned();
+            return Err(ZipError::SizeMismatch { filename, expected, found: self.bytes_read });
+        }
+
+        match self.compute_crc {
+            true => self.verify_crc(),
+            false => Ok(()),
+        }
+    }
+
+    /// Checks the computed CRC32 value of all bytes read so far against the expected value.
+    ///
+    /// Fails with [`ZipError::DataDescriptorNotRead`] if the expected value is held within a data descriptor which
+    /// hasn't yet been read (ie. the entry hasn't been fully consumed), or [`ZipError::CRC32CheckError`] on a mismatch.
+    /// Always fails with [`ZipError::CRC32Disabled`] if disabled via [`ZipEntryReader::without_crc()`].
+    ///
+    /// AES encrypted entries written as AE-2 hold no CRC32 value, as their data is instead authenticated once fully
+    /// decrypted (failing reads with [`ZipError::AuthenticationFailed`]), so this always succeeds for them.
+    pub fn verify_crc(&self) -> Result<()> {
+        if !self.compute_crc {
+            return Err(ZipError::CRC32Disabled);
+        }
+        if self.crc_omitted {
+            return Ok(());
+        }
+
+        let expected = self.expected_crc()?;
+
+        // Nothing has been hashed, so the computed value is trivially that of no data.
+        if self.bytes_read == 0 && expected == 0 {
+            return Ok(());
         }
+
+        let found = self.hasher.clone().finalize();
+
+        if found != expected {
+            let filename = self.entry.filename().to_owned();
+            return Err(ZipError::CRC32CheckError { filename, expected, found });
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the computed CRC32 value of all bytes read so far matches the expected value.
+    ///
+    /// False is returned, rather than panicking, if the expected value is held within a data descriptor which hasn't
+    /// yet been read. See [`ZipEntryReader::try_compare_crc()`] to distinguish between these cases.
+    pub fn compare_crc(&mut self) -> bool {
+        self.verify_crc().is_ok()
+    }
+
+    /// Returns whether the computed CRC32 value of all bytes read matches the expected value, once EOF has been
+    /// reached.
+    ///
+    /// Unlike [`ZipEntryReader::compare_crc()`], a partial hash is never compared. This instead fails with
+    /// [`ZipError::EntryNotFullyConsumed`] if EOF hasn't yet been reached, [`ZipError::DataDescriptorNotRead`] if the
+    /// entry's data descriptor has yet to be read, or [`ZipError::CRC32Disabled`] if disabled via
+    /// [`ZipEntryReader::without_crc()`].
+    pub fn try_compare_crc(&self) -> Result<bool> {
+        if !self.consumed {
+            return Err(ZipError::EntryNotFullyConsumed);
+        }
+
+        match self.verify_crc() {
+            Ok(()) => Ok(true),
+            Err(ZipError::CRC32CheckError { .. }) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Finishes reading this entry, returning the outcome of checking the bytes read against its expected size and
+    /// CRC32 value.
+    ///
+    /// Any data which hasn't yet been read is decompressed and discarded, unless disabled via
+    /// [`ZipEntryReader::drain_on_finish()`] (in which case this fails with [`ZipError::EntryNotFullyConsumed`]), and
+    /// the entry's data descriptor is read if it hasn't been already. A stream reader won't move on to the next entry
+    /// until its current entry's reader has reached EOF, which this ensures.
+    ///
+    /// # Note
+    /// Mismatches are reported via the returned [`EntryOutcome`] rather than as errors, so the caller may decide how
+    /// to handle them (eg. after the data read has already been written elsewhere). The CRC32 value is never reported
+    /// as matching if disabled via [`ZipEntryReader::without_crc()`].
+    pub async fn finish(mut self) -> Result<EntryOutcome> {
+        if !self.consumed && !self.drain_on_finish {
+            return Err(ZipError::EntryNotFullyConsumed);
+        }
+
+        // Reading to EOF also reads any data descriptor, whereas a finished reader only ever yields EOF.
+        tokio::io::copy(&mut self, &mut tokio::io::sink()).await.map_err(unwrap_io_error)?;
+
+        let compressed_size = match self.data_descriptor {
+            Some((_, compressed_size, _)) => compressed_size,
+            None => self.entry.compressed_size(),
+        };
+
+        Ok(EntryOutcome {
+            crc_ok: self.verify_crc().is_ok(),
+            bytes_read: self.bytes_read,
+            compressed_size,
+            uncompressed_size: self.expected_size()?,
+        })
     }
 
     /// For Streams, CRC-32, compressed size and uncompressed size may not be known yet (for example,
@@ -128,22 +420,28 @@ impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
     /// This method polls for the **Data Descriptor** values using a State Machine, sets
     /// [`Self::data_descriptor`] and prepares the next entry to be read.
     ///
-    /// Note that, this function may fail (with `Poll::Ready(Err(_))`) if the data descriptor is
-    /// not present in the entry since it tries to read the 12 bytes corresponding
-    /// to **Data descriptor** fields (without the signature).
+    /// As both the descriptor's signature and ZIP64 (8-byte) sizes are optional, there are four possible layouts. Up
+    /// to [`DESCRIPTOR_LOOKAHEAD`] bytes are read so that the layout may be decided with the help of the bytes which
+    /// follow it (see [`parse_data_descriptor`]). Any bytes read beyond the descriptor itself are prepended back to the
+    /// inner reader along with those remaining in the [`BufferedReader`].
+    ///
+    /// Note that, this function may fail (with `Poll::Ready(Err(_))`) if fewer bytes than the smallest
+    /// descriptor layout (12 bytes, without the signature) remain.
     ///
     /// The caller must ensure that it only calls this function if the data descriptor is present
     /// (see [`Self::poll_read`] implementation).
     pub(crate) fn poll_data_descriptor(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
         let state = self.state;
+        let zip64 = self.has_zip64_extra_field();
 
         let inner = &mut self.borrow_mut().reader;
 
-        if matches!(state, State::ReadData) {
+        if matches!(state, State::ReadData | State::Finished) {
             return Poll::Ready(Ok(()));
         }
 
         let inner_mut = inner.get_mut();
+        let mut descriptor = None;
 
         let state = if let State::ReadDescriptor(mut descriptor_buf, filled) = state {
             let mut buf = ReadBuf::new(&mut descriptor_buf);
@@ -176,48 +474,50 @@ impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
 
             let filled = buf.filled().len();
 
-            State::PrepareNext(descriptor_buf, filled)
+            match parse_data_descriptor(&descriptor_buf[..filled], zip64) {
+                Some((values, length)) => {
+                    descriptor = Some(values);
+                    State::PrepareNext(descriptor_buf, length, filled)
+                }
+                None => {
+                    let error = tokio::io::Error::new(tokio::io::ErrorKind::UnexpectedEof, "truncated data descriptor");
+                    return Poll::Ready(Err(error));
+                }
+            }
         } else {
             state
         };
 
-        let state = if let State::PrepareNext(descriptor_buf, filled) = state {
+        let state = if let State::PrepareNext(descriptor_buf, start, end) = state {
+            // We take any bytes read past the descriptor, followed by the data read by BufferedReader, and prepend them
+            // to the inner reader.
             let mut buffer = Vec::new();
-
-            let descriptor = if filled == 16 {
-                let delimiter = u32::from_le_bytes(descriptor_buf[0..4].try_into().unwrap());
-                let crc = u32::from_le_bytes(descriptor_buf[4..8].try_into().unwrap());
-                let compressed = u32::from_le_bytes(descriptor_buf[8..12].try_into().unwrap());
-                let uncompressed = u32::from_le_bytes(descriptor_buf[12..16].try_into().unwrap());
-
-                if delimiter == crate::spec::signature::DATA_DESCRIPTOR {
-                    Some((crc, compressed, uncompressed))
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-
-            if descriptor.is_none() {
-                buffer.extend_from_slice(&descriptor_buf[..filled]);
-            }
-
-            // We take the data read by BufReader and prepend it to the inner reader.
+            buffer.extend_from_slice(&descriptor_buf[start..end]);
             buffer.extend_from_slice(inner_mut.buffer());
 
             if let PrependReader::Prepend(inner) = inner_mut.get_mut() {
+                crate::trace::debug!(bytes = buffer.len(), "restored prepend buffer");
                 match inner {
                     OwnedReader::Owned(inner) => inner.prepend(&buffer),
                     OwnedReader::Borrow(inner) => inner.prepend(&buffer),
                 };
             }
 
-            if descriptor.is_some() {
-                self.data_descriptor = descriptor;
+            if let Some(unfinished) = &mut self.unfinished {
+                **unfinished = false;
+            }
+
+            if let Some(values) = descriptor {
+                crate::trace::debug!(
+                    crc = values.0,
+                    compressed_size = values.1,
+                    uncompressed_size = values.2,
+                    "resolved data descriptor"
+                );
+                self.data_descriptor = Some(values);
             }
 
-            State::ReadData
+            State::Finished
         } else {
             state
         };
@@ -227,52 +527,222 @@ impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
         Poll::Ready(Ok(()))
     }
 
+    // Poll the inner reader, limiting the bytes read to whatever remains of the poll byte budget.
+    fn poll_within_budget(&mut self, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let remaining = match self.poll_byte_budget {
+            0 => usize::MAX,
+            budget => budget - self.polled_bytes,
+        };
+
+        if b.remaining() <= remaining {
+            return Pin::new(&mut self.reader).poll_read(c, b);
+        }
+
+        let mut limited = ReadBuf::new(b.initialize_unfilled_to(remaining));
+        let poll = Pin::new(&mut self.reader).poll_read(c, &mut limited);
+        let read = limited.filled().len();
+
+        b.advance(read);
+        poll
+    }
+
+    // Whether or not either the central directory or local file header declares the entry's sizes as ZIP64.
+    fn has_zip64_extra_field(&self) -> bool {
+        let mut fields = self.entry.extra_fields().iter().chain(self.local_extra_fields.iter());
+        fields.any(|field| field.header_id() == crate::spec::extra_field::ZIP64_EXTENDED_INFORMATION)
+    }
+
     /// A convenience method similar to `AsyncReadExt::read_to_end()` but with the final CRC32 check integrated.
     ///
-    /// Reads all bytes until EOF and returns an owned vector of them.
+    /// Reads all bytes until EOF and returns an owned vector of them. The vector's length has been checked against the
+    /// entry's expected uncompressed size.
+    ///
+    /// # Note
+    /// The declared uncompressed size can't be trusted, so isn't relied upon beyond a small initial allocation and the
+    /// entry is buffered in full, however large its data really is. See
+    /// [`ZipEntryReader::read_to_end_crc_limited()`] to bound this, or [`ZipEntryReader::copy_to_end_crc()`] to check
+    /// an entry's data as it's streamed elsewhere without buffering it at all.
     pub async fn read_to_end_crc(mut self) -> Result<Vec<u8>> {
-        let mut buffer = Vec::with_capacity(self.entry.uncompressed_size().try_into().unwrap());
-        self.read_to_end(&mut buffer).await?;
+        let mut buffer = Vec::with_capacity(self.initial_capacity());
+        self.read_to_end(&mut buffer).await.map_err(unwrap_io_error)?;
+        self.verify()?;
 
-        if self.compare_crc() {
-            Ok(buffer)
-        } else {
-            Err(ZipError::CRC32CheckError)
-        }
+        Ok(buffer)
+    }
+
+    /// Equivalent to [`ZipEntryReader::read_to_end_crc()`], but fails with [`ZipError::DecompressedSizeExceeded`] as
+    /// soon as more than `max` bytes have been decompressed, rather than after buffering all of them.
+    ///
+    /// Any limit already set via [`ZipEntryReader::take_limit()`] still applies, so the lower of the two is used.
+    pub async fn read_to_end_crc_limited(self, max: u64) -> Result<Vec<u8>> {
+        self.lower_limit(max).read_to_end_crc().await
     }
 
     /// A convenience method similar to `AsyncReadExt::read_to_string()` but with the final CRC32 check integrated.
     ///
-    /// Reads all bytes until EOF and returns an owned string of them.
+    /// Reads all bytes until EOF and returns an owned string of them. The bytes are only decoded once the CRC32 check
+    /// has passed, and [`ZipError::NonUtf8Data`] is returned if they aren't valid UTF-8.
     pub async fn read_to_string_crc(mut self) -> Result<String> {
-        let mut buffer = String::with_capacity(self.entry.uncompressed_size().try_into().unwrap());
-        self.read_to_string(&mut buffer).await?;
+        let mut buffer = Vec::with_capacity(self.initial_capacity());
+        self.read_to_end(&mut buffer).await.map_err(unwrap_io_error)?;
+        self.verify()?;
 
-        if self.compare_crc() {
-            Ok(buffer)
-        } else {
-            Err(ZipError::CRC32CheckError)
-        }
+        String::from_utf8(buffer).map_err(|_| ZipError::NonUtf8Data(self.entry.filename().to_owned()))
+    }
+
+    /// Equivalent to [`ZipEntryReader::read_to_string_crc()`], but fails with [`ZipError::DecompressedSizeExceeded`]
+    /// as soon as more than `max` bytes have been decompressed, rather than after buffering all of them.
+    pub async fn read_to_string_crc_limited(self, max: u64) -> Result<String> {
+        self.lower_limit(max).read_to_string_crc().await
     }
 
     /// A convenience method for buffered copying of bytes to a writer with the final CRC32 check integrated.
     ///
+    /// Returns the number of decompressed bytes copied, which has been checked against the entry's expected
+    /// uncompressed size (from either its data descriptor or header). A mismatch is reported as
+    /// [`ZipError::SizeMismatch`], distinct from a failed CRC32 check.
+    ///
     /// # Note
     /// Any bytes written to the writer cannot be unwound, thus the caller should appropriately handle the side effects
-    /// of a failed CRC32 check.
+    /// of a failed size or CRC32 check.
     ///
     /// Prefer this method over tokio::io::copy as we have the ability to specify the buffer size (64kb recommended on
     /// modern systems), whereas, tokio's default implementation uses 2kb, so many more calls to read() have to take
     /// place.
-    pub async fn copy_to_end_crc<W: AsyncWrite + Unpin>(mut self, writer: &mut W, buffer: usize) -> Result<()> {
+    pub async fn copy_to_end_crc<W: AsyncWrite + Unpin>(mut self, writer: &mut W, buffer: usize) -> Result<u64> {
         let mut reader = BufReader::with_capacity(buffer, &mut self);
-        tokio::io::copy_buf(&mut reader, writer).await?;
+        let copied = tokio::io::copy_buf(&mut reader, writer).await.map_err(unwrap_io_error)?;
+        self.verify()?;
 
-        if self.compare_crc() {
-            Ok(())
-        } else {
-            Err(ZipError::CRC32CheckError)
-        }
+        Ok(copied)
+    }
+
+    /// Converts this reader into a stream of its decompressed data, in chunks of up to `chunk_size` bytes, with the
+    /// final CRC32 check integrated.
+    ///
+    /// Once the entry's data has been exhausted, its size and CRC32 value are checked, and on a mismatch the error
+    /// (eg. [`ZipError::CRC32CheckError`]) is yielded as the stream's last item. Consumers which forward each chunk
+    /// elsewhere (eg. as a HTTP response body) can therefore abort, rather than silently ending with corrupt data. A
+    /// chunk size of zero is treated as one byte.
+    #[cfg(feature = "stream")]
+    pub fn into_bytes_stream(self, chunk_size: usize) -> impl futures_util::Stream<Item = Result<bytes::Bytes>> + 'a {
+        let chunk_size = chunk_size.max(1);
+
+        futures_util::stream::unfold(Some(self), move |reader| async move {
+            let mut reader = reader?;
+            let mut chunk = bytes::BytesMut::with_capacity(chunk_size);
+
+            while chunk.len() < chunk_size {
+                match (&mut reader).take((chunk_size - chunk.len()) as u64).read_buf(&mut chunk).await {
+                    Ok(0) => break,
+                    Ok(_) => continue,
+                    Err(err) => return Some((Err(unwrap_io_error(err)), None)),
+                }
+            }
+
+            if !chunk.is_empty() {
+                return Some((Ok(chunk.freeze()), Some(reader)));
+            }
+
+            match reader.verify() {
+                Ok(()) => None,
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+
+    // Applies a limit on the number of decompressed bytes, unless a lower one has already been set.
+    fn lower_limit(mut self, max: u64) -> Self {
+        self.limit = Some(self.limit.map_or(max, |limit| std::cmp::min(limit, max)));
+        self
+    }
+
+    // Avoids trusting the declared uncompressed size beyond any configured limit when pre-allocating.
+    //
+    // The declared size is also capped at MAX_INITIAL_CAPACITY, so a malformed header can't cause a huge allocation
+    // upfront. Any larger entry's buffer instead grows as its data is actually read.
+    fn initial_capacity(&self) -> usize {
+        // A size of u32::MAX is a placeholder for the ZIP64 extra field's size, rather than the size itself.
+        let declared = match self.entry.uncompressed_size() {
+            size if size == u64::from(u32::MAX) => 0,
+            size => size,
+        };
+        let size = match self.limit {
+            Some(limit) => std::cmp::min(limit, declared),
+            None => declared,
+        };
+
+        std::cmp::min(size, MAX_INITIAL_CAPACITY) as usize
+    }
+}
+
+// The most bytes pre-allocated when reading an entry to its end, regardless of its declared size.
+const MAX_INITIAL_CAPACITY: u64 = 64 * 1024;
+
+// Decide upon the layout of a data descriptor from its leading bytes (and any which follow it), returning its values
+// (CRC32, compressed size, and uncompressed size) and length, or None if too few bytes are present.
+//
+// The signature is assumed present if the first four bytes match it, and the sizes are assumed to be ZIP64 if the
+// entry has a ZIP64 extra field. Otherwise, the first layout which is directly followed by a known signature is chosen,
+// falling back to the most likely layout if none are. As a CRC32 value may coincidentally match the signature, the
+// layouts without a signature are also considered in that case.
+// https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#439
+pub(crate) fn parse_data_descriptor(bytes: &[u8], zip64: bool) -> Option<((u32, u64, u64), usize)> {
+    let signed = read_u32(bytes, 0) == Some(crate::spec::signature::DATA_DESCRIPTOR);
+
+    // Layouts as pairs of the signature's length and the length of each size field, in order of preference.
+    let offsets: &[usize] = if signed { &[4, 0] } else { &[0] };
+    let widths: &[usize] = if zip64 { &[8] } else { &[4, 8] };
+    let mut layouts = offsets.iter().flat_map(|offset| widths.iter().map(move |width| (*offset, *width)));
+
+    let followed = layouts.find(|(offset, width)| next_is_signature(bytes, offset + 4 + 2 * width));
+    let (offset, width) = followed.unwrap_or((offsets[0], widths[0]));
+    let length = offset + 4 + 2 * width;
+
+    if bytes.len() < length {
+        return None;
+    }
+
+    let crc = read_u32(bytes, offset)?;
+    let (compressed, uncompressed) = if width == 8 {
+        (read_u64(bytes, offset + 4)?, read_u64(bytes, offset + 12)?)
+    } else {
+        (read_u32(bytes, offset + 4)?.into(), read_u32(bytes, offset + 8)?.into())
+    };
+
+    Some(((crc, compressed, uncompressed), length))
+}
+
+// Whether or not the bytes at an offset are the signature of a record which may follow an entry's data.
+fn next_is_signature(bytes: &[u8], offset: usize) -> bool {
+    use crate::spec::signature::{CENTRAL_DIRECTORY_FILE_HEADER, LOCAL_FILE_HEADER};
+    use crate::spec::signature::{END_OF_CENTRAL_DIRECTORY, ZIP64_END_OF_CENTRAL_DIRECTORY};
+
+    matches!(
+        read_u32(bytes, offset),
+        Some(
+            LOCAL_FILE_HEADER
+                | CENTRAL_DIRECTORY_FILE_HEADER
+                | END_OF_CENTRAL_DIRECTORY
+                | ZIP64_END_OF_CENTRAL_DIRECTORY
+        )
+    )
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    bytes.get(offset..offset + 8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+// Recovers a ZipError raised within poll_read() which had to be wrapped within an I/O error.
+fn unwrap_io_error(error: std::io::Error) -> ZipError {
+    if matches!(error.get_ref(), Some(inner) if inner.is::<ZipError>()) {
+        *error.into_inner().unwrap().downcast::<ZipError>().unwrap()
+    } else {
+        ZipError::UpstreamReadError(error)
     }
 }
 
@@ -280,36 +750,60 @@ impl<'a, R: AsyncRead + Unpin> AsyncRead for ZipEntryReader<'a, R> {
     fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
         return match self.state {
             State::ReadData => {
+                // Yield once the budget has been spent, so that other tasks on the runtime aren't starved.
+                if self.poll_byte_budget > 0 && self.polled_bytes >= self.poll_byte_budget {
+                    self.polled_bytes = 0;
+                    c.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+
                 let prev_len = b.filled().len();
-                let poll = Pin::new(&mut self.reader).poll_read(c, b);
+                let poll = self.poll_within_budget(c, b);
 
                 match poll {
-                    Poll::Ready(Err(_)) | Poll::Pending => return poll,
+                    Poll::Pending => {
+                        self.polled_bytes = 0;
+                        return poll;
+                    }
+                    Poll::Ready(Err(_)) => return poll,
                     _ => {}
                 };
 
                 if b.filled().len() - prev_len == 0 {
-                    let was_consumed = self.consumed;
-
                     self.consumed = true;
 
-                    if self.data_descriptor.is_none() && self.meta.general_purpose_flag.data_descriptor {
-                        self.state = State::ReadDescriptor([0u8; 16], 0);
-
-                        self.poll_data_descriptor(c)
-                    } else if !was_consumed {
-                        self.state = State::PrepareNext([0u8; 16], 0);
-
-                        self.poll_data_descriptor(c)
+                    if self.meta.general_purpose_flag.data_descriptor {
+                        self.state = State::ReadDescriptor([0u8; DESCRIPTOR_LOOKAHEAD], 0);
                     } else {
-                        poll
+                        self.state = State::PrepareNext([0u8; DESCRIPTOR_LOOKAHEAD], 0, 0);
                     }
+
+                    self.poll_data_descriptor(c)
                 } else {
-                    self.hasher.update(&b.filled()[prev_len..b.filled().len()]);
+                    let read = &b.filled()[prev_len..b.filled().len()];
+                    self.bytes_read += read.len() as u64;
+                    self.polled_bytes += read.len();
+
+                    if let Some(progress) = &self.progress {
+                        progress.set(self.bytes_read);
+                    }
+
+                    if matches!(self.limit, Some(limit) if self.bytes_read > limit) {
+                        let error = ZipError::DecompressedSizeExceeded;
+                        return Poll::Ready(Err(tokio::io::Error::new(tokio::io::ErrorKind::Other, error)));
+                    }
+
+                    // Checked once per read, rather than per byte.
+                    if self.compute_crc {
+                        self.hasher.update(read);
+                    }
+
                     poll
                 }
             }
-            // Any state other than ReadData means that descriptor is being read.
+            // Once finished, the inner reader is never polled again and all further reads yield EOF.
+            State::Finished => Poll::Ready(Ok(())),
+            // Any other state means that the descriptor is being read.
             _ => self.poll_data_descriptor(c),
         };
     }
@@ -327,29 +821,33 @@ impl<'a, R: AsyncRead + Unpin> AsyncRead for ZipEntryReader<'a, R> {
 /// termination, which is completely dependent on the algorithm used. In other words, they will
 /// never consume bytes that are not part of the compressed data, even if there still data to be read.
 ///
-/// However, [`BufReader`] takes a fixed-size number of bytes from the reader `R`. This means that
+/// However, [`BufferedReader`] takes a fixed-size number of bytes from the reader `R`. This means that
 /// when we “unwrap” the inner reader, some of the bytes will be already consumed from it, but
-/// still inside the [`BufReader`], se we need to ensure that we take those bytes and prepend them
+/// still inside the [`BufferedReader`], se we need to ensure that we take those bytes and prepend them
 /// back to the inner reader, so the next `poll_read` call consumes the un-consumed data. See
 /// this relevant code section for further reference:
 ///
 /// [`tokio::bufread::generic::decoder`](https://github.com/Nemo157/async-compression/blob/ada65c660bcea83dc6a0c3d6149e5fbcd039f739/src/tokio/bufread/generic/decoder.rs#L81)
 pub(crate) enum CompressionReader<R: AsyncRead + Unpin> {
-    Stored(Take<BufReader<R>>),
+    Stored(Take<BufferedReader<R>>),
     #[cfg(feature = "deflate")]
-    Deflate(bufread::DeflateDecoder<BufReader<R>>),
+    Deflate(bufread::DeflateDecoder<BufferedReader<R>>),
     #[cfg(feature = "bzip2")]
-    Bz(bufread::BzDecoder<BufReader<R>>),
+    Bz(bufread::BzDecoder<BufferedReader<R>>),
     #[cfg(feature = "lzma")]
-    Lzma(bufread::LzmaDecoder<BufReader<R>>),
+    Lzma(bufread::LzmaDecoder<BufferedReader<R>>),
     #[cfg(feature = "zstd")]
-    Zstd(bufread::ZstdDecoder<BufReader<R>>),
+    Zstd(bufread::ZstdDecoder<BufferedReader<R>>),
     #[cfg(feature = "xz")]
-    Xz(bufread::XzDecoder<BufReader<R>>),
+    Xz(bufread::XzDecoder<BufferedReader<R>>),
+    #[cfg(feature = "deflate64")]
+    Deflate64(deflate64_decoder::Deflate64Decoder<BufferedReader<R>>),
+    #[cfg(feature = "legacy-codecs")]
+    Legacy(legacy_decoder::LegacyDecoder<Take<BufferedReader<R>>>),
 }
 
 impl<R: AsyncRead + Unpin> CompressionReader<R> {
-    pub(crate) fn get_mut(&mut self) -> &mut BufReader<R> {
+    pub(crate) fn get_mut(&mut self) -> &mut BufferedReader<R> {
         match self {
             CompressionReader::Stored(inner) => inner.get_mut(),
             #[cfg(feature = "deflate")]
@@ -362,6 +860,10 @@ impl<R: AsyncRead + Unpin> CompressionReader<R> {
             CompressionReader::Zstd(inner) => inner.get_mut(),
             #[cfg(feature = "xz")]
             CompressionReader::Xz(inner) => inner.get_mut(),
+            #[cfg(feature = "deflate64")]
+            CompressionReader::Deflate64(inner) => inner.get_mut(),
+            #[cfg(feature = "legacy-codecs")]
+            CompressionReader::Legacy(inner) => inner.get_mut().get_mut(),
         }
     }
 }
@@ -380,28 +882,582 @@ impl<R: AsyncRead + Unpin> AsyncRead for CompressionReader<R> {
             CompressionReader::Zstd(ref mut inner) => Pin::new(inner).poll_read(c, b),
             #[cfg(feature = "xz")]
             CompressionReader::Xz(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "deflate64")]
+            CompressionReader::Deflate64(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "legacy-codecs")]
+            CompressionReader::Legacy(ref mut inner) => Pin::new(inner).poll_read(c, b),
         }
     }
 }
 
 impl<'a, R: AsyncRead + Unpin> CompressionReader<R> {
-    pub(crate) fn from_reader(compression: &Compression, reader: R, take: Option<u64>) -> Result<Self> {
+    pub(crate) fn from_reader(entry: &ZipEntry, reader: R, take: Option<u64>, buffer: BufferSource) -> Result<Self> {
+        Self::with_compression(entry, entry.compression(), reader, take, buffer)
+    }
+
+    // As from_reader(), but with the entry's data compressed with the provided method rather than that it records (eg.
+    // for AES encrypted entries, which record the method separately).
+    pub(crate) fn with_compression(
+        entry: &ZipEntry,
+        compression: Compression,
+        reader: R,
+        take: Option<u64>,
+        buffer: BufferSource,
+    ) -> Result<Self> {
+        // An entry without any compressed data (eg. a directory) needs neither a decoder nor a buffer, whatever its
+        // declared compression method.
+        if take == Some(0) {
+            return Ok(CompressionReader::Stored(BufferedReader::unbuffered(reader).take(0)));
+        }
+
+        let reader = BufferedReader::new(&buffer, reader);
+
         Ok(match compression {
-            Compression::Stored => CompressionReader::Stored(
-                BufReader::new(reader).take(take.ok_or_else(|| ZipError::MissingCompressedSize)?),
-            ),
+            Compression::Stored => {
+                CompressionReader::Stored(reader.take(take.ok_or_else(|| ZipError::MissingCompressedSize)?))
+            }
             #[cfg(feature = "deflate")]
-            Compression::Deflate => CompressionReader::Deflate(bufread::DeflateDecoder::new(BufReader::new(reader))),
+            Compression::Deflate => CompressionReader::Deflate(bufread::DeflateDecoder::new(reader)),
             #[cfg(feature = "bzip2")]
-            Compression::Bz => CompressionReader::Bz(bufread::BzDecoder::new(BufReader::new(reader))),
+            Compression::Bz => CompressionReader::Bz(bufread::BzDecoder::new(reader)),
             #[cfg(feature = "lzma")]
-            Compression::Lzma => CompressionReader::Lzma(bufread::LzmaDecoder::new(BufReader::new(reader))),
+            Compression::Lzma => CompressionReader::Lzma(bufread::LzmaDecoder::new(reader)),
             #[cfg(feature = "zstd")]
-            Compression::Zstd => CompressionReader::Zstd(bufread::ZstdDecoder::new(BufReader::new(reader))),
+            Compression::Zstd => CompressionReader::Zstd(bufread::ZstdDecoder::new(reader)),
             #[cfg(feature = "xz")]
-            Compression::Xz => CompressionReader::Xz(bufread::XzDecoder::new(BufReader::new(reader))),
+            Compression::Xz => CompressionReader::Xz(bufread::XzDecoder::new(reader)),
+            #[cfg(feature = "deflate64")]
+            Compression::Deflate64 => CompressionReader::Deflate64(deflate64_decoder::Deflate64Decoder::new(reader)),
+            // Neither method marks the end of its compressed data, so the data must be delimited by its size.
+            #[cfg(feature = "legacy-codecs")]
+            compression @ (Compression::Shrink | Compression::Implode) => {
+                let error = ZipError::FeatureNotSupported("Shrink or Implode compression with a data descriptor");
+                let take = take.ok_or(error)?;
+                CompressionReader::Legacy(legacy_decoder::LegacyDecoder::new(reader.take(take), compression, entry))
+            }
+            #[cfg(not(feature = "legacy-codecs"))]
+            compression @ (Compression::Shrink | Compression::Implode) => {
+                let (entry, compression) = (entry.filename().to_owned(), compression.into());
+                return Err(ZipError::FeatureNotEnabled { entry, compression, feature: "legacy-codecs" });
+            }
+            Compression::Reduce(_) => return Err(ZipError::FeatureNotSupported("Reduce compression")),
+            Compression::Unknown(value) => return Err(ZipError::UnsupportedCompressionError(value)),
+        })
+    }
+}
+
+/// A point reached whilst reading an entry, as returned by [`ZipEntryReader::checkpoint()`].
+///
+/// A checkpoint holds the number of decompressed bytes consumed and the CRC32 value computed over them, so may be
+/// persisted via these raw parts and later reconstructed with [`ReadCheckpoint::new()`] (eg. to resume extraction
+/// after a restart).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadCheckpoint {
+    crc32: u32,
+    bytes_consumed: u64,
+}
+
+impl ReadCheckpoint {
+    /// Constructs a checkpoint from its raw parts.
+    pub fn new(crc32: u32, bytes_consumed: u64) -> Self {
+        Self { crc32, bytes_consumed }
+    }
+
+    /// Returns the CRC32 value computed over the bytes consumed.
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    /// Returns the number of decompressed bytes consumed.
+    pub fn bytes_consumed(&self) -> u64 {
+        self.bytes_consumed
+    }
+}
+
+/// The outcome of reading an entry to its end, as returned by [`ZipEntryReader::finish()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryOutcome {
+    crc_ok: bool,
+    bytes_read: u64,
+    compressed_size: u64,
+    uncompressed_size: u64,
+}
+
+impl EntryOutcome {
+    /// Returns whether the computed CRC32 value of the bytes read matched the expected value.
+    pub fn crc_ok(&self) -> bool {
+        self.crc_ok
+    }
+
+    /// Returns the number of decompressed bytes read from the entry.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Returns the entry's expected compressed size, from either its data descriptor or header.
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    /// Returns the entry's expected uncompressed size, from either its data descriptor or header.
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    /// Returns whether the number of bytes read matched the expected uncompressed size.
+    pub fn size_ok(&self) -> bool {
+        self.bytes_read == self.uncompressed_size
+    }
+
+    /// Returns whether both the entry's CRC32 value and uncompressed size matched their expected values.
+    pub fn is_ok(&self) -> bool {
+        self.crc_ok && self.size_ok()
+    }
+}
+
+// Construct an entry reader which resumes from a checkpoint, given an inner reader positioned at the start of the
+// entry's compressed data.
+pub(crate) async fn resume_entry_reader<'a, R: AsyncRead + AsyncSeek + Unpin>(
+    entry: Cow<'a, ZipEntry>,
+    meta: Cow<'a, ZipEntryMeta>,
+    reader: OwnedReader<'a, R>,
+    compressed_size: u64,
+    checkpoint: ReadCheckpoint,
+    buffer: BufferSource,
+) -> Result<ZipEntryReader<'a, R>> {
+    let reader = skip_entry_data(&entry, reader, compressed_size, checkpoint.bytes_consumed, buffer).await?;
+    let reader = reader.ok_or(ZipError::CheckpointOutOfBounds(checkpoint.bytes_consumed))?;
+
+    let data_descriptor = meta.general_purpose_flag.data_descriptor;
+    let mut reader = ZipEntryReader::from_raw(entry, meta, reader, data_descriptor);
+    reader.hasher = Hasher::new_with_initial_len(checkpoint.crc32, checkpoint.bytes_consumed);
+    reader.bytes_read = checkpoint.bytes_consumed;
+
+    Ok(reader)
+}
+
+// Construct a reader over an entry's decompressed data with its first bytes skipped, given an inner reader positioned
+// at the start of the entry's compressed data (of which there are `compressed_size` bytes, see SizeSource). None is
+// returned if the entry's data ends before that many bytes.
+//
+// Stored entries seek past the skipped bytes. Any other entry must decompress them, but they're discarded.
+pub(crate) async fn skip_entry_data<'a, R: AsyncRead + AsyncSeek + Unpin>(
+    entry: &ZipEntry,
+    mut reader: OwnedReader<'a, R>,
+    mut compressed_size: u64,
+    mut skip: u64,
+    buffer: BufferSource,
+) -> Result<Option<CompressionReader<PrependReader<'a, R>>>> {
+    if entry.compression() == Compression::Stored {
+        compressed_size = match compressed_size.checked_sub(skip) {
+            Some(compressed_size) => compressed_size,
+            None => return Ok(None),
+        };
+        let offset = SeekFrom::Current(skip as i64);
+
+        match reader {
+            OwnedReader::Owned(ref mut inner) => inner.seek(offset).await?,
+            OwnedReader::Borrow(ref mut inner) => inner.seek(offset).await?,
+        };
+
+        skip = 0;
+    }
+
+    let reader = PrependReader::Normal(reader);
+    let mut reader = CompressionReader::from_reader(entry, reader, Some(compressed_size), buffer)?;
+    let skipped = tokio::io::copy(&mut (&mut reader).take(skip), &mut tokio::io::sink()).await?;
+
+    match skipped == skip {
+        true => Ok(Some(reader)),
+        false => Ok(None),
+    }
+}
+
+// Fail fast on an entry whose data isn't compressed data at all, rather than failing somewhere within its decoder.
+//
+// Entries compressed with a method whose cargo feature isn't enabled are also rejected here rather than when the ZIP
+// file is opened, so that the rest of its entries remain readable. As are encrypted entries, which may only be read
+// with a password (see check_decodable()).
+pub(crate) fn check_readable(entry: &ZipEntry, meta: &ZipEntryMeta) -> Result<()> {
+    check_decodable(entry, meta)?;
+
+    if meta.general_purpose_flag.encrypted {
+        return Err(ZipError::PasswordRequired(entry.filename().to_owned()));
+    }
+
+    Ok(())
+}
+
+// As check_readable(), but for an entry whose data will be decrypted before being decompressed.
+pub(crate) fn check_decodable(entry: &ZipEntry, meta: &ZipEntryMeta) -> Result<()> {
+    if meta.general_purpose_flag.strong_encryption {
+        return Err(ZipError::UnsupportedFeature(Feature::StrongEncryption));
+    }
+    if meta.general_purpose_flag.patched_data {
+        return Err(ZipError::UnsupportedFeature(Feature::PatchedData));
+    }
+    if let Some(feature) = entry.compression().required_feature() {
+        let (entry, compression) = (entry.filename().to_owned(), entry.compression_raw());
+        return Err(ZipError::FeatureNotEnabled { entry, compression, feature });
+    }
+
+    Ok(())
+}
+
+// Open an entry listed lazily from the central directory for reading, given an inner reader over the ZIP file. This
+// backs each seekable reader's reader_with_entry().
+pub(crate) async fn lazy_entry_reader<'a, R: AsyncRead + AsyncSeek + Unpin>(
+    entry: &'a seek::CentralDirectoryEntry,
+    mut reader: OwnedReader<'a, R>,
+    options: &ReaderOptions,
+) -> Result<ZipEntryReader<'a, R>> {
+    let (index, entry, meta) = (entry.index, &entry.entry, &entry.meta);
+    check_readable(entry, meta)?;
+
+    reader.seek(SeekFrom::Start(meta.file_offset.unwrap() + 4)).await?;
+    let (header, local_extra_fields) = read_local_header(&mut reader, index, entry, meta, options).await?;
+
+    let reader = PrependReader::Normal(reader);
+    let compressed_size = Some(options.size_source.compressed_size(entry, &header));
+    let reader = CompressionReader::from_reader(entry, reader, compressed_size, options.buffer_source())?;
+
+    let data_descriptor = meta.general_purpose_flag.data_descriptor;
+    let mut reader = ZipEntryReader::from_raw(Cow::Borrowed(entry), Cow::Borrowed(meta), reader, data_descriptor);
+    reader.poll_byte_budget = options.poll_byte_budget;
+    reader.local_extra_fields = local_extra_fields;
+
+    Ok(reader)
+}
+
+// Open the entry at the provided index for reading, decrypting its data with a password, given an inner reader over the
+// ZIP file. This backs each seekable reader's entry_reader_with_password().
+pub(crate) async fn password_entry_reader<'a, R: AsyncRead + AsyncSeek + Unpin>(
+    entries: &'a [(ZipEntry, ZipEntryMeta)],
+    index: usize,
+    mut reader: OwnedReader<'a, R>,
+    options: &ReaderOptions,
+    password: &[u8],
+) -> Result<ZipEntryReader<'a, R>> {
+    let (entry, meta) = entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+    check_decodable(entry, meta)?;
+
+    reader.seek(SeekFrom::Start(meta.file_offset.unwrap() + 4)).await?;
+    let (header, local_extra_fields) = read_local_header(&mut reader, index, entry, meta, options).await?;
+
+    let compressed_size = options.size_source.compressed_size(entry, &header);
+    let (entry, meta) = (Cow::Borrowed(entry), Cow::Borrowed(meta));
+    let buffer = options.buffer_source();
+
+    let reader = decrypting_entry_reader(entry, meta, &header, reader, compressed_size, password, buffer);
+    let mut reader = reader.await?;
+    reader.poll_byte_budget = options.poll_byte_budget;
+    reader.local_extra_fields = local_extra_fields;
+
+    Ok(reader)
+}
+
+// Construct an entry reader which decrypts the entry's data with a password, given an inner reader positioned at the
+// start of the entry's data (ie. any encryption header). The password is ignored for entries which aren't encrypted.
+pub(crate) async fn decrypting_entry_reader<'a, R: AsyncRead + Unpin>(
+    entry: Cow<'a, ZipEntry>,
+    meta: Cow<'a, ZipEntryMeta>,
+    header: &LocalFileHeader,
+    reader: OwnedReader<'a, R>,
+    compressed_size: u64,
+    password: &[u8],
+    buffer: BufferSource,
+) -> Result<ZipEntryReader<'a, R>> {
+    let decrypted = decrypting_reader(&entry, &meta, header, reader, compressed_size, password).await?;
+    let (compression, take) = (decrypted.compression, Some(decrypted.compressed_size));
+    let reader = CompressionReader::with_compression(&entry, compression, decrypted.reader, take, buffer)?;
+
+    let data_descriptor = meta.general_purpose_flag.data_descriptor;
+    let mut reader = ZipEntryReader::from_raw(entry, meta, reader, data_descriptor);
+    reader.crc_omitted = decrypted.crc_omitted;
+
+    Ok(reader)
+}
+
+// An entry's data with any encryption stripped from it.
+struct DecryptedData<'a, R: AsyncRead + Unpin> {
+    reader: PrependReader<'a, R>,
+    // The number of bytes of compressed data, excluding those added by the encryption.
+    compressed_size: u64,
+    // The method the data was compressed with, which AES encryption records separately.
+    compression: Compression,
+    // Whether the entry holds no CRC32 value (as with AE-2), in which case its data is authenticated instead.
+    crc_omitted: bool,
+}
+
+// Wrap an inner reader positioned at the start of an entry's data with a decrypting reader if the entry is encrypted,
+// with either the traditional PKWARE scheme (ZipCrypto) or AES (as denoted by its compression method).
+//
+// For ZipCrypto, the encryption header's check byte is the high byte of the entry's CRC32 value or, if a data
+// descriptor is used (as the CRC32 value isn't known until the data has been written), the high byte of its last
+// modification time.
+async fn decrypting_reader<'a, R: AsyncRead + Unpin>(
+    entry: &ZipEntry,
+    meta: &ZipEntryMeta,
+    header: &LocalFileHeader,
+    mut reader: OwnedReader<'a, R>,
+    compressed_size: u64,
+    password: &[u8],
+) -> Result<DecryptedData<'a, R>> {
+    let compression = entry.compression();
+
+    if !meta.general_purpose_flag.encrypted {
+        let reader = PrependReader::Normal(reader);
+        return Ok(DecryptedData { reader, compressed_size, compression, crc_omitted: false });
+    }
+    if entry.compression_raw() == crate::spec::extra_field::AES_COMPRESSION {
+        return aes_decrypting_reader(entry, reader, compressed_size, password).await;
+    }
+
+    let remaining = compressed_size.checked_sub(zipcrypto::HEADER_LENGTH as u64);
+    let found = compressed_size as usize;
+    let remaining = remaining.ok_or(ZipError::TruncatedRecord { expected: zipcrypto::HEADER_LENGTH, found })?;
+
+    let mut encryption_header = [0; zipcrypto::HEADER_LENGTH];
+    reader.read_exact(&mut encryption_header).await?;
+
+    let check = match meta.general_purpose_flag.data_descriptor {
+        true => (header.mod_time >> 8) as u8,
+        false => (entry.crc32() >> 24) as u8,
+    };
+
+    let keys = zipcrypto::decrypt_header(password, encryption_header, check);
+    let keys = keys.ok_or_else(|| ZipError::WrongPassword(entry.filename().to_owned()))?;
+
+    let reader = PrependReader::ZipCrypto(ZipCryptoReader::new(reader, keys, remaining));
+    Ok(DecryptedData { reader, compressed_size: remaining, compression, crc_omitted: false })
+}
+
+// Wrap an inner reader positioned at the start of an AES entry's data (ie. its salt) with a decrypting reader.
+//
+// The password is checked against the verification value which follows the salt, before any data is decrypted.
+#[cfg(feature = "aes")]
+async fn aes_decrypting_reader<'a, R: AsyncRead + Unpin>(
+    entry: &ZipEntry,
+    mut reader: OwnedReader<'a, R>,
+    compressed_size: u64,
+    password: &[u8],
+) -> Result<DecryptedData<'a, R>> {
+    use crate::crypto::aes::{self, AesReader};
+
+    let field = entry.extra_fields().iter().find(|field| field.header_id() == crate::spec::extra_field::AES_ENCRYPTION);
+    let field = field.and_then(|field| crate::spec::extra_field::parse_aes(field.data()));
+    let (version, strength, compression) =
+        field.ok_or_else(|| ZipError::InvalidAesExtraField(entry.filename().to_owned()))?;
+
+    let compression = Compression::from(compression);
+    if let Some(feature) = compression.required_feature() {
+        let (entry, compression) = (entry.filename().to_owned(), compression.into());
+        return Err(ZipError::FeatureNotEnabled { entry, compression, feature });
+    }
+
+    let salt_length = aes::salt_length(strength).unwrap();
+    let overhead = salt_length + aes::VERIFICATION_LENGTH + aes::AUTHENTICATION_CODE_LENGTH;
+    let found = compressed_size as usize;
+    let remaining = compressed_size.checked_sub(overhead as u64);
+    let remaining = remaining.ok_or(ZipError::TruncatedRecord { expected: overhead, found })?;
+
+    let mut salt = vec![0; salt_length + aes::VERIFICATION_LENGTH];
+    reader.read_exact(&mut salt).await?;
+    let verification = salt.split_off(salt_length);
+
+    let (cipher, mac, expected) = aes::derive_keys(password, &salt, strength);
+    if verification != expected {
+        return Err(ZipError::WrongPassword(entry.filename().to_owned()));
+    }
+
+    let reader = PrependReader::Aes(AesReader::new(reader, cipher, mac, remaining, entry.filename().to_owned()));
+    Ok(DecryptedData { reader, compressed_size: remaining, compression, crc_omitted: version == 2 })
+}
+
+#[cfg(not(feature = "aes"))]
+async fn aes_decrypting_reader<'a, R: AsyncRead + Unpin>(
+    entry: &ZipEntry,
+    _: OwnedReader<'a, R>,
+    _: u64,
+    _: &[u8],
+) -> Result<DecryptedData<'a, R>> {
+    let (entry, compression) = (entry.filename().to_owned(), entry.compression_raw());
+    Err(ZipError::FeatureNotEnabled { entry, compression, feature: "aes" })
+}
+
+// Read the local file header of the entry at the provided index, which must directly follow the reader's position.
+//
+// When enabled via ReaderOptions::verify_local_headers(), the header is cross-checked against the entry's central
+// directory record, and any divergence fails with ZipError::HeaderMismatch. Otherwise, its filename is skipped.
+pub(crate) async fn read_local_header<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    index: usize,
+    entry: &ZipEntry,
+    meta: &ZipEntryMeta,
+    options: &ReaderOptions,
+) -> Result<(LocalFileHeader, Vec<ExtraField>)> {
+    if !options.verify_local_headers {
+        return skip_local_header(reader).await;
+    }
+
+    let header = LocalFileHeader::from_reader(reader).await?;
+    let filename = async_io_utilities::read_bytes(reader, header.file_name_length.into()).await?;
+    let mismatch = |field| Err(ZipError::HeaderMismatch { field, index });
+
+    // Compare against the filename's bytes as stored, as it may since have been rewritten under PathPolicy::Sanitize
+    // or taken from a Unicode path extra field.
+    if filename != entry.filename_bytes() {
+        return mismatch("filename");
+    }
+    if header.compression != entry.compression_raw() {
+        return mismatch("compression");
+    }
+    if header.flags != meta.general_purpose_flag {
+        return mismatch("general_purpose_flags");
+    }
+
+    let extra_field = async_io_utilities::read_bytes(reader, header.extra_field_length.into()).await?;
+    let extra_fields = crate::spec::extra_field::parse_extra_fields(&extra_field);
+
+    // The local file header's CRC32 value and sizes are zeroed when a data descriptor is used.
+    if !header.flags.data_descriptor {
+        let (compressed_size, uncompressed_size) = local_sizes(&header, &extra_fields);
+        if header.crc != entry.crc32() {
+            return mismatch("crc32");
+        }
+        if compressed_size != entry.compressed_size() {
+            return mismatch("compressed_size");
+        }
+        if uncompressed_size != entry.uncompressed_size() {
+            return mismatch("uncompressed_size");
+        }
+    }
+
+    Ok((header, extra_fields))
+}
+
+pub(crate) async fn skip_local_header<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+) -> Result<(LocalFileHeader, Vec<ExtraField>)> {
+    let header = LocalFileHeader::from_reader(reader).await?;
+    reader.seek(SeekFrom::Current(header.file_name_length.into())).await?;
+
+    let extra_field = async_io_utilities::read_bytes(reader, header.extra_field_length.into()).await?;
+    Ok((header, crate::spec::extra_field::parse_extra_fields(&extra_field)))
+}
+
+// Returns the compressed and uncompressed sizes recorded within a local file header, resolving any which are deferred
+// to the ZIP64 extended information record within its extra fields.
+pub(crate) fn local_sizes(header: &LocalFileHeader, extra_fields: &[ExtraField]) -> (u64, u64) {
+    let mut sizes = [header.uncompressed_size.into(), header.compressed_size.into()];
+    crate::spec::extra_field::resolve_zip64(extra_fields, &mut sizes);
+
+    (sizes[1], sizes[0])
+}
+
+// The filename of the entry which identifies the type of an OpenDocument or EPUB file (see sniff_mimetype()).
+pub(crate) const MIMETYPE_FILENAME: &str = "mimetype";
+
+// The longest mimetype which is read when sniffing, far beyond that of any real format.
+const MAX_MIMETYPE_LENGTH: u64 = 1024;
+
+// Returns the mimetype held by the first entry, if it follows the convention of OpenDocument and EPUB files: named
+// `mimetype` and Stored without encryption, a data descriptor, or extra fields, with its local file header at offset 0.
+//
+// Both the central directory record and the local file header must follow the convention, as sniffers only see the
+// latter. No other entry's data is read.
+pub(crate) async fn sniff_mimetype<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    entries: &[(ZipEntry, ZipEntryMeta)],
+) -> Result<Option<String>> {
+    let (entry, meta) = match entries.first() {
+        Some(entry) => entry,
+        None => return Ok(None),
+    };
+
+    let flags = &meta.general_purpose_flag;
+    let conventional = entry.filename_raw() == MIMETYPE_FILENAME
+        && entry.compression() == Compression::Stored
+        && entry.extra_field().is_empty()
+        && !flags.encrypted
+        && !flags.data_descriptor
+        && (meta.disk_start, meta.file_offset) == (0, Some(0))
+        && entry.uncompressed_size() <= MAX_MIMETYPE_LENGTH;
+
+    if !conventional {
+        return Ok(None);
+    }
+
+    reader.seek(SeekFrom::Start(0)).await?;
+    if reader.read_u32_le().await? != crate::spec::signature::LOCAL_FILE_HEADER {
+        return Ok(None);
+    }
+
+    let header = LocalFileHeader::from_reader(reader).await?;
+    let conventional = usize::from(header.file_name_length) == MIMETYPE_FILENAME.len()
+        && header.extra_field_length == 0
+        && header.compression == entry.compression_raw()
+        && header.flags == *flags
+        && u64::from(header.compressed_size) == entry.uncompressed_size()
+        && u64::from(header.uncompressed_size) == entry.uncompressed_size();
+
+    if !conventional {
+        return Ok(None);
+    }
+
+    let mut data = vec![0; MIMETYPE_FILENAME.len() + entry.uncompressed_size() as usize];
+    reader.read_exact(&mut data).await?;
+
+    let mimetype = data.split_off(MIMETYPE_FILENAME.len());
+    match data == MIMETYPE_FILENAME.as_bytes() {
+        true => Ok(String::from_utf8(mimetype).ok()),
+        false => Ok(None),
+    }
+}
+
+/// A filename-based index over a reader's list of entries, built on the first lookup by filename so that readers which
+/// are never searched don't pay for it.
+///
+/// Where multiple entries share the same filename (or normalised or case-folded filename), the first occurrence wins.
+#[derive(Default)]
+pub(crate) struct EntryIndex(OnceLock<EntryMaps>);
+
+struct EntryMaps {
+    exact: HashMap<String, usize>,
+    normalized: HashMap<String, usize>,
+    case_insensitive: HashMap<String, usize>,
+}
+
+impl EntryIndex {
+    // Returns the index's maps, building them from the provided entries (which must be those of the owning reader) if
+    // this is the first lookup.
+    fn maps(&self, entries: &[(ZipEntry, ZipEntryMeta)]) -> &EntryMaps {
+        self.0.get_or_init(|| {
+            let mut exact = HashMap::with_capacity(entries.len());
+            let mut normalized = HashMap::with_capacity(entries.len());
+            let mut case_insensitive = HashMap::with_capacity(entries.len());
+
+            for (index, (entry, _)) in entries.iter().enumerate() {
+                exact.entry(entry.filename().to_owned()).or_insert(index);
+                normalized.entry(crate::utils::normalize_filename(entry.filename())).or_insert(index);
+                case_insensitive.entry(crate::utils::fold_case(entry.filename())).or_insert(index);
+            }
+
+            EntryMaps { exact, normalized, case_insensitive }
         })
     }
+
+    pub(crate) fn exact(&self, entries: &[(ZipEntry, ZipEntryMeta)], name: &str) -> Option<usize> {
+        self.maps(entries).exact.get(name).copied()
+    }
+
+    pub(crate) fn normalized(&self, entries: &[(ZipEntry, ZipEntryMeta)], name: &str) -> Option<usize> {
+        self.maps(entries).normalized.get(&crate::utils::normalize_filename(name)).copied()
+    }
+
+    pub(crate) fn case_insensitive(&self, entries: &[(ZipEntry, ZipEntryMeta)], name: &str) -> Option<usize> {
+        self.maps(entries).case_insensitive.get(&crate::utils::fold_case(name)).copied()
+    }
 }
 
 macro_rules! reader_entry_impl {
@@ -411,19 +1467,88 @@ macro_rules! reader_entry_impl {
             self.entries.iter().map(|entry| &entry.0).collect()
         }
 
+        /// Returns the ZIP file's entries along with their indices, in the order their data is physically stored.
+        ///
+        /// Entries are usually stored in the order they're listed within the central directory, but some producers
+        /// (eg. parallel compressors) write each entry's data out of order, so reading entries in this order instead
+        /// keeps reads of the underlying reader sequential. Entries are ordered by the disk they start on, then their
+        /// local file header's offset, with ties left in the order they're listed.
+        pub fn entries_by_offset(&self) -> Vec<(usize, &ZipEntry)> {
+            let mut entries: Vec<_> = self.entries.iter().enumerate().collect();
+            entries.sort_by_key(|(_, (_, meta))| (meta.disk_start, meta.file_offset));
+            entries.into_iter().map(|(index, entry)| (index, &entry.0)).collect()
+        }
+
+        /// Returns a snapshot of the metadata of each of the ZIP file's entries, in the order they're listed.
+        ///
+        /// This may be stored and compared against the manifest of a later version of the ZIP file to determine which
+        /// entries have been added, removed, or changed.
+        pub fn manifest(&self) -> Vec<crate::entry::EntrySummary> {
+            self.entries.iter().map(|entry| crate::entry::EntrySummary::from(&entry.0)).collect()
+        }
+
         /// Searches for an entry with a specific filename.
+        ///
+        /// The filename must match exactly. If multiple entries share the filename, the first is returned.
+        ///
+        /// # Note
+        /// The provided name is compared against each entry's decoded filename rather than its raw bytes (see
+        /// [`ZipEntry::filename_bytes()`] for how filenames are decoded).
         pub fn entry(&self, name: &str) -> Option<(usize, &ZipEntry)> {
-            for (index, entry) in self.entries().iter().enumerate() {
-                if entry.filename() == name {
-                    return Some((index, entry));
-                }
-            }
-            None
+            self.index.exact(&self.entries, name).map(|index| (index, &self.entries[index].0))
+        }
+
+        /// Searches for an entry with a filename which matches the provided name after normalisation.
+        ///
+        /// Both filenames are normalised by treating backslashes as separators, dropping empty and `.` components, and
+        /// collapsing `..` components, so `folder/file.txt`, `./folder/file.txt`, and `folder\file.txt` all match the
+        /// same entry. A `..` component which can't be collapsed is kept, so `etc/passwd` never matches an entry named
+        /// `../../etc/passwd`. If multiple entries normalise to the same filename, the first is returned.
+        pub fn entry_normalized(&self, name: &str) -> Option<(usize, &ZipEntry)> {
+            self.index.normalized(&self.entries, name).map(|index| (index, &self.entries[index].0))
+        }
+
+        /// Searches for an entry with a filename which matches the provided name, ignoring case.
+        ///
+        /// Both filenames are compared after Unicode case folding, so `README.TXT` matches an entry named
+        /// `readme.txt`. If multiple entries match, the first is returned; see [`Self::entries_matching_ci()`] to
+        /// find all of them.
+        pub fn entry_ci(&self, name: &str) -> Option<(usize, &ZipEntry)> {
+            self.index.case_insensitive(&self.entries, name).map(|index| (index, &self.entries[index].0))
+        }
+
+        /// Returns every entry with a filename which matches the provided name ignoring case, in the order they're
+        /// listed.
+        pub fn entries_matching_ci(&self, name: &str) -> Vec<(usize, &ZipEntry)> {
+            let name = crate::utils::fold_case(name);
+            let matches = self.entries.iter().enumerate().map(|(index, entry)| (index, &entry.0));
+            matches.filter(|(_, entry)| crate::utils::fold_case(entry.filename()) == name).collect()
+        }
+
+        /// Returns the index of the AppleDouble file which accompanies the entry at the provided index, if present.
+        ///
+        /// macOS stores the extended attributes and resource fork of each file and directory within a companion entry
+        /// whose name prefixes the final component with `._`. Finder places these within the `__MACOSX/` directory
+        /// (eg. `__MACOSX/docs/._lorem.txt` for `docs/lorem.txt`), whereas other tools place them alongside the entry,
+        /// so both locations are searched. None is returned for entries which are themselves macOS metadata.
+        pub fn apple_double_for(&self, index: usize) -> Option<usize> {
+            let filenames = crate::entry::ext::apple_double_filenames(&self.entries.get(index)?.0);
+            filenames.iter().find_map(|filename| self.index.exact(&self.entries, filename))
         }
 
         /// Returns an optional ending comment.
+        ///
+        /// Any invalid UTF-8 sequences are replaced, see [`ArchiveMetadata::comment_raw()`] for the comment's exact
+        /// bytes.
+        ///
+        /// [`ArchiveMetadata::comment_raw()`]: crate::read::ArchiveMetadata::comment_raw
         pub fn comment(&self) -> Option<&str> {
-            self.comment.as_ref().map(|x| &x[..])
+            self.metadata.comment()
+        }
+
+        /// Returns metadata of the ZIP file as a whole, as recorded within its end of central directory header.
+        pub fn metadata(&self) -> &crate::read::ArchiveMetadata {
+            &self.metadata
         }
     };
 }
@@ -0,0 +1,119 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::entry::ZipEntry;
+use crate::error::{Result, ZipError};
+use crate::read::ReaderOptions;
+use crate::spec::compression::Compression;
+
+use std::io::{Cursor, SeekFrom};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt, ReadBuf};
+
+/// A reader over the data of an entry which holds a nested ZIP file, as used by the `open_nested()` method of the seek
+/// and fs readers.
+///
+/// A Stored entry's data is read in place, via a window over the outer ZIP file's reader which starts at the entry's
+/// data and ends with it. Any other entry's data has been decompressed into memory in full.
+pub struct NestedReader<R: AsyncRead + AsyncSeek + Unpin> {
+    inner: Inner<R>,
+}
+
+enum Inner<R> {
+    Window { reader: R, start: u64, length: u64, position: u64 },
+    Buffered(Cursor<Vec<u8>>),
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> NestedReader<R> {
+    // Construct a window over the `length` bytes of a reader which begin at `start`.
+    pub(crate) async fn window(mut reader: R, start: u64, length: u64) -> Result<Self> {
+        reader.seek(SeekFrom::Start(start)).await?;
+        Ok(Self { inner: Inner::Window { reader, start, length, position: 0 } })
+    }
+
+    // Construct a reader over data which has been decompressed into memory.
+    pub(crate) fn buffered(data: Vec<u8>) -> Self {
+        Self { inner: Inner::Buffered(Cursor::new(data)) }
+    }
+
+    /// Returns whether or not the nested ZIP file has been decompressed into memory, rather than being read in place.
+    pub fn is_buffered(&self) -> bool {
+        matches!(self.inner, Inner::Buffered(_))
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncRead for NestedReader<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let (reader, length, position) = match &mut self.inner {
+            Inner::Window { reader, length, position, .. } => (reader, *length, position),
+            Inner::Buffered(cursor) => return Pin::new(cursor).poll_read(c, b),
+        };
+
+        let remaining = length.saturating_sub(*position);
+        if remaining == 0 || b.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let limit = remaining.min(b.remaining() as u64) as usize;
+        let mut limited = ReadBuf::new(b.initialize_unfilled_to(limit));
+        let poll = Pin::new(reader).poll_read(c, &mut limited);
+        let read = limited.filled().len();
+
+        b.advance(read);
+        *position += read as u64;
+        poll
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncSeek for NestedReader<R> {
+    fn start_seek(mut self: Pin<&mut Self>, seek: SeekFrom) -> tokio::io::Result<()> {
+        let (reader, start, length, position) = match &mut self.inner {
+            Inner::Window { reader, start, length, position } => (reader, *start, *length, *position),
+            Inner::Buffered(cursor) => return Pin::new(cursor).start_seek(seek),
+        };
+
+        // Positions are relative to the start of the window, so can't be allowed to seek before it.
+        let target = match seek {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => offset_by(length, offset),
+            SeekFrom::Current(offset) => offset_by(position, offset),
+        };
+        let target = target.and_then(|target| target.checked_add(start));
+        let error = || tokio::io::Error::new(tokio::io::ErrorKind::InvalidInput, "invalid seek before the window");
+        let target = target.ok_or_else(error)?;
+
+        Pin::new(reader).start_seek(SeekFrom::Start(target))
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<u64>> {
+        match &mut self.inner {
+            Inner::Window { reader, start, position, .. } => {
+                let absolute = std::task::ready!(Pin::new(reader).poll_complete(c))?;
+                *position = absolute.saturating_sub(*start);
+                Poll::Ready(Ok(*position))
+            }
+            Inner::Buffered(cursor) => Pin::new(cursor).poll_complete(c),
+        }
+    }
+}
+
+fn offset_by(base: u64, offset: i64) -> Option<u64> {
+    match offset >= 0 {
+        true => base.checked_add(offset as u64),
+        false => base.checked_sub(offset.unsigned_abs()),
+    }
+}
+
+// Whether or not a nested ZIP file's data may be read in place, rather than needing to be decompressed first.
+pub(crate) fn in_place(entry: &ZipEntry, options: &ReaderOptions) -> Result<bool> {
+    if entry.compression() == Compression::Stored && !entry.general_purpose_flags().encrypted() {
+        return Ok(true);
+    }
+
+    match options.decompress_nested {
+        true => Ok(false),
+        false => Err(ZipError::NestedEntryCompressed(entry.filename().to_owned())),
+    }
+}
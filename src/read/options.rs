@@ -0,0 +1,439 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::entry::ZipEntry;
+use crate::error::{Result, ZipError};
+use crate::read::buffered::BufferSource;
+use crate::read::pool::BufferPool;
+use crate::spec::compression::Compression;
+use crate::spec::header::LocalFileHeader;
+
+use std::sync::Arc;
+
+/// The default capacity of the buffer placed between the underlying reader and an entry's decompressor.
+///
+/// Small buffers result in many small reads against the underlying reader when extracting large entries. The
+/// `buffer_size` benchmark compares this default against both smaller and larger capacities.
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// The default number of decompressed bytes an entry reader produces before voluntarily yielding to the runtime.
+///
+/// See [`ReaderOptions::poll_byte_budget()`].
+pub const DEFAULT_POLL_BYTE_BUDGET: usize = 256 * 1024;
+
+/// The header from which an entry's compressed size is taken when reading its data, as set via
+/// [`ReaderOptions::size_source()`].
+///
+/// Only Stored entries are bounded by their compressed size, as every other compression method is self-terminating
+/// (see the `CompressionReader` docs), so this decides where a Stored entry's data ends and, when verifying, which
+/// header's compressed size is trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeSource {
+    /// The entry's local file header, falling back to the central directory when the local file header's sizes are
+    /// zeroed due to the use of a data descriptor (or deferred to a ZIP64 extended information extra field).
+    LocalHeader,
+    /// The entry's central directory record.
+    CentralDirectory,
+    /// Neither header, with decompression ending wherever the compressed data itself does.
+    ///
+    /// The decoders are only bounded by [`ReaderOptions::max_decompressed_size()`] (if set), and as a Stored entry's
+    /// data has no such end, its central directory size is used instead. When verifying, the number of compressed
+    /// bytes actually consumed is checked against both headers, and any which disagree are reported as a
+    /// [`FailureKind::CompressedSizeMismatch`].
+    ///
+    /// [`FailureKind::CompressedSizeMismatch`]: crate::read::FailureKind::CompressedSizeMismatch
+    TrustDecoder,
+}
+
+impl SizeSource {
+    // Returns the compressed size used to bound an entry's data, given its local file header.
+    pub(crate) fn compressed_size(self, entry: &ZipEntry, header: &LocalFileHeader) -> u64 {
+        match self {
+            SizeSource::LocalHeader if !header.flags.data_descriptor && header.compressed_size != u32::MAX => {
+                header.compressed_size.into()
+            }
+            _ => entry.compressed_size(),
+        }
+    }
+}
+
+/// How entries whose filenames would escape an extraction directory are handled when a ZIP file is read, as set via
+/// [`ReaderOptions::path_policy()`].
+///
+/// A filename is considered unsafe if it's absolute (including a Windows drive prefix such as `C:`, or a leading
+/// backslash), or contains a `..` component or a NUL byte. Unlike [`sanitize_entry_path()`], reserved Windows device
+/// names and overly long components aren't considered.
+///
+/// [`sanitize_entry_path()`]: crate::sanitize::sanitize_entry_path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathPolicy {
+    /// Filenames are left exactly as they're stored.
+    Preserve,
+    /// The first entry with an unsafe filename fails with [`ZipError::UnsafeEntryName`].
+    ///
+    /// [`ZipError::UnsafeEntryName`]: crate::error::ZipError::UnsafeEntryName
+    Reject,
+    /// Unsafe filenames are rewritten by stripping any leading separators or drive prefix, dropping `..` components,
+    /// and replacing NUL bytes with underscores. The stored filename remains available via
+    /// [`ZipEntry::filename_raw()`].
+    Sanitize,
+}
+
+impl PathPolicy {
+    // Apply the policy to an entry which has just been parsed, given its index amongst the ZIP file's entries.
+    pub(crate) fn apply(self, index: usize, entry: &mut ZipEntry) -> Result<()> {
+        if self == PathPolicy::Preserve || !crate::sanitize::is_unsafe_name(&entry.filename) {
+            return Ok(());
+        }
+
+        match self {
+            PathPolicy::Reject => Err(ZipError::UnsafeEntryName { index, name: entry.filename().to_owned() }),
+            _ => {
+                let sanitized = crate::sanitize::sanitize_name(&entry.filename);
+                entry.filename_raw = Some(std::mem::replace(&mut entry.filename, sanitized.into()));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How the end of central directory header is searched for when a ZIP file is opened by the seek, fs, or mem
+/// readers, as set via [`ReaderOptions::eocd_search()`].
+///
+/// The header is searched for backwards from the end of the ZIP file, as it's followed by the ZIP file's comment (of up
+/// to 65535 bytes) and possibly by other appended data. Each read made whilst searching also covers the 20 bytes which
+/// precede the bytes searched, so that a ZIP64 end of central directory locator is found without a further read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchStrategy {
+    /// Assume the ZIP file has no comment or appended data, so that the header occupies its last 22 bytes.
+    ///
+    /// Only a single read is made, and as no other candidate is possible, the central directory described by the
+    /// header isn't checked before being parsed. Opening a ZIP file with a comment fails.
+    Exact,
+    /// Read the provided number of bytes from the end of the ZIP file at once, and search backwards within them.
+    Windowed(usize),
+    /// Read backwards from the end of the ZIP file `step` bytes at a time, until the header is found or `max` bytes
+    /// have been searched.
+    Incremental { step: usize, max: usize },
+}
+
+impl SearchStrategy {
+    /// The window which covers the longest possible comment, used by default.
+    pub const MAX_WINDOW: usize = u16::MAX as usize + 22;
+
+    // Returns the number of bytes searched by each read, and the number of bytes from the end searched at most.
+    pub(crate) fn bounds(self) -> (u64, u64) {
+        match self {
+            SearchStrategy::Exact => (22, 22),
+            SearchStrategy::Windowed(window) => (window.max(1) as u64, window as u64),
+            SearchStrategy::Incremental { step, max } => (step.max(1) as u64, max as u64),
+        }
+    }
+}
+
+impl Default for SearchStrategy {
+    fn default() -> Self {
+        SearchStrategy::Windowed(Self::MAX_WINDOW)
+    }
+}
+
+/// A set of options which control how ZIP files are read.
+///
+/// The defaults mirror the behaviour of each reader's `new()` constructor, so only the options of interest need to be
+/// changed:
+/// ```
+/// # use async_zip::read::ReaderOptions;
+/// #
+/// let options = ReaderOptions::new().max_entries(10_000).allow_overlaps(false);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReaderOptions {
+    pub(crate) max_decompressed_size: Option<u64>,
+    pub(crate) max_entries: Option<usize>,
+    pub(crate) allow_overlaps: bool,
+    pub(crate) check_capabilities: bool,
+    pub(crate) lazy_entries: bool,
+    pub(crate) buffer_size: usize,
+    pub(crate) buffer_pool: Option<BufferPool>,
+    pub(crate) poll_byte_budget: usize,
+    pub(crate) size_source: SizeSource,
+    pub(crate) decompress_nested: bool,
+    pub(crate) entry_filter: Option<EntryFilter>,
+    pub(crate) skip_macos_metadata: bool,
+    pub(crate) verify_local_headers: bool,
+    pub(crate) path_policy: PathPolicy,
+    pub(crate) eocd_search: SearchStrategy,
+}
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        Self {
+            max_decompressed_size: None,
+            max_entries: None,
+            allow_overlaps: true,
+            check_capabilities: false,
+            lazy_entries: false,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            buffer_pool: None,
+            poll_byte_budget: DEFAULT_POLL_BYTE_BUDGET,
+            size_source: SizeSource::CentralDirectory,
+            decompress_nested: true,
+            entry_filter: None,
+            skip_macos_metadata: false,
+            verify_local_headers: false,
+            path_policy: PathPolicy::Preserve,
+            eocd_search: SearchStrategy::default(),
+        }
+    }
+}
+
+impl ReaderOptions {
+    /// Constructs a new set of options with their default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum uncompressed size any single entry may declare.
+    ///
+    /// Opening a ZIP file with an entry exceeding this size will fail with [`ZipError::DecompressedSizeExceeded`].
+    ///
+    /// [`ZipError::DecompressedSizeExceeded`]: crate::error::ZipError::DecompressedSizeExceeded
+    pub fn max_decompressed_size(mut self, size: u64) -> Self {
+        self.max_decompressed_size = Some(size);
+        self
+    }
+
+    /// Sets the maximum number of entries a ZIP file may contain.
+    ///
+    /// Opening a ZIP file with more entries will fail with [`ZipError::EntryLimitExceeded`] before any entries are
+    /// parsed.
+    ///
+    /// [`ZipError::EntryLimitExceeded`]: crate::error::ZipError::EntryLimitExceeded
+    pub fn max_entries(mut self, entries: usize) -> Self {
+        self.max_entries = Some(entries);
+        self
+    }
+
+    /// Sets whether or not entries' data may overlap one another.
+    ///
+    /// Overlapping entries are a common trait of ZIP bombs, but some unusual (yet legal) ZIP files also share data
+    /// between entries, so this defaults to true. When disabled, opening a ZIP file with overlapping entries will fail
+    /// with [`ZipError::OverlappingEntries`].
+    ///
+    /// [`ZipError::OverlappingEntries`]: crate::error::ZipError::OverlappingEntries
+    pub fn allow_overlaps(mut self, allow: bool) -> Self {
+        self.allow_overlaps = allow;
+        self
+    }
+
+    /// Sets whether or not each entry's requirements should be checked against this crate's capabilities.
+    ///
+    /// When enabled, opening a ZIP file containing an entry which this crate can't extract (eg. an encrypted entry,
+    /// or one requiring a newer version of the specification) will fail with [`ZipError::UnsupportedFeature`], rather
+    /// than failing at some later point whilst reading the entry.
+    ///
+    /// [`ZipError::UnsupportedFeature`]: crate::error::ZipError::UnsupportedFeature
+    pub fn check_capabilities(mut self, check: bool) -> Self {
+        self.check_capabilities = check;
+        self
+    }
+
+    /// Sets whether or not entries should be parsed lazily, rather than when the ZIP file is opened.
+    ///
+    /// When enabled, only the central directory is located when opening a ZIP file, so `entries()` will be empty and
+    /// entries must instead be listed via the reader's `cd_entries()` method. This avoids allocating every entry
+    /// upfront for very large ZIP files, but as entries are never fully collected, none of the other limits which
+    /// apply across entries are checked.
+    pub fn lazy_entries(mut self, lazy: bool) -> Self {
+        self.lazy_entries = lazy;
+        self
+    }
+
+    /// Sets the capacity of the buffer used when reading each entry's compressed data, in bytes.
+    ///
+    /// This defaults to [`DEFAULT_BUFFER_SIZE`]. Larger buffers reduce the number of reads made against the underlying
+    /// reader, at the cost of memory held for each open entry reader. A size of zero is treated as one byte.
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size.max(1);
+        self
+    }
+
+    /// Sets a pool from which each entry reader checks out its buffer when opened, returning it when dropped.
+    ///
+    /// By default, each entry reader allocates its own buffer, which is freed when it's dropped. Sharing a
+    /// [`BufferPool`] between readers instead avoids an allocation for each entry reader opened, which is most
+    /// noticeable when many entry readers are opened concurrently. The pool's buffer size takes precedence over
+    /// [`ReaderOptions::buffer_size()`].
+    pub fn buffer_pool(mut self, pool: BufferPool) -> Self {
+        self.buffer_pool = Some(pool);
+        self
+    }
+
+    /// Sets the number of decompressed bytes each entry reader may produce before voluntarily yielding to the runtime.
+    ///
+    /// Decompression happens synchronously within each poll, and as reads from an in-memory buffer (or a file whose
+    /// data is cached) rarely return [`Poll::Pending`], a highly-compressed entry could otherwise occupy a runtime's
+    /// thread for a long time whilst being read to its end. Once the budget has been spent, the reader wakes its task
+    /// and returns [`Poll::Pending`] so that other tasks get a chance to run. This defaults to
+    /// [`DEFAULT_POLL_BYTE_BUDGET`], and a budget of zero disables yielding entirely.
+    ///
+    /// See also [`ZipEntryReader::poll_byte_budget()`] to set the budget of a single entry reader.
+    ///
+    /// [`Poll::Pending`]: std::task::Poll::Pending
+    /// [`ZipEntryReader::poll_byte_budget()`]: crate::read::ZipEntryReader::poll_byte_budget
+    pub fn poll_byte_budget(mut self, budget: usize) -> Self {
+        self.poll_byte_budget = budget;
+        self
+    }
+
+    /// Sets the header from which each entry's compressed size is taken when reading its data.
+    ///
+    /// This defaults to [`SizeSource::CentralDirectory`], which recovers entries whose local file header declares the
+    /// wrong compressed size. It has no effect on the stream reader, which only ever has the local file header.
+    pub fn size_source(mut self, source: SizeSource) -> Self {
+        self.size_source = source;
+        self
+    }
+
+    /// Sets whether or not a ZIP file nested within a compressed entry may be decompressed into memory in order to open
+    /// it via the reader's `open_nested()` method.
+    ///
+    /// This defaults to true. When disabled, only ZIP files nested within Stored entries (which are read in place) may
+    /// be opened, and any other fail with [`ZipError::NestedEntryCompressed`].
+    ///
+    /// [`ZipError::NestedEntryCompressed`]: crate::error::ZipError::NestedEntryCompressed
+    pub fn decompress_nested(mut self, decompress: bool) -> Self {
+        self.decompress_nested = decompress;
+        self
+    }
+
+    /// Sets a filter which decides whether or not each entry is listed, given a view of its central directory record.
+    ///
+    /// Entries for which the filter returns false are skipped whilst the central directory is parsed, so they're never
+    /// materialised, don't appear in `entries()`, and aren't subject to any of the other limits or checks. The number
+    /// of entries including those filtered out is still available via each reader's `total_entries()` method.
+    /// ```
+    /// # use async_zip::read::ReaderOptions;
+    /// #
+    /// let options = ReaderOptions::new().entry_filter(|header| header.filename().ends_with(b".jpg"));
+    /// ```
+    ///
+    /// ## Note
+    /// The filter isn't applied to the lazy iterator returned by `cd_entries()`.
+    pub fn entry_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&RawEntryHeader<'_>) -> bool + Send + Sync + 'static,
+    {
+        self.entry_filter = Some(EntryFilter(Arc::new(filter)));
+        self
+    }
+
+    /// Sets whether or not metadata written by macOS (AppleDouble files, `.DS_Store` files, and anything within the
+    /// `__MACOSX/` directory) is skipped, as classified by [`ZipEntryExt::is_macos_metadata()`]. Defaults to false.
+    ///
+    /// ## Note
+    /// - As with [`ReaderOptions::entry_filter()`], skipped entries don't appear in `entries()`, and the indices
+    /// accepted by each reader (eg. by `entry_reader()`) refer to positions within that filtered list rather than
+    /// within the central directory.
+    /// - This doesn't apply to the stream reader, which has no list of entries (though each entry it reads may still
+    /// be classified), nor to the lazy iterator returned by `cd_entries()`.
+    ///
+    /// [`ZipEntryExt::is_macos_metadata()`]: crate::entry::ext::ZipEntryExt::is_macos_metadata
+    pub fn skip_macos_metadata(mut self, skip: bool) -> Self {
+        self.skip_macos_metadata = skip;
+        self
+    }
+
+    /// Sets whether or not each entry's local file header is cross-checked against its central directory record when
+    /// opening the entry for reading. Defaults to false.
+    ///
+    /// When enabled, the seek, fs, and mem readers' `entry_reader()` methods (along with their resumed, range, and
+    /// owned variants) compare the filename, compression method, and general purpose flags of both headers, as well as
+    /// the CRC32 value and sizes unless these are deferred to a data descriptor, and fail with
+    /// [`ZipError::HeaderMismatch`] on any divergence. Otherwise, the local file header is only skipped, and the
+    /// central directory is trusted exclusively.
+    ///
+    /// [`ZipError::HeaderMismatch`]: crate::error::ZipError::HeaderMismatch
+    pub fn verify_local_headers(mut self, verify: bool) -> Self {
+        self.verify_local_headers = verify;
+        self
+    }
+
+    /// Sets how entries whose filenames would escape an extraction directory are handled.
+    ///
+    /// This defaults to [`PathPolicy::Preserve`]. The policy is applied to each entry as it's parsed, so under
+    /// [`PathPolicy::Reject`] the seek, fs, and mem readers fail to open a ZIP file containing any such entry, whereas
+    /// the stream reader fails upon reaching its local file header.
+    pub fn path_policy(mut self, policy: PathPolicy) -> Self {
+        self.path_policy = policy;
+        self
+    }
+
+    /// Sets how the end of central directory header is searched for when opening a ZIP file.
+    ///
+    /// This defaults to a [`SearchStrategy::Windowed`] search of the last [`SearchStrategy::MAX_WINDOW`] bytes, which
+    /// covers the longest possible comment in a single read. Where every read is costly (eg. a reader backed by HTTP
+    /// range requests), [`SearchStrategy::Exact`] opens a ZIP file without a comment with one read for the header and
+    /// another for the central directory. If the header isn't found, opening fails with [`ZipError::EocdNotFound`].
+    ///
+    /// This has no effect on the stream reader, which never reads the central directory.
+    pub fn eocd_search(mut self, strategy: SearchStrategy) -> Self {
+        self.eocd_search = strategy;
+        self
+    }
+
+    // Returns where each entry reader's buffer is taken from.
+    pub(crate) fn buffer_source(&self) -> BufferSource {
+        match &self.buffer_pool {
+            Some(pool) => BufferSource::Pool(pool.clone()),
+            None => BufferSource::Allocate(self.buffer_size),
+        }
+    }
+}
+
+/// A filter set via [`ReaderOptions::entry_filter()`].
+#[derive(Clone)]
+pub(crate) struct EntryFilter(Arc<dyn Fn(&RawEntryHeader<'_>) -> bool + Send + Sync>);
+
+impl EntryFilter {
+    pub(crate) fn matches(&self, header: &RawEntryHeader<'_>) -> bool {
+        (self.0)(header)
+    }
+}
+
+impl std::fmt::Debug for EntryFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EntryFilter")
+    }
+}
+
+/// A lightweight view of an entry's central directory record, as passed to [`ReaderOptions::entry_filter()`].
+#[derive(Debug, Clone, Copy)]
+pub struct RawEntryHeader<'a> {
+    pub(crate) filename: &'a [u8],
+    pub(crate) compression: u16,
+    pub(crate) compressed_size: u32,
+    pub(crate) uncompressed_size: u32,
+}
+
+impl<'a> RawEntryHeader<'a> {
+    /// Returns the entry's filename as raw bytes, which aren't guaranteed to be valid UTF-8.
+    pub fn filename(&self) -> &'a [u8] {
+        self.filename
+    }
+
+    /// Returns the entry's compression method.
+    pub fn compression(&self) -> Compression {
+        Compression::from(self.compression)
+    }
+
+    /// Returns the entry's compressed size.
+    pub fn compressed_size(&self) -> u32 {
+        self.compressed_size
+    }
+
+    /// Returns the entry's uncompressed size.
+    pub fn uncompressed_size(&self) -> u32 {
+        self.uncompressed_size
+    }
+}
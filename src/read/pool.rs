@@ -0,0 +1,116 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module which provides a pool of buffers shared between entry readers.
+//!
+//! Each entry reader places a buffer between the underlying reader and its decompressor, which by default is allocated
+//! when the entry reader is opened and freed when it's dropped. When opening many entry readers (eg. a service
+//! extracting entries from many ZIP files concurrently), a [`BufferPool`] set via
+//! [`ReaderOptions::buffer_pool()`] lets them reuse those buffers instead:
+//! ```
+//! # use async_zip::read::pool::BufferPool;
+//! # use async_zip::read::ReaderOptions;
+//! #
+//! let pool = BufferPool::new(64 * 1024, 32);
+//! let options = ReaderOptions::new().buffer_pool(pool.clone());
+//! ```
+//!
+//! [`ReaderOptions::buffer_pool()`]: crate::read::ReaderOptions::buffer_pool
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A pool of fixed-size buffers which entry readers check out when opened and return when dropped.
+///
+/// A pool is cheaply cloneable, with every clone sharing the same buffers, so may be shared between any number of
+/// readers and tasks. Buffers are allocated on demand whenever none are available, so checking one out never waits,
+/// but at most `capacity` idle buffers are retained and any returned beyond that are freed.
+#[derive(Clone)]
+pub struct BufferPool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    buffer_size: usize,
+    capacity: usize,
+    buffers: Mutex<Vec<Box<[u8]>>>,
+}
+
+impl BufferPool {
+    /// Constructs a new, empty pool of buffers of `buffer_size` bytes, which retains at most `capacity` idle buffers.
+    ///
+    /// A buffer size of zero is treated as one byte.
+    pub fn new(buffer_size: usize, capacity: usize) -> Self {
+        let buffers = Mutex::new(Vec::with_capacity(capacity));
+        Self { inner: Arc::new(PoolInner { buffer_size: buffer_size.max(1), capacity, buffers }) }
+    }
+
+    /// Returns the size of each buffer within this pool, in bytes.
+    pub fn buffer_size(&self) -> usize {
+        self.inner.buffer_size
+    }
+
+    /// Returns the maximum number of idle buffers this pool retains.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+
+    /// Returns the number of idle buffers currently held by this pool.
+    pub fn available(&self) -> usize {
+        self.inner.lock().len()
+    }
+
+    // Check out an idle buffer, or allocate a new one if there are none.
+    pub(crate) fn checkout(&self) -> PooledBuffer {
+        let buffer = self.inner.lock().pop();
+        let buffer = buffer.unwrap_or_else(|| vec![0; self.inner.buffer_size].into_boxed_slice());
+
+        PooledBuffer { buffer, pool: self.inner.clone() }
+    }
+}
+
+impl PoolInner {
+    // A panic whilst the lock is held can't leave the list of buffers in an invalid state, so poisoning is ignored.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Box<[u8]>>> {
+        self.buffers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl std::fmt::Debug for BufferPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("buffer_size", &self.buffer_size())
+            .field("capacity", &self.capacity())
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+/// A buffer checked out from a [`BufferPool`], which is returned to it when dropped.
+pub(crate) struct PooledBuffer {
+    buffer: Box<[u8]>,
+    pool: Arc<PoolInner>,
+}
+
+impl std::ops::Deref for PooledBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl std::ops::DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        let mut buffers = self.pool.lock();
+
+        if buffers.len() < self.pool.capacity {
+            buffers.push(std::mem::take(&mut self.buffer));
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::entry::ZipEntry;
+use crate::error::{Result, ZipError};
+use crate::read::buffered::BufferSource;
+use crate::read::{CompressionReader, OwnedReader, PrependReader};
+
+use std::borrow::Cow;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, ReadBuf, Take};
+
+/// A reader over a range of an entry's decompressed data (eg. to serve a HTTP Range request).
+///
+/// Unlike [`ZipEntryReader`], no CRC32 value is computed as only part of the entry's data is read. Reads fail with
+/// [`std::io::ErrorKind::UnexpectedEof`] if the entry's data ends before the range does.
+///
+/// See the `entry_reader_range()` method of the seek, fs, and mem readers.
+///
+/// [`ZipEntryReader`]: crate::read::ZipEntryReader
+pub struct ZipEntryRangeReader<'a, R: AsyncRead + Unpin> {
+    entry: Cow<'a, ZipEntry>,
+    reader: Take<CompressionReader<PrependReader<'a, R>>>,
+    start: u64,
+}
+
+impl<'a, R: AsyncRead + Unpin> ZipEntryRangeReader<'a, R> {
+    /// Returns a reference to the inner entry's data.
+    pub fn entry(&self) -> &ZipEntry {
+        &self.entry
+    }
+
+    /// Returns the offset within the entry's decompressed data at which the range starts.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns the number of bytes remaining within the range.
+    pub fn remaining(&self) -> u64 {
+        self.reader.limit()
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin> AsyncRead for ZipEntryRangeReader<'a, R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let remaining = self.reader.limit();
+        let prev_len = b.filled().len();
+
+        match Pin::new(&mut self.reader).poll_read(c, b) {
+            Poll::Ready(Ok(())) if remaining > 0 && b.remaining() > 0 && b.filled().len() == prev_len => {
+                Poll::Ready(Err(tokio::io::ErrorKind::UnexpectedEof.into()))
+            }
+            poll => poll,
+        }
+    }
+}
+
+// Construct a reader over the decompressed bytes `start..end` of an entry (or `start..` if no end is provided), given
+// an inner reader positioned at the start of the entry's `compressed_size` bytes of compressed data.
+pub(crate) async fn range_entry_reader<'a, R: AsyncRead + AsyncSeek + Unpin>(
+    entry: Cow<'a, ZipEntry>,
+    reader: OwnedReader<'a, R>,
+    compressed_size: u64,
+    start: u64,
+    end: Option<u64>,
+    buffer: BufferSource,
+) -> Result<ZipEntryRangeReader<'a, R>> {
+    let size = u64::from(entry.uncompressed_size());
+    let end = end.unwrap_or(size);
+
+    if start > end || end > size {
+        return Err(ZipError::RangeOutOfBounds { start, end, size });
+    }
+
+    let reader = crate::read::skip_entry_data(&entry, reader, compressed_size, start, buffer).await?;
+    let reader = reader.ok_or(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+
+    Ok(ZipEntryRangeReader { entry, reader: reader.take(end - start), start })
+}
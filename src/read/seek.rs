@@ -25,142 +25,1022 @@
 //! # }
 //! ```
 
-use crate::error::{Result, ZipError};
-use crate::read::{CompressionReader, OwnedReader, PrependReader, ZipEntry, ZipEntryReader, ZipEntryMeta};
-use crate::spec::compression::Compression;
+use crate::entry::ext::ZipEntryExt;
+use crate::error::{Feature, Result, ZipError};
+use crate::read::buffered::BufferSource;
+use crate::read::options::{EntryFilter, PathPolicy, RawEntryHeader, SearchStrategy};
+use crate::read::{ArchiveMetadata, NestedReader};
+use crate::read::{CompressionReader, EntryIndex, OwnedReader, OwnedZipEntryReader, PrependReader, ReadCheckpoint};
+use crate::read::{ReaderOptions, VerificationReport, ZipEntry, ZipEntryMeta, ZipEntryRangeReader, ZipEntryReader};
 use crate::spec::attribute::AttributeCompatibility;
-use crate::spec::header::{CentralDirectoryHeader, EndOfCentralDirectoryHeader, LocalFileHeader};
+use crate::spec::compression::Compression;
+use crate::spec::extra_field::ExtraField;
+use crate::spec::header::{CentralDirectoryHeader, EndOfCentralDirectoryHeader};
+use crate::spec::header::{Zip64EndOfCentralDirectoryLocator, Zip64EndOfCentralDirectoryRecord};
+use crate::spec::version::{ZipVersion, SPEC_VERSION_MADE_BY};
 
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, BufReader, Take};
 
-use async_io_utilities::AsyncDelimiterReader;
+use std::borrow::Cow;
+use std::future::Future;
 use std::io::SeekFrom;
 
 /// A reader which acts over a seekable source.
 pub struct ZipFileReader<R: AsyncRead + AsyncSeek + Unpin> {
     pub(crate) reader: R,
     pub(crate) entries: Vec<(ZipEntry, ZipEntryMeta)>,
-    pub(crate) comment: Option<String>,
+    pub(crate) index: EntryIndex,
+    pub(crate) metadata: ArchiveMetadata,
+    pub(crate) options: ReaderOptions,
+    pub(crate) location: CentralDirectoryLocation,
 }
 
 impl<R: AsyncRead + AsyncSeek + Unpin> ZipFileReader<R> {
     /// Constructs a new ZIP file reader from a reader which implements [`AsyncRead`] and [`AsyncSeek`].
-    pub async fn new(mut reader: R) -> Result<ZipFileReader<R>> {
-        let (entries, comment) = read_cd(&mut reader).await?;
-        Ok(ZipFileReader { reader, entries, comment })
+    pub async fn new(reader: R) -> Result<ZipFileReader<R>> {
+        Self::with_options(reader, ReaderOptions::default()).await
+    }
+
+    /// Constructs a new ZIP file reader from a reader which implements [`AsyncRead`] and [`AsyncSeek`], using the
+    /// provided options.
+    pub async fn with_options(reader: R, options: ReaderOptions) -> Result<ZipFileReader<R>> {
+        Self::with_disks(reader, options, &[]).await
+    }
+
+    // Constructs a reader over a ZIP file which may span multiple disks, given the offset at which each disk begins.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "open_archive", level = "debug", skip_all, fields(reader = "seek"))
+    )]
+    pub(crate) async fn with_disks(mut reader: R, options: ReaderOptions, disks: &[u64]) -> Result<ZipFileReader<R>> {
+        let (entries, metadata, location) = read_cd_with_disks(&mut reader, &options, disks).await?;
+        Ok(ZipFileReader { reader, entries, index: EntryIndex::default(), metadata, options, location })
+    }
+
+    /// Returns the number of bytes which precede the ZIP data (eg. the stub of a self-extracting archive).
+    ///
+    /// ## Note
+    /// When entries are listed lazily, only a preamble which the ZIP file's recorded offsets don't account for is
+    /// detected.
+    pub fn preamble_length(&self) -> u64 {
+        self.location.preamble_length
+    }
+
+    /// Returns the number of entries declared by the ZIP file, including any skipped by an entry filter.
+    pub fn total_entries(&self) -> usize {
+        self.location.num_of_entries as usize
+    }
+
+    /// Returns a lazy iterator over the entries of the ZIP file's central directory.
+    ///
+    /// This is primarily intended for use alongside [`ReaderOptions::lazy_entries()`].
+    pub async fn cd_entries(&mut self) -> Result<CentralDirectoryEntries<&mut R>> {
+        CentralDirectoryEntries::from_raw(&mut self.reader, self.location, self.options.path_policy).await
+    }
+
+    /// Verifies the integrity of every entry within the ZIP file.
+    ///
+    /// Each entry is decompressed in full and its CRC32 value and sizes are checked against the local file header,
+    /// data descriptor, and central directory. Failures are collected into the returned report rather than aborting
+    /// verification, unless `stop_early` is set.
+    ///
+    /// ## Note
+    /// This isn't supported when entries are listed lazily.
+    pub async fn verify(&mut self, stop_early: bool) -> Result<VerificationReport> {
+        crate::read::verify::verify_entries(
+            &mut self.reader,
+            &self.entries,
+            self.location,
+            &self.options,
+            true,
+            stop_early,
+        )
+        .await
+    }
+
+    /// Verifies the structure of the ZIP file without decompressing any entry data.
+    ///
+    /// This only checks that each local file header agrees with the central directory, and that the number of
+    /// central directory records matches the number declared.
+    pub async fn verify_structure_only(&mut self, stop_early: bool) -> Result<VerificationReport> {
+        crate::read::verify::verify_entries(
+            &mut self.reader,
+            &self.entries,
+            self.location,
+            &self.options,
+            false,
+            stop_early,
+        )
+        .await
+    }
+
+    /// Returns the mimetype of an OpenDocument or EPUB file, if its first entry follows their convention.
+    ///
+    /// The first entry must be named `mimetype` and be Stored without encryption, a data descriptor, or extra fields,
+    /// with its local file header at the very start of the ZIP file (as written by
+    /// [`ZipFileWriter::write_mimetype_entry()`]). Only that entry's local file header and data are read, and `None`
+    /// is returned if the convention isn't followed (or entries are listed lazily).
+    ///
+    /// [`ZipFileWriter::write_mimetype_entry()`]: crate::write::ZipFileWriter::write_mimetype_entry
+    pub async fn sniff_mimetype(&mut self) -> Result<Option<String>> {
+        crate::read::sniff_mimetype(&mut self.reader, &self.entries).await
     }
 
     crate::read::reader_entry_impl!();
+    crate::read::extract::reader_extract_impl!();
 
     /// Opens an entry at the provided index for reading.
+    ///
+    /// Encrypted entries fail with [`ZipError::PasswordRequired`], and must instead be opened via
+    /// [`Self::entry_reader_with_password()`].
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "read_entry",
+            level = "debug",
+            skip(self),
+            fields(
+                filename = tracing::field::Empty,
+                compression = tracing::field::Empty,
+                compressed_size = tracing::field::Empty,
+                uncompressed_size = tracing::field::Empty
+            )
+        )
+    )]
     pub async fn entry_reader(&mut self, index: usize) -> Result<ZipEntryReader<'_, R>> {
         let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        crate::trace::record_entry!(&entry.0);
+        crate::read::check_readable(&entry.0, &entry.1)?;
 
         self.reader.seek(SeekFrom::Start(entry.1.file_offset.unwrap() as u64 + 4)).await?;
 
-        let header = LocalFileHeader::from_reader(&mut self.reader).await?;
-        let data_offset = (header.file_name_length + header.extra_field_length) as i64;
-        self.reader.seek(SeekFrom::Current(data_offset)).await?;
+        let header = crate::read::read_local_header(&mut self.reader, index, &entry.0, &entry.1, &self.options);
+        let (header, local_extra_fields) = header.await?;
+
+        let reader = OwnedReader::Borrow(&mut self.reader);
+        let reader = PrependReader::Normal(reader);
+        let compressed_size = Some(self.options.size_source.compressed_size(&entry.0, &header));
+        let buffer = self.options.buffer_source();
+        let reader = CompressionReader::from_reader(&entry.0, reader, compressed_size, buffer)?;
+
+        let mut reader = ZipEntryReader::from_raw(
+            Cow::Borrowed(&entry.0),
+            Cow::Borrowed(&entry.1),
+            reader,
+            entry.1.general_purpose_flag.data_descriptor,
+        );
+        reader.poll_byte_budget = self.options.poll_byte_budget;
+        reader.local_extra_fields = local_extra_fields;
 
+        Ok(reader)
+    }
+
+    /// Opens an entry at the provided index for reading, decrypting its data with the provided password.
+    ///
+    /// Entries encrypted with either the traditional PKWARE scheme (ZipCrypto) or (with the `aes` feature enabled)
+    /// WinZip's AES encryption are decrypted as they're read. A wrong password is detected before any data is read,
+    /// failing with [`ZipError::WrongPassword`] (although, as ZipCrypto only holds a single check byte, roughly one in
+    /// every 256 wrong passwords instead fail the final CRC32 check). AES encrypted data is also authenticated once
+    /// fully read, failing with [`ZipError::AuthenticationFailed`] if it has been corrupted or tampered with. The
+    /// password is ignored for entries which aren't encrypted.
+    pub async fn entry_reader_with_password(&mut self, index: usize, password: &[u8]) -> Result<ZipEntryReader<'_, R>> {
         let reader = OwnedReader::Borrow(&mut self.reader);
+        crate::read::password_entry_reader(&self.entries, index, reader, &self.options, password).await
+    }
+
+    /// Opens an entry listed lazily via [`Self::cd_entries()`] for reading.
+    ///
+    /// This allows an entry found via [`CentralDirectoryEntries::find()`] to be read without every entry having been
+    /// held in memory (see [`ReaderOptions::lazy_entries()`]). Encrypted entries fail with
+    /// [`ZipError::PasswordRequired`].
+    pub async fn reader_with_entry<'b>(
+        &'b mut self,
+        entry: &'b CentralDirectoryEntry,
+    ) -> Result<ZipEntryReader<'b, R>> {
+        let reader = OwnedReader::Borrow(&mut self.reader);
+        crate::read::lazy_entry_reader(entry, reader, &self.options).await
+    }
+
+    /// Opens an entry at the provided index for reading, resuming from a checkpoint reached by an earlier reader.
+    ///
+    /// The bytes already consumed are skipped (by seeking for Stored entries, or otherwise by decompressing them
+    /// again), and the returned reader's CRC32 value continues from the checkpoint's, so the final CRC32 check still
+    /// covers the entry's data in full.
+    pub async fn entry_reader_resumed(
+        &mut self,
+        index: usize,
+        checkpoint: ReadCheckpoint,
+    ) -> Result<ZipEntryReader<'_, R>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        crate::read::check_readable(&entry.0, &entry.1)?;
+
+        self.reader.seek(SeekFrom::Start(entry.1.file_offset.unwrap() as u64 + 4)).await?;
+
+        let header = crate::read::read_local_header(&mut self.reader, index, &entry.0, &entry.1, &self.options);
+        let (header, local_extra_fields) = header.await?;
+
+        let reader = OwnedReader::Borrow(&mut self.reader);
+        let compressed_size = self.options.size_source.compressed_size(&entry.0, &header);
+        let (entry, meta) = (Cow::Borrowed(&entry.0), Cow::Borrowed(&entry.1));
+        let buffer = self.options.buffer_source();
+
+        let reader = crate::read::resume_entry_reader(entry, meta, reader, compressed_size, checkpoint, buffer);
+        let mut reader = reader.await?;
+        reader.poll_byte_budget = self.options.poll_byte_budget;
+        reader.local_extra_fields = local_extra_fields;
+
+        Ok(reader)
+    }
+
+    /// Opens an entry at the provided index for reading a range of its decompressed data.
+    ///
+    /// The range spans from `start` up to (but excluding) `end`, or to the end of the entry's data if no end is
+    /// provided. For Stored entries, the reader seeks directly to the start of the range; any other entry must have
+    /// the bytes preceding it decompressed and discarded. Ranges beyond the entry's uncompressed size fail with
+    /// [`ZipError::RangeOutOfBounds`].
+    pub async fn entry_reader_range(
+        &mut self,
+        index: usize,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<ZipEntryRangeReader<'_, R>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        crate::read::check_readable(&entry.0, &entry.1)?;
+
+        self.reader.seek(SeekFrom::Start(entry.1.file_offset.unwrap() as u64 + 4)).await?;
+        let header = crate::read::read_local_header(&mut self.reader, index, &entry.0, &entry.1, &self.options);
+        let (header, _) = header.await?;
+
+        let reader = OwnedReader::Borrow(&mut self.reader);
+        let buffer = self.options.buffer_source();
+        let compressed_size = self.options.size_source.compressed_size(&entry.0, &header);
+        let entry = Cow::Borrowed(&entry.0);
+        crate::read::range::range_entry_reader(entry, reader, compressed_size, start, end, buffer).await
+    }
+
+    /// Copies the data of every entry into a writer opened for it, processing entries in the order their data is
+    /// physically stored (see [`Self::entries_by_offset()`]) so that the underlying reader is read sequentially.
+    ///
+    /// `open` is called with each entry's index, and returns the writer its data should be copied into, or None for
+    /// it to be skipped (eg. for directories). The result of each entry which wasn't skipped is returned alongside its
+    /// index, in the order processed, holding either the number of bytes copied (once its size and CRC32 value have
+    /// been checked) or the error which occurred. A failure doesn't prevent subsequent entries from being processed.
+    /// ```no_run
+    /// # use async_zip::read::seek::ZipFileReader;
+    /// # use async_zip::error::ZipError;
+    /// # use tokio::fs::File;
+    /// #
+    /// # async fn run() -> Result<(), ZipError> {
+    /// let mut zip = ZipFileReader::new(File::open("./Archive.zip").await?).await?;
+    ///
+    /// let results = zip.extract_all(|_, entry| {
+    ///     let path = async_zip::sanitize::sanitize_entry_path(entry.filename()).ok();
+    ///     let path = path.filter(|_| !entry.filename().ends_with('/'));
+    ///
+    ///     async move {
+    ///         let path = match path {
+    ///             Some(path) => std::path::Path::new("./output").join(path),
+    ///             None => return Ok(None),
+    ///         };
+    ///         Ok::<_, ZipError>(Some(File::create(path).await?))
+    ///     }
+    /// });
+    ///
+    /// for (_, result) in results.await {
+    ///     result?;
+    /// }
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub async fn extract_all<F, Fut, W>(&mut self, mut open: F) -> Vec<(usize, Result<u64>)>
+    where
+        F: FnMut(usize, &ZipEntry) -> Fut,
+        Fut: Future<Output = Result<Option<W>>>,
+        W: AsyncWrite + Unpin,
+    {
+        let order: Vec<usize> = self.entries_by_offset().into_iter().map(|(index, _)| index).collect();
+        let mut results = Vec::with_capacity(order.len());
+
+        for index in order {
+            let mut writer = match open(index, &self.entries[index].0).await {
+                Ok(Some(writer)) => writer,
+                Ok(None) => continue,
+                Err(err) => {
+                    results.push((index, Err(err)));
+                    continue;
+                }
+            };
+
+            let buffer_size = self.options.buffer_size;
+            let result = match self.entry_reader(index).await {
+                Ok(reader) => reader.copy_to_end_crc(&mut writer, buffer_size).await,
+                Err(err) => Err(err),
+            };
+
+            results.push((index, result));
+        }
+
+        results
+    }
+
+    /// Opens the ZIP file nested within the entry at the provided index (eg. a JAR within a fat JAR) as a new reader.
+    ///
+    /// A Stored entry's data is read in place via a window over this reader, so none of it is copied. Otherwise, the
+    /// entry is decompressed into memory in full, unless disabled via [`ReaderOptions::decompress_nested()`]. The
+    /// nested reader is opened with the same options as this one.
+    pub async fn open_nested(&mut self, index: usize) -> Result<ZipFileReader<NestedReader<&mut R>>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        let options = self.options.clone();
+
+        if !crate::read::nested::in_place(&entry.0, &options)? {
+            let data = self.entry_reader(index).await?.read_to_end_crc().await?;
+            return ZipFileReader::with_options(NestedReader::buffered(data), options).await;
+        }
+
+        self.reader.seek(SeekFrom::Start(entry.1.file_offset.unwrap() as u64 + 4)).await?;
+        let (header, _) = crate::read::skip_local_header(&mut self.reader).await?;
+
+        let start = self.reader.stream_position().await?;
+        let length = self.options.size_source.compressed_size(&entry.0, &header);
+        let reader = NestedReader::window(&mut self.reader, start, length).await?;
+
+        ZipFileReader::with_options(reader, options).await
+    }
+
+    /// Opens a reader over the raw (still compressed, and possibly encrypted) data of the entry at the provided index.
+    ///
+    /// The reader yields exactly the entry's compressed size as recorded within the central directory (which holds
+    /// the sizes of entries followed by a data descriptor too), so suits copying the entry into another ZIP file via
+    /// [`ZipFileWriter::write_entry_raw()`] without decompressing it.
+    ///
+    /// [`ZipFileWriter::write_entry_raw()`]: crate::write::ZipFileWriter::write_entry_raw
+    pub async fn raw_entry_reader(&mut self, index: usize) -> Result<Take<&mut R>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+
+        self.reader.seek(SeekFrom::Start(entry.1.file_offset.unwrap() as u64 + 4)).await?;
+        crate::read::skip_local_header(&mut self.reader).await?;
+
+        Ok((&mut self.reader).take(entry.0.compressed_size()))
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin + Clone> ZipFileReader<R> {
+    /// Opens an entry at the provided index for reading via a clone of the underlying reader, without borrowing from
+    /// this reader.
+    ///
+    /// Each clone must seek independently of the others (eg. a handle which tracks its own position over shared data),
+    /// so that any number of entries (including the same entry) may be read concurrently from spawned tasks. See
+    /// [`fs::ZipFileReader::entry_reader_owned()`] for a reader which opens a new file handle per entry instead.
+    ///
+    /// [`fs::ZipFileReader::entry_reader_owned()`]: crate::read::fs::ZipFileReader::entry_reader_owned
+    pub async fn entry_reader_owned(&self, index: usize) -> Result<OwnedZipEntryReader<R>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        crate::read::check_readable(&entry.0, &entry.1)?;
+
+        let mut reader = self.reader.clone();
+        reader.seek(SeekFrom::Start(entry.1.file_offset.unwrap() + 4)).await?;
+
+        let header = crate::read::read_local_header(&mut reader, index, &entry.0, &entry.1, &self.options);
+        let (header, local_extra_fields) = header.await?;
+
+        let reader = OwnedReader::Owned(reader);
         let reader = PrependReader::Normal(reader);
-        let reader = CompressionReader::from_reader(&entry.0.compression(), reader, Some(entry.0.compressed_size()).map(u32::into))?;
+        let compressed_size = Some(self.options.size_source.compressed_size(&entry.0, &header));
+        let buffer = self.options.buffer_source();
+        let reader = CompressionReader::from_reader(&entry.0, reader, compressed_size, buffer)?;
+
+        let data_descriptor = entry.1.general_purpose_flag.data_descriptor;
+        let (entry, meta) = (Cow::Owned(entry.0.clone()), Cow::Owned(entry.1.clone()));
+        let mut reader = ZipEntryReader::from_raw(entry, meta, reader, data_descriptor);
+        reader.poll_byte_budget = self.options.poll_byte_budget;
+        reader.local_extra_fields = local_extra_fields;
 
-        Ok(ZipEntryReader::from_raw(&entry.0, &entry.1, reader, entry.1.general_purpose_flag.data_descriptor))
+        Ok(reader)
     }
 }
 
+// Locate the central directory and, unless entries are to be listed lazily, parse all of its entries.
 pub(crate) async fn read_cd<R: AsyncRead + AsyncSeek + Unpin>(
     reader: &mut R,
-) -> Result<(Vec<(ZipEntry, ZipEntryMeta)>, Option<String>)> {
-    const MAX_ENDING_LENGTH: u64 = u16::MAX as u64 + 22;
+    options: &ReaderOptions,
+) -> Result<(Vec<(ZipEntry, ZipEntryMeta)>, ArchiveMetadata, CentralDirectoryLocation)> {
+    read_cd_with_disks(reader, options, &[]).await
+}
+
+// As read_cd(), but with the offset at which each disk begins within the reader when the ZIP file spans multiple disks.
+//
+// An empty slice of disks denotes a ZIP file which isn't spanned.
+pub(crate) async fn read_cd_with_disks<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    options: &ReaderOptions,
+    disks: &[u64],
+) -> Result<(Vec<(ZipEntry, ZipEntryMeta)>, ArchiveMetadata, CentralDirectoryLocation)> {
+    let (mut location, mut metadata) = locate_cd(reader, options, disks).await?;
+
+    if options.lazy_entries {
+        metadata.preamble_length = location.preamble_length;
+        return Ok((Vec::new(), metadata, location));
+    }
 
+    reader.seek(SeekFrom::Start(location.offset)).await?;
+    // A central directory record is at least 46 bytes long, so the declared number of entries can't be trusted beyond
+    // that which the central directory could hold when pre-allocating.
+    let capacity = location.num_of_entries.min(location.size / 46);
+    let mut entries = Vec::with_capacity(usize::try_from(capacity).unwrap_or(usize::MAX));
+
+    // The central directory is read through a buffer sized to hold it in full (up to the configured buffer size), so
+    // that a small central directory is read at once rather than record by record.
+    let capacity = usize::try_from(location.size).unwrap_or(usize::MAX).clamp(1, options.buffer_size);
+    let mut cd_reader = BufReader::with_capacity(capacity, &mut *reader);
+    let mut offset = location.offset;
+    let filter = options.entry_filter.as_ref();
+
+    for _ in 0..location.num_of_entries {
+        let (mut entry, mut meta) = match read_cd_entry(&mut cd_reader, &mut offset, filter).await? {
+            Some(entry) => entry,
+            None => continue,
+        };
+        if options.skip_macos_metadata && entry.is_macos_metadata() {
+            continue;
+        }
+        options.path_policy.apply(entries.len(), &mut entry)?;
+
+        // Offsets recorded relative to the start of the ZIP data (or the entry's disk) rather than the start of the
+        // file.
+        if location.adjustment > 0 || !disks.is_empty() {
+            let disk_offset = disk_offset(disks, meta.disk_start)?;
+            let offset = meta.file_offset.unwrap().checked_add(disk_offset);
+            let offset = offset.and_then(|offset| offset.checked_add(location.adjustment));
+            meta.file_offset = Some(offset.ok_or(ZipError::OffsetOverflow)?);
+        }
+
+        crate::trace::debug!(record = entries.len(), filename = entry.filename(), "parsed central directory record");
+        entries.push((entry, meta));
+    }
+
+    validate_entries(&entries, location.offset, options)?;
+
+    // The data of a symbolic link entry is its (small) target path, so it's read upfront and stored on the entry.
+    for (entry, meta) in entries.iter_mut() {
+        if entry.is_symlink() && entry.uncompressed_size <= MAX_SYMLINK_TARGET_LENGTH {
+            entry.symlink_target = read_symlink_target(&mut *reader, entry, meta, options.buffer_source()).await.ok();
+        }
+    }
+
+    let first_entry = entries.iter().map(|(_, meta)| meta.file_offset.unwrap()).min();
+    location.preamble_length = first_entry.unwrap_or(location.offset);
+
+    let zip64 = |field: &ExtraField| field.header_id() == crate::spec::extra_field::ZIP64_EXTENDED_INFORMATION;
+    metadata.zip64_entries = entries.iter().any(|(entry, _)| entry.extra_fields().iter().any(zip64));
+    metadata.preamble_length = location.preamble_length;
+
+    Ok((entries, metadata, location))
+}
+
+// The longest symbolic link target which is read upfront, matching the usual PATH_MAX on Linux.
+const MAX_SYMLINK_TARGET_LENGTH: u64 = 4096;
+
+// Read the data of a symbolic link entry as its target path.
+async fn read_symlink_target<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    entry: &ZipEntry,
+    meta: &ZipEntryMeta,
+    buffer: BufferSource,
+) -> Result<String> {
+    reader.seek(SeekFrom::Start(meta.file_offset.unwrap() + 4)).await?;
+    crate::read::skip_local_header(reader).await?;
+
+    let reader = PrependReader::Normal(OwnedReader::Borrow(reader));
+    let compressed_size = Some(entry.compressed_size);
+    let reader = CompressionReader::from_reader(entry, reader, compressed_size, buffer)?;
+
+    let mut target = String::new();
+    reader.take(MAX_SYMLINK_TARGET_LENGTH).read_to_string(&mut target).await?;
+
+    Ok(target)
+}
+
+/// The position of a ZIP file's central directory, as derived from its end of central directory header.
+#[derive(Clone, Copy)]
+pub(crate) struct CentralDirectoryLocation {
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+    pub(crate) num_of_entries: u64,
+    pub(crate) adjustment: u64,
+    pub(crate) preamble_length: u64,
+}
+
+// Locate the central directory via the end of central directory header, without parsing any of its entries.
+//
+// The EOCDH is searched for backwards from the end of the reader (as set by ReaderOptions::eocd_search()), as bytes may
+// have been appended after it (eg. by a signing tool) and its comment length may not match the bytes which actually
+// follow it. As both the comment and any such bytes may themselves contain the EOCDH's signature, each candidate is
+// only accepted once the central directory it describes is found to begin with a central directory header, and the
+// search otherwise resumes. If no candidate is accepted, the last is used regardless so that the resulting error
+// describes where parsing failed.
+pub(crate) async fn locate_cd<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    options: &ReaderOptions,
+    disks: &[u64],
+) -> Result<(CentralDirectoryLocation, ArchiveMetadata)> {
     let length = reader.seek(SeekFrom::End(0)).await?;
-    let seek_to = length.saturating_sub(MAX_ENDING_LENGTH);
-
-    reader.seek(SeekFrom::Start(seek_to)).await?;
-
-    let mut matched_offset: Option<u64> = None;
-    let mut comment = None;
-    let delimiter = crate::spec::signature::END_OF_CENTRAL_DIRECTORY.to_le_bytes();
-    let mut reader = AsyncDelimiterReader::new(reader, &delimiter);
-
-    // We need to find the last EOCDH as there's a possibility that an inner ZIP file exists with the Stored
-    // compression method, so matching that would be undesirable. For the moment, we match all EOCDHs
-    // sequentially and store the latest's offest.
-    // TODO: Seeking in reverse may be a better a approach for this - needs some testing.
-    'outer: loop {
-        'inner: loop {
-            let mut buffer = [0; async_io_utilities::SUGGESTED_BUFFER_SIZE];
-            if reader.read(&mut buffer).await? == 0 {
-                break 'inner;
+    let (step, max) = options.eocd_search.bounds();
+    let lower_bound = length.saturating_sub(max);
+
+    // The bytes read so far, which begin at `buffer_start` and extend to the end of the reader. Positions from
+    // `searched_from` onwards have been searched (or are too close to the end to hold an EOCDH).
+    let mut ending = Vec::new();
+    let mut buffer_start = length;
+    let mut searched_from = (length + 1).saturating_sub(EndOfCentralDirectoryHeader::LENGTH as u64);
+
+    // An exact search has only one possible candidate, so there's no need to check it against any other.
+    let check = options.eocd_search != SearchStrategy::Exact;
+    let signature = crate::spec::signature::END_OF_CENTRAL_DIRECTORY.to_le_bytes();
+    let mut fallback = None;
+    let mut next = length;
+
+    'search: while next > lower_bound {
+        next = next.saturating_sub(step).max(lower_bound);
+
+        // The ZIP64 end of central directory locator which may precede a candidate is read alongside it.
+        let read_from = next.saturating_sub(ZIP64_LOCATOR_LENGTH);
+        if read_from < buffer_start {
+            let mut buffer = vec![0; (buffer_start - read_from) as usize];
+            reader.seek(SeekFrom::Start(read_from)).await?;
+            reader.read_exact(&mut buffer).await?;
+
+            buffer.extend_from_slice(&ending);
+            ending = buffer;
+            buffer_start = read_from;
+        }
+
+        for eocdh_offset in (next..searched_from).rev() {
+            let index = (eocdh_offset - buffer_start) as usize;
+            if ending[index..index + 4] != signature {
+                continue;
+            }
+
+            let (eocdh, _) = EndOfCentralDirectoryHeader::from_bytes(&ending[index..])?;
+
+            // Spanned ZIP files are only supported when each disk has been provided (see the split module).
+            let spanned =
+                eocdh.disk_num != eocdh.start_cent_dir_disk || eocdh.num_of_entries != eocdh.num_of_entries_disk;
+            if spanned && disks.is_empty() {
+                fallback.get_or_insert(Err(ZipError::FeatureNotSupported("Spanned/split files")));
+                continue;
+            }
+
+            let candidate =
+                locate_candidate(reader, eocdh, eocdh_offset, disks, (&ending, buffer_start), check).await?;
+
+            if candidate.valid {
+                fallback = Some(Ok(candidate));
+                break 'search;
             }
+
+            fallback.get_or_insert(Ok(candidate));
+        }
+
+        searched_from = searched_from.min(next);
+    }
+
+    let candidate = match fallback {
+        Some(candidate) => candidate?,
+        None => {
+            crate::trace::error!(length, "no end of central directory header found");
+            return Err(ZipError::EocdNotFound { searched_bytes: length - next });
+        }
+    };
+
+    let (eocdh, num_of_entries) = (candidate.eocdh, candidate.location.num_of_entries);
+    crate::trace::debug!(
+        offset = candidate.eocdh_offset,
+        entries = num_of_entries,
+        "located end of central directory header"
+    );
+
+    if let Some(max_entries) = options.max_entries {
+        if num_of_entries > max_entries as u64 {
+            return Err(ZipError::EntryLimitExceeded(num_of_entries));
+        }
+    }
+
+    // The comment's raw bytes are kept as-is, as it's often encoded with a legacy code page rather than UTF-8. Where
+    // its length overstates the bytes remaining, only those which remain are taken.
+    let comment_start = (candidate.eocdh_offset - buffer_start) as usize + EndOfCentralDirectoryHeader::LENGTH;
+    let remaining = ending.len() - comment_start;
+    let comment_length = remaining.min(eocdh.file_comm_length.into());
+    let comment = ending[comment_start..comment_start + comment_length].to_vec();
+
+    let mut metadata = ArchiveMetadata::from_header(&eocdh, comment);
+    metadata.zip64_locator = candidate.zip64_locator;
+    if let Some(record) = &candidate.zip64_record {
+        metadata.apply_zip64(record);
+    }
+    metadata.trailing_length = (remaining - comment_length) as u64;
+
+    Ok((candidate.location, metadata))
+}
+
+// A possible end of central directory header found by locate_cd(), alongside the central directory it describes.
+struct Candidate {
+    eocdh: EndOfCentralDirectoryHeader,
+    eocdh_offset: u64,
+    location: CentralDirectoryLocation,
+    zip64_locator: bool,
+    zip64_record: Option<Zip64EndOfCentralDirectoryRecord>,
+    valid: bool,
+}
+
+// The length of a ZIP64 end of central directory locator, which immediately precedes the EOCDH when present.
+const ZIP64_LOCATOR_LENGTH: u64 = Zip64EndOfCentralDirectoryLocator::LENGTH as u64;
+
+// Derive the location of the central directory described by an EOCDH, and (if requested) check whether or not it
+// begins there. The bytes read whilst searching for the EOCDH are provided alongside the offset at which they begin.
+async fn locate_candidate<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    eocdh: EndOfCentralDirectoryHeader,
+    eocdh_offset: u64,
+    disks: &[u64],
+    (ending, ending_start): (&[u8], u64),
+    check: bool,
+) -> Result<Candidate> {
+    // A ZIP64 end of central directory locator is a fixed 20 bytes long, so it's only absent from the bytes already
+    // read when it would begin before the start of the reader.
+    let mut zip64_locator = None;
+    if let Some(locator_offset) = eocdh_offset.checked_sub(ZIP64_LOCATOR_LENGTH) {
+        let index = (locator_offset - ending_start) as usize;
+        if let Ok((locator, _)) = Zip64EndOfCentralDirectoryLocator::from_bytes(&ending[index..]) {
+            zip64_locator = Some((locator, locator_offset));
+        }
+    }
+
+    let zip64_record = match &zip64_locator {
+        Some((locator, locator_offset)) => read_zip64_record(reader, locator, *locator_offset, disks).await?,
+        None => None,
+    };
+
+    // The ZIP64 record (where found) holds the full-width values, including the disk on which the central directory
+    // begins. The central directory ends where the ZIP64 records begin, or otherwise at the EOCDH.
+    let (cd_offset, cd_size, num_of_entries, cd_disk) = match &zip64_record {
+        Some((record, _)) => {
+            let cd_disk = u16::try_from(record.start_cent_dir_disk).unwrap_or(u16::MAX);
+            (record.cent_dir_offset, record.size_cent_dir, record.num_of_entries, cd_disk)
+        }
+        None => (
+            eocdh.cent_dir_offset.into(),
+            eocdh.size_cent_dir.into(),
+            eocdh.num_of_entries.into(),
+            eocdh.start_cent_dir_disk,
+        ),
+    };
+    let cd_end = match (&zip64_record, &zip64_locator) {
+        (Some((_, record_offset)), _) => *record_offset,
+        (None, Some((_, locator_offset))) => *locator_offset,
+        (None, None) => eocdh_offset,
+    };
+
+    // If data has been prepended to the ZIP file (eg. a self-extracting stub) without its offsets having been updated,
+    // the central directory will actually begin later than recorded. As the central directory immediately precedes the
+    // EOCDH (or ZIP64 record), its real position can be found from its size and offsets corrected by the difference.
+    let recorded_offset = cd_offset.checked_add(disk_offset(disks, cd_disk)?);
+    let recorded_offset = recorded_offset.ok_or(ZipError::OffsetOverflow)?;
+    let actual_offset = cd_end.saturating_sub(cd_size);
+    let adjustment = actual_offset.saturating_sub(recorded_offset);
+    crate::trace::debug!(offset = actual_offset, adjustment, "located central directory");
+
+    let location = CentralDirectoryLocation {
+        offset: std::cmp::max(recorded_offset, actual_offset),
+        size: cd_size,
+        num_of_entries,
+        adjustment,
+        preamble_length: adjustment,
+    };
+
+    // An empty central directory has no header to check, so is accepted as-is.
+    let valid = match num_of_entries {
+        _ if !check => true,
+        0 => cd_size == 0,
+        _ if location.offset.saturating_add(4) <= cd_end => {
+            reader.seek(SeekFrom::Start(location.offset)).await?;
+            reader.read_u32_le().await? == crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER
+        }
+        _ => false,
+    };
+
+    let zip64_locator = zip64_locator.is_some();
+    let zip64_record = zip64_record.map(|(record, _)| record);
+    Ok(Candidate { eocdh, eocdh_offset, location, zip64_locator, zip64_record, valid })
+}
+
+// Read the ZIP64 end of central directory record which a locator points to, returning it alongside its offset.
+//
+// As with the central directory, the recorded offset won't account for any data prepended to the ZIP file, so the
+// record is also looked for where it conventionally sits (directly preceding the locator). None is returned if it's
+// found at neither.
+async fn read_zip64_record<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    locator: &Zip64EndOfCentralDirectoryLocator,
+    locator_offset: u64,
+    disks: &[u64],
+) -> Result<Option<(Zip64EndOfCentralDirectoryRecord, u64)>> {
+    const LENGTH: u64 = Zip64EndOfCentralDirectoryRecord::LENGTH as u64;
+
+    let disk = u16::try_from(locator.start_eocdr_disk).unwrap_or(u16::MAX);
+    let recorded_offset = locator.eocdr_offset.checked_add(disk_offset(disks, disk)?);
+    let conventional_offset = locator_offset.checked_sub(LENGTH);
+
+    for offset in [recorded_offset, conventional_offset].into_iter().flatten() {
+        if offset.saturating_add(LENGTH) > locator_offset {
+            continue;
+        }
+
+        let mut buffer = [0; Zip64EndOfCentralDirectoryRecord::LENGTH];
+        reader.seek(SeekFrom::Start(offset)).await?;
+        reader.read_exact(&mut buffer).await?;
+
+        if let Ok((record, _)) = Zip64EndOfCentralDirectoryRecord::from_bytes(&buffer) {
+            crate::trace::debug!(offset, entries = record.num_of_entries, "located ZIP64 end of central directory");
+            return Ok(Some((record, offset)));
+        }
+    }
+
+    Ok(None)
+}
+
+// Returns the offset at which a disk begins, where an empty slice of disks denotes a ZIP file which isn't spanned.
+fn disk_offset(disks: &[u64], disk: u16) -> Result<u64> {
+    if disks.is_empty() {
+        return Ok(0);
+    }
+
+    disks.get(usize::from(disk)).copied().ok_or(ZipError::MissingDisk(disk))
+}
+
+/// A lazy iterator over the entries of a ZIP file's central directory.
+///
+/// Entries are parsed one at a time as they're requested, so memory use is independent of the number of entries. This
+/// is returned by the `cd_entries()` method of the seek, fs, and mem readers.
+pub struct CentralDirectoryEntries<R: AsyncRead + Unpin> {
+    pub(crate) reader: BufReader<R>,
+    pub(crate) remaining: u64,
+    pub(crate) offset: u64,
+    pub(crate) index: usize,
+    pub(crate) path_policy: PathPolicy,
+    pub(crate) adjustment: u64,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> CentralDirectoryEntries<R> {
+    pub(crate) async fn from_raw(
+        mut reader: R,
+        location: CentralDirectoryLocation,
+        path_policy: PathPolicy,
+    ) -> Result<Self> {
+        reader.seek(SeekFrom::Start(location.offset)).await?;
+        let (reader, remaining, offset) = (BufReader::new(reader), location.num_of_entries, location.offset);
+        let adjustment = location.adjustment;
+        Ok(CentralDirectoryEntries { reader, remaining, offset, index: 0, path_policy, adjustment })
+    }
+}
+
+impl<R: AsyncRead + Unpin> CentralDirectoryEntries<R> {
+    /// Parses the next entry from the central directory, or returns `None` once all entries have been parsed.
+    pub async fn next_entry(&mut self) -> Result<Option<CentralDirectoryEntry>> {
+        if self.remaining == 0 {
+            return Ok(None);
         }
 
-        if reader.matched() {
-            let inner_offset = reader.get_mut().seek(SeekFrom::Current(0)).await?;
-            matched_offset = Some(inner_offset - reader.buffer().len() as u64);
-        } else if matched_offset.is_some() {
-            break 'outer;
-        } else {
-            return Err(ZipError::UnexpectedHeaderError(0, crate::spec::signature::END_OF_CENTRAL_DIRECTORY));
+        self.remaining -= 1;
+        let (mut entry, mut meta) = match read_cd_entry(&mut self.reader, &mut self.offset, None).await? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        self.path_policy.apply(self.index, &mut entry)?;
+
+        // As when entries are listed upfront, offsets are corrected for any data prepended to the ZIP file.
+        let offset = meta.file_offset.unwrap().checked_add(self.adjustment);
+        meta.file_offset = Some(offset.ok_or(ZipError::OffsetOverflow)?);
+
+        let entry = CentralDirectoryEntry { entry, meta, index: self.index };
+        self.index += 1;
+
+        Ok(Some(entry))
+    }
+
+    /// Scans the remaining entries for one with a specific filename, returning the first match.
+    ///
+    /// The filename must match exactly, and only one entry is held in memory at any point during the scan.
+    pub async fn find(&mut self, name: &str) -> Result<Option<CentralDirectoryEntry>> {
+        while let Some(entry) = self.next_entry().await? {
+            if entry.entry().filename() == name {
+                return Ok(Some(entry));
+            }
         }
 
-        reader.reset();
+        Ok(None)
     }
+}
 
-    let mut reader = reader.into_inner();
-    reader.seek(SeekFrom::Start(matched_offset.unwrap())).await?;
-    let eocdh = EndOfCentralDirectoryHeader::from_reader(&mut reader).await?;
+/// An entry parsed lazily from the central directory, alongside the position of its data.
+///
+/// This is returned by [`CentralDirectoryEntries`], and can be opened for reading via the `reader_with_entry()`
+/// method of the reader it was listed from.
+#[derive(Clone)]
+pub struct CentralDirectoryEntry {
+    pub(crate) entry: ZipEntry,
+    pub(crate) meta: ZipEntryMeta,
+    pub(crate) index: usize,
+}
 
-    // Outdated feature so unlikely to ever make it into this crate.
-    if eocdh.disk_num != eocdh.start_cent_dir_disk || eocdh.num_of_entries != eocdh.num_of_entries_disk {
-        return Err(ZipError::FeatureNotSupported("Spanned/split files"));
+impl CentralDirectoryEntry {
+    /// Returns the entry.
+    pub fn entry(&self) -> &ZipEntry {
+        &self.entry
     }
 
-    if eocdh.file_comm_length > 0 {
-        comment = Some(async_io_utilities::read_string(&mut reader, eocdh.file_comm_length as usize).await?);
+    /// Returns the index of the entry's record within the central directory.
+    pub fn index(&self) -> usize {
+        self.index
     }
 
-    reader.seek(SeekFrom::Start(eocdh.cent_dir_offset.into())).await?;
-    let mut entries = Vec::with_capacity(eocdh.num_of_entries.into());
+    /// Returns the offset of the entry's local file header within the ZIP file.
+    pub fn header_offset(&self) -> u64 {
+        self.meta.file_offset.unwrap()
+    }
 
-    for _ in 0..eocdh.num_of_entries {
-        entries.push(read_cd_entry(reader).await?);
+    /// Consumes this lazily-parsed entry, returning the entry.
+    pub fn into_entry(self) -> ZipEntry {
+        self.entry
     }
+}
+
+// Validate that each entry's data lies before the central directory and, if required, that no entries overlap.
+fn validate_entries(entries: &[(ZipEntry, ZipEntryMeta)], cd_offset: u64, options: &ReaderOptions) -> Result<()> {
+    let mut spans = Vec::with_capacity(entries.len());
+
+    for (index, (entry, meta)) in entries.iter().enumerate() {
+        if let Some(max_size) = options.max_decompressed_size {
+            if entry.uncompressed_size() > max_size {
+                return Err(ZipError::DecompressedSizeExceeded);
+            }
+        }
+
+        if options.check_capabilities {
+            if let Some(feature) = unsupported_feature(entry, meta) {
+                return Err(ZipError::UnsupportedFeature(feature));
+            }
+        }
+
+        // A local file header is at least 30 bytes long, so this is a lower bound of the space the entry occupies.
+        let start = meta.file_offset.unwrap();
+        let end = start.saturating_add(30).saturating_add(entry.compressed_size());
+
+        if end > cd_offset {
+            return Err(ZipError::EntryDataOutOfBounds(index));
+        }
+
+        spans.push((start, end, index));
+    }
+
+    if !options.allow_overlaps {
+        spans.sort_unstable();
 
-    Ok((entries, comment))
+        for pair in spans.windows(2) {
+            if pair[1].0 < pair[0].1 {
+                return Err(ZipError::OverlappingEntries(pair[0].2, pair[1].2));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Returns the first feature required by an entry which this crate doesn't support, if any.
+//
+// ZipCrypto entries can always be decrypted, and AES entries can be where the aes feature is enabled and their AES
+// record is recognised. The method AES entries are actually compressed with is held within that record.
+fn unsupported_feature(entry: &ZipEntry, meta: &ZipEntryMeta) -> Option<Feature> {
+    use crate::spec::extra_field::{parse_aes, AES_COMPRESSION, AES_ENCRYPTION};
+
+    let flags = &meta.general_purpose_flag;
+    let mut compression = entry.compression();
+
+    if flags.strong_encryption {
+        return Some(Feature::StrongEncryption);
+    }
+    if flags.encrypted && entry.compression_raw() == AES_COMPRESSION {
+        let field = entry.extra_fields().iter().find(|field| field.header_id() == AES_ENCRYPTION);
+
+        match field.and_then(|field| parse_aes(field.data())) {
+            Some((_, _, method)) if cfg!(feature = "aes") => compression = Compression::from(method),
+            _ => return Some(Feature::Encryption),
+        }
+    }
+    if flags.patched_data {
+        return Some(Feature::PatchedData);
+    }
+    if !compression.readable() {
+        return Some(Feature::Compression(compression.into()));
+    }
+
+    match entry.version_needed() {
+        Some(version) if version > ZipVersion::from_raw(SPEC_VERSION_MADE_BY) => Some(Feature::Version(version)),
+        _ => None,
+    }
 }
 
-pub(crate) async fn read_cd_entry<R: AsyncRead + Unpin>(reader: &mut R) -> Result<(ZipEntry, ZipEntryMeta)> {
-    crate::utils::assert_signature(reader, crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER).await?;
+// Read a central directory record beginning at the provided offset, advancing the offset past the record.
+//
+// If a filter is provided and rejects the record, the remainder of the record is skipped and None is returned.
+pub(crate) async fn read_cd_entry<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    offset: &mut u64,
+    filter: Option<&EntryFilter>,
+) -> Result<Option<(ZipEntry, ZipEntryMeta)>> {
+    let signature = crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER;
+    crate::utils::assert_signature(reader, signature, Some(*offset)).await?;
 
     let header = CentralDirectoryHeader::from_reader(reader).await?;
-    let filename = async_io_utilities::read_string(reader, header.file_name_length.into()).await?;
-    let compression = Compression::try_from(header.compression)?;
+    *offset += 46
+        + u64::from(header.file_name_length)
+        + u64::from(header.extra_field_length)
+        + u64::from(header.file_comment_length);
+
+    let filename = async_io_utilities::read_bytes(reader, header.file_name_length.into()).await?;
+
+    if let Some(filter) = filter {
+        let raw = RawEntryHeader {
+            filename: &filename,
+            compression: header.compression,
+            compressed_size: header.compressed_size,
+            uncompressed_size: header.uncompressed_size,
+        };
+
+        if !filter.matches(&raw) {
+            let remaining = u64::from(header.extra_field_length) + u64::from(header.file_comment_length);
+            tokio::io::copy(&mut (&mut *reader).take(remaining), &mut tokio::io::sink()).await?;
+            return Ok(None);
+        }
+    }
+
+    let compression = Compression::from(header.compression);
     let extra_field = async_io_utilities::read_bytes(reader, header.extra_field_length.into()).await?;
-    let comment = async_io_utilities::read_string(reader, header.file_comment_length.into()).await?;
+    let comment = async_io_utilities::read_bytes(reader, header.file_comment_length.into()).await?;
+    let comment = crate::utils::decode_text(comment, header.flags.filename_unicode)?;
     let last_modification_date = crate::spec::date::zip_date_to_chrono(header.mod_date, header.mod_time);
 
+    // The upper byte of the "version made by" field holds the host which the external file attribute is specific to.
+    // FIXME: Default to Unix for the moment where the host isn't supported.
+    let attribute_compatibility = AttributeCompatibility::try_from(header.v_made_by >> 8);
+
+    let extra_fields = crate::spec::extra_field::parse_extra_fields(&extra_field);
+
+    // An extended timestamp holds the modification time to the second (and beyond the MS-DOS range of 1980 to 2107),
+    // so it's preferred over the MS-DOS date & time where present.
+    let extended = crate::spec::extra_field::extended_modification_time(&extra_fields);
+    let last_modification_date = extended.unwrap_or(last_modification_date);
+    let (filename, filename_bytes) =
+        crate::utils::decode_entry_filename(filename, header.flags.filename_unicode, &extra_fields)?;
+
+    // Any sizes or offset which don't fit within the record itself are held in its ZIP64 extended information instead.
+    let mut values = [header.uncompressed_size.into(), header.compressed_size.into(), header.lh_offset.into()];
+    crate::spec::extra_field::resolve_zip64(&extra_fields, &mut values);
+    let [uncompressed_size, compressed_size, lh_offset] = values;
+
     let entry = ZipEntry {
-        filename,
+        filename: filename.into(),
+        filename_raw: None,
+        filename_bytes,
         compression,
         compression_level: async_compression::Level::Default,
-        attribute_compatibility: AttributeCompatibility::Unix, /// FIXME: Default to Unix for the moment
+        auto_compression: None,
+        attribute_compatibility: attribute_compatibility.unwrap_or(AttributeCompatibility::Unix),
         crc32: header.crc,
-        uncompressed_size: header.uncompressed_size,
-        compressed_size: header.compressed_size,
+        uncompressed_size,
+        compressed_size,
         last_modification_date,
         internal_file_attribute: header.inter_attr,
         external_file_attribute: header.exter_attr,
+        extra_fields,
         extra_field,
-        comment
+        comment,
+        version_made_by: Some(header.v_made_by),
+        version_needed: Some(header.v_needed),
+        general_purpose_flag: header.flags,
+        symlink_target: None,
+        alignment: 0,
+        force_zip64: false,
+        timestamps: [None; 3],
+        ntfs_timestamps: false,
+        encryption: None,
     };
 
     let meta = ZipEntryMeta {
         general_purpose_flag: header.flags,
-        file_offset: Some(header.lh_offset),
+        file_offset: Some(lh_offset),
+        disk_start: header.disk_start,
     };
 
-    Ok((entry, meta))
+    Ok(Some((entry, meta)))
 }
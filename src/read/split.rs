@@ -0,0 +1,192 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module for reading ZIP files which have been split across multiple parts (eg. `.z01`, `.z02`, ..., `.zip`).
+//!
+//! The parts are stitched together into a single logical reader, so entries which begin in one part and continue into
+//! the next are read transparently. Once constructed, the reader acts as [`seek::ZipFileReader`] does.
+//!
+//! # Note
+//! ZIP64 split archives are supported, including where the ZIP64 end of central directory record and locator sit on a
+//! different disk than the central directory.
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::read::split::ZipFileReader;
+//! # use async_zip::error::ZipError;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let mut zip = ZipFileReader::from_paths(&["./archive.z01", "./archive.z02", "./archive.zip"]).await?;
+//! let reader = zip.entry_reader(0).await?;
+//! let data = reader.read_to_string_crc().await?;
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::error::Result;
+use crate::read::{seek, ReaderOptions};
+
+use std::io::{Error, ErrorKind, SeekFrom};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt, ReadBuf};
+
+/// A reader over ZIP file parts, ordered by disk number.
+pub type ZipFileReader<R> = seek::ZipFileReader<SplitReader<R>>;
+
+impl<R: AsyncRead + AsyncSeek + Unpin> seek::ZipFileReader<SplitReader<R>> {
+    /// Constructs a new ZIP file reader from its parts, ordered by disk number (ie. with the `.zip` part last).
+    pub async fn from_parts(parts: Vec<R>) -> Result<Self> {
+        Self::from_parts_with_options(parts, ReaderOptions::default()).await
+    }
+
+    /// Constructs a new ZIP file reader from its parts, ordered by disk number, using the provided options.
+    pub async fn from_parts_with_options(parts: Vec<R>, options: ReaderOptions) -> Result<Self> {
+        let reader = SplitReader::new(parts).await?;
+        let disks = reader.starts.clone();
+
+        Self::with_disks(reader, options, &disks).await
+    }
+}
+
+impl seek::ZipFileReader<SplitReader<File>> {
+    /// Constructs a new ZIP file reader from the filenames of its parts, ordered by disk number.
+    pub async fn from_paths<P: AsRef<Path>>(paths: &[P]) -> Result<Self> {
+        let mut parts = Vec::with_capacity(paths.len());
+
+        for path in paths {
+            parts.push(File::open(path).await?);
+        }
+
+        Self::from_parts(parts).await
+    }
+}
+
+/// A reader which stitches multiple parts into a single logical byte space.
+///
+/// This implements [`AsyncRead`] and [`AsyncSeek`], with reads crossing from one part into the next as required.
+pub struct SplitReader<R: AsyncRead + AsyncSeek + Unpin> {
+    parts: Vec<R>,
+    starts: Vec<u64>,
+    length: u64,
+    position: u64,
+    // The part whose own position is known to match this reader's position, if any.
+    synced: Option<usize>,
+    // The part (and offset within it) of a seek which has been started but not yet completed, if any.
+    seeking: Option<(usize, u64)>,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> SplitReader<R> {
+    /// Constructs a new split reader from its parts, ordered by disk number.
+    pub async fn new(mut parts: Vec<R>) -> Result<Self> {
+        let mut starts = Vec::with_capacity(parts.len());
+        let mut length = 0;
+
+        for part in &mut parts {
+            starts.push(length);
+            length += part.seek(SeekFrom::End(0)).await?;
+        }
+
+        Ok(SplitReader { parts, starts, length, position: 0, synced: None, seeking: None })
+    }
+
+    /// Returns the offset at which each part begins within the logical byte space.
+    pub fn part_offsets(&self) -> &[u64] {
+        &self.starts
+    }
+
+    /// Consumes this reader, returning its parts.
+    pub fn into_inner(self) -> Vec<R> {
+        self.parts
+    }
+
+    // Returns the index of the non-empty part which contains the provided position.
+    fn part_index(&self, position: u64) -> usize {
+        self.starts.partition_point(|start| *start <= position) - 1
+    }
+
+    fn part_end(&self, index: usize) -> u64 {
+        self.starts.get(index + 1).copied().unwrap_or(self.length)
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncRead for SplitReader<R> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        if self.position >= self.length || buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let index = self.part_index(self.position);
+        let target = (index, self.position - self.starts[index]);
+        let this = &mut *self;
+
+        // A part's own position only needs to be moved when this reader has been seeked, or has moved onto the part.
+        while this.synced != Some(index) {
+            if let Some((part, offset)) = this.seeking {
+                match Pin::new(&mut this.parts[part]).poll_complete(cx) {
+                    Poll::Ready(result) => {
+                        this.seeking = None;
+                        result?;
+
+                        if (part, offset) == target {
+                            this.synced = Some(part);
+                        }
+                    }
+                    Poll::Pending => return Poll::Pending,
+                }
+            } else {
+                Pin::new(&mut this.parts[index]).start_seek(SeekFrom::Start(target.1))?;
+                this.seeking = Some(target);
+            }
+        }
+
+        // Never read beyond the end of the current part, so that the next read moves on to the following part.
+        let remaining = (this.part_end(index) - this.position).min(buf.remaining() as u64) as usize;
+        let mut part_buf = ReadBuf::new(buf.initialize_unfilled_to(remaining));
+
+        match Pin::new(&mut this.parts[index]).poll_read(cx, &mut part_buf) {
+            Poll::Ready(Ok(())) => {}
+            other => return other,
+        }
+
+        let read = part_buf.filled().len();
+        if read == 0 {
+            return Poll::Ready(Err(Error::new(ErrorKind::UnexpectedEof, "a part ended before its expected length")));
+        }
+
+        buf.advance(read);
+        this.position += read as u64;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncSeek for SplitReader<R> {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let position = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => offset_by(self.length, offset),
+            SeekFrom::Current(offset) => offset_by(self.position, offset),
+        };
+
+        // Parts are seeked lazily upon the next read, so any part seek which is still in flight is left to complete.
+        self.position = position.ok_or_else(|| Error::new(ErrorKind::InvalidInput, "invalid seek position"))?;
+        self.synced = None;
+
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        Poll::Ready(Ok(self.position))
+    }
+}
+
+fn offset_by(position: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        position.checked_add(offset as u64)
+    } else {
+        position.checked_sub(offset.unsigned_abs())
+    }
+}
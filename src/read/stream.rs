@@ -31,27 +31,58 @@
 //! ```
 
 use crate::error::{Result, ZipError};
-use crate::read::{CompressionReader, OwnedReader, PrependReader, ZipEntry, ZipEntryReader};
+use crate::read::{CompressionReader, OwnedReader, PrependReader, ReaderOptions, ZipEntry, ZipEntryReader};
 use crate::spec::compression::Compression;
 use crate::spec::header::LocalFileHeader;
 use crate::spec::attribute::AttributeCompatibility;
 use crate::read::ZipEntryMeta;
 
 use async_io_utilities::AsyncPrependReader;
-use tokio::io::{AsyncRead, AsyncReadExt};
+use std::borrow::Cow;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
 
 /// A reader which acts over a non-seekable source.
+///
+/// Alongside the async methods, a poll-based interface is available for use within manually-implemented futures or
+/// state machines: [`ZipFileReader::poll_next_entry()`] parses the next local file header, after which
+/// [`ZipFileReader::entry_data_reader()`] opens a reader over its data. The async methods are built upon these.
 pub struct ZipFileReader<R: AsyncRead + Unpin> {
     pub(crate) reader: AsyncPrependReader<R>,
     pub(crate) entry: Option<(ZipEntry, ZipEntryMeta)>,
     pub(crate) finished: bool,
+    pub(crate) options: ReaderOptions,
+    pub(crate) index: usize,
+    pub(crate) header: Vec<u8>,
+    pub(crate) skip: u64,
+    pub(crate) pending_error: Option<ZipError>,
+    // Whether an opened entry reader hasn't yet reached EOF, leaving the position of the next header unknown.
+    pub(crate) unfinished: bool,
 }
 
 impl<R: AsyncRead + Unpin> ZipFileReader<R> {
     /// Constructs a new ZIP file reader from a reader which implements [`AsyncRead`].
     pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ReaderOptions::default())
+    }
+
+    /// Constructs a new ZIP file reader from a reader which implements [`AsyncRead`], using the provided options.
+    ///
+    /// # Note
+    /// As there's no central directory to validate upfront, only [`ReaderOptions::buffer_size()`],
+    /// [`ReaderOptions::buffer_pool()`], [`ReaderOptions::poll_byte_budget()`], and [`ReaderOptions::path_policy()`]
+    /// apply to this reader.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "open_archive", level = "debug", skip_all, fields(reader = "stream"))
+    )]
+    pub fn with_options(reader: R, options: ReaderOptions) -> Self {
         let reader = AsyncPrependReader::new(reader);
-        ZipFileReader { reader, entry: None, finished: false }
+        let header = Vec::with_capacity(LocalFileHeader::LENGTH);
+        let (entry, pending_error) = (None, None);
+        let (finished, unfinished) = (false, false);
+        ZipFileReader { reader, entry, finished, options, index: 0, header, skip: 0, pending_error, unfinished }
     }
 
     /// Returns whether or not it's possible for this reader to yeild more entries.
@@ -64,66 +95,389 @@ impl<R: AsyncRead + Unpin> ZipFileReader<R> {
         self.finished
     }
 
+    /// Polls to parse the next entry's local file header, returning the entry if the central directory hasn't yet been
+    /// reached.
+    ///
+    /// The header is read incrementally, with any of its bytes read so far kept across polls, so this may be called
+    /// from within a manually-implemented future or state machine without borrowing the reader across awaits. Once an
+    /// entry has been returned, a reader over its data can be opened via [`ZipFileReader::entry_data_reader()`].
+    ///
+    /// # Note
+    /// Each entry's data must be fully consumed before the next entry is polled (eg. via [`ZipEntryReader::finish()`]),
+    /// otherwise this fails with [`ZipError::EntryNotFullyConsumed`]. Entries rejected by
+    /// [`ReaderOptions::path_policy()`] fail with [`ZipError::UnsafeEntryName`], and unless the entry uses a data
+    /// descriptor, its data is skipped beforehand so that the following entries may still be read.
+    pub fn poll_next_entry(&mut self, c: &mut Context<'_>) -> Poll<Result<Option<ZipEntry>>> {
+        // The data of an entry which was rejected or couldn't be read is discarded before the next header is parsed.
+        std::task::ready!(self.poll_skip(c))?;
+
+        if self.unfinished {
+            return Poll::Ready(Err(ZipError::EntryNotFullyConsumed));
+        }
+
+        if let Some(err) = self.pending_error.take() {
+            return Poll::Ready(Err(err));
+        } else if self.finished {
+            return Poll::Ready(Ok(None));
+        }
+
+        self.entry = None;
+
+        loop {
+            let length = match header_length(&self.header)? {
+                Some(length) => length,
+                None => {
+                    // The signature is left for whatever reads the central directory (or end record) thereafter.
+                    self.reader.prepend(&self.header);
+                    self.header.clear();
+                    self.finished = true;
+                    return Poll::Ready(Ok(None));
+                }
+            };
+
+            let filled = self.header.len();
+            if filled == length {
+                break;
+            }
+
+            // Only the bytes known to belong to the header are read, so none of the entry's data is consumed.
+            self.header.resize(length, 0);
+            let mut buffer = ReadBuf::new(&mut self.header[filled..]);
+            let poll = Pin::new(&mut self.reader).poll_read(c, &mut buffer);
+            let read = buffer.filled().len();
+            self.header.truncate(filled + read);
+
+            std::task::ready!(poll)?;
+            if read == 0 {
+                return Poll::Ready(Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()));
+            }
+        }
+
+        let parsed = parse_lfh(&self.header);
+        self.header.clear();
+        let (mut entry, meta) = parsed?;
+
+        let policy = self.options.path_policy.apply(self.index, &mut entry);
+        self.index += 1;
+
+        if let Err(err) = policy {
+            if meta.general_purpose_flag.data_descriptor {
+                return Poll::Ready(Err(err));
+            }
+
+            self.skip = entry.compressed_size();
+            self.pending_error = Some(err);
+            return self.poll_next_entry(c);
+        }
+
+        self.entry = Some((entry.clone(), meta));
+        Poll::Ready(Ok(Some(entry)))
+    }
+
+    /// Opens a reader over the data of the entry most recently returned by [`ZipFileReader::poll_next_entry()`].
+    ///
+    /// This fails with [`ZipError::NoCurrentEntry`] if no entry has been returned since the last was polled.
+    ///
+    /// # Note
+    /// The returned entry reader must be fully consumed (eg. via [`ZipEntryReader::finish()`]) before the next entry
+    /// is polled, otherwise polling fails with [`ZipError::EntryNotFullyConsumed`]. Entries whose
+    /// data uses strong encryption or is patched data fail with [`ZipError::UnsupportedFeature`], and unless the entry
+    /// uses a data descriptor, its data is skipped when the next entry is polled.
+    pub fn entry_data_reader(&mut self) -> Result<ZipEntryReader<'_, R>> {
+        self.check_entry()?;
+        self.open_entry()
+    }
+
     /// Opens the next entry for reading if the central directory hasn't yet been reached.
     ///
     /// # Note
-    /// It's essential that each entry reader returned by this function is fully consumed before a new one is opened.
+    /// Each entry reader returned by this function must be fully consumed (eg. via [`ZipEntryReader::finish()`])
+    /// before a new one is opened, otherwise opening fails with [`ZipError::EntryNotFullyConsumed`].
+    ///
+    /// Entries whose data uses strong encryption or is patched data fail with [`ZipError::UnsupportedFeature`], as do
+    /// entries rejected by [`ReaderOptions::path_policy()`] with [`ZipError::UnsafeEntryName`]. Unless the entry uses a
+    /// data descriptor, its data is skipped beforehand so that the following entries may still be read.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "read_entry",
+            level = "debug",
+            skip_all,
+            fields(
+                filename = tracing::field::Empty,
+                compression = tracing::field::Empty,
+                compressed_size = tracing::field::Empty,
+                uncompressed_size = tracing::field::Empty
+            )
+        )
+    )]
     pub async fn entry_reader(&mut self) -> Result<Option<ZipEntryReader<'_, R>>> {
-        if self.finished {
+        if std::future::poll_fn(|c| self.poll_next_entry(c)).await?.is_none() {
             return Ok(None);
-        } else if let Some(inner) = read_lfh(&mut self.reader).await? {
-            self.entry = Some(inner);
+        }
+
+        crate::trace::record_entry!(&self.entry.as_ref().unwrap().0);
+
+        if let Err(err) = self.check_entry() {
+            std::future::poll_fn(|c| self.poll_skip(c)).await?;
+            return Err(err);
+        }
+
+        self.open_entry().map(Some)
+    }
+
+    /// Skips over the next entry without constructing an entry reader, returning the entry if the central directory
+    /// hasn't yet been reached.
+    ///
+    /// The entry's compressed data is discarded without being decompressed. Where the entry uses a data descriptor (so
+    /// its compressed size isn't known from its local file header), the data is instead scanned for the descriptor,
+    /// which is only accepted if its compressed size matches the data preceding it and it's directly followed by the
+    /// signature of another header.
+    pub async fn skip_entry(&mut self) -> Result<Option<ZipEntry>> {
+        let entry = match std::future::poll_fn(|c| self.poll_next_entry(c)).await? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        self.skip_current_entry().await?;
+        Ok(Some(entry))
+    }
+
+    // Skip over the data of the entry whose local file header has just been read.
+    pub(crate) async fn skip_current_entry(&mut self) -> Result<()> {
+        let (entry, meta) = self.entry.as_ref().ok_or(ZipError::NoCurrentEntry)?;
+
+        if meta.general_purpose_flag.data_descriptor {
+            self.skip_descriptor_entry().await?;
         } else {
-            self.finished = true;
-            return Ok(None);
+            self.skip = entry.compressed_size();
+            std::future::poll_fn(|c| self.poll_skip(c)).await?;
         }
 
+        Ok(())
+    }
+
+    // Discard the data of an entry which uses a data descriptor, along with the descriptor itself.
+    //
+    // The data's length isn't known upfront, so the raw data is scanned for a descriptor whose compressed size matches
+    // the number of bytes preceding it and which is followed by the signature of another header. This distinguishes a
+    // genuine descriptor from the same bytes appearing within the compressed data, and also locates descriptors which
+    // were written without their optional signature.
+    async fn skip_descriptor_entry(&mut self) -> Result<()> {
+        use tokio::io::AsyncReadExt;
+
+        let mut window = Vec::with_capacity(self.options.buffer_size.max(DESCRIPTOR_LOOKAHEAD));
+        let mut discarded = 0u64;
+        let mut eof = false;
+
+        loop {
+            let mut position = 0;
+            while position < window.len() && (eof || window.len() - position >= DESCRIPTOR_LOOKAHEAD) {
+                if let Some(length) = descriptor_length(&window[position..], discarded + position as u64) {
+                    self.reader.prepend(&window[position + length..]);
+                    return Ok(());
+                }
+                position += 1;
+            }
+
+            if eof {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+
+            window.drain(..position);
+            discarded += position as u64;
+
+            let filled = window.len();
+            window.resize(window.capacity().max(filled + DESCRIPTOR_LOOKAHEAD), 0);
+            let read = self.reader.read(&mut window[filled..]).await?;
+            window.truncate(filled + read);
+            eof = read == 0;
+        }
+    }
+
+    // Check that the data of the entry whose local file header has just been read can be read, otherwise marking its
+    // data to be skipped.
+    fn check_entry(&mut self) -> Result<()> {
+        let (entry, meta) = self.entry.as_ref().ok_or(ZipError::NoCurrentEntry)?;
+
+        if let Err(err) = crate::read::check_readable(entry, meta) {
+            if !meta.general_purpose_flag.data_descriptor {
+                self.skip = entry.compressed_size();
+            }
+
+            self.entry = None;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    // Discard the remaining data of an entry which won't be read.
+    fn poll_skip(&mut self, c: &mut Context<'_>) -> Poll<Result<()>> {
+        let mut buffer = [0; 4096];
+
+        while self.skip > 0 {
+            let limit = self.skip.min(buffer.len() as u64) as usize;
+            let mut buffer = ReadBuf::new(&mut buffer[..limit]);
+            std::task::ready!(Pin::new(&mut self.reader).poll_read(c, &mut buffer))?;
+
+            match buffer.filled().len() {
+                0 => return Poll::Ready(Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into())),
+                read => self.skip -= read as u64,
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    // Open an entry reader over the entry whose local file header has just been read.
+    fn open_entry(&mut self) -> Result<ZipEntryReader<'_, R>> {
         let entry_borrow = self.entry.as_ref().unwrap();
 
+        // A data descriptor zeroes the local file header's compressed size, so the size only bounds the data of a
+        // Stored entry (which can't otherwise be delimited) and mustn't mark any other entry as empty.
+        let compression = entry_borrow.0.compression();
+        let data_descriptor = entry_borrow.1.general_purpose_flag.data_descriptor;
+        let compressed_size = match data_descriptor && compression != Compression::Stored {
+            true => None,
+            false => Some(entry_borrow.0.compressed_size()),
+        };
+
         let reader = OwnedReader::Borrow(&mut self.reader);
         let reader = PrependReader::Prepend(reader);
-        let reader = CompressionReader::from_reader(
-            &entry_borrow.0.compression(),
+        let buffer = self.options.buffer_source();
+        let reader = CompressionReader::from_reader(&entry_borrow.0, reader, compressed_size, buffer)?;
+
+        let mut reader = ZipEntryReader::from_raw(
+            Cow::Borrowed(&entry_borrow.0),
+            Cow::Borrowed(&entry_borrow.1),
             reader,
-            Some(entry_borrow.0.compressed_size()).map(u32::into),
-        )?;
+            entry_borrow.1.general_purpose_flag.data_descriptor,
+        );
+        reader.poll_byte_budget = self.options.poll_byte_budget;
+        reader.local_extra_fields = entry_borrow.0.extra_fields().to_vec();
+
+        // An entry reader which is consumed from the outset never reads from the inner reader, so needn't be finished.
+        self.unfinished = !reader.consumed;
+        reader.unfinished = Some(&mut self.unfinished);
 
-        Ok(Some(ZipEntryReader::from_raw(&entry_borrow.0, &entry_borrow.1, reader, entry_borrow.1.general_purpose_flag.data_descriptor)))
+        Ok(reader)
     }
 }
 
-pub(crate) async fn read_lfh<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<(ZipEntry, ZipEntryMeta)>> {
-    match reader.read_u32_le().await? {
+// The most bytes needed to recognise a data descriptor: a signed ZIP64 descriptor followed by the next signature.
+const DESCRIPTOR_LOOKAHEAD: usize = 28;
+
+// Returns the length of the data descriptor at the start of the buffer (if any), given the number of data bytes which
+// preceded it. Signed descriptors are preferred over unsigned ones, and 4-byte sizes over ZIP64's 8-byte sizes.
+fn descriptor_length(buffer: &[u8], preceding: u64) -> Option<usize> {
+    let read_u32 = |offset: usize| Some(u32::from_le_bytes(buffer.get(offset..offset + 4)?.try_into().unwrap()));
+    let read_u64 = |offset: usize| Some(u64::from_le_bytes(buffer.get(offset..offset + 8)?.try_into().unwrap()));
+    let signed = read_u32(0) == Some(crate::spec::signature::DATA_DESCRIPTOR);
+
+    let layouts = [(true, false), (true, true), (false, false), (false, true)];
+    for (signature, zip64) in layouts.into_iter().filter(|(signature, _)| !signature || signed) {
+        let offset = if signature { 8 } else { 4 };
+        let (compressed_size, length) = match zip64 {
+            true => (read_u64(offset), offset + 16),
+            false => (read_u32(offset).map(u64::from), offset + 8),
+        };
+
+        if compressed_size == Some(preceding) && read_u32(length).map_or(false, follows_descriptor) {
+            return Some(length);
+        }
+    }
+
+    None
+}
+
+// Returns whether a signature may directly follow an entry's data descriptor.
+fn follows_descriptor(signature: u32) -> bool {
+    matches!(
+        signature,
+        crate::spec::signature::LOCAL_FILE_HEADER
+            | crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER
+            | crate::spec::signature::ZIP64_END_OF_CENTRAL_DIRECTORY
+            | crate::spec::signature::END_OF_CENTRAL_DIRECTORY
+    )
+}
+
+// Returns the length of the local file header whose leading bytes have been buffered, as far as can yet be determined,
+// or None if the buffered signature instead begins the central directory (or the end of central directory record, as
+// a ZIP file without any entries doesn't have a central directory).
+fn header_length(buffer: &[u8]) -> Result<Option<usize>> {
+    if buffer.len() < 4 {
+        return Ok(Some(4));
+    }
+
+    match u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) {
         crate::spec::signature::LOCAL_FILE_HEADER => {}
-        crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER => return Ok(None),
-        actual => return Err(ZipError::UnexpectedHeaderError(actual, crate::spec::signature::LOCAL_FILE_HEADER)),
+        crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER
+        | crate::spec::signature::ZIP64_END_OF_CENTRAL_DIRECTORY
+        | crate::spec::signature::END_OF_CENTRAL_DIRECTORY => return Ok(None),
+        found => {
+            let expected = crate::spec::signature::LOCAL_FILE_HEADER;
+            return Err(ZipError::UnexpectedSignature { offset: None, expected, found });
+        }
     };
 
-    let header = LocalFileHeader::from_reader(reader).await?;
-    let filename = async_io_utilities::read_string(reader, header.file_name_length.into()).await?;
-    let compression = Compression::try_from(header.compression)?;
+    if buffer.len() < LocalFileHeader::LENGTH {
+        return Ok(Some(LocalFileHeader::LENGTH));
+    }
+
+    let file_name_length = u16::from_le_bytes([buffer[26], buffer[27]]);
+    let extra_field_length = u16::from_le_bytes([buffer[28], buffer[29]]);
+    Ok(Some(LocalFileHeader::LENGTH + usize::from(file_name_length) + usize::from(extra_field_length)))
+}
+
+// Parse an entry from a buffered local file header, including its signature, filename, and extra field.
+fn parse_lfh(buffer: &[u8]) -> Result<(ZipEntry, ZipEntryMeta)> {
+    let (header, length) = LocalFileHeader::from_bytes(buffer)?;
+    let (filename, extra_field) = buffer[length..].split_at(header.file_name_length.into());
+
+    let compression = Compression::from(header.compression);
     let last_modification_date = crate::spec::date::zip_date_to_chrono(header.mod_date, header.mod_time);
-    let extra_field = async_io_utilities::read_bytes(reader, header.extra_field_length.into()).await?;
+    let extra_field = extra_field.to_vec();
+    let extra_fields = crate::spec::extra_field::parse_extra_fields(&extra_field);
+    let extended = crate::spec::extra_field::extended_modification_time(&extra_fields);
+    let last_modification_date = extended.unwrap_or(last_modification_date);
+    let (filename, filename_bytes) =
+        crate::utils::decode_entry_filename(filename.to_vec(), header.flags.filename_unicode, &extra_fields)?;
+    let (compressed_size, uncompressed_size) = crate::read::local_sizes(&header, &extra_fields);
 
     let entry = ZipEntry {
-        filename,
+        filename: filename.into(),
+        filename_raw: None,
+        filename_bytes,
         compression,
         compression_level: async_compression::Level::Default,
+        auto_compression: None,
         attribute_compatibility: AttributeCompatibility::Unix,
         crc32: header.crc,
-        uncompressed_size: header.uncompressed_size,
-        compressed_size: header.compressed_size,
+        uncompressed_size,
+        compressed_size,
         last_modification_date,
         internal_file_attribute: 0,
         external_file_attribute: 0,
+        extra_fields,
         extra_field,
         comment: String::new(),
+        version_made_by: None,
+        version_needed: Some(header.version),
+        general_purpose_flag: header.flags,
+        symlink_target: None,
+        alignment: 0,
+        force_zip64: false,
+        timestamps: [None; 3],
+        ntfs_timestamps: false,
+        encryption: None,
     };
 
     let meta = ZipEntryMeta {
         general_purpose_flag: header.flags,
         file_offset: None,
+        disk_start: 0,
     };
 
-    Ok(Some((entry, meta)))
+    Ok((entry, meta))
 }
@@ -1,87 +1,255 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
-//! A module for reading ZIP file entries concurrently from a seekable source (synchronised over the underlying src).
+//! A module for reading ZIP files from synchronous (blocking) code.
 //!
-//! # Note
-//! This module is unimplemented, and calls to ZipFileReader::new() will panic. Whilst I haven't put much thought into
-//! impl, synchronising over a single seekable source creates a lot of challenges. Each call to read will have to do a
-//! preemptive seek to the entry's data offset, and concurrent seeks can't interfere with each other. Thus, if using a
-//! locking approach, we may have to hold the lock from the start of seeking to the end of reading.
+//! The readers within this module mirror those of the [`fs`](crate::read::fs) and [`mem`](crate::read::mem) modules,
+//! but each blocks the calling thread until its operation completes. They're intended for code which can't easily be
+//! made asynchronous (eg. a build script or a rayon pipeline), and internally drive the asynchronous readers via a
+//! lightweight current-thread runtime owned by each [`ZipFileReader`].
 //!
-//! An async impl creates even more challenges as we have no guarantee when or even if a future (async seek or read)
-//! will complete, thus we may create a deadlock.
+//! ## Note
+//! These readers may also be used from within an asynchronous context (where blocking the runtime's own thread would
+//! otherwise panic), in which case each operation is driven from a separate thread. This carries a cost per operation,
+//! so the asynchronous readers should be preferred wherever possible.
 //!
-//! Feel free to open an issue/PR if you have a good approach for this.
-
-use crate::error::{Result, ZipError};
-use crate::read::{CompressionReader, OwnedReader, PrependReader, ZipEntry, ZipEntryReader};
-use crate::spec::header::LocalFileHeader;
-use crate::read::ZipEntryMeta;
-
-use std::io::SeekFrom;
-use std::ops::DerefMut;
-use std::pin::Pin;
-use std::sync::{Arc, Mutex};
-use std::task::{Context, Poll};
-
-use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt, ReadBuf};
-
-/// A reader which acts concurrently over an in-memory buffer.
-pub struct ZipFileReader<R: AsyncRead + AsyncSeek + Unpin> {
-    pub(crate) reader: Arc<Mutex<R>>,
-    pub(crate) entries: Vec<(ZipEntry, ZipEntryMeta)>,
-    pub(crate) comment: Option<String>,
+//! ## Example
+//! ```no_run
+//! # use async_zip::read::sync::ZipFileReader;
+//! # use async_zip::error::ZipError;
+//! # use std::io::Read;
+//! #
+//! # fn run() -> Result<(), ZipError> {
+//! let mut zip = ZipFileReader::new("./Archive.zip")?;
+//! let mut data = Vec::new();
+//!
+//! let mut reader = zip.entry_reader(0)?;
+//! reader.read_to_end(&mut data)?;
+//! reader.verify_crc()?;
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::entry::ZipEntry;
+use crate::error::Result;
+use crate::read::mem::ConcurrentReader;
+use crate::read::{fs, mem, ArchiveMetadata, ReaderOptions, ZipEntryReader};
+
+use std::future::Future;
+use std::io::Read;
+use std::path::Path;
+
+use tokio::io::AsyncReadExt;
+use tokio::runtime::Runtime;
+
+// Expand an expression over the underlying asynchronous reader, whichever module it's from.
+macro_rules! delegate {
+    ($source:expr, $reader:ident => $body:expr) => {
+        match $source {
+            Source::File($reader) => $body,
+            Source::Slice($reader) => $body,
+        }
+    };
+}
+
+/// A reader which blocks the calling thread, acting over either a filesystem file or an in-memory buffer.
+pub struct ZipFileReader<'a> {
+    runtime: Option<Runtime>,
+    source: Source<'a>,
+}
+
+enum Source<'a> {
+    File(fs::ZipFileReader),
+    Slice(mem::ZipFileReader<'a>),
+}
+
+impl ZipFileReader<'static> {
+    /// Constructs a new ZIP file reader from a filename.
+    pub fn new<P: AsRef<Path>>(filename: P) -> Result<ZipFileReader<'static>> {
+        Self::with_options(filename, ReaderOptions::default())
+    }
+
+    /// Constructs a new ZIP file reader from a filename, using the provided options.
+    pub fn with_options<P: AsRef<Path>>(filename: P, options: ReaderOptions) -> Result<ZipFileReader<'static>> {
+        let runtime = new_runtime()?;
+        let filename = filename.as_ref().to_path_buf();
+        let reader = block_on(&runtime, fs::ZipFileReader::with_options(filename, options))?;
+
+        Ok(ZipFileReader { runtime: Some(runtime), source: Source::File(reader) })
+    }
 }
 
-#[allow(unreachable_code, unused_variables)]
-impl<R: AsyncRead + AsyncSeek + Unpin> ZipFileReader<R> {
+impl<'a> ZipFileReader<'a> {
     /// Constructs a new ZIP file reader from an in-memory buffer.
-    pub async fn new(reader: R) -> Result<ZipFileReader<R>> {
-        unimplemented!();
+    pub fn from_slice(data: &'a [u8]) -> Result<ZipFileReader<'a>> {
+        Self::from_slice_with_options(data, ReaderOptions::default())
+    }
 
-        let (entries, comment) = crate::read::seek::read_cd(&mut reader).await?;
-        Ok(ZipFileReader { reader: Arc::new(Mutex::new(reader)), entries, comment })
+    /// Constructs a new ZIP file reader from an in-memory buffer, using the provided options.
+    pub fn from_slice_with_options(data: &'a [u8], options: ReaderOptions) -> Result<ZipFileReader<'a>> {
+        let runtime = new_runtime()?;
+        let reader = block_on(&runtime, mem::ZipFileReader::with_options(data, options))?;
+
+        Ok(ZipFileReader { runtime: Some(runtime), source: Source::Slice(reader) })
     }
 
-    crate::read::reader_entry_impl!();
+    /// Returns a shared reference to a list of the ZIP file's entries.
+    pub fn entries(&self) -> Vec<&ZipEntry> {
+        delegate!(&self.source, reader => reader.entries())
+    }
 
-    /// Opens an entry at the provided index for reading.
-    pub async fn entry_reader(&self, index: usize) -> Result<ZipEntryReader<'_, GuardedReader<R>>> {
-        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+    /// Searches for an entry with a specific filename.
+    ///
+    /// The filename must match exactly. If multiple entries share the filename, the first is returned.
+    pub fn entry(&self, name: &str) -> Option<(usize, &ZipEntry)> {
+        delegate!(&self.source, reader => reader.entry(name))
+    }
+
+    /// Searches for an entry with a filename which matches the provided name after normalisation.
+    ///
+    /// See the equivalent method of the asynchronous readers for how filenames are normalised.
+    pub fn entry_normalized(&self, name: &str) -> Option<(usize, &ZipEntry)> {
+        delegate!(&self.source, reader => reader.entry_normalized(name))
+    }
 
-        let mut guarded_reader = GuardedReader { reader: self.reader.clone() };
-        guarded_reader.seek(SeekFrom::Start(entry.1.file_offset.unwrap() as u64 + 4)).await?;
+    /// Returns an optional ending comment.
+    pub fn comment(&self) -> Option<&str> {
+        delegate!(&self.source, reader => reader.comment())
+    }
+
+    /// Returns metadata of the ZIP file as a whole, as recorded within its end of central directory header.
+    pub fn metadata(&self) -> &ArchiveMetadata {
+        delegate!(&self.source, reader => reader.metadata())
+    }
 
-        let header = LocalFileHeader::from_reader(&mut guarded_reader).await?;
-        let data_offset = (header.file_name_length + header.extra_field_length) as i64;
-        guarded_reader.seek(SeekFrom::Current(data_offset)).await?;
+    /// Opens an entry at the provided index for reading.
+    pub fn entry_reader(&mut self, index: usize) -> Result<EntryReader<'_, 'a>> {
+        let runtime = self.runtime.as_ref().unwrap();
 
-        let reader = OwnedReader::Owned(guarded_reader);
-        let reader = PrependReader::Normal(reader);
-        let reader = CompressionReader::from_reader(&entry.0.compression(), reader, Some(entry.0.compressed_size()).map(u32::into))?;
+        let inner = match &mut self.source {
+            Source::File(reader) => EntryInner::File(block_on(runtime, reader.entry_reader(index))?),
+            Source::Slice(reader) => EntryInner::Slice(block_on(runtime, reader.entry_reader(index))?),
+        };
 
-        Ok(ZipEntryReader::from_raw(&entry.0, &entry.1, reader, entry.1.general_purpose_flag.data_descriptor))
+        Ok(EntryReader { runtime, inner })
     }
 }
 
-#[derive(Clone)]
-pub struct GuardedReader<R: AsyncRead + AsyncSeek + Unpin> {
-    pub(crate) reader: Arc<Mutex<R>>,
+impl<'a> Drop for ZipFileReader<'a> {
+    fn drop(&mut self) {
+        // Dropping a runtime normally blocks, which panics within an asynchronous context. As operations are only ever
+        // driven to completion, there's nothing left for the runtime to wait on.
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
 }
 
-impl<R: AsyncRead + AsyncSeek + Unpin> AsyncSeek for GuardedReader<R> {
-    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> tokio::io::Result<()> {
-        Pin::new(self.reader.lock().unwrap().deref_mut()).start_seek(position)
+/// An entry reader which blocks the calling thread, returned by [`ZipFileReader::entry_reader()`].
+///
+/// As with the asynchronous entry readers, the CRC32 value of the data read should be checked via
+/// [`EntryReader::verify_crc()`] once the entry has been fully read.
+pub struct EntryReader<'b, 'a> {
+    runtime: &'b Runtime,
+    inner: EntryInner<'b, 'a>,
+}
+
+enum EntryInner<'b, 'a> {
+    File(ZipEntryReader<'b, tokio::fs::File>),
+    Slice(ConcurrentReader<'b, 'a>),
+}
+
+// Expand an expression over the underlying asynchronous entry reader, whichever module it's from.
+macro_rules! delegate_entry {
+    ($inner:expr, $reader:ident => $body:expr) => {
+        match $inner {
+            EntryInner::File($reader) => $body,
+            EntryInner::Slice($reader) => $body,
+        }
+    };
+}
+
+impl<'b, 'a> EntryReader<'b, 'a> {
+    /// Disables the computation of the CRC32 value of the bytes read from this entry.
+    ///
+    /// See the equivalent method of the asynchronous entry reader for how this affects CRC32 checks.
+    pub fn without_crc(self) -> Self {
+        let inner = match self.inner {
+            EntryInner::File(reader) => EntryInner::File(reader.without_crc()),
+            EntryInner::Slice(reader) => EntryInner::Slice(reader.without_crc()),
+        };
+
+        EntryReader { runtime: self.runtime, inner }
+    }
+
+    /// Returns a reference to the inner entry's data.
+    pub fn entry(&self) -> &ZipEntry {
+        delegate_entry!(&self.inner, reader => reader.entry())
+    }
+
+    /// Returns whether or not the entry has been fully consumed.
+    pub fn consumed(&self) -> bool {
+        delegate_entry!(&self.inner, reader => reader.consumed())
     }
 
-    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<tokio::io::Result<u64>> {
-        Pin::new(self.reader.lock().unwrap().deref_mut()).poll_complete(cx)
+    /// Checks the CRC32 value of the data read against the entry's expected value.
+    ///
+    /// See the equivalent method of the asynchronous entry reader for the errors returned.
+    pub fn verify_crc(&self) -> Result<()> {
+        delegate_entry!(&self.inner, reader => reader.verify_crc())
+    }
+
+    /// Reads all remaining bytes until EOF, with the final CRC32 check integrated.
+    pub fn read_to_end_crc(self) -> Result<Vec<u8>> {
+        let runtime = self.runtime;
+        delegate_entry!(self.inner, reader => block_on(runtime, reader.read_to_end_crc()))
+    }
+
+    /// Reads all remaining bytes until EOF, with the final CRC32 check integrated, failing once more than `max` bytes
+    /// have been decompressed.
+    pub fn read_to_end_crc_limited(self, max: u64) -> Result<Vec<u8>> {
+        let runtime = self.runtime;
+        delegate_entry!(self.inner, reader => block_on(runtime, reader.read_to_end_crc_limited(max)))
+    }
+
+    /// Reads all remaining bytes until EOF into a string, with the final CRC32 check integrated.
+    pub fn read_to_string_crc(self) -> Result<String> {
+        let runtime = self.runtime;
+        delegate_entry!(self.inner, reader => block_on(runtime, reader.read_to_string_crc()))
+    }
+
+    /// Reads all remaining bytes until EOF into a string, with the final CRC32 check integrated, failing once more
+    /// than `max` bytes have been decompressed.
+    pub fn read_to_string_crc_limited(self, max: u64) -> Result<String> {
+        let runtime = self.runtime;
+        delegate_entry!(self.inner, reader => block_on(runtime, reader.read_to_string_crc_limited(max)))
     }
 }
 
-impl<R: AsyncRead + AsyncSeek + Unpin> AsyncRead for GuardedReader<R> {
-    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
-        Pin::new(self.reader.lock().unwrap().deref_mut()).poll_read(cx, buf)
+impl<'b, 'a> Read for EntryReader<'b, 'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let runtime = self.runtime;
+        delegate_entry!(&mut self.inner, reader => block_on(runtime, reader.read(buf)))
     }
 }
+
+fn new_runtime() -> Result<Runtime> {
+    Ok(tokio::runtime::Builder::new_current_thread().build()?)
+}
+
+// Drive a future to completion on the provided runtime, blocking the calling thread.
+//
+// A thread which is already within a runtime can't block on another, so the future is instead driven from a separate
+// (scoped) thread in that case.
+fn block_on<F>(runtime: &Runtime, future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    if tokio::runtime::Handle::try_current().is_err() {
+        return runtime.block_on(future);
+    }
+
+    let result = std::thread::scope(|scope| scope.spawn(move || runtime.block_on(future)).join());
+    result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
@@ -0,0 +1,244 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module which supports verifying the integrity of a whole ZIP file.
+
+use crate::error::{Result, ZipError};
+use crate::read::seek::CentralDirectoryLocation;
+use crate::read::{CompressionReader, OwnedReader, PrependReader, ReaderOptions, SizeSource};
+use crate::read::{ZipEntry, ZipEntryMeta, ZipEntryReader};
+use crate::spec::header::{CentralDirectoryHeader, LocalFileHeader};
+
+use std::borrow::Cow;
+use std::io::SeekFrom;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+/// A report of all problems found whilst verifying a ZIP file.
+///
+/// Returned by the `verify()` and `verify_structure_only()` methods of the seek, fs, and mem readers.
+#[derive(Debug)]
+pub struct VerificationReport {
+    pub(crate) entries_checked: usize,
+    pub(crate) failures: Vec<EntryFailure>,
+    pub(crate) declared_entries: u64,
+    pub(crate) actual_entries: u64,
+}
+
+impl VerificationReport {
+    /// Returns true if no problems were found.
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty() && self.declared_entries == self.actual_entries
+    }
+
+    /// Returns the number of entries which were checked before verification finished or stopped early.
+    pub fn entries_checked(&self) -> usize {
+        self.entries_checked
+    }
+
+    /// Returns the problems found, in order of the entries they were found within.
+    pub fn failures(&self) -> &[EntryFailure] {
+        &self.failures
+    }
+
+    /// Returns the number of entries declared by the end of central directory header.
+    pub fn declared_entries(&self) -> u64 {
+        self.declared_entries
+    }
+
+    /// Returns the number of records actually found within the central directory.
+    pub fn actual_entries(&self) -> u64 {
+        self.actual_entries
+    }
+}
+
+/// A problem found within a single entry whilst verifying a ZIP file.
+#[derive(Debug)]
+pub struct EntryFailure {
+    pub(crate) index: usize,
+    pub(crate) filename: String,
+    pub(crate) kind: FailureKind,
+}
+
+impl EntryFailure {
+    /// Returns the index of the entry.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the filename of the entry, as listed within the central directory.
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Returns the kind of problem found.
+    pub fn kind(&self) -> &FailureKind {
+        &self.kind
+    }
+}
+
+/// The kind of problem found within an entry.
+#[derive(Debug)]
+pub enum FailureKind {
+    /// A field of the local file header disagreed with the central directory (eg. `"crc32"`).
+    LocalHeaderMismatch(&'static str),
+    /// A field of the data descriptor disagreed with the central directory (eg. `"uncompressed_size"`).
+    DataDescriptorMismatch(&'static str),
+    /// The number of compressed bytes consumed by the decoder disagreed with the compressed size declared by a header
+    /// (either [`SizeSource::LocalHeader`] or [`SizeSource::CentralDirectory`]).
+    ///
+    /// This is only checked when verifying with [`SizeSource::TrustDecoder`], and takes the place of a
+    /// [`FailureKind::LocalHeaderMismatch`] of the `"compressed_size"` field. Where both headers are wrong, only the
+    /// local file header is reported.
+    CompressedSizeMismatch { header: SizeSource, declared: u64, actual: u64 },
+    /// The entry couldn't be read, or its decompressed data failed a size or CRC32 check.
+    Read(ZipError),
+}
+
+pub(crate) async fn verify_entries<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    entries: &[(ZipEntry, ZipEntryMeta)],
+    location: CentralDirectoryLocation,
+    options: &ReaderOptions,
+    decompress: bool,
+    stop_early: bool,
+) -> Result<VerificationReport> {
+    if options.lazy_entries {
+        return Err(ZipError::FeatureNotSupported("Verification of lazily listed entries"));
+    }
+
+    let mut report = VerificationReport {
+        entries_checked: 0,
+        failures: Vec::new(),
+        declared_entries: location.num_of_entries,
+        actual_entries: count_cd_records(reader, location.offset).await?,
+    };
+
+    for (index, (entry, meta)) in entries.iter().enumerate() {
+        report.entries_checked += 1;
+
+        if let Err(kind) = verify_entry(reader, entry, meta, decompress, options).await {
+            report.failures.push(EntryFailure { index, filename: entry.filename().to_owned(), kind });
+
+            if stop_early {
+                break;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+// Count the central directory records present, regardless of the number declared.
+async fn count_cd_records<R: AsyncRead + AsyncSeek + Unpin>(reader: &mut R, offset: u64) -> Result<u64> {
+    reader.seek(SeekFrom::Start(offset)).await?;
+    let mut count = 0;
+
+    while let Ok(crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER) = reader.read_u32_le().await {
+        let header = CentralDirectoryHeader::from_reader(reader).await?;
+        let lengths = [header.file_name_length, header.extra_field_length, header.file_comment_length];
+
+        reader.seek(SeekFrom::Current(lengths.iter().map(|length| i64::from(*length)).sum())).await?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+async fn verify_entry<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    entry: &ZipEntry,
+    meta: &ZipEntryMeta,
+    decompress: bool,
+    options: &ReaderOptions,
+) -> std::result::Result<(), FailureKind> {
+    reader.seek(SeekFrom::Start(meta.file_offset.unwrap())).await.map_err(|err| FailureKind::Read(err.into()))?;
+
+    let offset = meta.file_offset;
+    let signature = crate::spec::signature::LOCAL_FILE_HEADER;
+    crate::utils::assert_signature(reader, signature, offset).await.map_err(FailureKind::Read)?;
+
+    let lfh = LocalFileHeader::from_reader(reader).await.map_err(FailureKind::Read)?;
+    let filename = async_io_utilities::read_bytes(reader, lfh.file_name_length.into()).await;
+    let filename = filename.map_err(|err| FailureKind::Read(err.into()))?;
+    let extra_field = async_io_utilities::read_bytes(reader, lfh.extra_field_length.into()).await;
+    let extra_field = extra_field.map_err(|err| FailureKind::Read(err.into()))?;
+    let extra_fields = crate::spec::extra_field::parse_extra_fields(&extra_field);
+    let (lfh_compressed_size, lfh_uncompressed_size) = crate::read::local_sizes(&lfh, &extra_fields);
+
+    // Compare the decoded filenames where their bytes differ, as their encodings may differ whilst naming the same
+    // entry.
+    let unicode = lfh.flags.filename_unicode;
+    let identical = filename == entry.filename_bytes();
+    let filename = crate::utils::decode_entry_filename(filename, unicode, &extra_fields).map(|(filename, _)| filename);
+    if !identical && filename.ok().as_deref() != Some(entry.filename()) {
+        return Err(FailureKind::LocalHeaderMismatch("filename"));
+    }
+    if lfh.compression != u16::from(entry.compression()) {
+        return Err(FailureKind::LocalHeaderMismatch("compression"));
+    }
+
+    // The local file header's CRC32 value and sizes are zeroed when a data descriptor is used.
+    if !lfh.flags.data_descriptor {
+        if lfh.crc != entry.crc32() {
+            return Err(FailureKind::LocalHeaderMismatch("crc32"));
+        }
+        // When trusting the decoder, which of the two sizes is wrong is only known once the data has been decompressed.
+        if lfh_compressed_size != entry.compressed_size() && options.size_source != SizeSource::TrustDecoder {
+            return Err(FailureKind::LocalHeaderMismatch("compressed_size"));
+        }
+        if lfh_uncompressed_size != entry.uncompressed_size() {
+            return Err(FailureKind::LocalHeaderMismatch("uncompressed_size"));
+        }
+    }
+
+    if !decompress {
+        return Ok(());
+    }
+
+    crate::read::check_readable(entry, meta).map_err(FailureKind::Read)?;
+
+    let data_start = reader.stream_position().await.map_err(|err| FailureKind::Read(err.into()))?;
+    let inner = PrependReader::Normal(OwnedReader::Borrow(&mut *reader));
+    let compressed_size = Some(options.size_source.compressed_size(entry, &lfh));
+    let inner = CompressionReader::from_reader(entry, inner, compressed_size, options.buffer_source());
+    let inner = inner.map_err(FailureKind::Read)?;
+
+    let data_descriptor = meta.general_purpose_flag.data_descriptor;
+    let mut entry_reader = ZipEntryReader::from_raw(Cow::Borrowed(entry), Cow::Borrowed(meta), inner, data_descriptor);
+
+    let copied = tokio::io::copy(&mut entry_reader, &mut tokio::io::sink()).await;
+    copied.map_err(|err| FailureKind::Read(crate::read::unwrap_io_error(err)))?;
+
+    if let Some((crc, compressed_size, uncompressed_size)) = entry_reader.data_descriptor {
+        if crc != entry.crc32() {
+            return Err(FailureKind::DataDescriptorMismatch("crc32"));
+        }
+        if compressed_size != entry.compressed_size() {
+            return Err(FailureKind::DataDescriptorMismatch("compressed_size"));
+        }
+        if uncompressed_size != entry.uncompressed_size() {
+            return Err(FailureKind::DataDescriptorMismatch("uncompressed_size"));
+        }
+    }
+
+    entry_reader.verify().map_err(FailureKind::Read)?;
+
+    if options.size_source != SizeSource::TrustDecoder || lfh.flags.data_descriptor {
+        return Ok(());
+    }
+
+    // Any bytes left within the decoder's buffer were read from the inner reader but never consumed.
+    let buffered = entry_reader.reader.get_mut().buffer().len() as u64;
+    drop(entry_reader);
+
+    let position = reader.stream_position().await.map_err(|err| FailureKind::Read(err.into()))?;
+    let actual = position - buffered - data_start;
+
+    let lfh_size = (SizeSource::LocalHeader, lfh_compressed_size);
+    let cd_size = (SizeSource::CentralDirectory, entry.compressed_size());
+
+    match [lfh_size, cd_size].into_iter().find(|(_, size)| *size != actual) {
+        Some((header, declared)) => Err(FailureKind::CompressedSizeMismatch { header, declared, actual }),
+        None => Ok(()),
+    }
+}
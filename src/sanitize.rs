@@ -0,0 +1,162 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module which supports validating entry filenames before they're used as filesystem paths.
+//!
+//! An entry's filename is arbitrary data chosen by whoever created the ZIP file, so it may attempt to escape the
+//! directory it's being extracted into (eg. `../../etc/passwd`). These utilities enforce a single policy which rejects
+//! (or, in the lossy variant, rewrites) such filenames.
+//!
+//! # Example
+//! ```
+//! # use async_zip::sanitize::{sanitize_entry_path, SanitizeError};
+//! #
+//! assert_eq!(Ok("foo/bar.txt".into()), sanitize_entry_path("foo/./bar.txt"));
+//! assert_eq!(Err(SanitizeError::ParentTraversal), sanitize_entry_path("../../etc/passwd"));
+//! ```
+
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// The maximum length of a single path component, in bytes.
+pub const MAX_COMPONENT_LENGTH: usize = 255;
+
+// Device names which Windows reserves in every directory, regardless of any extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2",
+    "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// An enum of reasons for which an entry's filename may be rejected.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum SanitizeError {
+    #[error("The filename contains no path components.")]
+    Empty,
+    #[error("The filename is an absolute path.")]
+    Absolute,
+    #[error("The filename contains a '..' component.")]
+    ParentTraversal,
+    #[error("The filename contains a NUL byte.")]
+    NulByte,
+    #[error("The filename contains a component which is a reserved Windows device name: '{0}'.")]
+    ReservedName(String),
+    #[error("The filename contains a component which exceeds {MAX_COMPONENT_LENGTH} bytes.")]
+    ComponentTooLong,
+}
+
+/// Converts an entry's filename into a relative path which is safe to join onto an extraction directory.
+///
+/// Both forward slashes and backslashes are treated as separators, and empty or `.` components are dropped. The
+/// filename is rejected if it's absolute (including a Windows drive prefix such as `C:`), or contains a `..`
+/// component, a NUL byte, a reserved Windows device name (eg. `CON` or `com1.txt`), or a component longer than
+/// [`MAX_COMPONENT_LENGTH`] bytes.
+pub fn sanitize_entry_path(name: &str) -> Result<PathBuf, SanitizeError> {
+    if name.contains('\0') {
+        return Err(SanitizeError::NulByte);
+    }
+    if name.starts_with(['/', '\\']) || has_drive_prefix(name) {
+        return Err(SanitizeError::Absolute);
+    }
+
+    let mut path = PathBuf::new();
+
+    for component in components(name) {
+        if component == ".." {
+            return Err(SanitizeError::ParentTraversal);
+        }
+        if is_reserved_name(component) {
+            return Err(SanitizeError::ReservedName(component.to_owned()));
+        }
+        if component.len() > MAX_COMPONENT_LENGTH {
+            return Err(SanitizeError::ComponentTooLong);
+        }
+
+        path.push(component);
+    }
+
+    if path.as_os_str().is_empty() {
+        return Err(SanitizeError::Empty);
+    }
+
+    Ok(path)
+}
+
+/// Converts an entry's filename into a relative path which is safe to join onto an extraction directory, rewriting
+/// any offending components rather than rejecting the filename.
+///
+/// Under the same policy as [`sanitize_entry_path()`]:
+/// - A leading separator or drive prefix is stripped.
+/// - `..` components are dropped.
+/// - NUL bytes are replaced with underscores.
+/// - Reserved Windows device names are prefixed with an underscore.
+/// - Overly long components are truncated (on a character boundary).
+///
+/// The returned path may be empty if the filename contains no usable components.
+pub fn sanitize_entry_path_lossy(name: &str) -> PathBuf {
+    let name = name.replace('\0', "_");
+    let name = if has_drive_prefix(&name) { &name[2..] } else { &name[..] };
+
+    let mut path = PathBuf::new();
+
+    for component in components(name).filter(|component| *component != "..") {
+        let mut component = component.to_owned();
+
+        if is_reserved_name(&component) {
+            component.insert(0, '_');
+        }
+        if component.len() > MAX_COMPONENT_LENGTH {
+            let mut boundary = MAX_COMPONENT_LENGTH;
+            while !component.is_char_boundary(boundary) {
+                boundary -= 1;
+            }
+            component.truncate(boundary);
+        }
+
+        path.push(component);
+    }
+
+    path
+}
+
+// Returns whether or not a filename is absolute, or contains a ".." component or NUL byte (see PathPolicy).
+pub(crate) fn is_unsafe_name(name: &str) -> bool {
+    name.contains('\0')
+        || name.starts_with(['/', '\\'])
+        || has_drive_prefix(name)
+        || name.split(['/', '\\']).any(|component| component == "..")
+}
+
+// Rewrite an unsafe filename as a relative one, keeping any trailing separator which marks a directory entry.
+pub(crate) fn sanitize_name(name: &str) -> String {
+    let name = name.replace('\0', "_");
+    let stripped = if has_drive_prefix(&name) { &name[2..] } else { &name[..] };
+
+    let mut sanitized = components(stripped).filter(|component| *component != "..");
+    let mut rewritten = sanitized.next().unwrap_or_default().to_owned();
+
+    for component in sanitized {
+        rewritten.push('/');
+        rewritten.push_str(component);
+    }
+    if !rewritten.is_empty() && name.ends_with(['/', '\\']) {
+        rewritten.push('/');
+    }
+
+    rewritten
+}
+
+// Split a filename on both separators, dropping empty and "." components.
+fn components(name: &str) -> impl Iterator<Item = &str> {
+    name.split(['/', '\\']).filter(|component| !component.is_empty() && *component != ".")
+}
+
+pub(crate) fn has_drive_prefix(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+// Windows ignores any extension and trailing spaces or dots when matching a device name (eg. "nul.tar.gz").
+fn is_reserved_name(component: &str) -> bool {
+    let stem = component.split('.').next().unwrap_or_default().trim_end_matches(' ');
+    RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
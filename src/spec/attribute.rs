@@ -6,7 +6,10 @@ use crate::error::{Result, ZipError};
 /// An attribute host compatibility supported by this crate.
 #[non_exhaustive]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(try_from = "u16", into = "u16"))]
 pub enum AttributeCompatibility {
+    /// MS-DOS (and Windows), as written by many non-Unix producers.
+    Dos,
     Unix
 }
 
@@ -17,6 +20,7 @@ impl TryFrom<u16> for AttributeCompatibility {
     // https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#4422
     fn try_from(value: u16) -> Result<Self> {
         match value {
+            0 => Ok(AttributeCompatibility::Dos),
             3 => Ok(AttributeCompatibility::Unix),
             _ => Err(ZipError::UnsupportedAttributeCompatibility(value))
         }
@@ -28,6 +32,7 @@ impl From<&AttributeCompatibility> for u16 {
     // https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#4422
     fn from(compatibility: &AttributeCompatibility) -> Self {
         match compatibility {
+            AttributeCompatibility::Dos => 0,
             AttributeCompatibility::Unix => 3
         }
     }
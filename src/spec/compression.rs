@@ -1,10 +1,9 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
-use crate::error::{Result, ZipError};
-
 /// A compression method supported by this crate.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(from = "u16", into = "u16"))]
 pub enum Compression {
     Stored,
     #[cfg(feature = "deflate")]
@@ -17,27 +16,71 @@ pub enum Compression {
     Zstd,
     #[cfg(feature = "xz")]
     Xz,
+    /// Deflate64 is only supported when reading.
+    #[cfg(feature = "deflate64")]
+    Deflate64,
+    /// The legacy Shrink method used by PKZIP 1.x, which is only supported when reading with the `legacy-codecs`
+    /// feature enabled.
+    Shrink,
+    /// The legacy Reduce method used by PKZIP 1.x, holding its compression factor (1 to 4). Entries using it may be
+    /// listed, but not read or written.
+    Reduce(u8),
+    /// The legacy Implode method used by PKZIP 1.x, which is only supported when reading with the `legacy-codecs`
+    /// feature enabled.
+    Implode,
+    /// A compression method which isn't supported by this crate (or whose feature isn't enabled), holding its raw
+    /// value. Entries using it may be listed, but not read or written.
+    Unknown(u16),
 }
 
-impl TryFrom<u16> for Compression {
-    type Error = ZipError;
+impl Compression {
+    /// Returns the name of the cargo feature which would add support for this compression method, if it's one this
+    /// crate supports but whose feature isn't enabled.
+    pub fn required_feature(&self) -> Option<&'static str> {
+        match self {
+            Compression::Unknown(8) => Some("deflate"),
+            Compression::Unknown(9) => Some("deflate64"),
+            Compression::Unknown(12) => Some("bzip2"),
+            Compression::Unknown(14) => Some("lzma"),
+            Compression::Unknown(93) => Some("zstd"),
+            Compression::Unknown(95) => Some("xz"),
+            Compression::Shrink | Compression::Implode if !cfg!(feature = "legacy-codecs") => Some("legacy-codecs"),
+            _ => None,
+        }
+    }
+
+    // Returns whether or not entries using this compression method can be read with the enabled features.
+    pub(crate) fn readable(&self) -> bool {
+        match self {
+            Compression::Unknown(_) | Compression::Reduce(_) => false,
+            Compression::Shrink | Compression::Implode => cfg!(feature = "legacy-codecs"),
+            _ => true,
+        }
+    }
+}
 
-    // Convert a u16 stored with little endianness into a supported compression method.
+impl From<u16> for Compression {
+    // Convert a u16 stored with little endianness into a compression method, preserving any unsupported value.
     // https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#445
-    fn try_from(value: u16) -> Result<Self> {
+    fn from(value: u16) -> Self {
         match value {
-            0 => Ok(Compression::Stored),
+            0 => Compression::Stored,
+            1 => Compression::Shrink,
+            2..=5 => Compression::Reduce((value - 1) as u8),
+            6 => Compression::Implode,
             #[cfg(feature = "deflate")]
-            8 => Ok(Compression::Deflate),
+            8 => Compression::Deflate,
+            #[cfg(feature = "deflate64")]
+            9 => Compression::Deflate64,
             #[cfg(feature = "bzip2")]
-            12 => Ok(Compression::Bz),
+            12 => Compression::Bz,
             #[cfg(feature = "lzma")]
-            14 => Ok(Compression::Lzma),
+            14 => Compression::Lzma,
             #[cfg(feature = "zstd")]
-            93 => Ok(Compression::Zstd),
+            93 => Compression::Zstd,
             #[cfg(feature = "xz")]
-            95 => Ok(Compression::Xz),
-            _ => Err(ZipError::UnsupportedCompressionError(value)),
+            95 => Compression::Xz,
+            value => Compression::Unknown(value),
         }
     }
 }
@@ -48,8 +91,13 @@ impl From<&Compression> for u16 {
     fn from(compression: &Compression) -> u16 {
         match compression {
             Compression::Stored => 0,
+            Compression::Shrink => 1,
+            Compression::Reduce(factor) => u16::from(*factor) + 1,
+            Compression::Implode => 6,
             #[cfg(feature = "deflate")]
             Compression::Deflate => 8,
+            #[cfg(feature = "deflate64")]
+            Compression::Deflate64 => 9,
             #[cfg(feature = "bzip2")]
             Compression::Bz => 12,
             #[cfg(feature = "lzma")]
@@ -58,6 +106,7 @@ impl From<&Compression> for u16 {
             Compression::Zstd => 93,
             #[cfg(feature = "xz")]
             Compression::Xz => 95,
+            Compression::Unknown(value) => *value,
         }
     }
 }
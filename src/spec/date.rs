@@ -25,7 +25,16 @@ pub fn zip_date_to_chrono(date: u16, time: u16) -> DateTime<Utc> {
 }
 
 // Converts a `chrono` structure into a date and time stored in ZIP headers.
+//
+// MS-DOS dates can only represent the years 1980 to 2107, so dates outside that range are clamped to its bounds.
 pub fn chrono_to_zip_time(dt: &DateTime<Utc>) -> (u16, u16) {
+    if dt.date().year() < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    if dt.date().year() > 2107 {
+        return ((23 << 11) | (59 << 5) | 29, (127 << 9) | (12 << 5) | 31);
+    }
+
     let year: u16 = (((dt.date().year() - 1980) << 9) & 0xFE00).try_into().unwrap();
     let month: u16 = ((dt.date().month() << 5) & 0x1E0).try_into().unwrap();
     let day: u16 = (dt.date().day() & 0x1F).try_into().unwrap();
@@ -48,4 +57,22 @@ mod tests {
         let result_dt = zip_date_to_chrono(date, time);
         assert_eq!(result_dt, original_dt);
     }
+
+    #[test]
+    fn date_conversion_clamp_test() {
+        // 1969-07-20 20:17:41 is clamped to the start of 1980, and 2200-01-01 to the end of 2107.
+        let (time, date) = chrono_to_zip_time(&Utc.timestamp_opt(-14_182_939, 0).unwrap());
+        assert_eq!(zip_date_to_chrono(date, time), Utc.timestamp_opt(315_532_800, 0).unwrap());
+
+        let (time, date) = chrono_to_zip_time(&Utc.timestamp_opt(7_258_118_400, 0).unwrap());
+        assert_eq!(zip_date_to_chrono(date, time), Utc.timestamp_opt(4_354_819_198, 0).unwrap());
+    }
+
+    #[test]
+    fn date_conversion_invalid_test() {
+        // Month 15 day 0, and 31:63:62, are both out of range so are treated as absent rather than panicking.
+        assert_eq!(zip_date_to_chrono(0x01E0, 0x0821), DateTime::<Utc>::MIN_UTC);
+        assert_eq!(zip_date_to_chrono(0x0021, 0xFFFF), DateTime::<Utc>::MIN_UTC);
+        assert_eq!(zip_date_to_chrono(0xFFFF, 0xFFFF), DateTime::<Utc>::MIN_UTC);
+    }
 }
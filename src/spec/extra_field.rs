@@ -0,0 +1,319 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use chrono::{DateTime, TimeZone, Utc};
+
+// https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#45
+
+// https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#453
+pub(crate) const ZIP64_EXTENDED_INFORMATION: u16 = 0x0001;
+
+// The header ID used by Android's zipalign tool for padding, holding the alignment followed by zeroed bytes.
+pub(crate) const ALIGNMENT_PADDING: u16 = 0xD935;
+
+// The header ID of Info-ZIP's Unicode path extra field, which other readers prefer over the recorded filename.
+pub(crate) const UNICODE_PATH: u16 = 0x7075;
+
+// The header ID of the NTFS extra field, holding the modification, access, and creation times as Windows FILETIMEs.
+pub(crate) const NTFS: u16 = 0x000A;
+
+// The header ID of Info-ZIP's extended timestamp extra field, holding whole seconds since the Unix epoch.
+pub(crate) const EXTENDED_TIMESTAMP: u16 = 0x5455;
+
+// The header ID of WinZip's AES extra field, which accompanies entries encrypted with AES.
+pub(crate) const AES_ENCRYPTION: u16 = 0x9901;
+
+// The compression method recorded for entries encrypted with AES, whose actual method is held by the AES extra field.
+pub(crate) const AES_COMPRESSION: u16 = 99;
+
+// The number of seconds between the FILETIME epoch (1601-01-01) and the Unix epoch.
+const FILETIME_UNIX_OFFSET: i64 = 11_644_473_600;
+
+/// A single record within an entry's extra field block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraField {
+    pub(crate) header_id: u16,
+    pub(crate) data: Vec<u8>,
+}
+
+impl ExtraField {
+    /// Constructs a new extra field record from its header ID and data.
+    pub fn new(header_id: u16, data: Vec<u8>) -> Self {
+        Self { header_id, data }
+    }
+
+    /// Returns the record's header ID.
+    pub fn header_id(&self) -> u16 {
+        self.header_id
+    }
+
+    /// Returns the record's data (excluding the header ID and length prefix).
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    // Append the serialised record (header ID, length, and data) to a buffer.
+    pub(crate) fn write_to(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&self.header_id.to_le_bytes());
+        buffer.extend_from_slice(&(self.data.len() as u16).to_le_bytes());
+        buffer.extend_from_slice(&self.data);
+    }
+}
+
+// Parse a raw extra field block into its records.
+//
+// Parsing stops at the first record whose declared length overruns the block, so a malformed tail is dropped rather
+// than failing the entry as a whole (the raw block remains available to callers).
+pub(crate) fn parse_extra_fields(mut data: &[u8]) -> Vec<ExtraField> {
+    let mut fields = Vec::new();
+
+    while data.len() >= 4 {
+        let header_id = u16::from_le_bytes([data[0], data[1]]);
+        let length = u16::from_le_bytes([data[2], data[3]]) as usize;
+
+        if data.len() - 4 < length {
+            break;
+        }
+
+        fields.push(ExtraField { header_id, data: data[4..4 + length].to_vec() });
+        data = &data[4 + length..];
+    }
+
+    fields
+}
+
+// Resolve the values of a header's fields which may be deferred to a ZIP64 extended information record, being (in this
+// order) the uncompressed size, the compressed size, and the offset of the local file header.
+//
+// Only those values saturated at u32::MAX are deferred, and the record holds an 8-byte value for each of them alone, so
+// any others are left as-is (as are all values if the record is absent or too short).
+pub(crate) fn resolve_zip64(fields: &[ExtraField], values: &mut [u64]) {
+    let data = match fields.iter().find(|field| field.header_id == ZIP64_EXTENDED_INFORMATION) {
+        Some(field) => &field.data,
+        None => return,
+    };
+
+    let mut chunks = data.chunks_exact(8);
+    for value in values.iter_mut().filter(|value| **value == u64::from(u32::MAX)) {
+        match chunks.next() {
+            Some(chunk) => *value = u64::from_le_bytes(chunk.try_into().unwrap()),
+            None => break,
+        }
+    }
+}
+
+// Parse the UTF-8 filename from an Info-ZIP Unicode path record, provided that its CRC32 value matches the filename
+// recorded within the header (otherwise the record is stale, eg. as the entry was renamed by a tool unaware of it).
+pub(crate) fn parse_unicode_path(fields: &[ExtraField], filename: &[u8]) -> Option<String> {
+    let data = &fields.iter().find(|field| field.header_id == UNICODE_PATH)?.data;
+    if data.len() < 5 || data[0] != 1 {
+        return None;
+    }
+
+    let crc = u32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+    match crc == crc32fast::hash(filename) {
+        true => String::from_utf8(data[5..].to_vec()).ok(),
+        false => None,
+    }
+}
+
+// Parse the vendor version (1 for AE-1, or 2 for AE-2), key strength (1 to 3, for 128, 192, and 256-bit keys), and
+// actual compression method (in that order) from an AES record.
+pub(crate) fn parse_aes(data: &[u8]) -> Option<(u16, u8, u16)> {
+    if data.len() < 7 || &data[2..4] != b"AE" {
+        return None;
+    }
+
+    let version = u16::from_le_bytes([data[0], data[1]]);
+    let strength = data[4];
+
+    if !(1..=2).contains(&version) || !(1..=3).contains(&strength) {
+        return None;
+    }
+
+    Some((version, strength, u16::from_le_bytes([data[5], data[6]])))
+}
+
+// Parse the modification, access, and creation times (in that order) from an extended timestamp record.
+//
+// The flags byte denotes which times are present, but the central directory's copy of the record conventionally only
+// holds the modification time whilst keeping the local file header's flags, so only times which fit are returned.
+pub(crate) fn parse_extended_timestamp(data: &[u8]) -> [Option<DateTime<Utc>>; 3] {
+    let mut times = [None; 3];
+    let (flags, mut values) = match data.split_first() {
+        Some((flags, values)) => (*flags, values),
+        None => return times,
+    };
+
+    for (bit, time) in times.iter_mut().enumerate() {
+        if flags & (1 << bit) == 0 || values.len() < 4 {
+            continue;
+        }
+
+        let seconds = i32::from_le_bytes([values[0], values[1], values[2], values[3]]);
+        *time = Utc.timestamp_opt(seconds.into(), 0).single();
+        values = &values[4..];
+    }
+
+    times
+}
+
+// Serialise the data of an extended timestamp record holding any of the provided times which it can represent.
+//
+// `None` is returned if there are none, as the record only holds whole seconds within the range of an `i32`.
+pub(crate) fn extended_timestamp_data(times: &[Option<DateTime<Utc>>; 3]) -> Option<Vec<u8>> {
+    let mut data = vec![0];
+
+    for (bit, time) in times.iter().enumerate() {
+        if let Some(seconds) = time.and_then(|time| i32::try_from(time.timestamp()).ok()) {
+            data[0] |= 1 << bit;
+            data.extend_from_slice(&seconds.to_le_bytes());
+        }
+    }
+
+    (data[0] != 0).then_some(data)
+}
+
+// Returns the modification time held by an extended timestamp record amongst an entry's extra fields, if any.
+pub(crate) fn extended_modification_time(fields: &[ExtraField]) -> Option<DateTime<Utc>> {
+    let field = fields.iter().find(|field| field.header_id == EXTENDED_TIMESTAMP)?;
+    parse_extended_timestamp(&field.data)[0]
+}
+
+// Parse the modification, access, and creation times (in that order) from an NTFS record.
+//
+// The record holds a reserved field followed by tagged attributes, of which only the timestamps attribute (tag 1) is
+// defined. Zeroed FILETIMEs are treated as absent.
+pub(crate) fn parse_ntfs(data: &[u8]) -> [Option<DateTime<Utc>>; 3] {
+    let mut attributes = data.get(4..).unwrap_or_default();
+
+    while attributes.len() >= 4 {
+        let tag = u16::from_le_bytes([attributes[0], attributes[1]]);
+        let size = u16::from_le_bytes([attributes[2], attributes[3]]) as usize;
+        let value = match attributes.get(4..4 + size) {
+            Some(value) => value,
+            None => break,
+        };
+
+        if tag == 0x0001 && size >= 24 {
+            let filetime = |index: usize| u64::from_le_bytes(value[index * 8..index * 8 + 8].try_into().unwrap());
+            return [0, 1, 2].map(|index| filetime_to_chrono(filetime(index)));
+        }
+
+        attributes = &attributes[4 + size..];
+    }
+
+    [None; 3]
+}
+
+// Serialise the data of an NTFS record holding the provided times, with any which are absent zeroed.
+pub(crate) fn ntfs_data(times: &[Option<DateTime<Utc>>; 3]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(32);
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(&0x0001u16.to_le_bytes());
+    data.extend_from_slice(&24u16.to_le_bytes());
+
+    for time in times {
+        data.extend_from_slice(&time.as_ref().map_or(0, chrono_to_filetime).to_le_bytes());
+    }
+
+    data
+}
+
+// FILETIMEs count 100-nanosecond intervals since the start of 1601.
+fn filetime_to_chrono(filetime: u64) -> Option<DateTime<Utc>> {
+    if filetime == 0 {
+        return None;
+    }
+
+    let seconds = (filetime / 10_000_000) as i64 - FILETIME_UNIX_OFFSET;
+    let nanos = (filetime % 10_000_000) as u32 * 100;
+    Utc.timestamp_opt(seconds, nanos).single()
+}
+
+// Times before 1601 (or too far beyond it) can't be represented, so are saturated.
+fn chrono_to_filetime(time: &DateTime<Utc>) -> u64 {
+    let seconds = time.timestamp().saturating_add(FILETIME_UNIX_OFFSET);
+    let intervals = i128::from(seconds) * 10_000_000 + i128::from(time.timestamp_subsec_nanos() / 100);
+    intervals.clamp(1, u64::MAX.into()) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    #[test]
+    fn extra_field_parse_test() {
+        let mut raw = Vec::new();
+        ExtraField::new(0xCAFE, vec![1, 2, 3]).write_to(&mut raw);
+        ExtraField::new(0x000A, Vec::new()).write_to(&mut raw);
+
+        assert_eq!(
+            parse_extra_fields(&raw),
+            vec![ExtraField::new(0xCAFE, vec![1, 2, 3]), ExtraField::new(0x000A, vec![])]
+        );
+
+        // A record claiming more data than is present should be dropped without affecting the preceding records.
+        raw.extend_from_slice(&[0x01, 0x00, 0xFF, 0x00, 0x00]);
+        assert_eq!(parse_extra_fields(&raw).len(), 2);
+    }
+
+    #[test]
+    fn unicode_path_parse_test() {
+        let mut data = vec![1];
+        data.extend_from_slice(&crc32fast::hash(b"caf\x82.txt").to_le_bytes());
+        data.extend_from_slice("café.txt".as_bytes());
+        let fields = [ExtraField::new(UNICODE_PATH, data.clone())];
+
+        assert_eq!(parse_unicode_path(&fields, b"caf\x82.txt").as_deref(), Some("café.txt"));
+        assert_eq!(parse_unicode_path(&fields, b"renamed.txt"), None);
+
+        // Only version 1 of the record is defined.
+        data[0] = 2;
+        assert_eq!(parse_unicode_path(&[ExtraField::new(UNICODE_PATH, data)], b"caf\x82.txt"), None);
+    }
+
+    #[test]
+    fn timestamp_fields_parse_test() {
+        let modified = Utc.timestamp_opt(1_666_544_103, 0).unwrap();
+        let accessed = Utc.timestamp_opt(-86_400, 0).unwrap();
+        let created = Utc.timestamp_opt(1_000_000_000, 123_456_700).unwrap();
+        let times = [Some(modified), Some(accessed), Some(created)];
+
+        let extended = extended_timestamp_data(&times).unwrap();
+        assert_eq!(extended[0], 0b111);
+        let whole_seconds = created.with_nanosecond(0);
+        assert_eq!(parse_extended_timestamp(&extended), [Some(modified), Some(accessed), whole_seconds]);
+
+        // A central directory record keeps the local flags but only holds the modification time.
+        assert_eq!(parse_extended_timestamp(&extended[..5]), [Some(modified), None, None]);
+        assert_eq!(parse_ntfs(&ntfs_data(&times)), times);
+        assert_eq!(parse_ntfs(&ntfs_data(&[Some(modified), None, None])), [Some(modified), None, None]);
+
+        // Times beyond the range of an `i32` are omitted from the extended timestamp record.
+        let distant = Utc.timestamp_opt(i64::from(i32::MAX) + 1, 0).unwrap();
+        assert_eq!(extended_timestamp_data(&[Some(distant), None, None]), None);
+        assert_eq!(parse_ntfs(&[0, 0, 0, 0, 1, 0, 24]), [None; 3]);
+    }
+
+    #[test]
+    fn zip64_resolve_test() {
+        let saturated = u64::from(u32::MAX);
+        let mut data = 5_000_000_000u64.to_le_bytes().to_vec();
+        data.extend_from_slice(&6_000_000_000u64.to_le_bytes());
+        let fields = vec![ExtraField::new(ZIP64_EXTENDED_INFORMATION, data)];
+
+        // Only saturated values are deferred to the record, which holds their values in order.
+        let mut values = [saturated, 1024, saturated];
+        resolve_zip64(&fields, &mut values);
+        assert_eq!(values, [5_000_000_000, 1024, 6_000_000_000]);
+
+        // Values without a counterpart within the record (or without a record at all) are left as-is.
+        let mut values = [saturated; 3];
+        resolve_zip64(&fields[..], &mut values);
+        assert_eq!(values, [5_000_000_000, 6_000_000_000, saturated]);
+        resolve_zip64(&[], &mut values[2..]);
+        assert_eq!(values[2], saturated);
+    }
+}
@@ -1,7 +1,14 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
+//! A module which holds the fixed-length portion of each ZIP file record.
+//!
+//! Each record may be parsed from and serialised to bytes via its `from_bytes()` and `to_bytes()` methods, both of
+//! which include the record's signature.
+
 // https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#437
+/// A local file header, which directly precedes each entry's filename, extra field, and data.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LocalFileHeader {
     pub version: u16,
     pub flags: GeneralPurposeFlag,
@@ -16,14 +23,54 @@ pub struct LocalFileHeader {
 }
 
 // https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#444
-#[derive(Copy, Clone)]
+/// The general purpose bit flags of an entry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct GeneralPurposeFlag {
-    pub encrypted: bool,
-    pub data_descriptor: bool,
-    pub filename_unicode: bool,
+    pub(crate) encrypted: bool,
+    pub(crate) data_descriptor: bool,
+    pub(crate) patched_data: bool,
+    pub(crate) strong_encryption: bool,
+    pub(crate) filename_unicode: bool,
+    pub(crate) compression_options: u8,
+}
+
+impl GeneralPurposeFlag {
+    /// Returns whether the entry is encrypted.
+    pub fn encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    /// Returns whether the entry's CRC32 value and sizes follow its data within a data descriptor.
+    pub fn data_descriptor(&self) -> bool {
+        self.data_descriptor
+    }
+
+    /// Returns whether the entry holds compressed patched data.
+    pub fn patched_data(&self) -> bool {
+        self.patched_data
+    }
+
+    /// Returns whether the entry is encrypted using PKWARE's strong encryption.
+    pub fn strong_encryption(&self) -> bool {
+        self.strong_encryption
+    }
+
+    /// Returns whether the entry's filename and comment are encoded with UTF-8.
+    pub fn filename_unicode(&self) -> bool {
+        self.filename_unicode
+    }
+
+    /// Returns bits 1 and 2 of the flags, whose meaning is specific to the entry's compression method (eg. the size of
+    /// the sliding dictionary and the number of trees used by Implode).
+    pub fn compression_options(&self) -> u8 {
+        self.compression_options
+    }
 }
 
 // https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#4312
+/// A central directory file header, which directly precedes each entry's filename, extra field, and comment within the
+/// central directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CentralDirectoryHeader {
     pub v_made_by: u16,
     pub v_needed: u16,
@@ -44,12 +91,38 @@ pub struct CentralDirectoryHeader {
 }
 
 // https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#4316
+/// An end of central directory header, which directly precedes the ZIP file's comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EndOfCentralDirectoryHeader {
-    pub(crate) disk_num: u16,
-    pub(crate) start_cent_dir_disk: u16,
-    pub(crate) num_of_entries_disk: u16,
-    pub(crate) num_of_entries: u16,
-    pub(crate) size_cent_dir: u32,
-    pub(crate) cent_dir_offset: u32,
-    pub(crate) file_comm_length: u16,
+    pub disk_num: u16,
+    pub start_cent_dir_disk: u16,
+    pub num_of_entries_disk: u16,
+    pub num_of_entries: u16,
+    pub size_cent_dir: u32,
+    pub cent_dir_offset: u32,
+    pub file_comm_length: u16,
+}
+
+// https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#4314
+/// The fixed-length portion of a ZIP64 end of central directory record, which holds the full-width counterparts of the
+/// end of central directory header's fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Zip64EndOfCentralDirectoryRecord {
+    pub v_made_by: u16,
+    pub v_needed: u16,
+    pub disk_num: u32,
+    pub start_cent_dir_disk: u32,
+    pub num_of_entries_disk: u64,
+    pub num_of_entries: u64,
+    pub size_cent_dir: u64,
+    pub cent_dir_offset: u64,
+}
+
+// https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#4315
+/// A ZIP64 end of central directory locator, which directly precedes the end of central directory header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Zip64EndOfCentralDirectoryLocator {
+    pub start_eocdr_disk: u32,
+    pub eocdr_offset: u64,
+    pub total_disks: u32,
 }
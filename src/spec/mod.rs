@@ -1,10 +1,32 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
+//! A module which exposes the ZIP file records themselves, for working with them outside of this crate's readers and
+//! writers (eg. within a fuzzer, or where tokio isn't available).
+//!
+//! # Note
+//! This is a lower-level API than the rest of this crate. The record types mirror the fixed-length portion of each
+//! record within the [specification](https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md), so no
+//! validation is performed beyond checking signatures and lengths, and the variable-length data which follows each
+//! record (eg. filenames) is left to the caller.
+//!
+//! # Example
+//! ```
+//! # use async_zip::spec::header::LocalFileHeader;
+//! # use async_zip::error::ZipError;
+//! #
+//! # fn run(bytes: &[u8]) -> Result<(), ZipError> {
+//! let (header, consumed) = LocalFileHeader::from_bytes(bytes)?;
+//! let filename = &bytes[consumed..consumed + header.file_name_length as usize];
+//! #   Ok(())
+//! # }
+//! ```
+
 pub(crate) mod compression;
 pub(crate) mod date;
-pub(crate) mod header;
+pub(crate) mod extra_field;
+pub mod header;
 pub(crate) mod parse;
-pub(crate) mod signature;
+pub mod signature;
 pub(crate) mod version;
 pub(crate) mod attribute;
\ No newline at end of file
@@ -1,13 +1,32 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
-use crate::error::Result;
-use crate::spec::header::{CentralDirectoryHeader, EndOfCentralDirectoryHeader, GeneralPurposeFlag, LocalFileHeader};
+use crate::error::{Result, ZipError};
+use crate::spec::header::{
+    CentralDirectoryHeader, EndOfCentralDirectoryHeader, GeneralPurposeFlag, LocalFileHeader,
+    Zip64EndOfCentralDirectoryLocator, Zip64EndOfCentralDirectoryRecord,
+};
+use crate::spec::signature;
 
 use tokio::io::{AsyncRead, AsyncReadExt};
 
 impl LocalFileHeader {
-    pub fn as_slice(&self) -> [u8; 26] {
+    /// The length of a serialised header, including its signature.
+    pub const LENGTH: usize = 30;
+
+    /// Parses a header (including its signature) from the start of a slice, returning it alongside the number of
+    /// bytes consumed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<(LocalFileHeader, usize)> {
+        let fixed: [u8; 26] = strip_signature(bytes, signature::LOCAL_FILE_HEADER)?;
+        Ok((LocalFileHeader::from(fixed), Self::LENGTH))
+    }
+
+    /// Serialises this header, including its signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        with_signature(signature::LOCAL_FILE_HEADER, &self.as_slice())
+    }
+
+    pub(crate) fn as_slice(&self) -> [u8; 26] {
         let mut array = [0; 26];
         let mut cursor = 0;
 
@@ -36,17 +55,43 @@ impl GeneralPurposeFlag {
             false => 0x0,
             true => 0x8,
         };
+        let patched_data: u16 = match self.patched_data {
+            false => 0x0,
+            true => 0x20,
+        };
+        let strong_encryption: u16 = match self.strong_encryption {
+            false => 0x0,
+            true => 0x40,
+        };
         let filename_unicode: u16 = match self.filename_unicode {
             false => 0x0,
             true => 0x800,
         };
 
-        (encrypted | data_descriptor | filename_unicode).to_le_bytes()
+        let compression_options = u16::from(self.compression_options & 0b11) << 1;
+
+        (encrypted | compression_options | data_descriptor | patched_data | strong_encryption | filename_unicode)
+            .to_le_bytes()
     }
 }
 
 impl CentralDirectoryHeader {
-    pub fn as_slice(&self) -> [u8; 42] {
+    /// The length of a serialised header, including its signature.
+    pub const LENGTH: usize = 46;
+
+    /// Parses a header (including its signature) from the start of a slice, returning it alongside the number of
+    /// bytes consumed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<(CentralDirectoryHeader, usize)> {
+        let fixed: [u8; 42] = strip_signature(bytes, signature::CENTRAL_DIRECTORY_FILE_HEADER)?;
+        Ok((CentralDirectoryHeader::from(fixed), Self::LENGTH))
+    }
+
+    /// Serialises this header, including its signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        with_signature(signature::CENTRAL_DIRECTORY_FILE_HEADER, &self.as_slice())
+    }
+
+    pub(crate) fn as_slice(&self) -> [u8; 42] {
         let mut array = [0; 42];
         let mut cursor = 0;
 
@@ -72,7 +117,22 @@ impl CentralDirectoryHeader {
 }
 
 impl EndOfCentralDirectoryHeader {
-    pub fn as_slice(&self) -> [u8; 18] {
+    /// The length of a serialised header, including its signature.
+    pub const LENGTH: usize = 22;
+
+    /// Parses a header (including its signature) from the start of a slice, returning it alongside the number of
+    /// bytes consumed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<(EndOfCentralDirectoryHeader, usize)> {
+        let fixed: [u8; 18] = strip_signature(bytes, signature::END_OF_CENTRAL_DIRECTORY)?;
+        Ok((EndOfCentralDirectoryHeader::from(fixed), Self::LENGTH))
+    }
+
+    /// Serialises this header, including its signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        with_signature(signature::END_OF_CENTRAL_DIRECTORY, &self.as_slice())
+    }
+
+    pub(crate) fn as_slice(&self) -> [u8; 18] {
         let mut array = [0; 18];
         let mut cursor = 0;
 
@@ -88,6 +148,71 @@ impl EndOfCentralDirectoryHeader {
     }
 }
 
+impl Zip64EndOfCentralDirectoryRecord {
+    /// The length of a serialised record without any extensible data, including its signature.
+    pub const LENGTH: usize = 56;
+
+    /// Parses a record (including its signature) from the start of a slice, returning it alongside the number of
+    /// bytes consumed.
+    ///
+    /// Any extensible data following the fixed-length portion of the record is left unconsumed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Zip64EndOfCentralDirectoryRecord, usize)> {
+        let fixed: [u8; 52] = strip_signature(bytes, signature::ZIP64_END_OF_CENTRAL_DIRECTORY)?;
+        Ok((Zip64EndOfCentralDirectoryRecord::from(fixed), Self::LENGTH))
+    }
+
+    /// Serialises this record without any extensible data, including its signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        with_signature(signature::ZIP64_END_OF_CENTRAL_DIRECTORY, &self.as_slice())
+    }
+
+    pub(crate) fn as_slice(&self) -> [u8; 52] {
+        let mut array = [0; 52];
+        let mut cursor = 0;
+
+        // The size of the remaining record, excluding the signature and this field itself.
+        array_push!(array, cursor, (Self::LENGTH as u64 - 12).to_le_bytes());
+        array_push!(array, cursor, self.v_made_by.to_le_bytes());
+        array_push!(array, cursor, self.v_needed.to_le_bytes());
+        array_push!(array, cursor, self.disk_num.to_le_bytes());
+        array_push!(array, cursor, self.start_cent_dir_disk.to_le_bytes());
+        array_push!(array, cursor, self.num_of_entries_disk.to_le_bytes());
+        array_push!(array, cursor, self.num_of_entries.to_le_bytes());
+        array_push!(array, cursor, self.size_cent_dir.to_le_bytes());
+        array_push!(array, cursor, self.cent_dir_offset.to_le_bytes());
+
+        array
+    }
+}
+
+impl Zip64EndOfCentralDirectoryLocator {
+    /// The length of a serialised locator, including its signature.
+    pub const LENGTH: usize = 20;
+
+    /// Parses a locator (including its signature) from the start of a slice, returning it alongside the number of
+    /// bytes consumed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Zip64EndOfCentralDirectoryLocator, usize)> {
+        let fixed: [u8; 16] = strip_signature(bytes, signature::ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR)?;
+        Ok((Zip64EndOfCentralDirectoryLocator::from(fixed), Self::LENGTH))
+    }
+
+    /// Serialises this locator, including its signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        with_signature(signature::ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR, &self.as_slice())
+    }
+
+    pub(crate) fn as_slice(&self) -> [u8; 16] {
+        let mut array = [0; 16];
+        let mut cursor = 0;
+
+        array_push!(array, cursor, self.start_eocdr_disk.to_le_bytes());
+        array_push!(array, cursor, self.eocdr_offset.to_le_bytes());
+        array_push!(array, cursor, self.total_disks.to_le_bytes());
+
+        array
+    }
+}
+
 impl From<[u8; 26]> for LocalFileHeader {
     fn from(value: [u8; 26]) -> LocalFileHeader {
         LocalFileHeader {
@@ -109,9 +234,19 @@ impl From<u16> for GeneralPurposeFlag {
     fn from(value: u16) -> GeneralPurposeFlag {
         let encrypted = !matches!(value & 0x1, 0);
         let data_descriptor = !matches!((value & 0x8) >> 3, 0);
+        let patched_data = !matches!((value & 0x20) >> 5, 0);
+        let strong_encryption = !matches!((value & 0x40) >> 6, 0);
         let filename_unicode = !matches!((value & 0x800) >> 11, 0);
+        let compression_options = ((value & 0x6) >> 1) as u8;
 
-        GeneralPurposeFlag { encrypted, data_descriptor, filename_unicode }
+        GeneralPurposeFlag {
+            encrypted,
+            data_descriptor,
+            patched_data,
+            strong_encryption,
+            filename_unicode,
+            compression_options,
+        }
     }
 }
 
@@ -152,8 +287,54 @@ impl From<[u8; 18]> for EndOfCentralDirectoryHeader {
     }
 }
 
+impl From<[u8; 52]> for Zip64EndOfCentralDirectoryRecord {
+    fn from(value: [u8; 52]) -> Zip64EndOfCentralDirectoryRecord {
+        Zip64EndOfCentralDirectoryRecord {
+            v_made_by: u16::from_le_bytes(value[8..10].try_into().unwrap()),
+            v_needed: u16::from_le_bytes(value[10..12].try_into().unwrap()),
+            disk_num: u32::from_le_bytes(value[12..16].try_into().unwrap()),
+            start_cent_dir_disk: u32::from_le_bytes(value[16..20].try_into().unwrap()),
+            num_of_entries_disk: u64::from_le_bytes(value[20..28].try_into().unwrap()),
+            num_of_entries: u64::from_le_bytes(value[28..36].try_into().unwrap()),
+            size_cent_dir: u64::from_le_bytes(value[36..44].try_into().unwrap()),
+            cent_dir_offset: u64::from_le_bytes(value[44..52].try_into().unwrap()),
+        }
+    }
+}
+
+impl From<[u8; 16]> for Zip64EndOfCentralDirectoryLocator {
+    fn from(value: [u8; 16]) -> Zip64EndOfCentralDirectoryLocator {
+        Zip64EndOfCentralDirectoryLocator {
+            start_eocdr_disk: u32::from_le_bytes(value[0..4].try_into().unwrap()),
+            eocdr_offset: u64::from_le_bytes(value[4..12].try_into().unwrap()),
+            total_disks: u32::from_le_bytes(value[12..16].try_into().unwrap()),
+        }
+    }
+}
+
+// Check a record's signature at the start of a slice, returning the fixed-length portion which follows it.
+fn strip_signature<const N: usize>(bytes: &[u8], expected: u32) -> Result<[u8; N]> {
+    if bytes.len() < 4 + N {
+        return Err(ZipError::TruncatedRecord { expected: 4 + N, found: bytes.len() });
+    }
+
+    let found = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+    if found != expected {
+        return Err(ZipError::UnexpectedSignature { offset: None, expected, found });
+    }
+
+    Ok(bytes[4..4 + N].try_into().unwrap())
+}
+
+fn with_signature(signature: u32, fixed: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(4 + fixed.len());
+    bytes.extend_from_slice(&signature.to_le_bytes());
+    bytes.extend_from_slice(fixed);
+    bytes
+}
+
 impl LocalFileHeader {
-    pub async fn from_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<LocalFileHeader> {
+    pub(crate) async fn from_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<LocalFileHeader> {
         let mut buffer: [u8; 26] = [0; 26];
         reader.read_exact(&mut buffer).await?;
         Ok(LocalFileHeader::from(buffer))
@@ -161,7 +342,7 @@ impl LocalFileHeader {
 }
 
 impl EndOfCentralDirectoryHeader {
-    pub async fn from_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<EndOfCentralDirectoryHeader> {
+    pub(crate) async fn from_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<EndOfCentralDirectoryHeader> {
         let mut buffer: [u8; 18] = [0; 18];
         reader.read_exact(&mut buffer).await?;
         Ok(EndOfCentralDirectoryHeader::from(buffer))
@@ -169,7 +350,7 @@ impl EndOfCentralDirectoryHeader {
 }
 
 impl CentralDirectoryHeader {
-    pub async fn from_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<CentralDirectoryHeader> {
+    pub(crate) async fn from_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<CentralDirectoryHeader> {
         let mut buffer: [u8; 42] = [0; 42];
         reader.read_exact(&mut buffer).await?;
         Ok(CentralDirectoryHeader::from(buffer))
@@ -187,3 +368,86 @@ macro_rules! array_push {
 }
 
 pub(crate) use array_push;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_file_header_codec_test() {
+        let header = LocalFileHeader {
+            version: 20,
+            flags: GeneralPurposeFlag::from(0x808),
+            compression: 8,
+            mod_time: 0x1234,
+            mod_date: 0x21,
+            crc: 0xDEADBEEF,
+            compressed_size: 10,
+            uncompressed_size: 20,
+            file_name_length: 7,
+            extra_field_length: 0,
+        };
+
+        let mut bytes = header.to_bytes();
+        assert_eq!(LocalFileHeader::LENGTH, bytes.len());
+
+        // Any trailing data is left unconsumed.
+        bytes.extend_from_slice(b"foo.bar");
+        assert_eq!(header, LocalFileHeader::from_bytes(&bytes).unwrap().0);
+        assert_eq!(LocalFileHeader::LENGTH, LocalFileHeader::from_bytes(&bytes).unwrap().1);
+
+        assert!(matches!(
+            LocalFileHeader::from_bytes(&bytes[..29]),
+            Err(ZipError::TruncatedRecord { expected: 30, found: 29 })
+        ));
+        assert!(matches!(
+            CentralDirectoryHeader::from_bytes(&[bytes.clone(), vec![0; 16]].concat()),
+            Err(ZipError::UnexpectedSignature { .. })
+        ));
+    }
+
+    #[test]
+    fn end_of_central_directory_codec_test() {
+        let header = EndOfCentralDirectoryHeader {
+            disk_num: 0,
+            start_cent_dir_disk: 0,
+            num_of_entries_disk: 2,
+            num_of_entries: 2,
+            size_cent_dir: 100,
+            cent_dir_offset: 200,
+            file_comm_length: 3,
+        };
+
+        let bytes = header.to_bytes();
+        assert_eq!(&signature::END_OF_CENTRAL_DIRECTORY.to_le_bytes(), &bytes[0..4]);
+        let parsed = EndOfCentralDirectoryHeader::from_bytes(&bytes).unwrap();
+        assert_eq!((header, EndOfCentralDirectoryHeader::LENGTH), parsed);
+    }
+
+    #[test]
+    fn zip64_end_of_central_directory_codec_test() {
+        let record = Zip64EndOfCentralDirectoryRecord {
+            v_made_by: 45,
+            v_needed: 45,
+            disk_num: 0,
+            start_cent_dir_disk: 0,
+            num_of_entries_disk: 70_000,
+            num_of_entries: 70_000,
+            size_cent_dir: 5_000_000,
+            cent_dir_offset: 6_000_000_000,
+        };
+
+        let bytes = record.to_bytes();
+        assert_eq!(Zip64EndOfCentralDirectoryRecord::LENGTH, bytes.len());
+        assert_eq!(44, u64::from_le_bytes(bytes[4..12].try_into().unwrap()));
+        let parsed = Zip64EndOfCentralDirectoryRecord::from_bytes(&bytes).unwrap();
+        assert_eq!((record, Zip64EndOfCentralDirectoryRecord::LENGTH), parsed);
+
+        let locator =
+            Zip64EndOfCentralDirectoryLocator { start_eocdr_disk: 0, eocdr_offset: 6_005_000_000, total_disks: 1 };
+        let bytes = locator.to_bytes();
+        assert_eq!(Zip64EndOfCentralDirectoryLocator::LENGTH, bytes.len());
+        let parsed = Zip64EndOfCentralDirectoryLocator::from_bytes(&bytes).unwrap();
+        assert_eq!((locator, Zip64EndOfCentralDirectoryLocator::LENGTH), parsed);
+    }
+}
@@ -12,3 +12,12 @@ pub const DATA_DESCRIPTOR: u32 = 0x8074b50;
 
 // https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#4316
 pub const END_OF_CENTRAL_DIRECTORY: u32 = 0x6054b50;
+
+// https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#4314
+pub const ZIP64_END_OF_CENTRAL_DIRECTORY: u32 = 0x6064b50;
+
+// https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#4315
+pub const ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR: u32 = 0x7064b50;
+
+// https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#853
+pub const SPANNING: u32 = 0x8074b50;
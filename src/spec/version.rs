@@ -1,27 +1,92 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
-#[cfg(any(feature = "deflate", feature = "bzip2", feature = "zstd", feature = "lzma", feature = "xz"))]
+#[cfg(any(
+    feature = "deflate",
+    feature = "bzip2",
+    feature = "zstd",
+    feature = "lzma",
+    feature = "xz",
+    feature = "deflate64"
+))]
 use crate::spec::compression::Compression;
+use crate::spec::extra_field::{AES_ENCRYPTION, ZIP64_EXTENDED_INFORMATION};
+use crate::spec::header::GeneralPurposeFlag;
 use crate::entry::ZipEntry;
 
 pub(crate) const SPEC_VERSION_MADE_BY: u16 = 63;
 
+// The version needed to extract any entry (or ZIP file) which uses ZIP64.
+pub(crate) const ZIP64_VERSION_NEEDED: u16 = 45;
+
+/// A version of the ZIP specification, as stored within an entry's "version made by" & "version needed to extract"
+/// fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZipVersion {
+    pub(crate) major: u8,
+    pub(crate) minor: u8,
+}
+
+impl ZipVersion {
+    /// Constructs a new version from its major & minor parts (eg. 4.5 would be `ZipVersion::new(4, 5)`).
+    pub fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    /// Returns the version's major part.
+    pub fn major(&self) -> u8 {
+        self.major
+    }
+
+    /// Returns the version's minor part.
+    pub fn minor(&self) -> u8 {
+        self.minor
+    }
+
+    // The lower byte holds the version multiplied by ten, whereas the upper byte may hold a host compatibility.
+    pub(crate) fn from_raw(value: u16) -> Self {
+        let version = (value & 0xFF) as u8;
+        Self { major: version / 10, minor: version % 10 }
+    }
+}
+
 // https://github.com/Majored/rs-async-zip/blob/main/SPECIFICATION.md#443
-pub fn as_needed_to_extract(entry: &ZipEntry) -> u16 {
+//
+// The version is the highest of those required by each feature the entry uses, given the general purpose flags which
+// are written alongside it. Both the local file header and central directory record must hold the same value.
+pub fn as_needed_to_extract(entry: &ZipEntry, flags: &GeneralPurposeFlag) -> u16 {
     let mut version = match entry.compression() {
         #[cfg(feature = "deflate")]
         Compression::Deflate => 20,
+        #[cfg(feature = "deflate64")]
+        Compression::Deflate64 => 21,
         #[cfg(feature = "bzip2")]
         Compression::Bz => 46,
         #[cfg(feature = "lzma")]
         Compression::Lzma => 63,
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => 63,
+        #[cfg(feature = "xz")]
+        Compression::Xz => 63,
         _ => 10,
     };
 
-    if entry.filename().ends_with('/') {
+    // Directories, data descriptors, and traditional PKWARE encryption were all introduced alongside Deflate.
+    if entry.filename().ends_with('/') || flags.data_descriptor || flags.encrypted {
         version = std::cmp::max(version, 20);
     }
+    if flags.strong_encryption {
+        version = std::cmp::max(version, 50);
+    }
+
+    // AES encryption was introduced in version 5.1.
+    if entry.extra_fields().iter().any(|field| field.header_id() == AES_ENCRYPTION) {
+        version = std::cmp::max(version, 51);
+    }
+
+    if entry.extra_fields().iter().any(|field| field.header_id() == ZIP64_EXTENDED_INFORMATION) {
+        version = std::cmp::max(version, ZIP64_VERSION_NEEDED);
+    }
 
     version
 }
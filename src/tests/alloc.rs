@@ -0,0 +1,60 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A global allocator which tracks the bytes held by each thread, so that tests may bound their memory usage.
+//!
+//! Tests run on their own threads (and `#[tokio::test]` runtimes on the test's thread), so allocations made by other
+//! tests running concurrently aren't counted.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    static ALLOCATED: Cell<isize> = const { Cell::new(0) };
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+}
+
+struct TrackingAllocator;
+
+// Adjust the current thread's count, ignoring allocations made whilst its thread-local storage is being destroyed.
+fn track(change: isize) {
+    let _ = ALLOCATED.try_with(|allocated| {
+        allocated.set(allocated.get() + change);
+        let _ = PEAK.try_with(|peak| peak.set(std::cmp::max(peak.get(), allocated.get())));
+    });
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        track(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        track(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        track(new_size as isize - layout.size() as isize);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+/// Returns the number of bytes currently held by allocations made on this thread (less any it has since freed).
+pub(crate) fn allocated() -> isize {
+    ALLOCATED.with(Cell::get)
+}
+
+/// Returns the most bytes held by allocations made on this thread at once since [`reset_peak()`] was last called.
+pub(crate) fn peak() -> isize {
+    PEAK.with(Cell::get)
+}
+
+/// Resets the peak number of bytes held to the number currently held.
+pub(crate) fn reset_peak() {
+    PEAK.with(|peak| peak.set(allocated()));
+}
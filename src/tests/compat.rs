@@ -0,0 +1,244 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Differential tests against the synchronous `zip` crate, enabled via the `compat-tests` feature.
+//!
+//! Every fixture archive (see `fixtures/README.md`), along with randomly generated archives written by both this
+//! crate and the `zip` crate, is read by both libraries, which must agree on each entry's filename, sizes, CRC32
+//! value, last modification date, and data. Each generated archive is reproducible from its seed, which is included
+//! within any assertion message.
+//!
+//! Run with `cargo test --features compat-tests compat`.
+
+use crate::entry::builder::ZipEntryBuilder;
+use crate::entry::ZipEntry;
+use crate::read::mem;
+use crate::spec::compression::Compression;
+use crate::write::{DataDescriptorStyle, ZipFileWriter};
+
+use std::io::{Cursor, Read, Write};
+
+use chrono::{Datelike, Timelike};
+use tokio::io::AsyncWriteExt;
+
+const GENERATED_ARCHIVES: u64 = 64;
+
+// A xorshift generator for the random archives, so that each can be reproduced from its seed.
+struct Generator(u64);
+
+impl Generator {
+    fn new(seed: u64) -> Self {
+        Self(0x2545_F491_4F6C_DD1D ^ seed.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn chance(&mut self, denominator: u64) -> bool {
+        self.below(denominator) == 0
+    }
+
+    // Data which is either incompressible or highly repetitive, of up to 4 KiB.
+    fn data(&mut self) -> Vec<u8> {
+        let length = self.below(4097) as usize;
+
+        match self.chance(2) {
+            true => (0..length).map(|_| (self.next() >> 56) as u8).collect(),
+            false => b"Lorem ipsum dolor sit amet. ".iter().copied().cycle().take(length).collect(),
+        }
+    }
+
+    // A valid MS-DOS date and time, as (year, month, day, hour, minute, second).
+    fn date_time(&mut self) -> (u16, u8, u8, u8, u8, u8) {
+        let year = 1980 + self.below(100) as u16;
+        let month = 1 + self.below(12) as u8;
+        let day = 1 + self.below(28) as u8;
+
+        (year, month, day, self.below(24) as u8, self.below(60) as u8, 2 * self.below(30) as u8)
+    }
+
+    // A filename which is unique within its archive, occasionally containing non-ASCII characters.
+    fn filename(&mut self, index: usize, directory: bool) -> String {
+        let folder = self.below(4);
+
+        match (directory, self.chance(8)) {
+            (true, _) => format!("folder-{}/sub-{}/", folder, index),
+            (false, true) => format!("folder-{}/données-{}.txt", folder, index),
+            (false, false) => format!("folder-{}/entry-{}.bin", folder, index),
+        }
+    }
+}
+
+// The details of an entry which both libraries must agree on (other than its data).
+#[derive(Debug, PartialEq, Eq)]
+struct Summary {
+    filename: String,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    crc32: u32,
+    date_time: (u16, u8, u8, u8, u8, u8),
+}
+
+impl From<&ZipEntry> for Summary {
+    fn from(entry: &ZipEntry) -> Self {
+        let date = entry.last_modification_date();
+
+        Summary {
+            filename: entry.filename().to_owned(),
+            compressed_size: entry.compressed_size(),
+            uncompressed_size: entry.uncompressed_size(),
+            crc32: entry.crc32(),
+            date_time: (
+                date.year() as u16,
+                date.month() as u8,
+                date.day() as u8,
+                date.hour() as u8,
+                date.minute() as u8,
+                date.second() as u8,
+            ),
+        }
+    }
+}
+
+impl From<&zip::read::ZipFile<'_>> for Summary {
+    fn from(file: &zip::read::ZipFile<'_>) -> Self {
+        let date = file.last_modified();
+
+        Summary {
+            filename: file.name().to_owned(),
+            compressed_size: file.compressed_size(),
+            uncompressed_size: file.size(),
+            crc32: file.crc32(),
+            date_time: (date.year(), date.month(), date.day(), date.hour(), date.minute(), date.second()),
+        }
+    }
+}
+
+// Intentional differences between the libraries, which are allowed wherever they're found.
+//
+// This crate prefers the filename held by an Info-ZIP Unicode path extra field (where its CRC32 value matches that
+// recorded), whereas the `zip` crate decodes the recorded filename. Both libraries otherwise decode filenames as UTF-8
+// or CP437 according to the language encoding flag.
+fn allowed_difference(entry: &ZipEntry, ours: &Summary, theirs: &Summary) -> bool {
+    let unicode_path = crate::spec::extra_field::parse_unicode_path(entry.extra_fields(), entry.filename_bytes());
+    unicode_path.is_some() && Summary { filename: theirs.filename.clone(), ..*ours } == *theirs
+}
+
+// Read an archive with both libraries, asserting that they agree on every entry and its data.
+async fn assert_agree(name: &str, archive: &[u8]) {
+    let mut zip_reader = mem::ZipFileReader::new(archive).await.unwrap_or_else(|err| panic!("{}: {}", name, err));
+    let mut zip_archive = zip::ZipArchive::new(Cursor::new(archive)).unwrap_or_else(|err| panic!("{}: {}", name, err));
+    assert_eq!(zip_archive.len(), zip_reader.entries().len(), "{}", name);
+
+    for index in 0..zip_archive.len() {
+        let entry = zip_reader.entries()[index].clone();
+        let mut file = zip_archive.by_index(index).unwrap_or_else(|err| panic!("{}: {}: {}", name, index, err));
+
+        let (ours, theirs) = (Summary::from(&entry), Summary::from(&file));
+        if ours != theirs && !allowed_difference(&entry, &ours, &theirs) {
+            assert_eq!(theirs, ours, "{}: {}", name, index);
+        }
+
+        let mut expected = Vec::new();
+        file.read_to_end(&mut expected).unwrap_or_else(|err| panic!("{}: {}: {}", name, entry.filename(), err));
+        drop(file);
+
+        let entry_reader = zip_reader.entry_reader(index).await.unwrap_or_else(|err| panic!("{}: {}", name, err));
+        let read = entry_reader.read_to_end_crc().await;
+        let read = read.unwrap_or_else(|err| panic!("{}: {}: {}", name, entry.filename(), err));
+        assert!(expected == read, "{}: {}: data differs", name, entry.filename());
+    }
+}
+
+#[tokio::test]
+async fn compat_fixtures() {
+    for &(name, data) in super::interop::FIXTURES {
+        assert_agree(name, data).await;
+    }
+}
+
+// Write a random archive with this crate, with a mix of Stored and Deflate entries written whole or streamed, and
+// streamed entries either patched in place or followed by (signed or unsigned) data descriptors.
+async fn write_generated(generator: &mut Generator) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+    zip_writer.patch_local_headers(generator.chance(3));
+
+    if generator.chance(2) {
+        zip_writer.data_descriptor_style(DataDescriptorStyle::Unsigned);
+    }
+
+    for index in 0..generator.below(9) as usize {
+        let directory = generator.chance(6);
+        let filename = generator.filename(index, directory);
+        let compression = match directory || generator.chance(2) {
+            true => Compression::Stored,
+            false => Compression::Deflate,
+        };
+
+        let (year, month, day, hour, minute, second) = generator.date_time();
+        let date = ((year - 1980) << 9) | (u16::from(month) << 5) | u16::from(day);
+        let time = (u16::from(hour) << 11) | (u16::from(minute) << 5) | u16::from(second / 2);
+
+        let builder = ZipEntryBuilder::new(filename, compression);
+        let builder = builder.last_modification_date(crate::spec::date::zip_date_to_chrono(date, time));
+        let data = if directory { Vec::new() } else { generator.data() };
+
+        if directory || generator.chance(2) {
+            zip_writer.write_entry_whole(builder, &data).await.expect("failed to write entry");
+        } else {
+            let mut entry_writer = zip_writer.write_entry_stream(builder).await.expect("failed to open entry");
+            entry_writer.write_all(&data).await.expect("failed to write entry");
+            entry_writer.close().await.expect("failed to close entry");
+        }
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    output.into_inner()
+}
+
+// Write a random archive with the `zip` crate, with a mix of Stored and Deflate entries and directories.
+fn zip_crate_generated(generator: &mut Generator) -> Vec<u8> {
+    let mut zip_writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+
+    for index in 0..generator.below(9) as usize {
+        let directory = generator.chance(6);
+        let filename = generator.filename(index, directory);
+        let method = match generator.chance(2) {
+            true => zip::CompressionMethod::Stored,
+            false => zip::CompressionMethod::Deflated,
+        };
+
+        let (year, month, day, hour, minute, second) = generator.date_time();
+        let date_time = zip::DateTime::from_date_and_time(year, month, day, hour, minute, second).unwrap();
+        let options = zip::write::FileOptions::default().compression_method(method).last_modified_time(date_time);
+
+        if directory {
+            zip_writer.add_directory(filename, options).expect("failed to write directory");
+        } else {
+            zip_writer.start_file(filename, options).expect("failed to start entry");
+            zip_writer.write_all(&generator.data()).expect("failed to write entry");
+        }
+    }
+
+    zip_writer.finish().expect("failed to finish writer").into_inner()
+}
+
+#[tokio::test]
+async fn compat_generated_archives() {
+    for seed in 0..GENERATED_ARCHIVES {
+        let archive = write_generated(&mut Generator::new(seed)).await;
+        assert_agree(&format!("async_zip seed {}", seed), &archive).await;
+
+        let archive = zip_crate_generated(&mut Generator::new(seed));
+        assert_agree(&format!("zip seed {}", seed), &archive).await;
+    }
+}
@@ -0,0 +1,324 @@
+# Regenerates the interoperability fixtures within this directory (see README.md).
+#
+# Requires Info-ZIP's `zip` and the JDK's `jar` on the path.
+
+import io
+import os
+import shutil
+import struct
+import subprocess
+import tempfile
+import zipfile
+import zlib
+
+OUT = os.path.dirname(os.path.abspath(__file__))
+WORK = tempfile.mkdtemp()
+os.makedirs(WORK + '/src/docs')
+
+HELLO = b'Hello, world!\n'
+LOREM = b'Lorem ipsum dolor sit amet, consectetur adipiscing elit. ' * 20
+MTIME = 1664625600  # 2022-10-01 12:00:00 UTC
+
+with open(WORK + '/src/hello.txt', 'wb') as f:
+    f.write(HELLO)
+with open(WORK + '/src/docs/lorem.txt', 'wb') as f:
+    f.write(LOREM)
+for p in ['src/hello.txt', 'src/docs/lorem.txt', 'src/docs']:
+    os.utime(WORK + '/' + p, (MTIME, MTIME))
+
+
+class Unseekable(io.RawIOBase):
+    def __init__(self):
+        self.buf = bytearray()
+
+    def writable(self):
+        return True
+
+    def write(self, b):
+        self.buf += b
+        return len(b)
+
+
+# Info-ZIP writing to a pipe, with the __MACOSX AppleDouble entry Finder adds alongside each file.
+apple_double = b'\x00\x05\x16\x07\x00\x02\x00\x00' + b'Mac OS X        ' + b'\x00\x01' + \
+    struct.pack('>III', 9, 50, 32) + b'\x00' * 32
+os.makedirs(WORK + '/src/__MACOSX', exist_ok=True)
+with open(WORK + '/src/__MACOSX/._hello.txt', 'wb') as f:
+    f.write(apple_double)
+os.utime(WORK + '/src/__MACOSX/._hello.txt', (MTIME, MTIME))
+os.utime(WORK + '/src/__MACOSX', (MTIME, MTIME))
+data = subprocess.run(['zip', '-q', '-r', '-', 'hello.txt', 'docs', '__MACOSX'], cwd=WORK + '/src',
+                      stdout=subprocess.PIPE, check=True, env={**os.environ, 'TZ': 'UTC'}).stdout
+open(OUT + '/macos_finder.zip', 'wb').write(data)
+
+# Python's zipfile writing to an unseekable stream (so every entry uses a data descriptor).
+stream = Unseekable()
+with zipfile.ZipFile(stream, 'w', compression=zipfile.ZIP_DEFLATED) as z:
+    info = zipfile.ZipInfo('hello.txt', (2022, 10, 1, 12, 0, 0))
+    info.compress_type = zipfile.ZIP_DEFLATED
+    z.writestr(info, HELLO)
+    info = zipfile.ZipInfo('docs/', (2022, 10, 1, 12, 0, 0))
+    info.external_attr = (0o40755 << 16) | 0x10
+    z.writestr(info, b'')
+    info = zipfile.ZipInfo('docs/lorem.txt', (2022, 10, 1, 12, 0, 0))
+    info.compress_type = zipfile.ZIP_DEFLATED
+    with z.open(info, 'w') as w:
+        w.write(LOREM)
+open(OUT + '/python_zipfile.zip', 'wb').write(bytes(stream.buf))
+
+
+# The same archive with the data descriptors' optional signatures removed (and offsets fixed up to match).
+def strip_descriptor_signatures(data):
+    out = bytearray()
+    shift = {}
+    pos = 0
+    removed = 0
+    eocd_start = data.rindex(b'PK\x05\x06')
+    cd_start = struct.unpack('<I', data[eocd_start + 16:eocd_start + 20])[0]
+    while data[pos:pos + 4] == b'PK\x03\x04':
+        shift[pos] = pos - removed
+        flags = struct.unpack('<H', data[pos + 6:pos + 8])[0]
+        csize = struct.unpack('<I', data[pos + 18:pos + 22])[0]
+        nlen, elen = struct.unpack('<HH', data[pos + 26:pos + 30])
+        header_end = pos + 30 + nlen + elen
+        out += data[pos:header_end]
+        if flags & 8:
+            # Locate the descriptor via the central directory's compressed size.
+            name = data[pos + 30:pos + 30 + nlen]
+            cd = cd_start
+            while True:
+                cnlen, celen, ccomlen = struct.unpack('<HHH', data[cd + 28:cd + 34])
+                if data[cd + 46:cd + 46 + cnlen] == name:
+                    csize = struct.unpack('<I', data[cd + 20:cd + 24])[0]
+                    break
+                cd += 46 + cnlen + celen + ccomlen
+            out += data[header_end:header_end + csize]
+            desc = header_end + csize
+            assert data[desc:desc + 4] == b'PK\x07\x08'
+            out += data[desc + 4:desc + 16]
+            removed += 4
+            pos = desc + 16
+        else:
+            out += data[header_end:header_end + csize]
+            pos = header_end + csize
+    new_cd_start = len(out)
+    cd = pos
+    while data[cd:cd + 4] == b'PK\x01\x02':
+        cnlen, celen, ccomlen = struct.unpack('<HHH', data[cd + 28:cd + 34])
+        rec = bytearray(data[cd:cd + 46 + cnlen + celen + ccomlen])
+        off = struct.unpack('<I', rec[42:46])[0]
+        rec[42:46] = struct.pack('<I', shift[off])
+        out += rec
+        cd += len(rec)
+    eocd = bytearray(data[cd:])
+    eocd[16:20] = struct.pack('<I', new_cd_start)
+    out += eocd
+    return bytes(out)
+
+
+open(OUT + '/descriptor_no_signature.zip', 'wb').write(strip_descriptor_signatures(bytes(stream.buf)))
+
+# The layout Go's archive/zip writes via Writer.Create(): a FAT host (no mode set), data descriptors on every entry,
+# and the MS-DOS directory attribute on directory entries.
+stream = Unseekable()
+with zipfile.ZipFile(stream, 'w') as z:
+    for name, data, method in [('hello.txt', HELLO, zipfile.ZIP_DEFLATED), ('docs/', b'', zipfile.ZIP_STORED),
+                               ('docs/lorem.txt', LOREM, zipfile.ZIP_DEFLATED)]:
+        info = zipfile.ZipInfo(name, (2022, 10, 1, 12, 0, 0))
+        info.create_system = 0
+        info.create_version = 20
+        info.compress_type = method
+        info.external_attr = 0x10 if name.endswith('/') else 0
+        with z.open(info, 'w') as w:
+            w.write(data)
+open(OUT + '/go_archive_zip.zip', 'wb').write(bytes(stream.buf))
+
+# The layout Windows' built-in zipper writes: filenames in the OEM code page (CP437 here) without the language
+# encoding flag, and a FAT host. Another entry carries an Info-ZIP Unicode path record (as Info-ZIP's Windows port
+# writes), and a third a stale one, as left by a tool which renamed the entry without updating the record.
+def unicode_path(raw, name):
+    return struct.pack('<HHBI', 0x7075, 5 + len(name.encode()), 1, zlib.crc32(raw)) + name.encode()
+
+
+buffer = io.BytesIO()
+with zipfile.ZipFile(buffer, 'w') as z:
+    for name, data, extra in [('hello.txt', HELLO, b''), ('docs/', b'', b''), ('docs/lorem.txt', LOREM, b''),
+                              ('cafX.txt', HELLO, b''),
+                              ('rXsumX.txt', HELLO, unicode_path(b'r\x82sum\x82.txt', 'résumé.txt')),
+                              ('old.txt', HELLO, unicode_path(b'older.txt', 'new.txt'))]:
+        info = zipfile.ZipInfo(name, (2022, 10, 1, 12, 0, 0))
+        info.create_system = 0
+        info.compress_type = zipfile.ZIP_DEFLATED if data else zipfile.ZIP_STORED
+        info.external_attr = 0x10 if name.endswith('/') else 0x20
+        info.extra = extra
+        z.writestr(info, data)
+data = buffer.getvalue().replace(b'cafX.txt', b'caf\x82.txt').replace(b'rXsumX.txt', b'r\x82sum\x82.txt')
+open(OUT + '/windows_cp437.zip', 'wb').write(data)
+
+# Info-ZIP writing the full set of metadata a Finder-compressed folder holds: a .DS_Store file, and an AppleDouble
+# companion within __MACOSX for each file and directory carrying extended attributes.
+FINDER = WORK + '/finder'
+os.makedirs(FINDER + '/docs')
+os.makedirs(FINDER + '/__MACOSX/docs')
+DS_STORE = b'\x00\x00\x00\x01Bud1' + b'\x00' * 24
+for name, data in [('hello.txt', HELLO), ('docs/lorem.txt', LOREM), ('docs/.DS_Store', DS_STORE),
+                   ('__MACOSX/._hello.txt', apple_double), ('__MACOSX/docs/._lorem.txt', apple_double),
+                   ('__MACOSX/._docs', apple_double)]:
+    with open(FINDER + '/' + name, 'wb') as f:
+        f.write(data)
+for name in ['hello.txt', 'docs/lorem.txt', 'docs/.DS_Store', '__MACOSX/._hello.txt', '__MACOSX/docs/._lorem.txt',
+             '__MACOSX/._docs', '__MACOSX/docs', '__MACOSX', 'docs']:
+    os.utime(FINDER + '/' + name, (MTIME, MTIME))
+data = subprocess.run(['zip', '-q', '-r', '-', 'hello.txt', 'docs', '__MACOSX'], cwd=FINDER,
+                      stdout=subprocess.PIPE, check=True, env={**os.environ, 'TZ': 'UTC'}).stdout
+open(OUT + '/macos_finder_metadata.zip', 'wb').write(data)
+
+# The layout of a parallel compressor, which writes each entry's data once compressed: the entries' local file
+# headers appear in a different order to their central directory records, with unused gaps between them.
+out_of_order = io.BytesIO()
+with zipfile.ZipFile(out_of_order, 'w') as z:
+    for name, data, method in [('docs/lorem.txt', LOREM, zipfile.ZIP_DEFLATED),
+                               ('hello.txt', HELLO, zipfile.ZIP_STORED), ('docs/', b'', zipfile.ZIP_STORED)]:
+        info = zipfile.ZipInfo(name, (2022, 10, 1, 12, 0, 0))
+        info.compress_type = method
+        info.external_attr = (0o40755 << 16) | 0x10 if name.endswith('/') else 0o100644 << 16
+        z.writestr(info, data)
+        z.fp.write(b'\x00' * 64)
+        z.start_dir = z.fp.tell()
+    z.filelist = [z.getinfo(name) for name in ['hello.txt', 'docs/', 'docs/lorem.txt']]
+open(OUT + '/out_of_order.zip', 'wb').write(out_of_order.getvalue())
+
+# The layout LibreOffice writes for an OpenDocument file: a Stored `mimetype` entry without extra fields at offset 0,
+# followed by Deflated entries (from a FAT host) and the manifest last.
+MANIFEST = b'<?xml version="1.0" encoding="UTF-8"?>\n<manifest:manifest xmlns:manifest="urn:oasis:names:tc:' \
+    b'opendocument:xmlns:manifest:1.0" manifest:version="1.3">\n <manifest:file-entry manifest:full-path="/" ' \
+    b'manifest:media-type="application/vnd.oasis.opendocument.text"/>\n</manifest:manifest>\n'
+odt = io.BytesIO()
+with zipfile.ZipFile(odt, 'w') as z:
+    for name, data, method in [('mimetype', b'application/vnd.oasis.opendocument.text', zipfile.ZIP_STORED),
+                               ('hello.txt', HELLO, zipfile.ZIP_DEFLATED), ('docs/', b'', zipfile.ZIP_STORED),
+                               ('docs/lorem.txt', LOREM, zipfile.ZIP_DEFLATED),
+                               ('META-INF/manifest.xml', MANIFEST, zipfile.ZIP_DEFLATED)]:
+        info = zipfile.ZipInfo(name, (2022, 10, 1, 12, 0, 0))
+        info.create_system = 0
+        info.create_version = 20
+        info.compress_type = method
+        info.external_attr = 0x10 if name.endswith('/') else 0
+        z.writestr(info, data)
+open(OUT + '/libreoffice_odt.zip', 'wb').write(odt.getvalue())
+
+# The JDK's jar tool, which adds a zero-length 0xCAFE extra field to its first entry.
+subprocess.run(['jar', '--create', '--file', WORK + '/out.jar', '--no-manifest', '-C', WORK + '/src', 'hello.txt',
+                '-C', WORK + '/src', 'docs'], check=True)
+shutil.copy(WORK + '/out.jar', OUT + '/jdk_jar.zip')
+
+# Info-ZIP storing symbolic links as links (-y) rather than following them: a link beside its target, and one within a
+# directory which leads back up to it.
+os.symlink('hello.txt', WORK + '/src/link')
+os.symlink('../hello.txt', WORK + '/src/docs/up')
+for p in ['src/link', 'src/docs/up']:
+    os.utime(WORK + '/' + p, (MTIME, MTIME), follow_symlinks=False)
+os.utime(WORK + '/src/docs', (MTIME, MTIME))
+subprocess.run(['zip', '-q', '-y', '-r', WORK + '/symlinks.zip', 'hello.txt', 'docs', 'link'], cwd=WORK + '/src',
+               check=True, env={**os.environ, 'TZ': 'UTC'})
+shutil.copy(WORK + '/symlinks.zip', OUT + '/info_zip_symlinks.zip')
+
+# Deflate64, encoded by hand (rather than by any other producer) as a single block of fixed Huffman codes: 2 KiB of
+# pseudo-random literals, extended to 60,000 bytes by back-references within Deflate's 32 KiB window, followed by those
+# which only Deflate64 can express. These are a 300-byte copy from 40,000 bytes back (distance code 30) and a
+# 5,000-byte copy from 60,000 bytes back (distance code 31), both using length code 285, which Deflate64 extends with
+# 16 extra bits.
+LENGTH_BASES = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195,
+                227, 3]
+LENGTH_EXTRA = [0] * 8 + [1] * 4 + [2] * 4 + [3] * 4 + [4] * 4 + [5] * 4 + [16]
+DISTANCE_BASES = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073,
+                  4097, 6145, 8193, 12289, 16385, 24577, 32769, 49153]
+DISTANCE_EXTRA = [0, 0, 0, 0] + [n // 2 for n in range(2, 30)]
+
+
+class BitWriter:
+    def __init__(self):
+        self.out = bytearray()
+        self.acc = 0
+        self.count = 0
+
+    def bits(self, value, count):
+        self.acc |= value << self.count
+        self.count += count
+        while self.count >= 8:
+            self.out.append(self.acc & 0xFF)
+            self.acc >>= 8
+            self.count -= 8
+
+    def code(self, value, count):
+        self.bits(int(format(value, '0%db' % count)[::-1], 2), count)
+
+    def symbol(self, symbol):
+        if symbol < 144:
+            self.code(0x30 + symbol, 8)
+        elif symbol < 256:
+            self.code(0x190 + symbol - 144, 9)
+        elif symbol < 280:
+            self.code(symbol - 256, 7)
+        else:
+            self.code(0xC0 + symbol - 280, 8)
+
+    def finish(self):
+        return bytes(self.out) + (bytes([self.acc]) if self.count else b'')
+
+
+def deflate64(tokens):
+    writer = BitWriter()
+    writer.bits(1, 1)
+    writer.bits(1, 2)
+    for token in tokens:
+        if isinstance(token, int):
+            writer.symbol(token)
+            continue
+        length, distance = token
+        code = 28 if length > 258 else max(c for c in range(28) if LENGTH_BASES[c] <= length)
+        writer.symbol(257 + code)
+        writer.bits(length - LENGTH_BASES[code], LENGTH_EXTRA[code])
+        code = max(c for c in range(32) if DISTANCE_BASES[c] <= distance)
+        writer.code(code, 5)
+        writer.bits(distance - DISTANCE_BASES[code], DISTANCE_EXTRA[code])
+    writer.symbol(256)
+    return writer.finish()
+
+
+state = [MTIME]
+
+
+def rand(n):
+    state[0] = (state[0] * 1103515245 + 12345) % 2 ** 31
+    return (state[0] >> 8) % n
+
+
+window, tokens = bytearray(), []
+for _ in range(2048):
+    tokens.append(rand(256))
+    window.append(tokens[-1])
+while len(window) < 60000:
+    tokens.append((3 + rand(255), 1 + rand(min(len(window), 32768))))
+    for _ in range(tokens[-1][0]):
+        window.append(window[-tokens[-1][1]])
+for length, distance in [(300, 40000), (5000, 60000)]:
+    tokens.append((length, distance))
+    for _ in range(length):
+        window.append(window[-distance])
+
+buffer = io.BytesIO()
+with zipfile.ZipFile(buffer, 'w') as z:
+    z.writestr(zipfile.ZipInfo('window.bin', (2022, 10, 1, 12, 0, 0)), deflate64(tokens))
+data = bytearray(buffer.getvalue())
+central = data.rindex(b'PK\x01\x02')
+# The version needed, compression method, CRC32 value, and uncompressed size lie at the same relative offsets within
+# the local file header and central directory header.
+for base in [4, central + 6]:
+    struct.pack_into('<H', data, base, 21)
+    struct.pack_into('<H', data, base + 4, 9)
+    struct.pack_into('<I', data, base + 10, zlib.crc32(window))
+    struct.pack_into('<I', data, base + 18, len(window))
+open(OUT + '/deflate64.zip', 'wb').write(data)
+
+shutil.rmtree(WORK)
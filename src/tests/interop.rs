@@ -0,0 +1,335 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Tests against archives from other common producers, as described in `fixtures/README.md`.
+
+use crate::entry::ext::ZipEntryExt;
+use crate::entry::ZipEntry;
+use crate::read::{mem, stream, ReaderOptions};
+use crate::spec::attribute::AttributeCompatibility;
+use crate::spec::extra_field::ExtraField;
+
+use std::io::Cursor;
+
+pub(super) const FIXTURES: &[(&str, &[u8])] = &[
+    ("macos_finder.zip", include_bytes!("fixtures/macos_finder.zip")),
+    ("macos_finder_metadata.zip", include_bytes!("fixtures/macos_finder_metadata.zip")),
+    ("python_zipfile.zip", include_bytes!("fixtures/python_zipfile.zip")),
+    ("descriptor_no_signature.zip", include_bytes!("fixtures/descriptor_no_signature.zip")),
+    ("go_archive_zip.zip", include_bytes!("fixtures/go_archive_zip.zip")),
+    ("jdk_jar.zip", include_bytes!("fixtures/jdk_jar.zip")),
+    ("libreoffice_odt.zip", include_bytes!("fixtures/libreoffice_odt.zip")),
+    ("windows_cp437.zip", include_bytes!("fixtures/windows_cp437.zip")),
+    ("info_zip_symlinks.zip", include_bytes!("fixtures/info_zip_symlinks.zip")),
+];
+
+fn fixture(name: &str) -> &'static [u8] {
+    FIXTURES.iter().find(|(fixture, _)| *fixture == name).map(|(_, data)| *data).unwrap()
+}
+
+// The expected content of an entry shared by every fixture, or None for producer-specific entries.
+fn expected_data(filename: &str) -> Option<Vec<u8>> {
+    match filename {
+        "hello.txt" => Some(b"Hello, world!\n".to_vec()),
+        "docs/lorem.txt" => Some(b"Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(20)),
+        "docs/" | "__MACOSX/" => Some(Vec::new()),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn fixtures_mem_reader() {
+    for &(name, data) in FIXTURES {
+        let mut zip_reader = mem::ZipFileReader::new(data).await.expect(name);
+        assert!(zip_reader.entries().len() >= 3, "{}", name);
+
+        for index in 0..zip_reader.entries().len() {
+            let filename = zip_reader.entries()[index].filename().to_owned();
+            let entry_reader = zip_reader.entry_reader(index).await.expect(name);
+            let read = entry_reader.read_to_end_crc().await;
+            let read = read.unwrap_or_else(|err| panic!("{}: {}: {}", name, filename, err));
+
+            if let Some(expected) = expected_data(&filename) {
+                assert_eq!(expected, read, "{}: {}", name, filename);
+            }
+        }
+
+        let report = zip_reader.verify(false).await.expect(name);
+        assert!(report.failures().is_empty(), "{}: {:?}", name, report.failures());
+    }
+}
+
+#[tokio::test]
+async fn fixtures_stream_reader() {
+    for &(name, data) in FIXTURES {
+        let mem_reader = mem::ZipFileReader::new(data).await.expect(name);
+        let expected: Vec<String> = mem_reader.entries().iter().map(|entry| entry.filename().to_owned()).collect();
+
+        let mut zip_reader = stream::ZipFileReader::new(Cursor::new(data));
+        let mut filenames = Vec::new();
+
+        while let Some(entry_reader) = zip_reader.entry_reader().await.expect(name) {
+            let filename = entry_reader.entry().filename().to_owned();
+            let read = entry_reader.read_to_end_crc().await;
+            let read = read.unwrap_or_else(|err| panic!("{}: {}: {}", name, filename, err));
+
+            if let Some(expected) = expected_data(&filename) {
+                assert_eq!(expected, read, "{}: {}", name, filename);
+            }
+
+            filenames.push(filename);
+        }
+
+        assert_eq!(expected, filenames, "{}", name);
+    }
+}
+
+#[tokio::test]
+async fn fixture_extra_field_lengths() {
+    let mut zip_reader = mem::ZipFileReader::new(fixture("macos_finder.zip")).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+
+    // The extended timestamp holds the modification and access times locally, but only the former centrally.
+    let lengths = |fields: &[ExtraField]| -> Vec<(u16, usize)> {
+        fields.iter().map(|field| (field.header_id(), field.data().len())).collect()
+    };
+
+    let local = lengths(entry_reader.local_extra_fields());
+    let central = lengths(entry_reader.entry().extra_fields());
+
+    assert_eq!(vec![(0x5455, 9), (0x7875, 11)], local);
+    assert_eq!(vec![(0x5455, 5), (0x7875, 11)], central);
+
+    let zip_reader = mem::ZipFileReader::new(fixture("jdk_jar.zip")).await.expect("failed to open reader");
+    let fields = zip_reader.entries()[0].extra_fields();
+    assert_eq!(1, fields.len());
+    assert_eq!((0xCAFE, &[][..]), (fields[0].header_id(), fields[0].data()));
+}
+
+#[tokio::test]
+async fn fixture_directory_attributes() {
+    let zip_reader = mem::ZipFileReader::new(fixture("go_archive_zip.zip")).await.expect("failed to open reader");
+    let entry = zip_reader.entry("docs/").map(|(_, entry)| entry).expect("missing directory entry");
+
+    // Only the MS-DOS directory attribute is set, which mustn't be mistaken for Unix permissions.
+    assert_eq!(AttributeCompatibility::Dos, entry.attribute_compatibility());
+    assert_eq!(0x10, entry.external_file_attribute());
+    assert_eq!(None, entry.unix_permissions());
+
+    let zip_reader = mem::ZipFileReader::new(fixture("python_zipfile.zip")).await.expect("failed to open reader");
+    let entry = zip_reader.entry("docs/").map(|(_, entry)| entry).expect("missing directory entry");
+
+    assert_eq!(AttributeCompatibility::Unix, entry.attribute_compatibility());
+    assert_eq!(Some(0o40755), entry.unix_permissions());
+}
+
+#[tokio::test]
+async fn fixture_macos_metadata() {
+    let data = fixture("macos_finder_metadata.zip");
+    let zip_reader = mem::ZipFileReader::new(data).await.expect("failed to open reader");
+
+    let filenames = |predicate: fn(&ZipEntry) -> bool| -> Vec<String> {
+        let entries = zip_reader.entries().into_iter().filter(|entry| predicate(entry));
+        entries.map(|entry| entry.filename().to_owned()).collect()
+    };
+
+    let expected = ["__MACOSX/._docs", "__MACOSX/docs/._lorem.txt", "__MACOSX/._hello.txt"];
+    assert_eq!(expected.to_vec(), filenames(ZipEntry::is_apple_double));
+
+    let expected = ["docs/.DS_Store", "__MACOSX/", "__MACOSX/._docs", "__MACOSX/docs/", "__MACOSX/docs/._lorem.txt"];
+    let mut expected = expected.to_vec();
+    expected.push("__MACOSX/._hello.txt");
+    assert_eq!(expected, filenames(ZipEntry::is_macos_metadata));
+
+    // Files and directories are paired with their companions, whereas metadata has none.
+    let companion = |filename: &str| -> Option<&str> {
+        let index = zip_reader.apple_double_for(zip_reader.entry(filename)?.0)?;
+        Some(zip_reader.entries()[index].filename())
+    };
+
+    assert_eq!(Some("__MACOSX/._hello.txt"), companion("hello.txt"));
+    assert_eq!(Some("__MACOSX/docs/._lorem.txt"), companion("docs/lorem.txt"));
+    assert_eq!(Some("__MACOSX/._docs"), companion("docs/"));
+    assert_eq!(None, companion("__MACOSX/._hello.txt"));
+    assert_eq!(None, companion("docs/.DS_Store"));
+
+    // Indices refer to the filtered list of entries.
+    let options = ReaderOptions::new().skip_macos_metadata(true);
+    let mut zip_reader = mem::ZipFileReader::with_options(data, options).await.expect("failed to open reader");
+    let filenames: Vec<&str> = zip_reader.entries().into_iter().map(|entry| entry.filename()).collect();
+    assert_eq!(vec!["hello.txt", "docs/", "docs/lorem.txt"], filenames);
+    assert_eq!(9, zip_reader.total_entries());
+    assert_eq!(None, zip_reader.apple_double_for(0));
+
+    let index = zip_reader.entry("docs/lorem.txt").expect("missing entry").0;
+    let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+    let read = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+    assert_eq!(expected_data("docs/lorem.txt"), Some(read));
+}
+
+#[tokio::test]
+async fn fixture_out_of_order() {
+    use crate::read::seek;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+    // A reader which logs the offset of every seek from its start.
+    struct SeekLog {
+        inner: Cursor<&'static [u8]>,
+        offsets: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl AsyncRead for SeekLog {
+        fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(c, b)
+        }
+    }
+
+    impl AsyncSeek for SeekLog {
+        fn start_seek(mut self: Pin<&mut Self>, position: std::io::SeekFrom) -> std::io::Result<()> {
+            if let std::io::SeekFrom::Start(offset) = position {
+                self.offsets.lock().unwrap().push(offset);
+            }
+            Pin::new(&mut self.inner).start_seek(position)
+        }
+
+        fn poll_complete(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+            Pin::new(&mut self.inner).poll_complete(c)
+        }
+    }
+
+    let data = include_bytes!("fixtures/out_of_order.zip");
+    let zip_reader = mem::ZipFileReader::new(data).await.expect("failed to open reader");
+
+    let filenames: Vec<&str> = zip_reader.entries().into_iter().map(|entry| entry.filename()).collect();
+    assert_eq!(vec!["hello.txt", "docs/", "docs/lorem.txt"], filenames);
+
+    let by_offset: Vec<(usize, &str)> =
+        zip_reader.entries_by_offset().into_iter().map(|(index, entry)| (index, entry.filename())).collect();
+    assert_eq!(vec![(2, "docs/lorem.txt"), (0, "hello.txt"), (1, "docs/")], by_offset);
+
+    let offsets = Arc::new(Mutex::new(Vec::new()));
+    let reader = SeekLog { inner: Cursor::new(&data[..]), offsets: offsets.clone() };
+    let mut zip_reader = seek::ZipFileReader::new(reader).await.expect("failed to open reader");
+
+    // Reading in the order listed seeks back and forth, whereas extracting every entry only ever seeks forwards.
+    let ascending = |offsets: &[u64]| offsets.windows(2).all(|pair| pair[0] < pair[1]);
+    offsets.lock().unwrap().clear();
+
+    for index in 0..3 {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        entry_reader.read_to_end_crc().await.expect("failed to read entry");
+    }
+
+    let listed = std::mem::take(&mut *offsets.lock().unwrap());
+    assert!(!ascending(&listed), "{:?}", listed);
+
+    let results = zip_reader.extract_all(|_, entry| {
+        let directory = entry.filename().ends_with('/');
+        async move { Ok::<_, crate::error::ZipError>(Some(tokio::io::sink()).filter(|_| !directory)) }
+    });
+
+    let results: Vec<(usize, u64)> =
+        results.await.into_iter().map(|(index, result)| (index, result.expect("failed to extract entry"))).collect();
+    let lorem = expected_data("docs/lorem.txt").unwrap().len() as u64;
+    assert_eq!(vec![(2, lorem), (0, 14)], results);
+
+    let extracted = offsets.lock().unwrap().clone();
+    assert!(ascending(&extracted), "{:?}", extracted);
+}
+
+#[tokio::test]
+async fn fixture_cp437_filenames() {
+    let zip_reader = mem::ZipFileReader::new(fixture("windows_cp437.zip")).await.expect("failed to open reader");
+    let entries = &zip_reader.entries()[3..];
+
+    // The Unicode path extra field is preferred unless its CRC32 value doesn't match the recorded filename.
+    let filenames: Vec<(&str, &[u8])> =
+        entries.iter().map(|entry| (entry.filename(), entry.filename_bytes())).collect();
+    let expected =
+        vec![("café.txt", &b"caf\x82.txt"[..]), ("résumé.txt", &b"r\x82sum\x82.txt"[..]), ("old.txt", &b"old.txt"[..])];
+    assert_eq!(expected, filenames);
+    assert!(entries.iter().all(|entry| !entry.general_purpose_flags().filename_unicode()));
+
+    // Entries are found by their decoded filenames, and local file headers are compared by their raw filenames.
+    let options = ReaderOptions::new().verify_local_headers(true);
+    let zip_reader = mem::ZipFileReader::with_options(fixture("windows_cp437.zip"), options).await;
+    let mut zip_reader = zip_reader.expect("failed to open reader");
+    let index = zip_reader.entry("résumé.txt").map(|(index, _)| index).expect("missing entry");
+    let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+    assert_eq!(expected_data("hello.txt"), entry_reader.read_to_end_crc().await.ok());
+
+    let mut zip_reader = stream::ZipFileReader::new(Cursor::new(fixture("windows_cp437.zip")));
+    let mut filenames = Vec::new();
+    while let Some(entry_reader) = zip_reader.entry_reader().await.expect("failed to read entry") {
+        filenames.push(entry_reader.entry().filename().to_owned());
+        entry_reader.read_to_end_crc().await.expect("failed to read entry");
+    }
+    assert_eq!(vec!["café.txt", "résumé.txt", "old.txt"], filenames[3..]);
+}
+
+#[tokio::test]
+async fn fixture_mimetype() {
+    let zip_reader = mem::ZipFileReader::new(fixture("libreoffice_odt.zip")).await.expect("failed to open reader");
+    let mimetype = zip_reader.sniff_mimetype().await.expect("failed to sniff mimetype");
+    assert_eq!(Some("application/vnd.oasis.opendocument.text"), mimetype.as_deref());
+
+    for &(name, data) in FIXTURES.iter().filter(|(name, _)| *name != "libreoffice_odt.zip") {
+        let zip_reader = mem::ZipFileReader::new(data).await.expect(name);
+        assert_eq!(None, zip_reader.sniff_mimetype().await.expect(name), "{}", name);
+    }
+}
+
+#[tokio::test]
+async fn fixture_symlinks() {
+    let zip_reader = mem::ZipFileReader::new(fixture("info_zip_symlinks.zip")).await.expect("failed to open reader");
+    let links: Vec<(&str, Option<&str>)> = zip_reader
+        .entries()
+        .iter()
+        .filter(|entry| entry.is_symlink())
+        .map(|entry| (entry.filename(), entry.symlink_target()))
+        .collect();
+
+    assert_eq!(vec![("docs/up", Some("../hello.txt")), ("link", Some("hello.txt"))], links);
+    assert!(!zip_reader.entry("hello.txt").map(|(_, entry)| entry).expect("missing entry").is_symlink());
+
+    #[cfg(unix)]
+    {
+        use crate::entry::ext::ZipEntryBuilderExt;
+        use crate::error::ZipError;
+        use crate::read::ExtractOptions;
+        use crate::write::ZipFileWriter;
+        use crate::{Compression, ZipEntryBuilder};
+
+        let dir = tempfile::tempdir().expect("failed to create directory");
+        let root = dir.path();
+
+        // Both links stay within the directory, so are created as links which resolve to the extracted file.
+        let mut zip_reader = mem::ZipFileReader::new(fixture("info_zip_symlinks.zip")).await.expect("failed to open");
+        zip_reader.extract(&root, ExtractOptions::new()).await.expect("failed to extract");
+
+        for (link, target) in [("link", "hello.txt"), ("docs/up", "../hello.txt")] {
+            assert_eq!(std::path::Path::new(target), std::fs::read_link(root.join(link)).expect("not a link"));
+            assert_eq!(expected_data("hello.txt"), std::fs::read(root.join(link)).ok());
+        }
+
+        // The same link from the root of the directory would escape it, as would an absolute target.
+        for (filename, target) in [("up", "../hello.txt"), ("docs/abs", "/etc/passwd")] {
+            let mut output = Cursor::new(Vec::new());
+            let mut zip_writer = ZipFileWriter::new(&mut output);
+            let builder = ZipEntryBuilder::new(filename.to_owned(), Compression::Stored).symlink(target);
+            zip_writer.write_entry_whole(builder, &[]).await.expect("failed to write entry");
+            zip_writer.close().await.expect("failed to close writer");
+
+            let archive = output.into_inner();
+            let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+            let result = zip_reader.extract(&root, ExtractOptions::new()).await;
+            assert!(matches!(result, Err(ZipError::UnsafeSymlinkTarget { index: 0, .. })), "{}", filename);
+
+            zip_reader.extract(&root, ExtractOptions::new().skip_unsafe(true)).await.expect("failed to extract");
+            assert!(std::fs::symlink_metadata(root.join(filename)).is_err());
+        }
+    }
+}
@@ -1,13 +1,19 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
+use crate::entry::builder::ZipEntryBuilder;
 use crate::spec::compression::Compression;
 use crate::write::ZipFileWriter;
-use crate::entry::builder::ZipEntryBuilder;
 
 use std::io::Cursor;
 use std::vec::Vec;
 
+mod alloc;
+#[cfg(feature = "compat-tests")]
+mod compat;
+mod interop;
+mod roundtrip;
+
 #[tokio::test]
 async fn empty() {
     use crate::read::seek::ZipFileReader;
@@ -41,7 +47,7 @@ async fn single_entry_with_comment() {
     let mut input_stream = Cursor::new(Vec::<u8>::new());
     let mut zip_writer = ZipFileWriter::new(&mut input_stream);
 
-    zip_writer.comment(String::from(comment));
+    zip_writer.comment(String::from(comment)).expect("failed to set comment");
     zip_writer.close().await.expect("failed to close writer");
 
     input_stream.set_position(0);
@@ -88,7 +94,8 @@ async fn entry_with_specific_last_modified() {
 
     let mut zip_writer = ZipFileWriter::new(&mut input_stream);
 
-    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored).last_modification_date(last_modified);
+    let open_opts =
+        ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored).last_modification_date(last_modified);
     zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
 
     let open_opts = ZipEntryBuilder::new(String::from("foo.baz"), Compression::Stored);
@@ -107,6 +114,80 @@ async fn entry_with_specific_last_modified() {
     assert_ne!(&last_modified, zip_reader.entry("foo.baz").unwrap().1.last_modification_date());
 }
 
+#[tokio::test]
+async fn entry_timestamps() {
+    use crate::entry::{TimestampKind, TimestampSource};
+    use crate::read::mem::ZipFileReader;
+    use chrono::{DateTime, Timelike, Utc};
+    use std::time::{Duration, SystemTime};
+
+    // An odd number of seconds (which MS-DOS dates can't represent) and times before 1980 and the Unix epoch.
+    let modified = SystemTime::UNIX_EPOCH + Duration::new(1_666_544_103, 500_000_000);
+    let accessed = SystemTime::UNIX_EPOCH + Duration::from_secs(300_000_000);
+    let created = SystemTime::UNIX_EPOCH - Duration::from_secs(86_400);
+    let builder = ZipEntryBuilder::new("foo.bar", Compression::Stored).modified(modified);
+    let builder = builder.accessed(accessed).created(created);
+
+    let mut data = Vec::new();
+    let mut zip_writer = ZipFileWriter::new(&mut data);
+    zip_writer.write_entry_whole(builder.clone(), b"extended").await.expect("failed to write entry");
+    let builder = builder.filename("foo.baz").ntfs_timestamps(true);
+    zip_writer.write_entry_whole(builder, b"ntfs").await.expect("failed to write entry");
+
+    let builder = ZipEntryBuilder::new("foo.dos", Compression::Stored);
+    let builder = builder.last_modification_date(DateTime::<Utc>::from(accessed));
+    zip_writer.write_entry_whole(builder, b"dos").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let zip_reader = ZipFileReader::new(&data).await.expect("failed to open reader");
+    let entries = zip_reader.entries();
+    let whole_seconds = |time: SystemTime| DateTime::<Utc>::from(time).with_nanosecond(0).unwrap();
+
+    assert_eq!(entries[0].modified(), Some(whole_seconds(modified)));
+    assert_eq!(entries[0].accessed(), Some(whole_seconds(accessed)));
+    assert_eq!(entries[0].created(), Some(whole_seconds(created)));
+    let source = entries[0].timestamp(TimestampKind::Modified).map(|(_, source)| source);
+    assert_eq!(source, Some(TimestampSource::ExtendedTimestamp));
+    assert_eq!(*entries[0].last_modification_date(), whole_seconds(modified));
+
+    assert_eq!(entries[1].modified(), Some(DateTime::<Utc>::from(modified)));
+    assert_eq!(entries[1].accessed(), Some(DateTime::<Utc>::from(accessed)));
+    assert_eq!(entries[1].created(), Some(DateTime::<Utc>::from(created)));
+    let source = entries[1].timestamp(TimestampKind::Created).map(|(_, source)| source);
+    assert_eq!(source, Some(TimestampSource::Ntfs));
+
+    // The pre-1980 date has been clamped to the start of 1980 within the MS-DOS date & time of the local file header
+    // (at offsets 10 to 14), but is kept by the extended timestamp extra field.
+    let (time, source) = entries[2].timestamp(TimestampKind::Modified).expect("no modification time");
+    assert_eq!((time, source), (whole_seconds(accessed), TimestampSource::ExtendedTimestamp));
+    assert_eq!(entries[2].accessed(), None);
+    let offset = data.windows(7).position(|window| window == b"foo.dos").expect("missing entry") - 30;
+    assert_eq!([0, 0, 0x21, 0], data[offset + 10..offset + 14]);
+}
+
+#[tokio::test]
+async fn last_modification_date_round_trip() {
+    use crate::read::{mem, stream};
+    use chrono::{TimeZone, Utc};
+
+    // An odd number of seconds, which the MS-DOS date & time alone would round down.
+    let date = Utc.timestamp_opt(1_666_544_103, 0).unwrap();
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new("odd.txt", Compression::Stored).last_modification_date(date);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+    let data = input_stream.into_inner();
+
+    let zip_reader = mem::ZipFileReader::new(&data).await.expect("failed to open reader");
+    assert_eq!(date, *zip_reader.entries()[0].last_modification_date());
+
+    let mut zip_reader = stream::ZipFileReader::new(Cursor::new(&data));
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to read entry").expect("missing entry");
+    assert_eq!(date, *entry_reader.entry().last_modification_date());
+}
+
 #[cfg(feature = "deflate")]
 #[tokio::test]
 async fn data_descriptor_single() {
@@ -128,12 +209,12 @@ async fn data_descriptor_single() {
     input_stream.set_position(0);
 
     let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
- 
+
     assert_eq!(1, zip_reader.entries().len());
 
     let entry = zip_reader.entry("foo.bar").expect("no 'foo.bar' entry");
     assert_eq!(0, entry.0);
-    assert_eq!(data.len() as u32, entry.1.uncompressed_size());
+    assert_eq!(data.len() as u64, entry.1.uncompressed_size());
     assert_eq!(Compression::Deflate, entry.1.compression());
 
     let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
@@ -192,6 +273,62 @@ async fn data_descriptor_double_stream() {
     assert!(zip_reader.finished());
 }
 
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn entry_reader_finish() {
+    use crate::read::stream::ZipFileReader;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+    entry_writer.write_all(data.as_bytes()).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.baz"), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let zip_reader = crate::read::mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let compressed_size = zip_reader.entries()[0].compressed_size();
+
+    // A partial hash is never compared, and the next entry can't be located until the current one is finished.
+    let mut zip_reader = ZipFileReader::new(Cursor::new(&archive));
+    let mut entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+    entry_reader.read_exact(&mut [0; 10]).await.expect("failed to read entry");
+    assert!(matches!(entry_reader.try_compare_crc(), Err(ZipError::EntryNotFullyConsumed)));
+    assert!(!entry_reader.compare_crc());
+
+    let result = entry_reader.drain_on_finish(false).finish().await;
+    assert!(matches!(result, Err(ZipError::EntryNotFullyConsumed)));
+    assert!(matches!(zip_reader.entry_reader().await, Err(ZipError::EntryNotFullyConsumed)));
+
+    // Finishing drains the remaining data and resolves the data descriptor.
+    let mut zip_reader = ZipFileReader::new(Cursor::new(&archive));
+    let mut entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+    entry_reader.read_exact(&mut [0; 10]).await.expect("failed to read entry");
+
+    let outcome = entry_reader.finish().await.expect("failed to finish entry");
+    assert!(outcome.is_ok());
+    assert_eq!((data.len() as u64, data.len() as u64), (outcome.bytes_read(), outcome.uncompressed_size()));
+    assert_eq!(compressed_size, outcome.compressed_size());
+
+    let mut entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+    let mut buffer = String::new();
+    entry_reader.read_to_string(&mut buffer).await.expect("failed to read entry");
+    assert_eq!(data, buffer);
+    assert!(entry_reader.try_compare_crc().expect("failed to compare CRC32 value"));
+
+    let outcome = entry_reader.without_crc().finish().await.expect("failed to finish entry");
+    assert!(!outcome.crc_ok() && outcome.size_ok());
+    assert!(zip_reader.entry_reader().await.expect("failed to read central directory").is_none());
+}
+
 #[cfg(feature = "deflate")]
 #[tokio::test]
 async fn data_tokio_copy_stream() {
@@ -322,7 +459,7 @@ macro_rules! single_entry_gen {
 
             let entry = zip_reader.entry("foo.bar").expect("no 'foo.bar' entry");
             assert_eq!(0, entry.0);
-            assert_eq!(data.len() as u32, entry.1.uncompressed_size());
+            assert_eq!(data.len() as u64, entry.1.uncompressed_size());
             assert_eq!($typ, entry.1.compression());
 
             let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
@@ -344,3 +481,5339 @@ single_entry_gen!(single_entry_lzma, Compression::Lzma);
 single_entry_gen!(single_entry_zstd, Compression::Zstd);
 #[cfg(feature = "xz")]
 single_entry_gen!(single_entry_xz, Compression::Xz);
+
+#[tokio::test]
+async fn reproducible_output() {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    async fn write_archive(modified: DateTime<Utc>, reproducible: bool) -> Vec<u8> {
+        let mut output = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut output);
+        if reproducible {
+            zip_writer.reproducible(crate::spec::date::zip_date_to_chrono(0x21, 0));
+        }
+
+        let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+        let open_opts = open_opts.last_modification_date(modified);
+        zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+
+        let open_opts = ZipEntryBuilder::new(String::from("foo.baz"), Compression::Stored).version_made_by(0x0314);
+        let open_opts = open_opts.last_modification_date(modified);
+        zip_writer.write_entry_whole(open_opts, b"bar").await.expect("failed to write entry");
+
+        zip_writer.close().await.expect("failed to close writer");
+        output.into_inner()
+    }
+
+    // Entries modified at different times (including their extended timestamps) only produce identical bytes once the
+    // writer is reproducible.
+    let (first, second) = (Utc.timestamp_opt(1_600_000_000, 0).unwrap(), Utc.timestamp_opt(1_666_544_103, 0).unwrap());
+    assert_ne!(write_archive(first, false).await, write_archive(second, false).await);
+    assert_eq!(write_archive(first, true).await, write_archive(second, true).await);
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn written_entry_summary() {
+    use crate::read::seek::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let open_opts = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Stored);
+    let whole = zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+
+    let open_opts = ZipEntryBuilder::new("foo.baz".to_string(), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+    entry_writer.write_all(data.as_bytes()).await.expect("failed to write entry");
+    let stream = entry_writer.close().await.expect("failed to close entry");
+
+    zip_writer.close().await.expect("failed to close writer");
+    input_stream.set_position(0);
+
+    let zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+
+    assert_eq!(0, whole.header_offset());
+    assert!(stream.header_offset() > 0);
+
+    for (written, entry) in [whole, stream].iter().zip(zip_reader.entries()) {
+        assert_eq!(written.crc32(), entry.crc32());
+        assert_eq!(u64::from(written.compressed_size()), entry.compressed_size());
+        assert_eq!(u64::from(written.uncompressed_size()), entry.uncompressed_size());
+        assert_eq!(data.len() as u64, written.uncompressed_size());
+    }
+}
+
+#[tokio::test]
+async fn entry_builder_reuse() {
+    use crate::read::mem::ZipFileReader;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let builder = ZipEntryBuilder::new("first.txt", Compression::Stored).comment(String::from("first"));
+    let mut builder = builder.extra_field(0xCAFE, vec![1, 2, 3]);
+    zip_writer.write_entry_whole(&builder, b"foo").await.expect("failed to write entry");
+
+    // Clones share the filename rather than copying it.
+    let entry = builder.clone().build();
+    assert!(std::sync::Arc::ptr_eq(&entry.filename, &builder.0.filename));
+
+    let capacity = builder.0.comment.capacity();
+    builder.reset("second.txt", Compression::Stored);
+    assert_eq!(capacity, builder.0.comment.capacity());
+    assert!(builder.0.comment.is_empty() && builder.0.extra_field.is_empty() && builder.0.extra_fields.is_empty());
+    zip_writer.write_entry_whole(&builder, b"bar").await.expect("failed to write entry");
+
+    let builder = builder.filename(String::from("third.txt"));
+    zip_writer.write_entry_whole(builder, b"baz").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let data = input_stream.into_inner();
+    let zip_reader = ZipFileReader::new(&data).await.expect("failed to open reader");
+    let filenames: Vec<&str> = zip_reader.entries().into_iter().map(|entry| entry.filename()).collect();
+    assert_eq!(vec!["first.txt", "second.txt", "third.txt"], filenames);
+
+    assert_eq!("first", zip_reader.entries()[0].comment());
+    assert_eq!(7, zip_reader.entries()[0].extra_field().len());
+    assert_eq!("", zip_reader.entries()[1].comment());
+    assert!(zip_reader.entries()[1].extra_field().is_empty());
+}
+
+#[tokio::test]
+async fn cp437_filename_valid_as_utf8() {
+    use crate::read::{mem, stream};
+
+    // Without the language encoding flag, a filename is CP437 even where its bytes also happen to be valid UTF-8.
+    let archive = raw_archive("é.txt", 0, compute_crc(b"foo"), b"foo", 3);
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entry = &zip_reader.entries()[0];
+    assert!(!entry.general_purpose_flags().filename_unicode());
+    assert_eq!(("├⌐.txt", "é.txt".as_bytes()), (entry.filename(), entry.filename_bytes()));
+    assert!(zip_reader.entry("├⌐.txt").is_some());
+
+    let mut zip_reader = stream::ZipFileReader::new(Cursor::new(&archive));
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to read entry").expect("missing entry");
+    assert_eq!("├⌐.txt", entry_reader.entry().filename());
+}
+
+#[tokio::test]
+async fn comments_round_trip() {
+    use crate::error::ZipError;
+    use crate::read::seek;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    // A non-ASCII comment requires the UTF-8 flag even where the filename is ASCII.
+    for (filename, comment) in [("a.txt", "cafX"), ("b.txt", "naïve"), ("c.txt", "")] {
+        let builder = ZipEntryBuilder::new(filename, Compression::Stored).comment(String::from(comment));
+        zip_writer.write_entry_whole(builder, b"foo").await.expect("failed to write entry");
+    }
+    zip_writer.comment(String::from("archive comment")).expect("failed to set comment");
+    zip_writer.close().await.expect("failed to close writer");
+
+    // Without the flag, a comment is decoded as CP437.
+    let mut data = input_stream.into_inner();
+    let offset = data.windows(4).position(|window| window == b"cafX").expect("missing comment");
+    data[offset + 3] = 0x82;
+
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(&data)).await.expect("failed to open reader");
+    let comments: Vec<(&str, bool)> = zip_reader
+        .entries()
+        .iter()
+        .map(|entry| (entry.comment(), entry.general_purpose_flags().filename_unicode()))
+        .collect();
+    assert_eq!(vec![("café", false), ("naïve", true), ("", false)], comments);
+    assert_eq!(Some("archive comment"), zip_reader.comment());
+    let entry_reader = zip_reader.entry_reader(1).await.expect("failed to open entry reader");
+    assert_eq!(b"foo", &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+
+    // An archive comment which doesn't fit within its 16-bit length fails as it's set (before any entries are written),
+    // rather than being truncated, and any previous comment is kept.
+    let mut zip_writer = ZipFileWriter::new(Cursor::new(Vec::<u8>::new()));
+    zip_writer.comment("a".repeat(65535)).expect("failed to set comment");
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.comment(String::from("kept")).expect("failed to set comment");
+    assert!(matches!(zip_writer.comment("a".repeat(65536)), Err(ZipError::CommentTooLong(65536))));
+    zip_writer.close().await.expect("failed to close writer");
+
+    let zip_reader = seek::ZipFileReader::new(Cursor::new(input_stream.into_inner())).await;
+    assert_eq!(Some("kept"), zip_reader.expect("failed to open reader").comment());
+}
+
+/// Builds a single-entry archive from raw parts, for fixtures which the writer itself can't produce.
+fn raw_archive(filename: &str, compression: u16, crc: u32, data: &[u8], uncompressed_size: u32) -> Vec<u8> {
+    use crate::spec::header::{
+        CentralDirectoryHeader, EndOfCentralDirectoryHeader, GeneralPurposeFlag, LocalFileHeader,
+    };
+    use crate::spec::signature;
+
+    let lfh = LocalFileHeader {
+        version: 20,
+        flags: GeneralPurposeFlag::from(0),
+        compression,
+        mod_time: 0,
+        mod_date: 0x21,
+        crc,
+        compressed_size: data.len() as u32,
+        uncompressed_size,
+        file_name_length: filename.len() as u16,
+        extra_field_length: 0,
+    };
+
+    let mut archive = Vec::new();
+    archive.extend_from_slice(&signature::LOCAL_FILE_HEADER.to_le_bytes());
+    archive.extend_from_slice(&lfh.as_slice());
+    archive.extend_from_slice(filename.as_bytes());
+    archive.extend_from_slice(data);
+
+    let cdh = CentralDirectoryHeader {
+        v_made_by: 20,
+        v_needed: lfh.version,
+        flags: lfh.flags,
+        compression: lfh.compression,
+        mod_time: lfh.mod_time,
+        mod_date: lfh.mod_date,
+        crc: lfh.crc,
+        compressed_size: lfh.compressed_size,
+        uncompressed_size: lfh.uncompressed_size,
+        file_name_length: lfh.file_name_length,
+        extra_field_length: 0,
+        file_comment_length: 0,
+        disk_start: 0,
+        inter_attr: 0,
+        exter_attr: 0,
+        lh_offset: 0,
+    };
+
+    let cd_offset = archive.len();
+    archive.extend_from_slice(&signature::CENTRAL_DIRECTORY_FILE_HEADER.to_le_bytes());
+    archive.extend_from_slice(&cdh.as_slice());
+    archive.extend_from_slice(filename.as_bytes());
+
+    let eocdh = EndOfCentralDirectoryHeader {
+        disk_num: 0,
+        start_cent_dir_disk: 0,
+        num_of_entries_disk: 1,
+        num_of_entries: 1,
+        size_cent_dir: (archive.len() - cd_offset) as u32,
+        cent_dir_offset: cd_offset as u32,
+        file_comm_length: 0,
+    };
+
+    archive.extend_from_slice(&signature::END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    archive.extend_from_slice(&eocdh.as_slice());
+    archive
+}
+
+fn compute_crc(data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+#[cfg(feature = "deflate64")]
+#[tokio::test]
+async fn deflate64_read() {
+    use crate::read::mem::ZipFileReader;
+
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    // A Deflate64 stream consisting of a single final stored block, which shares its layout with Deflate.
+    let mut compressed = vec![0x01];
+    compressed.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    compressed.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+    compressed.extend_from_slice(data);
+
+    let archive = raw_archive("foo.bar", 9, compute_crc(data), &compressed, data.len() as u32);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    assert_eq!(Compression::Deflate64, zip_reader.entries()[0].compression());
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+    assert_eq!(&data[..], &buffer[..]);
+
+    // A hand-encoded stream whose final back-references reach beyond Deflate's 32 KiB window (via distance codes 30
+    // and 31), with lengths only expressible via Deflate64's 16-bit length code 285 (see `fixtures/README.md`).
+    let fixture = include_bytes!("fixtures/deflate64.zip");
+    let mut zip_reader = ZipFileReader::new(&fixture[..]).await.expect("failed to open reader");
+    let uncompressed_size = zip_reader.entries()[0].uncompressed_size();
+    assert_eq!(Compression::Deflate64, zip_reader.entries()[0].compression());
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+    assert_eq!(uncompressed_size, buffer.len() as u64);
+
+    // The last 5,000 bytes were copied from 60,000 bytes back, and the 300 before them from 40,000 bytes back.
+    let end = buffer.len();
+    assert_eq!(buffer[end - 65_000..end - 60_000], buffer[end - 5_000..]);
+    assert_eq!(buffer[end - 45_300..end - 45_000], buffer[end - 5_300..end - 5_000]);
+
+    let mut output = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Deflate64);
+    assert!(zip_writer.write_entry_whole(open_opts, data).await.is_err());
+}
+
+#[tokio::test]
+async fn legacy_compression_methods() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+
+    let expected =
+        [(1, Compression::Shrink), (2, Compression::Reduce(1)), (5, Compression::Reduce(4)), (6, Compression::Implode)];
+
+    for (method, compression) in expected {
+        let archive = raw_archive("foo.bar", method, compute_crc(b"foo"), b"foo", 3);
+        let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+        // Listing succeeds whatever the method, and only reading the entry itself fails.
+        assert_eq!(compression, zip_reader.entries()[0].compression());
+        assert_eq!(method, zip_reader.entries()[0].compression_raw());
+
+        let result = zip_reader.entry_reader(0).await;
+        match compression {
+            Compression::Reduce(_) => {
+                assert!(matches!(result, Err(ZipError::FeatureNotSupported("Reduce compression"))));
+            }
+            _ if !cfg!(feature = "legacy-codecs") => {
+                assert_eq!(Some("legacy-codecs"), compression.required_feature());
+                assert!(matches!(result, Err(ZipError::FeatureNotEnabled { feature: "legacy-codecs", .. })));
+            }
+            _ => assert_eq!(None, compression.required_feature()),
+        }
+
+        let mut output = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut output);
+        let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), compression);
+        assert!(zip_writer.write_entry_whole(open_opts, b"foo").await.is_err());
+    }
+}
+
+// Pack values of the given bit counts, least significant bit first, as both legacy methods do.
+#[cfg(feature = "legacy-codecs")]
+fn pack_bits(values: &[(u32, u32)]) -> Vec<u8> {
+    let mut packed = Vec::new();
+    let mut position = 0;
+
+    for &(value, count) in values {
+        for index in 0..count {
+            if position % 8 == 0 {
+                packed.push(0);
+            }
+            *packed.last_mut().unwrap() |= (((value >> index) & 1) as u8) << (position % 8);
+            position += 1;
+        }
+    }
+
+    packed
+}
+
+#[cfg(feature = "legacy-codecs")]
+#[tokio::test]
+async fn legacy_codecs_read() {
+    use crate::read::mem::ZipFileReader;
+    use std::collections::HashMap;
+
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let mut data: Vec<u8> = (0..4000)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            b'a' + (state >> 60) as u8
+        })
+        .collect();
+    data.extend_from_slice(&b"aaaa".repeat(50));
+
+    // A plain LZW encoding of the data, which grows its code size as needed but never clears its table.
+    let mut dictionary = HashMap::new();
+    let (mut codes, mut next_code, mut current) = (Vec::new(), 257, None);
+    for &byte in &data {
+        current = match current {
+            None => Some(u32::from(byte)),
+            Some(prefix) => match dictionary.get(&(prefix, byte)) {
+                Some(&code) => Some(code),
+                None => {
+                    codes.push(prefix);
+                    dictionary.insert((prefix, byte), next_code);
+                    next_code += 1;
+                    Some(u32::from(byte))
+                }
+            },
+        };
+    }
+    codes.extend(current);
+
+    let mut code_size = 9;
+    let mut values = Vec::new();
+    for code in codes {
+        while code >= 1 << code_size {
+            values.extend([(256, code_size), (1, code_size)]);
+            code_size += 1;
+        }
+        values.push((code, code_size));
+    }
+    assert!(code_size > 10);
+
+    let archive = raw_archive("foo.bar", 1, compute_crc(&data), &pack_bits(&values), data.len() as u32);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+    assert_eq!(Compression::Shrink, zip_reader.entries()[0].compression());
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+    assert_eq!(data, buffer);
+
+    // Three literals followed by a back-reference with a distance of three and a length of nine, using uniform 6-bit
+    // length and distance trees (and an 8-bit literal tree), under which each symbol is written as its own value.
+    let reversed = |value: u32, count: u32| (value.reverse_bits() >> (32 - count), count);
+    let data = b"abcabcabcabc";
+
+    for options in [0u16, 3] {
+        let literal_tree = options & 2 != 0;
+        let (distance_bits, minimum_length) = if options & 1 != 0 { (7, 3) } else { (6, 2) };
+
+        let mut values = Vec::new();
+        if literal_tree {
+            values.push((15, 8));
+            values.extend([(0xF7, 8); 16]);
+        }
+        for _ in 0..2 {
+            values.push((3, 8));
+            values.extend([(0xF5, 8); 4]);
+        }
+        for &literal in b"abc" {
+            values.push((1, 1));
+            values.push(if literal_tree { reversed(literal.into(), 8) } else { (literal.into(), 8) });
+        }
+        values.extend([(0, 1), (2, distance_bits), reversed(0, 6), reversed(9 - minimum_length, 6)]);
+
+        let mut archive = raw_archive("foo.bar", 6, compute_crc(data), &pack_bits(&values), data.len() as u32);
+        let signature = crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER.to_le_bytes();
+        let cd_offset = archive.windows(4).position(|window| window == signature).unwrap();
+        archive[6..8].copy_from_slice(&(options << 1).to_le_bytes());
+        archive[cd_offset + 8..cd_offset + 10].copy_from_slice(&(options << 1).to_le_bytes());
+
+        let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+        assert_eq!(options as u8, zip_reader.entries()[0].general_purpose_flags().compression_options());
+
+        let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+        let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(&data[..], &buffer[..]);
+    }
+}
+
+#[tokio::test]
+async fn custom_extra_fields() {
+    use crate::spec::extra_field::ExtraField;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored)
+        .extra_field(0xCAFE, vec![1, 2, 3, 4])
+        .extra_field(0xBEEF, Vec::new());
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let expected = vec![ExtraField::new(0xCAFE, vec![1, 2, 3, 4]), ExtraField::new(0xBEEF, Vec::new())];
+
+    input_stream.set_position(0);
+    let mut zip_reader = crate::read::seek::ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    assert_eq!(expected, zip_reader.entries()[0].extra_fields());
+    assert_eq!(12, zip_reader.entries()[0].extra_field().len());
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert_eq!(expected, entry_reader.local_extra_fields());
+
+    input_stream.set_position(0);
+    let mut zip_reader = crate::read::stream::ZipFileReader::new(&mut input_stream);
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+    assert_eq!(expected, entry_reader.entry().extra_fields());
+
+    // Records appended in bulk are serialised identically to those appended one at a time.
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Deflate).extra_fields(expected.clone());
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let zip_reader = crate::read::mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    assert_eq!(expected, zip_reader.entries()[0].extra_fields());
+    assert_eq!(12, zip_reader.entries()[0].extra_field().len());
+}
+
+#[tokio::test]
+async fn entry_lookup_index() {
+    use crate::read::mem::ZipFileReader;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for index in 0..10_000 {
+        let open_opts = ZipEntryBuilder::new(format!("folder/{}.txt", index), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
+    }
+
+    let open_opts = ZipEntryBuilder::new(String::from("folder/0.txt"), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let data = input_stream.into_inner();
+    let zip_reader = ZipFileReader::new(&data).await.expect("failed to open reader");
+
+    // The index is built by the first lookup, and that same index then serves every later lookup.
+    assert!(zip_reader.index.0.get().is_none());
+    assert_eq!(0, zip_reader.entry("folder/0.txt").unwrap().0);
+    let maps = zip_reader.index.0.get().expect("index not built");
+
+    for index in 0..10_000 {
+        let (found, entry) = zip_reader.entry(&format!("folder/{}.txt", index)).expect("missing entry");
+        assert_eq!(index, found);
+        assert_eq!(format!("folder/{}.txt", index), entry.filename());
+    }
+
+    assert!(std::ptr::eq(maps, zip_reader.index.0.get().unwrap()));
+
+    assert_eq!(0, zip_reader.entry("folder/0.txt").unwrap().0);
+    assert!(zip_reader.entry("./folder/1.txt").is_none());
+    assert_eq!(1, zip_reader.entry_normalized("./folder/1.txt").unwrap().0);
+    assert_eq!(2, zip_reader.entry_normalized("folder\\2.txt").unwrap().0);
+    assert_eq!(3, zip_reader.entry_normalized("folder//other/../3.txt").unwrap().0);
+    assert!(zip_reader.entry_normalized("folder/10000.txt").is_none());
+    assert!(std::ptr::eq(maps, zip_reader.index.0.get().unwrap()));
+}
+
+#[tokio::test]
+async fn entry_lookup_traversal() {
+    use crate::read::mem::ZipFileReader;
+
+    let archive = raw_archive("../../etc/passwd", 0, compute_crc(b"foo"), b"foo", 3);
+    let zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    // The index is only built once the reader is first searched by filename.
+    assert!(zip_reader.index.0.get().is_none());
+    assert_eq!(1, zip_reader.entries().len());
+    assert!(zip_reader.index.0.get().is_none());
+
+    // A `..` component with nothing to collapse is kept, so a filename which escapes its root is only found as such.
+    assert!(zip_reader.entry_normalized("etc/passwd").is_none());
+    assert!(zip_reader.index.0.get().is_some());
+    assert!(zip_reader.entry_normalized("/etc/passwd").is_none());
+    assert_eq!(0, zip_reader.entry_normalized("..\\../etc/passwd").unwrap().0);
+    assert_eq!(0, zip_reader.entry_normalized("folder/../../../etc/./passwd").unwrap().0);
+}
+
+#[tokio::test]
+async fn entry_lookup_encoding_and_case() {
+    use crate::read::mem::ZipFileReader;
+
+    // An entry without the language encoding flag set, whose filename is CP437 encoded.
+    let data = b"Hello, world!";
+    let archive = raw_archive("caf?.txt", 0, compute_crc(data), data, data.len() as u32);
+    let archive = archive.split(|&byte| byte == b'?').collect::<Vec<_>>().join(&0x82);
+
+    let zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+    assert_eq!("caf\u{E9}.txt", zip_reader.entries()[0].filename());
+    assert_eq!(0, zip_reader.entry("caf\u{E9}.txt").unwrap().0);
+    assert_eq!(0, zip_reader.entry_ci("CAF\u{C9}.TXT").unwrap().0);
+    assert!(zip_reader.verify(false).await.expect("failed to verify archive").is_valid());
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for filename in ["Report.PDF", "notes.txt", "report.pdf", "\u{C9}T\u{C9}.txt"] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    let data = input_stream.into_inner();
+    let zip_reader = ZipFileReader::new(&data).await.expect("failed to open reader");
+
+    assert_eq!(2, zip_reader.entry("report.pdf").unwrap().0);
+    assert!(zip_reader.entry("REPORT.PDF").is_none());
+    assert_eq!(0, zip_reader.entry_ci("REPORT.PDF").unwrap().0);
+    assert_eq!(0, zip_reader.entry_ci("report.pdf").unwrap().0);
+    assert_eq!(3, zip_reader.entry_ci("\u{E9}t\u{E9}.TXT").unwrap().0);
+    assert!(zip_reader.entry_ci("report.doc").is_none());
+
+    let matches: Vec<usize> = zip_reader.entries_matching_ci("rePort.pDf").iter().map(|(index, _)| *index).collect();
+    assert_eq!(vec![0, 2], matches);
+    assert!(zip_reader.entries_matching_ci("missing.txt").is_empty());
+}
+
+#[tokio::test]
+async fn verify_local_headers() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+    use crate::read::ReaderOptions;
+    use tokio::io::AsyncWriteExt;
+
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit.";
+    let archive = raw_archive("foo.bar", 0, compute_crc(data), data, data.len() as u32);
+    let strict = ReaderOptions::new().verify_local_headers(true);
+
+    let mut zip_reader = ZipFileReader::with_options(&archive, strict.clone()).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert_eq!(&data[..], entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+
+    // Each field of the local file header (which starts the archive) is corrupted in turn, given its offset.
+    let patches: [(&str, usize, fn(&mut u8)); 6] = [
+        ("filename", 30, |byte| *byte = b'g'),
+        ("compression", 8, |byte| *byte = 8),
+        ("general_purpose_flags", 6, |byte| *byte |= 0x02),
+        ("crc32", 14, |byte| *byte ^= 1),
+        ("compressed_size", 18, |byte| *byte += 1),
+        ("uncompressed_size", 22, |byte| *byte += 1),
+    ];
+
+    for (field, offset, patch) in patches {
+        let mut archive = archive.clone();
+        patch(&mut archive[offset]);
+
+        // The central directory is trusted exclusively by default.
+        let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+        let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+        assert_eq!(&data[..], entry_reader.read_to_end_crc().await.expect("failed to read entry"), "{}", field);
+
+        let mismatch = |result: Result<(), ZipError>| match result {
+            Err(ZipError::HeaderMismatch { field: found, index: 0 }) => found == field,
+            _ => false,
+        };
+
+        let zip_reader = ZipFileReader::with_options(&archive, strict.clone()).await;
+        let result = zip_reader.expect("failed to open reader").entry_reader(0).await.map(|_| ());
+        assert!(mismatch(result), "{}", field);
+
+        let zip_reader = crate::read::seek::ZipFileReader::with_options(Cursor::new(&archive), strict.clone()).await;
+        let result = zip_reader.expect("failed to open reader").entry_reader_range(0, 0, None).await.map(|_| ());
+        assert!(mismatch(result), "{}", field);
+    }
+
+    // The local file header's zeroed CRC32 value and sizes aren't compared when they follow in a data descriptor.
+    let mut output = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+    entry_writer.write_all(data).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = output.into_inner();
+    let mut zip_reader = ZipFileReader::with_options(&archive, strict).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert_eq!(&data[..], entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn write_entry_copy() {
+    use crate::read::seek::ZipFileReader;
+    use crate::write::DEFAULT_COPY_BUFFER_SIZE;
+
+    let data =
+        "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...".repeat(2048);
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Deflate);
+    let copied = zip_writer
+        .write_entry_copy(open_opts, data.as_bytes(), DEFAULT_COPY_BUFFER_SIZE)
+        .await
+        .expect("failed to copy entry");
+    assert_eq!(data.len() as u64, copied.uncompressed_size());
+
+    let open_opts = ZipEntryBuilder::new("foo.baz".to_string(), Compression::Deflate);
+    zip_writer.write_entry_copy_sized(open_opts, data.as_bytes(), data.len()).await.expect("failed to copy entry");
+
+    let open_opts = ZipEntryBuilder::new("foo.qux".to_string(), Compression::Deflate);
+    assert!(zip_writer.write_entry_copy_sized(open_opts, data.as_bytes(), data.len() + 1).await.is_err());
+
+    zip_writer.close().await.expect("failed to close writer");
+    input_stream.set_position(0);
+
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    assert_eq!(2, zip_reader.entries().len());
+
+    for index in 0..2 {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        assert_eq!(data, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[tokio::test]
+async fn reader_options_limits() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+    use crate::read::ReaderOptions;
+    use crate::spec::header::EndOfCentralDirectoryHeader;
+    use crate::spec::signature;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for filename in ["foo.bar", "foo.baz"] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    let data = input_stream.into_inner();
+
+    let options = ReaderOptions::new().max_entries(2).max_decompressed_size(3).allow_overlaps(false);
+    assert_eq!(2, ZipFileReader::with_options(&data, options).await.expect("failed to open reader").entries().len());
+
+    let result = ZipFileReader::with_options(&data, ReaderOptions::new().max_entries(1)).await;
+    assert!(matches!(result, Err(ZipError::EntryLimitExceeded(2))));
+
+    let result = ZipFileReader::with_options(&data, ReaderOptions::new().max_decompressed_size(2)).await;
+    assert!(matches!(result, Err(ZipError::DecompressedSizeExceeded)));
+
+    // Point the single entry's local file header offset past the central directory.
+    let archive = raw_archive("foo.bar", 0, compute_crc(b"foo"), b"foo", 3);
+    let cd_offset = archive.len() - 22 - (46 + 7);
+    let mut bogus = archive.clone();
+    bogus[cd_offset + 42..cd_offset + 46].copy_from_slice(&1000u32.to_le_bytes());
+
+    let result = ZipFileReader::new(&bogus).await;
+    assert!(matches!(result, Err(ZipError::EntryDataOutOfBounds(0))));
+
+    // Duplicate the central directory record so that two entries share the same local file header.
+    let record = archive[cd_offset..archive.len() - 22].to_vec();
+    let mut overlapping = archive[..archive.len() - 22].to_vec();
+    overlapping.extend_from_slice(&record);
+
+    let eocdh = EndOfCentralDirectoryHeader {
+        disk_num: 0,
+        start_cent_dir_disk: 0,
+        num_of_entries_disk: 2,
+        num_of_entries: 2,
+        size_cent_dir: (record.len() * 2) as u32,
+        cent_dir_offset: cd_offset as u32,
+        file_comm_length: 0,
+    };
+
+    overlapping.extend_from_slice(&signature::END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    overlapping.extend_from_slice(&eocdh.as_slice());
+
+    assert_eq!(2, ZipFileReader::new(&overlapping).await.expect("failed to open reader").entries().len());
+
+    let result = ZipFileReader::with_options(&overlapping, ReaderOptions::new().allow_overlaps(false)).await;
+    assert!(matches!(result, Err(ZipError::OverlappingEntries(0, 1))));
+}
+
+#[tokio::test]
+async fn entry_size_limits() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+
+    let data = b"Lorem ipsum dolor sit amet";
+    let archive = raw_archive("foo.bar", 0, compute_crc(data), data, data.len() as u32);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let result = entry_reader.take_limit(10).read_to_end_crc().await;
+    assert!(matches!(result, Err(ZipError::DecompressedSizeExceeded)));
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let buffer = entry_reader.take_limit(data.len() as u64).read_to_end_crc().await.expect("failed to read entry");
+    assert_eq!(&data[..], &buffer[..]);
+
+    // A header which lies about the uncompressed size whilst having a valid CRC32 value.
+    let archive = raw_archive("foo.bar", 0, compute_crc(data), data, data.len() as u32 + 1);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let result = entry_reader.read_to_end_crc().await;
+    assert!(matches!(result, Err(ZipError::SizeMismatch { expected: 27, found: 26, .. })));
+}
+
+#[tokio::test]
+async fn read_to_end_bounded_memory() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+
+    let data = b"0123456789";
+
+    // Neither a ZIP64 placeholder nor a merely huge declared size should be pre-allocated for.
+    for uncompressed_size in [u32::MAX, u32::MAX - 1] {
+        let archive = raw_archive("foo.bar", 0, compute_crc(data), data, uncompressed_size);
+        let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+        let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+        alloc::reset_peak();
+        let before = alloc::allocated();
+        let result = entry_reader.read_to_end_crc().await;
+        assert!((alloc::peak() - before) < 128 * 1024);
+        assert!(matches!(result, Err(ZipError::SizeMismatch { found: 10, .. })));
+
+        let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+        let result = entry_reader.read_to_end_crc_limited(5).await;
+        assert!(matches!(result, Err(ZipError::DecompressedSizeExceeded)));
+
+        let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+        let result = entry_reader.read_to_string_crc_limited(5).await;
+        assert!(matches!(result, Err(ZipError::DecompressedSizeExceeded)));
+    }
+
+    // The lower of an existing limit and the method's limit applies.
+    let archive = raw_archive("foo.bar", 0, compute_crc(data), data, data.len() as u32);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let buffer = entry_reader.read_to_end_crc_limited(10).await.expect("failed to read entry");
+    assert_eq!(&data[..], &buffer[..]);
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let result = entry_reader.take_limit(5).read_to_end_crc_limited(u64::MAX).await;
+    assert!(matches!(result, Err(ZipError::DecompressedSizeExceeded)));
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert_eq!("0123456789", entry_reader.read_to_string_crc_limited(10).await.expect("failed to read entry"));
+
+    // Invalid UTF-8 is reported as such once the whole entry has been read and checked.
+    let data = b"foo\xFFbar";
+    let archive = raw_archive("foo.bar", 0, compute_crc(data), data, data.len() as u32);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let result = entry_reader.read_to_string_crc().await;
+    assert!(matches!(result, Err(ZipError::NonUtf8Data(filename)) if filename == "foo.bar"));
+}
+
+#[tokio::test]
+async fn owned_entry_reader_spawn() {
+    use crate::read::fs::ZipFileReader;
+
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let path = dir.path().join("owned.zip");
+    let mut output = tokio::fs::File::create(&path).await.expect("failed to create file");
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+    tokio::io::AsyncWriteExt::flush(&mut output).await.expect("failed to flush file");
+    drop(output);
+
+    let zip_reader = ZipFileReader::new(&path).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader_owned(0).await.expect("failed to open entry reader");
+    drop(zip_reader);
+
+    let handle = tokio::spawn(async move { entry_reader.read_to_string_crc().await });
+    let result = handle.await.expect("failed to join task");
+
+    assert_eq!(data, result.expect("failed to read entry"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn owned_entry_readers_concurrent() {
+    use crate::read::{fs, seek};
+    use std::sync::Arc;
+
+    const ENTRIES: usize = 6;
+    let data = |index: usize| format!("{} Lorem ipsum dolor sit amet", index).repeat(2_000 * (index + 1));
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    for index in 0..ENTRIES {
+        let open_opts = ZipEntryBuilder::new(format!("{}.txt", index), Compression::Deflate);
+        zip_writer.write_entry_whole(open_opts, data(index).as_bytes()).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+    let archive: Arc<[u8]> = input_stream.into_inner().into();
+
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let path = dir.path().join("concurrent.zip");
+    tokio::fs::write(&path, &archive).await.expect("failed to write file");
+
+    // Eight readers over six entries, so two pairs of readers are over the same entry, each read from its own task.
+    let fs_reader = fs::ZipFileReader::new(&path).await.expect("failed to open reader");
+    let seek_reader = seek::ZipFileReader::new(Cursor::new(archive.clone())).await.expect("failed to open reader");
+    let mut tasks = Vec::new();
+
+    for task in 0..8 {
+        let index = task % ENTRIES;
+        let fs_entry = fs_reader.entry_reader_owned(index).await.expect("failed to open entry reader");
+        let seek_entry = seek_reader.entry_reader_owned(index).await.expect("failed to open entry reader");
+
+        tasks.push(tokio::spawn(async move {
+            let (fs_data, seek_data) = tokio::join!(fs_entry.read_to_string_crc(), seek_entry.read_to_string_crc());
+            (index, fs_data, seek_data)
+        }));
+    }
+
+    for task in tasks {
+        let (index, fs_data, seek_data) = task.await.expect("failed to join task");
+        assert_eq!(data(index), fs_data.expect("failed to read entry"));
+        assert_eq!(data(index), seek_data.expect("failed to read entry"));
+    }
+}
+
+#[tokio::test]
+async fn version_and_capabilities() {
+    use crate::error::{Feature, ZipError};
+    use crate::read::mem::ZipFileReader;
+    use crate::read::ReaderOptions;
+    use crate::spec::version::ZipVersion;
+
+    let archive = raw_archive("foo.bar", 0, compute_crc(b"foo"), b"foo", 3);
+    let cd_offset = archive.len() - 22 - (46 + 7);
+    let options = ReaderOptions::new().check_capabilities(true);
+
+    let zip_reader = ZipFileReader::with_options(&archive, options.clone()).await.expect("failed to open reader");
+    assert_eq!(Some(ZipVersion::new(2, 0)), zip_reader.entries()[0].version_made_by());
+    assert_eq!(Some(ZipVersion::new(2, 0)), zip_reader.entries()[0].version_needed());
+
+    let mut newer = archive.clone();
+    newer[cd_offset + 6..cd_offset + 8].copy_from_slice(&70u16.to_le_bytes());
+    assert!(ZipFileReader::new(&newer).await.is_ok());
+
+    let result = ZipFileReader::with_options(&newer, options.clone()).await;
+    assert!(matches!(result, Err(ZipError::UnsupportedFeature(Feature::Version(v))) if v == ZipVersion::new(7, 0)));
+
+    // ZipCrypto can always be decrypted, so isn't reported.
+    let mut encrypted = archive.clone();
+    encrypted[cd_offset + 8..cd_offset + 10].copy_from_slice(&1u16.to_le_bytes());
+    assert!(ZipFileReader::with_options(&encrypted, options).await.is_ok());
+}
+
+#[tokio::test]
+async fn capabilities_encrypted() {
+    use crate::entry::EncryptionMethod;
+    use crate::read::mem::ZipFileReader;
+    use crate::read::ReaderOptions;
+
+    #[allow(unused_mut)]
+    let mut methods = vec![EncryptionMethod::ZipCrypto];
+    #[cfg(feature = "aes")]
+    methods.push(EncryptionMethod::Aes256);
+
+    for method in methods {
+        let mut output = Cursor::new(Vec::new());
+        let mut zip_writer = ZipFileWriter::new(&mut output);
+        let builder = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Stored).password("secret", method);
+        zip_writer.write_entry_whole(builder, b"foo").await.expect("failed to write entry");
+        zip_writer.close().await.expect("failed to close writer");
+
+        let archive = output.into_inner();
+        let options = ReaderOptions::new().check_capabilities(true);
+        let mut zip_reader = ZipFileReader::with_options(&archive, options).await.expect("failed to open reader");
+        let entry_reader = zip_reader.entry_reader_with_password(0, b"secret").await.expect("failed to open entry");
+        assert_eq!("foo", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[cfg(feature = "aes")]
+#[tokio::test]
+async fn capabilities_unknown_aes_strength() {
+    use crate::entry::EncryptionMethod;
+    use crate::error::{Feature, ZipError};
+    use crate::read::mem::ZipFileReader;
+    use crate::read::ReaderOptions;
+
+    let mut output = Cursor::new(Vec::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+    let builder = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Stored);
+    let builder = builder.password("secret", EncryptionMethod::Aes256);
+    zip_writer.write_entry_whole(builder, b"foo").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    // The AES record's key strength (its fifth byte) within the central directory is replaced with an unknown one.
+    let mut archive = output.into_inner();
+    let record = archive.windows(4).rposition(|window| window == [0x01, 0x99, 7, 0]).expect("missing AES record");
+    archive[record + 8] = 4;
+
+    let options = ReaderOptions::new().check_capabilities(true);
+    let result = ZipFileReader::with_options(&archive, options).await;
+    assert!(matches!(result, Err(ZipError::UnsupportedFeature(Feature::Encryption))));
+}
+
+#[tokio::test]
+async fn strong_encryption_and_patched_data() {
+    use crate::error::{Feature, ZipError};
+    use crate::read::{mem, stream};
+
+    let archive = raw_archive("foo.bar", 0, compute_crc(b"foo"), b"foo", 3);
+    let cd_offset = archive.len() - 22 - (46 + 7);
+
+    for (flags, feature) in [(0x41u16, Feature::StrongEncryption), (0x20u16, Feature::PatchedData)] {
+        let mut flagged = archive.clone();
+        flagged[6..8].copy_from_slice(&flags.to_le_bytes());
+        flagged[cd_offset + 8..cd_offset + 10].copy_from_slice(&flags.to_le_bytes());
+
+        // Such entries are still listed, but fail before any of their data is read.
+        let mut zip_reader = mem::ZipFileReader::new(&flagged).await.expect("failed to open reader");
+        assert_eq!("foo.bar", zip_reader.entries()[0].filename());
+        assert_eq!(flags == 0x41, zip_reader.entries()[0].general_purpose_flags().strong_encryption());
+        assert_eq!(flags == 0x20, zip_reader.entries()[0].general_purpose_flags().patched_data());
+
+        let result = zip_reader.entry_reader(0).await;
+        assert!(matches!(result, Err(ZipError::UnsupportedFeature(f)) if f == feature));
+
+        let result = zip_reader.entry_reader_range(0, 0, None).await;
+        assert!(matches!(result, Err(ZipError::UnsupportedFeature(f)) if f == feature));
+
+        // The stream reader skips over the entry's data, so the central directory is then reached as normal.
+        let mut zip_reader = stream::ZipFileReader::new(Cursor::new(&flagged));
+        let result = zip_reader.entry_reader().await;
+        assert!(matches!(result, Err(ZipError::UnsupportedFeature(f)) if f == feature));
+        assert!(zip_reader.entry_reader().await.expect("failed to read next entry").is_none());
+    }
+}
+
+// Encrypt data with the traditional PKWARE scheme, preceded by an encryption header ending with the check byte.
+fn zipcrypto_encrypt(password: &[u8], data: &[u8], check: u8) -> Vec<u8> {
+    let mut keys = crate::crypto::zipcrypto::ZipCryptoKeys::new(password);
+    let mut plaintext = vec![0xA5; 11];
+    plaintext.push(check);
+    plaintext.extend_from_slice(data);
+
+    let encrypt = |byte: u8| {
+        let encrypted = byte ^ keys.stream_byte();
+        keys.update(byte);
+        encrypted
+    };
+
+    plaintext.into_iter().map(encrypt).collect()
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn zipcrypto_decryption() {
+    use crate::error::ZipError;
+    use crate::read::{mem, seek};
+
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+    let crc = compute_crc(data);
+
+    // A Deflate stream consisting of a single final stored block.
+    let mut deflated = vec![0x01];
+    deflated.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    deflated.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+    deflated.extend_from_slice(data);
+
+    for (compression, compressed) in [(0, &data[..]), (8, &deflated[..])] {
+        let encrypted = zipcrypto_encrypt(b"secret", compressed, (crc >> 24) as u8);
+        let mut archive = raw_archive("foo.bar", compression, crc, &encrypted, data.len() as u32);
+        let cd_offset = archive.len() - 22 - (46 + 7);
+        archive[6..8].copy_from_slice(&1u16.to_le_bytes());
+        archive[cd_offset + 8..cd_offset + 10].copy_from_slice(&1u16.to_le_bytes());
+
+        let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+        assert!(zip_reader.entries()[0].general_purpose_flags().encrypted());
+
+        let result = zip_reader.entry_reader(0).await;
+        assert!(matches!(result, Err(ZipError::PasswordRequired(name)) if name == "foo.bar"));
+
+        // The wrong password is rejected via the encryption header's check byte, before any data is read.
+        let result = zip_reader.entry_reader_with_password(0, b"wrong").await;
+        assert!(matches!(result, Err(ZipError::WrongPassword(name)) if name == "foo.bar"));
+
+        let entry_reader = zip_reader.entry_reader_with_password(0, b"secret").await.expect("failed to open entry");
+        let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(&data[..], &buffer[..]);
+
+        let mut zip_reader = seek::ZipFileReader::new(Cursor::new(&archive)).await.expect("failed to open reader");
+        let entry_reader = zip_reader.entry_reader_with_password(0, b"secret").await.expect("failed to open entry");
+        let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(&data[..], &buffer[..]);
+    }
+
+    // The password is ignored for entries which aren't encrypted.
+    let archive = raw_archive("foo.bar", 0, crc, data, data.len() as u32);
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader_with_password(0, b"secret").await.expect("failed to open entry");
+    let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+    assert_eq!(&data[..], &buffer[..]);
+}
+
+// Write a ZIP file holding a single entry encrypted with AES (with a fixed salt), given its compressed data.
+#[cfg(feature = "aes")]
+async fn aes_archive(
+    password: &[u8],
+    strength: u8,
+    version: u16,
+    compression: u16,
+    compressed: &[u8],
+    data: &[u8],
+) -> Vec<u8> {
+    use crate::crypto::aes;
+    use hmac::Mac;
+
+    let salt = vec![0x5A; aes::salt_length(strength).unwrap()];
+    let (mut cipher, mut mac, verification) = aes::derive_keys(password, &salt, strength);
+
+    let mut encrypted = compressed.to_vec();
+    cipher.apply(&mut encrypted);
+    mac.update(&encrypted);
+
+    let mut payload = salt;
+    payload.extend_from_slice(&verification);
+    payload.extend_from_slice(&encrypted);
+    payload.extend_from_slice(&mac.finalize().into_bytes()[..aes::AUTHENTICATION_CODE_LENGTH]);
+
+    let mut field = version.to_le_bytes().to_vec();
+    field.extend_from_slice(b"AE");
+    field.push(strength);
+    field.extend_from_slice(&compression.to_le_bytes());
+
+    let builder = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Unknown(99)).extra_field(0x9901, field);
+    let mut entry = crate::entry::ZipEntry::from(builder);
+    entry.general_purpose_flag.encrypted = true;
+    entry.crc32 = if version == 2 { 0 } else { compute_crc(data) };
+    entry.compressed_size = payload.len() as u64;
+    entry.uncompressed_size = data.len() as u64;
+
+    let mut archive = Cursor::new(Vec::new());
+    let mut writer = ZipFileWriter::new(&mut archive);
+    crate::write::entry_raw::write_entry_raw(&mut writer, entry, &payload[..]).await.expect("failed to write entry");
+    writer.close().await.expect("failed to close writer");
+
+    archive.into_inner()
+}
+
+#[cfg(all(feature = "aes", feature = "deflate"))]
+#[tokio::test]
+async fn aes_decryption() {
+    use crate::error::ZipError;
+    use crate::read::{mem, seek};
+
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    // A Deflate stream consisting of a single final stored block.
+    let mut deflated = vec![0x01];
+    deflated.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    deflated.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+    deflated.extend_from_slice(data);
+
+    let cases = [(1, 1, 0, &data[..]), (2, 2, 8, &deflated[..]), (3, 2, 0, &data[..])];
+
+    for (strength, version, compression, compressed) in cases {
+        let archive = aes_archive(b"secret", strength, version, compression, compressed, data).await;
+
+        let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+        assert!(zip_reader.entries()[0].general_purpose_flags().encrypted());
+
+        let result = zip_reader.entry_reader(0).await;
+        assert!(matches!(result, Err(ZipError::PasswordRequired(name)) if name == "foo.bar"));
+
+        // The wrong password is rejected via the verification value, before any data is decrypted.
+        let result = zip_reader.entry_reader_with_password(0, b"wrong").await;
+        assert!(matches!(result, Err(ZipError::WrongPassword(name)) if name == "foo.bar"));
+
+        // AE-2 entries hold no CRC32 value, so are only checked via their authentication code.
+        let entry_reader = zip_reader.entry_reader_with_password(0, b"secret").await.expect("failed to open entry");
+        let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(&data[..], &buffer[..]);
+
+        let mut zip_reader = seek::ZipFileReader::new(Cursor::new(&archive)).await.expect("failed to open reader");
+        let entry_reader = zip_reader.entry_reader_with_password(0, b"secret").await.expect("failed to open entry");
+        let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(&data[..], &buffer[..]);
+    }
+
+    // Tampering with the encrypted data fails authentication, rather than returning the tampered data.
+    let mut archive = aes_archive(b"secret", 3, 2, 0, data, data).await;
+    let header_length = 30
+        + usize::from(u16::from_le_bytes([archive[26], archive[27]]))
+        + usize::from(u16::from_le_bytes([archive[28], archive[29]]));
+    archive[header_length + 16 + 2] ^= 0x01;
+
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader_with_password(0, b"secret").await.expect("failed to open entry");
+    let result = entry_reader.read_to_end_crc().await;
+    assert!(matches!(result, Err(ZipError::AuthenticationFailed(name)) if name == "foo.bar"));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn encrypted_writing() {
+    use crate::entry::EncryptionMethod;
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    #[allow(unused_mut)]
+    let mut methods = vec![EncryptionMethod::ZipCrypto];
+    #[cfg(feature = "aes")]
+    methods.push(EncryptionMethod::Aes256);
+
+    for method in methods {
+        for compression in [Compression::Stored, Compression::Deflate] {
+            let mut output = Cursor::new(Vec::new());
+            let mut zip_writer = ZipFileWriter::new(&mut output);
+            zip_writer.patch_local_headers(true);
+
+            let builder = ZipEntryBuilder::new("whole".to_string(), compression).password("secret", method);
+            zip_writer.write_entry_whole(builder, data).await.expect("failed to write entry");
+
+            for name in ["stream", "plain"] {
+                let mut builder = ZipEntryBuilder::new(name.to_string(), compression);
+                if name == "stream" {
+                    builder = builder.password("secret", method);
+                }
+
+                let mut entry_writer = zip_writer.write_entry_stream(builder).await.expect("failed to open entry");
+                entry_writer.write_all(data).await.expect("failed to write entry");
+                entry_writer.close().await.expect("failed to close entry");
+            }
+
+            zip_writer.close().await.expect("failed to close writer");
+            let archive = output.into_inner();
+            let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+            for index in 0..2 {
+                let entry = &zip_reader.entries()[index];
+                assert!(entry.general_purpose_flags().encrypted());
+
+                let result = zip_reader.entry_reader(index).await;
+                assert!(matches!(result, Err(ZipError::PasswordRequired(_))));
+                // The header's random bytes occasionally let a wrong password pass its check, but never the data's.
+                match zip_reader.entry_reader_with_password(index, b"wrong").await {
+                    Ok(entry_reader) => assert!(entry_reader.read_to_end_crc().await.is_err()),
+                    Err(err) => assert!(matches!(err, ZipError::WrongPassword(_))),
+                }
+
+                let entry_reader = zip_reader.entry_reader_with_password(index, b"secret").await.expect("open failed");
+                let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+                assert_eq!(&data[..], &buffer[..]);
+            }
+
+            // Encryption doesn't carry over to the entries which follow.
+            assert!(!zip_reader.entries()[2].general_purpose_flags().encrypted());
+            let entry_reader = zip_reader.entry_reader(2).await.expect("failed to open entry");
+            let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+            assert_eq!(&data[..], &buffer[..]);
+        }
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn append_to_existing() {
+    use crate::read::mem::ZipFileReader;
+    use crate::write::DuplicatePolicy;
+
+    let mut archive = Cursor::new(Vec::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    let comment = "existing comment ".repeat(8);
+    zip_writer.comment(comment.clone()).expect("failed to set comment");
+
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        let builder = ZipEntryBuilder::new(name.to_string(), Compression::Deflate);
+        zip_writer.write_entry_whole(builder, name.as_bytes()).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    let mut zip_writer = ZipFileWriter::from_existing(&mut archive).await.expect("failed to open writer");
+    for name in ["d.txt", "e.txt"] {
+        let builder = ZipEntryBuilder::new(name.to_string(), Compression::Stored);
+        zip_writer.write_entry_whole(builder, name.as_bytes()).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    let mut zip_reader = ZipFileReader::new(archive.get_ref()).await.expect("failed to open reader");
+    assert_eq!(Some(comment.as_str()), zip_reader.comment());
+
+    let names: Vec<_> = zip_reader.entries().iter().map(|entry| entry.filename().to_owned()).collect();
+    assert_eq!(vec!["a.txt", "b.txt", "c.txt", "d.txt", "e.txt"], names);
+
+    for index in 0..5 {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+        let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(names[index].as_bytes(), &buffer[..]);
+    }
+
+    // Replacing an existing entry omits its record, and the comment is removed, so the (otherwise shorter) ZIP file is
+    // padded to its existing length.
+    let length = archive.get_ref().len();
+    let mut zip_writer = ZipFileWriter::from_existing(&mut archive).await.expect("failed to open writer");
+    zip_writer.deduplicate(DuplicatePolicy::Overwrite);
+    zip_writer.comment(String::new()).expect("failed to set comment");
+    let builder = ZipEntryBuilder::new("a.txt".to_string(), Compression::Stored);
+    zip_writer.write_entry_whole(builder, b"a").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    assert_eq!(length, archive.get_ref().len());
+    let mut zip_reader = ZipFileReader::new(archive.get_ref()).await.expect("failed to open reader");
+    let names: Vec<_> = zip_reader.entries().iter().map(|entry| entry.filename().to_owned()).collect();
+    assert_eq!(vec!["b.txt", "c.txt", "d.txt", "e.txt", "a.txt"], names);
+
+    let entry_reader = zip_reader.entry_reader(4).await.expect("failed to open entry");
+    assert_eq!(b"a", &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+}
+
+#[tokio::test]
+async fn append_overwrite_cp437() {
+    use crate::read::mem::ZipFileReader;
+    use crate::write::DuplicatePolicy;
+
+    // The existing entry's filename is CP437, so it's overwritten by an entry whose decoded filename matches, despite
+    // their bytes differing.
+    let mut archive = Cursor::new(raw_archive("é.txt", 0, compute_crc(b"foo"), b"foo", 3));
+    let mut zip_writer = ZipFileWriter::from_existing(&mut archive).await.expect("failed to open writer");
+    zip_writer.deduplicate(DuplicatePolicy::Overwrite);
+    let builder = ZipEntryBuilder::new("├⌐.txt".to_string(), Compression::Stored);
+    zip_writer.write_entry_whole(builder, b"bar").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let mut zip_reader = ZipFileReader::new(archive.get_ref()).await.expect("failed to open reader");
+    assert_eq!(1, zip_reader.entries().len());
+    assert_eq!("├⌐.txt", zip_reader.entries()[0].filename());
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    assert_eq!(b"bar", &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+
+    // The same applies to entries written under the Error policy.
+    let mut archive = Cursor::new(raw_archive("é.txt", 0, compute_crc(b"foo"), b"foo", 3));
+    let mut zip_writer = ZipFileWriter::from_existing(&mut archive).await.expect("failed to open writer");
+    zip_writer.deduplicate(DuplicatePolicy::Error);
+    let builder = ZipEntryBuilder::new("├⌐.txt".to_string(), Compression::Stored);
+    let result = zip_writer.write_entry_whole(builder, b"bar").await;
+    assert!(matches!(result, Err(crate::error::ZipError::DuplicateFilename(filename)) if filename == "├⌐.txt"));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn raw_entry_copy() {
+    use crate::read::seek::ZipFileReader;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Half of the entries are followed by data descriptors, whose sizes are only recorded alongside the CRC32 value.
+    let mut source = Cursor::new(Vec::new());
+    let mut zip_writer = ZipFileWriter::new(&mut source);
+    for index in 0..100 {
+        let builder = ZipEntryBuilder::new(format!("{}.txt", index), Compression::Deflate);
+        let data = format!("Entry {} ", index).repeat(index + 1);
+
+        if index % 2 == 0 {
+            zip_writer.write_entry_whole(builder, data.as_bytes()).await.expect("failed to write entry");
+            continue;
+        }
+
+        let mut entry_writer = zip_writer.write_entry_stream(builder).await.expect("failed to open entry");
+        entry_writer.write_all(data.as_bytes()).await.expect("failed to write entry");
+        entry_writer.close().await.expect("failed to close entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    let mut source_reader = ZipFileReader::new(Cursor::new(source.into_inner())).await.expect("failed to open reader");
+    let mut output = Cursor::new(Vec::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+
+    for index in 0..100 {
+        let entry = source_reader.entries()[index].clone();
+        let raw_reader = source_reader.raw_entry_reader(index).await.expect("failed to open raw reader");
+        let written = zip_writer.write_entry_raw(entry, raw_reader).await.expect("failed to write entry");
+        assert_eq!(source_reader.entries()[index].crc32(), written.crc32());
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    let mut output_reader = ZipFileReader::new(Cursor::new(output.into_inner())).await.expect("failed to open reader");
+    assert_eq!(100, output_reader.entries().len());
+
+    for index in 0..100 {
+        let mut source_raw = Vec::new();
+        let mut output_raw = Vec::new();
+        source_reader.raw_entry_reader(index).await.unwrap().read_to_end(&mut source_raw).await.unwrap();
+        output_reader.raw_entry_reader(index).await.unwrap().read_to_end(&mut output_raw).await.unwrap();
+        assert_eq!(source_raw, output_raw);
+
+        let entry_reader = output_reader.entry_reader(index).await.expect("failed to open entry");
+        let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(format!("Entry {} ", index).repeat(index + 1).as_bytes(), &buffer[..]);
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn copy_to_end_counted() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+    entry_writer.write_all(data.as_bytes()).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    let mut output = Vec::new();
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let copied = entry_reader.copy_to_end_crc(&mut output, 65536).await.expect("failed to copy entry");
+
+    assert_eq!(data.len() as u64, copied);
+    assert_eq!(data.as_bytes(), &output[..]);
+
+    let archive = raw_archive("foo.bar", 0, compute_crc(data.as_bytes()), data.as_bytes(), 1);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let result = entry_reader.copy_to_end_crc(&mut Vec::new(), 65536).await;
+    assert!(matches!(result, Err(ZipError::SizeMismatch { expected: 1, .. })));
+}
+
+#[tokio::test]
+async fn entry_progress() {
+    use crate::read::mem::ZipFileReader;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Stored);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+    entry_writer.write_all(&data.as_bytes()[..10]).await.expect("failed to write entry");
+
+    let progress = entry_writer.progress();
+    assert_eq!(10, progress.bytes());
+
+    entry_writer.write_all(&data.as_bytes()[10..]).await.expect("failed to write entry");
+    assert_eq!(data.len() as u64, progress.bytes());
+    assert_eq!(data.len() as u64, entry_writer.bytes_written());
+
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let mut entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+
+    let progress = entry_reader.progress();
+    assert_eq!(0, progress.bytes());
+
+    let mut buffer = [0; 10];
+    entry_reader.read_exact(&mut buffer).await.expect("failed to read entry");
+    assert_eq!(10, progress.bytes());
+
+    entry_reader.read_to_end(&mut Vec::new()).await.expect("failed to read entry");
+    assert_eq!(data.len() as u64, progress.bytes());
+    assert_eq!(data.len() as u64, entry_reader.bytes_read());
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn entry_progress_compressed() {
+    use crate::entry::EncryptionMethod;
+    use tokio::io::AsyncWriteExt;
+
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(4096);
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    // Both writers report the compressed bytes written, rather than the uncompressed bytes they're given.
+    let open_opts = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+    let progress = entry_writer.progress();
+    entry_writer.write_all(data.as_bytes()).await.expect("failed to write entry");
+    entry_writer.flush().await.expect("failed to flush entry");
+    assert!(progress.bytes() > 0 && progress.bytes() < data.len() as u64);
+
+    let written = entry_writer.close().await.expect("failed to close entry");
+    assert_eq!(written.compressed_size(), progress.bytes());
+
+    let progress = zip_writer.progress();
+    assert_eq!(0, progress.bytes());
+
+    let open_opts = ZipEntryBuilder::new("bar.foo".to_string(), Compression::Deflate);
+    let written = zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+    assert_eq!(written.compressed_size(), progress.bytes());
+    assert!(progress.bytes() < data.len() as u64);
+
+    // The count is reset for each entry, and includes any encryption header.
+    let open_opts = ZipEntryBuilder::new("baz.foo".to_string(), Compression::Stored);
+    let open_opts = open_opts.password("secret", EncryptionMethod::ZipCrypto);
+    let written = zip_writer.write_entry_whole(open_opts, b"baz").await.expect("failed to write entry");
+    assert_eq!(15, progress.bytes());
+    assert_eq!(written.compressed_size(), progress.bytes());
+
+    zip_writer.close().await.expect("failed to close writer");
+}
+
+#[tokio::test]
+async fn lazy_central_directory() {
+    use crate::read::fs::ZipFileReader;
+    use crate::read::ReaderOptions;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for index in 0..100_000 {
+        let open_opts = ZipEntryBuilder::new(format!("folder/{}.txt", index), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, index.to_string().as_bytes()).await.expect("failed to write entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let path = dir.path().join("lazy.zip");
+    tokio::fs::write(&path, input_stream.into_inner()).await.expect("failed to write file");
+
+    let zip_reader = ZipFileReader::with_options(&path, ReaderOptions::new().lazy_entries(true)).await;
+    let zip_reader = zip_reader.expect("failed to open reader");
+    assert!(zip_reader.entries().is_empty());
+
+    let mut cd_entries = zip_reader.cd_entries().await.expect("failed to open central directory");
+    let entry = cd_entries.find("folder/99999.txt").await.expect("failed to scan entries").expect("missing entry");
+    assert_eq!("folder/99999.txt", entry.entry().filename());
+    assert_eq!(99_999, entry.index());
+    assert!(cd_entries.next_entry().await.expect("failed to scan entries").is_none());
+
+    let entry_reader = zip_reader.reader_with_entry(&entry).await.expect("failed to open entry");
+    assert_eq!("99999", entry_reader.read_to_string_crc().await.expect("failed to read entry to string"));
+
+    let mut cd_entries = zip_reader.cd_entries().await.expect("failed to open central directory");
+    assert!(cd_entries.find("folder/100000.txt").await.expect("failed to scan entries").is_none());
+
+    // Only one entry is held at a time, so the scan's memory usage doesn't grow with the number of entries.
+    alloc::reset_peak();
+    let before = alloc::allocated();
+
+    let mut count = 0;
+    let mut cd_entries = zip_reader.cd_entries().await.expect("failed to open central directory");
+    while cd_entries.next_entry().await.expect("failed to scan entries").is_some() {
+        count += 1;
+    }
+
+    assert_eq!(100_000, count);
+    assert!((alloc::peak() - before) < 128 * 1024);
+}
+
+#[tokio::test]
+async fn preamble_tolerance() {
+    use crate::read::mem::ZipFileReader;
+
+    let data = b"Lorem ipsum dolor sit amet";
+    let archive = raw_archive("foo.bar", 0, compute_crc(data), data, data.len() as u32);
+
+    // Pseudo-random bytes standing in for a self-extracting stub.
+    let mut state: u32 = 0x2545F491;
+    let mut prepended: Vec<u8> = (0..1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+    prepended.extend_from_slice(&archive);
+
+    let mut zip_reader = ZipFileReader::new(&prepended).await.expect("failed to open reader");
+    assert_eq!(1024, zip_reader.preamble_length());
+    assert_eq!("foo.bar", zip_reader.entries()[0].filename());
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert_eq!(&data[..], &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+
+    let zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+    assert_eq!(0, zip_reader.preamble_length());
+}
+
+#[tokio::test]
+async fn preamble_tolerance_seek_and_fs() {
+    use crate::read::{fs, seek, ExtractOptions};
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    for (filename, data) in [("a.txt", &b"foo"[..]), ("dir/b.txt", b"lorem ipsum"), ("dir/c.txt", b"")] {
+        let open_opts = ZipEntryBuilder::new(filename, Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, data).await.expect("failed to write entry");
+    }
+
+    // A comment holding a false end of central directory signature, which is found first when searching backwards but
+    // doesn't describe a central directory beginning with a central directory header.
+    zip_writer.comment(format!("PK\u{5}\u{6}{}", "x".repeat(18))).expect("failed to set comment");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let mut archive = vec![0xAA; 1024];
+    archive.extend_from_slice(&input_stream.into_inner());
+
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(&archive)).await.expect("failed to open reader");
+    assert_eq!((1024, 3), (zip_reader.preamble_length(), zip_reader.entries().len()));
+    let entry_reader = zip_reader.entry_reader(1).await.expect("failed to open entry reader");
+    assert_eq!(b"lorem ipsum", &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let root = dir.path();
+    std::fs::write(root.join("archive.exe"), &archive).expect("failed to write file");
+
+    let mut zip_reader = fs::ZipFileReader::new(root.join("archive.exe")).await.expect("failed to open reader");
+    assert_eq!(1024, zip_reader.preamble_length());
+    zip_reader.extract(root.join("out"), ExtractOptions::new()).await.expect("failed to extract");
+
+    assert_eq!(b"foo", &std::fs::read(root.join("out/a.txt")).expect("failed to read file")[..]);
+    assert_eq!(b"lorem ipsum", &std::fs::read(root.join("out/dir/b.txt")).expect("failed to read file")[..]);
+    assert!(std::fs::read(root.join("out/dir/c.txt")).expect("failed to read file").is_empty());
+}
+
+#[tokio::test]
+async fn central_directory_snapshot() {
+    use crate::read::mem::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for filename in ["foo.bar", "foo.baz"] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+    }
+
+    let snapshot = zip_writer.central_directory_snapshot();
+
+    // Simulate a crash part-way through writing a third entry.
+    let open_opts = ZipEntryBuilder::new(String::from("foo.qux"), Compression::Stored);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+    entry_writer.write_all(data.as_bytes()).await.expect("failed to write entry");
+    drop(entry_writer);
+    drop(zip_writer);
+
+    // The snapshot records where it should be placed within its end of central directory header.
+    let eocdh_offset = snapshot.len() - 22;
+    let cd_offset = u32::from_le_bytes(snapshot[eocdh_offset + 16..eocdh_offset + 20].try_into().unwrap());
+
+    let mut recovered = input_stream.into_inner();
+    recovered.truncate(cd_offset as usize);
+    recovered.extend_from_slice(&snapshot);
+
+    let mut zip_reader = ZipFileReader::new(&recovered).await.expect("failed to open reader");
+    assert_eq!(2, zip_reader.entries().len());
+
+    for index in 0..2 {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        assert_eq!(data, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[tokio::test]
+async fn write_entry_whole_vectored() {
+    use crate::read::mem::ZipFileReader;
+    use std::io::IoSlice;
+
+    let parts = ["Lorem ipsum ", "dolor sit amet, ", "consectetur adipiscing elit..."];
+    let slices: Vec<IoSlice<'_>> = parts.iter().map(|part| IoSlice::new(part.as_bytes())).collect();
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+    let written = zip_writer.write_entry_whole_vectored(open_opts, &slices).await.expect("failed to write entry");
+    assert_eq!(compute_crc(parts.concat().as_bytes()), written.crc32());
+
+    #[cfg(feature = "deflate")]
+    {
+        let open_opts = ZipEntryBuilder::new(String::from("foo.baz"), Compression::Deflate);
+        zip_writer.write_entry_whole_vectored(open_opts, &slices).await.expect("failed to write entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    for index in 0..zip_reader.entries().len() {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        assert_eq!(parts.concat(), entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[tokio::test]
+async fn contextual_errors() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+    use crate::spec::signature;
+    use std::error::Error;
+
+    let data = b"Lorem ipsum dolor sit amet";
+    let archive = raw_archive("foo.bar", 0, compute_crc(data), data, data.len() as u32);
+    let cd_offset = archive.len() - 22 - (46 + 7);
+
+    let mut corrupted = archive.clone();
+    corrupted[cd_offset] = 0;
+
+    let result = ZipFileReader::new(&corrupted).await;
+    match result {
+        Err(ZipError::UnexpectedSignature { offset, expected, found }) => {
+            assert_eq!(Some(cd_offset as u64), offset);
+            assert_eq!(signature::CENTRAL_DIRECTORY_FILE_HEADER, expected);
+            assert_eq!(signature::CENTRAL_DIRECTORY_FILE_HEADER & !0xFF, found);
+        }
+        _ => panic!("expected an unexpected signature error"),
+    }
+
+    let archive = raw_archive("foo.bar", 0, 0xDEADBEEF, data, data.len() as u32);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+
+    match entry_reader.read_to_end_crc().await {
+        Err(ZipError::CRC32CheckError { filename, expected, found }) => {
+            assert_eq!("foo.bar", filename);
+            assert_eq!(0xDEADBEEF, expected);
+            assert_eq!(compute_crc(data), found);
+        }
+        _ => panic!("expected a CRC32 check error"),
+    }
+
+    let result = ZipFileReader::new(&[]).await;
+    assert!(matches!(result, Err(ZipError::EocdNotFound { searched_bytes: 0 })));
+
+    let error = ZipError::from(std::io::Error::from(std::io::ErrorKind::UnexpectedEof));
+    assert!(error.source().is_some());
+}
+
+#[tokio::test]
+async fn stream_skip_entry() {
+    use crate::read::stream::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+
+    #[cfg(feature = "deflate")]
+    {
+        let open_opts = ZipEntryBuilder::new(String::from("foo.baz"), Compression::Deflate);
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+        entry_writer.write_all(data.as_bytes()).await.expect("failed to write entry");
+        entry_writer.close().await.expect("failed to close entry");
+    }
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.qux"), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream);
+
+    let skipped = zip_reader.skip_entry().await.expect("failed to skip entry");
+    assert_eq!("foo.bar", skipped.expect("missing entry").filename());
+
+    #[cfg(feature = "deflate")]
+    {
+        let skipped = zip_reader.skip_entry().await.expect("failed to skip entry");
+        assert_eq!("foo.baz", skipped.expect("missing entry").filename());
+    }
+
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").expect("missing entry");
+    assert_eq!("foo.qux", entry_reader.entry().filename());
+    assert_eq!(data, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+
+    assert!(zip_reader.skip_entry().await.expect("failed to skip entry").is_none());
+    assert!(zip_reader.finished());
+}
+
+#[tokio::test]
+async fn stream_skip_descriptor_entry() {
+    use crate::read::stream::ZipFileReader;
+    use crate::spec::signature::{DATA_DESCRIPTOR, LOCAL_FILE_HEADER};
+    use crate::write::DataDescriptorStyle;
+    use tokio::io::AsyncWriteExt;
+
+    // A signature whose compressed size matches the bytes preceding it but isn't followed by another header, and one
+    // followed by a local file header signature but whose compressed size doesn't match.
+    let mut decoy = b"abc".to_vec();
+    for (size, next) in [(3u32, *b"more"), (0xFFFF, LOCAL_FILE_HEADER.to_le_bytes())] {
+        decoy.extend_from_slice(&DATA_DESCRIPTOR.to_le_bytes());
+        decoy.extend_from_slice(&[0; 4]);
+        decoy.extend_from_slice(&size.to_le_bytes());
+        decoy.extend_from_slice(&size.to_le_bytes());
+        decoy.extend_from_slice(&next);
+    }
+    let data = decoy.repeat(5_000);
+
+    for style in [DataDescriptorStyle::Signed, DataDescriptorStyle::Unsigned] {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+        zip_writer.data_descriptor_style(style);
+
+        for (filename, zip64) in [("decoy.bin", false), ("zip64.bin", true)] {
+            let open_opts = ZipEntryBuilder::new(filename, Compression::Stored).force_zip64(zip64);
+            let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+            entry_writer.write_all(&data).await.expect("failed to write entry");
+            entry_writer.close().await.expect("failed to close entry");
+        }
+
+        let open_opts = ZipEntryBuilder::new("last.txt", Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+        zip_writer.close().await.expect("failed to close writer");
+
+        // Neither entry's data can be delimited by its local file header, so is skipped by locating its descriptor.
+        input_stream.set_position(0);
+        let mut zip_reader = ZipFileReader::new(&mut input_stream);
+        for filename in ["decoy.bin", "zip64.bin"] {
+            let skipped = zip_reader.skip_entry().await.expect("failed to skip entry");
+            assert_eq!(filename, skipped.expect("missing entry").filename(), "{:?}", style);
+        }
+
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader");
+        let entry_reader = entry_reader.expect("missing entry");
+        assert_eq!("last.txt", entry_reader.entry().filename());
+        assert_eq!("foo", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        assert!(zip_reader.skip_entry().await.expect("failed to skip entry").is_none());
+    }
+}
+
+#[tokio::test]
+async fn archive_verification() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+    use crate::read::FailureKind;
+    use tokio::io::AsyncWriteExt;
+
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.baz"), Compression::Stored);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+    entry_writer.write_all(data.as_bytes()).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    let report = zip_reader.verify(false).await.expect("failed to verify archive");
+    assert!(report.is_valid());
+    assert_eq!(2, report.entries_checked());
+    assert_eq!(2, report.actual_entries());
+
+    // Corrupt the data of the first entry, which only a full verification will notice.
+    let mut corrupted = archive.clone();
+    corrupted[30 + 7] ^= 0xFF;
+
+    let zip_reader = ZipFileReader::new(&corrupted).await.expect("failed to open reader");
+    assert!(zip_reader.verify_structure_only(false).await.expect("failed to verify archive").is_valid());
+
+    let report = zip_reader.verify(false).await.expect("failed to verify archive");
+    assert!(!report.is_valid());
+    assert_eq!(2, report.entries_checked());
+    assert_eq!(1, report.failures().len());
+    assert_eq!("foo.bar", report.failures()[0].filename());
+    assert!(matches!(report.failures()[0].kind(), FailureKind::Read(ZipError::CRC32CheckError { .. })));
+
+    // A local file header which disagrees with the central directory is reported, and can stop verification early.
+    let data = b"Lorem ipsum dolor sit amet";
+    let mut archive = raw_archive("foo.bar", 0, compute_crc(data), data, data.len() as u32);
+    archive[14] ^= 0xFF;
+
+    let zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let report = zip_reader.verify_structure_only(true).await.expect("failed to verify archive");
+    assert_eq!(1, report.failures().len());
+    assert!(matches!(report.failures()[0].kind(), FailureKind::LocalHeaderMismatch("crc32")));
+}
+
+#[tokio::test]
+async fn size_source_inconsistent_headers() {
+    use crate::read::mem::ZipFileReader;
+    use crate::read::{FailureKind, ReaderOptions, SizeSource};
+
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for filename in ["foo.bar", "foo.baz"] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, data).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    // Declare a compressed size a few bytes short within the first entry's local file header only.
+    let mut archive = input_stream.into_inner();
+    archive[18..22].copy_from_slice(&(data.len() as u32 - 4).to_le_bytes());
+
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert_eq!(&data[..], &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+
+    let report = zip_reader.verify(false).await.expect("failed to verify archive");
+    assert_eq!(1, report.failures().len());
+    assert!(matches!(report.failures()[0].kind(), FailureKind::LocalHeaderMismatch("compressed_size")));
+
+    let options = ReaderOptions::new().size_source(SizeSource::LocalHeader);
+    let mut zip_reader = ZipFileReader::with_options(&archive, options).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert!(entry_reader.read_to_end_crc().await.is_err());
+
+    // Stored entries aren't self-terminating, so fall back to the central directory's size.
+    let options = ReaderOptions::new().size_source(SizeSource::TrustDecoder);
+    let mut zip_reader = ZipFileReader::with_options(&archive, options).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert_eq!(&data[..], &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+
+    let report = zip_reader.verify(false).await.expect("failed to verify archive");
+    assert_eq!(1, report.failures().len());
+    assert_eq!("foo.bar", report.failures()[0].filename());
+
+    let declared = data.len() as u64 - 4;
+    let actual = data.len() as u64;
+    let expected = FailureKind::CompressedSizeMismatch { header: SizeSource::LocalHeader, declared, actual };
+    assert_eq!(format!("{:?}", expected), format!("{:?}", report.failures()[0].kind()));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn size_source_trust_decoder_deflate() {
+    use crate::read::mem::ZipFileReader;
+    use crate::read::{FailureKind, ReaderOptions, SizeSource};
+
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    // A Deflate stream consisting of a single final stored block.
+    let mut compressed = vec![0x01];
+    compressed.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    compressed.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+    compressed.extend_from_slice(data);
+
+    // Declare an excessive compressed size within the central directory only.
+    let mut archive = raw_archive("foo.bar", 8, compute_crc(data), &compressed, data.len() as u32);
+    let cd_offset = 30 + "foo.bar".len() + compressed.len();
+    archive[cd_offset + 20..cd_offset + 24].copy_from_slice(&(compressed.len() as u32 + 3).to_le_bytes());
+
+    for source in [SizeSource::LocalHeader, SizeSource::CentralDirectory, SizeSource::TrustDecoder] {
+        let options = ReaderOptions::new().size_source(source);
+        let mut zip_reader = ZipFileReader::with_options(&archive, options).await.expect("failed to open reader");
+        let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+        assert_eq!(&data[..], &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+
+        let report = zip_reader.verify(false).await.expect("failed to verify archive");
+        assert_eq!(1, report.failures().len());
+
+        match source {
+            SizeSource::TrustDecoder => {
+                let (declared, actual) = (compressed.len() as u64 + 3, compressed.len() as u64);
+                let header = SizeSource::CentralDirectory;
+                let expected = FailureKind::CompressedSizeMismatch { header, declared, actual };
+                assert_eq!(format!("{:?}", expected), format!("{:?}", report.failures()[0].kind()));
+            }
+            _ => assert!(matches!(report.failures()[0].kind(), FailureKind::LocalHeaderMismatch("compressed_size"))),
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn entry_bytes_stream_and_sink() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+    use futures_util::{stream, StreamExt, TryStreamExt};
+
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+    let chunks = data.chunks(7).map(|chunk| Ok::<_, ZipError>(bytes::Bytes::copy_from_slice(chunk)));
+    stream::iter(chunks).forward(entry_writer.sink_bytes()).await.expect("failed to forward chunks");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+
+    let chunks: Vec<bytes::Bytes> = entry_reader.into_bytes_stream(16).try_collect().await.expect("failed to read");
+    assert!(chunks.iter().all(|chunk| chunk.len() <= 16));
+    assert_eq!(&data[..], &chunks.concat()[..]);
+
+    // A failed CRC32 check is yielded as the last item, after all of the entry's data.
+    let archive = raw_archive("foo.bar", 0, compute_crc(data) ^ 1, data, data.len() as u32);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+
+    let mut items: Vec<_> = entry_reader.into_bytes_stream(16).collect().await;
+    assert!(matches!(items.pop(), Some(Err(ZipError::CRC32CheckError { .. }))));
+
+    let chunks: Vec<bytes::Bytes> = items.into_iter().map(|item| item.expect("failed to read chunk")).collect();
+    assert_eq!(&data[..], &chunks.concat()[..]);
+}
+
+#[cfg(all(feature = "stream", feature = "deflate"))]
+#[tokio::test]
+async fn stream_archive_round_trip() {
+    use crate::error::ZipError;
+    use crate::read::stream::ZipFileReader;
+    use futures_util::{stream, StreamExt, TryStreamExt};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    let data: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8).collect();
+    let sizes = [20_000, 0, 100_000];
+
+    let entries: Vec<_> = sizes
+        .iter()
+        .enumerate()
+        .map(|(index, size)| {
+            (
+                ZipEntryBuilder::new(format!("foo{}.bar", index), Compression::Deflate),
+                Cursor::new(data[..*size].to_vec()),
+            )
+        })
+        .collect();
+    let (body, task) = crate::write::stream_archive(stream::iter(entries), 4096);
+
+    let chunks: Vec<bytes::Bytes> = body.try_collect().await.expect("failed to stream archive");
+    task.await.expect("writer task panicked").expect("failed to write archive");
+    assert!(chunks.iter().all(|chunk| chunk.len() <= 4096));
+
+    let archive = chunks.concat();
+    let mut zip_reader = ZipFileReader::new(Cursor::new(&archive));
+
+    for size in sizes {
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+        assert_eq!(&data[..size], entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    }
+    assert!(zip_reader.entry_reader().await.expect("failed to read central directory").is_none());
+
+    // A source which fails part-way through ends the stream with an error after the bytes already written, and the
+    // task returns the original error.
+    struct Failing(usize);
+
+    impl AsyncRead for Failing {
+        fn poll_read(mut self: Pin<&mut Self>, _: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+            if self.0 == 0 {
+                return Poll::Ready(Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "source failed")));
+            }
+
+            let length = self.0.min(b.remaining()).min(1024);
+            b.put_slice(&[0; 1024][..length]);
+            self.0 -= length;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    let entry = (ZipEntryBuilder::new(String::from("foo.bar"), Compression::Deflate), Failing(50_000));
+    let (body, task) = crate::write::stream_archive(stream::iter([entry]), 4096);
+
+    let mut items: Vec<_> = body.collect().await;
+    match items.pop() {
+        Some(Err(ZipError::UpstreamReadError(err))) => assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset),
+        item => panic!("expected the stream to end with an error, found {:?}", item),
+    }
+    assert!(items.into_iter().all(|item| item.is_ok()));
+
+    match task.await.expect("writer task panicked") {
+        Err(ZipError::UpstreamReadError(err)) => assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset),
+        result => panic!("expected the task to fail, found {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn central_directory_memory() {
+    use crate::read::seek::ZipFileReader;
+
+    // Only the serialised records of written entries should be retained (46 bytes each, plus their 7-byte filename).
+    const ENTRIES: usize = 300_000;
+    const RECORD_LENGTH: usize = 46 + 7;
+
+    let mut zip_writer = ZipFileWriter::new(tokio::io::sink());
+    let before = alloc::allocated();
+
+    for index in 0..ENTRIES {
+        let open_opts = ZipEntryBuilder::new(format!("{:07}", index), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
+    }
+
+    // The records' buffer may have grown to up to twice their length.
+    let retained = (alloc::allocated() - before) as usize;
+    assert!(retained <= 2 * ENTRIES * RECORD_LENGTH, "{} bytes retained", retained);
+    drop(zip_writer);
+
+    // The EOCDH can only count 65535 entries, so listing more relies on the ZIP64 end of central directory record.
+    const LISTED: usize = 70_000;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for index in 0..LISTED {
+        let open_opts = ZipEntryBuilder::new(format!("{:07}", index), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+
+    assert!(zip_reader.metadata().has_zip64_locator());
+    assert_eq!(LISTED, zip_reader.entries().len());
+    for (index, entry) in zip_reader.entries().iter().enumerate().step_by(997) {
+        assert_eq!(format!("{:07}", index), entry.filename());
+    }
+}
+
+#[tokio::test]
+async fn duplicate_filenames() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+    use crate::write::DuplicatePolicy;
+
+    let policies = [DuplicatePolicy::Allow, DuplicatePolicy::Overwrite, DuplicatePolicy::Error];
+
+    for policy in policies {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+        zip_writer.deduplicate(policy);
+
+        for (filename, data) in [("foo.bar", "first"), ("foo.baz", "other"), ("foo.bar", "last")] {
+            let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Stored);
+            let result = zip_writer.write_entry_whole(open_opts, data.as_bytes()).await;
+
+            match result {
+                Err(ZipError::DuplicateFilename(filename)) => {
+                    assert_eq!(DuplicatePolicy::Error, policy);
+                    assert_eq!("foo.bar", filename);
+                }
+                result => {
+                    result.expect("failed to write entry");
+                }
+            }
+        }
+
+        zip_writer.close().await.expect("failed to close writer");
+
+        let archive = input_stream.into_inner();
+        let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+        let filenames: Vec<String> = zip_reader.entries().iter().map(|entry| entry.filename().to_owned()).collect();
+
+        let (expected_filenames, expected_data): (&[&str], _) = match policy {
+            DuplicatePolicy::Allow => (&["foo.bar", "foo.baz", "foo.bar"], "first"),
+            DuplicatePolicy::Overwrite => (&["foo.baz", "foo.bar"], "other"),
+            DuplicatePolicy::Error => (&["foo.bar", "foo.baz"], "first"),
+        };
+        assert_eq!(filenames, expected_filenames);
+
+        let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+        assert_eq!(expected_data, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[tokio::test]
+async fn duplicate_filenames_failed_write() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+    use crate::write::DuplicatePolicy;
+
+    for policy in [DuplicatePolicy::Overwrite, DuplicatePolicy::Error] {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+        zip_writer.deduplicate(policy);
+
+        // A write which is rejected neither removes the earlier entry's record, nor records its own filename.
+        let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, b"first").await.expect("failed to write entry");
+
+        let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Unknown(99));
+        let result = zip_writer.write_entry_whole(open_opts, b"rejected").await;
+        assert!(matches!(result, Err(ZipError::UnsupportedCompressionError(99))));
+
+        let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Unknown(99));
+        let result = zip_writer.write_entry_stream(open_opts).await.map(|_| ());
+        assert!(matches!(result, Err(ZipError::UnsupportedCompressionError(99))));
+
+        let open_opts = ZipEntryBuilder::new(String::from("foo.baz"), Compression::Unknown(99));
+        assert!(zip_writer.write_entry_whole(open_opts, b"rejected").await.is_err());
+
+        let open_opts = ZipEntryBuilder::new(String::from("foo.baz"), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, b"retried").await.expect("failed to write entry");
+        zip_writer.close().await.expect("failed to close writer");
+
+        let archive = input_stream.into_inner();
+        let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+        let filenames: Vec<&str> = zip_reader.entries().iter().map(|entry| entry.filename()).collect();
+        assert_eq!(filenames, ["foo.bar", "foo.baz"]);
+
+        let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+        assert_eq!("first", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[test]
+fn sanitize_entry_paths() {
+    use crate::sanitize::{sanitize_entry_path, sanitize_entry_path_lossy, SanitizeError};
+    use std::path::PathBuf;
+
+    assert_eq!(Ok(PathBuf::from("foo/bar.txt")), sanitize_entry_path("foo/./bar.txt"));
+    assert_eq!(Ok(PathBuf::from("foo/bar/")), sanitize_entry_path("foo\\bar\\"));
+
+    assert_eq!(Err(SanitizeError::ParentTraversal), sanitize_entry_path("../../etc/passwd"));
+    assert_eq!(Err(SanitizeError::ParentTraversal), sanitize_entry_path("foo/..\\bar"));
+    assert_eq!(Err(SanitizeError::Absolute), sanitize_entry_path("C:\\windows\\evil"));
+    assert_eq!(Err(SanitizeError::Absolute), sanitize_entry_path("/etc/passwd"));
+    assert_eq!(Err(SanitizeError::NulByte), sanitize_entry_path("foo\0.txt"));
+    assert_eq!(Err(SanitizeError::ReservedName(String::from("com1.txt"))), sanitize_entry_path("foo/com1.txt"));
+    assert_eq!(Err(SanitizeError::ComponentTooLong), sanitize_entry_path(&"a".repeat(256)));
+    assert_eq!(Err(SanitizeError::Empty), sanitize_entry_path("./"));
+
+    assert_eq!(PathBuf::from("etc/passwd"), sanitize_entry_path_lossy("../../etc/passwd"));
+    assert_eq!(PathBuf::from("foo/bar"), sanitize_entry_path_lossy("foo/..\\bar"));
+    assert_eq!(PathBuf::from("windows/evil"), sanitize_entry_path_lossy("C:\\windows\\evil"));
+    assert_eq!(PathBuf::from("_NUL/foo_.txt"), sanitize_entry_path_lossy("NUL/foo\0.txt"));
+    // Two-byte characters are truncated on the nearest character boundary below the limit.
+    assert_eq!(254, sanitize_entry_path_lossy(&"é".repeat(200)).as_os_str().len());
+
+    let entry = ZipEntryBuilder::new(String::from("../evil.txt"), Compression::Stored).build();
+    assert_eq!(None, entry.sanitized_path());
+}
+
+#[tokio::test]
+async fn entry_stream_write_vectored() {
+    use crate::read::mem::ZipFileReader;
+    use std::io::IoSlice;
+    use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    #[allow(unused_mut)]
+    let mut compressions = vec![Compression::Stored];
+    #[cfg(feature = "deflate")]
+    compressions.push(Compression::Deflate);
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for (index, compression) in compressions.iter().enumerate() {
+        let open_opts = ZipEntryBuilder::new(format!("foo{}.bar", index), *compression);
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+
+        if *compression != Compression::Stored {
+            assert!(entry_writer.is_write_vectored());
+        }
+
+        // Vectored writes may be short, so resubmit whatever remains in small slices until all data is written.
+        let mut written = 0;
+        while written < data.len() {
+            let slices: Vec<IoSlice<'_>> = data.as_bytes()[written..].chunks(10).map(IoSlice::new).collect();
+            written += entry_writer.write_vectored(&slices).await.expect("failed to write entry");
+            entry_writer.flush().await.expect("failed to flush entry");
+        }
+
+        assert_eq!(data.len() as u64, entry_writer.bytes_written());
+        entry_writer.close().await.expect("failed to close entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    for index in 0..compressions.len() {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        assert_eq!(data, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[tokio::test]
+async fn split_archive_reading() {
+    use crate::error::ZipError;
+    use crate::read::split::ZipFileReader;
+
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for filename in ["foo.bar", "foo.baz"] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    let mut archive = input_stream.into_inner();
+
+    // Split the archive so that the first entry spans all three parts, then make each offset relative to its disk.
+    let part_starts = [0, 40, 120];
+    let disk_of = |offset: u32| part_starts.iter().rposition(|start| *start <= offset).unwrap();
+
+    let read_u16 = |archive: &[u8], at: usize| u16::from_le_bytes(archive[at..at + 2].try_into().unwrap());
+    let read_u32 = |archive: &[u8], at: usize| u32::from_le_bytes(archive[at..at + 4].try_into().unwrap());
+
+    let eocdh_offset = archive.len() - 22;
+    let cd_offset = read_u32(&archive, eocdh_offset + 16);
+    let mut record_offset = cd_offset as usize;
+
+    for _ in 0..2 {
+        let lh_offset = read_u32(&archive, record_offset + 42);
+        let disk = disk_of(lh_offset);
+
+        archive[record_offset + 34..record_offset + 36].copy_from_slice(&(disk as u16).to_le_bytes());
+        archive[record_offset + 42..record_offset + 46].copy_from_slice(&(lh_offset - part_starts[disk]).to_le_bytes());
+
+        let lengths = [28, 30, 32].map(|at| usize::from(read_u16(&archive, record_offset + at)));
+        record_offset += 46 + lengths.iter().sum::<usize>();
+    }
+
+    let cd_disk = disk_of(cd_offset);
+    archive[eocdh_offset + 4..eocdh_offset + 6].copy_from_slice(&2u16.to_le_bytes());
+    archive[eocdh_offset + 6..eocdh_offset + 8].copy_from_slice(&(cd_disk as u16).to_le_bytes());
+    archive[eocdh_offset + 16..eocdh_offset + 20].copy_from_slice(&(cd_offset - part_starts[cd_disk]).to_le_bytes());
+
+    let parts = || {
+        let ends = [part_starts[1] as usize, part_starts[2] as usize, archive.len()];
+        let starts = part_starts.map(|start| start as usize);
+        starts.iter().zip(ends).map(|(start, end)| Cursor::new(archive[*start..end].to_vec())).collect::<Vec<_>>()
+    };
+
+    let mut zip_reader = ZipFileReader::from_parts(parts()).await.expect("failed to open reader");
+    assert_eq!(2, zip_reader.entries().len());
+
+    for index in 0..2 {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        assert_eq!(data, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+
+    // Without the first part, the final part (on which the central directory begins) is taken to be the second disk.
+    let result = ZipFileReader::from_parts(parts().into_iter().skip(1).collect()).await;
+    assert!(matches!(result, Err(ZipError::MissingDisk(2))));
+}
+
+#[tokio::test]
+async fn split_archive_reading_zip64() {
+    use crate::read::split::ZipFileReader;
+    use crate::spec::header::EndOfCentralDirectoryHeader;
+    use crate::spec::header::{Zip64EndOfCentralDirectoryLocator, Zip64EndOfCentralDirectoryRecord};
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for filename in ["foo.bar", "foo.baz"] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, filename.as_bytes()).await.expect("failed to write entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    let mut first = input_stream.into_inner();
+
+    // The first part holds the entries and central directory, and the second holds only the ZIP64 end of central
+    // directory record, its locator, and an EOCDH whose values all defer to the record (including its disk numbers).
+    let eocdh_offset = first.len() - 22;
+    let cent_dir_offset = u32::from_le_bytes(first[eocdh_offset + 16..eocdh_offset + 20].try_into().unwrap());
+    first.truncate(eocdh_offset);
+
+    let record = Zip64EndOfCentralDirectoryRecord {
+        v_made_by: 45,
+        v_needed: 45,
+        disk_num: 1,
+        start_cent_dir_disk: 0,
+        num_of_entries_disk: 0,
+        num_of_entries: 2,
+        size_cent_dir: (eocdh_offset as u32 - cent_dir_offset).into(),
+        cent_dir_offset: cent_dir_offset.into(),
+    };
+    let locator = Zip64EndOfCentralDirectoryLocator { start_eocdr_disk: 1, eocdr_offset: 0, total_disks: 2 };
+    let header = EndOfCentralDirectoryHeader {
+        disk_num: u16::MAX,
+        start_cent_dir_disk: u16::MAX,
+        num_of_entries_disk: u16::MAX,
+        num_of_entries: u16::MAX,
+        size_cent_dir: u32::MAX,
+        cent_dir_offset: u32::MAX,
+        file_comm_length: 0,
+    };
+
+    let mut second = record.to_bytes();
+    second.extend_from_slice(&locator.to_bytes());
+    second.extend_from_slice(&header.to_bytes());
+
+    let zip_reader = ZipFileReader::from_parts(vec![Cursor::new(first), Cursor::new(second)]).await;
+    let mut zip_reader = zip_reader.expect("failed to open reader");
+    assert_eq!((1, 0), (zip_reader.metadata().disk_number(), zip_reader.metadata().central_directory_disk()));
+    assert_eq!(2, zip_reader.entries().len());
+
+    for (index, filename) in ["foo.bar", "foo.baz"].into_iter().enumerate() {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        assert_eq!(filename, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[tokio::test]
+async fn split_archive_writing() {
+    use crate::write::split::{SplitZipFileWriter, MIN_PART_SIZE};
+
+    let mut state = 0x2545F491u32;
+    let data: Vec<u8> = (0..150_000)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+
+    for spanning in [true, false] {
+        let dir = tempfile::tempdir().expect("failed to create directory");
+        let factory_dir = dir.path().to_path_buf();
+        let factory = move |disk: u32| tokio::fs::File::create(factory_dir.join(format!("part{}", disk)));
+        let mut zip_writer = SplitZipFileWriter::new(factory, MIN_PART_SIZE).await.expect("failed to open writer");
+        zip_writer.spanning(spanning);
+
+        // Entries smaller than a part only ever span parts when spanning is allowed.
+        let sizes = if spanning { [150_000, 1_000, 150_000] } else { [40_000, 40_000, 40_000] };
+
+        for (index, size) in sizes.iter().enumerate() {
+            let open_opts = ZipEntryBuilder::new(format!("foo{}.bar", index), Compression::Stored);
+            let written = zip_writer.write_entry_whole(open_opts, &data[..*size]).await.expect("failed to write entry");
+
+            if !spanning {
+                assert!(written.header_offset() + 30 + 8 + *size as u64 <= MIN_PART_SIZE);
+            }
+        }
+
+        let part_count = zip_writer.close().await.expect("failed to close writer");
+        let paths: Vec<_> = (0..part_count).map(|disk| dir.path().join(format!("part{}", disk))).collect();
+
+        for path in &paths {
+            assert!(tokio::fs::metadata(path).await.expect("missing part").len() <= MIN_PART_SIZE);
+        }
+
+        let mut zip_reader =
+            crate::read::split::ZipFileReader::from_paths(&paths).await.expect("failed to open reader");
+        assert_eq!(3, zip_reader.entries().len());
+
+        for (index, size) in sizes.iter().enumerate() {
+            let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+            assert_eq!(&data[..*size], entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+        }
+    }
+}
+
+#[tokio::test]
+async fn split_archive_writing_zip64() {
+    use crate::spec::signature::ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR;
+    use crate::write::split::SplitZipFileWriter;
+
+    // The end of central directory header holds at most 65534 entries, as 65535 is reserved to defer to ZIP64.
+    for count in [u16::MAX - 1, u16::MAX] {
+        let dir = tempfile::tempdir().expect("failed to create directory");
+        let factory_dir = dir.path().to_path_buf();
+        let factory = move |disk: u32| tokio::fs::File::create(factory_dir.join(format!("part{}", disk)));
+        let mut zip_writer = SplitZipFileWriter::new(factory, 1024 * 1024).await.expect("failed to open writer");
+
+        for index in 0..count {
+            let open_opts = ZipEntryBuilder::new(format!("{:05}", index), Compression::Stored);
+            zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
+        }
+
+        let part_count = zip_writer.close().await.expect("failed to close writer");
+        let paths: Vec<_> = (0..part_count).map(|disk| dir.path().join(format!("part{}", disk))).collect();
+
+        let last = std::fs::read(paths.last().unwrap()).expect("failed to read part");
+        let locator = &last[last.len() - 42..last.len() - 38];
+        assert_eq!(count == u16::MAX, locator == ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR.to_le_bytes());
+
+        let zip_reader = crate::read::split::ZipFileReader::from_paths(&paths).await.expect("failed to open reader");
+        assert_eq!(usize::from(count), zip_reader.entries().len());
+        assert_eq!(format!("{:05}", count - 1), zip_reader.entries()[usize::from(count) - 1].filename());
+    }
+}
+
+#[tokio::test]
+async fn unknown_compression_and_flags() {
+    use crate::error::{Feature, ZipError};
+    use crate::read::mem::ZipFileReader;
+    use crate::read::ReaderOptions;
+
+    let archive = raw_archive("foo.bar", 99, compute_crc(b"foo"), b"foo", 3);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    // Entries using an unknown method can still be listed, but not read.
+    assert_eq!(Compression::Unknown(99), zip_reader.entries()[0].compression());
+    assert_eq!(99, zip_reader.entries()[0].compression_raw());
+    assert!(matches!(zip_reader.entry_reader(0).await, Err(ZipError::UnsupportedCompressionError(99))));
+
+    let options = ReaderOptions::new().check_capabilities(true);
+    let result = ZipFileReader::with_options(&archive, options).await;
+    assert!(matches!(result, Err(ZipError::UnsupportedFeature(Feature::Compression(99)))));
+
+    let cd_offset = archive.len() - 22 - (46 + 7);
+    let mut flagged = archive.clone();
+    flagged[cd_offset + 8..cd_offset + 10].copy_from_slice(&(1u16 << 11).to_le_bytes());
+
+    let zip_reader = ZipFileReader::new(&flagged).await.expect("failed to open reader");
+    let flags = zip_reader.entries()[0].general_purpose_flags();
+    assert!(flags.filename_unicode());
+    assert!(!flags.encrypted() && !flags.data_descriptor());
+
+    let mut output = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Unknown(99));
+    let result = zip_writer.write_entry_whole(open_opts, b"foo").await;
+    assert!(matches!(result, Err(ZipError::UnsupportedCompressionError(99))));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn reader_buffer_sizes() {
+    use crate::read::ReaderOptions;
+    use tokio::io::AsyncWriteExt;
+
+    let data: Vec<u8> = (0..300_000u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8).collect();
+    let entries = [
+        (Compression::Stored, 10, false),
+        (Compression::Deflate, 300_000, true),
+        (Compression::Stored, 300_000, false),
+        (Compression::Deflate, 70_000, true),
+        (Compression::Deflate, 70_000, false),
+    ];
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for (index, (compression, size, stream)) in entries.iter().enumerate() {
+        let open_opts = ZipEntryBuilder::new(format!("foo{}.bar", index), *compression);
+
+        if *stream {
+            let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+            entry_writer.write_all(&data[..*size]).await.expect("failed to write entry");
+            entry_writer.close().await.expect("failed to close entry");
+        } else {
+            zip_writer.write_entry_whole(open_opts, &data[..*size]).await.expect("failed to write entry");
+        }
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+
+    // The stream reader prepends whatever remains buffered after each entry, so entries must follow on correctly
+    // regardless of how far past an entry's end the buffer was filled.
+    for buffer_size in [1, 16, 8 * 1024, 64 * 1024, 256 * 1024] {
+        let options = ReaderOptions::new().buffer_size(buffer_size);
+        let mut zip_reader = crate::read::stream::ZipFileReader::with_options(Cursor::new(&archive), options.clone());
+
+        for (_, size, _) in entries.iter() {
+            let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+            assert_eq!(&data[..*size], entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+        }
+        assert!(zip_reader.entry_reader().await.expect("failed to read central directory").is_none());
+
+        let zip_reader = crate::read::seek::ZipFileReader::with_options(Cursor::new(&archive), options).await;
+        let mut zip_reader = zip_reader.expect("failed to open reader");
+
+        for (index, (_, size, _)) in entries.iter().enumerate() {
+            let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+            assert_eq!(&data[..*size], entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+        }
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn reader_buffer_pool() {
+    use crate::read::pool::BufferPool;
+    use crate::read::ReaderOptions;
+
+    let data: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8).collect();
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for (index, compression) in [Compression::Stored, Compression::Deflate, Compression::Stored].iter().enumerate() {
+        let open_opts = ZipEntryBuilder::new(format!("foo{}.bar", index), *compression);
+        zip_writer.write_entry_whole(open_opts, &data).await.expect("failed to write entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+
+    let pool = BufferPool::new(4096, 1);
+    assert_eq!((pool.buffer_size(), pool.capacity(), pool.available()), (4096, 1, 0));
+
+    let options = ReaderOptions::new().buffer_size(64 * 1024).buffer_pool(pool.clone());
+    let mut zip_reader = crate::read::mem::ZipFileReader::with_options(&archive, options.clone()).await.unwrap();
+
+    // Each entry reader returns its buffer when dropped, so the single buffer is reused for every entry.
+    for index in 0..3 {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        assert_eq!(pool.available(), 0);
+        assert_eq!(data, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+        assert_eq!(pool.available(), 1);
+    }
+
+    // The stream reader prepends whatever remains within a pooled buffer just as it does an allocated one.
+    let mut zip_reader = crate::read::stream::ZipFileReader::with_options(Cursor::new(&archive), options);
+
+    for _ in 0..3 {
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+        assert_eq!(data, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    }
+    assert!(zip_reader.entry_reader().await.expect("failed to read central directory").is_none());
+    assert_eq!(pool.available(), 1);
+
+    // Buffers returned beyond the pool's capacity are freed rather than retained.
+    let (first, second) = (pool.checkout(), pool.checkout());
+    assert_eq!((first.len(), pool.available()), (4096, 0));
+    drop((first, second));
+    assert_eq!(pool.available(), 1);
+}
+
+#[tokio::test]
+async fn entry_manifest() {
+    use crate::read::mem::ZipFileReader;
+
+    async fn build(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut output = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut output);
+
+        for (filename, data) in entries {
+            let open_opts = ZipEntryBuilder::new(filename.to_string(), Compression::Stored)
+                .last_modification_date(crate::spec::date::zip_date_to_chrono(0x21, 0));
+            zip_writer.write_entry_whole(open_opts, data).await.expect("failed to write entry");
+        }
+
+        zip_writer.close().await.expect("failed to close writer");
+        output.into_inner()
+    }
+
+    let before = build(&[("foo.bar", &b"foo"[..]), ("bar.foo", &b"bar"[..])]).await;
+    let after = build(&[("foo.bar", &b"foo"[..]), ("bar.foo", &b"baz"[..])]).await;
+
+    let before = ZipFileReader::new(&before).await.expect("failed to open reader").manifest();
+    let after = ZipFileReader::new(&after).await.expect("failed to open reader").manifest();
+
+    assert_eq!(2, before.len());
+    assert_eq!("foo.bar", before[0].filename());
+    assert_eq!(Compression::Stored, before[0].compression());
+    assert_eq!(3, before[0].uncompressed_size());
+
+    assert_eq!(before[0], after[0]);
+    assert_ne!(before[1], after[1]);
+    assert!(before[1].data_differs(&after[1]));
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_string(&before).expect("failed to serialize manifest");
+        let restored: Vec<crate::EntrySummary> = serde_json::from_str(&json).expect("failed to deserialize manifest");
+        assert_eq!(before, restored);
+    }
+}
+
+/// Builds a stream of entries which each end in a data descriptor of the provided layout, followed by a truncated
+/// central directory (which the stream reader never reads beyond the signature of).
+#[cfg(feature = "deflate")]
+fn descriptor_stream(entries: &[&[u8]], signed: bool, zip64: bool, zip64_extra_field: bool) -> Vec<u8> {
+    use crate::spec::header::{GeneralPurposeFlag, LocalFileHeader};
+    use crate::spec::signature;
+
+    let mut archive = Vec::new();
+
+    for (index, data) in entries.iter().enumerate() {
+        let filename = format!("foo{}.bar", index);
+        let mut extra_field = Vec::new();
+
+        if zip64_extra_field {
+            crate::spec::extra_field::ExtraField::new(0x0001, vec![0; 16]).write_to(&mut extra_field);
+        }
+
+        let sizes = if zip64_extra_field { u32::MAX } else { 0 };
+        let lfh = LocalFileHeader {
+            version: if zip64 { 45 } else { 20 },
+            flags: GeneralPurposeFlag::from(0x8),
+            compression: 8,
+            mod_time: 0,
+            mod_date: 0x21,
+            crc: 0,
+            compressed_size: sizes,
+            uncompressed_size: sizes,
+            file_name_length: filename.len() as u16,
+            extra_field_length: extra_field.len() as u16,
+        };
+
+        // A Deflate stream consisting of a single final stored block.
+        let mut compressed = vec![0x01];
+        compressed.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        compressed.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+        compressed.extend_from_slice(data);
+
+        archive.extend_from_slice(&signature::LOCAL_FILE_HEADER.to_le_bytes());
+        archive.extend_from_slice(&lfh.as_slice());
+        archive.extend_from_slice(filename.as_bytes());
+        archive.extend_from_slice(&extra_field);
+        archive.extend_from_slice(&compressed);
+
+        if signed {
+            archive.extend_from_slice(&signature::DATA_DESCRIPTOR.to_le_bytes());
+        }
+
+        archive.extend_from_slice(&compute_crc(data).to_le_bytes());
+
+        if zip64 {
+            archive.extend_from_slice(&(compressed.len() as u64).to_le_bytes());
+            archive.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        } else {
+            archive.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+            archive.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        }
+    }
+
+    archive.extend_from_slice(&signature::CENTRAL_DIRECTORY_FILE_HEADER.to_le_bytes());
+    archive.extend_from_slice(&[0; 42]);
+    archive
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn data_descriptor_layouts() {
+    use crate::read::stream::ZipFileReader;
+
+    let entries: [&[u8]; 3] = [b"Lorem ipsum dolor sit amet", b"consectetur adipiscing elit", b""];
+
+    // ZIP64 sizes should be detected both from the extra field and from the bytes following the descriptor.
+    let layouts = [
+        (true, false, false),
+        (false, false, false),
+        (true, true, true),
+        (false, true, true),
+        (true, true, false),
+        (false, true, false),
+    ];
+
+    for (signed, zip64, zip64_extra_field) in layouts {
+        let archive = descriptor_stream(&entries, signed, zip64, zip64_extra_field);
+        let mut zip_reader = ZipFileReader::new(Cursor::new(&archive));
+
+        for data in entries.iter() {
+            let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+            let buffer = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+            assert_eq!(*data, &buffer[..], "layout: {:?}", (signed, zip64, zip64_extra_field));
+        }
+
+        assert!(zip_reader.entry_reader().await.expect("failed to read central directory").is_none());
+    }
+}
+
+#[test]
+fn data_descriptor_parsing() {
+    use crate::read::parse_data_descriptor;
+    use crate::spec::signature;
+
+    let mut descriptor = Vec::new();
+    descriptor.extend_from_slice(&signature::DATA_DESCRIPTOR.to_le_bytes());
+    descriptor.extend_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+
+    // Without any following signature, a signed descriptor with 32-bit sizes is assumed.
+    assert_eq!(Some(((1, 2, 3), 16)), parse_data_descriptor(&descriptor, false));
+    assert_eq!(None, parse_data_descriptor(&descriptor[..15], false));
+
+    // A CRC32 value may coincidentally match the signature, which is disambiguated by the following record.
+    let mut unsigned = signature::DATA_DESCRIPTOR.to_le_bytes().to_vec();
+    unsigned.extend_from_slice(&[2, 0, 0, 0, 3, 0, 0, 0]);
+    unsigned.extend_from_slice(&signature::LOCAL_FILE_HEADER.to_le_bytes());
+    assert_eq!(Some(((signature::DATA_DESCRIPTOR, 2, 3), 12)), parse_data_descriptor(&unsigned, false));
+}
+
+/// A writer which accepts a limited number of bytes, and then never completes another write.
+struct StallingWriter {
+    written: Vec<u8>,
+    limit: usize,
+}
+
+impl tokio::io::AsyncWrite for StallingWriter {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        let remaining = self.limit - self.written.len();
+        if remaining == 0 {
+            return std::task::Poll::Pending;
+        }
+
+        let length = std::cmp::min(remaining, buf.len());
+        self.written.extend_from_slice(&buf[..length]);
+        std::task::Poll::Ready(Ok(length))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn writer_poisoning() {
+    use crate::error::ZipError;
+    use crate::write::DuplicatePolicy;
+
+    let mut output = StallingWriter { written: Vec::new(), limit: 10 };
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+
+    // Cancel a write once it has stalled part-way through the local file header.
+    tokio::select! {
+        biased;
+        _ = zip_writer.write_entry_whole(ZipEntryBuilder::new("foo.bar".to_string(), Compression::Stored), b"foo") => {
+            panic!("write should have stalled");
+        }
+        _ = std::future::ready(()) => {}
+    }
+
+    assert!(zip_writer.is_poisoned());
+    let open_opts = ZipEntryBuilder::new("bar.foo".to_string(), Compression::Stored);
+    assert!(matches!(zip_writer.write_entry_whole(open_opts, b"bar").await, Err(ZipError::WriterPoisoned)));
+    assert!(matches!(zip_writer.close().await, Err(ZipError::WriterPoisoned)));
+
+    // Dropping a stream writer without closing it poisons the writer.
+    let mut output = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+    let open_opts = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Stored);
+    drop(zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry"));
+
+    assert!(zip_writer.is_poisoned());
+    assert!(matches!(zip_writer.close().await, Err(ZipError::WriterPoisoned)));
+
+    // Writes rejected before anything is written don't poison the writer.
+    let mut output = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+    zip_writer.deduplicate(DuplicatePolicy::Error);
+
+    let open_opts = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    let open_opts = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Stored);
+    assert!(zip_writer.write_entry_whole(open_opts, b"foo").await.is_err());
+
+    assert!(!zip_writer.is_poisoned());
+    zip_writer.close().await.expect("failed to close writer");
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn entry_reader_consumption() {
+    use crate::error::ZipError;
+    use crate::read::stream::ZipFileReader;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let data = b"Lorem ipsum dolor sit amet";
+
+    let open_opts = ZipEntryBuilder::new("empty.bar".to_string(), Compression::Deflate);
+    let entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+    entry_writer.close().await.expect("failed to close entry");
+
+    let open_opts = ZipEntryBuilder::new("foo/".to_string(), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
+
+    let open_opts = ZipEntryBuilder::new("foo.bar".to_string(), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+    entry_writer.write_all(data).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+
+    zip_writer.close().await.expect("failed to close writer");
+    input_stream.set_position(0);
+
+    let mut zip_reader = ZipFileReader::new(&mut input_stream);
+    let mut buffer = [0; 16];
+
+    // The expected CRC32 of a streamed entry is unknown until its data descriptor has been read.
+    let mut entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+    assert!(!entry_reader.consumed());
+    assert!(!entry_reader.compare_crc());
+    assert!(matches!(entry_reader.verify_crc(), Err(ZipError::DataDescriptorNotRead)));
+
+    for _ in 0..3 {
+        assert_eq!(entry_reader.read(&mut buffer).await.expect("failed to read entry"), 0);
+    }
+
+    assert!(entry_reader.consumed());
+    entry_reader.verify_crc().expect("failed to verify entry");
+
+    // An entry without data or a data descriptor has nothing left to consume.
+    let mut entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+    assert!(entry_reader.consumed());
+
+    for _ in 0..3 {
+        assert_eq!(entry_reader.read(&mut buffer).await.expect("failed to read entry"), 0);
+    }
+
+    assert!(entry_reader.compare_crc());
+
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+    let read = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+    assert_eq!(&data[..], &read[..]);
+
+    assert!(zip_reader.entry_reader().await.expect("failed to read central directory").is_none());
+}
+
+#[tokio::test]
+async fn reader_entry_filter() {
+    use crate::read::mem::ZipFileReader;
+    use crate::read::{RawEntryHeader, ReaderOptions};
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let entries: [(&str, &[u8]); 3] = [("foo.jpg", b"foo"), ("large.bin", &[0; 64]), ("bar/baz.jpg", b"baz")];
+
+    for (filename, data) in entries {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, data).await.expect("failed to write entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    let data = input_stream.into_inner();
+
+    // The filtered out entry would otherwise exceed the configured limit.
+    let options = ReaderOptions::new().max_decompressed_size(3);
+    assert!(ZipFileReader::with_options(&data, options.clone()).await.is_err());
+
+    let options = options.entry_filter(|header| header.filename().ends_with(b".jpg"));
+    let mut zip_reader = ZipFileReader::with_options(&data, options).await.expect("failed to open reader");
+
+    let filenames: Vec<&str> = zip_reader.entries().iter().map(|entry| entry.filename()).collect();
+    assert_eq!(vec!["foo.jpg", "bar/baz.jpg"], filenames);
+    assert_eq!(3, zip_reader.total_entries());
+    assert!(zip_reader.entry("large.bin").is_none());
+
+    let entry_reader = zip_reader.entry_reader(1).await.expect("failed to open entry reader");
+    assert_eq!(b"baz", &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+
+    let filter = |header: &RawEntryHeader| header.compression() == Compression::Stored && header.compressed_size() > 3;
+    let zip_reader = ZipFileReader::with_options(&data, ReaderOptions::new().entry_filter(filter)).await;
+    let zip_reader = zip_reader.expect("failed to open reader");
+    assert_eq!(Some("large.bin"), zip_reader.entries().first().map(|entry| entry.filename()));
+}
+
+#[tokio::test]
+async fn stream_stored_patched_headers() {
+    use crate::read::mem;
+    use crate::read::stream;
+    use crate::spec::header::LocalFileHeader;
+    use tokio::io::AsyncWriteExt;
+
+    let data = b"\x89PNG\r\n\x1a\n already compressed data";
+
+    for patch in [false, true] {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+        zip_writer.patch_local_headers(patch);
+
+        for filename in ["foo.png", "bar.png"] {
+            let open_opts = ZipEntryBuilder::new(filename.to_string(), Compression::Stored);
+            let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+            entry_writer.write_all(data).await.expect("failed to write entry");
+
+            let written = entry_writer.close().await.expect("failed to close entry");
+            assert_eq!(written.compressed_size(), written.uncompressed_size());
+            assert_eq!(data.len() as u64, written.uncompressed_size());
+            assert_eq!(compute_crc(data), written.crc32());
+        }
+
+        zip_writer.close().await.expect("failed to close writer");
+        let archive = input_stream.into_inner();
+
+        let (lfh, _) = LocalFileHeader::from_bytes(&archive).expect("failed to parse local file header");
+        assert_eq!(!patch, lfh.flags.data_descriptor);
+
+        if patch {
+            assert_eq!(compute_crc(data), lfh.crc);
+            assert_eq!(data.len() as u32, lfh.compressed_size);
+            assert_eq!(data.len() as u32, lfh.uncompressed_size);
+        }
+
+        let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+        for index in 0..2 {
+            let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+            assert_eq!(&data[..], &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+        }
+
+        let report = zip_reader.verify(false).await.expect("failed to verify");
+        assert!(report.failures().is_empty());
+
+        // Stored entries can only be streamed when their compressed size is present in the local file header.
+        if patch {
+            let mut zip_reader = stream::ZipFileReader::new(Cursor::new(&archive));
+            for _ in 0..2 {
+                let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+                assert_eq!(&data[..], &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn streaming_consumer_conformance() {
+    use crate::read::stream::ZipFileReader;
+    use crate::spec::signature;
+    use crate::spec::version::ZipVersion;
+    use crate::write::DataDescriptorStyle;
+    use tokio::io::AsyncWriteExt;
+
+    let data = b"abc";
+
+    for style in [DataDescriptorStyle::Signed, DataDescriptorStyle::Unsigned] {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+        zip_writer.reproducible(crate::spec::date::zip_date_to_chrono(0x21, 0));
+        zip_writer.data_descriptor_style(style);
+
+        let open_opts = ZipEntryBuilder::new(String::from("a.txt"), Compression::Deflate);
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+        entry_writer.write_all(data).await.expect("failed to write entry");
+        let written = entry_writer.close().await.expect("failed to close entry");
+        zip_writer.close().await.expect("failed to close writer");
+        let archive = input_stream.into_inner();
+
+        // Version 2.0 (for both Deflate and the data descriptor), flag bit 3, Deflate, the epoch, and zeroed sizes.
+        let mut expected = vec![0x50, 0x4B, 0x03, 0x04, 20, 0, 0x08, 0, 8, 0, 0, 0, 0x21, 0];
+        expected.extend_from_slice(&[0; 12]);
+        expected.extend_from_slice(&[5, 0, 0, 0]);
+        expected.extend_from_slice(b"a.txt");
+        assert_eq!(&expected[..], &archive[..expected.len()]);
+
+        let mut descriptor = match style {
+            DataDescriptorStyle::Signed => signature::DATA_DESCRIPTOR.to_le_bytes().to_vec(),
+            DataDescriptorStyle::Unsigned => Vec::new(),
+        };
+        descriptor.extend_from_slice(&compute_crc(data).to_le_bytes());
+        descriptor.extend_from_slice(&(written.compressed_size() as u32).to_le_bytes());
+        descriptor.extend_from_slice(&(data.len() as u32).to_le_bytes());
+
+        let data_end = expected.len() + written.compressed_size() as usize;
+        let cd_offset = data_end + descriptor.len();
+        assert_eq!(&descriptor[..], &archive[data_end..cd_offset]);
+
+        // The central directory record must agree with the local file header on the version needed to extract.
+        assert_eq!(signature::CENTRAL_DIRECTORY_FILE_HEADER.to_le_bytes(), archive[cd_offset..cd_offset + 4]);
+        assert_eq!(archive[4..6], archive[cd_offset + 6..cd_offset + 8]);
+
+        let mut zip_reader = ZipFileReader::new(Cursor::new(&archive));
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+        assert_eq!(Some(ZipVersion::new(2, 0)), entry_reader.entry().version_needed());
+        assert_eq!(&data[..], &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn seekable_writer_matches_zip_crate() {
+    use crate::spec::header::LocalFileHeader;
+    use std::io::{Read, Write};
+    use tokio::io::AsyncWriteExt;
+
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new_seekable(&mut input_stream);
+
+    for compression in [Compression::Stored, Compression::Deflate] {
+        let open_opts = ZipEntryBuilder::new(format!("foo{}.bar", u16::from(compression)), compression);
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+        entry_writer.write_all(data).await.expect("failed to write entry");
+        entry_writer.close().await.expect("failed to close entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+
+    // The zip crate patches the local file headers of entries written to a seekable writer in the same way.
+    let mut fixture = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    fixture.start_file("foo0.bar", options).expect("failed to start fixture entry");
+    fixture.write_all(data).expect("failed to write fixture entry");
+    let fixture = fixture.finish().expect("failed to finish fixture").into_inner();
+
+    let (lfh, _) = LocalFileHeader::from_bytes(&archive).expect("failed to parse local file header");
+    let (fixture_lfh, _) = LocalFileHeader::from_bytes(&fixture).expect("failed to parse fixture local file header");
+    assert!(!lfh.flags.data_descriptor);
+    assert_eq!(fixture_lfh.flags.data_descriptor, lfh.flags.data_descriptor);
+    assert_eq!((fixture_lfh.crc, fixture_lfh.compressed_size), (lfh.crc, lfh.compressed_size));
+    assert_eq!(fixture_lfh.uncompressed_size, lfh.uncompressed_size);
+
+    // The data of each entry immediately follows it, with no data descriptor in between.
+    let second = LocalFileHeader::LENGTH + "foo0.bar".len() + data.len();
+    assert_eq!(&crate::spec::signature::LOCAL_FILE_HEADER.to_le_bytes()[..], &archive[second..second + 4]);
+
+    let mut zip_archive = zip::ZipArchive::new(std::io::Cursor::new(archive)).expect("failed to open archive");
+    for index in 0..2 {
+        let mut file = zip_archive.by_index(index).expect("failed to open entry");
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).expect("failed to read entry");
+        assert_eq!(&data[..], &buffer[..]);
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn write_entries_parallel() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+    use crate::write::DuplicatePolicy;
+
+    // Large enough that the entry's compressed output can't all be buffered whilst earlier entries are written.
+    let large: Vec<u8> = (0..4 * 1024 * 1024u32).map(|index| (index.wrapping_mul(2654435761) >> 24) as u8).collect();
+    let mut entries = vec![(String::from("large.bin"), Compression::Stored, large)];
+
+    for index in 0..16 {
+        let compression = if index % 2 == 0 { Compression::Deflate } else { Compression::Stored };
+        let data = format!("Lorem ipsum dolor sit amet {}", index).repeat(index * 100).into_bytes();
+        entries.push((format!("foo{}.bar", index), compression, data));
+    }
+
+    for concurrency in [1, 4] {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+        let sources = entries.iter().map(|(filename, compression, data)| {
+            (ZipEntryBuilder::new(filename.clone(), *compression), Cursor::new(data.clone()))
+        });
+
+        let written = zip_writer.write_entries_parallel(sources, concurrency).await.expect("failed to write entries");
+        zip_writer.close().await.expect("failed to close writer");
+
+        let archive = input_stream.into_inner();
+        let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+        assert_eq!(entries.len(), zip_reader.entries().len());
+
+        for (index, (filename, _, data)) in entries.iter().enumerate() {
+            assert_eq!(compute_crc(data), written[index].crc32());
+            assert_eq!(filename, zip_reader.entries()[index].filename());
+
+            let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+            assert_eq!(data, &entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+        }
+    }
+
+    // Errors are returned once the failing entry's turn comes, leaving earlier entries written.
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.deduplicate(DuplicatePolicy::Error);
+
+    let sources = ["foo.bar", "bar.foo", "foo.bar"].map(|filename| {
+        (ZipEntryBuilder::new(filename.to_string(), Compression::Deflate), Cursor::new(b"foo".to_vec()))
+    });
+    let result = zip_writer.write_entries_parallel(sources, 2).await;
+    assert!(matches!(result, Err(ZipError::DuplicateFilename(_))));
+    assert!(!zip_writer.is_poisoned());
+}
+
+#[tokio::test]
+async fn entry_validation_boundaries() {
+    use crate::entry::CompressionLevel;
+    use crate::error::ZipError;
+
+    let filename = |length: usize| "a".repeat(length);
+
+    // Each field may be exactly 65535 bytes long, but no longer.
+    let cases = [
+        (ZipEntryBuilder::new(filename(65535), Compression::Stored), true),
+        (ZipEntryBuilder::new(filename(65536), Compression::Stored), false),
+        (ZipEntryBuilder::new(filename(1), Compression::Stored).comment(filename(65535)), true),
+        (ZipEntryBuilder::new(filename(1), Compression::Stored).comment(filename(65536)), false),
+        (ZipEntryBuilder::new(filename(1), Compression::Stored).extra_field(0xCAFE, vec![0; 65531]), true),
+        (ZipEntryBuilder::new(filename(1), Compression::Stored).extra_field(0xCAFE, vec![0; 65532]), false),
+    ];
+
+    for (index, (builder, valid)) in cases.into_iter().enumerate() {
+        let entry = builder.build();
+        assert_eq!(valid, ZipEntryBuilder::from(entry.clone()).try_build().is_ok(), "case: {}", index);
+
+        let mut zip_writer = ZipFileWriter::new(Cursor::new(Vec::<u8>::new()));
+        let result = zip_writer.write_entry_whole(entry, b"foo").await;
+        assert_eq!(valid, result.is_ok(), "case: {}", index);
+
+        match (index, result) {
+            (1, Err(ZipError::FilenameTooLong(65536))) => {}
+            (3, Err(ZipError::CommentTooLong(65536))) => {}
+            (5, Err(ZipError::ExtraFieldTooLong(65536))) => {}
+            (_, result) => assert!(result.is_ok(), "case: {}", index),
+        }
+
+        assert!(!zip_writer.is_poisoned());
+    }
+
+    let result = ZipEntryBuilder::new(String::from("foo\0.bar"), Compression::Stored).try_build();
+    assert!(matches!(result, Err(ZipError::InvalidFilename(_))));
+
+    let entry = ZipEntryBuilder::new(String::from("foo\\bar\\baz.txt"), Compression::Stored);
+    assert_eq!("foo/bar/baz.txt", entry.normalize_separators().build().filename());
+    let entry = ZipEntryBuilder::new(String::from("foo\\bar.txt"), Compression::Stored).build();
+    assert_eq!("foo\\bar.txt", entry.filename());
+
+    // Everything which the builder can set can be read back.
+    let entry = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored)
+        .set_compression_level(CompressionLevel::Best)
+        .attribute_compatibility(crate::AttributeCompatibility::Unix)
+        .internal_file_attribute(1)
+        .external_file_attribute(2)
+        .build();
+
+    assert!(matches!(entry.compression_level(), CompressionLevel::Best));
+    assert_eq!(crate::AttributeCompatibility::Unix, entry.attribute_compatibility());
+    assert_eq!((1, 2), (entry.internal_file_attribute(), entry.external_file_attribute()));
+}
+
+#[tokio::test]
+async fn entry_reader_checkpoints() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+    use crate::read::ReadCheckpoint;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit".repeat(8);
+
+    for compression in [Compression::Stored, Compression::Deflate] {
+        let open_opts = ZipEntryBuilder::new(format!("{:?}.bar", compression), compression);
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open write entry");
+        entry_writer.write_all(&data).await.expect("failed to write entry");
+        entry_writer.close().await.expect("failed to close entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    for index in 0..2 {
+        let mut prefix = vec![0; 100];
+        let mut entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        entry_reader.read_exact(&mut prefix).await.expect("failed to read entry");
+
+        let checkpoint = entry_reader.checkpoint();
+        assert_eq!(compute_crc(&prefix), entry_reader.current_crc32());
+        assert_eq!(ReadCheckpoint::new(compute_crc(&prefix), 100), checkpoint);
+        drop(entry_reader);
+
+        let checkpoint = ReadCheckpoint::new(checkpoint.crc32(), checkpoint.bytes_consumed());
+        let entry_reader = zip_reader.entry_reader_resumed(index, checkpoint).await.expect("failed to resume entry");
+        let remaining = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+
+        prefix.extend_from_slice(&remaining);
+        assert_eq!(data, prefix);
+
+        let checkpoint = ReadCheckpoint::new(0, data.len() as u64 + 1);
+        let result = zip_reader.entry_reader_resumed(index, checkpoint).await;
+        assert!(matches!(result, Err(ZipError::CheckpointOutOfBounds(_))));
+    }
+}
+
+#[test]
+fn sync_reader_blocking() {
+    use crate::read::sync::ZipFileReader;
+    use std::io::Read;
+
+    let data = include_bytes!("fixtures/python_zipfile.zip");
+    let lorem = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(20);
+
+    let mut zip_reader = ZipFileReader::from_slice(data).expect("failed to open reader");
+    assert_eq!(3, zip_reader.entries().len());
+
+    let (index, entry) = zip_reader.entry("docs/lorem.txt").expect("missing entry");
+    assert_eq!(lorem.len() as u64, entry.uncompressed_size());
+
+    let mut entry_reader = zip_reader.entry_reader(index).expect("failed to open entry reader");
+    let mut read = Vec::new();
+    entry_reader.read_to_end(&mut read).expect("failed to read entry");
+    entry_reader.verify_crc().expect("failed to verify entry");
+    assert_eq!(lorem, read);
+
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let path = dir.path().join("sync.zip");
+    std::fs::write(&path, data).expect("failed to write file");
+
+    let mut zip_reader = ZipFileReader::new(&path).expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).expect("failed to open entry reader");
+    assert_eq!("Hello, world!\n", entry_reader.read_to_string_crc().expect("failed to read entry"));
+}
+
+#[tokio::test]
+async fn sync_reader_within_runtime() {
+    use crate::read::sync::ZipFileReader;
+    use std::io::Read;
+
+    let data = include_bytes!("fixtures/python_zipfile.zip");
+
+    // Blocking within a (current-thread) runtime mustn't panic, nor should dropping the reader's own runtime.
+    let mut zip_reader = ZipFileReader::from_slice(data).expect("failed to open reader");
+    let mut entry_reader = zip_reader.entry_reader(0).expect("failed to open entry reader");
+
+    let mut read = String::new();
+    entry_reader.read_to_string(&mut read).expect("failed to read entry");
+    entry_reader.verify_crc().expect("failed to verify entry");
+    assert_eq!("Hello, world!\n", read);
+
+    drop(zip_reader);
+}
+
+#[tokio::test]
+async fn entry_reader_without_crc() {
+    use crate::error::ZipError;
+    use crate::read::mem::ZipFileReader;
+
+    // The entry's recorded CRC32 value doesn't match its data.
+    let archive = raw_archive("foo.bar", 0, !compute_crc(b"foo"), b"foo", 3);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert!(matches!(entry_reader.read_to_end_crc().await, Err(ZipError::CRC32CheckError { .. })));
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader").without_crc();
+    assert_eq!(b"foo", &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+
+    let mut entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader").without_crc();
+    tokio::io::copy(&mut entry_reader, &mut tokio::io::sink()).await.expect("failed to read entry");
+    assert!(entry_reader.compare_size());
+    assert!(!entry_reader.compare_crc());
+    assert!(matches!(entry_reader.verify_crc(), Err(ZipError::CRC32Disabled)));
+
+    // The uncompressed size is still checked.
+    let archive = raw_archive("foo.bar", 0, compute_crc(b"foo"), b"foo", 4);
+    let mut zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader").without_crc();
+    assert!(matches!(entry_reader.read_to_end_crc().await, Err(ZipError::SizeMismatch { .. })));
+}
+
+#[tokio::test]
+async fn entry_reader_range() {
+    use crate::error::ZipError;
+    use crate::read::{mem, seek};
+    use tokio::io::AsyncReadExt;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit".repeat(8);
+
+    for compression in [Compression::Stored, Compression::Deflate] {
+        let open_opts = ZipEntryBuilder::new(format!("{:?}.bar", compression), compression);
+        zip_writer.write_entry_whole(open_opts, &data).await.expect("failed to write entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let size = data.len() as u64;
+
+    for index in 0..2 {
+        let ranges = [(0, None), (0, Some(1)), (10, Some(100)), (size - 1, None), (size, None), (17, Some(17))];
+
+        for (start, end) in ranges {
+            let range_reader = zip_reader.entry_reader_range(index, start, end).await;
+            let mut range_reader = range_reader.expect("failed to open range");
+            assert_eq!(start, range_reader.start());
+
+            let mut read = Vec::new();
+            range_reader.read_to_end(&mut read).await.expect("failed to read range");
+            assert_eq!(&data[start as usize..end.unwrap_or(size) as usize], &read[..]);
+            assert_eq!(0, range_reader.remaining());
+        }
+
+        for (start, end) in [(size + 1, None), (0, Some(size + 1)), (2, Some(1))] {
+            let result = zip_reader.entry_reader_range(index, start, end).await;
+            assert!(matches!(result, Err(ZipError::RangeOutOfBounds { .. })));
+        }
+    }
+
+    let mut input_stream = Cursor::new(archive);
+    let mut zip_reader = seek::ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    let mut range_reader = zip_reader.entry_reader_range(1, 6, Some(11)).await.expect("failed to open range");
+
+    let mut read = String::new();
+    range_reader.read_to_string(&mut read).await.expect("failed to read range");
+    assert_eq!("ipsum", read);
+}
+
+#[tokio::test]
+async fn symlink_entries() {
+    use crate::entry::ext::{ZipEntryBuilderExt, ZipEntryExt};
+    use crate::error::ZipError;
+    use crate::read::{mem, ReaderOptions};
+    use crate::spec::attribute::AttributeCompatibility;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Deflate).unix_permissions(0o100644);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+
+    let open_opts = ZipEntryBuilder::new(String::from("link"), Compression::Deflate).symlink("../foo.bar");
+    let written = zip_writer.write_entry_whole(open_opts.unix_ownership(1000, 100), &[]).await;
+    assert_eq!(10, written.expect("failed to write symlink").uncompressed_size());
+
+    let open_opts = ZipEntryBuilder::new(String::from("bad_link"), Compression::Stored).symlink("foo.bar");
+    let result = zip_writer.write_entry_whole(open_opts, b"foo").await;
+    assert!(matches!(result, Err(ZipError::SymlinkData(_))));
+
+    let open_opts = ZipEntryBuilder::new(String::from("bad_link"), Compression::Stored).symlink("foo.bar");
+    assert!(matches!(zip_writer.write_entry_stream(open_opts).await, Err(ZipError::FeatureNotSupported(_))));
+
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    assert_eq!(2, zip_reader.entries().len());
+
+    let entry = zip_reader.entries()[0];
+    assert!(!entry.is_symlink());
+    assert_eq!((None, None), (entry.symlink_target(), entry.unix_ownership()));
+
+    let entry = zip_reader.entries()[1];
+    assert!(entry.is_symlink());
+    assert_eq!(AttributeCompatibility::Unix, entry.attribute_compatibility());
+    assert_eq!(0o120777 << 16, entry.external_file_attribute());
+    assert_eq!(Compression::Stored, entry.compression());
+    assert_eq!(Some("../foo.bar"), entry.symlink_target());
+    assert_eq!(Some((1000, 100)), entry.unix_ownership());
+
+    let entry_reader = zip_reader.entry_reader(1).await.expect("failed to open entry reader");
+    assert_eq!(b"../foo.bar", &entry_reader.read_to_end_crc().await.expect("failed to read symlink")[..]);
+
+    // Lazily-listed entries can still be classified, although their targets aren't read upfront.
+    let options = ReaderOptions::new().lazy_entries(true);
+    let zip_reader = mem::ZipFileReader::with_options(&archive, options).await.expect("failed to open reader");
+    let mut cd_entries = zip_reader.cd_entries().await.expect("failed to open central directory");
+    let entry = cd_entries.find("link").await.expect("failed to scan entries").expect("missing entry");
+
+    assert!(entry.entry().is_symlink());
+    assert_eq!(None, entry.entry().symlink_target());
+}
+
+#[tokio::test]
+async fn unix_permissions_entries() {
+    use crate::entry::ext::{ZipEntryBuilderExt, ZipEntryExt};
+    use crate::read::mem;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("run.sh"), Compression::Stored).unix_permissions(0o755);
+    zip_writer.write_entry_whole(open_opts, b"#!/bin/sh").await.expect("failed to write entry");
+    let open_opts = ZipEntryBuilder::new(String::from("bin/"), Compression::Stored).unix_permissions(0o755);
+    zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
+    let open_opts = ZipEntryBuilder::new(String::from("dos"), Compression::Stored);
+    let open_opts = open_opts.attribute_compatibility(crate::spec::attribute::AttributeCompatibility::Dos);
+    zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entries = zip_reader.entries();
+
+    // Directories gain the directory file type and MS-DOS attribute, and files the regular file type, so that
+    // `unzip -Z` lists them as `drwxr-xr-x` and `-rwxr-xr-x` rather than with an unknown type.
+    assert_eq!(Some(0o100755), entries[0].unix_permissions());
+    assert_eq!(Some(0o040755), entries[1].unix_permissions());
+    assert_eq!(0o040755 << 16 | 0x10, entries[1].external_file_attribute());
+    assert_eq!(None, entries[2].unix_permissions());
+
+    // The mode is held within the upper bits of the central directory's external attributes (as read by `unzip -Z`),
+    // with the upper byte of the version made by denoting a Unix host.
+    let eocdr = archive.len() - 22;
+    let cd_offset = u32::from_le_bytes(archive[eocdr + 16..eocdr + 20].try_into().unwrap()) as usize;
+    assert_eq!(3, archive[cd_offset + 5]);
+    assert_eq!(0o100755 << 16, u32::from_le_bytes(archive[cd_offset + 38..cd_offset + 42].try_into().unwrap()));
+}
+
+#[tokio::test]
+async fn directory_entries() {
+    use crate::entry::ext::ZipEntryExt;
+    use crate::read::mem;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    // Trailing slashes are appended where missing, and backslashes replaced.
+    let written = zip_writer.write_dir_entry("a").await.expect("failed to write entry");
+    assert_eq!((0, 0), (written.compressed_size(), written.uncompressed_size()));
+    let open_opts = ZipEntryBuilder::dir("a\\b").comment(String::from("bar"));
+    zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
+    let open_opts = ZipEntryBuilder::new("a/b/file.txt", Compression::Deflate);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+
+    // An entry written without a trailing slash but with the MS-DOS directory attribute.
+    let open_opts = ZipEntryBuilder::new("c", Compression::Stored).external_file_attribute(0x10);
+    zip_writer.write_entry_whole(open_opts, &[]).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entries = zip_reader.entries();
+
+    let filenames: Vec<_> = entries.iter().map(|entry| entry.filename()).collect();
+    assert_eq!(vec!["a/", "a/b/", "a/b/file.txt", "c"], filenames);
+    assert_eq!(vec![true, true, false, true], entries.iter().map(|entry| entry.dir()).collect::<Vec<_>>());
+    assert_eq!("bar", entries[1].comment());
+
+    for entry in &entries[..2] {
+        assert_eq!(Compression::Stored, entry.compression());
+        assert_eq!((0, 0), (entry.compressed_size(), entry.uncompressed_size()));
+        assert_eq!(Some(0o040755), entry.unix_permissions());
+        assert_eq!(0x10, entry.external_file_attribute() & 0xFF);
+    }
+}
+
+// Reproducers of panics (or huge allocations) found by fuzzing the mem and stream readers.
+#[tokio::test]
+async fn malformed_header_regressions() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream};
+
+    // A declared uncompressed size just below the ZIP64 placeholder was trusted when pre-allocating.
+    let archive = raw_archive("foo.bar", 0, compute_crc(b"foo"), b"foo", 0xFFFFFFFE);
+
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert!(matches!(entry_reader.read_to_end_crc().await, Err(ZipError::SizeMismatch { .. })));
+
+    let mut zip_reader = stream::ZipFileReader::new(Cursor::new(&archive));
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").expect("missing entry");
+    assert!(matches!(entry_reader.read_to_string_crc().await, Err(ZipError::SizeMismatch { .. })));
+
+    // An extra field record whose declared length overruns its block.
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+    let open_opts = open_opts.extra_field(0xCAFE, vec![1, 2, 3]);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let mut archive = input_stream.into_inner();
+    for index in 0..archive.len() - 4 {
+        if archive[index..index + 4] == [0xFE, 0xCA, 0x03, 0x00] {
+            archive[index + 2] = 0xFF;
+        }
+    }
+
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    assert!(zip_reader.entries()[0].extra_fields().is_empty());
+    assert_eq!(7, zip_reader.entries()[0].extra_field().len());
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert!(entry_reader.local_extra_fields().is_empty());
+    assert_eq!(b"foo", &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+
+    // A local file header offset which, along with the compressed size, lies far beyond the ZIP file.
+    let mut archive = raw_archive("foo.bar", 0, compute_crc(b"foo"), b"foo", 3);
+    let eocdh_offset = archive.len() - 22;
+    let cd_offset = u32::from_le_bytes(archive[eocdh_offset + 16..eocdh_offset + 20].try_into().unwrap()) as usize;
+    archive[cd_offset + 42..cd_offset + 46].copy_from_slice(&0xFFFFFF00u32.to_le_bytes());
+
+    let result = mem::ZipFileReader::new(&archive).await;
+    assert!(matches!(result, Err(ZipError::EntryDataOutOfBounds(0))));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn write_dir_round_trip() {
+    use crate::entry::ext::ZipEntryExt;
+    use crate::read::mem;
+    use crate::write::fs::{write_dir, DirWriteOptions, SymlinkPolicy};
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let root = dir.path();
+    let source = root.join("source");
+    let extracted = root.join("extracted");
+
+    let files: &[(&str, &[u8], u32)] = &[
+        ("a.txt", b"Lorem ipsum dolor sit amet", 0o640),
+        ("sub/b.bin", &[0, 1, 2, 3], 0o755),
+        ("sub/deeper/c.txt", b"consectetur adipiscing elit", 0o600),
+    ];
+
+    for (path, data, mode) in files {
+        let path = source.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).expect("failed to create directory");
+        std::fs::write(&path, data).expect("failed to write file");
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(*mode)).expect("failed to set permissions");
+    }
+
+    std::fs::write(source.join(".hidden"), b"hidden").expect("failed to write file");
+    std::fs::write(source.join("sub/build.log"), b"excluded").expect("failed to write file");
+    std::os::unix::fs::symlink("a.txt", source.join("link")).expect("failed to create symlink");
+
+    let options = DirWriteOptions::new()
+        .prefix("root/")
+        .exclude("*.log")
+        .skip_hidden(true)
+        .symlinks(SymlinkPolicy::Store)
+        .compression(|path| match path.extension() {
+            Some(extension) if extension == "txt" => Compression::Deflate,
+            _ => Compression::Stored,
+        });
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    write_dir(&mut zip_writer, &source, options).await.expect("failed to write directory");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let filenames: Vec<_> = zip_reader.entries().iter().map(|entry| entry.filename().to_owned()).collect();
+    let expected =
+        ["root/a.txt", "root/link", "root/sub/", "root/sub/b.bin", "root/sub/deeper/", "root/sub/deeper/c.txt"];
+    assert_eq!(&expected[..], &filenames[..]);
+
+    let link = zip_reader.entry("root/link").map(|(_, entry)| entry).expect("missing symlink");
+    assert!(link.is_symlink());
+    assert_eq!(Some("a.txt"), link.symlink_target());
+    assert_eq!(Compression::Deflate, zip_reader.entries()[0].compression());
+    assert_eq!(Compression::Stored, zip_reader.entries()[3].compression());
+
+    // Extract the regular files, applying each entry's mode, and compare against the originals.
+    for index in 0..zip_reader.entries().len() {
+        let entry = zip_reader.entries()[index];
+        let path = extracted.join(entry.sanitized_path().expect("unsafe filename"));
+        let mode = entry.unix_permissions().expect("missing permissions");
+
+        if entry.filename().ends_with('/') {
+            assert_eq!(0o040000, u32::from(mode) & 0o170000);
+            std::fs::create_dir_all(&path).expect("failed to create directory");
+            continue;
+        } else if entry.is_symlink() {
+            continue;
+        }
+
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        let data = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+        std::fs::write(&path, data).expect("failed to write file");
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode.into())).expect("failed to set mode");
+    }
+
+    for (path, data, mode) in files {
+        let path = extracted.join("root").join(Path::new(path));
+        assert_eq!(*data, &std::fs::read(&path).expect("missing file")[..]);
+        assert_eq!(*mode, std::fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+    }
+}
+
+#[tokio::test]
+async fn write_from_directory_round_trip() {
+    use crate::entry::CompressionLevel;
+    use crate::read::{fs, ExtractOptions};
+    use crate::write::fs::DirWriteOptions;
+    use std::path::{Path, PathBuf};
+
+    // Lists every file (with its contents) and directory beneath a path, relative to it.
+    fn list(root: &Path, relative: PathBuf, listing: &mut Vec<(PathBuf, Option<Vec<u8>>)>) {
+        let children = std::fs::read_dir(root.join(&relative)).unwrap();
+        let mut children: Vec<_> = children.map(|child| child.unwrap()).collect();
+        children.sort_by_key(|child| child.file_name());
+
+        for child in children {
+            let relative = relative.join(child.file_name());
+            if child.file_type().unwrap().is_dir() {
+                listing.push((relative.clone(), None));
+                list(root, relative, listing);
+            } else {
+                listing.push((relative, Some(std::fs::read(child.path()).unwrap())));
+            }
+        }
+    }
+
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let root = dir.path();
+    let source = root.join("tree");
+
+    std::fs::create_dir_all(source.join("nested/deeper")).expect("failed to create directory");
+    std::fs::create_dir_all(source.join("empty")).expect("failed to create directory");
+    std::fs::write(source.join("nested/deeper/small.txt"), b"Lorem ipsum").expect("failed to write file");
+    let large: Vec<u8> = (0..10 * 1024 * 1024u32).map(|index| (index % 251) as u8 ^ (index >> 16) as u8).collect();
+    std::fs::write(source.join("nested/large.bin"), &large).expect("failed to write file");
+
+    let path = root.join("tree.zip");
+    let mut output = tokio::fs::File::create(&path).await.expect("failed to create file");
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+
+    let options = DirWriteOptions::new()
+        .include_root(true)
+        .compression(|_| Compression::Deflate)
+        .compression_level(CompressionLevel::Fastest);
+    let written = zip_writer.write_from_directory(&source, options).await.expect("failed to write directory");
+    assert_eq!(6, written.len());
+    zip_writer.close().await.expect("failed to close writer");
+    tokio::io::AsyncWriteExt::flush(&mut output).await.expect("failed to flush file");
+    drop(output);
+
+    let mut zip_reader = fs::ZipFileReader::new(&path).await.expect("failed to open reader");
+    assert!(zip_reader.entries().iter().all(|entry| entry.filename().starts_with("tree/")));
+    assert!(zip_reader.entry("tree/empty/").map_or(false, |(_, entry)| entry.dir()));
+    zip_reader.extract(root.join("extracted"), ExtractOptions::new()).await.expect("failed to extract");
+
+    let (mut expected, mut extracted) = (Vec::new(), Vec::new());
+    list(&source, PathBuf::new(), &mut expected);
+    list(&root.join("extracted/tree"), PathBuf::new(), &mut extracted);
+    assert_eq!(expected, extracted);
+}
+
+#[tokio::test]
+async fn extract_to_directory() {
+    use crate::error::ZipError;
+    use crate::read::{fs, mem, seek, ExtractOptions};
+    use chrono::{DateTime, Utc};
+    use std::time::{Duration, SystemTime};
+
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let root = dir.path();
+    let dest = root.join("a").join("b");
+    let escaped = root.join("etc").join("evil");
+
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_666_544_103);
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new("foo.txt", Compression::Stored).modified(modified);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    for (filename, data) in [("empty/", &b""[..]), ("sub/bar.txt", b"lorem ipsum"), ("../../etc/evil", b"evil")] {
+        let open_opts = ZipEntryBuilder::new(filename, Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, data).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+
+    // The traversing entry is rejected by default, and never escapes the destination whether rejected or skipped.
+    let path = root.join("archive.zip");
+    std::fs::write(&path, &archive).expect("failed to write file");
+    let mut zip_reader = fs::ZipFileReader::new(&path).await.expect("failed to open reader");
+    let result = zip_reader.extract(&dest, ExtractOptions::new()).await;
+    assert!(matches!(result, Err(ZipError::UnsafeEntryName { index: 3, .. })));
+    assert!(!escaped.exists());
+
+    // Existing files aren't overwritten by default.
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(&archive)).await.expect("failed to open reader");
+    let result = zip_reader.extract(&dest, ExtractOptions::new().skip_unsafe(true)).await;
+    assert!(matches!(result, Err(ZipError::UpstreamReadError(err)) if err.kind() == std::io::ErrorKind::AlreadyExists));
+
+    let options = ExtractOptions::new().skip_unsafe(true).overwrite(true);
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    zip_reader.extract(&dest, options.clone()).await.expect("failed to extract");
+    assert!(!escaped.exists());
+
+    assert_eq!(b"foo", &std::fs::read(dest.join("foo.txt")).expect("failed to read file")[..]);
+    assert_eq!(b"lorem ipsum", &std::fs::read(dest.join("sub/bar.txt")).expect("failed to read file")[..]);
+    assert!(dest.join("empty").is_dir());
+    let restored = std::fs::metadata(dest.join("foo.txt")).and_then(|metadata| metadata.modified());
+    assert_eq!(DateTime::<Utc>::from(modified), DateTime::<Utc>::from(restored.expect("failed to read metadata")));
+
+    // A corrupted entry fails its CRC32 check by name, and its partially-written file is removed.
+    let mut corrupted = archive.clone();
+    let offset = corrupted.windows(5).position(|window| window == b"lorem").expect("missing data");
+    corrupted[offset] = b'L';
+    let _ = std::fs::remove_dir_all(&dest);
+
+    let mut zip_reader = mem::ZipFileReader::new(&corrupted).await.expect("failed to open reader");
+    let result = zip_reader.extract(&dest, options).await;
+    assert!(matches!(result, Err(ZipError::CRC32CheckError { filename, .. }) if filename == "sub/bar.txt"));
+    assert!(dest.join("foo.txt").exists());
+    assert!(!dest.join("sub/bar.txt").exists());
+}
+
+#[tokio::test]
+async fn entry_reader_poll_byte_budget() {
+    use crate::read::{mem, ReaderOptions};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // A highly-compressible entry, read from memory, never returns Poll::Pending of its own accord.
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let data = vec![0; 4 * 1024 * 1024];
+
+    let open_opts = ZipEntryBuilder::new(String::from("zeros.bin"), Compression::Deflate);
+    zip_writer.write_entry_whole(open_opts, &data).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+
+    // A probe task which counts each time it's given a chance to run.
+    let probe = |polls: Arc<AtomicUsize>| {
+        tokio::spawn(async move {
+            loop {
+                polls.fetch_add(1, Ordering::SeqCst);
+                tokio::task::yield_now().await;
+            }
+        })
+    };
+
+    // Without a budget, the probe is starved until the entry has been read to its end.
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let polls = Arc::new(AtomicUsize::new(0));
+    let task = probe(polls.clone());
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader").poll_byte_budget(0);
+    assert_eq!(data, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    assert_eq!(0, polls.load(Ordering::SeqCst));
+    task.abort();
+
+    let options = ReaderOptions::new().poll_byte_budget(64 * 1024);
+    let mut zip_reader = mem::ZipFileReader::with_options(&archive, options).await.expect("failed to open reader");
+    let polls = Arc::new(AtomicUsize::new(0));
+    let task = probe(polls.clone());
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert_eq!(data, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    assert!(polls.load(Ordering::SeqCst) > 0);
+    task.abort();
+}
+
+#[tokio::test]
+async fn internal_file_attribute_text() {
+    use crate::entry::ext::{ZipEntryBuilderExt, ZipEntryExt};
+    use crate::read::mem;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("explicit.txt"), Compression::Stored).text(true);
+    zip_writer.write_entry_whole(open_opts, &[0, 1, 2]).await.expect("failed to write entry");
+
+    let open_opts = ZipEntryBuilder::new(String::from("raw.bin"), Compression::Stored).internal_file_attribute(0x8000);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+
+    zip_writer.detect_text(true);
+    let entries: [(&str, Vec<u8>); 5] = [
+        ("text.txt", b"Lorem ipsum\r\n\tdolor sit amet\n".to_vec()),
+        ("binary.bin", vec![b'a', 0, b'b']),
+        ("empty.txt", Vec::new()),
+        ("late_binary.txt", [b"a".repeat(8 * 1024), vec![0]].concat()),
+        ("control.txt", vec![7, 8, 11, 12, 26, 27]),
+    ];
+
+    for (filename, data) in &entries {
+        let open_opts = ZipEntryBuilder::new(filename.to_string(), Compression::Deflate);
+        zip_writer.write_entry_whole(open_opts, data).await.expect("failed to write entry");
+    }
+
+    let open_opts = ZipEntryBuilder::new(String::from("stream.txt"), Compression::Deflate);
+    zip_writer.write_entry_copy(open_opts, &b"foo"[..], 1024).await.expect("failed to write entry");
+
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    let attributes: Vec<_> = zip_reader.entries().iter().map(|entry| entry.internal_file_attribute()).collect();
+    assert_eq!(vec![0x0001, 0x8000, 0x0001, 0, 0, 0x0001, 0, 0], attributes);
+
+    let text: Vec<_> = zip_reader.entries().iter().map(|entry| entry.is_text()).collect();
+    assert_eq!(vec![true, false, true, false, false, true, false, false], text);
+}
+
+#[tokio::test]
+async fn archive_metadata() {
+    use crate::read::mem;
+
+    // A Latin-1 encoded comment, which isn't valid UTF-8.
+    let mut archive = raw_archive("foo.bar", 0, compute_crc(b"foo"), b"foo", 3);
+    let eocdh_offset = archive.len() - 22;
+    archive[eocdh_offset + 20..eocdh_offset + 22].copy_from_slice(&4u16.to_le_bytes());
+    archive.extend_from_slice(b"Caf\xe9");
+
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let metadata = zip_reader.metadata();
+
+    assert_eq!(b"Caf\xe9", metadata.comment_raw());
+    assert_eq!(Some("Caf\u{FFFD}"), metadata.comment());
+    assert_eq!(Some("Caf\u{FFFD}"), zip_reader.comment());
+    assert_eq!((0, 0), (metadata.disk_number(), metadata.central_directory_disk()));
+    assert_eq!((1, 1), (metadata.entries_on_disk(), metadata.total_entries()));
+    assert_eq!((53, 40), (metadata.central_directory_size(), metadata.central_directory_offset()));
+    assert_eq!(0, metadata.preamble_length());
+    assert!(!metadata.has_zip64_locator());
+    assert!(!metadata.requires_zip64());
+
+    // A ZIP64 end of central directory locator directly preceding the EOCDH.
+    let mut located = archive[..eocdh_offset].to_vec();
+    located.extend_from_slice(&crate::spec::signature::ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR.to_le_bytes());
+    located.extend_from_slice(&[0; 16]);
+    located.extend_from_slice(&archive[eocdh_offset..]);
+
+    let mut zip_reader = mem::ZipFileReader::new(&located).await.expect("failed to open reader");
+    assert!(zip_reader.metadata().has_zip64_locator());
+    assert_eq!(40, zip_reader.metadata().central_directory_offset());
+    assert_eq!(b"foo", &zip_reader.entry_reader(0).await.unwrap().read_to_end_crc().await.unwrap()[..]);
+
+    // An entry declaring a ZIP64 extended information extra field.
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored).extra_field(0x0001, vec![0; 8]);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    assert!(zip_reader.metadata().requires_zip64());
+    assert_eq!(None, zip_reader.metadata().comment());
+}
+
+#[tokio::test]
+async fn eocdh_trailing_bytes() {
+    use crate::read::mem;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+
+    // 100 bytes appended after the EOCDH, which themselves begin with its signature.
+    let mut trailing = archive.clone();
+    trailing.extend_from_slice(&crate::spec::signature::END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    trailing.extend_from_slice(&[0xFF; 96]);
+
+    let mut zip_reader = mem::ZipFileReader::new(&trailing).await.expect("failed to open reader");
+    assert_eq!(100, zip_reader.metadata().trailing_length());
+    assert_eq!(1, zip_reader.entries().len());
+    assert_eq!(b"foo", &zip_reader.entry_reader(0).await.unwrap().read_to_end_crc().await.unwrap()[..]);
+
+    // A comment which contains the EOCDH's signature, followed by enough bytes to be parsed as one.
+    let comment = String::from("signed: PK\x05\x06 0123456789abcdefghijklmnopqrstuvwxyz");
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    zip_writer.comment(comment.clone()).expect("failed to set comment");
+    zip_writer.close().await.expect("failed to close writer");
+    let commented = input_stream.into_inner();
+
+    let zip_reader = mem::ZipFileReader::new(&commented).await.expect("failed to open reader");
+    assert_eq!(Some(comment.as_str()), zip_reader.comment());
+    assert_eq!(0, zip_reader.metadata().trailing_length());
+    assert_eq!(1, zip_reader.entries().len());
+
+    // A comment length which overstates, and then understates, the bytes which follow the EOCDH.
+    let eocdh_offset = archive.len() - 22;
+    let mut lying = archive.clone();
+    lying[eocdh_offset + 20..eocdh_offset + 22].copy_from_slice(&200u16.to_le_bytes());
+    lying.extend_from_slice(b"hello");
+
+    let zip_reader = mem::ZipFileReader::new(&lying).await.expect("failed to open reader");
+    assert_eq!(Some("hello"), zip_reader.comment());
+    assert_eq!(0, zip_reader.metadata().trailing_length());
+
+    lying[eocdh_offset + 20..eocdh_offset + 22].copy_from_slice(&2u16.to_le_bytes());
+    let zip_reader = mem::ZipFileReader::new(&lying).await.expect("failed to open reader");
+    assert_eq!(Some("he"), zip_reader.comment());
+    assert_eq!(3, zip_reader.metadata().trailing_length());
+}
+
+#[tokio::test]
+async fn eocd_search_strategies() {
+    use crate::error::ZipError;
+    use crate::read::{mem, seek, ReaderOptions, SearchStrategy};
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+    // A reader which counts every read made against it, as a reader backed by HTTP range requests would issue.
+    struct ReadCounter {
+        inner: Cursor<Vec<u8>>,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl AsyncRead for ReadCounter {
+        fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            Pin::new(&mut self.inner).poll_read(c, b)
+        }
+    }
+
+    impl AsyncSeek for ReadCounter {
+        fn start_seek(mut self: Pin<&mut Self>, position: std::io::SeekFrom) -> std::io::Result<()> {
+            Pin::new(&mut self.inner).start_seek(position)
+        }
+
+        fn poll_complete(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+            Pin::new(&mut self.inner).poll_complete(c)
+        }
+    }
+
+    let write = |comment: Option<String>| async move {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+        for name in ["foo.bar", "bar.foo"] {
+            let open_opts = ZipEntryBuilder::new(String::from(name), Compression::Stored);
+            zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+        }
+
+        if let Some(comment) = comment {
+            zip_writer.comment(comment).expect("failed to set comment");
+        }
+
+        zip_writer.close().await.expect("failed to close writer");
+        input_stream.into_inner()
+    };
+
+    // An exact search opens a ZIP file without a comment with one read for the EOCDH, and another for the central
+    // directory.
+    let archive = write(None).await;
+    let reads = Arc::new(AtomicUsize::new(0));
+    let reader = ReadCounter { inner: Cursor::new(archive.clone()), reads: reads.clone() };
+
+    let options = ReaderOptions::new().eocd_search(SearchStrategy::Exact);
+    let zip_reader = seek::ZipFileReader::with_options(reader, options.clone()).await.expect("failed to open reader");
+    assert_eq!(2, zip_reader.entries().len());
+    assert_eq!(2, reads.load(Ordering::SeqCst));
+
+    let commented = write(Some("a".repeat(1000))).await;
+    let result = mem::ZipFileReader::with_options(&commented, options).await;
+    assert!(matches!(result, Err(ZipError::EocdNotFound { searched_bytes: 22 })));
+
+    // A window shorter than the comment doesn't reach the EOCDH, whereas the default covers the longest comment.
+    let options = ReaderOptions::new().eocd_search(SearchStrategy::Windowed(100));
+    let result = mem::ZipFileReader::with_options(&commented, options).await;
+    assert!(matches!(result, Err(ZipError::EocdNotFound { searched_bytes: 100 })));
+
+    let zip_reader = mem::ZipFileReader::new(&commented).await.expect("failed to open reader");
+    assert_eq!(Some("a".repeat(1000).as_str()), zip_reader.comment());
+
+    // An incremental search reads backwards in steps, whose boundaries the EOCDH may straddle.
+    let search = SearchStrategy::Incremental { step: 250, max: SearchStrategy::MAX_WINDOW };
+    let options = ReaderOptions::new().eocd_search(search);
+    let zip_reader = mem::ZipFileReader::with_options(&commented, options).await.expect("failed to open reader");
+    assert_eq!(Some("a".repeat(1000).as_str()), zip_reader.comment());
+    assert_eq!(2, zip_reader.entries().len());
+
+    let search = SearchStrategy::Incremental { step: 250, max: 900 };
+    let result = mem::ZipFileReader::with_options(&commented, ReaderOptions::new().eocd_search(search)).await;
+    assert!(matches!(result, Err(ZipError::EocdNotFound { searched_bytes: 900 })));
+
+    // Every strategy finds the EOCDH of a ZIP file without a comment.
+    let incremental = SearchStrategy::Incremental { step: 7, max: 22 };
+    for search in [SearchStrategy::Exact, SearchStrategy::Windowed(22), incremental] {
+        let options = ReaderOptions::new().eocd_search(search);
+        let zip_reader = mem::ZipFileReader::with_options(&archive, options).await.expect("failed to open reader");
+        assert_eq!(2, zip_reader.entries().len(), "{:?}", search);
+    }
+}
+
+#[tokio::test]
+async fn mimetype_entry() {
+    use crate::error::ZipError;
+    use crate::read::{mem, seek};
+
+    let mimetype = "application/epub+zip";
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.reproducible(crate::spec::date::zip_date_to_chrono(0x21, 0));
+
+    zip_writer.write_mimetype_entry(mimetype).await.expect("failed to write mimetype entry");
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+
+    // The mimetype entry may only begin the ZIP file.
+    let result = zip_writer.write_mimetype_entry(mimetype).await;
+    assert!(matches!(result, Err(ZipError::MimetypeNotFirst)));
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+
+    // The local file header which validators such as epubcheck expect, followed by the filename and mimetype.
+    let mut expected = crate::spec::signature::LOCAL_FILE_HEADER.to_le_bytes().to_vec();
+    expected.extend_from_slice(&10u16.to_le_bytes());
+    expected.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    expected.extend_from_slice(&0x21u16.to_le_bytes());
+    expected.extend_from_slice(&compute_crc(mimetype.as_bytes()).to_le_bytes());
+    expected.extend_from_slice(&(mimetype.len() as u32).to_le_bytes());
+    expected.extend_from_slice(&(mimetype.len() as u32).to_le_bytes());
+    expected.extend_from_slice(&8u16.to_le_bytes());
+    expected.extend_from_slice(&0u16.to_le_bytes());
+    expected.extend_from_slice(b"mimetype");
+    expected.extend_from_slice(mimetype.as_bytes());
+    assert_eq!(expected, archive[..38 + mimetype.len()]);
+
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    assert_eq!(Some(mimetype), zip_reader.sniff_mimetype().await.expect("failed to sniff mimetype").as_deref());
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(&archive)).await.expect("failed to open reader");
+    assert_eq!(Some(mimetype), zip_reader.sniff_mimetype().await.expect("failed to sniff mimetype").as_deref());
+
+    // A mimetype entry with an extra field, or one which isn't first, doesn't follow the convention.
+    let unconventional = [
+        vec![ZipEntryBuilder::new(String::from("mimetype"), Compression::Stored).extra_field(0xCAFE, Vec::new())],
+        vec![
+            ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored),
+            ZipEntryBuilder::new(String::from("mimetype"), Compression::Stored),
+        ],
+    ];
+
+    for entries in unconventional {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+        for open_opts in entries {
+            zip_writer.write_entry_whole(open_opts, mimetype.as_bytes()).await.expect("failed to write entry");
+        }
+        zip_writer.close().await.expect("failed to close writer");
+
+        let archive = input_stream.into_inner();
+        let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+        assert_eq!(None, zip_reader.sniff_mimetype().await.expect("failed to sniff mimetype"));
+    }
+}
+
+#[tokio::test]
+async fn path_policies() {
+    use crate::error::{Result, ZipError};
+    use crate::read::{fs, mem, seek, stream, PathPolicy, ReaderOptions};
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for filename in ["safe/file.txt", "..\\..\\evil.dll", "/etc/cron.d/x"] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let path = dir.path().join("path_policy.zip");
+    tokio::fs::write(&path, &archive).await.expect("failed to write archive");
+
+    let policies = [
+        (PathPolicy::Preserve, ["safe/file.txt", "..\\..\\evil.dll", "/etc/cron.d/x"]),
+        (PathPolicy::Sanitize, ["safe/file.txt", "evil.dll", "etc/cron.d/x"]),
+    ];
+
+    for (policy, expected) in policies {
+        let options = ReaderOptions::new().path_policy(policy);
+
+        let zip_reader = mem::ZipFileReader::with_options(&archive, options.clone()).await.unwrap();
+        let filenames: Vec<_> = zip_reader.entries().iter().map(|entry| entry.filename().to_owned()).collect();
+        assert_eq!(expected.to_vec(), filenames);
+        assert_eq!("..\\..\\evil.dll", zip_reader.entries()[1].filename_raw());
+
+        let zip_reader = seek::ZipFileReader::with_options(Cursor::new(&archive), options.clone()).await.unwrap();
+        let filenames: Vec<_> = zip_reader.entries().iter().map(|entry| entry.filename().to_owned()).collect();
+        assert_eq!(expected.to_vec(), filenames);
+
+        let zip_reader = fs::ZipFileReader::with_options(&path, options.clone()).await.unwrap();
+        let filenames: Vec<_> = zip_reader.entries().iter().map(|entry| entry.filename().to_owned()).collect();
+        assert_eq!(expected.to_vec(), filenames);
+
+        let lazy = options.clone().lazy_entries(true);
+        let zip_reader = mem::ZipFileReader::with_options(&archive, lazy).await.unwrap();
+        let mut cd_entries = zip_reader.cd_entries().await.unwrap();
+        let mut filenames = Vec::new();
+        while let Some(entry) = cd_entries.next_entry().await.unwrap() {
+            filenames.push(entry.entry().filename().to_owned());
+        }
+        assert_eq!(expected.to_vec(), filenames);
+
+        let mut zip_reader = stream::ZipFileReader::with_options(Cursor::new(&archive), options);
+        let mut filenames = Vec::new();
+        while let Some(entry) = zip_reader.skip_entry().await.unwrap() {
+            assert_eq!(expected[filenames.len()], entry.filename());
+            filenames.push(entry.filename_raw().to_owned());
+        }
+        assert_eq!(["safe/file.txt", "..\\..\\evil.dll", "/etc/cron.d/x"].to_vec(), filenames);
+    }
+
+    // The first unsafe filename is rejected, whichever reader parses it.
+    let options = ReaderOptions::new().path_policy(PathPolicy::Reject);
+    let rejected = |result: Result<()>| match result {
+        Err(ZipError::UnsafeEntryName { index, name }) => (index, name),
+        Err(err) => panic!("unexpected error: {:?}", err),
+        Ok(_) => panic!("unsafe filename wasn't rejected"),
+    };
+    let expected = (1, String::from("..\\..\\evil.dll"));
+
+    assert_eq!(expected, rejected(mem::ZipFileReader::with_options(&archive, options.clone()).await.map(|_| ())));
+    let seek = seek::ZipFileReader::with_options(Cursor::new(&archive), options.clone()).await;
+    assert_eq!(expected, rejected(seek.map(|_| ())));
+    assert_eq!(expected, rejected(fs::ZipFileReader::with_options(&path, options.clone()).await.map(|_| ())));
+
+    let lazy = options.clone().lazy_entries(true);
+    let zip_reader = mem::ZipFileReader::with_options(&archive, lazy).await.unwrap();
+    let mut cd_entries = zip_reader.cd_entries().await.unwrap();
+    assert_eq!("safe/file.txt", cd_entries.next_entry().await.unwrap().unwrap().entry().filename());
+    assert_eq!(expected, rejected(cd_entries.next_entry().await.map(|_| ())));
+
+    // The stream reader skips a rejected entry's data, so reading may continue past it.
+    let mut zip_reader = stream::ZipFileReader::with_options(Cursor::new(&archive), options);
+    zip_reader.entry_reader().await.unwrap().unwrap().read_to_end_crc().await.unwrap();
+    assert_eq!(expected, rejected(zip_reader.entry_reader().await.map(|_| ())));
+    assert_eq!((2, String::from("/etc/cron.d/x")), rejected(zip_reader.skip_entry().await.map(|_| ())));
+    assert!(zip_reader.skip_entry().await.unwrap().is_none());
+}
+
+#[cfg(feature = "deflate")]
+#[test]
+fn stream_poll_api() {
+    use crate::read::stream::ZipFileReader;
+    use std::pin::Pin;
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+    use tokio::io::{AsyncRead, AsyncWriteExt, ReadBuf};
+
+    // A reader which returns Pending on every other call, and otherwise only yields a few bytes at once.
+    struct Alternating {
+        inner: Cursor<Vec<u8>>,
+        pending: bool,
+    }
+
+    impl AsyncRead for Alternating {
+        fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+            self.pending = !self.pending;
+            if self.pending {
+                return Poll::Pending;
+            }
+
+            let mut limited = ReadBuf::new(b.initialize_unfilled_to(b.remaining().min(7)));
+            let poll = Pin::new(&mut self.inner).poll_read(c, &mut limited);
+            let read = limited.filled().len();
+            b.advance(read);
+            poll
+        }
+    }
+
+    fn noop_raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            noop_raw_waker()
+        }
+        fn noop(_: *const ()) {}
+
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let archive = runtime.block_on(async {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+        let open_opts = ZipEntryBuilder::new(String::from("stored.txt"), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, b"Lorem ipsum dolor sit amet").await.unwrap();
+        let open_opts = ZipEntryBuilder::new(String::from("empty/"), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, &[]).await.unwrap();
+
+        // A streamed entry, whose sizes are only known from its data descriptor.
+        let open_opts = ZipEntryBuilder::new(String::from("streamed.txt"), Compression::Deflate);
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.unwrap();
+        entry_writer.write_all(&[b'a'; 5000]).await.unwrap();
+        entry_writer.close().await.unwrap();
+
+        let open_opts = ZipEntryBuilder::new(String::from("deflated.txt"), Compression::Deflate);
+        zip_writer.write_entry_whole(open_opts, b"consectetur adipiscing elit").await.unwrap();
+
+        zip_writer.close().await.unwrap();
+        input_stream.into_inner()
+    });
+
+    let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+    let mut context = Context::from_waker(&waker);
+    let mut zip_reader = ZipFileReader::new(Alternating { inner: Cursor::new(archive), pending: false });
+    let mut extracted = Vec::new();
+
+    loop {
+        let entry = match zip_reader.poll_next_entry(&mut context) {
+            Poll::Ready(entry) => entry.expect("failed to parse local file header"),
+            Poll::Pending => continue,
+        };
+        let entry = match entry {
+            Some(entry) => entry,
+            None => break,
+        };
+
+        let mut entry_reader = zip_reader.entry_data_reader().expect("failed to open entry data reader");
+        let mut data = Vec::new();
+
+        loop {
+            let mut buffer = [0; 64];
+            let mut buffer = ReadBuf::new(&mut buffer);
+
+            match Pin::new(&mut entry_reader).poll_read(&mut context, &mut buffer) {
+                Poll::Ready(result) => result.expect("failed to read entry data"),
+                Poll::Pending => continue,
+            }
+            if buffer.filled().is_empty() {
+                break;
+            }
+
+            data.extend_from_slice(buffer.filled());
+        }
+
+        entry_reader.verify_crc().expect("CRC32 mismatch");
+        extracted.push((entry.filename().to_owned(), data));
+    }
+
+    assert!(zip_reader.finished());
+    assert!(matches!(zip_reader.poll_next_entry(&mut context), Poll::Ready(Ok(None))));
+    assert!(matches!(zip_reader.entry_data_reader(), Err(crate::error::ZipError::NoCurrentEntry)));
+
+    let expected = [
+        (String::from("stored.txt"), b"Lorem ipsum dolor sit amet".to_vec()),
+        (String::from("empty/"), Vec::new()),
+        (String::from("streamed.txt"), vec![b'a'; 5000]),
+        (String::from("deflated.txt"), b"consectetur adipiscing elit".to_vec()),
+    ];
+    assert_eq!(expected.to_vec(), extracted);
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn auto_compression() {
+    use crate::error::ZipError;
+    use crate::read::mem;
+    use crate::write::DEFAULT_AUTO_COMPRESSION_THRESHOLD;
+
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let random: Vec<u8> = (0..200_000)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 56) as u8
+        })
+        .collect();
+    let text = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(2000);
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let entries: [(&str, &[u8], f32); 5] = [
+        ("random.bin", &random, DEFAULT_AUTO_COMPRESSION_THRESHOLD),
+        ("text.txt", &text, DEFAULT_AUTO_COMPRESSION_THRESHOLD),
+        ("short.txt", &text[..1000], DEFAULT_AUTO_COMPRESSION_THRESHOLD),
+        ("never.txt", &text, 0.0),
+        ("empty.txt", &[], DEFAULT_AUTO_COMPRESSION_THRESHOLD),
+    ];
+
+    for (filename, data, threshold) in entries {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Deflate).auto_compression(threshold);
+        zip_writer.write_entry_whole(open_opts, data).await.expect("failed to write entry");
+    }
+
+    // The method can't be chosen for streamed entries, as none of their data is known upfront.
+    let open_opts = ZipEntryBuilder::new(String::from("streamed.txt"), Compression::Deflate).auto_compression(0.95);
+    let result = zip_writer.write_entry_stream(open_opts).await.map(|_| ());
+    assert!(matches!(result, Err(ZipError::FeatureNotSupported(_))));
+
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let compressions: Vec<_> = zip_reader.entries().iter().map(|entry| entry.compression()).collect();
+    let (stored, deflate) = (Compression::Stored, Compression::Deflate);
+    assert_eq!([stored, deflate, deflate, stored, stored].to_vec(), compressions);
+
+    assert_eq!(random.len() as u64, zip_reader.entries()[0].compressed_size());
+    assert!(zip_reader.entries()[1].compressed_size() < text.len() as u64 / 10);
+
+    for (index, (_, data, _)) in entries.iter().enumerate() {
+        let read = zip_reader.entry_reader(index).await.unwrap().read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(*data, &read[..]);
+    }
+}
+
+#[tokio::test]
+async fn entry_alignment() {
+    use crate::error::ZipError;
+    use crate::read::seek;
+    use tokio::io::AsyncWriteExt;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    // Filenames (and an extra field) of differing lengths, so each entry requires a different amount of padding.
+    let filenames = ["a", "lib/arm64-v8a/libfoo.so", "res/raw/bar.bin", "baz"];
+    for filename in &filenames[..3] {
+        let open_opts = ZipEntryBuilder::new(filename.to_string(), Compression::Stored).alignment(4096);
+        zip_writer.write_entry_whole(open_opts, filename.as_bytes()).await.expect("failed to write entry");
+    }
+
+    let open_opts = ZipEntryBuilder::new(filenames[3].to_string(), Compression::Stored);
+    let open_opts = open_opts.extra_field(0xCAFE, vec![1, 2, 3]).alignment(4096);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+    entry_writer.write_all(filenames[3].as_bytes()).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+
+    let open_opts = ZipEntryBuilder::new(String::from("overflow"), Compression::Stored).alignment(65535);
+    assert!(matches!(zip_writer.write_entry_whole(open_opts, b"").await, Err(ZipError::ExtraFieldTooLong(_))));
+
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = input_stream.into_inner();
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(&archive)).await.expect("failed to open reader");
+
+    for (index, filename) in filenames.iter().enumerate() {
+        let lfh_offset = zip_reader.entries[index].1.file_offset.unwrap() as usize;
+        let name_length = u16::from_le_bytes([archive[lfh_offset + 26], archive[lfh_offset + 27]]) as usize;
+        let extra_length = u16::from_le_bytes([archive[lfh_offset + 28], archive[lfh_offset + 29]]) as usize;
+        let data_offset = lfh_offset + 30 + name_length + extra_length;
+
+        assert_eq!(0, data_offset % 4096);
+        assert_eq!(filename.as_bytes(), &archive[data_offset..data_offset + filename.len()]);
+
+        // The padding record is appended after any of the entry's own, and is never copied into the central directory.
+        let padding = &archive[data_offset - extra_length..data_offset];
+        let padding = if index == 3 { &padding[7..] } else { padding };
+        assert_eq!([0x35, 0xD9], padding[..2]);
+        assert_eq!(4096u16.to_le_bytes(), padding[4..6]);
+        assert_eq!(usize::from(index == 3) * 7, zip_reader.entries()[index].extra_field().len());
+
+        let data = zip_reader.entry_reader(index).await.unwrap().read_to_end_crc().await.unwrap();
+        assert_eq!(filename.as_bytes(), &data[..]);
+    }
+}
+
+#[cfg(not(feature = "zstd"))]
+#[tokio::test]
+async fn compression_feature_not_enabled() {
+    use crate::error::ZipError;
+    use crate::read::mem;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    for (filename, data) in [("foo.zst", b"foo"), ("bar.txt", b"bar")] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, data).await.expect("failed to write entry");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    let mut archive = input_stream.into_inner();
+
+    // Declare the first entry as zstd compressed within both its local file header and central directory record.
+    let signature = crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER.to_le_bytes();
+    let cd_offset = archive.windows(4).position(|window| window == signature).unwrap();
+    archive[8..10].copy_from_slice(&93u16.to_le_bytes());
+    archive[cd_offset + 10..cd_offset + 12].copy_from_slice(&93u16.to_le_bytes());
+
+    // Listing succeeds, and only reading the entry itself fails.
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    assert_eq!(2, zip_reader.entries().len());
+    assert_eq!(Compression::Unknown(93), zip_reader.entries()[0].compression());
+    assert_eq!(Some("zstd"), zip_reader.entries()[0].compression().required_feature());
+    assert_eq!(None, zip_reader.entries()[1].compression().required_feature());
+
+    let result = zip_reader.entry_reader(0).await;
+    assert!(matches!(
+        result,
+        Err(ZipError::FeatureNotEnabled { entry, compression: 93, feature: "zstd" }) if entry == "foo.zst"
+    ));
+
+    let data = zip_reader.entry_reader(1).await.unwrap().read_to_end_crc().await.unwrap();
+    assert_eq!(b"bar", &data[..]);
+}
+
+#[cfg(all(feature = "deflate", feature = "zstd"))]
+#[tokio::test]
+async fn merge_archives() {
+    use crate::error::ZipError;
+    use crate::read::seek;
+    use crate::write::merge::{merge_archives, MergePolicy, MergeSource};
+    use tokio::io::AsyncWriteExt;
+
+    // 2022-10-01 12:30:20, as a date which survives conversion to and from the MS-DOS format unchanged.
+    let date = crate::spec::date::zip_date_to_chrono(0x5541, 0x63CA);
+
+    let mut first = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut first);
+    let entries = [("a.txt", Compression::Deflate), ("shared.txt", Compression::Stored), ("z.bin", Compression::Zstd)];
+    for (filename, compression) in entries {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), compression)
+            .last_modification_date(date)
+            .external_file_attribute(0o100640 << 16)
+            .comment(format!("first/{}", filename));
+        zip_writer.write_entry_whole(open_opts, format!("first {}", filename).as_bytes()).await.unwrap();
+    }
+    zip_writer.close().await.unwrap();
+
+    // The second ZIP file's entries use data descriptors, which are preserved when copied.
+    let mut second = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut second);
+    for (filename, compression) in [("shared.txt", Compression::Deflate), ("b.txt", Compression::Stored)] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), compression).last_modification_date(date);
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.unwrap();
+        entry_writer.write_all(format!("second {}", filename).as_bytes()).await.unwrap();
+        entry_writer.close().await.unwrap();
+    }
+    zip_writer.close().await.unwrap();
+
+    let (first, second) = (first.into_inner(), second.into_inner());
+    let sources = |prefixes: [&str; 2]| {
+        vec![
+            MergeSource::new(Cursor::new(first.clone())).prefix(prefixes[0]),
+            MergeSource::new(Cursor::new(second.clone())).prefix(prefixes[1]),
+        ]
+    };
+
+    let cases = [
+        (MergePolicy::KeepFirst, ["", ""], ["a.txt", "shared.txt", "z.bin", "b.txt"].to_vec()),
+        (MergePolicy::KeepLast, ["", ""], ["a.txt", "z.bin", "shared.txt", "b.txt"].to_vec()),
+        (MergePolicy::Error, ["1/", "2/"], ["1/a.txt", "1/shared.txt", "1/z.bin", "2/shared.txt", "2/b.txt"].to_vec()),
+    ];
+    let sources_of = [
+        ["first", "first", "first", "second"].to_vec(),
+        ["first", "first", "second", "second"].to_vec(),
+        ["first", "first", "first", "second", "second"].to_vec(),
+    ];
+
+    for ((policy, prefixes, filenames), sources_of) in cases.into_iter().zip(sources_of) {
+        let mut output = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut output);
+        let written = merge_archives(&mut zip_writer, sources(prefixes), policy).await.expect("failed to merge");
+        assert_eq!(filenames.len(), written.len());
+        zip_writer.close().await.unwrap();
+
+        let output = output.into_inner();
+        let mut zip_reader = seek::ZipFileReader::new(Cursor::new(&output)).await.expect("failed to open reader");
+        let listed: Vec<_> = zip_reader.entries().iter().map(|entry| entry.filename().to_owned()).collect();
+        assert_eq!(filenames, listed);
+
+        for (index, source) in sources_of.into_iter().enumerate() {
+            let entry = zip_reader.entries()[index].clone();
+            let filename = entry.filename().rsplit('/').next().unwrap();
+
+            let data = zip_reader.entry_reader(index).await.unwrap().read_to_string_crc().await.unwrap();
+            assert_eq!(format!("{} {}", source, filename), data);
+            assert_eq!(date, *entry.last_modification_date());
+
+            if source == "first" {
+                assert_eq!(format!("first/{}", filename), entry.comment());
+                assert_eq!(0o100640 << 16, entry.external_file_attribute());
+            }
+        }
+
+        assert!(zip_reader.verify(false).await.unwrap().is_valid());
+    }
+
+    let result = merge_archives(&mut ZipFileWriter::new(Vec::new()), sources(["", ""]), MergePolicy::Error).await;
+    assert!(matches!(result, Err(ZipError::DuplicateFilename(filename)) if filename == "shared.txt"));
+}
+
+#[cfg(all(feature = "deflate", feature = "zstd"))]
+#[tokio::test]
+async fn transcode_archive() {
+    use crate::entry::CompressionLevel;
+    use crate::read::mem;
+    use crate::write::{transcode, TranscodeAction};
+    use std::time::{Duration, SystemTime};
+    use tokio::io::AsyncWriteExt;
+
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_666_544_103);
+    let mut source = Vec::new();
+    let mut zip_writer = ZipFileWriter::new(&mut source);
+
+    for (filename, compression) in [("kept.txt", Compression::Deflate), ("dropped.txt", Compression::Stored)] {
+        let open_opts = ZipEntryBuilder::new(filename, compression)
+            .modified(modified)
+            .external_file_attribute(0o100640 << 16)
+            .comment(format!("{} comment", filename));
+        zip_writer.write_entry_whole(open_opts, format!("{} data", filename).as_bytes()).await.unwrap();
+    }
+
+    // Streamed entries are followed by data descriptors, so a kept entry's end is only found by decompressing it.
+    for filename in ["streamed.txt", "recompressed.txt"] {
+        let open_opts = ZipEntryBuilder::new(filename, Compression::Deflate).comment(format!("{} comment", filename));
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.unwrap();
+        entry_writer.write_all(format!("{} data", filename).repeat(100).as_bytes()).await.unwrap();
+        entry_writer.close().await.unwrap();
+    }
+
+    zip_writer.comment(String::from("archive comment")).expect("failed to set comment");
+    zip_writer.close().await.unwrap();
+
+    let mut output = Vec::new();
+    let written = transcode(&source[..], &mut output, |entry| match entry.filename() {
+        "dropped.txt" => TranscodeAction::Drop,
+        "recompressed.txt" => TranscodeAction::Recompress(Compression::Zstd, CompressionLevel::Best),
+        _ => TranscodeAction::Keep,
+    })
+    .await
+    .expect("failed to transcode archive");
+    assert_eq!(3, written.len());
+
+    let mut zip_reader = mem::ZipFileReader::new(&output).await.expect("failed to open reader");
+    assert_eq!(Some("archive comment"), zip_reader.comment());
+    assert!(zip_reader.verify(false).await.expect("failed to verify archive").is_valid());
+
+    let filenames = ["kept.txt", "streamed.txt", "recompressed.txt"];
+    let compressions = [Compression::Deflate, Compression::Deflate, Compression::Zstd];
+    for (index, (filename, compression)) in filenames.into_iter().zip(compressions).enumerate() {
+        let entry = zip_reader.entries()[index].clone();
+        assert_eq!((filename, compression), (entry.filename(), entry.compression()));
+        assert_eq!(format!("{} comment", filename), entry.comment());
+
+        let data = zip_reader.entry_reader(index).await.unwrap().read_to_string_crc().await.unwrap();
+        match index {
+            0 => assert_eq!("kept.txt data", data),
+            _ => assert_eq!(format!("{} data", filename).repeat(100), data),
+        }
+    }
+
+    // Metadata only held by the source's central directory is carried over, as are the local extra fields.
+    assert_eq!(0o100640 << 16, zip_reader.entries()[0].external_file_attribute());
+    assert_eq!(Some(chrono::DateTime::<chrono::Utc>::from(modified)), zip_reader.entries()[0].modified());
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn transcode_bounded_memory() {
+    use crate::entry::CompressionLevel;
+    use crate::write::{transcode, TranscodeAction};
+
+    const KEPT_SIZE: u64 = 32 * 1024 * 1024;
+    const RECOMPRESSED_SIZE: u64 = 16 * 1024 * 1024;
+
+    // The source is written on the fly, so neither it nor its entries' data are ever held in memory whole.
+    let (mut source_writer, source) = tokio::io::duplex(64 * 1024);
+    let task = tokio::spawn(async move {
+        let mut zip_writer = ZipFileWriter::new(&mut source_writer);
+
+        for (filename, size) in [("kept.bin", KEPT_SIZE), ("recompressed.bin", RECOMPRESSED_SIZE)] {
+            let open_opts = ZipEntryBuilder::new(filename, Compression::Deflate);
+            let data = tokio::io::AsyncReadExt::take(tokio::io::repeat(b'a'), size);
+            zip_writer.write_entry_copy(open_opts, data, 64 * 1024).await?;
+        }
+
+        zip_writer.close().await
+    });
+
+    alloc::reset_peak();
+    let before = alloc::allocated();
+
+    let written = transcode(source, tokio::io::sink(), |entry| match entry.filename() {
+        "recompressed.bin" => TranscodeAction::Recompress(Compression::Stored, CompressionLevel::Default),
+        _ => TranscodeAction::Keep,
+    })
+    .await
+    .expect("failed to transcode archive");
+
+    assert!((alloc::peak() - before) < 4 * 1024 * 1024);
+    task.await.unwrap().expect("failed to write source");
+
+    assert_eq!(KEPT_SIZE, written[0].uncompressed_size());
+    assert_eq!(RECOMPRESSED_SIZE, written[1].uncompressed_size());
+    assert_eq!(RECOMPRESSED_SIZE, written[1].compressed_size());
+}
+
+#[tokio::test]
+async fn edit_in_place() {
+    use crate::error::ZipError;
+    use crate::read::seek;
+    use crate::read::FailureKind;
+    use crate::write::edit::ZipFileEditor;
+    use tokio::fs::OpenOptions;
+    use tokio::io::AsyncWriteExt;
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    for (filename, data) in [("a.txt", "first"), ("b.txt", "second"), ("c.txt", "third"), ("d.txt", "fourth")] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+    }
+
+    // A streamed entry is followed by a data descriptor, which must move along with its data when compacting.
+    let open_opts = ZipEntryBuilder::new(String::from("streamed-entry.txt"), Compression::Stored);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to write entry");
+    entry_writer.write_all(b"fifth").await.expect("failed to write data");
+    entry_writer.close().await.expect("failed to close entry");
+
+    zip_writer.comment(String::from("original")).expect("failed to set comment");
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = archive.into_inner();
+
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let path = dir.path().join("edit.zip");
+    let mut lengths = Vec::new();
+
+    for (compact, patch) in [(false, false), (false, true), (true, false), (true, true)] {
+        tokio::fs::write(&path, &archive).await.expect("failed to write ZIP file");
+        let file = OpenOptions::new().read(true).write(true).open(&path).await.expect("failed to open ZIP file");
+
+        let mut editor = ZipFileEditor::open(file).await.expect("failed to open editor");
+        editor.compact(compact);
+        editor.patch_local_headers(patch);
+
+        editor.remove_entry(1).expect("failed to remove entry");
+        assert!(matches!(editor.remove_entry(1), Err(ZipError::EntryIndexOutOfBounds)));
+        assert!(matches!(editor.rename_entry(0, "c.txt"), Err(ZipError::DuplicateFilename(_))));
+
+        // A removed entry's filename is free to be reused. Only the first and last renames can be patched in place.
+        editor.rename_entry(0, "b.txt").expect("failed to rename entry");
+        editor.rename_entry(2, "renamed/c.txt").expect("failed to rename entry");
+        editor.rename_entry(4, "e.txt").expect("failed to rename entry");
+        editor.set_comment(String::from("edited"));
+        assert!(editor.is_removed(1));
+
+        let file = editor.commit().await.expect("failed to commit edits");
+        lengths.push(file.metadata().await.expect("failed to read metadata").len());
+
+        let mut zip_reader = seek::ZipFileReader::new(file).await.expect("failed to open reader");
+        assert_eq!(Some("edited"), zip_reader.comment());
+
+        let expected = [("b.txt", "first"), ("renamed/c.txt", "third"), ("d.txt", "fourth"), ("e.txt", "fifth")];
+        assert_eq!(expected.len(), zip_reader.entries().len());
+
+        for (index, (filename, data)) in expected.into_iter().enumerate() {
+            assert_eq!(filename, zip_reader.entries()[index].filename());
+            let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+            assert_eq!(data, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        }
+
+        // Only the local file headers which weren't patched disagree with the central directory.
+        let report = zip_reader.verify(false).await.expect("failed to verify");
+        let failures: Vec<usize> = report.failures().iter().map(|failure| failure.index()).collect();
+        assert_eq!(if patch { vec![1] } else { vec![0, 1, 3] }, failures);
+        let filename_mismatch = |kind: &FailureKind| matches!(kind, FailureKind::LocalHeaderMismatch("filename"));
+        assert!(report.failures().iter().all(|failure| filename_mismatch(failure.kind())));
+        assert_eq!(report.declared_entries(), report.actual_entries());
+    }
+
+    // Compacting reclaims the removed entry's 30-byte local file header, 5-byte filename, and 5 bytes of data.
+    assert_eq!(lengths[0], lengths[1]);
+    assert_eq!(lengths[2], lengths[3]);
+    assert_eq!(lengths[0] - 40, lengths[2]);
+
+    // With only patchable renames, the edited ZIP file verifies in full.
+    tokio::fs::write(&path, &archive).await.expect("failed to write ZIP file");
+    let file = OpenOptions::new().read(true).write(true).open(&path).await.expect("failed to open ZIP file");
+
+    let mut editor = ZipFileEditor::open(file).await.expect("failed to open editor");
+    editor.compact(true);
+    editor.patch_local_headers(true);
+    editor.remove_entry(0).expect("failed to remove entry");
+    editor.rename_entry(4, "e.txt").expect("failed to rename entry");
+
+    let file = editor.commit().await.expect("failed to commit edits");
+    let mut zip_reader = seek::ZipFileReader::new(file).await.expect("failed to open reader");
+    assert!(zip_reader.verify(false).await.expect("failed to verify").is_valid());
+    assert_eq!("b.txt", zip_reader.entries()[0].filename());
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn entry_digest() {
+    use crate::read::mem;
+    use crate::write::EntryDigest;
+    use sha2::{Digest, Sha256};
+    use tokio::io::AsyncWriteExt;
+
+    struct Sha256Digest(Sha256);
+
+    impl EntryDigest for Sha256Digest {
+        fn update(&mut self, data: &[u8]) {
+            self.0.update(data);
+        }
+
+        fn finalize(self: Box<Self>) -> Vec<u8> {
+            self.0.finalize().to_vec()
+        }
+    }
+
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(2048);
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.digest(|| Sha256Digest(Sha256::new()));
+
+    let mut written = Vec::new();
+    for (filename, compression) in [("stored.txt", Compression::Stored), ("deflate.txt", Compression::Deflate)] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), compression);
+        written.push(zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.unwrap());
+    }
+
+    let open_opts = ZipEntryBuilder::new(String::from("stream.txt"), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.unwrap();
+    for chunk in data.as_bytes().chunks(1000) {
+        entry_writer.write_all(chunk).await.unwrap();
+    }
+    written.push(entry_writer.close().await.unwrap());
+
+    let parallel = (0..2).map(|index| {
+        let open_opts = ZipEntryBuilder::new(format!("parallel{}.txt", index), Compression::Deflate);
+        (open_opts, Cursor::new(data.clone().into_bytes()))
+    });
+    written.extend(zip_writer.write_entries_parallel(parallel, 2).await.unwrap());
+
+    zip_writer.close().await.unwrap();
+    let archive = input_stream.into_inner();
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+
+    assert_eq!(5, written.len());
+    for (index, written) in written.iter().enumerate() {
+        let extracted = zip_reader.entry_reader(index).await.unwrap().read_to_end_crc().await.unwrap();
+        assert_eq!(Some(&Sha256::digest(&extracted)[..]), written.digest());
+    }
+
+    // Without a digest being set, none is computed.
+    let mut zip_writer = ZipFileWriter::new(Vec::new());
+    let open_opts = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored);
+    assert_eq!(None, zip_writer.write_entry_whole(open_opts, b"foo").await.unwrap().digest());
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn open_nested() {
+    use crate::error::ZipError;
+    use crate::read::{fs, seek, ReaderOptions};
+
+    let mut inner = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut inner);
+    for filename in ["a.txt", "b.txt"] {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), Compression::Deflate);
+        zip_writer.write_entry_whole(open_opts, filename.repeat(100).as_bytes()).await.unwrap();
+    }
+    zip_writer.close().await.unwrap();
+    let inner = inner.into_inner();
+
+    // A leading entry, so that neither nested ZIP file begins at the start of the outer one.
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let path = dir.path().join("nested.zip");
+    let mut output = tokio::fs::File::create(&path).await.expect("failed to create file");
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+    let entries = [("foo.txt", Compression::Deflate), ("stored.zip", Compression::Stored)];
+    for (filename, compression) in entries.into_iter().chain([("deflate.zip", Compression::Deflate)]) {
+        let open_opts = ZipEntryBuilder::new(String::from(filename), compression);
+        zip_writer.write_entry_whole(open_opts, &inner).await.unwrap();
+    }
+    zip_writer.close().await.unwrap();
+    tokio::io::AsyncWriteExt::flush(&mut output).await.expect("failed to flush file");
+    drop(output);
+
+    let outer = std::fs::read(&path).unwrap();
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(&outer)).await.expect("failed to open reader");
+    let fs_reader = fs::ZipFileReader::new(&path).await.expect("failed to open reader");
+
+    for (index, buffered) in [(1, false), (2, true)] {
+        let mut nested = zip_reader.open_nested(index).await.expect("failed to open nested reader");
+        assert_eq!(buffered, nested.reader.is_buffered());
+        assert_eq!(2, nested.entries().len());
+        assert_eq!("b.txt".repeat(100), nested.entry_reader(1).await.unwrap().read_to_string_crc().await.unwrap());
+        assert!(nested.verify(false).await.unwrap().is_valid());
+
+        let mut nested = fs_reader.open_nested(index).await.expect("failed to open nested reader");
+        assert_eq!(buffered, nested.reader.is_buffered());
+        assert_eq!("a.txt".repeat(100), nested.entry_reader(0).await.unwrap().read_to_string_crc().await.unwrap());
+    }
+
+    let options = ReaderOptions::new().decompress_nested(false);
+    let mut zip_reader = seek::ZipFileReader::with_options(Cursor::new(&outer), options).await.unwrap();
+    assert!(zip_reader.open_nested(1).await.is_ok());
+    let result = zip_reader.open_nested(2).await;
+    assert!(matches!(result, Err(ZipError::NestedEntryCompressed(name)) if name == "deflate.zip"));
+}
+
+#[cfg(all(feature = "tracing", feature = "deflate"))]
+#[tokio::test]
+async fn tracing_instrumentation() {
+    use std::sync::{Arc, Mutex};
+    use tokio::io::AsyncWriteExt;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // Captures the name of each span opened and the message of each event emitted, in order.
+    struct Capture(Arc<Mutex<Vec<String>>>);
+
+    struct Message<'a>(&'a mut Vec<String>);
+
+    impl Visit for Message<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                self.0.push(format!("{:?}", value));
+            }
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            self.0.lock().unwrap().push(span.metadata().name().to_owned());
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut Message(&mut self.0.lock().unwrap()));
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let _guard = tracing::subscriber::set_default(Capture(captured.clone()));
+
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let entry = ZipEntryBuilder::new("whole.txt".to_string(), Compression::Deflate);
+    zip_writer.write_entry_whole(entry, data).await.expect("failed to write entry");
+
+    let entry = ZipEntryBuilder::new("stream.txt".to_string(), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(entry).await.expect("failed to open write entry");
+    entry_writer.write_all(data).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.into_inner();
+
+    let mut zip_reader = crate::read::mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert_eq!(&data[..], &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+
+    let mut zip_reader = crate::read::stream::ZipFileReader::new(Cursor::new(archive.as_slice()));
+    while let Some(entry_reader) = zip_reader.entry_reader().await.expect("failed to open entry reader") {
+        assert_eq!(&data[..], &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+    }
+
+    let captured = captured.lock().unwrap();
+    for expected in [
+        "write_entry",
+        "wrote entry",
+        "closed entry stream writer",
+        "open_archive",
+        "located end of central directory header",
+        "parsed central directory record",
+        "read_entry",
+        "resolved data descriptor",
+        "restored prepend buffer",
+    ] {
+        assert!(captured.iter().any(|name| name == expected), "missing {:?} in {:?}", expected, captured);
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn empty_entries_skip_decoders() {
+    use crate::read::{mem, stream, CompressionReader};
+    use tokio::io::AsyncReadExt;
+
+    for filename in ["empty.txt", "directory/"] {
+        let archive = raw_archive(filename, 8, 0, &[], 0);
+
+        let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+        let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+        assert!(matches!(entry_reader.reader, CompressionReader::Stored(_)));
+        assert!(entry_reader.read_to_end_crc().await.expect("failed to read entry").is_empty());
+
+        let mut zip_reader = stream::ZipFileReader::new(Cursor::new(&archive));
+        let mut entry_reader = zip_reader.entry_reader().await.expect("failed to open entry reader").unwrap();
+        assert!(matches!(entry_reader.reader, CompressionReader::Stored(_)));
+
+        let mut buffer = Vec::new();
+        entry_reader.read_to_end(&mut buffer).await.expect("failed to read entry");
+        assert!(buffer.is_empty());
+        assert!(entry_reader.compare_crc());
+    }
+
+    // A zero-length entry which nonetheless declares a CRC32 value fails cleanly once read.
+    let archive = raw_archive("empty.txt", 8, 0xDEADBEEF, &[], 0);
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let result = entry_reader.read_to_end_crc().await;
+    assert!(matches!(result, Err(crate::error::ZipError::CRC32CheckError { expected: 0xDEADBEEF, found: 0, .. })));
+}
+
+/// A ZIP file holding a long run of zeros (eg. an entry's data), synthesised on demand rather than held in memory.
+#[derive(Default)]
+struct SparseArchive {
+    head: Vec<u8>,
+    zeros: u64,
+    tail: Vec<u8>,
+    position: u64,
+}
+
+impl tokio::io::AsyncRead for SparseArchive {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+        b: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let (head, zeros) = (self.head.len() as u64, self.zeros);
+        let position = self.position;
+
+        let read = if position < head {
+            let length = b.remaining().min((head - position) as usize);
+            b.put_slice(&self.head[position as usize..position as usize + length]);
+            length
+        } else if position < head + zeros {
+            let length = b.remaining().min((head + zeros - position).min(64 * 1024) as usize);
+            b.put_slice(&[0; 64 * 1024][..length]);
+            length
+        } else {
+            let start = ((position - head - zeros) as usize).min(self.tail.len());
+            let length = b.remaining().min(self.tail.len() - start);
+            b.put_slice(&self.tail[start..start + length]);
+            length
+        };
+
+        self.position += read as u64;
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+impl tokio::io::AsyncSeek for SparseArchive {
+    fn start_seek(mut self: std::pin::Pin<&mut Self>, position: std::io::SeekFrom) -> std::io::Result<()> {
+        let length = self.head.len() as u64 + self.zeros + self.tail.len() as u64;
+        self.position = match position {
+            std::io::SeekFrom::Start(offset) => offset,
+            std::io::SeekFrom::End(offset) => (length as i64 + offset) as u64,
+            std::io::SeekFrom::Current(offset) => (self.position as i64 + offset) as u64,
+        };
+        Ok(())
+    }
+
+    fn poll_complete(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<u64>> {
+        std::task::Poll::Ready(Ok(self.position))
+    }
+}
+
+/// A writer which collapses the first long run of zeros written to it, so that a ZIP file holding a large entry of
+/// zeros can be written without being held in memory (and then read back via a [`SparseArchive`]).
+#[derive(Default)]
+struct SparseRecorder {
+    archive: SparseArchive,
+}
+
+impl tokio::io::AsyncWrite for SparseRecorder {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        let archive = &mut self.archive;
+
+        if buf.len() >= 64 * 1024 && archive.tail.is_empty() && buf.iter().all(|byte| *byte == 0) {
+            archive.zeros += buf.len() as u64;
+        } else if archive.zeros == 0 {
+            archive.head.extend_from_slice(buf);
+        } else {
+            archive.tail.extend_from_slice(buf);
+        }
+
+        std::task::Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn zip64_small_entry() {
+    // The same hand-built ZIP64 layout as below, but with a small entry, so that the ZIP64 records (rather than the
+    // size of the data) are what's exercised by the default test run.
+    zip64_sparse_round_trip(4096).await;
+}
+
+// This pushes 5 GiB of data through CRC32, so is left to explicit runs (`cargo test -- --ignored`).
+#[tokio::test]
+#[ignore]
+async fn zip64_sparse_entry() {
+    zip64_sparse_round_trip(5 * 1024 * 1024 * 1024).await;
+}
+
+// Reads back a hand-built archive holding an entry of `size` zeros, with its sizes (and the following entry's offset)
+// only held within ZIP64 extended information, and the central directory located via a ZIP64 record.
+async fn zip64_sparse_round_trip(size: u64) {
+    use crate::read::seek::ZipFileReader;
+    use crate::spec::extra_field::ExtraField;
+    use crate::spec::header::{CentralDirectoryHeader, EndOfCentralDirectoryHeader, GeneralPurposeFlag};
+    use crate::spec::header::{LocalFileHeader, Zip64EndOfCentralDirectoryLocator, Zip64EndOfCentralDirectoryRecord};
+
+    const SATURATED: u32 = u32::MAX;
+
+    // The CRC32 value of the zeroed data, combined from that of each (at most) MiB.
+    let chunk = size.min(1024 * 1024);
+    let mut mib = crc32fast::Hasher::new();
+    mib.update(&vec![0; chunk as usize]);
+    let mut crc = crc32fast::Hasher::new();
+    for _ in 0..size / chunk {
+        crc.combine(&mib);
+    }
+    let crc = crc.finalize();
+
+    let zip64_field = |values: &[u64]| {
+        let mut field = Vec::new();
+        let data = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+        ExtraField::new(0x0001, data).write_to(&mut field);
+        field
+    };
+
+    let lfh = |filename: &str, crc: u32, size: u32, extra_field: &[u8]| LocalFileHeader {
+        version: 45,
+        flags: GeneralPurposeFlag::from(0),
+        compression: 0,
+        mod_time: 0,
+        mod_date: 0x21,
+        crc,
+        compressed_size: size,
+        uncompressed_size: size,
+        file_name_length: filename.len() as u16,
+        extra_field_length: extra_field.len() as u16,
+    };
+
+    let cdh = |lfh: &LocalFileHeader, lh_offset: u32, extra_field: &[u8]| CentralDirectoryHeader {
+        v_made_by: 45,
+        v_needed: lfh.version,
+        flags: lfh.flags,
+        compression: lfh.compression,
+        mod_time: lfh.mod_time,
+        mod_date: lfh.mod_date,
+        crc: lfh.crc,
+        compressed_size: lfh.compressed_size,
+        uncompressed_size: lfh.uncompressed_size,
+        file_name_length: lfh.file_name_length,
+        extra_field_length: extra_field.len() as u16,
+        file_comment_length: 0,
+        disk_start: 0,
+        inter_attr: 0,
+        exter_attr: 0,
+        lh_offset,
+    };
+
+    // The first entry's sizes, and the second entry's offset, are only held within ZIP64 extended information.
+    let big_extra_field = zip64_field(&[size, size]);
+    let big_lfh = lfh("big.bin", crc, SATURATED, &big_extra_field);
+    let mut head = big_lfh.to_bytes();
+    head.extend_from_slice(b"big.bin");
+    head.extend_from_slice(&big_extra_field);
+
+    let small_offset = head.len() as u64 + size;
+    let small_lfh = lfh("small.txt", compute_crc(b"foo"), 3, &[]);
+    let mut tail = small_lfh.to_bytes();
+    tail.extend_from_slice(b"small.txt");
+    tail.extend_from_slice(b"foo");
+
+    let cd_offset = small_offset + tail.len() as u64;
+    tail.extend_from_slice(&cdh(&big_lfh, 0, &big_extra_field).to_bytes());
+    tail.extend_from_slice(b"big.bin");
+    tail.extend_from_slice(&big_extra_field);
+
+    let small_extra_field = zip64_field(&[small_offset]);
+    tail.extend_from_slice(&cdh(&small_lfh, SATURATED, &small_extra_field).to_bytes());
+    tail.extend_from_slice(b"small.txt");
+    tail.extend_from_slice(&small_extra_field);
+
+    let record_offset = small_offset + tail.len() as u64;
+    let record = Zip64EndOfCentralDirectoryRecord {
+        v_made_by: 45,
+        v_needed: 45,
+        disk_num: 0,
+        start_cent_dir_disk: 0,
+        num_of_entries_disk: 2,
+        num_of_entries: 2,
+        size_cent_dir: record_offset - cd_offset,
+        cent_dir_offset: cd_offset,
+    };
+    tail.extend_from_slice(&record.to_bytes());
+    let locator =
+        Zip64EndOfCentralDirectoryLocator { start_eocdr_disk: 0, eocdr_offset: record_offset, total_disks: 1 };
+    tail.extend_from_slice(&locator.to_bytes());
+
+    // Each field of the EOCDH which the ZIP64 record holds is saturated, as Info-ZIP writes it.
+    let eocdh = EndOfCentralDirectoryHeader {
+        disk_num: 0,
+        start_cent_dir_disk: 0,
+        num_of_entries_disk: u16::MAX,
+        num_of_entries: u16::MAX,
+        size_cent_dir: SATURATED,
+        cent_dir_offset: SATURATED,
+        file_comm_length: 0,
+    };
+    tail.extend_from_slice(&eocdh.to_bytes());
+
+    let archive = SparseArchive { head, zeros: size, tail, position: 0 };
+    let mut zip_reader = ZipFileReader::new(archive).await.expect("failed to open reader");
+
+    let metadata = zip_reader.metadata();
+    assert!(metadata.has_zip64_locator() && metadata.requires_zip64());
+    assert_eq!((2, cd_offset), (metadata.total_entries(), metadata.central_directory_offset()));
+    assert_eq!(2, zip_reader.entries().len());
+
+    let entry = &zip_reader.entries()[0];
+    assert_eq!((size, size), (entry.compressed_size(), entry.uncompressed_size()));
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let copied = entry_reader.copy_to_end_crc(&mut tokio::io::sink(), 1024 * 1024).await.expect("failed to read entry");
+    assert_eq!(size, copied);
+
+    let entry_reader = zip_reader.entry_reader(1).await.expect("failed to open entry reader");
+    assert_eq!(b"foo", &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+}
+
+// This writes (and reads back) 5 GiB of data, so is left to explicit runs (`cargo test -- --ignored`). The decisions
+// it exercises are covered at their thresholds by `zip64_write_thresholds` below.
+#[tokio::test]
+#[ignore]
+async fn zip64_write_large_entry() {
+    use crate::read::seek::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    const SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+    // The stream-written entry's sizes only fit within a ZIP64 data descriptor and central directory record, and the
+    // following entry (along with the central directory itself) begins beyond the reach of a 32-bit offset.
+    let mut recorder = SparseRecorder::default();
+    let mut zip_writer = ZipFileWriter::new(&mut recorder);
+
+    let open_opts = ZipEntryBuilder::new(String::from("big.bin"), Compression::Stored);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry writer");
+    let chunk = vec![0; 1024 * 1024];
+    for _ in 0..SIZE / chunk.len() as u64 {
+        entry_writer.write_all(&chunk).await.expect("failed to write entry");
+    }
+    let big = entry_writer.close().await.expect("failed to close entry writer");
+    assert_eq!((SIZE, SIZE), (big.compressed_size(), big.uncompressed_size()));
+
+    let open_opts = ZipEntryBuilder::new(String::from("small.txt"), Compression::Stored);
+    let small = zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    assert!(small.header_offset() > u64::from(u32::MAX));
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = std::mem::take(&mut recorder.archive);
+    assert_eq!(SIZE, archive.zeros);
+    let mut zip_reader = ZipFileReader::new(archive).await.expect("failed to open reader");
+
+    let metadata = zip_reader.metadata();
+    assert!(metadata.has_zip64_locator() && metadata.requires_zip64());
+    assert_eq!(2, metadata.total_entries());
+
+    let entry = &zip_reader.entries()[0];
+    assert_eq!((SIZE, SIZE, big.crc32()), (entry.compressed_size(), entry.uncompressed_size(), entry.crc32()));
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let copied = entry_reader.copy_to_end_crc(&mut tokio::io::sink(), 1024 * 1024).await.expect("failed to read entry");
+    assert_eq!(SIZE, copied);
+
+    let entry_reader = zip_reader.entry_reader(1).await.expect("failed to open entry reader");
+    assert_eq!(b"foo", &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+}
+
+#[test]
+fn zip64_write_thresholds() {
+    use crate::write::{central_zip64_split, local_zip64_split, DataDescriptorStyle};
+
+    const MAX: u64 = u32::MAX as u64;
+    let entry = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored).build();
+    let forced = ZipEntryBuilder::new(String::from("foo.bar"), Compression::Stored).force_zip64(true).build();
+
+    // The local file header defers both sizes once either reaches u32::MAX (a saturated field), or if forced.
+    assert_eq!(([3, MAX as u32 - 1], Vec::new()), local_zip64_split(&entry, [3, MAX - 1]));
+    assert_eq!(([u32::MAX; 2], vec![3, MAX]), local_zip64_split(&entry, [3, MAX]));
+    assert_eq!(([u32::MAX; 2], vec![MAX + 1, 3]), local_zip64_split(&entry, [MAX + 1, 3]));
+    assert_eq!(([u32::MAX; 2], vec![3, 3]), local_zip64_split(&forced, [3, 3]));
+
+    // The central directory record only defers those values (including the header offset) which can't be held.
+    assert_eq!(([3, 3, MAX as u32 - 1], Vec::new()), central_zip64_split([3, 3, MAX - 1]));
+    assert_eq!(([3, u32::MAX, 3], vec![MAX]), central_zip64_split([3, MAX, 3]));
+    assert_eq!(([u32::MAX, 3, u32::MAX], vec![MAX + 1, 5 * MAX]), central_zip64_split([MAX + 1, 3, 5 * MAX]));
+
+    // A ZIP64 data descriptor holds 8-byte sizes rather than truncating them.
+    let descriptor = DataDescriptorStyle::Unsigned.descriptor(0, MAX + 1, MAX + 2, true);
+    assert_eq!(&[&0u32.to_le_bytes()[..], &(MAX + 1).to_le_bytes(), &(MAX + 2).to_le_bytes()].concat(), &descriptor);
+    assert_eq!(12, DataDescriptorStyle::Unsigned.descriptor(0, 3, 3, false).len());
+}
+
+#[tokio::test]
+async fn zip64_forced_entries() {
+    use crate::read::seek::ZipFileReader;
+    use crate::spec::signature::DATA_DESCRIPTOR;
+    use tokio::io::AsyncWriteExt;
+
+    let data = b"Lorem ipsum dolor sit amet";
+
+    for patch in [false, true] {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+        zip_writer.patch_local_headers(patch);
+
+        let open_opts = ZipEntryBuilder::new(String::from("stream.txt"), Compression::Stored).force_zip64(true);
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry writer");
+        entry_writer.write_all(data).await.expect("failed to write entry");
+        entry_writer.close().await.expect("failed to close entry writer");
+
+        let open_opts = ZipEntryBuilder::new(String::from("whole.txt"), Compression::Stored).force_zip64(true);
+        zip_writer.write_entry_whole(open_opts, data).await.expect("failed to write entry");
+        zip_writer.close().await.expect("failed to close writer");
+
+        // The local file header's sizes are saturated, and its ZIP64 record directly follows the filename. The sizes
+        // are only patched into the record if there's no data descriptor, which must then use the ZIP64 layout.
+        let archive = input_stream.into_inner();
+        assert_eq!(45, u16::from_le_bytes([archive[4], archive[5]]));
+        assert_eq!(&[0xFF; 8], &archive[18..26]);
+        assert_eq!(&[1, 0, 16, 0], &archive[40..44]);
+
+        let size = if patch { data.len() as u64 } else { 0 };
+        assert_eq!(&[size.to_le_bytes(), size.to_le_bytes()].concat(), &archive[44..60]);
+
+        if !patch {
+            let mut descriptor = DATA_DESCRIPTOR.to_le_bytes().to_vec();
+            descriptor.extend_from_slice(&compute_crc(data).to_le_bytes());
+            descriptor.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            descriptor.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            assert_eq!(&descriptor[..], &archive[60 + data.len()..84 + data.len()]);
+        }
+
+        let mut zip_reader = ZipFileReader::new(Cursor::new(archive)).await.expect("failed to open reader");
+        for index in 0..2 {
+            let entry = &zip_reader.entries()[index];
+            assert_eq!((data.len() as u64, data.len() as u64), (entry.compressed_size(), entry.uncompressed_size()));
+
+            let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+            assert_eq!(data, &entry_reader.read_to_end_crc().await.expect("failed to read entry")[..]);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Property-based round-trip tests: arbitrary archives are written with [`ZipFileWriter`] and re-read with each reader.
+
+use crate::entry::builder::ZipEntryBuilder;
+use crate::entry::ZipEntry;
+use crate::read::{fs, mem, seek, stream};
+use crate::spec::compression::Compression;
+use crate::write::ZipFileWriter;
+
+use proptest::prelude::*;
+use std::io::Cursor;
+use tokio::io::AsyncWriteExt;
+
+// Each case writes up to 50 entries of up to 64 KiB through every compression method, so the count is kept low.
+const CASES: u32 = 24;
+
+#[derive(Debug, Clone)]
+struct EntrySpec {
+    filename: String,
+    data: Vec<u8>,
+    compression: Compression,
+    stream: bool,
+    comment: String,
+}
+
+#[derive(Debug, Clone)]
+struct ArchiveSpec {
+    entries: Vec<EntrySpec>,
+    comment: Option<String>,
+}
+
+fn compression() -> impl Strategy<Value = Compression> {
+    #[allow(unused_mut)]
+    let mut methods = vec![Compression::Stored];
+
+    #[cfg(feature = "deflate")]
+    methods.push(Compression::Deflate);
+    #[cfg(feature = "bzip2")]
+    methods.push(Compression::Bz);
+    #[cfg(feature = "lzma")]
+    methods.push(Compression::Lzma);
+    #[cfg(feature = "zstd")]
+    methods.push(Compression::Zstd);
+    #[cfg(feature = "xz")]
+    methods.push(Compression::Xz);
+
+    proptest::sample::select(methods)
+}
+
+// Both incompressible and highly compressible data, as the latter exercises the compressors' buffering differently.
+fn data() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        proptest::collection::vec(any::<u8>(), 0..=64 * 1024),
+        (any::<u8>(), 0..=64 * 1024usize).prop_map(|(byte, length)| vec![byte; length]),
+    ]
+}
+
+// Comments (and filenames) mix ASCII, '%', and multibyte UTF-8, so that both settings of the UTF-8 flag are covered.
+fn comment() -> impl Strategy<Value = String> {
+    prop_oneof![Just(String::new()), "[a-z %é日本]{1,16}"]
+}
+
+fn entry() -> impl Strategy<Value = EntrySpec> {
+    let filename = "[a-zA-Z0-9 %._é日本-]{0,8}(/[a-zA-Z0-9 %._é日本-]{1,8}){0,2}";
+
+    (filename, data(), compression(), any::<bool>(), comment()).prop_map(
+        |(filename, data, compression, stream, comment)| EntrySpec { filename, data, compression, stream, comment },
+    )
+}
+
+fn archive() -> impl Strategy<Value = ArchiveSpec> {
+    let entries = proptest::collection::vec(entry(), 0..=50).prop_map(|mut entries| {
+        // Filenames are suffixed with their index so they're unique and never name a directory.
+        for (index, entry) in entries.iter_mut().enumerate() {
+            entry.filename = format!("{}{}", entry.filename, index);
+        }
+        entries
+    });
+
+    (entries, proptest::option::of(comment())).prop_map(|(entries, comment)| ArchiveSpec { entries, comment })
+}
+
+async fn write(spec: &ArchiveSpec) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+
+    for entry in &spec.entries {
+        let builder = ZipEntryBuilder::new(entry.filename.clone(), entry.compression).comment(entry.comment.clone());
+
+        if entry.stream {
+            let mut entry_writer = zip_writer.write_entry_stream(builder).await.expect("failed to open entry");
+            entry_writer.write_all(&entry.data).await.expect("failed to write entry");
+            entry_writer.close().await.expect("failed to close entry");
+        } else {
+            zip_writer.write_entry_whole(builder, &entry.data).await.expect("failed to write entry");
+        }
+    }
+
+    if let Some(comment) = &spec.comment {
+        zip_writer.comment(comment.clone()).expect("failed to set comment");
+    }
+
+    zip_writer.close().await.expect("failed to close writer");
+    output.into_inner()
+}
+
+// Compare an entry listed from the central directory against the one which was written.
+fn assert_entry(reader: &str, expected: &EntrySpec, entry: &ZipEntry) {
+    let context = format!("{}: {}", reader, expected.filename);
+
+    assert_eq!(expected.filename, entry.filename(), "{}", context);
+    assert_eq!(expected.compression, entry.compression(), "{}", context);
+    assert_eq!(expected.comment, entry.comment(), "{}", context);
+    assert_eq!(crc32fast::hash(&expected.data), entry.crc32(), "{}", context);
+    assert_eq!(expected.data.len() as u64, entry.uncompressed_size(), "{}", context);
+
+    let utf8 = !expected.filename.is_ascii() || !expected.comment.is_ascii();
+    assert_eq!(utf8, entry.general_purpose_flags().filename_unicode(), "{}", context);
+}
+
+fn assert_comment(reader: &str, expected: &ArchiveSpec, comment: Option<&str>) {
+    let expected = expected.comment.as_deref().unwrap_or_default();
+    assert_eq!(expected, comment.unwrap_or_default(), "{}", reader);
+}
+
+async fn read_mem(spec: &ArchiveSpec, archive: &[u8]) {
+    let mut zip_reader = mem::ZipFileReader::new(archive).await.expect("failed to open mem reader");
+    assert_eq!(spec.entries.len(), zip_reader.entries().len());
+    assert_comment("mem", spec, zip_reader.comment());
+
+    for (index, expected) in spec.entries.iter().enumerate() {
+        assert_entry("mem", expected, zip_reader.entries()[index]);
+
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        assert_eq!(expected.data, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    }
+}
+
+async fn read_seek(spec: &ArchiveSpec, archive: &[u8]) {
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(archive)).await.expect("failed to open seek reader");
+    assert_eq!(spec.entries.len(), zip_reader.entries().len());
+    assert_comment("seek", spec, zip_reader.comment());
+
+    for (index, expected) in spec.entries.iter().enumerate() {
+        assert_entry("seek", expected, zip_reader.entries()[index]);
+
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        assert_eq!(expected.data, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    }
+}
+
+async fn read_fs(spec: &ArchiveSpec, archive: &[u8]) {
+    let dir = tempfile::tempdir().expect("failed to create directory");
+    let path = dir.path().join("roundtrip.zip");
+    tokio::fs::write(&path, archive).await.expect("failed to write archive");
+
+    let zip_reader = fs::ZipFileReader::new(&path).await.expect("failed to open fs reader");
+    assert_eq!(spec.entries.len(), zip_reader.entries().len());
+    assert_comment("fs", spec, zip_reader.comment());
+
+    for (index, expected) in spec.entries.iter().enumerate() {
+        assert_entry("fs", expected, zip_reader.entries()[index]);
+
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+        assert_eq!(expected.data, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    }
+}
+
+// The stream reader only sees each entry's local file header, so sizes and CRCs deferred to a data descriptor aren't
+// known upfront and are instead checked by reading the entry.
+async fn read_stream(spec: &ArchiveSpec, archive: &[u8]) {
+    let mut zip_reader = stream::ZipFileReader::new(Cursor::new(archive));
+    let mut expected = spec.entries.iter();
+
+    while let Some(entry_reader) = zip_reader.entry_reader().await.expect("failed to open entry reader") {
+        let expected = expected.next().expect("more entries read than were written");
+        let context = format!("stream: {}", expected.filename);
+
+        assert_eq!(expected.filename, entry_reader.entry().filename(), "{}", context);
+        assert_eq!(expected.compression, entry_reader.entry().compression(), "{}", context);
+        assert_eq!(expected.data, entry_reader.read_to_end_crc().await.expect("failed to read entry"), "{}", context);
+    }
+
+    assert!(expected.next().is_none(), "fewer entries read than were written");
+    assert!(zip_reader.finished());
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+    #[test]
+    fn roundtrip(spec in archive()) {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+
+        runtime.block_on(async {
+            let archive = write(&spec).await;
+
+            read_mem(&spec, &archive).await;
+            read_seek(&spec, &archive).await;
+            read_fs(&spec, &archive).await;
+            read_stream(&spec, &archive).await;
+        });
+    }
+}
+
+#[tokio::test]
+async fn roundtrip_empty_stream() {
+    let spec = ArchiveSpec { entries: Vec::new(), comment: None };
+    read_stream(&spec, &write(&spec).await).await;
+
+    let spec = ArchiveSpec { entries: Vec::new(), comment: Some(String::from("Foo bar.")) };
+    read_stream(&spec, &write(&spec).await).await;
+}
+
+#[tokio::test]
+async fn roundtrip_utf8_comment_flag() {
+    let entry = EntrySpec {
+        filename: String::from("100%.txt"),
+        data: b"Lorem ipsum".to_vec(),
+        compression: Compression::Stored,
+        stream: false,
+        comment: String::from("100% café"),
+    };
+
+    let streamed = EntrySpec { filename: String::from("日本%.txt"), stream: true, ..entry.clone() };
+    let spec = ArchiveSpec { entries: vec![entry, streamed], comment: None };
+    let archive = write(&spec).await;
+
+    read_mem(&spec, &archive).await;
+    read_stream(&spec, &archive).await;
+}
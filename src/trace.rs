@@ -0,0 +1,42 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+// Internal wrappers around the optional `tracing` instrumentation.
+//
+// Each macro expands to nothing unless the `tracing` feature is enabled, so none of its arguments are evaluated (and
+// no overhead is incurred) otherwise. Spans are instead added via `cfg_attr` on the instrumented functions.
+
+// Emit a debug-level event at a key point whilst parsing or writing a ZIP file.
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)+);
+    };
+}
+
+// Emit an error-level event just before an error is returned.
+macro_rules! error {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::error!($($arg)+);
+    };
+}
+
+// Record an entry's filename, compression method, and sizes onto the current span's (initially empty) fields.
+macro_rules! record_entry {
+    ($entry:expr) => {
+        #[cfg(feature = "tracing")]
+        crate::trace::record_entry_fields($entry);
+    };
+}
+
+pub(crate) use {debug, error, record_entry};
+
+#[cfg(feature = "tracing")]
+pub(crate) fn record_entry_fields(entry: &crate::entry::ZipEntry) {
+    let span = tracing::Span::current();
+    span.record("filename", entry.filename());
+    span.record("compression", tracing::field::debug(entry.compression()));
+    span.record("compressed_size", entry.compressed_size());
+    span.record("uncompressed_size", entry.uncompressed_size());
+}
@@ -2,12 +2,110 @@
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
 use crate::error::{Result, ZipError};
+use crate::spec::extra_field::ExtraField;
+
+use std::sync::Arc;
 use tokio::io::{AsyncRead, AsyncReadExt};
 
 // Assert that the next four-byte signature read by a reader which impls AsyncRead matches the expected signature.
-pub(crate) async fn assert_signature<R: AsyncRead + Unpin>(reader: &mut R, expected: u32) -> Result<()> {
+//
+// The offset of the signature, if known, is only used for error reporting.
+pub(crate) async fn assert_signature<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    expected: u32,
+    offset: Option<u64>,
+) -> Result<()> {
     match reader.read_u32_le().await? {
-        actual if actual == expected => Ok(()),
-        actual => Err(ZipError::UnexpectedHeaderError(actual, expected)),
+        found if found == expected => Ok(()),
+        found => {
+            crate::trace::error!(?offset, expected, found, "unexpected signature");
+            Err(ZipError::UnexpectedSignature { offset, expected, found })
+        }
+    }
+}
+
+// Normalise an entry's filename for comparison purposes.
+//
+// Backslashes are treated as separators, empty and "." components are dropped, and ".." components collapse the
+// preceding component. A ".." component with nothing to collapse is kept, so that a filename which escapes its root
+// (eg. "../../etc/passwd") never normalises to one which doesn't. A trailing separator (denoting a directory) is
+// preserved.
+pub(crate) fn normalize_filename(filename: &str) -> String {
+    let filename = filename.replace('\\', "/");
+    let mut components: Vec<&str> = Vec::new();
+
+    for component in filename.split('/') {
+        match (component, components.last()) {
+            ("" | ".", _) => {}
+            ("..", Some(&last)) if last != ".." => {
+                components.pop();
+            }
+            (component, _) => components.push(component),
+        }
+    }
+
+    let mut normalized = components.join("/");
+    if filename.ends_with('/') && !normalized.is_empty() {
+        normalized.push('/');
+    }
+
+    normalized
+}
+
+// Decode an entry's raw filename or comment.
+//
+// Both are UTF-8 when the entry's language encoding flag is set, and otherwise CP437, as the specification dictates
+// (ASCII text is identical in both). Text which merely happens to be valid UTF-8 is still decoded as CP437.
+pub(crate) fn decode_text(text: Vec<u8>, unicode: bool) -> Result<String> {
+    match unicode {
+        true => String::from_utf8(text).map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err).into()),
+        false => Ok(text.iter().map(|&byte| cp437_to_char(byte)).collect()),
     }
 }
+
+// Decode an entry's raw filename as with decode_text(), unless one of its extra fields is an Info-ZIP Unicode path
+// record which matches it, in which case the record's UTF-8 filename is preferred.
+//
+// The raw filename is also returned where its bytes differ from those of the decoded filename, so that it remains
+// available via ZipEntry::filename_bytes().
+pub(crate) fn decode_entry_filename(
+    filename: Vec<u8>,
+    unicode: bool,
+    extra_fields: &[ExtraField],
+) -> Result<(String, Option<Arc<[u8]>>)> {
+    let decoded = match crate::spec::extra_field::parse_unicode_path(extra_fields, &filename) {
+        Some(decoded) => decoded,
+        None if filename.is_ascii() => return Ok((String::from_utf8(filename).unwrap(), None)),
+        None => decode_text(filename.clone(), unicode)?,
+    };
+
+    match decoded.as_bytes() == filename {
+        true => Ok((decoded, None)),
+        false => Ok((decoded, Some(filename.into()))),
+    }
+}
+
+// Map a CP437 byte to its character. The lower half matches ASCII.
+fn cp437_to_char(byte: u8) -> char {
+    match byte {
+        0x00..=0x7F => char::from(byte),
+        _ => CP437_UPPER_HALF[usize::from(byte - 0x80)],
+    }
+}
+
+#[rustfmt::skip]
+const CP437_UPPER_HALF: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}',
+];
+
+// Fold the case of a filename for case-insensitive comparison purposes.
+pub(crate) fn fold_case(filename: &str) -> String {
+    filename.chars().flat_map(char::to_lowercase).collect()
+}
@@ -1,55 +1,56 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
+use crate::error::{Result, ZipError};
 use crate::spec::compression::Compression;
 
-use std::io::Error;
+use std::io::{Error, IoSlice};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
 #[cfg(any(feature = "deflate", feature = "bzip2", feature = "zstd", feature = "lzma", feature = "xz"))]
 use async_compression::tokio::write;
-use async_io_utilities::AsyncOffsetWriter;
 use tokio::io::AsyncWrite;
 
-pub enum CompressedAsyncWriter<'b, W: AsyncWrite + Unpin> {
-    Stored(ShutdownIgnoredWriter<&'b mut AsyncOffsetWriter<W>>),
+pub enum CompressedAsyncWriter<T: AsyncWrite + Unpin> {
+    Stored(ShutdownIgnoredWriter<T>),
     #[cfg(feature = "deflate")]
-    Deflate(write::DeflateEncoder<ShutdownIgnoredWriter<&'b mut AsyncOffsetWriter<W>>>),
+    Deflate(write::DeflateEncoder<ShutdownIgnoredWriter<T>>),
     #[cfg(feature = "bzip2")]
-    Bz(write::BzEncoder<ShutdownIgnoredWriter<&'b mut AsyncOffsetWriter<W>>>),
+    Bz(write::BzEncoder<ShutdownIgnoredWriter<T>>),
     #[cfg(feature = "lzma")]
-    Lzma(write::LzmaEncoder<ShutdownIgnoredWriter<&'b mut AsyncOffsetWriter<W>>>),
+    Lzma(write::LzmaEncoder<ShutdownIgnoredWriter<T>>),
     #[cfg(feature = "zstd")]
-    Zstd(write::ZstdEncoder<ShutdownIgnoredWriter<&'b mut AsyncOffsetWriter<W>>>),
+    Zstd(write::ZstdEncoder<ShutdownIgnoredWriter<T>>),
     #[cfg(feature = "xz")]
-    Xz(write::XzEncoder<ShutdownIgnoredWriter<&'b mut AsyncOffsetWriter<W>>>),
+    Xz(write::XzEncoder<ShutdownIgnoredWriter<T>>),
 }
 
-impl<'b, W: AsyncWrite + Unpin> CompressedAsyncWriter<'b, W> {
-    pub fn from_raw(writer: &'b mut AsyncOffsetWriter<W>, compression: Compression) -> Self {
+impl<T: AsyncWrite + Unpin> CompressedAsyncWriter<T> {
+    pub fn from_raw(writer: T, compression: Compression, level: async_compression::Level) -> Self {
+        let writer = ShutdownIgnoredWriter { 0: writer };
+
         match compression {
-            Compression::Stored => CompressedAsyncWriter::Stored(ShutdownIgnoredWriter { 0: writer }),
+            Compression::Stored => CompressedAsyncWriter::Stored(writer),
             #[cfg(feature = "deflate")]
-            Compression::Deflate => {
-                CompressedAsyncWriter::Deflate(write::DeflateEncoder::new(ShutdownIgnoredWriter { 0: writer }))
-            }
+            Compression::Deflate => CompressedAsyncWriter::Deflate(write::DeflateEncoder::with_quality(writer, level)),
             #[cfg(feature = "bzip2")]
-            Compression::Bz => CompressedAsyncWriter::Bz(write::BzEncoder::new(ShutdownIgnoredWriter { 0: writer })),
+            Compression::Bz => CompressedAsyncWriter::Bz(write::BzEncoder::with_quality(writer, level)),
             #[cfg(feature = "lzma")]
-            Compression::Lzma => {
-                CompressedAsyncWriter::Lzma(write::LzmaEncoder::new(ShutdownIgnoredWriter { 0: writer }))
-            }
+            Compression::Lzma => CompressedAsyncWriter::Lzma(write::LzmaEncoder::with_quality(writer, level)),
             #[cfg(feature = "zstd")]
-            Compression::Zstd => {
-                CompressedAsyncWriter::Zstd(write::ZstdEncoder::new(ShutdownIgnoredWriter { 0: writer }))
-            }
+            Compression::Zstd => CompressedAsyncWriter::Zstd(write::ZstdEncoder::with_quality(writer, level)),
             #[cfg(feature = "xz")]
-            Compression::Xz => CompressedAsyncWriter::Xz(write::XzEncoder::new(ShutdownIgnoredWriter { 0: writer })),
+            Compression::Xz => CompressedAsyncWriter::Xz(write::XzEncoder::with_quality(writer, level)),
+            // Rejected by check_compression() before a compressed writer is ever constructed.
+            #[cfg(feature = "deflate64")]
+            Compression::Deflate64 => unreachable!(),
+            Compression::Shrink | Compression::Reduce(_) | Compression::Implode => unreachable!(),
+            Compression::Unknown(_) => unreachable!(),
         }
     }
 
-    pub fn into_inner(self) -> &'b mut AsyncOffsetWriter<W> {
+    pub fn into_inner(self) -> T {
         match self {
             CompressedAsyncWriter::Stored(inner) => inner.into_inner(),
             #[cfg(feature = "deflate")]
@@ -64,9 +65,25 @@ impl<'b, W: AsyncWrite + Unpin> CompressedAsyncWriter<'b, W> {
             CompressedAsyncWriter::Xz(inner) => inner.into_inner().into_inner(),
         }
     }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        match self {
+            CompressedAsyncWriter::Stored(inner) => inner.get_mut(),
+            #[cfg(feature = "deflate")]
+            CompressedAsyncWriter::Deflate(inner) => inner.get_mut().get_mut(),
+            #[cfg(feature = "bzip2")]
+            CompressedAsyncWriter::Bz(inner) => inner.get_mut().get_mut(),
+            #[cfg(feature = "lzma")]
+            CompressedAsyncWriter::Lzma(inner) => inner.get_mut().get_mut(),
+            #[cfg(feature = "zstd")]
+            CompressedAsyncWriter::Zstd(inner) => inner.get_mut().get_mut(),
+            #[cfg(feature = "xz")]
+            CompressedAsyncWriter::Xz(inner) => inner.get_mut().get_mut(),
+        }
+    }
 }
 
-impl<'b, W: AsyncWrite + Unpin> AsyncWrite for CompressedAsyncWriter<'b, W> {
+impl<T: AsyncWrite + Unpin> AsyncWrite for CompressedAsyncWriter<T> {
     fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<std::result::Result<usize, Error>> {
         match *self {
             CompressedAsyncWriter::Stored(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
@@ -83,6 +100,35 @@ impl<'b, W: AsyncWrite + Unpin> AsyncWrite for CompressedAsyncWriter<'b, W> {
         }
     }
 
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::result::Result<usize, Error>> {
+        match *self {
+            CompressedAsyncWriter::Stored(ref mut inner) => Pin::new(inner).poll_write_vectored(cx, bufs),
+            #[cfg(feature = "deflate")]
+            CompressedAsyncWriter::Deflate(ref mut inner) => poll_write_each(inner, cx, bufs),
+            #[cfg(feature = "bzip2")]
+            CompressedAsyncWriter::Bz(ref mut inner) => poll_write_each(inner, cx, bufs),
+            #[cfg(feature = "lzma")]
+            CompressedAsyncWriter::Lzma(ref mut inner) => poll_write_each(inner, cx, bufs),
+            #[cfg(feature = "zstd")]
+            CompressedAsyncWriter::Zstd(ref mut inner) => poll_write_each(inner, cx, bufs),
+            #[cfg(feature = "xz")]
+            CompressedAsyncWriter::Xz(ref mut inner) => poll_write_each(inner, cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            CompressedAsyncWriter::Stored(inner) => inner.is_write_vectored(),
+            // Compressors buffer their input, so feeding each slice in turn within a single call is cheap.
+            #[allow(unreachable_patterns)]
+            _ => true,
+        }
+    }
+
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
         match *self {
             CompressedAsyncWriter::Stored(ref mut inner) => Pin::new(inner).poll_flush(cx),
@@ -116,12 +162,57 @@ impl<'b, W: AsyncWrite + Unpin> AsyncWrite for CompressedAsyncWriter<'b, W> {
     }
 }
 
+// Reject compression methods which can't be written before any compressed writer is constructed.
+pub(crate) fn check_compression(compression: Compression) -> Result<()> {
+    match compression {
+        #[cfg(feature = "deflate64")]
+        Compression::Deflate64 => Err(ZipError::FeatureNotSupported("Deflate64 compression when writing")),
+        Compression::Shrink | Compression::Reduce(_) | Compression::Implode => {
+            Err(ZipError::FeatureNotSupported("Legacy compression methods when writing"))
+        }
+        Compression::Unknown(value) => Err(ZipError::UnsupportedCompressionError(value)),
+        _ => Ok(()),
+    }
+}
+
+// Write as many of the slices as possible in turn, stopping at the first short or pending write.
+#[cfg(any(feature = "deflate", feature = "bzip2", feature = "zstd", feature = "lzma", feature = "xz"))]
+fn poll_write_each<T: AsyncWrite + Unpin>(
+    inner: &mut T,
+    cx: &mut Context,
+    bufs: &[IoSlice<'_>],
+) -> Poll<std::result::Result<usize, Error>> {
+    let mut written = 0;
+
+    for buf in bufs.iter().filter(|buf| !buf.is_empty()) {
+        match Pin::new(&mut *inner).poll_write(cx, buf) {
+            Poll::Ready(Ok(count)) => {
+                written += count;
+
+                if count < buf.len() {
+                    break;
+                }
+            }
+            // Errors and pending writes are only surfaced once nothing has been written in this call.
+            Poll::Ready(Err(err)) if written == 0 => return Poll::Ready(Err(err)),
+            Poll::Pending if written == 0 => return Poll::Pending,
+            _ => break,
+        }
+    }
+
+    Poll::Ready(Ok(written))
+}
+
 pub struct ShutdownIgnoredWriter<W: AsyncWrite + Unpin>(W);
 
 impl<W: AsyncWrite + Unpin> ShutdownIgnoredWriter<W> {
     pub fn into_inner(self) -> W {
         self.0
     }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.0
+    }
 }
 
 impl<W: AsyncWrite + Unpin> AsyncWrite for ShutdownIgnoredWriter<W> {
@@ -129,6 +220,18 @@ impl<W: AsyncWrite + Unpin> AsyncWrite for ShutdownIgnoredWriter<W> {
         Pin::new(&mut self.0).poll_write(cx, buf)
     }
 
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::result::Result<usize, Error>> {
+        Pin::new(&mut self.0).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.0.is_write_vectored()
+    }
+
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
         Pin::new(&mut self.0).poll_flush(cx)
     }
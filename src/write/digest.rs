@@ -0,0 +1,51 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use std::io::IoSlice;
+use std::sync::Arc;
+
+/// A hash function computed over each entry's uncompressed data alongside its CRC32 value.
+///
+/// See [`ZipFileWriter::digest()`]. This trait may be implemented directly for the hasher types of other crates, eg.
+/// for `sha2::Sha256`:
+/// ```
+/// # use async_zip::write::EntryDigest;
+/// # struct Sha256(Vec<u8>);
+/// # impl Sha256 { fn update(&mut self, data: &[u8]) {} fn finalize(self) -> Vec<u8> { self.0 } }
+/// struct ManifestDigest(Sha256);
+///
+/// impl EntryDigest for ManifestDigest {
+///     fn update(&mut self, data: &[u8]) {
+///         self.0.update(data);
+///     }
+///
+///     fn finalize(self: Box<Self>) -> Vec<u8> {
+///         self.0.finalize().to_vec()
+///     }
+/// }
+/// ```
+///
+/// [`ZipFileWriter::digest()`]: crate::write::ZipFileWriter::digest
+pub trait EntryDigest: Send + Sync {
+    /// Feeds the next chunk of the entry's uncompressed data into the digest.
+    fn update(&mut self, data: &[u8]);
+
+    /// Consumes the digest once all of the entry's data has been fed into it, returning its output.
+    fn finalize(self: Box<Self>) -> Vec<u8>;
+}
+
+/// Constructs a new digest for each entry written, as set via [`ZipFileWriter::digest()`].
+///
+/// [`ZipFileWriter::digest()`]: crate::write::ZipFileWriter::digest
+pub(crate) type DigestFactory = Arc<dyn Fn() -> Box<dyn EntryDigest> + Send + Sync>;
+
+// Compute a new digest over data which is entirely known upfront.
+pub(crate) fn compute(factory: &DigestFactory, parts: &[IoSlice<'_>]) -> Vec<u8> {
+    let mut digest = factory();
+
+    for part in parts {
+        digest.update(part);
+    }
+
+    digest.finalize()
+}
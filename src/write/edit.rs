@@ -0,0 +1,368 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module which supports removing and renaming the entries of an existing ZIP file in place.
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::write::edit::ZipFileEditor;
+//! # use tokio::fs::OpenOptions;
+//! # use async_zip::error::ZipError;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let file = OpenOptions::new().read(true).write(true).open("archive.zip").await?;
+//! let mut editor = ZipFileEditor::open(file).await?;
+//!
+//! editor.remove_entry(0)?;
+//! editor.rename_entry(1, "renamed.txt")?;
+//! editor.commit().await?;
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::entry::ZipEntry;
+use crate::error::{Result, ZipError};
+use crate::read::seek::ZipFileReader;
+use crate::spec::extra_field::{ExtraField, ALIGNMENT_PADDING, UNICODE_PATH};
+use crate::spec::header::{CentralDirectoryHeader, EndOfCentralDirectoryHeader, LocalFileHeader};
+use crate::write::{record_field, record_length};
+
+use std::collections::HashSet;
+use std::io::SeekFrom;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+// The size of the buffer used to shift entry data when compacting.
+const COMPACT_BUFFER_SIZE: usize = 65536;
+
+/// An editor which removes and renames the entries of an existing ZIP file, without rewriting their data.
+///
+/// Edits are only applied once [`ZipFileEditor::commit()`] is called, which rewrites the central directory and end of
+/// central directory header in place. Entry indices always refer to the entries as listed when the ZIP file was
+/// opened, regardless of any which have since been removed.
+///
+/// # Note
+/// - ZIP64 and spanned ZIP files aren't supported.
+/// - Any bytes following the end of central directory header (eg. a signature appended by a signing tool) are
+/// truncated when committing, as they'd no longer describe the ZIP file.
+pub struct ZipFileEditor {
+    file: File,
+    entries: Vec<EditedEntry>,
+    comment: Vec<u8>,
+    cd_offset: u64,
+    adjustment: u64,
+    compact: bool,
+    patch_local_headers: bool,
+}
+
+struct EditedEntry {
+    entry: ZipEntry,
+    record: Vec<u8>,
+    offset: u64,
+    removed: bool,
+    renamed: bool,
+}
+
+impl ZipFileEditor {
+    /// Opens an existing ZIP file for editing, reading its central directory.
+    ///
+    /// The file must have been opened for both reading and writing.
+    pub async fn open(file: File) -> Result<Self> {
+        let reader = ZipFileReader::new(file).await?;
+
+        if reader.metadata.has_zip64_locator() || reader.metadata.requires_zip64() {
+            return Err(ZipError::FeatureNotSupported("Editing ZIP64 files"));
+        }
+
+        let ZipFileReader { reader: mut file, entries, metadata, location, .. } = reader;
+        file.seek(SeekFrom::Start(location.offset)).await?;
+
+        // Each record is kept in its raw form, so that any field which isn't edited is written back exactly.
+        let mut edited = Vec::with_capacity(entries.len());
+        let mut position = location.offset;
+
+        for (entry, meta) in entries {
+            let mut record = vec![0; CentralDirectoryHeader::LENGTH];
+            file.read_exact(&mut record).await?;
+
+            let expected = crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER;
+            let found = u32::from_le_bytes([record[0], record[1], record[2], record[3]]);
+            if found != expected {
+                return Err(ZipError::UnexpectedSignature { offset: Some(position), expected, found });
+            }
+
+            record.resize(record_length(&record), 0);
+            file.read_exact(&mut record[CentralDirectoryHeader::LENGTH..]).await?;
+            position += record.len() as u64;
+
+            let offset = meta.file_offset.unwrap();
+            edited.push(EditedEntry { entry, record, offset, removed: false, renamed: false });
+        }
+
+        Ok(Self {
+            file,
+            entries: edited,
+            comment: metadata.comment_raw,
+            cd_offset: location.offset,
+            adjustment: location.adjustment,
+            compact: false,
+            patch_local_headers: false,
+        })
+    }
+
+    /// Returns the ZIP file's entries as listed when it was opened, with any renames applied.
+    pub fn entries(&self) -> Vec<&ZipEntry> {
+        self.entries.iter().map(|edited| &edited.entry).collect()
+    }
+
+    /// Returns whether or not the entry at the provided index has been removed.
+    pub fn is_removed(&self, index: usize) -> bool {
+        self.entries.get(index).map(|edited| edited.removed).unwrap_or_default()
+    }
+
+    /// Removes the entry at the provided index.
+    ///
+    /// Its data is left in place as dead space, unless the ZIP file is compacted (see [`ZipFileEditor::compact()`]).
+    pub fn remove_entry(&mut self, index: usize) -> Result<()> {
+        self.kept_mut(index)?.removed = true;
+        Ok(())
+    }
+
+    /// Renames the entry at the provided index.
+    ///
+    /// Only the entry's central directory record is changed by default, as most readers only consider that filename.
+    /// See [`ZipFileEditor::patch_local_headers()`] to also change the filename within its local file header.
+    pub fn rename_entry(&mut self, index: usize, filename: impl Into<String>) -> Result<()> {
+        let filename = filename.into();
+
+        if filename.len() > u16::MAX as usize {
+            return Err(ZipError::FilenameTooLong(filename.len()));
+        }
+        if filename.contains('\0') {
+            return Err(ZipError::InvalidFilename(filename));
+        }
+
+        let mut kept = self.entries.iter().enumerate().filter(|(other, edited)| *other != index && !edited.removed);
+        if kept.any(|(_, edited)| edited.entry.filename() == filename) {
+            return Err(ZipError::DuplicateFilename(filename));
+        }
+
+        let edited = self.kept_mut(index)?;
+        edited.record = renamed_record(&edited.record, &filename);
+        edited.entry.filename = filename.into();
+        edited.entry.filename_raw = None;
+        edited.entry.filename_bytes = None;
+        edited.renamed = true;
+
+        Ok(())
+    }
+
+    // Returns the entry at the provided index, unless it's out of bounds or has been removed.
+    fn kept_mut(&mut self, index: usize) -> Result<&mut EditedEntry> {
+        self.entries.get_mut(index).filter(|edited| !edited.removed).ok_or(ZipError::EntryIndexOutOfBounds)
+    }
+
+    /// Sets the ZIP file's comment.
+    pub fn set_comment(&mut self, comment: String) {
+        self.comment = comment.into_bytes();
+    }
+
+    /// Sets whether or not the data of removed entries should be reclaimed when committing, by shifting the data of
+    /// every later entry down over it. Defaults to false.
+    ///
+    /// # Note
+    /// Compacting rewrites the data of every entry after the first removed, so may take as long as rewriting the ZIP
+    /// file in full. It isn't supported where entries share their data.
+    pub fn compact(&mut self, compact: bool) {
+        self.compact = compact;
+    }
+
+    /// Sets whether or not renamed entries should also have the filename within their local file header changed.
+    /// Defaults to false.
+    ///
+    /// As this is done in place, an entry's local file header is only patched if its new filename is the same length
+    /// as the old, or short enough that the difference can be filled with a padding extra field (at least six bytes).
+    /// Any other entry keeps its original local filename.
+    pub fn patch_local_headers(&mut self, patch: bool) {
+        self.patch_local_headers = patch;
+    }
+
+    /// Applies all edits, rewriting the central directory and end of central directory header, and returns the file.
+    ///
+    /// The data of untouched entries is never rewritten, unless the ZIP file is compacted and it follows the data of a
+    /// removed entry.
+    pub async fn commit(mut self) -> Result<File> {
+        if self.comment.len() > u16::MAX as usize {
+            return Err(ZipError::CommentTooLong(self.comment.len()));
+        }
+
+        if self.patch_local_headers {
+            for edited in self.entries.iter().filter(|edited| edited.renamed && !edited.removed) {
+                patch_local_header(&mut self.file, edited).await?;
+            }
+        }
+
+        let cd_offset = match self.compact {
+            true => self.compact_data().await?,
+            false => self.cd_offset,
+        };
+
+        let mut central_directory = Vec::new();
+        let mut count = 0;
+
+        for edited in self.entries.iter_mut().filter(|edited| !edited.removed) {
+            let lh_offset = (edited.offset - self.adjustment) as u32;
+            edited.record[42..46].copy_from_slice(&lh_offset.to_le_bytes());
+            central_directory.extend_from_slice(&edited.record);
+            count += 1;
+        }
+
+        let header = EndOfCentralDirectoryHeader {
+            disk_num: 0,
+            start_cent_dir_disk: 0,
+            num_of_entries_disk: count,
+            num_of_entries: count,
+            size_cent_dir: central_directory.len() as u32,
+            cent_dir_offset: (cd_offset - self.adjustment) as u32,
+            file_comm_length: self.comment.len() as u16,
+        };
+
+        central_directory.extend_from_slice(&header.to_bytes());
+        central_directory.extend_from_slice(&self.comment);
+
+        self.file.seek(SeekFrom::Start(cd_offset)).await?;
+        self.file.write_all(&central_directory).await?;
+        self.file.flush().await?;
+        self.file.set_len(cd_offset + central_directory.len() as u64).await?;
+
+        Ok(self.file)
+    }
+
+    // Shift each retained entry's data down over that of any removed entries before it, updating their offsets and
+    // returning the offset at which the central directory now begins.
+    //
+    // Each entry's data is taken to extend up until the next entry (or the central directory), so that any data
+    // descriptor or padding moves along with it.
+    async fn compact_data(&mut self) -> Result<u64> {
+        let mut order: Vec<usize> = (0..self.entries.len()).collect();
+        order.sort_by_key(|&index| self.entries[index].offset);
+
+        let offsets: HashSet<u64> = self.entries.iter().map(|edited| edited.offset).collect();
+        if offsets.len() != self.entries.len() {
+            return Err(ZipError::FeatureNotSupported("Compacting ZIP files whose entries share data"));
+        }
+
+        let mut position = match order.first() {
+            Some(&first) => self.entries[first].offset,
+            None => return Ok(self.cd_offset),
+        };
+        let mut buffer = vec![0; COMPACT_BUFFER_SIZE];
+
+        for (rank, &index) in order.iter().enumerate() {
+            let start = self.entries[index].offset;
+            let end = order.get(rank + 1).map(|&next| self.entries[next].offset).unwrap_or(self.cd_offset);
+
+            if self.entries[index].removed {
+                continue;
+            }
+
+            // The destination always precedes the source, so copying forwards never overwrites unread data.
+            if start != position {
+                let mut copied = 0;
+
+                while start + copied < end {
+                    let length = buffer.len().min((end - start - copied) as usize);
+                    self.file.seek(SeekFrom::Start(start + copied)).await?;
+                    self.file.read_exact(&mut buffer[..length]).await?;
+                    self.file.seek(SeekFrom::Start(position + copied)).await?;
+                    self.file.write_all(&buffer[..length]).await?;
+                    copied += length as u64;
+                }
+
+                self.entries[index].offset = position;
+            }
+
+            position += end - start;
+        }
+
+        Ok(position)
+    }
+}
+
+// Returns a copy of a raw central directory record with its filename replaced.
+//
+// The UTF-8 flag is set for a non-ASCII filename, and any Info-ZIP Unicode path extra field is dropped as it would
+// otherwise take precedence over the new filename within other readers.
+fn renamed_record(record: &[u8], filename: &str) -> Vec<u8> {
+    let name_length = record_field(record, 28);
+    let extra_length = record_field(record, 30);
+    let extra_start = CentralDirectoryHeader::LENGTH + name_length;
+
+    let extra_field = without_unicode_path(&record[extra_start..extra_start + extra_length]);
+    let comment = &record[extra_start + extra_length..];
+
+    let mut renamed = record[..CentralDirectoryHeader::LENGTH].to_vec();
+    if !filename.is_ascii() {
+        renamed[9] |= 0x08;
+    }
+    renamed[28..30].copy_from_slice(&(filename.len() as u16).to_le_bytes());
+    renamed[30..32].copy_from_slice(&(extra_field.len() as u16).to_le_bytes());
+
+    renamed.extend_from_slice(filename.as_bytes());
+    renamed.extend_from_slice(&extra_field);
+    renamed.extend_from_slice(comment);
+    renamed
+}
+
+fn without_unicode_path(extra_field: &[u8]) -> Vec<u8> {
+    let mut retained = Vec::with_capacity(extra_field.len());
+
+    for field in crate::spec::extra_field::parse_extra_fields(extra_field) {
+        if field.header_id() != UNICODE_PATH {
+            field.write_to(&mut retained);
+        }
+    }
+
+    retained
+}
+
+// Replace the filename within a renamed entry's local file header, if it can be done without moving the entry's data.
+//
+// The new filename and extra field must together occupy the same number of bytes as before, so any bytes freed by a
+// shorter filename are filled with a padding record after the retained extra fields.
+async fn patch_local_header(file: &mut File, edited: &EditedEntry) -> Result<()> {
+    file.seek(SeekFrom::Start(edited.offset)).await?;
+    crate::utils::assert_signature(file, crate::spec::signature::LOCAL_FILE_HEADER, Some(edited.offset)).await?;
+
+    let mut header = LocalFileHeader::from_reader(file).await?;
+    let mut name_and_extra = vec![0; usize::from(header.file_name_length) + usize::from(header.extra_field_length)];
+    file.read_exact(&mut name_and_extra).await?;
+
+    let filename = edited.entry.filename();
+    let available = name_and_extra.len();
+    let mut extra_field = without_unicode_path(&name_and_extra[usize::from(header.file_name_length)..]);
+
+    // The padding record's header ID, length, and alignment value occupy six bytes before any zeroed bytes.
+    let padding = match available.checked_sub(filename.len() + extra_field.len()) {
+        Some(0) => None,
+        Some(padding) if padding >= 6 => Some(padding - 6),
+        _ => return Ok(()),
+    };
+
+    if let Some(padding) = padding {
+        let mut data = 1u16.to_le_bytes().to_vec();
+        data.resize(2 + padding, 0);
+        ExtraField::new(ALIGNMENT_PADDING, data).write_to(&mut extra_field);
+    }
+
+    header.flags.filename_unicode |= !filename.is_ascii();
+    header.file_name_length = filename.len() as u16;
+    header.extra_field_length = extra_field.len() as u16;
+
+    file.seek(SeekFrom::Start(edited.offset)).await?;
+    file.write_all(&header.to_bytes()).await?;
+    file.write_all(filename.as_bytes()).await?;
+    file.write_all(&extra_field).await?;
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::crypto::zipcrypto::{self, ZipCryptoKeys};
+use crate::entry::{EncryptionMethod, ZipEntry};
+
+#[cfg(feature = "aes")]
+use crate::crypto::aes::{self, Cipher, HmacSha1};
+#[cfg(feature = "aes")]
+use crate::spec::extra_field::{ExtraField, AES_COMPRESSION, AES_ENCRYPTION};
+
+use std::io::{Error, ErrorKind};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+#[cfg(feature = "aes")]
+use hmac::Mac;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+// The key strength and vendor version (AE-2) of entries encrypted with AES.
+#[cfg(feature = "aes")]
+const AES_STRENGTH: u8 = 3;
+#[cfg(feature = "aes")]
+const AES_VERSION: u16 = 2;
+
+// Prepare an entry's extra field for its encryption (if any), returning the compression method to record within its
+// headers.
+//
+// An entry encrypted with AES records method 99, with its actual method held by an AES extra field. As with the ZIP64
+// record, this must be called before the local file header's extra field is constructed.
+pub(crate) fn prepare(entry: &mut ZipEntry) -> u16 {
+    #[cfg(feature = "aes")]
+    if let Some((EncryptionMethod::Aes256, _)) = entry.encryption {
+        let mut data = AES_VERSION.to_le_bytes().to_vec();
+        data.extend_from_slice(b"AE");
+        data.push(AES_STRENGTH);
+        data.extend_from_slice(&u16::from(entry.compression()).to_le_bytes());
+
+        entry.extra_fields.retain(|field| field.header_id() != AES_ENCRYPTION);
+        entry.extra_fields.push(ExtraField::new(AES_ENCRYPTION, data));
+        entry.extra_field.clear();
+        for field in &entry.extra_fields {
+            field.write_to(&mut entry.extra_field);
+        }
+
+        return AES_COMPRESSION;
+    }
+
+    entry.compression().into()
+}
+
+// Returns the number of bytes an entry's encryption adds to its compressed size.
+pub(crate) fn overhead(entry: &ZipEntry) -> u64 {
+    match entry.encryption {
+        None => 0,
+        Some((EncryptionMethod::ZipCrypto, _)) => zipcrypto::HEADER_LENGTH as u64,
+        #[cfg(feature = "aes")]
+        Some((EncryptionMethod::Aes256, _)) => {
+            let salt_length = aes::salt_length(AES_STRENGTH).unwrap();
+            (salt_length + aes::VERIFICATION_LENGTH + aes::AUTHENTICATION_CODE_LENGTH) as u64
+        }
+    }
+}
+
+// Returns the CRC32 value to record for an entry, which AE-2 omits (as it could reveal information about the data).
+pub(crate) fn recorded_crc(entry: &ZipEntry, crc: u32) -> u32 {
+    #[cfg(feature = "aes")]
+    if let Some((EncryptionMethod::Aes256, _)) = entry.encryption {
+        return 0;
+    }
+
+    #[cfg(not(feature = "aes"))]
+    let _ = entry;
+    crc
+}
+
+// Returns whether an entry must be followed by a data descriptor regardless of the writer's configuration.
+//
+// ZipCrypto's check byte is taken from the CRC32 value unless the data descriptor flag is set, and a stream-written
+// entry's value isn't known when its encryption header is written.
+pub(crate) fn requires_data_descriptor(entry: &ZipEntry) -> bool {
+    matches!(entry.encryption, Some((EncryptionMethod::ZipCrypto, _)))
+}
+
+// The state of the cipher which an entry's data is encrypted with.
+enum WriteCipher {
+    ZipCrypto(ZipCryptoKeys),
+    #[cfg(feature = "aes")]
+    Aes(Cipher, HmacSha1),
+}
+
+/// A writer which encrypts the data written to it with its entry's encryption (if any).
+///
+/// The encryption header is written when it's constructed, and any authentication code which follows the data is
+/// only written by [`EncryptedAsyncWriter::finish()`], so that must be called in place of shutting the writer down.
+pub struct EncryptedAsyncWriter<T: AsyncWrite + Unpin> {
+    inner: T,
+    cipher: Option<WriteCipher>,
+    encrypted: Vec<u8>,
+    encrypted_offset: usize,
+}
+
+impl<T: AsyncWrite + Unpin> EncryptedAsyncWriter<T> {
+    // Write an entry's encryption header (if any) and construct a writer which encrypts all data which follows it.
+    //
+    // The check byte is only used by ZipCrypto, and must be the upper byte of the entry's CRC32 value (or of its
+    // modification time if a data descriptor follows the data).
+    pub(crate) async fn start(mut inner: T, entry: &ZipEntry, check: u8) -> std::io::Result<Self> {
+        let cipher = match &entry.encryption {
+            None => None,
+            Some((EncryptionMethod::ZipCrypto, password)) => {
+                let (keys, header) = zipcrypto::encrypt_header(password, check)?;
+                inner.write_all(&header).await?;
+                Some(WriteCipher::ZipCrypto(keys))
+            }
+            #[cfg(feature = "aes")]
+            Some((EncryptionMethod::Aes256, password)) => {
+                let mut salt = vec![0; aes::salt_length(AES_STRENGTH).unwrap()];
+                crate::crypto::fill_random(&mut salt)?;
+
+                let (cipher, mac, verification) = aes::derive_keys(password, &salt, AES_STRENGTH);
+                inner.write_all(&salt).await?;
+                inner.write_all(&verification).await?;
+                Some(WriteCipher::Aes(cipher, mac))
+            }
+        };
+
+        Ok(Self { inner, cipher, encrypted: Vec::new(), encrypted_offset: 0 })
+    }
+
+    /// Writes any encrypted data which the inner writer hasn't yet accepted, followed by the authentication code (if
+    /// any), and returns the inner writer.
+    pub async fn finish(mut self) -> std::io::Result<T> {
+        self.inner.write_all(&self.encrypted[self.encrypted_offset..]).await?;
+
+        #[cfg(feature = "aes")]
+        if let Some(WriteCipher::Aes(_, mac)) = self.cipher {
+            let code = mac.finalize().into_bytes();
+            self.inner.write_all(&code[..aes::AUTHENTICATION_CODE_LENGTH]).await?;
+        }
+
+        Ok(self.inner)
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    // Write whatever remains of the data last encrypted.
+    fn poll_write_encrypted(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        while self.encrypted_offset < self.encrypted.len() {
+            let written = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.encrypted[self.encrypted_offset..]))?;
+
+            if written == 0 {
+                return Poll::Ready(Err(Error::from(ErrorKind::WriteZero)));
+            }
+
+            self.encrypted_offset += written;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for EncryptedAsyncWriter<T> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<std::result::Result<usize, Error>> {
+        let this = self.get_mut();
+
+        if this.cipher.is_none() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+
+        // Each byte's encryption depends upon those before it, so a buffer is encrypted (and accepted) in full, and
+        // then written by later calls.
+        ready!(this.poll_write_encrypted(cx))?;
+        this.encrypted.clear();
+        this.encrypted.extend_from_slice(buf);
+        this.encrypted_offset = 0;
+
+        match this.cipher.as_mut() {
+            Some(WriteCipher::ZipCrypto(keys)) => keys.encrypt(&mut this.encrypted),
+            #[cfg(feature = "aes")]
+            Some(WriteCipher::Aes(cipher, mac)) => {
+                cipher.apply(&mut this.encrypted);
+                mac.update(&this.encrypted);
+            }
+            None => unreachable!(),
+        }
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_encrypted(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_encrypted(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
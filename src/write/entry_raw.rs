@@ -0,0 +1,159 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::entry::ZipEntry;
+use crate::error::Result;
+use crate::spec::header::{CentralDirectoryHeader, LocalFileHeader};
+use crate::write::{WrittenEntry, ZipFileWriter};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+// Write an entry whose data has already been compressed (eg. as read from another ZIP file), without recompressing it.
+//
+// The entry's CRC32 value, sizes, and compression method are written as-is, so must describe the provided data. If
+// the entry's flags declare a data descriptor, one is written after the data (and the local file header's CRC32 value
+// and sizes are zeroed) so that the entry's layout, and any encryption header relying upon it, is preserved.
+pub(crate) async fn write_entry_raw<W, R>(
+    writer: &mut ZipFileWriter<W>,
+    entry: ZipEntry,
+    data: R,
+) -> Result<WrittenEntry>
+where
+    W: AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+{
+    let values = (entry.crc32(), entry.compressed_size(), entry.uncompressed_size());
+    let mut data = data.take(entry.compressed_size());
+
+    write_entry_raw_resolved(writer, entry, &mut data, |_, copied| match copied == values.1 {
+        true => Ok(values),
+        false => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+    })
+    .await
+}
+
+// Write an entry whose already-compressed data is copied from a reader until EOF, only then resolving its CRC32 value
+// and sizes from the reader and the number of bytes copied (eg. where the data is followed by a data descriptor within
+// another ZIP file, which is only read once the data has been).
+//
+// The entry's flags should declare a data descriptor, as the local file header is otherwise written with the entry's
+// own CRC32 value and sizes before they're resolved.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "write_entry",
+        level = "debug",
+        skip_all,
+        fields(filename = entry.filename(), compression = ?entry.compression())
+    )
+)]
+pub(crate) async fn write_entry_raw_resolved<W, R, F>(
+    writer: &mut ZipFileWriter<W>,
+    mut entry: ZipEntry,
+    data: &mut R,
+    resolve: F,
+) -> Result<WrittenEntry>
+where
+    W: AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+    F: FnOnce(&mut R, u64) -> Result<(u32, u64, u64)>,
+{
+    entry.validate()?;
+    writer.check_poisoned()?;
+    writer.duplicates.check(&entry)?;
+
+    let mut flags = entry.general_purpose_flags();
+    flags.filename_unicode |= crate::write::requires_utf8_flag(&entry);
+
+    let (mod_time, mod_date) = writer.entry_zip_time(&entry);
+    let lh_offset = writer.writer.offset();
+
+    // As with stream-written entries, a ZIP64 record is only reserved ahead of a data descriptor if forced. Any record
+    // the entry already holds (eg. from another ZIP file) is replaced where one is required, as its values may no
+    // longer apply.
+    let (crc, sizes, zip64) = match flags.data_descriptor {
+        true if entry.force_zip64 => (0, [u32::MAX; 2], vec![0, 0]),
+        true => (0, [0; 2], Vec::new()),
+        false => {
+            let (sizes, zip64) =
+                crate::write::local_zip64_split(&entry, [entry.uncompressed_size(), entry.compressed_size()]);
+            (entry.crc32(), sizes, zip64)
+        }
+    };
+    let local_zip64 = !zip64.is_empty();
+    if local_zip64 {
+        crate::write::set_zip64_field(&mut entry, &zip64);
+    }
+    let extra_field = crate::write::local_extra_field(&entry, lh_offset).into_owned();
+
+    let mut version = crate::spec::version::as_needed_to_extract(&entry, &flags);
+    if writer.disk_position(lh_offset).1 >= u64::from(u32::MAX) {
+        version = version.max(crate::spec::version::ZIP64_VERSION_NEEDED);
+    }
+
+    let lf_header = LocalFileHeader {
+        compressed_size: sizes[1],
+        uncompressed_size: sizes[0],
+        compression: entry.compression_raw(),
+        crc,
+        extra_field_length: extra_field.len() as u16,
+        file_name_length: entry.filename().as_bytes().len() as u16,
+        mod_time,
+        mod_date,
+        version: entry.version_needed.unwrap_or(version),
+        flags,
+    };
+
+    // Cleared only once the entry's been fully written, so a failed or cancelled write poisons the ZIP writer.
+    writer.poisoned = true;
+
+    writer.writer.write_all(&lf_header.to_bytes()).await?;
+    writer.writer.write_all(entry.filename().as_bytes()).await?;
+    writer.writer.write_all(&extra_field).await?;
+
+    let (disk_start, lh_offset) = writer.disk_position(lh_offset);
+
+    let copied = tokio::io::copy(&mut *data, &mut writer.writer).await?;
+    let (crc, compressed_size, uncompressed_size) = resolve(data, copied)?;
+
+    if flags.data_descriptor {
+        let exceeds = compressed_size >= u64::from(u32::MAX) || uncompressed_size >= u64::from(u32::MAX);
+        let zip64 = entry.force_zip64 || exceeds;
+        let descriptor = writer.descriptor_style.descriptor(crc, compressed_size, uncompressed_size, zip64);
+        writer.writer.write_all(&descriptor).await?;
+    }
+
+    let (fields, zip64) = crate::write::central_zip64_split([uncompressed_size, compressed_size, lh_offset]);
+    if local_zip64 || !zip64.is_empty() {
+        crate::write::set_zip64_field(&mut entry, &zip64);
+    }
+    let v_needed = match entry.version_needed {
+        None if !zip64.is_empty() => lf_header.version.max(crate::spec::version::ZIP64_VERSION_NEEDED),
+        _ => lf_header.version,
+    };
+
+    let header = CentralDirectoryHeader {
+        v_made_by: entry.version_made_by.unwrap_or_else(crate::spec::version::as_made_by),
+        v_needed,
+        compressed_size: fields[1],
+        uncompressed_size: fields[0],
+        compression: lf_header.compression,
+        crc,
+        extra_field_length: entry.extra_field().len() as u16,
+        file_name_length: lf_header.file_name_length,
+        file_comment_length: entry.comment().len() as u16,
+        mod_time,
+        mod_date,
+        flags,
+        disk_start,
+        inter_attr: entry.internal_file_attribute(),
+        exter_attr: entry.external_file_attribute(),
+        lh_offset: fields[2],
+    };
+
+    let written = WrittenEntry::new(crc, compressed_size, uncompressed_size, lh_offset);
+    writer.duplicates.push(&mut writer.cd_records, &header, &entry);
+    writer.poisoned = false;
+
+    Ok(written)
+}
@@ -1,18 +1,21 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
-use crate::error::Result;
+use crate::error::{Result, ZipError};
 use crate::spec::header::{CentralDirectoryHeader, GeneralPurposeFlag, LocalFileHeader};
+use crate::spec::compression::Compression;
 use crate::write::compressed_writer::CompressedAsyncWriter;
-use crate::write::CentralDirectoryEntry;
-use crate::write::ZipFileWriter;
+use crate::write::encrypted_writer::EncryptedAsyncWriter;
+use crate::write::offset_writer::{AsyncOffsetWriter, Seeker};
+use crate::write::{CentralDirectoryRecords, DataDescriptorStyle, DuplicateTracker, EntryDigest};
+use crate::write::{WrittenEntry, ZipFileWriter};
 use crate::entry::ZipEntry;
+use crate::progress::Progress;
 
-use std::io::Error;
+use std::io::{Error, IoSlice, SeekFrom};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use async_io_utilities::AsyncOffsetWriter;
 use crc32fast::Hasher;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
@@ -20,16 +23,30 @@ use tokio::io::{AsyncWrite, AsyncWriteExt};
 ///
 /// # Note
 /// - This writer cannot be manually constructed; instead, use [`ZipFileWriter::write_entry_stream()`].
-/// - [`EntryStreamWriter::close()`] must be called before a stream writer goes out of scope.
+/// - [`EntryStreamWriter::close()`] must be called before a stream writer goes out of scope, otherwise the parent
+/// [`ZipFileWriter`] is poisoned (see its documentation).
 /// - Utilities for working with [`AsyncWrite`] values are provided by [`AsyncWriteExt`].
+///
+/// ## Flushing
+/// Flushing this writer also flushes the entry's compressor, so that all data written so far becomes visible to the
+/// underlying writer (eg. a reader on the other end of a pipe). Compressors must end their current block to do so,
+/// so flushing frequently reduces the compression ratio.
 pub struct EntryStreamWriter<'b, W: AsyncWrite + Unpin> {
-    writer: AsyncOffsetWriter<CompressedAsyncWriter<'b, W>>,
-    cd_entries: &'b mut Vec<CentralDirectoryEntry>,
+    writer: CompressedAsyncWriter<EncryptedAsyncWriter<&'b mut AsyncOffsetWriter<W>>>,
+    cd_records: &'b mut CentralDirectoryRecords,
+    duplicates: &'b mut DuplicateTracker,
+    poisoned: &'b mut bool,
     entry: ZipEntry,
     hasher: Hasher,
+    digest: Option<Box<dyn EntryDigest>>,
     lfh: LocalFileHeader,
     lfh_offset: usize,
+    lfh_position: (u16, u64),
+    seeker: Option<Seeker<W>>,
+    descriptor_style: DataDescriptorStyle,
     data_offset: usize,
+    bytes_written: u64,
+    progress: Option<Progress>,
 }
 
 impl<'b, W: AsyncWrite + Unpin> EntryStreamWriter<'b, W> {
@@ -37,44 +54,158 @@ impl<'b, W: AsyncWrite + Unpin> EntryStreamWriter<'b, W> {
         writer: &'b mut ZipFileWriter<W>,
         entry: ZipEntry,
     ) -> Result<EntryStreamWriter<'b, W>> {
+        let compression = entry.compression();
+        EntryStreamWriter::from_raw_with(writer, entry, compression).await
+    }
+
+    // Construct a writer for data which has already been compressed with the entry's compression method, which is
+    // written via write_precompressed() and close_precompressed().
+    pub(crate) async fn from_raw_precompressed(
+        writer: &'b mut ZipFileWriter<W>,
+        entry: ZipEntry,
+    ) -> Result<EntryStreamWriter<'b, W>> {
+        let mut entry_writer = EntryStreamWriter::from_raw_with(writer, entry, Compression::Stored).await?;
+
+        // The uncompressed data is never seen, so any digest is computed by the caller instead.
+        entry_writer.digest = None;
+        Ok(entry_writer)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "write_entry",
+            level = "debug",
+            skip_all,
+            fields(filename = entry.filename(), compression = ?entry.compression())
+        )
+    )]
+    async fn from_raw_with(
+        writer: &'b mut ZipFileWriter<W>,
+        mut entry: ZipEntry,
+        compression: Compression,
+    ) -> Result<EntryStreamWriter<'b, W>> {
+        crate::write::compressed_writer::check_compression(entry.compression())?;
+
+        // Cleared only once this writer has been closed, so dropping it beforehand poisons the ZIP writer.
+        writer.poisoned = true;
+
         let lfh_offset = writer.writer.offset();
-        let lfh = EntryStreamWriter::write_lfh(writer, &entry).await?;
+        let lfh = EntryStreamWriter::write_lfh(writer, &mut entry).await?;
         let data_offset = writer.writer.offset();
+        let lfh_position = writer.disk_position(lfh_offset);
+
+        let seeker = writer.seeker.filter(|_| !crate::write::encrypted_writer::requires_data_descriptor(&entry));
+        let descriptor_style = writer.descriptor_style;
+        let digest = writer.digest.as_ref().map(|factory| factory());
+        let cd_records = &mut writer.cd_records;
+        let duplicates = &mut writer.duplicates;
+        let poisoned = &mut writer.poisoned;
 
-        let cd_entries = &mut writer.cd_entries;
-        let writer = AsyncOffsetWriter::new(CompressedAsyncWriter::from_raw(&mut writer.writer, entry.compression()));
+        // The encryption header is counted as part of the entry's compressed data.
+        let check = (lfh.mod_time >> 8) as u8;
+        let writer = EncryptedAsyncWriter::start(&mut writer.writer, &entry, check).await?;
+        let writer = CompressedAsyncWriter::from_raw(writer, compression, entry.compression_level);
 
-        Ok(EntryStreamWriter { writer, cd_entries, entry, lfh, lfh_offset, data_offset, hasher: Hasher::new() })
+        Ok(EntryStreamWriter {
+            writer,
+            cd_records,
+            duplicates,
+            poisoned,
+            entry,
+            lfh,
+            lfh_offset,
+            lfh_position,
+            seeker,
+            descriptor_style,
+            data_offset,
+            hasher: Hasher::new(),
+            digest,
+            bytes_written: 0,
+            progress: None,
+        })
     }
 
-    async fn write_lfh(writer: &'b mut ZipFileWriter<W>, entry: &ZipEntry) -> Result<LocalFileHeader> {
-        let (mod_time, mod_date) = crate::spec::date::chrono_to_zip_time(entry.last_modification_date());
+    async fn write_lfh(writer: &'b mut ZipFileWriter<W>, entry: &mut ZipEntry) -> Result<LocalFileHeader> {
+        let (mod_time, mod_date) = writer.entry_zip_time(entry);
+
+        // The sizes aren't yet known, so a ZIP64 record is only reserved (with zeroed values) if forced.
+        if entry.force_zip64 {
+            crate::write::set_zip64_field(entry, &[0, 0]);
+        }
+        let compression = crate::write::encrypted_writer::prepare(entry);
+        let extra_field = crate::write::local_extra_field(entry, writer.writer.offset());
+
+        let flags = GeneralPurposeFlag {
+            data_descriptor: writer.seeker.is_none() || crate::write::encrypted_writer::requires_data_descriptor(entry),
+            encrypted: entry.encryption.is_some(),
+            patched_data: false,
+            strong_encryption: false,
+            filename_unicode: crate::write::requires_utf8_flag(entry),
+            compression_options: 0,
+        };
+        let mut version = crate::spec::version::as_needed_to_extract(entry, &flags);
 
+        // As with whole entries, the central directory record's offset may already be known to require ZIP64.
+        if writer.disk_position(writer.writer.offset()).1 >= u64::from(u32::MAX) {
+            version = version.max(crate::spec::version::ZIP64_VERSION_NEEDED);
+        }
+
+        let sizes = if entry.force_zip64 { u32::MAX } else { 0 };
         let lfh = LocalFileHeader {
-            compressed_size: 0,
-            uncompressed_size: 0,
-            compression: entry.compression().into(),
+            compressed_size: sizes,
+            uncompressed_size: sizes,
+            compression,
             crc: 0,
-            extra_field_length: entry.extra_field().len() as u16,
+            extra_field_length: extra_field.len() as u16,
             file_name_length: entry.filename().as_bytes().len() as u16,
             mod_time,
             mod_date,
-            version: crate::spec::version::as_needed_to_extract(entry),
-            flags: GeneralPurposeFlag {
-                data_descriptor: true,
-                encrypted: false,
-                filename_unicode: !entry.filename().is_ascii(),
-            },
+            version: entry.version_needed.unwrap_or(version),
+            flags,
         };
 
-        writer.writer.write_all(&crate::spec::signature::LOCAL_FILE_HEADER.to_le_bytes()).await?;
-        writer.writer.write_all(&lfh.as_slice()).await?;
+        writer.writer.write_all(&lfh.to_bytes()).await?;
         writer.writer.write_all(entry.filename().as_bytes()).await?;
-        writer.writer.write_all(entry.extra_field()).await?;
+        writer.writer.write_all(&extra_field).await?;
 
         Ok(lfh)
     }
 
+    /// Returns the number of uncompressed bytes written to this entry so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Returns a handle which observes the number of compressed bytes written for this entry (including any encryption
+    /// header), unlike [`EntryStreamWriter::bytes_written()`].
+    ///
+    /// The handle is updated on each write, so may be polled from another task. Compressors buffer their output, so
+    /// the count only advances as they emit data (or on flushing), and reaches the entry's compressed size once it's
+    /// closed. Writers which never have a handle requested don't incur the cost of these updates.
+    pub fn progress(&mut self) -> Progress {
+        let progress = self.progress.get_or_insert_with(|| Progress::new(0)).clone();
+        self.update_progress();
+        progress
+    }
+
+    // Update any progress handle with the number of compressed bytes written so far.
+    fn update_progress(&mut self) {
+        if let Some(progress) = &self.progress {
+            progress.set((self.writer.get_mut().get_mut().offset() - self.data_offset) as u64);
+        }
+    }
+
+    /// Returns a sink which writes each chunk of bytes it's sent to this entry.
+    ///
+    /// This allows a stream of chunks (eg. a HTTP request body) to be forwarded directly into an entry. Closing the
+    /// sink doesn't close the entry, so [`EntryStreamWriter::close()`] must still be called once the sink has been
+    /// dropped.
+    #[cfg(feature = "stream")]
+    pub fn sink_bytes(&mut self) -> EntryBytesSink<'_, 'b, W> {
+        EntryBytesSink { writer: self, chunk: bytes::Bytes::new() }
+    }
+
     /// Consumes this entry writer and completes all closing tasks.
     ///
     /// This includes:
@@ -83,41 +214,114 @@ impl<'b, W: AsyncWrite + Unpin> EntryStreamWriter<'b, W> {
     /// - Constructing a central directory header.
     /// - Pushing that central directory header to the [`ZipFileWriter`]'s store.
     ///
+    /// The returned [`WrittenEntry`] holds the CRC32 value and sizes which were written to the data descriptor (or
+    /// patched into the local file header, see [`ZipFileWriter::patch_local_headers()`]).
+    ///
+    /// Where the local file header is patched, this fails with [`ZipError::Zip64NotReserved`] if the entry's sizes
+    /// require ZIP64 but the header didn't reserve space for them (see [`ZipEntryBuilder::force_zip64()`]).
+    ///
+    /// [`ZipEntryBuilder::force_zip64()`]: crate::entry::builder::ZipEntryBuilder::force_zip64
+    ///
     /// Failiure to call this function before going out of scope would result in a corrupted ZIP file.
-    pub async fn close(mut self) -> Result<()> {
+    pub async fn close(mut self) -> Result<WrittenEntry> {
+        let crc = std::mem::take(&mut self.hasher).finalize();
+        let uncompressed_size = self.bytes_written;
+
+        self.close_precompressed(crc, uncompressed_size).await
+    }
+
+    // Write data which has already been compressed, bypassing the hasher and the count of uncompressed bytes.
+    pub(crate) async fn write_precompressed(&mut self, data: &[u8]) -> Result<()> {
+        Ok(self.writer.write_all(data).await?)
+    }
+
+    // Complete all closing tasks, given the CRC32 value and size of the uncompressed data.
+    pub(crate) async fn close_precompressed(mut self, crc: u32, uncompressed_size: u64) -> Result<WrittenEntry> {
         self.writer.shutdown().await?;
 
-        let crc = self.hasher.finalize();
-        let uncompressed_size = self.writer.offset() as u32;
-        let inner_writer = self.writer.into_inner().into_inner();
-        let compressed_size = (inner_writer.offset() - self.data_offset) as u32;
+        let inner_writer = self.writer.into_inner().finish().await?;
+        let crc = crate::write::encrypted_writer::recorded_crc(&self.entry, crc);
+        let compressed_size = (inner_writer.offset() - self.data_offset) as u64;
+        if let Some(progress) = &self.progress {
+            progress.set(compressed_size);
+        }
+        let exceeds = compressed_size >= u64::from(u32::MAX) || uncompressed_size >= u64::from(u32::MAX);
 
-        inner_writer.write_all(&crate::spec::signature::DATA_DESCRIPTOR.to_le_bytes()).await?;
-        inner_writer.write_all(&crc.to_le_bytes()).await?;
-        inner_writer.write_all(&compressed_size.to_le_bytes()).await?;
-        inner_writer.write_all(&uncompressed_size.to_le_bytes()).await?;
+        // Without a reserved ZIP64 record, the local file header's fields can't hold larger sizes.
+        if self.seeker.is_some() && exceeds && !self.entry.force_zip64 {
+            return Err(ZipError::Zip64NotReserved(self.entry.filename().to_owned()));
+        }
+
+        if let Some(seeker) = self.seeker {
+            let (sizes, zip64) = crate::write::local_zip64_split(&self.entry, [uncompressed_size, compressed_size]);
+            self.lfh.crc = crc;
+            self.lfh.compressed_size = sizes[1];
+            self.lfh.uncompressed_size = sizes[0];
+
+            // Any reserved ZIP64 record directly follows the filename, so is patched alongside the header.
+            let mut patch = self.lfh.to_bytes();
+            if !zip64.is_empty() {
+                patch.extend_from_slice(self.entry.filename().as_bytes());
+                crate::write::zip64_field(&zip64).write_to(&mut patch);
+            }
+
+            // Overwrite the local file header in place (bypassing the offset count), then return to the data's end.
+            let distance = (inner_writer.offset() - self.lfh_offset) as i64;
+            seeker.seek(inner_writer.inner_mut(), SeekFrom::Current(-distance)).await?;
+            inner_writer.inner_mut().write_all(&patch).await?;
+            seeker.seek(inner_writer.inner_mut(), SeekFrom::Current(distance - patch.len() as i64)).await?;
+        } else {
+            // Readers expect a ZIP64 data descriptor wherever the local file header holds a ZIP64 record, and it's
+            // the only layout which can hold larger sizes otherwise.
+            let zip64 = self.entry.force_zip64 || exceeds;
+            let descriptor = self.descriptor_style.descriptor(crc, compressed_size, uncompressed_size, zip64);
+            inner_writer.write_all(&descriptor).await?;
+        }
+
+        let values = [uncompressed_size, compressed_size, self.lfh_position.1];
+        let (fields, zip64) = crate::write::central_zip64_split(values);
+        if self.entry.force_zip64 || !zip64.is_empty() {
+            crate::write::set_zip64_field(&mut self.entry, &zip64);
+        }
+
+        // The local file header couldn't know whether the sizes would require ZIP64.
+        let v_needed = match self.entry.version_needed {
+            None if !zip64.is_empty() => self.lfh.version.max(crate::spec::version::ZIP64_VERSION_NEEDED),
+            _ => self.lfh.version,
+        };
 
         let cdh = CentralDirectoryHeader {
-            compressed_size,
-            uncompressed_size,
+            compressed_size: fields[1],
+            uncompressed_size: fields[0],
             crc,
-            v_made_by: crate::spec::version::as_made_by(),
-            v_needed: self.lfh.version,
+            v_made_by: self.entry.version_made_by.unwrap_or_else(crate::spec::version::as_made_by),
+            v_needed,
             compression: self.lfh.compression,
-            extra_field_length: self.lfh.extra_field_length,
+            extra_field_length: self.entry.extra_field().len() as u16,
             file_name_length: self.lfh.file_name_length,
             file_comment_length: self.entry.comment().len() as u16,
             mod_time: self.lfh.mod_time,
             mod_date: self.lfh.mod_date,
             flags: self.lfh.flags,
-            disk_start: 0,
+            disk_start: self.lfh_position.0,
             inter_attr: self.entry.internal_file_attribute(),
             exter_attr: self.entry.external_file_attribute(),
-            lh_offset: self.lfh_offset as u32,
+            lh_offset: fields[2],
         };
 
-        self.cd_entries.push(CentralDirectoryEntry { header: cdh, entry: self.entry });
-        Ok(())
+        let mut written = WrittenEntry::new(crc, compressed_size, uncompressed_size, self.lfh_position.1);
+        written.digest = self.digest.take().map(|digest| digest.finalize());
+        crate::trace::debug!(
+            filename = self.entry.filename(),
+            crc,
+            compressed_size,
+            uncompressed_size,
+            "closed entry stream writer"
+        );
+        self.duplicates.push(self.cd_records, &cdh, &self.entry);
+        *self.poisoned = false;
+
+        Ok(written)
     }
 }
 
@@ -127,16 +331,110 @@ impl<'a, 'b, W: AsyncWrite + Unpin> AsyncWrite for EntryStreamWriter<'b, W> {
 
         if let Poll::Ready(Ok(written)) = poll {
             self.hasher.update(&buf[0..written]);
+            if let Some(digest) = &mut self.digest {
+                digest.update(&buf[0..written]);
+            }
+            self.bytes_written += written as u64;
+            self.update_progress();
         }
 
         poll
     }
 
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::result::Result<usize, Error>> {
+        let poll = Pin::new(&mut self.writer).poll_write_vectored(cx, bufs);
+
+        if let Poll::Ready(Ok(written)) = poll {
+            let mut remaining = written;
+
+            for buf in bufs {
+                let hashed = remaining.min(buf.len());
+                self.hasher.update(&buf[0..hashed]);
+                if let Some(digest) = &mut self.digest {
+                    digest.update(&buf[0..hashed]);
+                }
+                remaining -= hashed;
+
+                if remaining == 0 {
+                    break;
+                }
+            }
+
+            self.bytes_written += written as u64;
+            self.update_progress();
+        }
+
+        poll
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.writer.is_write_vectored()
+    }
+
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
-        Pin::new(&mut self.writer).poll_flush(cx)
+        let poll = Pin::new(&mut self.writer).poll_flush(cx);
+        self.update_progress();
+        poll
     }
 
     fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
         Pin::new(&mut self.writer).poll_shutdown(cx)
     }
 }
+
+/// A sink which writes each chunk of bytes it's sent to an entry, as returned by [`EntryStreamWriter::sink_bytes()`].
+///
+/// Flushing the sink also flushes the entry writer (and so its compressor, see [`EntryStreamWriter`]), whereas
+/// closing the sink only waits for the last chunk to be written.
+#[cfg(feature = "stream")]
+pub struct EntryBytesSink<'s, 'b, W: AsyncWrite + Unpin> {
+    writer: &'s mut EntryStreamWriter<'b, W>,
+    chunk: bytes::Bytes,
+}
+
+#[cfg(feature = "stream")]
+impl<'s, 'b, W: AsyncWrite + Unpin> EntryBytesSink<'s, 'b, W> {
+    // Write whatever remains of the chunk last sent.
+    fn poll_write_chunk(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        while !self.chunk.is_empty() {
+            let written = std::task::ready!(Pin::new(&mut *self.writer).poll_write(cx, &self.chunk))?;
+
+            if written == 0 {
+                return Poll::Ready(Err(Error::from(std::io::ErrorKind::WriteZero).into()));
+            }
+
+            bytes::Buf::advance(&mut self.chunk, written);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<'s, 'b, W: AsyncWrite + Unpin> futures_util::Sink<bytes::Bytes> for EntryBytesSink<'s, 'b, W> {
+    type Error = crate::error::ZipError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().poll_write_chunk(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, chunk: bytes::Bytes) -> Result<()> {
+        self.get_mut().chunk = chunk;
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        std::task::ready!(this.poll_write_chunk(cx))?;
+
+        Pin::new(&mut *this.writer).poll_flush(cx).map_err(Into::into)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().poll_write_chunk(cx)
+    }
+}
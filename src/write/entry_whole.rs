@@ -1,129 +1,288 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
-use crate::error::Result;
+use crate::error::{Result, ZipError};
 use crate::spec::compression::Compression;
 use crate::spec::header::{CentralDirectoryHeader, GeneralPurposeFlag, LocalFileHeader};
-use crate::write::{CentralDirectoryEntry, ZipFileWriter};
+use crate::write::encrypted_writer::EncryptedAsyncWriter;
+use crate::write::offset_writer::AsyncOffsetWriter;
+use crate::write::{WrittenEntry, ZipFileWriter};
 use crate::entry::ZipEntry;
+use crate::progress::Progress;
 
 #[cfg(any(feature = "deflate", feature = "bzip2", feature = "zstd", feature = "lzma", feature = "xz"))]
 use std::io::Cursor;
+use std::io::IoSlice;
 
 #[cfg(any(feature = "deflate", feature = "bzip2", feature = "zstd", feature = "lzma", feature = "xz"))]
 use async_compression::tokio::write;
 use crc32fast::Hasher;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
+// The size of the chunks an entry's data is written in, so that any progress handle is updated as it's written.
+const PROGRESS_CHUNK_SIZE: usize = 64 * 1024;
+
 pub struct EntryWholeWriter<'b, 'c, W: AsyncWrite + Unpin> {
     writer: &'b mut ZipFileWriter<W>,
     entry: ZipEntry,
-    data: &'c [u8],
+    parts: &'c [IoSlice<'c>],
 }
 
 impl<'b, 'c, W: AsyncWrite + Unpin> EntryWholeWriter<'b, 'c, W> {
-    pub fn from_raw(writer: &'b mut ZipFileWriter<W>, entry: ZipEntry, data: &'c [u8]) -> Self {
-        Self { writer, entry, data }
+    pub fn from_raw(writer: &'b mut ZipFileWriter<W>, entry: ZipEntry, parts: &'c [IoSlice<'c>]) -> Self {
+        Self { writer, entry, parts }
     }
 
-    pub async fn write(self) -> Result<()> {
-        let mut _compressed_data: Option<Vec<u8>> = None;
-        let compressed_data = match self.entry.compression() {
-            Compression::Stored => self.data,
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "write_entry",
+            level = "debug",
+            skip_all,
+            fields(filename = self.entry.filename(), compression = ?self.entry.compression())
+        )
+    )]
+    pub async fn write(mut self) -> Result<WrittenEntry> {
+        let uncompressed_size: usize = self.parts.iter().map(|part| part.len()).sum();
+
+        // The parts are written directly when stored, so only compressed data is ever buffered.
+        #[allow(unused_mut)]
+        let mut compressed_data: Option<Vec<u8>> = None;
+
+        #[cfg(feature = "deflate")]
+        if let Some(threshold) = self.entry.auto_compression {
+            let level = self.entry.compression_level;
+            (self.entry.compression, compressed_data) = select_compression(self.parts, threshold, level).await;
+        }
+
+        match self.entry.compression() {
+            _ if compressed_data.is_some() => {}
+            Compression::Stored => {}
+            Compression::Unknown(value) => return Err(ZipError::UnsupportedCompressionError(value)),
+            #[cfg(feature = "deflate64")]
+            Compression::Deflate64 => return Err(ZipError::FeatureNotSupported("Deflate64 compression when writing")),
+            Compression::Shrink | Compression::Reduce(_) | Compression::Implode => {
+                return Err(ZipError::FeatureNotSupported("Legacy compression methods when writing"));
+            }
             #[cfg(any(feature = "deflate", feature = "bzip2", feature = "zstd", feature = "lzma", feature = "xz"))]
             _ => {
-                _compressed_data = Some(compress(self.entry.compression(), self.data, self.entry.compression_level).await);
-                _compressed_data.as_ref().unwrap()
+                compressed_data =
+                    Some(compress(self.entry.compression(), self.parts, self.entry.compression_level).await);
             }
         };
 
-        let (mod_time, mod_date) = crate::spec::date::chrono_to_zip_time(self.entry.last_modification_date());
+        let compressed_size = compressed_data.as_ref().map(Vec::len).unwrap_or(uncompressed_size) as u64;
+        let compressed_size = compressed_size + crate::write::encrypted_writer::overhead(&self.entry);
+        let uncompressed_size = uncompressed_size as u64;
+        let compression = crate::write::encrypted_writer::prepare(&mut self.entry);
+        let crc = compute_crc(self.parts);
+        let (mod_time, mod_date) = self.writer.entry_zip_time(&self.entry);
+        let digest = self.writer.digest.as_ref().map(|factory| crate::write::digest::compute(factory, self.parts));
+        let lh_offset = self.writer.writer.offset();
+
+        // Each header is given a ZIP64 record only where its own values require one.
+        let sizes = [uncompressed_size, compressed_size];
+        let (local_sizes, local_zip64) = crate::write::local_zip64_split(&self.entry, sizes);
+        if !local_zip64.is_empty() {
+            crate::write::set_zip64_field(&mut self.entry, &local_zip64);
+        }
+        let extra_field = crate::write::local_extra_field(&self.entry, lh_offset).into_owned();
+
+        let flags = GeneralPurposeFlag {
+            data_descriptor: false,
+            encrypted: self.entry.encryption.is_some(),
+            patched_data: false,
+            strong_encryption: false,
+            filename_unicode: crate::write::requires_utf8_flag(&self.entry),
+            compression_options: 0,
+        };
+        let mut version = crate::spec::version::as_needed_to_extract(&self.entry, &flags);
+
+        // The version must match the central directory record's, which also requires ZIP64 if the header's offset
+        // can't be held (a split ZIP file's parts are each limited to 4 GiB, so this is known before it's written).
+        if self.writer.disk_position(lh_offset).1 >= u64::from(u32::MAX) {
+            version = version.max(crate::spec::version::ZIP64_VERSION_NEEDED);
+        }
 
         let lf_header = LocalFileHeader {
-            compressed_size: compressed_data.len() as u32,
-            uncompressed_size: self.data.len() as u32,
-            compression: self.entry.compression().into(),
-            crc: compute_crc(self.data),
-            extra_field_length: self.entry.extra_field().len() as u16,
+            compressed_size: local_sizes[1],
+            uncompressed_size: local_sizes[0],
+            compression,
+            crc: crate::write::encrypted_writer::recorded_crc(&self.entry, crc),
+            extra_field_length: extra_field.len() as u16,
             file_name_length: self.entry.filename().as_bytes().len() as u16,
             mod_time,
             mod_date,
-            version: crate::spec::version::as_needed_to_extract(&self.entry),
-            flags: GeneralPurposeFlag {
-                data_descriptor: false,
-                encrypted: false,
-                filename_unicode: !self.entry.filename().is_ascii(),
-            },
+            version: self.entry.version_needed.unwrap_or(version),
+            flags,
         };
 
+        // Cleared only once the entry's been fully written, so a failed or cancelled write poisons the ZIP writer.
+        self.writer.poisoned = true;
+        if let Some(progress) = &self.writer.progress {
+            progress.set(0);
+        }
+
+        self.writer.writer.write_all(&lf_header.to_bytes()).await?;
+        self.writer.writer.write_all(self.entry.filename().as_bytes()).await?;
+        self.writer.writer.write_all(&extra_field).await?;
+
+        // Only known once the header's been written, as a split ZIP file may have moved onto a new part to fit it.
+        let (disk_start, lh_offset) = self.writer.disk_position(lh_offset);
+        let (central_fields, central_zip64) = crate::write::central_zip64_split([sizes[0], sizes[1], lh_offset]);
+        if !local_zip64.is_empty() || !central_zip64.is_empty() {
+            crate::write::set_zip64_field(&mut self.entry, &central_zip64);
+        }
         let header = CentralDirectoryHeader {
-            v_made_by: crate::spec::version::as_made_by(),
+            v_made_by: self.entry.version_made_by.unwrap_or_else(crate::spec::version::as_made_by),
             v_needed: lf_header.version,
-            compressed_size: lf_header.compressed_size,
-            uncompressed_size: lf_header.uncompressed_size,
+            compressed_size: central_fields[1],
+            uncompressed_size: central_fields[0],
             compression: lf_header.compression,
             crc: lf_header.crc,
-            extra_field_length: lf_header.extra_field_length,
+            extra_field_length: self.entry.extra_field().len() as u16,
             file_name_length: lf_header.file_name_length,
             file_comment_length: self.entry.comment().len() as u16,
             mod_time: lf_header.mod_time,
             mod_date: lf_header.mod_date,
             flags: lf_header.flags,
-            disk_start: 0,
+            disk_start,
             inter_attr: self.entry.internal_file_attribute(),
             exter_attr: self.entry.external_file_attribute(),
-            lh_offset: self.writer.writer.offset() as u32,
+            lh_offset: central_fields[2],
         };
 
-        self.writer.writer.write_all(&crate::spec::signature::LOCAL_FILE_HEADER.to_le_bytes()).await?;
-        self.writer.writer.write_all(&lf_header.as_slice()).await?;
-        self.writer.writer.write_all(self.entry.filename().as_bytes()).await?;
-        self.writer.writer.write_all(&self.entry.extra_field()).await?;
-        self.writer.writer.write_all(compressed_data).await?;
+        // No data descriptor follows the data, so any ZipCrypto check byte is taken from the CRC32 value.
+        let check = (crc >> 24) as u8;
+        let data_offset = self.writer.writer.offset();
+        let progress = self.writer.progress.as_ref();
+        let mut data_writer = EncryptedAsyncWriter::start(&mut self.writer.writer, &self.entry, check).await?;
+        match &compressed_data {
+            Some(compressed_data) => write_data(&mut data_writer, compressed_data, data_offset, progress).await?,
+            None => {
+                for part in self.parts {
+                    write_data(&mut data_writer, part, data_offset, progress).await?;
+                }
+            }
+        }
+        data_writer.finish().await?;
+
+        if let Some(progress) = progress {
+            progress.set(compressed_size);
+        }
 
-        self.writer.cd_entries.push(CentralDirectoryEntry { header, entry: self.entry });
+        let mut written = WrittenEntry::new(header.crc, compressed_size, uncompressed_size, lh_offset);
+        written.digest = digest;
+        crate::trace::debug!(crc = header.crc, compressed_size, uncompressed_size, "wrote entry");
+        self.writer.duplicates.push(&mut self.writer.cd_records, &header, &self.entry);
+        self.writer.poisoned = false;
 
-        Ok(())
+        Ok(written)
     }
 }
 
+// Write a slice of an entry's data in chunks, updating any progress handle with the number of compressed bytes written
+// since the data's start (which includes any encryption header) after each.
+async fn write_data<W: AsyncWrite + Unpin>(
+    writer: &mut EncryptedAsyncWriter<&mut AsyncOffsetWriter<W>>,
+    data: &[u8],
+    data_offset: usize,
+    progress: Option<&Progress>,
+) -> std::io::Result<()> {
+    for chunk in data.chunks(PROGRESS_CHUNK_SIZE) {
+        writer.write_all(chunk).await?;
+
+        if let Some(progress) = progress {
+            progress.set((writer.get_mut().offset() - data_offset) as u64);
+        }
+    }
+
+    Ok(())
+}
+
+// Choose between the Stored and Deflate compression methods by compressing a sample of an entry's data. Where the
+// sample holds all of the data and Deflate is chosen, the compressed sample is also returned so it's not compressed
+// twice.
+#[cfg(feature = "deflate")]
+async fn select_compression(
+    parts: &[IoSlice<'_>],
+    threshold: f32,
+    level: async_compression::Level,
+) -> (Compression, Option<Vec<u8>>) {
+    let mut sample = Vec::new();
+    let mut remaining = crate::write::AUTO_COMPRESSION_SAMPLE_SIZE;
+
+    for part in parts.iter().filter(|part| !part.is_empty()) {
+        if remaining == 0 {
+            break;
+        }
+
+        let length = part.len().min(remaining);
+        sample.push(IoSlice::new(&part[..length]));
+        remaining -= length;
+    }
+
+    let sample_size = crate::write::AUTO_COMPRESSION_SAMPLE_SIZE - remaining;
+    if sample_size == 0 {
+        return (Compression::Stored, None);
+    }
+
+    let compressed = compress(Compression::Deflate, &sample, level).await;
+    if compressed.len() as f32 >= threshold * sample_size as f32 {
+        return (Compression::Stored, None);
+    }
+
+    let whole = sample_size == parts.iter().map(|part| part.len()).sum::<usize>();
+    (Compression::Deflate, whole.then_some(compressed))
+}
+
 #[cfg(any(feature = "deflate", feature = "bzip2", feature = "zstd", feature = "lzma", feature = "xz"))]
-async fn compress(compression: Compression, data: &[u8], level: async_compression::Level) -> Vec<u8> {
+async fn compress(compression: Compression, parts: &[IoSlice<'_>], level: async_compression::Level) -> Vec<u8> {
     // TODO: Reduce reallocations of Vec by making a lower-bound estimate of the length reduction and
     // pre-initialising the Vec to that length. Then truncate() to the actual number of bytes written.
     match compression {
         #[cfg(feature = "deflate")]
         Compression::Deflate => {
             let mut writer = write::DeflateEncoder::with_quality(Cursor::new(Vec::new()), level);
-            writer.write_all(data).await.unwrap();
+            for part in parts {
+                writer.write_all(part).await.unwrap();
+            }
             writer.shutdown().await.unwrap();
             writer.into_inner().into_inner()
         }
         #[cfg(feature = "bzip2")]
         Compression::Bz => {
             let mut writer = write::BzEncoder::with_quality(Cursor::new(Vec::new()), level);
-            writer.write_all(data).await.unwrap();
+            for part in parts {
+                writer.write_all(part).await.unwrap();
+            }
             writer.shutdown().await.unwrap();
             writer.into_inner().into_inner()
         }
         #[cfg(feature = "lzma")]
         Compression::Lzma => {
             let mut writer = write::LzmaEncoder::with_quality(Cursor::new(Vec::new()), level);
-            writer.write_all(data).await.unwrap();
+            for part in parts {
+                writer.write_all(part).await.unwrap();
+            }
             writer.shutdown().await.unwrap();
             writer.into_inner().into_inner()
         }
         #[cfg(feature = "xz")]
         Compression::Xz => {
             let mut writer = write::XzEncoder::with_quality(Cursor::new(Vec::new()), level);
-            writer.write_all(data).await.unwrap();
+            for part in parts {
+                writer.write_all(part).await.unwrap();
+            }
             writer.shutdown().await.unwrap();
             writer.into_inner().into_inner()
         }
         #[cfg(feature = "zstd")]
         Compression::Zstd => {
             let mut writer = write::ZstdEncoder::with_quality(Cursor::new(Vec::new()), level);
-            writer.write_all(data).await.unwrap();
+            for part in parts {
+                writer.write_all(part).await.unwrap();
+            }
             writer.shutdown().await.unwrap();
             writer.into_inner().into_inner()
         }
@@ -131,8 +290,12 @@ async fn compress(compression: Compression, data: &[u8], level: async_compressio
     }
 }
 
-fn compute_crc(data: &[u8]) -> u32 {
+fn compute_crc(parts: &[IoSlice<'_>]) -> u32 {
     let mut hasher = Hasher::new();
-    hasher.update(data);
+
+    for part in parts {
+        hasher.update(part);
+    }
+
     hasher.finalize()
 }
@@ -0,0 +1,387 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module which supports writing the contents of a filesystem directory tree into a ZIP file.
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::{Compression, write::ZipFileWriter};
+//! # use async_zip::write::fs::{write_dir, DirWriteOptions, SymlinkPolicy};
+//! # use tokio::fs::File;
+//! # use async_zip::error::ZipError;
+//! # use std::path::Path;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let mut file = File::create("project.zip").await?;
+//! let mut writer = ZipFileWriter::new(&mut file);
+//!
+//! let options = DirWriteOptions::new()
+//!     .prefix("project-1.0/")
+//!     .exclude("target")
+//!     .skip_hidden(true)
+//!     .symlinks(SymlinkPolicy::Store);
+//!
+//! write_dir(&mut writer, Path::new("./project"), options).await?;
+//! writer.close().await?;
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::entry::builder::ZipEntryBuilder;
+use crate::entry::ext::ZipEntryBuilderExt;
+use crate::entry::CompressionLevel;
+use crate::error::{Result, ZipError};
+use crate::spec::compression::Compression;
+use crate::write::{WrittenEntry, ZipFileWriter, DEFAULT_COPY_BUFFER_SIZE};
+
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWrite;
+
+/// How symbolic links within a directory tree are written by [`write_dir()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Write the file or directory which the link points to, as if it were at the link's path.
+    ///
+    /// Links to directories which have already been walked (eg. a link to a parent directory) are skipped.
+    Follow,
+    /// Write a symbolic link entry holding the link's target (see [`ZipEntryBuilderExt::symlink()`]).
+    Store,
+    /// Don't write links at all.
+    Skip,
+}
+
+/// A set of options which control how a directory tree is written by [`write_dir()`].
+///
+/// The defaults write every file and directory within the tree (including hidden ones) using the Stored compression
+/// method, with each entry named by its path relative to the tree's root and symbolic links followed.
+#[derive(Clone)]
+pub struct DirWriteOptions {
+    pub(crate) prefix: String,
+    pub(crate) strip_prefix: Option<PathBuf>,
+    pub(crate) compression: Arc<dyn Fn(&Path) -> Compression + Send + Sync>,
+    pub(crate) compression_level: CompressionLevel,
+    pub(crate) include_root: bool,
+    pub(crate) symlinks: SymlinkPolicy,
+    pub(crate) skip_hidden: bool,
+    pub(crate) exclude: Vec<String>,
+    pub(crate) buffer_size: usize,
+}
+
+impl Default for DirWriteOptions {
+    fn default() -> Self {
+        Self {
+            prefix: String::new(),
+            strip_prefix: None,
+            compression: Arc::new(|_| Compression::Stored),
+            compression_level: CompressionLevel::Default,
+            include_root: false,
+            symlinks: SymlinkPolicy::Follow,
+            skip_hidden: false,
+            exclude: Vec::new(),
+            buffer_size: DEFAULT_COPY_BUFFER_SIZE,
+        }
+    }
+}
+
+impl DirWriteOptions {
+    /// Constructs a new set of options with their default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a prefix which is added to the start of every entry's filename (eg. `project-1.0/`).
+    ///
+    /// The prefix is added as-is, so it should end with a forward slash if it's intended to be a directory.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_owned();
+        self
+    }
+
+    /// Sets a leading path which is removed from each path relative to the tree's root before it's used as a filename.
+    ///
+    /// Paths which don't begin with the provided path are left unchanged, and no entry is written for the directory
+    /// itself.
+    pub fn strip_prefix<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.strip_prefix = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets a callback which decides upon the compression method of each file, given its path relative to the tree's
+    /// root.
+    /// ```
+    /// # use async_zip::Compression;
+    /// # use async_zip::write::fs::DirWriteOptions;
+    /// #
+    /// let options = DirWriteOptions::new().compression(|path| match path.extension() {
+    ///     Some(extension) if extension == "jpg" || extension == "zip" => Compression::Stored,
+    ///     _ => Compression::Deflate,
+    /// });
+    /// ```
+    ///
+    /// ## Note
+    /// Directories and symbolic links are always written using the Stored compression method.
+    pub fn compression<F>(mut self, compression: F) -> Self
+    where
+        F: Fn(&Path) -> Compression + Send + Sync + 'static,
+    {
+        self.compression = Arc::new(compression);
+        self
+    }
+
+    /// Returns the compression method which will be used for a file, given its path relative to the tree's root.
+    pub fn compression_for(&self, path: &Path) -> Compression {
+        (self.compression)(path)
+    }
+
+    /// Sets the compression level applied to every file.
+    pub fn compression_level(mut self, level: CompressionLevel) -> Self {
+        self.compression_level = level;
+        self
+    }
+
+    /// Sets whether or not the tree's root directory itself is written as an entry, with every other entry named
+    /// beneath it (eg. `project/src/lib.rs` rather than `src/lib.rs`). Defaults to false.
+    ///
+    /// The root's name is added after any [prefix](DirWriteOptions::prefix()).
+    pub fn include_root(mut self, include: bool) -> Self {
+        self.include_root = include;
+        self
+    }
+
+    /// Sets how symbolic links are written.
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Sets whether or not hidden files and directories (ie. those whose names begin with a dot) are skipped.
+    pub fn skip_hidden(mut self, skip: bool) -> Self {
+        self.skip_hidden = skip;
+        self
+    }
+
+    /// Adds a glob-style pattern of paths to exclude, along with everything beneath them.
+    ///
+    /// Patterns are matched against paths relative to the tree's root, using forward slashes as separators. `?`
+    /// matches any single character and `*` matches any run of characters, but neither match a separator, whereas
+    /// `**` matches any run of characters including separators. A pattern without any separators (eg. `*.log`) is
+    /// instead matched against each file or directory's name alone.
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(pattern.to_owned());
+        self
+    }
+
+    /// Sets the size of the buffer used to copy each file's contents into its entry.
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size;
+        self
+    }
+
+    // Whether or not a path relative to the tree's root should be skipped (along with anything beneath it).
+    fn is_excluded(&self, relative: &str) -> bool {
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+
+        if self.skip_hidden && name.starts_with('.') {
+            return true;
+        }
+
+        self.exclude.iter().any(|pattern| match pattern.contains('/') {
+            true => glob_matches(pattern.as_bytes(), relative.as_bytes()),
+            false => glob_matches(pattern.as_bytes(), name.as_bytes()),
+        })
+    }
+
+    // Map a path relative to the tree's root onto an entry's filename, or None if no entry should be written for it.
+    fn filename(&self, relative: &Path, directory: bool) -> Option<String> {
+        let relative = match &self.strip_prefix {
+            Some(prefix) => relative.strip_prefix(prefix).unwrap_or(relative),
+            None => relative,
+        };
+
+        if relative.as_os_str().is_empty() {
+            return None;
+        }
+
+        // Components are always valid UTF-8, as the path has already been converted from a string.
+        let components: Vec<_> = relative.components().map(|part| part.as_os_str().to_str().unwrap()).collect();
+        let mut filename = format!("{}{}", self.prefix, components.join("/"));
+
+        if directory {
+            filename.push('/');
+        }
+
+        Some(filename)
+    }
+}
+
+impl std::fmt::Debug for DirWriteOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DirWriteOptions")
+            .field("prefix", &self.prefix)
+            .field("strip_prefix", &self.strip_prefix)
+            .field("compression_level", &self.compression_level)
+            .field("include_root", &self.include_root)
+            .field("symlinks", &self.symlinks)
+            .field("skip_hidden", &self.skip_hidden)
+            .field("exclude", &self.exclude)
+            .field("buffer_size", &self.buffer_size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Writes every file and directory within a directory tree into a ZIP file, returning the written entries.
+///
+/// The tree is walked depth-first with each directory's children in name order, so the output is deterministic. A
+/// directory entry is written for each directory, and each file's contents are streamed into its entry (see
+/// [`ZipFileWriter::write_entry_copy()`]). Each entry's last modification date is taken from the filesystem, as are
+/// its Unix permissions on Unix platforms.
+///
+/// # Note
+/// - Paths which aren't valid UTF-8 fail with [`ZipError::NonUtf8Path`].
+/// - Files which are neither regular files, directories, nor symbolic links (eg. sockets) are skipped.
+pub async fn write_dir<W: AsyncWrite + Unpin>(
+    writer: &mut ZipFileWriter<W>,
+    dir: &Path,
+    mut options: DirWriteOptions,
+) -> Result<Vec<WrittenEntry>> {
+    let mut written = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![PathBuf::new()];
+
+    // The root is named as it's resolved (rather than as provided), so that a path such as `.` is named meaningfully.
+    if options.include_root {
+        let root = tokio::fs::canonicalize(dir).await?;
+
+        if let Some(name) = root.file_name() {
+            let name = name.to_str().ok_or_else(|| ZipError::NonUtf8Path(root.clone()))?;
+            options.prefix = format!("{}{}/", options.prefix, name);
+
+            let metadata = tokio::fs::metadata(dir).await?;
+            let builder = with_metadata(ZipEntryBuilder::dir(&options.prefix), &metadata);
+            written.push(writer.write_entry_whole(builder, &[]).await?);
+        }
+    }
+
+    if options.symlinks == SymlinkPolicy::Follow {
+        visited.insert(tokio::fs::canonicalize(dir).await?);
+    }
+
+    while let Some(relative_dir) = pending.pop() {
+        let mut names = Vec::new();
+        let mut read_dir = tokio::fs::read_dir(dir.join(&relative_dir)).await?;
+
+        while let Some(child) = read_dir.next_entry().await? {
+            names.push(child.file_name());
+        }
+
+        names.sort();
+        let mut directories = Vec::new();
+
+        for name in names {
+            let path = dir.join(&relative_dir).join(&name);
+            let relative = relative_dir.join(&name);
+            let relative_str = relative.to_str().ok_or_else(|| ZipError::NonUtf8Path(path.clone()))?;
+
+            if options.is_excluded(&relative_str.replace(std::path::MAIN_SEPARATOR, "/")) {
+                continue;
+            }
+
+            let mut metadata = tokio::fs::symlink_metadata(&path).await?;
+
+            if metadata.file_type().is_symlink() {
+                match options.symlinks {
+                    SymlinkPolicy::Follow => metadata = tokio::fs::metadata(&path).await?,
+                    SymlinkPolicy::Skip => continue,
+                    SymlinkPolicy::Store => {
+                        let target = tokio::fs::read_link(&path).await?;
+                        let target = target.to_str().ok_or_else(|| ZipError::NonUtf8Path(target.clone()))?;
+
+                        if let Some(filename) = options.filename(&relative, false) {
+                            let builder = ZipEntryBuilder::new(filename, Compression::Stored);
+                            let builder = with_metadata(builder, &metadata).symlink(target);
+                            written.push(writer.write_entry_whole(builder, &[]).await?);
+                        }
+                        continue;
+                    }
+                }
+            }
+
+            if metadata.is_dir() {
+                // When following links, a link to a directory which has already been walked would otherwise loop.
+                if options.symlinks == SymlinkPolicy::Follow && !visited.insert(tokio::fs::canonicalize(&path).await?) {
+                    continue;
+                }
+
+                if let Some(filename) = options.filename(&relative, true) {
+                    let builder = with_metadata(ZipEntryBuilder::dir(&filename), &metadata);
+                    written.push(writer.write_entry_whole(builder, &[]).await?);
+                }
+
+                directories.push(relative);
+            } else if metadata.is_file() {
+                if let Some(filename) = options.filename(&relative, false) {
+                    let compression = options.compression_for(&relative);
+                    let builder = ZipEntryBuilder::new(filename, compression);
+                    let builder = with_metadata(builder.set_compression_level(options.compression_level), &metadata);
+                    let file = tokio::fs::File::open(&path).await?;
+
+                    written.push(writer.write_entry_copy(builder, file, options.buffer_size).await?);
+                }
+            }
+        }
+
+        // Walked in reverse as the last directory pushed is the first popped.
+        pending.extend(directories.into_iter().rev());
+    }
+
+    Ok(written)
+}
+
+// Apply a file's last modification date and (on Unix platforms) its permissions mode to an entry.
+fn with_metadata(mut builder: ZipEntryBuilder, metadata: &Metadata) -> ZipEntryBuilder {
+    if let Ok(modified) = metadata.modified() {
+        builder = builder.last_modification_date(DateTime::<Utc>::from(modified));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder = builder.unix_permissions(metadata.permissions().mode() as u16);
+    }
+
+    builder
+}
+
+// Match a glob-style pattern against a path (see DirWriteOptions::exclude()).
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|skip| glob_matches(rest, &text[skip..])),
+        [b'*', rest @ ..] => {
+            let run = text.iter().position(|&byte| byte == b'/').unwrap_or(text.len());
+            (0..=run).any(|skip| glob_matches(rest, &text[skip..]))
+        }
+        [b'?', rest @ ..] => matches!(text, [byte, text @ ..] if *byte != b'/' && glob_matches(rest, text)),
+        [byte, rest @ ..] => matches!(text, [other, text @ ..] if other == byte && glob_matches(rest, text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_test() {
+        assert!(glob_matches(b"*.log", b"build.log"));
+        assert!(!glob_matches(b"*.log", b"logs/build.log"));
+        assert!(glob_matches(b"logs/*.log", b"logs/build.log"));
+        assert!(glob_matches(b"**/*.log", b"a/b/build.log"));
+        assert!(glob_matches(b"file?.txt", b"file1.txt"));
+        assert!(!glob_matches(b"file?.txt", b"file/.txt"));
+        assert!(!glob_matches(b"target", b"targets"));
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module which supports merging the entries of multiple ZIP files into one, without recompressing any data.
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::write::ZipFileWriter;
+//! # use async_zip::write::merge::{merge_archives, MergePolicy, MergeSource};
+//! # use tokio::fs::File;
+//! # use async_zip::error::ZipError;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let mut file = File::create("weekly.zip").await?;
+//! let mut writer = ZipFileWriter::new(&mut file);
+//!
+//! let sources = vec![
+//!     MergeSource::new(File::open("monday.zip").await?).prefix("monday/"),
+//!     MergeSource::new(File::open("tuesday.zip").await?).prefix("tuesday/"),
+//! ];
+//!
+//! merge_archives(&mut writer, sources, MergePolicy::KeepFirst).await?;
+//! writer.close().await?;
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::error::{Result, ZipError};
+use crate::read::seek::ZipFileReader;
+use crate::write::{WrittenEntry, ZipFileWriter};
+
+use std::collections::hash_map::{Entry, HashMap};
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite};
+
+/// How entries with the same filename across multiple sources are resolved by [`merge_archives()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Write the entry from the earliest source, and skip any later copies.
+    KeepFirst,
+    /// Write the entry from the latest source, and skip any earlier copies.
+    KeepLast,
+    /// Return a [`ZipError::DuplicateFilename`] error before any entry is written.
+    Error,
+}
+
+/// A ZIP file to be merged by [`merge_archives()`], alongside a prefix for the filenames of its entries.
+pub struct MergeSource<R: AsyncRead + AsyncSeek + Unpin> {
+    pub(crate) reader: R,
+    pub(crate) prefix: String,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> MergeSource<R> {
+    /// Constructs a new source from a reader over a ZIP file.
+    pub fn new(reader: R) -> Self {
+        Self { reader, prefix: String::new() }
+    }
+
+    /// Sets a prefix which is prepended to the filename of each of the source's entries (eg. to namespace entries
+    /// which would otherwise collide with those of another source).
+    ///
+    /// The prefix is prepended as-is, so should typically end with a forward slash.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+/// Write every entry from each of the sources into a ZIP file writer, in the order the sources are provided.
+///
+/// Each entry's compressed data is copied as-is alongside its CRC32 value, sizes, compression method, extra field,
+/// last modification date, attributes, and comment, so no entry is ever decompressed or recompressed (and entries
+/// compressed with a method whose feature isn't enabled can still be merged). Duplicate filenames (after any prefixes
+/// have been applied) are resolved via the provided policy before anything is written, so a skipped copy never
+/// occupies space within the output.
+///
+/// # Note
+/// - Only duplicates across the sources are resolved here; entries already written to the output are subject to the
+/// output's own [`DuplicatePolicy`].
+/// - Entries are written with the output's settings, so their dates are still replaced if the output is reproducible
+/// (see [`ZipFileWriter::reproducible()`]).
+///
+/// [`DuplicatePolicy`]: crate::write::DuplicatePolicy
+pub async fn merge_archives<W, R>(
+    output: &mut ZipFileWriter<W>,
+    sources: Vec<MergeSource<R>>,
+    policy: MergePolicy,
+) -> Result<Vec<WrittenEntry>>
+where
+    W: AsyncWrite + Unpin,
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    let mut readers = Vec::with_capacity(sources.len());
+    for source in sources {
+        readers.push((ZipFileReader::new(source.reader).await?, source.prefix));
+    }
+
+    // The (source, entry) index pair which is written for each distinct filename.
+    let mut chosen: HashMap<String, (usize, usize)> = HashMap::new();
+    for (source, (reader, prefix)) in readers.iter().enumerate() {
+        for (index, entry) in reader.entries.iter().enumerate() {
+            let filename = format!("{}{}", prefix, entry.0.filename());
+
+            match chosen.entry(filename) {
+                Entry::Vacant(vacant) => {
+                    vacant.insert((source, index));
+                }
+                Entry::Occupied(occupied) => match policy {
+                    MergePolicy::Error => return Err(ZipError::DuplicateFilename(occupied.key().clone())),
+                    MergePolicy::KeepFirst => {}
+                    MergePolicy::KeepLast => *occupied.into_mut() = (source, index),
+                },
+            }
+        }
+    }
+
+    let mut written = Vec::with_capacity(chosen.len());
+    for (source, (reader, prefix)) in readers.iter_mut().enumerate() {
+        for index in 0..reader.entries.len() {
+            let mut entry = reader.entries[index].0.clone();
+            entry.filename = format!("{}{}", prefix, entry.filename).into();
+
+            if chosen.get(entry.filename()) != Some(&(source, index)) {
+                continue;
+            }
+
+            let data = reader.raw_entry_reader(index).await?;
+            written.push(crate::write::entry_raw::write_entry_raw(output, entry, data).await?);
+        }
+    }
+
+    Ok(written)
+}
@@ -51,90 +51,957 @@
 //! ```
 
 pub(crate) mod compressed_writer;
+pub(crate) mod digest;
+pub mod edit;
+pub(crate) mod encrypted_writer;
+pub(crate) mod entry_raw;
 pub(crate) mod entry_stream;
 pub(crate) mod entry_whole;
+pub mod fs;
+pub mod merge;
+pub(crate) mod offset_writer;
+pub(crate) mod pipeline;
+pub mod split;
+#[cfg(feature = "stream")]
+pub(crate) mod stream;
+pub(crate) mod transcode;
 
+pub use digest::EntryDigest;
+#[cfg(feature = "stream")]
+pub use entry_stream::EntryBytesSink;
 pub use entry_stream::EntryStreamWriter;
+#[cfg(feature = "stream")]
+pub use stream::stream_archive;
+pub use transcode::{transcode, TranscodeAction};
 
-use crate::error::Result;
-use crate::spec::header::{CentralDirectoryHeader, EndOfCentralDirectoryHeader};
+use crate::error::{Result, ZipError};
+use crate::spec::extra_field::{ExtraField, ALIGNMENT_PADDING, ZIP64_EXTENDED_INFORMATION};
+use crate::spec::header::{CentralDirectoryHeader, EndOfCentralDirectoryHeader, LocalFileHeader};
+use crate::spec::header::{Zip64EndOfCentralDirectoryLocator, Zip64EndOfCentralDirectoryRecord};
+use crate::entry::ext::TEXT_ATTRIBUTE;
 use crate::entry::ZipEntry;
-use async_io_utilities::AsyncOffsetWriter;
+use crate::progress::Progress;
+use chrono::{DateTime, Utc};
 use entry_whole::EntryWholeWriter;
+use offset_writer::{AsyncOffsetWriter, Seeker};
 
-use tokio::io::{AsyncWrite, AsyncWriteExt};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::{IoSlice, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufReader};
 
-pub(crate) struct CentralDirectoryEntry {
-    pub header: CentralDirectoryHeader,
-    pub entry: ZipEntry,
+/// The buffer size recommended for [`ZipFileWriter::write_entry_copy()`] on modern systems (64 KiB).
+pub const DEFAULT_COPY_BUFFER_SIZE: usize = 65536;
+
+/// The number of leading bytes of an entry's data which are compressed in order to choose its compression method, as
+/// enabled via [`ZipEntryBuilder::auto_compression()`] (64 KiB).
+///
+/// [`ZipEntryBuilder::auto_compression()`]: crate::entry::builder::ZipEntryBuilder::auto_compression
+#[cfg(feature = "deflate")]
+pub const AUTO_COMPRESSION_SAMPLE_SIZE: usize = 65536;
+
+/// The threshold recommended for [`ZipEntryBuilder::auto_compression()`], under which an entry is only compressed if
+/// doing so saves more than 5% of its sample's size.
+///
+/// [`ZipEntryBuilder::auto_compression()`]: crate::entry::builder::ZipEntryBuilder::auto_compression
+#[cfg(feature = "deflate")]
+pub const DEFAULT_AUTO_COMPRESSION_THRESHOLD: f32 = 0.95;
+
+/// The central directory records of all entries written so far.
+///
+/// Each record is serialised as soon as its entry has been written, so only its on-disk form (46 bytes plus the
+/// entry's filename, extra field, and comment) is held in memory until the ZIP file is closed.
+#[derive(Default)]
+pub(crate) struct CentralDirectoryRecords {
+    buffer: Vec<u8>,
+    len: usize,
+}
+
+impl CentralDirectoryRecords {
+    // Serialise and append an entry's central directory record, including its signature.
+    pub(crate) fn push(&mut self, header: &CentralDirectoryHeader, entry: &ZipEntry) {
+        self.buffer.extend_from_slice(&header.to_bytes());
+        self.buffer.extend_from_slice(entry.filename().as_bytes());
+        self.buffer.extend_from_slice(entry.extra_field());
+        self.buffer.extend_from_slice(entry.comment().as_bytes());
+        self.len += 1;
+    }
+
+    // Append a record which has already been serialised, including its signature.
+    pub(crate) fn push_raw(&mut self, record: &[u8]) {
+        self.buffer.extend_from_slice(record);
+        self.len += 1;
+    }
+
+    // Returns the number of records.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    // Returns all records, serialised back-to-back.
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    // Iterate over each serialised record.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        let mut remaining = &self.buffer[..];
+
+        std::iter::from_fn(move || {
+            if remaining.is_empty() {
+                return None;
+            }
+
+            let (record, rest) = remaining.split_at(record_length(remaining));
+            remaining = rest;
+            Some(record)
+        })
+    }
+
+    // Iterate over the decoded filename of each record.
+    pub(crate) fn filenames(&self) -> impl Iterator<Item = String> + '_ {
+        self.iter().map(record_filename)
+    }
+
+    // Remove every record with the provided (decoded) filename.
+    pub(crate) fn remove(&mut self, filename: &str) {
+        let mut retained = Self::default();
+
+        for record in self.iter().filter(|record| record_filename(record) != filename) {
+            retained.buffer.extend_from_slice(record);
+            retained.len += 1;
+        }
+
+        *self = retained;
+    }
+}
+
+// Read a two-byte length from a serialised central directory record.
+pub(crate) fn record_field(record: &[u8], offset: usize) -> usize {
+    u16::from_le_bytes([record[offset], record[offset + 1]]).into()
+}
+
+// Decode the filename of a serialised record as a reader would, so that records written by other tools (eg. with CP437
+// filenames, when appending to an existing ZIP file) match the filenames of entries written with the same name.
+fn record_filename(record: &[u8]) -> String {
+    let filename = &record[46..46 + record_field(record, 28)];
+    let extra_field = &record[46 + filename.len()..46 + filename.len() + record_field(record, 30)];
+    let extra_fields = crate::spec::extra_field::parse_extra_fields(extra_field);
+    let unicode = record_field(record, 8) & 0x800 != 0;
+
+    match crate::utils::decode_entry_filename(filename.to_vec(), unicode, &extra_fields) {
+        Ok((decoded, _)) => decoded,
+        Err(_) => String::from_utf8_lossy(filename).into_owned(),
+    }
+}
+
+// The length of the serialised record at the start of the buffer, from the filename, extra field, and comment lengths
+// within its fixed-size part.
+pub(crate) fn record_length(record: &[u8]) -> usize {
+    46 + record_field(record, 28) + record_field(record, 30) + record_field(record, 32)
+}
+
+// The largest number of bytes an alignment padding record may add to an entry's local file header extra field.
+pub(crate) fn max_alignment_padding(alignment: u32) -> usize {
+    match alignment {
+        0 | 1 => 0,
+        alignment => 6 + alignment as usize - 1,
+    }
+}
+
+// Returns the extra field data to write within an entry's local file header, when that header begins at the provided
+// offset.
+//
+// If the entry requests its data be aligned, a padding record is appended after all of the entry's other records so
+// that the data begins at a multiple of the alignment. Any records the writer adds itself must therefore already be
+// part of the entry's extra field.
+pub(crate) fn local_extra_field(entry: &ZipEntry, lfh_offset: usize) -> Cow<'_, [u8]> {
+    if entry.alignment <= 1 {
+        return Cow::Borrowed(entry.extra_field());
+    }
+
+    // The padding record's header ID, length, and alignment value occupy six bytes before any zeroed bytes.
+    let alignment = entry.alignment as usize;
+    let unpadded = lfh_offset + LocalFileHeader::LENGTH + entry.filename().len() + entry.extra_field().len() + 6;
+    let padding = (alignment - unpadded % alignment) % alignment;
+
+    let mut data = (entry.alignment as u16).to_le_bytes().to_vec();
+    data.resize(2 + padding, 0);
+
+    let mut extra_field = entry.extra_field().to_vec();
+    ExtraField::new(ALIGNMENT_PADDING, data).write_to(&mut extra_field);
+    Cow::Owned(extra_field)
+}
+
+// Split the sizes written within an entry's local file header (uncompressed, then compressed) into the values of the
+// header's 32-bit fields and those deferred to a ZIP64 extended information record.
+//
+// The specification requires the local record to hold both sizes if it holds either, so both are deferred if either
+// can't be held (or if the entry forces ZIP64). As a reader resolves any saturated field from the record, a value of
+// exactly u32::MAX is also deferred.
+pub(crate) fn local_zip64_split(entry: &ZipEntry, sizes: [u64; 2]) -> ([u32; 2], Vec<u64>) {
+    match entry.force_zip64 || sizes.iter().any(|size| *size >= u64::from(u32::MAX)) {
+        true => ([u32::MAX; 2], sizes.to_vec()),
+        false => (sizes.map(|size| size as u32), Vec::new()),
+    }
+}
+
+// Split the values written within an entry's central directory record (its uncompressed size, compressed size, and
+// local file header offset, in that order) into the values of the record's 32-bit fields and those deferred to a ZIP64
+// extended information record. Unlike the local record, only the values which can't be held are deferred.
+pub(crate) fn central_zip64_split(values: [u64; 3]) -> ([u32; 3], Vec<u64>) {
+    let fields = values.map(|value| u32::try_from(value).unwrap_or(u32::MAX));
+    let deferred = values.iter().zip(fields).filter(|(_, field)| *field == u32::MAX).map(|(value, _)| *value);
+
+    (fields, deferred.collect())
+}
+
+// Construct a ZIP64 extended information record holding the provided values.
+pub(crate) fn zip64_field(values: &[u64]) -> ExtraField {
+    ExtraField::new(ZIP64_EXTENDED_INFORMATION, values.iter().flat_map(|value| value.to_le_bytes()).collect())
+}
+
+// Replace any ZIP64 extended information record within an entry's extra field with one holding the provided values,
+// or remove it if there are none.
+//
+// This is only called where the writer requires a record (or has added one) itself, so any record provided by the
+// caller is otherwise written as-is. The record is placed first so that it lies at a fixed offset within the local file
+// header, where the stream writer may patch it in place.
+pub(crate) fn set_zip64_field(entry: &mut ZipEntry, values: &[u64]) {
+    entry.extra_fields.retain(|field| field.header_id != ZIP64_EXTENDED_INFORMATION);
+    if !values.is_empty() {
+        entry.extra_fields.insert(0, zip64_field(values));
+    }
+
+    entry.extra_field.clear();
+    for field in &entry.extra_fields {
+        field.write_to(&mut entry.extra_field);
+    }
+}
+
+// Returns whether the UTF-8 flag must be set for an entry.
+//
+// The flag applies to both the filename and the comment, so a non-ASCII comment requires it even where the filename is
+// ASCII (otherwise other readers decode the comment as CP437).
+pub(crate) fn requires_utf8_flag(entry: &ZipEntry) -> bool {
+    !entry.filename().is_ascii() || !entry.comment().is_ascii()
+}
+
+/// A summary of an entry which has been fully written, as recorded within its central directory header.
+#[derive(Debug, Clone)]
+pub struct WrittenEntry {
+    pub(crate) crc32: u32,
+    pub(crate) compressed_size: u64,
+    pub(crate) uncompressed_size: u64,
+    pub(crate) header_offset: u64,
+    pub(crate) digest: Option<Vec<u8>>,
+}
+
+impl WrittenEntry {
+    // Construct a summary from the entry's full values, rather than those saturated within its headers for ZIP64.
+    pub(crate) fn new(crc32: u32, compressed_size: u64, uncompressed_size: u64, header_offset: u64) -> Self {
+        Self { crc32, compressed_size, uncompressed_size, header_offset, digest: None }
+    }
+
+    /// Returns the CRC32 value of the entry's uncompressed data.
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    /// Returns the number of bytes the entry's data occupies within the archive.
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    /// Returns the number of bytes written to the entry before compression.
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    /// Returns the offset of the entry's local file header from the start of the archive (or of its part, when the
+    /// archive is split).
+    pub fn header_offset(&self) -> u64 {
+        self.header_offset
+    }
+
+    /// Returns the output of the digest computed over the entry's uncompressed data, if one was set.
+    ///
+    /// See [`ZipFileWriter::digest()`].
+    pub fn digest(&self) -> Option<&[u8]> {
+        self.digest.as_deref()
+    }
+}
+
+/// The action taken when an entry is written with the same filename as an earlier entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Return a [`ZipError::DuplicateFilename`] error before any of the entry is written.
+    Error,
+    /// Write the entry, and omit the earlier entry's record from the central directory so that only the last copy is
+    /// listed. The earlier entry's data still occupies space within the ZIP file.
+    Overwrite,
+    /// Write the entry, and list both copies within the central directory.
+    #[default]
+    Allow,
+}
+
+// The filenames of the entries written so far, which are tracked to apply a duplicate policy.
+#[derive(Default)]
+pub(crate) struct DuplicateTracker {
+    policy: DuplicatePolicy,
+    // Only tracked whilst duplicate filenames aren't allowed, see ZipFileWriter::deduplicate().
+    filenames: HashSet<Arc<str>>,
+}
+
+impl DuplicateTracker {
+    // Return an error if an entry which is about to be written is a duplicate which the policy rejects. Nothing is
+    // recorded until the entry has been written, so a failed write leaves the earlier entries as they were.
+    pub(crate) fn check(&self, entry: &ZipEntry) -> Result<()> {
+        match self.policy == DuplicatePolicy::Error && self.filenames.contains(entry.filename()) {
+            true => Err(ZipError::DuplicateFilename(entry.filename().to_owned())),
+            false => Ok(()),
+        }
+    }
+
+    // Append the central directory record of an entry which has been written, first removing the records of any
+    // earlier entries it overwrites.
+    pub(crate) fn push(
+        &mut self,
+        cd_records: &mut CentralDirectoryRecords,
+        header: &CentralDirectoryHeader,
+        entry: &ZipEntry,
+    ) {
+        if self.policy != DuplicatePolicy::Allow
+            && !self.filenames.insert(entry.filename.clone())
+            && self.policy == DuplicatePolicy::Overwrite
+        {
+            cd_records.remove(entry.filename());
+        }
+
+        cd_records.push(header, entry);
+    }
+}
+
+/// The layout of the data descriptors which follow stream-written entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataDescriptorStyle {
+    /// Begin each data descriptor with its optional signature, which some readers (eg. Java's `ZipInputStream`)
+    /// require.
+    #[default]
+    Signed,
+    /// Omit the signature, as in the specification's original data descriptor layout.
+    Unsigned,
+}
+
+impl DataDescriptorStyle {
+    // Construct a data descriptor of this style holding an entry's CRC32 value and sizes, which are 8 bytes each
+    // under ZIP64 rather than 4.
+    pub(crate) fn descriptor(self, crc: u32, compressed_size: u64, uncompressed_size: u64, zip64: bool) -> Vec<u8> {
+        let mut descriptor = Vec::with_capacity(24);
+
+        if self == DataDescriptorStyle::Signed {
+            descriptor.extend_from_slice(&crate::spec::signature::DATA_DESCRIPTOR.to_le_bytes());
+        }
+
+        descriptor.extend_from_slice(&crc.to_le_bytes());
+        if zip64 {
+            descriptor.extend_from_slice(&compressed_size.to_le_bytes());
+            descriptor.extend_from_slice(&uncompressed_size.to_le_bytes());
+        } else {
+            descriptor.extend_from_slice(&(compressed_size as u32).to_le_bytes());
+            descriptor.extend_from_slice(&(uncompressed_size as u32).to_le_bytes());
+        }
+        descriptor
+    }
 }
 
 /// A ZIP file writer which acts over AsyncWrite implementers.
 ///
 /// # Note
 /// - [`ZipFileWriter::close()`] must be called before a stream writer goes out of scope.
+/// - If an entry write fails or is cancelled (ie. its future is dropped) once it has begun writing, or an
+/// [`EntryStreamWriter`] is dropped without being closed, the underlying writer is left holding an incomplete entry.
+/// All further writes (including [`ZipFileWriter::close()`]) then fail with [`ZipError::WriterPoisoned`], rather than
+/// producing a corrupt ZIP file.
 pub struct ZipFileWriter<W: AsyncWrite + Unpin> {
     pub(crate) writer: AsyncOffsetWriter<W>,
-    pub(crate) cd_entries: Vec<CentralDirectoryEntry>,
-    comment_opt: Option<String>,
+    pub(crate) cd_records: CentralDirectoryRecords,
+    pub(crate) comment_opt: Option<String>,
+    pub(crate) reproducible: Option<DateTime<Utc>>,
+    pub(crate) duplicates: DuplicateTracker,
+    pub(crate) descriptor_style: DataDescriptorStyle,
+    detect_text: bool,
+    pub(crate) digest: Option<digest::DigestFactory>,
+    pub(crate) parts: Option<Arc<split::PartState>>,
+    // Set whilst an entry is being written, so remains set if that write never completes.
+    pub(crate) poisoned: bool,
+    // Only present if local file headers of stream-written entries are to be patched in place.
+    pub(crate) seeker: Option<Seeker<W>>,
+    // The length of the existing ZIP file being appended to, which the ZIP file must not end before (see close()).
+    existing_length: usize,
+    pub(crate) progress: Option<Progress>,
 }
 
 impl<W: AsyncWrite + Unpin> ZipFileWriter<W> {
     /// Construct a new ZIP file writer from a mutable reference to a writer.
     pub fn new(writer: W) -> Self {
-        Self { writer: AsyncOffsetWriter::new(writer), cd_entries: Vec::new(), comment_opt: None }
+        Self::from_offset_writer(AsyncOffsetWriter::new(writer))
+    }
+
+    fn from_offset_writer(writer: AsyncOffsetWriter<W>) -> Self {
+        Self {
+            writer,
+            cd_records: CentralDirectoryRecords::default(),
+            comment_opt: None,
+            reproducible: None,
+            duplicates: DuplicateTracker::default(),
+            descriptor_style: DataDescriptorStyle::default(),
+            detect_text: false,
+            digest: None,
+            parts: None,
+            poisoned: false,
+            seeker: None,
+            existing_length: 0,
+            progress: None,
+        }
     }
 
     /// Write a new ZIP entry of known size and data.
-    pub async fn write_entry_whole<E: Into<ZipEntry>>(&mut self, entry: E, data: &[u8]) -> Result<()> {
-        EntryWholeWriter::from_raw(self, entry.into(), data).write().await
+    pub async fn write_entry_whole<E: Into<ZipEntry>>(&mut self, entry: E, data: &[u8]) -> Result<WrittenEntry> {
+        self.write_entry_whole_vectored(entry, &[IoSlice::new(data)]).await
+    }
+
+    /// Write a new ZIP entry of known size, whose data is split across multiple slices.
+    ///
+    /// The slices are fed sequentially to the CRC32 hasher and compressor, so there's no need to first concatenate
+    /// them. Slices written with the Stored compression method are never copied.
+    pub async fn write_entry_whole_vectored<E: Into<ZipEntry>>(
+        &mut self,
+        entry: E,
+        parts: &[IoSlice<'_>],
+    ) -> Result<WrittenEntry> {
+        let mut entry = entry.into();
+        entry.validate()?;
+        if entry.symlink_target.is_some() && parts.iter().any(|part| !part.is_empty()) {
+            return Err(ZipError::SymlinkData(entry.filename().to_owned()));
+        }
+        self.check_poisoned()?;
+        self.duplicates.check(&entry)?;
+
+        if self.reproducible.is_some() {
+            entry.remove_timestamp_fields();
+        }
+        if self.detect_text && is_text(parts) {
+            entry.internal_file_attribute |= TEXT_ATTRIBUTE;
+        }
+
+        // A symbolic link entry's data is its target path, which is held by the entry itself.
+        if let Some(target) = entry.symlink_target.clone() {
+            return EntryWholeWriter::from_raw(self, entry, &[IoSlice::new(target.as_bytes())]).write().await;
+        }
+
+        EntryWholeWriter::from_raw(self, entry, parts).write().await
     }
 
     /// Write an entry of unknown size and data via streaming (ie. using a data descriptor).
+    ///
+    /// # Note
+    /// Entries may exceed 4 GiB, in which case ZIP64 sizes are written to the data descriptor and central directory.
+    /// Where local file headers are patched instead (see [`ZipFileWriter::patch_local_headers()`]), such an entry must
+    /// reserve space for its ZIP64 sizes via [`ZipEntryBuilder::force_zip64()`].
+    ///
+    /// Symbolic link entries (see [`ZipEntryBuilderExt::symlink()`]) and those whose compression method is chosen
+    /// automatically (see [`ZipEntryBuilder::auto_compression()`]) must instead be written via
+    /// [`ZipFileWriter::write_entry_whole()`].
+    ///
+    /// [`ZipEntryBuilderExt::symlink()`]: crate::entry::ext::ZipEntryBuilderExt::symlink
+    /// [`ZipEntryBuilder::auto_compression()`]: crate::entry::builder::ZipEntryBuilder::auto_compression
+    /// [`ZipEntryBuilder::force_zip64()`]: crate::entry::builder::ZipEntryBuilder::force_zip64
     pub async fn write_entry_stream<E: Into<ZipEntry>>(&mut self, entry: E) -> Result<EntryStreamWriter<'_, W>> {
-        EntryStreamWriter::from_raw(self, entry.into()).await
+        let mut entry = entry.into();
+        entry.validate()?;
+        if entry.symlink_target.is_some() {
+            return Err(ZipError::FeatureNotSupported("Streaming symbolic link entries"));
+        }
+        if entry.auto_compression.is_some() {
+            return Err(ZipError::FeatureNotSupported("Automatic compression selection for streamed entries"));
+        }
+        self.check_poisoned()?;
+        self.duplicates.check(&entry)?;
+
+        if self.reproducible.is_some() {
+            entry.remove_timestamp_fields();
+        }
+
+        EntryStreamWriter::from_raw(self, entry).await
+    }
+
+    /// Write an entry of unknown size by copying all data from a reader until EOF (ie. using a data descriptor).
+    ///
+    /// Data is copied in chunks of the provided buffer size through the entry's compressor, so at most one buffer's
+    /// worth of data is held in memory at a time. [`DEFAULT_COPY_BUFFER_SIZE`] is a sensible choice on modern systems.
+    pub async fn write_entry_copy<E, R>(&mut self, entry: E, reader: R, buffer_size: usize) -> Result<WrittenEntry>
+    where
+        E: Into<ZipEntry>,
+        R: AsyncRead + Unpin,
+    {
+        let mut reader = BufReader::with_capacity(buffer_size, reader);
+        let mut entry_writer = self.write_entry_stream(entry).await?;
+
+        tokio::io::copy_buf(&mut reader, &mut entry_writer).await?;
+        entry_writer.close().await
+    }
+
+    /// Write an entry of known size by reading exactly that many bytes from a reader.
+    ///
+    /// Unlike [`ZipFileWriter::write_entry_copy()`], the CRC32 value and sizes are written to the local file header
+    /// rather than a data descriptor. As a result, the data is buffered in memory before being written. An error is
+    /// returned if the reader reaches EOF before the known size is reached, and any further data is left unread.
+    pub async fn write_entry_copy_sized<E, R>(&mut self, entry: E, reader: R, known_size: usize) -> Result<WrittenEntry>
+    where
+        E: Into<ZipEntry>,
+        R: AsyncRead + Unpin,
+    {
+        let mut data = Vec::new();
+        reader.take(known_size as u64).read_to_end(&mut data).await?;
+
+        if data.len() != known_size {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        self.write_entry_whole(entry, &data).await
+    }
+
+    /// Write an entry whose data has already been compressed, copying it from a reader as-is rather than recompressing
+    /// it (eg. to repack entries from another ZIP file, as read via its reader's `raw_entry_reader()` method).
+    ///
+    /// The entry's compression method, CRC32 value, and sizes are written unchanged, so must describe the provided
+    /// data (as they do for an entry read from another ZIP file). Exactly the entry's compressed size is read from the
+    /// reader, and an error is returned if it reaches EOF beforehand. If the entry's flags declare a data descriptor,
+    /// one is written after the data, so that any encryption header which relies upon it remains valid.
+    ///
+    /// # Note
+    /// As the entry's data is never seen uncompressed, it has no digest (see [`ZipFileWriter::digest()`]).
+    pub async fn write_entry_raw<E, R>(&mut self, entry: E, reader: R) -> Result<WrittenEntry>
+    where
+        E: Into<ZipEntry>,
+        R: AsyncRead + Unpin,
+    {
+        entry_raw::write_entry_raw(self, entry.into(), reader).await
+    }
+
+    /// Write multiple entries, each compressing all data from its reader until EOF, with up to `concurrency` entries
+    /// being compressed at once.
+    ///
+    /// Each entry is compressed on its own spawned task (so this must be called from within a Tokio runtime), but
+    /// entries are still written to the ZIP file in the order they're provided. Only a bounded amount of compressed
+    /// data (around 1 MiB) is buffered for each entry awaiting its turn, after which its task waits, so large entries
+    /// are never held in memory whole. The returned [`WrittenEntry`] values are in the same order as the entries.
+    ///
+    /// # Note
+    /// Like [`ZipFileWriter::write_entry_stream()`], each entry is followed by a data descriptor unless local file
+    /// headers are being patched (see [`ZipFileWriter::patch_local_headers()`]). A concurrency of zero is treated as
+    /// one.
+    pub async fn write_entries_parallel<I, E, R>(&mut self, entries: I, concurrency: usize) -> Result<Vec<WrittenEntry>>
+    where
+        I: IntoIterator<Item = (E, R)>,
+        E: Into<ZipEntry>,
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        pipeline::write_entries(self, entries, concurrency).await
+    }
+
+    /// Write the `mimetype` entry which must begin OpenDocument and EPUB files, so that their type may be identified
+    /// from their leading bytes alone.
+    ///
+    /// The entry is Stored without any extra fields or data descriptor, and must be the first written, so that its
+    /// local file header begins at offset 0 and the mimetype itself at offset 38. This fails with
+    /// [`ZipError::MimetypeNotFirst`] if anything has already been written.
+    pub async fn write_mimetype_entry(&mut self, mimetype: &str) -> Result<()> {
+        if self.writer.offset() != 0 || self.cd_records.len() != 0 {
+            return Err(ZipError::MimetypeNotFirst);
+        }
+
+        let entry = crate::ZipEntryBuilder::new(crate::read::MIMETYPE_FILENAME, crate::Compression::Stored);
+        self.write_entry_whole(entry, mimetype.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Writes every file and directory within a directory tree into this ZIP file, returning the written entries.
+    ///
+    /// Each file's contents are streamed into its entry, so large files are never held in memory whole. See
+    /// [`write_dir()`] for how the tree is walked, and [`DirWriteOptions`] for how entries are named and compressed.
+    ///
+    /// [`write_dir()`]: crate::write::fs::write_dir
+    /// [`DirWriteOptions`]: crate::write::fs::DirWriteOptions
+    pub async fn write_from_directory<P: AsRef<Path>>(
+        &mut self,
+        src: P,
+        options: fs::DirWriteOptions,
+    ) -> Result<Vec<WrittenEntry>> {
+        fs::write_dir(self, src.as_ref(), options).await
+    }
+
+    /// Write an empty directory entry, returning the written entry.
+    ///
+    /// The entry is constructed via [`ZipEntryBuilder::dir()`], so any backslashes within the name are replaced with
+    /// forward slashes and a trailing slash is appended if it's missing. Use [`ZipFileWriter::write_entry_whole()`]
+    /// with that builder and empty data to set any other fields.
+    ///
+    /// [`ZipEntryBuilder::dir()`]: crate::entry::builder::ZipEntryBuilder::dir
+    pub async fn write_dir_entry(&mut self, name: &str) -> Result<WrittenEntry> {
+        self.write_entry_whole(crate::ZipEntryBuilder::dir(name), &[]).await
     }
 
     /// Set the ZIP file comment.
-    pub fn comment(&mut self, comment: String) {
+    ///
+    /// The comment's length is recorded as a 16-bit value, so this fails with [`ZipError::CommentTooLong`] (leaving any
+    /// previous comment in place) rather than truncating a comment longer than 65535 bytes.
+    pub fn comment(&mut self, comment: String) -> Result<()> {
+        if comment.len() > u16::MAX as usize {
+            return Err(ZipError::CommentTooLong(comment.len()));
+        }
+
         self.comment_opt = Some(comment);
+        Ok(())
+    }
+
+    /// Enables reproducible output where the same sequence of entries and data always produces identical bytes.
+    ///
+    /// # Note
+    /// Every entry's last modification date is replaced with the provided epoch (the MS-DOS epoch of 1980-01-01
+    /// 00:00:00 is a sensible choice) and any timestamp extra fields are removed, and the writer won't add any extra
+    /// fields of its own. Any "version made by" or "version needed to extract" values set explicitly via
+    /// [`ZipEntryBuilder`] are still respected.
+    ///
+    /// [`ZipEntryBuilder`]: crate::ZipEntryBuilder
+    pub fn reproducible(&mut self, epoch: DateTime<Utc>) {
+        self.reproducible = Some(epoch);
+    }
+
+    /// Sets the action taken when an entry is written with the same filename as an earlier entry.
+    ///
+    /// Defaults to [`DuplicatePolicy::Allow`], where both copies are listed and extractors disagree on which to use.
+    ///
+    /// # Note
+    /// Under any other policy, the filename of every entry written is also held in memory so that duplicates can be
+    /// found (including those written before the policy was set).
+    pub fn deduplicate(&mut self, policy: DuplicatePolicy) {
+        if self.duplicates.policy == DuplicatePolicy::Allow && policy != DuplicatePolicy::Allow {
+            self.duplicates.filenames = self.cd_records.filenames().map(Arc::from).collect();
+        }
+
+        self.duplicates.policy = policy;
+    }
+
+    /// Sets the layout of the data descriptors which follow stream-written entries (and raw entries flagged as
+    /// having one).
+    ///
+    /// Defaults to [`DataDescriptorStyle::Signed`], which is the most widely compatible. Has no effect on entries
+    /// whose local file headers are patched instead, see [`ZipFileWriter::patch_local_headers()`].
+    pub fn data_descriptor_style(&mut self, style: DataDescriptorStyle) {
+        self.descriptor_style = style;
+    }
+
+    /// Sets whether or not entries written as whole data are checked for text, setting their text attribute if so.
+    ///
+    /// The first 8 KiB of each entry's data is inspected in the same way as zlib's `deflate()`: the data is believed to
+    /// be text if it contains none of the control characters zlib considers binary (eg. NUL), and at least one
+    /// printable or whitespace character.
+    /// Disabled by default, as the attribute is only used by some extractors (eg. to convert line endings).
+    ///
+    /// # Note
+    /// An entry's text attribute is never cleared by this check, so it may still be set explicitly via
+    /// [`ZipEntryBuilderExt::text()`]. Entries written via streaming aren't checked, as their data isn't known (and
+    /// their headers are written) before it's written.
+    ///
+    /// [`ZipEntryBuilderExt::text()`]: crate::entry::ext::ZipEntryBuilderExt::text
+    pub fn detect_text(&mut self, detect: bool) {
+        self.detect_text = detect;
+    }
+
+    /// Sets a digest which is computed over each entry's uncompressed data alongside its CRC32 value (eg. a SHA-256
+    /// hash to be recorded within a manifest), so that the data needn't be read a second time to compute it.
+    ///
+    /// A new digest is constructed via the provided factory for each entry written, and is fed exactly the bytes which
+    /// the CRC32 hasher is (ie. before compression). It's finalised once the entry has been fully written, and its
+    /// output is available via the returned [`WrittenEntry::digest()`].
+    ///
+    /// # Note
+    /// Entries whose data is copied without being decompressed (see [`merge_archives()`]) have no digest.
+    ///
+    /// [`merge_archives()`]: crate::write::merge::merge_archives
+    pub fn digest<D, F>(&mut self, factory: F)
+    where
+        D: EntryDigest + 'static,
+        F: Fn() -> D + Send + Sync + 'static,
+    {
+        self.digest = Some(Arc::new(move || Box::new(factory()) as Box<dyn EntryDigest>));
+    }
+
+    /// Returns a handle which observes the number of compressed bytes written for the entry currently being written via
+    /// [`ZipFileWriter::write_entry_whole()`] (including any encryption header).
+    ///
+    /// The count is reset as each entry begins and is updated as its data is written, so may be polled from another
+    /// task. Entries written via [`ZipFileWriter::write_entry_stream()`] instead report their progress via
+    /// [`EntryStreamWriter::progress()`].
+    pub fn progress(&mut self) -> Progress {
+        self.progress.get_or_insert_with(|| Progress::new(0)).clone()
+    }
+
+    /// Returns whether or not an earlier entry write was left incomplete, in which case all further writes will fail.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    pub(crate) fn check_poisoned(&self) -> Result<()> {
+        match self.poisoned {
+            true => Err(ZipError::WriterPoisoned),
+            false => Ok(()),
+        }
+    }
+
+    /// Returns the disk number and offset relative to that disk for an offset within the written data.
+    ///
+    /// Unless the ZIP file is being split across multiple parts, this is always the first disk.
+    pub(crate) fn disk_position(&self, offset: usize) -> (u16, u64) {
+        match &self.parts {
+            Some(parts) => {
+                let (disk, offset) = parts.disk_position(offset as u64);
+                (disk, offset.into())
+            }
+            None => (0, offset as u64),
+        }
+    }
+
+    /// Returns the MS-DOS time & date which should be written for an entry, respecting reproducible output.
+    pub(crate) fn entry_zip_time(&self, entry: &ZipEntry) -> (u16, u16) {
+        let date = self.reproducible.as_ref().unwrap_or_else(|| entry.last_modification_date());
+        crate::spec::date::chrono_to_zip_time(date)
+    }
+
+    /// Returns the central directory & end of central directory header for all entries written so far.
+    ///
+    /// When appended directly after the data written so far, the snapshot completes a valid ZIP file containing those
+    /// entries. This allows a crash-recovery tool to salvage a partially-written ZIP file by truncating any incomplete
+    /// trailing entry and appending the latest snapshot (which records its own offset within the end of central
+    /// directory header). Writing may continue as normal after taking a snapshot.
+    pub fn central_directory_snapshot(&self) -> Vec<u8> {
+        self.central_directory(self.writer.offset())
+    }
+
+    // Serialise the central directory and end of central directory header, as if beginning at the provided offset.
+    fn central_directory(&self, cd_offset: usize) -> Vec<u8> {
+        let mut buffer = self.cd_records.as_bytes().to_vec();
+        buffer.extend_from_slice(&self.end_of_central_directory(cd_offset));
+
+        buffer
+    }
+
+    // Serialise the end of central directory header and ZIP file comment, for a central directory beginning at the
+    // provided offset.
+    //
+    // If the number of entries, or the central directory's size or offset, can't be held within the header, they're
+    // saturated and the header is preceded by a ZIP64 end of central directory record and locator.
+    fn end_of_central_directory(&self, cd_offset: usize) -> Vec<u8> {
+        let num_of_entries = self.cd_records.len() as u64;
+        let size_cent_dir = self.cd_records.as_bytes().len() as u64;
+        let cent_dir_offset = cd_offset as u64;
+
+        let mut buffer = Vec::new();
+        let zip64 = num_of_entries >= u64::from(u16::MAX)
+            || size_cent_dir >= u64::from(u32::MAX)
+            || cent_dir_offset >= u64::from(u32::MAX);
+
+        if zip64 {
+            let record = Zip64EndOfCentralDirectoryRecord {
+                v_made_by: crate::spec::version::as_made_by(),
+                v_needed: crate::spec::version::ZIP64_VERSION_NEEDED,
+                disk_num: 0,
+                start_cent_dir_disk: 0,
+                num_of_entries_disk: num_of_entries,
+                num_of_entries,
+                size_cent_dir,
+                cent_dir_offset,
+            };
+            let locator = Zip64EndOfCentralDirectoryLocator {
+                start_eocdr_disk: 0,
+                eocdr_offset: cent_dir_offset + size_cent_dir,
+                total_disks: 1,
+            };
+
+            buffer.extend_from_slice(&record.to_bytes());
+            buffer.extend_from_slice(&locator.to_bytes());
+        }
+
+        let num_of_entries = num_of_entries.min(u16::MAX.into()) as u16;
+        let header = EndOfCentralDirectoryHeader {
+            disk_num: 0,
+            start_cent_dir_disk: 0,
+            num_of_entries_disk: num_of_entries,
+            num_of_entries,
+            size_cent_dir: size_cent_dir.min(u32::MAX.into()) as u32,
+            cent_dir_offset: cent_dir_offset.min(u32::MAX.into()) as u32,
+            file_comm_length: self.comment_opt.as_ref().map(|v| v.len() as u16).unwrap_or_default(),
+        };
+
+        buffer.extend_from_slice(&header.to_bytes());
+        if let Some(comment) = &self.comment_opt {
+            buffer.extend_from_slice(comment.as_bytes());
+        }
+
+        buffer
     }
 
     /// Consumes this ZIP writer and completes all closing tasks.
     ///
     /// This includes:
     /// - Writing all central directroy headers.
+    /// - Writing the ZIP64 end of central directory record and locator, if required.
     /// - Writing the end of central directory header.
     /// - Writing the file comment.
     ///
     /// Failiure to call this function before going out of scope would result in a corrupted ZIP file.
     pub async fn close(mut self) -> Result<()> {
-        let cd_offset = self.writer.offset();
+        self.check_poisoned()?;
 
-        for entry in &self.cd_entries {
-            self.writer.write_all(&crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER.to_le_bytes()).await?;
-            self.writer.write_all(&entry.header.as_slice()).await?;
-            self.writer.write_all(entry.entry.filename().as_bytes()).await?;
-            self.writer.write_all(&entry.entry.extra_field()).await?;
-            self.writer.write_all(entry.entry.comment().as_bytes()).await?;
+        // A comment carried over from an appended ZIP file can outgrow its recorded length once any invalid UTF-8
+        // within it has been replaced.
+        if let Some(comment) = self.comment_opt.as_ref().filter(|comment| comment.len() > u16::MAX as usize) {
+            return Err(ZipError::CommentTooLong(comment.len()));
         }
 
-        let header = EndOfCentralDirectoryHeader {
-            disk_num: 0,
-            start_cent_dir_disk: 0,
-            num_of_entries_disk: self.cd_entries.len() as u16,
-            num_of_entries: self.cd_entries.len() as u16,
-            size_cent_dir: (self.writer.offset() - cd_offset) as u32,
-            cent_dir_offset: cd_offset as u32,
-            file_comm_length: self.comment_opt.as_ref().map(|v| v.len() as u16).unwrap_or_default(),
-        };
+        // An appended ZIP file can't be truncated, so it's padded before its central directory to end where the
+        // existing file did. Otherwise, a reader searching backwards would find the existing end of central directory
+        // header first.
+        if self.existing_length > 0 {
+            let end_length = self.end_of_central_directory(self.writer.offset()).len();
+            let length = self.writer.offset() + self.cd_records.as_bytes().len() + end_length;
 
-        self.writer.write_all(&crate::spec::signature::END_OF_CENTRAL_DIRECTORY.to_le_bytes()).await?;
-        self.writer.write_all(&header.as_slice()).await?;
-        if let Some(comment) = self.comment_opt {
-            self.writer.write_all(comment.as_bytes()).await?;
+            if length < self.existing_length {
+                self.writer.write_all(&vec![0; self.existing_length - length]).await?;
+            }
         }
 
+        let end_of_central_directory = self.end_of_central_directory(self.writer.offset());
+        self.writer.write_all(self.cd_records.as_bytes()).await?;
+        self.writer.write_all(&end_of_central_directory).await?;
+
         Ok(())
     }
 }
+
+impl<W: AsyncWrite + AsyncSeek + Unpin> ZipFileWriter<W> {
+    /// Construct a new ZIP file writer over a seekable writer, where stream-written entries are never followed by a
+    /// data descriptor.
+    ///
+    /// This is equivalent to calling [`ZipFileWriter::patch_local_headers()`] after [`ZipFileWriter::new()`].
+    pub fn new_seekable(writer: W) -> Self {
+        let mut zip_writer = Self::new(writer);
+        zip_writer.patch_local_headers(true);
+        zip_writer
+    }
+
+    /// Sets whether or not stream-written entries should have their local file header patched once closed, rather
+    /// than being followed by a data descriptor.
+    ///
+    /// When enabled, [`EntryStreamWriter::close()`] seeks back to the entry's local file header to fill in its CRC32
+    /// value and sizes, then seeks forward again. This suits readers which dislike data descriptors (particularly on
+    /// Stored entries), at the cost of requiring a seekable writer. Defaults to false.
+    pub fn patch_local_headers(&mut self, patch: bool) {
+        self.seeker = patch.then(Seeker::new);
+    }
+}
+
+impl<W: AsyncRead + AsyncWrite + AsyncSeek + Unpin> ZipFileWriter<W> {
+    /// Construct a ZIP file writer which appends entries to an existing ZIP file, without rewriting its entries.
+    ///
+    /// The existing central directory is read and kept as-is, and the writer then seeks to where it began so that new
+    /// entries are written over it. Once closed, the central directory lists the existing entries followed by the new
+    /// ones. The existing ZIP file comment is kept unless replaced via [`ZipFileWriter::comment()`].
+    ///
+    /// Entries may be written with the same filename as an existing entry, in which case both are listed unless
+    /// configured otherwise via [`ZipFileWriter::deduplicate()`] (eg. [`DuplicatePolicy::Overwrite`] lists only the
+    /// new entry, as Info-ZIP's `zip` does).
+    ///
+    /// # Note
+    /// - Spanned ZIP files aren't supported.
+    /// - The writer can't truncate the file, so if the new central directory would end before the existing file did
+    /// (eg. where existing entries are no longer listed), zeroed bytes are written before it to make up the
+    /// difference.
+    /// - If the writer fails or is dropped before being closed, the existing central directory may have been partly
+    /// overwritten, leaving the ZIP file unreadable.
+    pub async fn from_existing(mut writer: W) -> Result<Self> {
+        let options = crate::read::ReaderOptions::default();
+        let (location, metadata) = crate::read::seek::locate_cd(&mut writer, &options, &[]).await?;
+
+        if metadata.disk_num != 0 || metadata.start_cent_dir_disk != 0 {
+            return Err(ZipError::FeatureNotSupported("Appending to ZIP files which span multiple disks"));
+        }
+
+        let existing_length = writer.seek(SeekFrom::End(0)).await?;
+        let size = usize::try_from(location.size).map_err(|_| ZipError::OffsetOverflow)?;
+        let mut central_directory = vec![0; size];
+        writer.seek(SeekFrom::Start(location.offset)).await?;
+        writer.read_exact(&mut central_directory).await?;
+
+        // Each record is kept in its raw form, so that existing entries are listed exactly as they were.
+        let mut cd_records = CentralDirectoryRecords::default();
+        let mut remaining = &central_directory[..];
+        let mut position = location.offset;
+
+        for _ in 0..location.num_of_entries {
+            let expected = CentralDirectoryHeader::LENGTH;
+            if remaining.len() < expected {
+                return Err(ZipError::TruncatedRecord { expected, found: remaining.len() });
+            }
+
+            let expected = crate::spec::signature::CENTRAL_DIRECTORY_FILE_HEADER;
+            let found = u32::from_le_bytes([remaining[0], remaining[1], remaining[2], remaining[3]]);
+            if found != expected {
+                return Err(ZipError::UnexpectedSignature { offset: Some(position), expected, found });
+            }
+
+            let length = record_length(remaining);
+            if remaining.len() < length {
+                return Err(ZipError::TruncatedRecord { expected: length, found: remaining.len() });
+            }
+
+            let (record, rest) = remaining.split_at(length);
+            cd_records.push_raw(record);
+            remaining = rest;
+            position += length as u64;
+        }
+
+        // Offsets are written relative to the start of the ZIP data (as the existing records' are), so any preamble
+        // which they don't account for must be excluded.
+        let cd_offset = location.offset - location.adjustment;
+        let cd_offset = usize::try_from(cd_offset).map_err(|_| ZipError::OffsetOverflow)?;
+        writer.seek(SeekFrom::Start(location.offset)).await?;
+
+        let mut zip_writer = Self::from_offset_writer(AsyncOffsetWriter::with_offset(writer, cd_offset));
+        zip_writer.cd_records = cd_records;
+        zip_writer.comment_opt = metadata.comment;
+        zip_writer.existing_length = (existing_length - location.adjustment) as usize;
+
+        Ok(zip_writer)
+    }
+}
+
+// Decide whether or not an entry's data is text from its first bytes, as zlib's detect_data_type() does.
+fn is_text(parts: &[IoSlice<'_>]) -> bool {
+    const SNIFF_LENGTH: usize = 8 * 1024;
+    let mut text = false;
+
+    for &byte in parts.iter().flat_map(|part| part.iter()).take(SNIFF_LENGTH) {
+        match byte {
+            0..=6 | 14..=25 | 28..=31 => return false,
+            9 | 10 | 13 | 32..=255 => text = true,
+            _ => {}
+        }
+    }
+
+    text
+}
@@ -0,0 +1,108 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use std::io::{Error, IoSlice, SeekFrom};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncSeek, AsyncWrite};
+
+/// A writer which tracks the number of bytes written to its inner writer.
+///
+/// Unlike the equivalent within `async_io_utilities`, this exposes its inner writer so that the ZIP writer can seek
+/// back and patch previously written headers.
+pub struct AsyncOffsetWriter<W: AsyncWrite + Unpin> {
+    inner: W,
+    offset: usize,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncOffsetWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, offset: 0 }
+    }
+
+    // Construct a writer whose inner writer is already positioned at the provided offset within the ZIP file.
+    pub fn with_offset(inner: W, offset: usize) -> Self {
+        Self { inner, offset }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn inner_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncOffsetWriter<W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<std::result::Result<usize, Error>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+
+        if let Poll::Ready(Ok(written)) = poll {
+            self.offset += written;
+        }
+
+        poll
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::result::Result<usize, Error>> {
+        let poll = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+
+        if let Poll::Ready(Ok(written)) = poll {
+            self.offset += written;
+        }
+
+        poll
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// The seeking functions of a writer, captured where the writer is known to implement [`AsyncSeek`].
+///
+/// This allows the ZIP writer (which only requires [`AsyncWrite`]) to seek its inner writer when it's been configured
+/// to do so via a method only available to seekable writers.
+pub(crate) struct Seeker<W> {
+    start_seek: fn(Pin<&mut W>, SeekFrom) -> std::io::Result<()>,
+    poll_complete: fn(Pin<&mut W>, &mut Context<'_>) -> Poll<std::io::Result<u64>>,
+}
+
+impl<W> Clone for Seeker<W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<W> Copy for Seeker<W> {}
+
+impl<W: AsyncSeek + Unpin> Seeker<W> {
+    pub(crate) fn new() -> Self {
+        Self { start_seek: <W as AsyncSeek>::start_seek, poll_complete: <W as AsyncSeek>::poll_complete }
+    }
+}
+
+impl<W: Unpin> Seeker<W> {
+    pub(crate) async fn seek(&self, writer: &mut W, position: SeekFrom) -> std::io::Result<u64> {
+        (self.start_seek)(Pin::new(&mut *writer), position)?;
+        std::future::poll_fn(|cx| (self.poll_complete)(Pin::new(&mut *writer), cx)).await
+    }
+}
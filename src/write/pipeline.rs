@@ -0,0 +1,157 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Concurrent compression of multiple entries, backing [`ZipFileWriter::write_entries_parallel()`].
+//!
+//! Each entry's data is compressed on its own spawned task, which sends its compressed output in chunks over a
+//! bounded channel. Entries are then written to the ZIP file one at a time (in the order they were provided) by
+//! draining these channels, so a task blocks once its channel is full until its entry's turn comes. This bounds the
+//! memory held for each in-flight entry, regardless of its size.
+
+use crate::entry::ZipEntry;
+use crate::error::{Result, ZipError};
+use crate::spec::compression::Compression;
+use crate::write::compressed_writer::CompressedAsyncWriter;
+use crate::write::digest::DigestFactory;
+use crate::write::{EntryDigest, EntryStreamWriter, WrittenEntry, ZipFileWriter};
+
+use std::collections::VecDeque;
+
+use crc32fast::Hasher;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// The size of each chunk of data read from an entry's source, and the threshold at which compressed output is sent.
+pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The number of chunks of compressed output which may be buffered for each in-flight entry.
+pub(crate) const CHUNKS_IN_FLIGHT: usize = 16;
+
+// The CRC32 value, size, and any digest of an entry's uncompressed data, once it has been compressed in full.
+type Compressed = (u32, u64, Option<Vec<u8>>);
+
+struct InFlight {
+    entry: ZipEntry,
+    chunks: mpsc::Receiver<Vec<u8>>,
+    task: JoinHandle<Result<Compressed>>,
+}
+
+pub(crate) async fn write_entries<W, I, E, R>(
+    writer: &mut ZipFileWriter<W>,
+    entries: I,
+    concurrency: usize,
+) -> Result<Vec<WrittenEntry>>
+where
+    W: AsyncWrite + Unpin,
+    I: IntoIterator<Item = (E, R)>,
+    E: Into<ZipEntry>,
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let mut entries = entries.into_iter();
+    let mut in_flight = VecDeque::new();
+    let mut written = Vec::new();
+
+    loop {
+        while in_flight.len() < concurrency.max(1) {
+            match entries.next() {
+                Some((entry, reader)) => in_flight.push_back(spawn(entry.into(), reader, writer.digest.as_ref())?),
+                None => break,
+            }
+        }
+
+        let InFlight { entry, mut chunks, task } = match in_flight.pop_front() {
+            Some(in_flight) => in_flight,
+            None => break,
+        };
+
+        writer.check_poisoned()?;
+        writer.duplicates.check(&entry)?;
+
+        let mut entry_writer = EntryStreamWriter::from_raw_precompressed(writer, entry).await?;
+
+        while let Some(chunk) = chunks.recv().await {
+            entry_writer.write_precompressed(&chunk).await?;
+        }
+
+        // A task which panicked is surfaced as an error, rather than propagating the panic.
+        let joined = task.await.map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        let (crc, uncompressed_size, digest) = joined?;
+
+        let mut entry_written = entry_writer.close_precompressed(crc, uncompressed_size).await?;
+        entry_written.digest = digest;
+        written.push(entry_written);
+    }
+
+    Ok(written)
+}
+
+// Spawn a task which compresses an entry's data, having first checked that the entry can be written.
+fn spawn<R>(entry: ZipEntry, reader: R, digest: Option<&DigestFactory>) -> Result<InFlight>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    entry.validate()?;
+    if entry.symlink_target.is_some() {
+        return Err(ZipError::FeatureNotSupported("Streaming symbolic link entries"));
+    }
+    if entry.auto_compression.is_some() {
+        return Err(ZipError::FeatureNotSupported("Automatic compression selection for streamed entries"));
+    }
+    crate::write::compressed_writer::check_compression(entry.compression())?;
+
+    let (sender, chunks) = mpsc::channel(CHUNKS_IN_FLIGHT);
+    let digest = digest.map(|factory| factory());
+    let task = tokio::spawn(compress(entry.compression(), entry.compression_level, reader, digest, sender));
+
+    Ok(InFlight { entry, chunks, task })
+}
+
+// Compress all data from a reader until EOF, returning the CRC32 value, size, and any digest of the uncompressed data.
+//
+// If the receiving end is dropped (ie. writing the ZIP file failed or was cancelled), compression stops early.
+async fn compress<R: AsyncRead + Unpin>(
+    compression: Compression,
+    level: async_compression::Level,
+    mut reader: R,
+    mut digest: Option<Box<dyn EntryDigest>>,
+    sender: mpsc::Sender<Vec<u8>>,
+) -> Result<Compressed> {
+    let mut writer = CompressedAsyncWriter::from_raw(Vec::with_capacity(CHUNK_SIZE), compression, level);
+    let mut hasher = Hasher::new();
+    let mut uncompressed_size = 0;
+    let mut buffer = vec![0; CHUNK_SIZE];
+
+    loop {
+        let read = reader.read(&mut buffer).await?;
+
+        if read == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..read]);
+        if let Some(digest) = &mut digest {
+            digest.update(&buffer[..read]);
+        }
+        uncompressed_size += read as u64;
+        writer.write_all(&buffer[..read]).await?;
+
+        if writer.get_mut().len() >= CHUNK_SIZE {
+            let chunk = std::mem::replace(writer.get_mut(), Vec::with_capacity(CHUNK_SIZE));
+            send(&sender, chunk).await?;
+        }
+    }
+
+    writer.shutdown().await?;
+    let chunk = writer.into_inner();
+
+    if !chunk.is_empty() {
+        send(&sender, chunk).await?;
+    }
+
+    Ok((hasher.finalize(), uncompressed_size, digest.map(|digest| digest.finalize())))
+}
+
+async fn send(sender: &mpsc::Sender<Vec<u8>>, chunk: Vec<u8>) -> Result<()> {
+    sender.send(chunk).await.map_err(|_| ZipError::from(std::io::Error::from(std::io::ErrorKind::BrokenPipe)))
+}
@@ -0,0 +1,404 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module which supports writing ZIP files split across multiple parts of a maximum size.
+//!
+//! Parts are requested from a factory in order of their disk number, starting at zero. Conventionally, every part but
+//! the last is named with a `.z01`, `.z02`, ... extension (for disk numbers zero, one, ...), and the last is named with
+//! a `.zip` extension. As the number of parts isn't known until the ZIP file is closed, parts are typically written
+//! under temporary names and renamed afterwards.
+//!
+//! # Note
+//! - Each part is limited to [`u32::MAX`] bytes (ie. just under 4 GiB, which matches the limit of FAT32), as are each
+//! entry's sizes. Where too many entries are written (or the central directory is too large) for the end of central
+//! directory header to hold, a ZIP64 end of central directory record and locator are written alongside it. The
+//! specification also requires each part to be at least 64 KiB.
+//! - The first part always begins with the spanning signature, even if only one part is written.
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::{Compression, ZipEntryBuilder, write::split::SplitZipFileWriter};
+//! # use tokio::fs::File;
+//! # use async_zip::error::ZipError;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let factory = |disk: u32| File::create(format!("archive.part{}", disk));
+//! let mut writer = SplitZipFileWriter::new(factory, 100 * 1024 * 1024).await?;
+//!
+//! let opts = ZipEntryBuilder::new(String::from("foo.txt"), Compression::Stored);
+//! writer.write_entry_whole(opts, b"This is an example file.").await?;
+//!
+//! let part_count = writer.close().await?;
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::entry::ZipEntry;
+use crate::error::Result;
+use crate::spec::header::EndOfCentralDirectoryHeader;
+use crate::spec::header::{Zip64EndOfCentralDirectoryLocator, Zip64EndOfCentralDirectoryRecord};
+use crate::write::{DuplicatePolicy, EntryDigest, EntryStreamWriter, WrittenEntry, ZipFileWriter};
+
+use std::future::Future;
+use std::io::{Error, IoSlice};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// The minimum size of a part permitted by the specification (64 KiB).
+pub const MIN_PART_SIZE: u64 = 65536;
+
+/// A ZIP file writer which splits its output across multiple parts, each no larger than a maximum size.
+///
+/// # Note
+/// - [`SplitZipFileWriter::close()`] must be called before this writer goes out of scope.
+pub struct SplitZipFileWriter<W, F, Fut>
+where
+    W: AsyncWrite + Unpin,
+    F: FnMut(u32) -> Fut + Unpin,
+    Fut: Future<Output = std::io::Result<W>>,
+{
+    inner: ZipFileWriter<SplitWriter<W, F, Fut>>,
+    state: Arc<PartState>,
+    max_part_size: u64,
+    spanning: bool,
+}
+
+impl<W, F, Fut> SplitZipFileWriter<W, F, Fut>
+where
+    W: AsyncWrite + Unpin,
+    F: FnMut(u32) -> Fut + Unpin,
+    Fut: Future<Output = std::io::Result<W>>,
+{
+    /// Constructs a new split ZIP file writer from a factory of parts and the maximum size of each part.
+    ///
+    /// The factory is called with disk number zero before this function returns, and with each subsequent disk
+    /// number whenever the current part is full. The maximum part size is clamped between 64 KiB and [`u32::MAX`]
+    /// bytes.
+    pub async fn new(mut part_factory: F, max_part_size: u64) -> Result<Self> {
+        let max_part_size = max_part_size.clamp(MIN_PART_SIZE, u32::MAX as u64);
+        let state = Arc::new(PartState { starts: Mutex::new(vec![0]), roll_over: AtomicBool::new(false) });
+
+        let part = part_factory(0).await?;
+        let writer = SplitWriter {
+            part,
+            factory: part_factory,
+            state: state.clone(),
+            phase: Phase::Writing,
+            disk: 0,
+            offset: 0,
+            part_written: 0,
+            max_part_size,
+        };
+
+        let mut inner = ZipFileWriter::new(writer);
+        inner.parts = Some(state.clone());
+        inner.writer.write_all(&crate::spec::signature::SPANNING.to_le_bytes()).await?;
+
+        Ok(Self { inner, state, max_part_size, spanning: true })
+    }
+
+    /// Sets whether an entry may span multiple parts.
+    ///
+    /// Local file headers and central directory records are never split across parts. When spanning is disallowed,
+    /// an entry which wouldn't fit within the remainder of the current part begins on a fresh part instead (though
+    /// it may still span parts if larger than the maximum part size). As the size of a streamed entry isn't known
+    /// upfront, it always begins on a fresh part unless nothing has been written to the current part.
+    ///
+    /// Spanning is allowed by default.
+    pub fn spanning(&mut self, spanning: bool) {
+        self.spanning = spanning;
+    }
+
+    /// Set the ZIP file comment.
+    ///
+    /// See [`ZipFileWriter::comment()`].
+    pub fn comment(&mut self, comment: String) -> Result<()> {
+        self.inner.comment(comment)
+    }
+
+    /// Sets the action taken when an entry is written with the same filename as an earlier entry.
+    ///
+    /// See [`ZipFileWriter::deduplicate()`].
+    pub fn deduplicate(&mut self, policy: DuplicatePolicy) {
+        self.inner.deduplicate(policy);
+    }
+
+    /// Sets whether or not entries written as whole data are checked for text, setting their text attribute if so.
+    ///
+    /// See [`ZipFileWriter::detect_text()`].
+    pub fn detect_text(&mut self, detect: bool) {
+        self.inner.detect_text(detect);
+    }
+
+    /// Sets a digest which is computed over each entry's uncompressed data alongside its CRC32 value.
+    ///
+    /// See [`ZipFileWriter::digest()`].
+    pub fn digest<D, F>(&mut self, factory: F)
+    where
+        D: EntryDigest + 'static,
+        F: Fn() -> D + Send + Sync + 'static,
+    {
+        self.inner.digest(factory);
+    }
+
+    /// Write a new ZIP entry of known size and data.
+    ///
+    /// The returned [`WrittenEntry`]'s header offset is relative to the start of the part in which the entry begins.
+    pub async fn write_entry_whole<E: Into<ZipEntry>>(&mut self, entry: E, data: &[u8]) -> Result<WrittenEntry> {
+        self.write_entry_whole_vectored(entry, &[IoSlice::new(data)]).await
+    }
+
+    /// Write a new ZIP entry of known size, whose data is split across multiple slices.
+    ///
+    /// When spanning is disallowed, the entry's uncompressed size is used as an estimate of its compressed size.
+    pub async fn write_entry_whole_vectored<E: Into<ZipEntry>>(
+        &mut self,
+        entry: E,
+        parts: &[IoSlice<'_>],
+    ) -> Result<WrittenEntry> {
+        let entry = entry.into();
+
+        let mut length = lfh_length(&entry);
+        if !self.spanning {
+            length += parts.iter().map(|part| part.len() as u64).sum::<u64>();
+        }
+
+        self.reserve(length);
+        self.inner.write_entry_whole_vectored(entry, parts).await
+    }
+
+    /// Write an entry of unknown size and data via streaming (ie. using a data descriptor).
+    pub async fn write_entry_stream<E: Into<ZipEntry>>(
+        &mut self,
+        entry: E,
+    ) -> Result<EntryStreamWriter<'_, SplitWriter<W, F, Fut>>> {
+        let entry = entry.into();
+
+        let length = if self.spanning { lfh_length(&entry) } else { u64::MAX };
+        self.reserve(length);
+
+        self.inner.write_entry_stream(entry).await
+    }
+
+    /// Consumes this ZIP writer and completes all closing tasks, returning the number of parts written.
+    ///
+    /// This includes:
+    /// - Writing all central directory headers, each within a single part.
+    /// - Writing the ZIP64 end of central directory record and locator (if required), the end of central directory
+    /// header, and the file comment, within the last part.
+    /// - Shutting down the last part.
+    ///
+    /// Failiure to call this function before going out of scope would result in a corrupted ZIP file.
+    pub async fn close(mut self) -> Result<u32> {
+        self.inner.check_poisoned()?;
+
+        let size_cent_dir_start = self.inner.writer.offset();
+        let mut cd_start = None;
+        let mut num_of_entries_disk = 0u64;
+
+        let records = std::mem::take(&mut self.inner.cd_records);
+        let mut last_disk = None;
+
+        for record in records.iter() {
+            self.reserve(record.len() as u64);
+            let offset = self.inner.writer.offset();
+            self.inner.writer.write_all(record).await?;
+
+            let (disk, relative_offset) = self.state.disk_position(offset as u64);
+            cd_start.get_or_insert((disk, relative_offset));
+
+            if last_disk == Some(disk) {
+                num_of_entries_disk += 1;
+            } else {
+                num_of_entries_disk = 1;
+                last_disk = Some(disk);
+            }
+        }
+
+        let comment = self.inner.comment_opt.take().unwrap_or_default();
+        let num_of_entries = records.len() as u64;
+        let size_cent_dir = (self.inner.writer.offset() - size_cent_dir_start) as u64;
+
+        // Each part is limited to 4 GiB, so only the number of entries and the central directory's size (which may
+        // span parts) can require the ZIP64 record.
+        let zip64 = num_of_entries >= u64::from(u16::MAX) || size_cent_dir >= u64::from(u32::MAX);
+        let zip64_length = if zip64 { 56 + 20 } else { 0 };
+        let disk_num = self.reserve(zip64_length + 22 + comment.len() as u64);
+
+        // Records written to an earlier part than the end of central directory header don't count towards its disk.
+        let num_of_entries_disk = if last_disk == Some(disk_num) { num_of_entries_disk } else { 0 };
+        let (start_cent_dir_disk, cent_dir_offset) = cd_start.unwrap_or((disk_num, 0));
+
+        if zip64 {
+            let offset = self.inner.writer.offset();
+            let record = Zip64EndOfCentralDirectoryRecord {
+                v_made_by: crate::spec::version::as_made_by(),
+                v_needed: crate::spec::version::ZIP64_VERSION_NEEDED,
+                disk_num: disk_num.into(),
+                start_cent_dir_disk: start_cent_dir_disk.into(),
+                num_of_entries_disk,
+                num_of_entries,
+                size_cent_dir,
+                cent_dir_offset: cent_dir_offset.into(),
+            };
+            self.inner.writer.write_all(&record.to_bytes()).await?;
+
+            // The record's position within its part is only known once it's been written, as it may begin a new part.
+            let locator = Zip64EndOfCentralDirectoryLocator {
+                start_eocdr_disk: disk_num.into(),
+                eocdr_offset: self.state.disk_position(offset as u64).1.into(),
+                total_disks: u32::from(disk_num) + 1,
+            };
+            self.inner.writer.write_all(&locator.to_bytes()).await?;
+        }
+
+        let header = EndOfCentralDirectoryHeader {
+            disk_num,
+            start_cent_dir_disk,
+            num_of_entries_disk: num_of_entries_disk.min(u16::MAX.into()) as u16,
+            num_of_entries: num_of_entries.min(u16::MAX.into()) as u16,
+            size_cent_dir: size_cent_dir.min(u32::MAX.into()) as u32,
+            cent_dir_offset,
+            file_comm_length: comment.len() as u16,
+        };
+
+        self.inner.writer.write_all(&header.to_bytes()).await?;
+        self.inner.writer.write_all(comment.as_bytes()).await?;
+        self.inner.writer.shutdown().await?;
+
+        Ok(u32::from(disk_num) + 1)
+    }
+
+    // Ensure that the next number of bytes written don't straddle two parts, returning the disk they'll begin within.
+    //
+    // A length longer than the remainder of the current part begins a new part, unless the current part is empty.
+    fn reserve(&mut self, length: u64) -> u16 {
+        let offset = self.inner.writer.offset() as u64;
+        let starts = self.state.starts.lock().unwrap();
+        let used = offset - starts.last().unwrap();
+        let disk = (starts.len() - 1) as u16;
+
+        if used > 0 && used.saturating_add(length) > self.max_part_size {
+            self.state.roll_over.store(true, Ordering::Relaxed);
+            disk + 1
+        } else {
+            disk
+        }
+    }
+}
+
+// An upper bound of the length of an entry's local file header, as any alignment padding depends on its offset.
+fn lfh_length(entry: &ZipEntry) -> u64 {
+    let extra_field_length = entry.extra_field().len() + crate::write::max_alignment_padding(entry.alignment);
+    30 + entry.filename().len() as u64 + extra_field_length as u64
+}
+
+/// The state of the parts written so far, shared between a split writer and its ZIP file writer.
+pub(crate) struct PartState {
+    // The offset at which each part begins within the written data.
+    starts: Mutex<Vec<u64>>,
+    // Whether the next write should begin a new part, even if the current part isn't yet full.
+    roll_over: AtomicBool,
+}
+
+impl PartState {
+    // Returns the disk number and offset relative to that disk for an offset within the written data.
+    pub(crate) fn disk_position(&self, offset: u64) -> (u16, u32) {
+        let starts = self.starts.lock().unwrap();
+        let disk = starts.iter().rposition(|start| *start <= offset).unwrap_or(0);
+
+        (disk as u16, (offset - starts[disk]) as u32)
+    }
+}
+
+enum Phase<Fut> {
+    Writing,
+    Closing,
+    Opening(Pin<Box<Fut>>),
+}
+
+/// A writer which splits its output across parts requested from a factory.
+///
+/// This writer cannot be manually constructed; it's the underlying writer of a [`SplitZipFileWriter`].
+pub struct SplitWriter<W, F, Fut>
+where
+    W: AsyncWrite + Unpin,
+    F: FnMut(u32) -> Fut + Unpin,
+    Fut: Future<Output = std::io::Result<W>>,
+{
+    part: W,
+    factory: F,
+    state: Arc<PartState>,
+    phase: Phase<Fut>,
+    disk: u32,
+    offset: u64,
+    part_written: u64,
+    max_part_size: u64,
+}
+
+impl<W, F, Fut> SplitWriter<W, F, Fut>
+where
+    W: AsyncWrite + Unpin,
+    F: FnMut(u32) -> Fut + Unpin,
+    Fut: Future<Output = std::io::Result<W>>,
+{
+    // Drive any in-progress move onto a new part to completion.
+    fn poll_phase(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        loop {
+            match &mut self.phase {
+                Phase::Writing => return Poll::Ready(Ok(())),
+                Phase::Closing => {
+                    ready!(Pin::new(&mut self.part).poll_shutdown(cx))?;
+                    self.phase = Phase::Opening(Box::pin((self.factory)(self.disk + 1)));
+                }
+                Phase::Opening(future) => {
+                    self.part = ready!(future.as_mut().poll(cx))?;
+                    self.phase = Phase::Writing;
+                    self.disk += 1;
+                    self.part_written = 0;
+                    self.state.starts.lock().unwrap().push(self.offset);
+                }
+            }
+        }
+    }
+}
+
+impl<W, F, Fut> AsyncWrite for SplitWriter<W, F, Fut>
+where
+    W: AsyncWrite + Unpin,
+    F: FnMut(u32) -> Fut + Unpin,
+    Fut: Future<Output = std::io::Result<W>>,
+{
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<std::result::Result<usize, Error>> {
+        loop {
+            ready!(self.poll_phase(cx))?;
+
+            let roll_over = self.state.roll_over.swap(false, Ordering::Relaxed);
+            if self.part_written > 0 && (roll_over || self.part_written >= self.max_part_size) {
+                self.phase = Phase::Closing;
+                continue;
+            }
+
+            let length = buf.len().min((self.max_part_size - self.part_written) as usize);
+            let written = ready!(Pin::new(&mut self.part).poll_write(cx, &buf[..length]))?;
+
+            self.part_written += written as u64;
+            self.offset += written as u64;
+
+            return Poll::Ready(Ok(written));
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
+        ready!(self.poll_phase(cx))?;
+        Pin::new(&mut self.part).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
+        ready!(self.poll_phase(cx))?;
+        Pin::new(&mut self.part).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Writing a ZIP file on the fly as a stream of bytes, backing [`stream_archive()`].
+//!
+//! The writer runs on its own spawned task over one half of a bounded duplex pipe, and the returned stream reads the
+//! other half. Once the pipe's buffer is full, the writer (and so the reading of each entry's source) waits until the
+//! stream has been polled for more data, so no more than the buffer's worth of the ZIP file is ever held in memory.
+
+use crate::entry::ZipEntry;
+use crate::error::{Result, ZipError};
+use crate::write::ZipFileWriter;
+
+use bytes::{Bytes, BytesMut};
+use futures_util::{Stream, StreamExt};
+use tokio::io::{AsyncRead, AsyncReadExt, DuplexStream};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// Writes a ZIP file on the fly from a stream of entries and their sources, returning a stream of its bytes (eg. to be
+/// used as a HTTP response body) along with a handle to the spawned task writing it.
+///
+/// Each entry's data is copied from its source until EOF, so is followed by a data descriptor. At most `buffer` bytes
+/// of the ZIP file are buffered before writing waits for the returned stream to be polled, which also bounds the size
+/// of each chunk it yields. A buffer size of zero is treated as one byte.
+/// ```no_run
+/// # #[cfg(feature = "deflate")]
+/// # {
+/// # use async_zip::{Compression, ZipEntryBuilder, write::stream_archive};
+/// # use futures_util::StreamExt;
+/// # use tokio::fs::File;
+/// #
+/// # async fn run() -> Result<(), async_zip::error::ZipError> {
+/// let entries = futures_util::stream::iter(["foo.txt", "bar.txt"]).then(|path| async move {
+///     let file = File::open(path).await.expect("failed to open file");
+///     (ZipEntryBuilder::new(path, Compression::Deflate), file)
+/// });
+///
+/// let (body, task) = stream_archive(entries, 64 * 1024);
+/// // Hand `body` to the HTTP server as the response body.
+/// # drop(body);
+/// task.await.expect("writer task panicked")?;
+/// #   Ok(())
+/// # }
+/// # }
+/// ```
+///
+/// # Note
+/// - If writing fails (eg. as an entry's source returned an error), the stream ends with an error after any bytes
+/// already written, and the task returns the original error. As [`ZipError`] can't be cloned, the stream's error is a
+/// [`ZipError::UpstreamReadError`] carrying the original's message (and kind, for IO errors).
+/// - If the stream is dropped before it has ended, writing fails and the task returns an error.
+/// - This must be called from within a Tokio runtime, as the writer's task is spawned onto it.
+pub fn stream_archive<S, E, R>(
+    entries: S,
+    buffer: usize,
+) -> (impl Stream<Item = Result<Bytes>> + Send + 'static, JoinHandle<Result<()>>)
+where
+    S: Stream<Item = (E, R)> + Send + 'static,
+    E: Into<ZipEntry> + Send + 'static,
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let buffer = buffer.max(1);
+    let (mut writer, reader) = tokio::io::duplex(buffer);
+    let (outcome_sender, outcome) = oneshot::channel();
+
+    let task = tokio::spawn(async move {
+        let result = write_archive(entries, &mut writer, buffer).await;
+        let _ = outcome_sender.send(result.as_ref().copied().map_err(duplicate_error));
+
+        // The stream only reaches EOF once the writer's half is dropped, by which point the outcome has been sent.
+        drop(writer);
+        result
+    });
+
+    (bytes_stream(reader, outcome, buffer), task)
+}
+
+async fn write_archive<S, E, R>(entries: S, writer: &mut DuplexStream, buffer: usize) -> Result<()>
+where
+    S: Stream<Item = (E, R)>,
+    E: Into<ZipEntry>,
+    R: AsyncRead + Unpin,
+{
+    futures_util::pin_mut!(entries);
+    let mut zip_writer = ZipFileWriter::new(writer);
+
+    while let Some((entry, reader)) = entries.next().await {
+        zip_writer.write_entry_copy(entry, reader, buffer).await?;
+    }
+
+    zip_writer.close().await
+}
+
+// Read the ZIP file's bytes in chunks of up to `buffer` bytes, ending with the writer's error if it failed.
+fn bytes_stream(
+    reader: DuplexStream,
+    outcome: oneshot::Receiver<Result<()>>,
+    buffer: usize,
+) -> impl Stream<Item = Result<Bytes>> + Send + 'static {
+    futures_util::stream::unfold(Some((reader, outcome)), move |state| async move {
+        let (mut reader, outcome) = state?;
+        let mut chunk = BytesMut::with_capacity(buffer);
+
+        match reader.read_buf(&mut chunk).await {
+            Ok(0) => (),
+            Ok(_) => return Some((Ok(chunk.freeze()), Some((reader, outcome)))),
+            Err(err) => return Some((Err(err.into()), None)),
+        }
+
+        match outcome.await {
+            Ok(Ok(())) => None,
+            Ok(Err(err)) => Some((Err(err), None)),
+            Err(_) => {
+                let error =
+                    std::io::Error::new(std::io::ErrorKind::Other, "the archive writer task ended unexpectedly");
+                Some((Err(ZipError::UpstreamReadError(error)), None))
+            }
+        }
+    })
+}
+
+// Duplicate an error for the stream, keeping its message (and kind, for IO errors).
+fn duplicate_error(err: &ZipError) -> ZipError {
+    let kind = match err {
+        ZipError::UpstreamReadError(err) => err.kind(),
+        _ => std::io::ErrorKind::Other,
+    };
+
+    ZipError::UpstreamReadError(std::io::Error::new(kind, err.to_string()))
+}
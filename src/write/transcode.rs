@@ -0,0 +1,265 @@
+// Copyright (c) 2022 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Rewriting a ZIP file from a non-seekable source to a non-seekable sink in a single pass, backing [`transcode()`].
+//!
+//! The source is read with the stream reader, so each entry is described by its local file header until the central
+//! directory is reached at the end of the source. As the writer only writes its own central directory once closed,
+//! the metadata which is only held within the source's central directory (comments, attributes, and the host of the
+//! "version made by" field) is applied to the written entries' records just beforehand.
+
+use crate::entry::{CompressionLevel, ZipEntry};
+use crate::error::{Result, ZipError};
+use crate::read::stream::ZipFileReader;
+use crate::read::ZipEntryReader;
+use crate::spec::compression::Compression;
+use crate::spec::signature::{CENTRAL_DIRECTORY_FILE_HEADER, END_OF_CENTRAL_DIRECTORY};
+use crate::write::{CentralDirectoryRecords, WrittenEntry, ZipFileWriter, DEFAULT_COPY_BUFFER_SIZE};
+
+use async_io_utilities::AsyncPrependReader;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+/// What [`transcode()`] does with each entry of its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscodeAction {
+    /// Copy the entry's compressed data as-is, without recompressing it.
+    Keep,
+    /// Decompress the entry's data and compress it again with the provided method and level.
+    Recompress(Compression, CompressionLevel),
+    /// Omit the entry from the output.
+    Drop,
+}
+
+/// Rewrites a ZIP file read from a non-seekable source into a non-seekable sink, in a single pass, choosing what is
+/// done with each entry via the provided function (eg. recompressing every Deflate entry with Zstd).
+///
+/// Each entry's filename, last modification date, extra field (including any timestamps), comment, and attributes are
+/// carried over whichever action is chosen, and each entry's data is streamed through a bounded buffer, so memory use
+/// doesn't grow with the size of any entry. The written entries are returned in the order they were written.
+/// ```no_run
+/// # #[cfg(all(feature = "deflate", feature = "zstd"))]
+/// # {
+/// # use async_zip::{Compression, write::{transcode, TranscodeAction}};
+/// # use async_zip::entry::CompressionLevel;
+/// # use tokio::fs::File;
+/// #
+/// # async fn run() -> Result<(), async_zip::error::ZipError> {
+/// let (input, mut output) = (File::open("deflate.zip").await?, File::create("zstd.zip").await?);
+///
+/// transcode(input, &mut output, |entry| match entry.compression() {
+///     Compression::Deflate => TranscodeAction::Recompress(Compression::Zstd, CompressionLevel::Default),
+///     _ => TranscodeAction::Keep,
+/// })
+/// .await?;
+/// #   Ok(())
+/// # }
+/// # }
+/// ```
+///
+/// # Note
+/// - The function is passed each entry as described by its local file header, as the source's central directory is
+/// only reached after every entry. Its comment and attributes are therefore not yet known, and are only applied to the
+/// output once the central directory has been read. The source's archive comment is also carried over.
+/// - Recompressed entries are written with data descriptors, whereas kept entries keep their source's layout. Finding
+/// the end of a kept entry which is followed by a data descriptor requires decompressing it (to verify its CRC32
+/// value, whilst discarding the decompressed data), so its compression method's feature must be enabled.
+/// - The source's central directory metadata is matched to its entries by the order of their local file headers, and
+/// isn't applied to an entry whose filename differs between the two.
+pub async fn transcode<R, W, F>(input: R, output: W, mut action: F) -> Result<Vec<WrittenEntry>>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FnMut(&ZipEntry) -> TranscodeAction,
+{
+    let mut reader = ZipFileReader::new(input);
+    let mut writer = ZipFileWriter::new(output);
+    let mut written = Vec::new();
+
+    // The filename of each of the source's entries, and whether it was written.
+    let mut sources: Vec<(Arc<str>, bool)> = Vec::new();
+
+    while let Some(entry) = std::future::poll_fn(|c| reader.poll_next_entry(c)).await? {
+        let action = action(&entry);
+        sources.push((entry.filename.clone(), action != TranscodeAction::Drop));
+
+        match action {
+            TranscodeAction::Keep => written.push(keep_entry(&mut reader, &mut writer, entry).await?),
+            TranscodeAction::Recompress(compression, level) => {
+                let mut recompressed = entry;
+                recompressed.compression = compression;
+                recompressed.compression_level = level.into_level();
+                recompressed.version_needed = None;
+
+                let mut entry_reader = reader.entry_data_reader()?;
+                let copied = writer.write_entry_copy(recompressed, &mut entry_reader, DEFAULT_COPY_BUFFER_SIZE).await?;
+                entry_reader.verify_crc()?;
+                written.push(copied);
+            }
+            TranscodeAction::Drop => reader.skip_current_entry().await?,
+        }
+    }
+
+    let (mut central_directory, comment) = read_central_directory(&mut reader.reader).await?;
+    central_directory.sort_by_key(|(_, disk_start, offset)| (*disk_start, *offset));
+
+    // Without a record for every local file header, the records can't be matched to the entries.
+    if central_directory.len() == sources.len() {
+        let matched = sources.iter().zip(&central_directory).filter(|((_, written), _)| *written);
+        let mut records = CentralDirectoryRecords::default();
+
+        for (record, ((filename, _), (source, _, _))) in writer.cd_records.iter().zip(matched) {
+            match source.filename() == &**filename {
+                true => records.push_raw(&with_central_metadata(record, source)),
+                false => records.push_raw(record),
+            }
+        }
+
+        writer.cd_records = records;
+    }
+
+    if let Some(comment) = comment {
+        writer.comment(comment)?;
+    }
+
+    writer.close().await?;
+    Ok(written)
+}
+
+// Copy the compressed data of the entry whose local file header has just been read.
+async fn keep_entry<R, W>(
+    reader: &mut ZipFileReader<R>,
+    writer: &mut ZipFileWriter<W>,
+    entry: ZipEntry,
+) -> Result<WrittenEntry>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    if !entry.general_purpose_flags().data_descriptor {
+        return crate::write::entry_raw::write_entry_raw(writer, entry, &mut reader.reader).await;
+    }
+
+    let mut entry_reader = reader.entry_data_reader()?;
+    entry_reader.reader.get_mut().capture();
+
+    let scratch = vec![0; DEFAULT_COPY_BUFFER_SIZE].into_boxed_slice();
+    let mut data = CapturedData { reader: entry_reader, scratch, position: 0, yielded: 0, finished: false };
+
+    crate::write::entry_raw::write_entry_raw_resolved(writer, entry, &mut data, |data, _| data.resolve()).await
+}
+
+// Reads the compressed data of an entry which is followed by a data descriptor, whose end can only be found by
+// decompressing it. The decompressed data is discarded, whilst the compressed bytes consumed by the decompressor are
+// yielded as-is.
+struct CapturedData<'a, R: AsyncRead + Unpin> {
+    reader: ZipEntryReader<'a, R>,
+    scratch: Box<[u8]>,
+    position: usize,
+    yielded: u64,
+    finished: bool,
+}
+
+impl<'a, R: AsyncRead + Unpin> CapturedData<'a, R> {
+    // Returns the CRC32 value and sizes held by the data descriptor, once the decompressed data has been verified.
+    fn resolve(&self) -> Result<(u32, u64, u64)> {
+        self.reader.verify_crc()?;
+
+        let descriptor = self.reader.data_descriptor.ok_or(ZipError::DataDescriptorNotRead)?;
+        let (crc, compressed_size, uncompressed_size) = descriptor;
+        if compressed_size != self.yielded {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok((crc, compressed_size, uncompressed_size))
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin> AsyncRead for CapturedData<'a, R> {
+    fn poll_read(self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let this = self.get_mut();
+
+        loop {
+            let captured = this.reader.reader.get_mut().captured().expect("capturing wasn't enabled");
+
+            if this.position < captured.len() {
+                let length = (captured.len() - this.position).min(b.remaining());
+                b.put_slice(&captured[this.position..this.position + length]);
+                this.position += length;
+                this.yielded += length as u64;
+                return Poll::Ready(Ok(()));
+            }
+
+            captured.clear();
+            this.position = 0;
+
+            if this.finished {
+                return Poll::Ready(Ok(()));
+            }
+
+            let mut scratch = ReadBuf::new(&mut this.scratch);
+            ready!(Pin::new(&mut this.reader).poll_read(c, &mut scratch))?;
+
+            // Reaching the end of the decompressed data also reads the data descriptor, whose bytes were captured
+            // after the compressed data and so are left out.
+            if scratch.filled().is_empty() {
+                this.finished = true;
+
+                let compressed_size = this.reader.data_descriptor.map_or(0, |(_, compressed_size, _)| compressed_size);
+                let remaining = compressed_size.saturating_sub(this.yielded);
+                let captured = this.reader.reader.get_mut().captured().expect("capturing wasn't enabled");
+                captured.truncate(remaining.min(captured.len() as u64) as usize);
+            }
+        }
+    }
+}
+
+// Read the source's central directory (whose first signature has been left unconsumed by the stream reader) and its
+// archive comment, returning each entry along with the disk and offset of its local file header. The archive comment is
+// only returned if the end of central directory record directly follows.
+async fn read_central_directory<R: AsyncRead + Unpin>(
+    reader: &mut AsyncPrependReader<R>,
+) -> Result<(Vec<(ZipEntry, u16, u64)>, Option<String>)> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let signature = reader.read_u32_le().await?;
+        reader.prepend(&signature.to_le_bytes());
+
+        match signature {
+            CENTRAL_DIRECTORY_FILE_HEADER => {
+                let (entry, meta) = crate::read::seek::read_cd_entry(reader, &mut offset, None).await?.unwrap();
+                entries.push((entry, meta.disk_start, meta.file_offset.unwrap_or_default()));
+            }
+            END_OF_CENTRAL_DIRECTORY => {
+                crate::utils::assert_signature(reader, END_OF_CENTRAL_DIRECTORY, None).await?;
+                let header = crate::spec::header::EndOfCentralDirectoryHeader::from_reader(reader).await?;
+
+                let mut comment = Vec::new();
+                reader.take(header.file_comm_length.into()).read_to_end(&mut comment).await?;
+                return Ok((entries, Some(String::from_utf8_lossy(&comment).into_owned())));
+            }
+            // Eg. the ZIP64 end of central directory record, or a digital signature.
+            _ => return Ok((entries, None)),
+        }
+    }
+}
+
+// Apply the comment, attributes, and host of a source entry's central directory record to a written record.
+fn with_central_metadata(record: &[u8], source: &ZipEntry) -> Vec<u8> {
+    let comment_start = crate::write::record_length(record) - crate::write::record_field(record, 32);
+    let mut patched = record[..comment_start].to_vec();
+
+    if let Some(version_made_by) = source.version_made_by {
+        patched[5] = (version_made_by >> 8) as u8;
+    }
+
+    patched[32..34].copy_from_slice(&(source.comment().len() as u16).to_le_bytes());
+    patched[36..38].copy_from_slice(&source.internal_file_attribute().to_le_bytes());
+    patched[38..42].copy_from_slice(&source.external_file_attribute().to_le_bytes());
+    patched.extend_from_slice(source.comment().as_bytes());
+    patched
+}